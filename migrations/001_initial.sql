@@ -1,5 +1,8 @@
--- Users table to track Discord users and their keys
-CREATE TABLE users (
+-- Core ledger schema. Uses IF NOT EXISTS because databases created before
+-- migrations were introduced already have these tables.
+
+-- Users table
+CREATE TABLE IF NOT EXISTS users (
     discord_id TEXT PRIMARY KEY,
     username TEXT NOT NULL,
     public_key TEXT NOT NULL,
@@ -9,47 +12,38 @@ CREATE TABLE users (
     updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
--- Transaction ledger - immutable append-only log
-CREATE TABLE transactions (
+-- Transactions table
+CREATE TABLE IF NOT EXISTS transactions (
     id TEXT PRIMARY KEY,
     from_user TEXT NOT NULL,
     to_user TEXT NOT NULL,
-    amount INTEGER NOT NULL,  -- Store in smallest units to avoid floating point
-    transaction_type TEXT NOT NULL DEFAULT 'transfer', -- 'transfer', 'mint', 'burn'
+    amount INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL DEFAULT 'transfer',
     message TEXT,
     nonce INTEGER NOT NULL,
     signature TEXT NOT NULL,
     timestamp_unix INTEGER NOT NULL,
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    
-    FOREIGN KEY (from_user) REFERENCES users(discord_id),
-    FOREIGN KEY (to_user) REFERENCES users(discord_id)
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
--- Balance cache for performance (calculated from transactions)
-CREATE TABLE balances (
+-- Balances table
+CREATE TABLE IF NOT EXISTS balances (
     discord_id TEXT PRIMARY KEY,
     balance INTEGER NOT NULL DEFAULT 0,
-    last_updated DATETIME DEFAULT CURRENT_TIMESTAMP,
-    
-    FOREIGN KEY (discord_id) REFERENCES users(discord_id)
+    last_updated DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
--- Indexes for performance
-CREATE INDEX idx_transactions_from_user ON transactions(from_user);
-CREATE INDEX idx_transactions_to_user ON transactions(to_user);
-CREATE INDEX idx_transactions_timestamp ON transactions(timestamp_unix);
-CREATE INDEX idx_users_username ON users(username);
+-- System config table (bot-wide key/value settings such as the KDF salt)
+CREATE TABLE IF NOT EXISTS system_config (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
 
--- Triggers to update the updated_at timestamp
-CREATE TRIGGER update_users_timestamp 
-    AFTER UPDATE ON users
-    BEGIN
-        UPDATE users SET updated_at = CURRENT_TIMESTAMP WHERE discord_id = NEW.discord_id;
-    END;
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_transactions_from_user ON transactions(from_user);
+CREATE INDEX IF NOT EXISTS idx_transactions_to_user ON transactions(to_user);
+CREATE INDEX IF NOT EXISTS idx_transactions_timestamp ON transactions(timestamp_unix);
 
-CREATE TRIGGER update_balances_timestamp 
-    AFTER UPDATE ON balances
-    BEGIN
-        UPDATE balances SET last_updated = CURRENT_TIMESTAMP WHERE discord_id = NEW.discord_id;
-    END;
+-- Counterparty lookups scan a user's sent and received transfers by recency
+CREATE INDEX IF NOT EXISTS idx_transactions_from_user_time ON transactions(from_user, timestamp_unix);
+CREATE INDEX IF NOT EXISTS idx_transactions_to_user_time ON transactions(to_user, timestamp_unix);
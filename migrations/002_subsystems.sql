@@ -0,0 +1,136 @@
+-- Settings, games, shop, events and triggers, as created before migrations existed
+
+-- Guild settings table (per-guild overrides for config::SETTINGS)
+CREATE TABLE IF NOT EXISTS guild_settings (
+    guild_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (guild_id, key)
+);
+
+-- Faucet claims table
+CREATE TABLE IF NOT EXISTS faucet_claims (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    guild_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    claimed_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_faucet_claims_user ON faucet_claims(discord_id, claimed_at);
+
+CREATE INDEX IF NOT EXISTS idx_faucet_claims_guild ON faucet_claims(guild_id, claimed_at);
+
+-- Claims table (daily reward state per user)
+CREATE TABLE IF NOT EXISTS claims (
+    discord_id TEXT PRIMARY KEY,
+    last_claim_unix INTEGER NOT NULL,
+    streak INTEGER NOT NULL DEFAULT 0
+);
+
+-- Pinned leaderboards table (messages refreshed by the background task)
+CREATE TABLE IF NOT EXISTS pinned_leaderboards (
+    message_id TEXT PRIMARY KEY,
+    guild_id TEXT NOT NULL,
+    channel_id TEXT NOT NULL,
+    interval_minutes INTEGER NOT NULL,
+    display_limit INTEGER NOT NULL,
+    last_refreshed INTEGER NOT NULL
+);
+
+-- Treasury spends table (category for each treasury payout)
+CREATE TABLE IF NOT EXISTS treasury_spends (
+    transaction_id TEXT PRIMARY KEY,
+    guild_id TEXT NOT NULL,
+    category TEXT NOT NULL,
+    spent_by TEXT NOT NULL
+);
+
+-- Lottery tables
+CREATE TABLE IF NOT EXISTS lottery_rounds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    ticket_price INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    draw_at INTEGER NOT NULL,
+    drawn INTEGER NOT NULL DEFAULT 0,
+    winner TEXT,
+    pot INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS lottery_tickets (
+    round_id INTEGER NOT NULL,
+    discord_id TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (round_id, discord_id)
+);
+
+-- Shop tables
+CREATE TABLE IF NOT EXISTS shop_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    role_id TEXT,
+    stock INTEGER,
+    consumable INTEGER NOT NULL DEFAULT 0,
+    tradeable INTEGER NOT NULL DEFAULT 0,
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (guild_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS purchases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    transaction_id TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS inventories (
+    discord_id TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (discord_id, item_id)
+);
+
+-- Event tables (tickets are hidden shop items held in inventories)
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    starts_at INTEGER NOT NULL,
+    ticket_price INTEGER NOT NULL,
+    capacity INTEGER NOT NULL,
+    attendance_bonus INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    created_by TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS event_checkins (
+    event_id INTEGER NOT NULL,
+    discord_id TEXT NOT NULL,
+    checked_in_at INTEGER NOT NULL,
+    PRIMARY KEY (event_id, discord_id)
+);
+
+-- Triggers table (phrase -> response pairs for the message handler)
+CREATE TABLE IF NOT EXISTS triggers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    phrase TEXT NOT NULL,
+    response TEXT NOT NULL,
+    user_id TEXT,
+    created_by TEXT NOT NULL
+);
+
+-- Carry over the trigger that used to be hardcoded in funny.rs, once
+INSERT INTO triggers (guild_id, phrase, response, user_id, created_by)
+SELECT '1078723086448349365', 'right agelbub?', 'yes', '339829749218017281', 'SYSTEM'
+WHERE NOT EXISTS (SELECT 1 FROM system_config WHERE key = 'triggers.legacy_seeded');
+
+INSERT OR IGNORE INTO system_config (key, value) VALUES ('triggers.legacy_seeded', 'true');
@@ -0,0 +1,14 @@
+-- Shop items can grant a role for a limited time
+ALTER TABLE shop_items ADD COLUMN role_duration_hours INTEGER;
+
+-- Roles granted by purchases that expire
+CREATE TABLE role_grants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    role_id TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_role_grants_expires ON role_grants(expires_at);
@@ -0,0 +1,15 @@
+-- User-to-user deals with funds held by the ESCROW account
+CREATE TABLE escrows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    buyer TEXT NOT NULL,
+    seller TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open',
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    resolved_by TEXT
+);
+
+CREATE INDEX idx_escrows_status_expires ON escrows(status, expires_at);
@@ -0,0 +1,15 @@
+-- Requests for a user to pay someone, settled with a Pay button on the posted embed
+CREATE TABLE payment_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    requester TEXT NOT NULL,
+    payer TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    reason TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    transaction_id TEXT
+);
+
+CREATE INDEX idx_payment_requests_payer ON payment_requests(payer, status);
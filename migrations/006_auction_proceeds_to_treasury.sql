@@ -0,0 +1,13 @@
+-- Auction proceeds used to pile up in AUCTION_SYSTEM where nothing could spend them.
+-- Move whatever it holds into the treasury with a ledger entry so audits still balance.
+INSERT INTO transactions (id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix)
+SELECT 'auction-system-sweep', 'AUCTION_SYSTEM', 'TREASURY', balance, 'treasury_sweep',
+       'Legacy auction proceeds moved to the treasury', 0, 'system', CAST(strftime('%s', 'now') AS INTEGER)
+FROM balances
+WHERE discord_id = 'AUCTION_SYSTEM' AND balance > 0;
+
+INSERT INTO balances (discord_id, balance)
+SELECT 'TREASURY', balance FROM balances WHERE discord_id = 'AUCTION_SYSTEM' AND balance > 0
+ON CONFLICT(discord_id) DO UPDATE SET balance = balances.balance + excluded.balance, last_updated = CURRENT_TIMESTAMP;
+
+UPDATE balances SET balance = 0, last_updated = CURRENT_TIMESTAMP WHERE discord_id = 'AUCTION_SYSTEM';
@@ -0,0 +1,16 @@
+-- Completed auctions, kept after the in-memory auction is dropped
+CREATE TABLE auction_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    voice_channel_id TEXT NOT NULL,
+    creator_id TEXT NOT NULL,
+    item TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    winner_id TEXT,
+    amount INTEGER,
+    bid_count INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_auction_history_guild ON auction_history(guild_id, ended_at);
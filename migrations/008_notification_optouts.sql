@@ -0,0 +1,6 @@
+-- Users who don't want a given kind of DM from the bot
+CREATE TABLE notification_optouts (
+    discord_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    PRIMARY KEY (discord_id, kind)
+);
@@ -0,0 +1,5 @@
+-- Per-user display preferences; users without a row get the defaults
+CREATE TABLE user_preferences (
+    discord_id TEXT PRIMARY KEY,
+    private_replies INTEGER NOT NULL DEFAULT 1
+);
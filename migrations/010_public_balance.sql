@@ -0,0 +1,2 @@
+-- Whether other users can look up this user's balance with /balance @user
+ALTER TABLE user_preferences ADD COLUMN public_balance INTEGER NOT NULL DEFAULT 0;
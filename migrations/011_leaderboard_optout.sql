@@ -0,0 +1,2 @@
+-- Users who'd rather not be listed on /baltop; they still count towards totals
+ALTER TABLE user_preferences ADD COLUMN hide_from_leaderboard INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,21 @@
+-- Admin-created codes that credit a fixed amount to each user who redeems them
+CREATE TABLE codes (
+    code TEXT PRIMARY KEY,
+    guild_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    max_uses INTEGER NOT NULL,
+    uses INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- NULL means the code never expires
+    expires_at INTEGER
+);
+
+-- One row per user per code, so nobody can redeem the same code twice
+CREATE TABLE code_redemptions (
+    code TEXT NOT NULL REFERENCES codes(code),
+    discord_id TEXT NOT NULL,
+    transaction_id TEXT NOT NULL,
+    redeemed_at INTEGER NOT NULL,
+    PRIMARY KEY (code, discord_id)
+);
@@ -0,0 +1,26 @@
+-- Recurring salaries paid to every member holding a role
+CREATE TABLE payroll (
+    guild_id TEXT NOT NULL,
+    role_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    interval_seconds INTEGER NOT NULL,
+    next_payout_at INTEGER NOT NULL,
+    created_by TEXT NOT NULL,
+    PRIMARY KEY (guild_id, role_id)
+);
+
+-- One row per member per payout, including members skipped for not being registered
+CREATE TABLE payroll_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    role_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    -- 'paid', or 'skipped' when the member wasn't registered
+    status TEXT NOT NULL,
+    transaction_id TEXT,
+    paid_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_payroll_due ON payroll(next_payout_at);
+CREATE INDEX idx_payroll_log_guild ON payroll_log(guild_id, paid_at);
@@ -0,0 +1,10 @@
+-- Savings held in each user's vault, per guild. The coins themselves sit in the VAULT ledger account,
+-- so the sum of these balances always matches its balance.
+CREATE TABLE vault_balances (
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    balance INTEGER NOT NULL DEFAULT 0,
+    -- Interest accrues from here; reset by each deposit so last-minute deposits don't earn a full period
+    last_interest_at INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, discord_id)
+);
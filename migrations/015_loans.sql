@@ -0,0 +1,33 @@
+-- Loans paid out of the treasury and repaid in scheduled installments
+CREATE TABLE loans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    borrower TEXT NOT NULL,
+    principal INTEGER NOT NULL,
+    -- principal plus interest
+    total_due INTEGER NOT NULL,
+    repaid INTEGER NOT NULL DEFAULT 0,
+    installment INTEGER NOT NULL,
+    payment_interval_seconds INTEGER NOT NULL,
+    reason TEXT,
+    -- 'pending', 'denied', 'active', 'repaid' or 'defaulted'
+    status TEXT NOT NULL DEFAULT 'pending',
+    requested_at INTEGER NOT NULL,
+    approved_by TEXT,
+    next_payment_at INTEGER,
+    missed_payments INTEGER NOT NULL DEFAULT 0
+);
+
+-- Every installment collected or missed, plus early repayments
+CREATE TABLE loan_payments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    loan_id INTEGER NOT NULL REFERENCES loans(id),
+    amount INTEGER NOT NULL,
+    -- 'paid' or 'missed'
+    status TEXT NOT NULL,
+    transaction_id TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_loans_borrower ON loans(borrower, status);
+CREATE INDEX idx_loans_due ON loans(status, next_payment_at);
@@ -0,0 +1,12 @@
+-- One row per wealth tax collection in a guild; the individual deductions are "tax" ledger entries
+CREATE TABLE tax_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    percent INTEGER NOT NULL,
+    threshold INTEGER NOT NULL,
+    taxpayers INTEGER NOT NULL,
+    collected INTEGER NOT NULL,
+    ran_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_tax_runs_guild ON tax_runs(guild_id, ran_at);
@@ -0,0 +1,15 @@
+-- Frozen users can't send, bid or gamble until an admin unfreezes them
+ALTER TABLE users ADD COLUMN frozen INTEGER NOT NULL DEFAULT 0;
+
+-- Record of admin moderation actions such as freezing an account
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target TEXT NOT NULL,
+    reason TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_audit_log_target ON audit_log(target, created_at);
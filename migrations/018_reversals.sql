@@ -0,0 +1,8 @@
+-- Transactions undone with /reverse, each pointing at the compensating ledger entry.
+-- The primary key stops a transaction from being reversed twice.
+CREATE TABLE reversals (
+    transaction_id TEXT PRIMARY KEY,
+    reversal_id TEXT NOT NULL,
+    reversed_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+-- The audit log grows to cover every admin action, with the amount of coins involved where there is one
+ALTER TABLE audit_log RENAME TO admin_audit;
+ALTER TABLE admin_audit ADD COLUMN amount INTEGER;
+
+DROP INDEX idx_audit_log_target;
+CREATE INDEX idx_admin_audit_guild ON admin_audit(guild_id, created_at);
+CREATE INDEX idx_admin_audit_target ON admin_audit(target, created_at);
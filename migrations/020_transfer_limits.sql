@@ -0,0 +1,9 @@
+-- Per-user overrides of a guild's transfer.daily_limit setting, set by admins. 0 means no limit.
+CREATE TABLE transfer_limit_overrides (
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    daily_limit INTEGER NOT NULL,
+    set_by TEXT NOT NULL,
+    set_at INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, discord_id)
+);
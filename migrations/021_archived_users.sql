@@ -0,0 +1,18 @@
+-- Accounts removed with /unregister. The user row moves here with its keys so the
+-- account's signed transactions stay verifiable, and registering again restores it.
+CREATE TABLE archived_users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    encrypted_private_key TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    frozen INTEGER NOT NULL DEFAULT 0,
+    balance INTEGER NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    registered_at DATETIME,
+    archived_by TEXT NOT NULL,
+    archived_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_archived_users_discord_id ON archived_users(discord_id);
@@ -0,0 +1,20 @@
+-- Every user-signed ledger entry carries the sender's next nonce. Entries signed before
+-- nonces were assigned keep nonce 0 and are left out of the uniqueness constraint.
+CREATE UNIQUE INDEX idx_transactions_from_user_nonce ON transactions(from_user, nonce) WHERE nonce > 0;
+
+-- A signed entry is only accepted with exactly the nonce after the sender's last one,
+-- so replayed, duplicated or out-of-order entries are rejected at insert time
+CREATE TRIGGER transactions_check_nonce
+BEFORE INSERT ON transactions
+WHEN NEW.signature NOT IN ('', 'system')
+BEGIN
+    SELECT RAISE(ABORT, 'nonce out of order')
+    WHERE NEW.nonce != COALESCE((SELECT nonce FROM users WHERE discord_id = NEW.from_user), 0) + 1;
+END;
+
+CREATE TRIGGER transactions_advance_nonce
+AFTER INSERT ON transactions
+WHEN NEW.signature NOT IN ('', 'system')
+BEGIN
+    UPDATE users SET nonce = NEW.nonce, updated_at = CURRENT_TIMESTAMP WHERE discord_id = NEW.from_user;
+END;
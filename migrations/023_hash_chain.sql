@@ -0,0 +1,7 @@
+-- Hash chain over the ledger. Entries are ordered by chain_seq and each stores the
+-- hash of the one before it (see Transaction::chain_hash), so editing or deleting a
+-- past entry breaks every link after it. Existing entries are linked on startup.
+ALTER TABLE transactions ADD COLUMN chain_seq INTEGER;
+ALTER TABLE transactions ADD COLUMN prev_hash TEXT;
+
+CREATE UNIQUE INDEX idx_transactions_chain_seq ON transactions(chain_seq);
@@ -0,0 +1,11 @@
+-- Periodic digests of the ledger signed with the bot's system key. Each one pins the
+-- number of entries, the hash chain head and the net supply at that point, so a later
+-- rollback or edit of the ledger no longer matches an earlier checkpoint.
+CREATE TABLE ledger_checkpoints (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_count INTEGER NOT NULL,
+    last_hash TEXT NOT NULL,
+    total_supply INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
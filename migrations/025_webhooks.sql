@@ -0,0 +1,32 @@
+-- Outgoing webhooks for notable ledger activity. Events are queued per webhook and
+-- retried with backoff until they're delivered, so an endpoint that is down for a
+-- while doesn't lose anything.
+CREATE TABLE webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    -- Comma-separated event names: large_transaction, auction_end, mint
+    events TEXT NOT NULL,
+    -- Smallest amount that counts as a large_transaction
+    min_amount INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_webhooks_guild ON webhooks(guild_id);
+
+CREATE TABLE webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL,
+    last_error TEXT,
+    -- Set once the delivery has used up its retries
+    failed INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(failed, next_attempt_at);
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id);
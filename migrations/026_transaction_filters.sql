@@ -0,0 +1,2 @@
+-- Ledger queries filter by transaction type within a time range (/ledger, /export, /import)
+CREATE INDEX IF NOT EXISTS idx_transactions_type_time ON transactions(transaction_type, timestamp_unix);
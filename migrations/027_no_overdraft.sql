@@ -0,0 +1,16 @@
+-- No balance may be taken below zero. Ledger writes already debit with a conditional update;
+-- these triggers also stop direct writes such as update_balance. Accounts that were negative
+-- before this migration can still be credited back up.
+CREATE TRIGGER balances_no_overdraft_insert
+BEFORE INSERT ON balances
+WHEN NEW.balance < 0
+BEGIN
+    SELECT RAISE(ABORT, 'insufficient funds');
+END;
+
+CREATE TRIGGER balances_no_overdraft_update
+BEFORE UPDATE OF balance ON balances
+WHEN NEW.balance < 0 AND NEW.balance < OLD.balance
+BEGIN
+    SELECT RAISE(ABORT, 'insufficient funds');
+END;
@@ -0,0 +1,7 @@
+-- Weeks whose economy digest has been posted in a guild, so restarts don't post one twice
+CREATE TABLE digest_posts (
+    guild_id TEXT NOT NULL,
+    week_start INTEGER NOT NULL,
+    posted_at INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, week_start)
+);
@@ -0,0 +1,22 @@
+-- Leaderboard seasons. The running season has no ended_at; balances are archived when it ends.
+CREATE TABLE seasons (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    started_by TEXT NOT NULL
+);
+
+-- At most one season runs at a time
+CREATE UNIQUE INDEX idx_seasons_running ON seasons((ended_at IS NULL)) WHERE ended_at IS NULL;
+
+-- Every registered user's balance at the end of a season
+CREATE TABLE season_archive (
+    season_id INTEGER NOT NULL REFERENCES seasons(id),
+    discord_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    balance INTEGER NOT NULL,
+    PRIMARY KEY (season_id, discord_id)
+);
+
+CREATE INDEX idx_season_archive_balance ON season_archive(season_id, balance);
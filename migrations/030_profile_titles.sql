@@ -0,0 +1,2 @@
+-- Custom title shown on a user's /profile
+ALTER TABLE user_preferences ADD COLUMN title TEXT;
@@ -0,0 +1,24 @@
+-- Giveaways entered with a button on the posted embed; paid entries go into the pot the winner takes
+CREATE TABLE giveaways (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    channel_id TEXT NOT NULL,
+    message_id TEXT,
+    host_id TEXT NOT NULL,
+    prize TEXT NOT NULL,
+    entry_cost INTEGER NOT NULL DEFAULT 0,
+    pot INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'open',
+    winner_id TEXT,
+    created_at INTEGER NOT NULL,
+    ends_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_giveaways_due ON giveaways(status, ends_at);
+
+CREATE TABLE giveaway_entries (
+    giveaway_id INTEGER NOT NULL REFERENCES giveaways(id),
+    discord_id TEXT NOT NULL,
+    entered_at INTEGER NOT NULL,
+    PRIMARY KEY (giveaway_id, discord_id)
+);
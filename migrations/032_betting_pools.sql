@@ -0,0 +1,33 @@
+-- Pari-mutuel betting pools on custom questions. Stakes sit in the BETTING_POOL account until
+-- the bet is resolved, when the whole pot is split between the winning option's backers.
+CREATE TABLE bets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    question TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- No new stakes after this; NULL keeps betting open until the bet is resolved
+    closes_at INTEGER,
+    status TEXT NOT NULL DEFAULT 'open',
+    winning_option_id INTEGER,
+    resolved_at INTEGER
+);
+
+CREATE INDEX idx_bets_guild ON bets(guild_id, status);
+
+CREATE TABLE bet_options (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bet_id INTEGER NOT NULL REFERENCES bets(id),
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    UNIQUE (bet_id, position)
+);
+
+-- A user backs one option per bet; betting again adds to their stake
+CREATE TABLE bet_entries (
+    bet_id INTEGER NOT NULL REFERENCES bets(id),
+    option_id INTEGER NOT NULL REFERENCES bet_options(id),
+    discord_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (bet_id, discord_id)
+);
@@ -0,0 +1,9 @@
+-- Progressive jackpot per guild, fed by a slice of gambling losses and won outright by a lucky game.
+-- The coins themselves sit in the JACKPOT ledger account; pot is each guild's share of it.
+CREATE TABLE jackpots (
+    guild_id TEXT PRIMARY KEY,
+    pot INTEGER NOT NULL DEFAULT 0,
+    last_winner_id TEXT,
+    last_payout INTEGER,
+    last_won_at INTEGER
+);
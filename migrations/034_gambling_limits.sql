@@ -0,0 +1,3 @@
+-- Self-imposed gambling controls. Users can only tighten them; loosening or lifting early takes an admin.
+ALTER TABLE user_preferences ADD COLUMN gamble_daily_limit INTEGER;
+ALTER TABLE user_preferences ADD COLUMN gamble_excluded_until INTEGER;
@@ -0,0 +1,34 @@
+-- Multiple-choice trivia questions by category. guild_id is NULL for the built-in bank
+-- every server shares, or set for custom questions a server's admins added.
+CREATE TABLE trivia_questions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT,
+    category TEXT NOT NULL,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    wrong1 TEXT NOT NULL,
+    wrong2 TEXT NOT NULL,
+    wrong3 TEXT,
+    created_by TEXT,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+CREATE INDEX idx_trivia_questions_category ON trivia_questions(guild_id, category);
+
+INSERT INTO trivia_questions (category, question, answer, wrong1, wrong2, wrong3) VALUES
+    ('general', 'How many days are in a leap year?', '366', '365', '364', '360'),
+    ('general', 'Which colour do you get by mixing blue and yellow?', 'Green', 'Purple', 'Orange', 'Brown'),
+    ('general', 'How many sides does a hexagon have?', '6', '5', '7', '8'),
+    ('general', 'What is the largest planet in our solar system?', 'Jupiter', 'Saturn', 'Neptune', 'Earth'),
+    ('science', 'What is the chemical symbol for gold?', 'Au', 'Ag', 'Gd', 'Go'),
+    ('science', 'What gas do plants absorb from the air for photosynthesis?', 'Carbon dioxide', 'Oxygen', 'Nitrogen', 'Hydrogen'),
+    ('science', 'How many bones are in the adult human body?', '206', '201', '212', '198'),
+    ('science', 'At sea level, what temperature does water boil at in Celsius?', '100', '90', '110', '212'),
+    ('geography', 'What is the capital of Australia?', 'Canberra', 'Sydney', 'Melbourne', 'Perth'),
+    ('geography', 'Which is the longest river in South America?', 'Amazon', 'Paraná', 'Orinoco', 'Magdalena'),
+    ('geography', 'Which country has the most natural lakes?', 'Canada', 'Russia', 'Finland', 'United States'),
+    ('geography', 'Mount Kilimanjaro is in which country?', 'Tanzania', 'Kenya', 'Uganda', 'Ethiopia'),
+    ('history', 'In which year did the Berlin Wall fall?', '1989', '1991', '1987', '1985'),
+    ('history', 'Who was the first person to walk on the Moon?', 'Neil Armstrong', 'Buzz Aldrin', 'Yuri Gagarin', 'John Glenn'),
+    ('history', 'Which empire built Machu Picchu?', 'Inca', 'Aztec', 'Maya', 'Olmec'),
+    ('history', 'The Magna Carta was sealed in which century?', '13th', '11th', '15th', '17th');
@@ -0,0 +1,5 @@
+-- Last /spin per user, one spin a day
+CREATE TABLE spins (
+    discord_id TEXT PRIMARY KEY,
+    last_spin_unix INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- What /sell pays for an item. Fishing catches are hidden shop items with a sell value.
+ALTER TABLE shop_items ADD COLUMN sell_value INTEGER;
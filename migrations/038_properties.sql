@@ -0,0 +1,14 @@
+-- Income-generating properties, at most one of each kind per user per guild.
+-- Income accrues from last_collected_at and is minted when collected.
+CREATE TABLE properties (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    tier INTEGER NOT NULL DEFAULT 1,
+    purchased_at INTEGER NOT NULL,
+    last_collected_at INTEGER NOT NULL,
+    UNIQUE (guild_id, discord_id, kind)
+);
+
+CREATE INDEX idx_properties_last_collected ON properties(last_collected_at);
@@ -0,0 +1,28 @@
+-- Fake per-guild stocks. Prices random-walk on a schedule, drifting back towards base_price.
+CREATE TABLE stocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    base_price INTEGER NOT NULL,
+    UNIQUE (guild_id, symbol)
+);
+
+-- Every price a stock has had, kept for charts
+CREATE TABLE stock_prices (
+    stock_id INTEGER NOT NULL,
+    recorded_at INTEGER NOT NULL,
+    price INTEGER NOT NULL
+);
+
+CREATE INDEX idx_stock_prices_stock ON stock_prices(stock_id, recorded_at);
+
+-- Shares each user holds, with what they paid in total for the ones they still have
+CREATE TABLE stock_holdings (
+    discord_id TEXT NOT NULL,
+    stock_id INTEGER NOT NULL,
+    shares INTEGER NOT NULL,
+    cost_basis INTEGER NOT NULL,
+    PRIMARY KEY (discord_id, stock_id)
+);
@@ -0,0 +1,27 @@
+-- Latest live price of each asset paper trading follows, in US cents
+CREATE TABLE crypto_prices (
+    asset TEXT PRIMARY KEY,
+    price_cents INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Paper positions opened with Slumcoins and settled against the live price
+CREATE TABLE paper_positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    -- long or short
+    direction TEXT NOT NULL,
+    stake INTEGER NOT NULL,
+    leverage INTEGER NOT NULL,
+    entry_price_cents INTEGER NOT NULL,
+    opened_at INTEGER NOT NULL,
+    -- open, closed or liquidated
+    status TEXT NOT NULL DEFAULT 'open',
+    exit_price_cents INTEGER,
+    payout INTEGER,
+    closed_at INTEGER
+);
+
+CREATE INDEX idx_paper_positions_open ON paper_positions(status, guild_id, discord_id);
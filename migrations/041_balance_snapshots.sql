@@ -0,0 +1,15 @@
+-- Once-a-day snapshots for balance and supply charts. `day` is midnight UTC as unix seconds.
+CREATE TABLE balance_snapshots (
+    discord_id TEXT NOT NULL,
+    day INTEGER NOT NULL,
+    balance INTEGER NOT NULL,
+    -- Wallet plus vault savings plus shares at that day's prices
+    net_worth INTEGER NOT NULL,
+    PRIMARY KEY (discord_id, day)
+);
+
+CREATE TABLE supply_snapshots (
+    day INTEGER PRIMARY KEY,
+    circulating INTEGER NOT NULL,
+    users INTEGER NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Teams pool coins in a shared bank, one team per user per guild. The coins themselves sit in the
+-- TEAM_BANK ledger account, so the sum of these balances always matches its balance.
+CREATE TABLE teams (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    balance INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    UNIQUE (guild_id, name)
+);
+
+-- role is leader, officer, member or invited. Pending invites don't count as membership.
+CREATE TABLE team_members (
+    team_id INTEGER NOT NULL,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    joined_at INTEGER NOT NULL,
+    PRIMARY KEY (team_id, discord_id)
+);
+
+CREATE UNIQUE INDEX idx_team_members_one_team ON team_members(guild_id, discord_id) WHERE role != 'invited';
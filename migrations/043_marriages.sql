@@ -0,0 +1,17 @@
+-- Married couples, one partner each per guild while the marriage lasts. Joint account coins sit in the
+-- JOINT_ACCOUNT ledger account, so the sum of the open joint balances always matches its balance.
+CREATE TABLE marriages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    -- Whoever proposed
+    partner_a TEXT NOT NULL,
+    partner_b TEXT NOT NULL,
+    married_at INTEGER NOT NULL,
+    has_joint_account INTEGER NOT NULL DEFAULT 0,
+    joint_balance INTEGER NOT NULL DEFAULT 0,
+    -- Set on divorce, when the joint balance is split and zeroed
+    divorced_at INTEGER
+);
+
+CREATE INDEX idx_marriages_partner_a ON marriages(guild_id, partner_a) WHERE divorced_at IS NULL;
+CREATE INDEX idx_marriages_partner_b ON marriages(guild_id, partner_b) WHERE divorced_at IS NULL;
@@ -0,0 +1,43 @@
+-- Each guild's charity pool, paid out to a random active member at next_distribution_at. The coins
+-- themselves sit in the CHARITY_POOL ledger account, so the sum of these balances always matches its balance.
+CREATE TABLE charity_pools (
+    guild_id TEXT PRIMARY KEY,
+    balance INTEGER NOT NULL DEFAULT 0,
+    next_distribution_at INTEGER NOT NULL
+);
+
+CREATE TABLE charity_donations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    -- What the treasury added on top through a matching event
+    matched INTEGER NOT NULL DEFAULT 0,
+    donated_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_charity_donations_guild ON charity_donations(guild_id, discord_id);
+
+-- While a match runs the treasury adds `percent` of every donation, up to `cap` coins in total (NULL = no cap)
+CREATE TABLE charity_matches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    percent INTEGER NOT NULL,
+    cap INTEGER,
+    matched INTEGER NOT NULL DEFAULT 0,
+    starts_at INTEGER NOT NULL,
+    ends_at INTEGER NOT NULL,
+    created_by TEXT NOT NULL
+);
+
+CREATE INDEX idx_charity_matches_guild ON charity_matches(guild_id, ends_at);
+
+CREATE TABLE charity_distributions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    distributed_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_charity_distributions_guild ON charity_distributions(guild_id, distributed_at);
@@ -0,0 +1,14 @@
+-- Referral bonuses claimed with /refer. Each member names one inviter per guild, and a pair of
+-- accounts only ever earns one referral between them, whichever way round and in whichever guild.
+CREATE TABLE referrals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    referrer_id TEXT NOT NULL,
+    referred_id TEXT NOT NULL,
+    referrer_bonus INTEGER NOT NULL,
+    referred_bonus INTEGER NOT NULL,
+    claimed_at INTEGER NOT NULL,
+    UNIQUE (guild_id, referred_id)
+);
+
+CREATE INDEX idx_referrals_pair ON referrals(referrer_id, referred_id);
@@ -0,0 +1,18 @@
+-- Shop items sold as perks are bought through /perk and fulfilled straight away instead of
+-- landing in the inventory: 'nickname', 'pin' or 'topic'. NULL for ordinary items.
+ALTER TABLE shop_items ADD COLUMN perk TEXT;
+
+-- Channel topics bought with the topic perk. Once expires_at passes the channel goes back to
+-- original_topic, unless someone has changed it from `topic` in the meantime. One per channel, so a
+-- topic can't be bought over while another is still up.
+CREATE TABLE topic_perks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT NOT NULL,
+    channel_id TEXT NOT NULL UNIQUE,
+    discord_id TEXT NOT NULL,
+    topic TEXT NOT NULL,
+    original_topic TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_topic_perks_expires ON topic_perks(expires_at);
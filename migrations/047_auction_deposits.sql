@@ -0,0 +1,8 @@
+-- Bid deposits sitting in AUCTION_ESCROW, one row per bidder per auction. Auctions themselves only
+-- live in memory, so these rows are what lets a restart give the deposits back.
+CREATE TABLE auction_deposits (
+    voice_channel_id TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (voice_channel_id, discord_id)
+);
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use tokio::sync::RwLock;
+
+// Longest lookback anyone can ask for; older sightings are dropped as new messages come in
+pub const MAX_WINDOW_SECONDS: i64 = 3600;
+
+/// When each user last spoke in each channel, kept in memory so /rain knows who's around
+#[derive(Debug, Clone, Default)]
+pub struct ActivityTracker {
+    channels: Arc<RwLock<HashMap<serenity::ChannelId, HashMap<serenity::UserId, i64>>>>,
+}
+
+impl ActivityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn record(&self, channel_id: serenity::ChannelId, user_id: serenity::UserId, now_unix: i64) {
+        let mut channels = self.channels.write().await;
+        let seen = channels.entry(channel_id).or_default();
+        seen.insert(user_id, now_unix);
+        seen.retain(|_, last_seen| *last_seen > now_unix - MAX_WINDOW_SECONDS);
+    }
+
+    /// Users who have sent a message in the channel since `since_unix`
+    pub async fn active_since(&self, channel_id: serenity::ChannelId, since_unix: i64) -> Vec<serenity::UserId> {
+        self.channels
+            .read()
+            .await
+            .get(&channel_id)
+            .map(|seen| {
+                seen.iter()
+                    .filter(|(_, last_seen)| **last_seen >= since_unix)
+                    .map(|(user_id, _)| *user_id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,189 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use poise::serenity_prelude as serenity;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::auction::AuctionManager;
+use crate::database::{Database, DatabaseError};
+use crate::ledger::{self, FeeSchedule, LedgerError};
+use crate::metrics;
+
+#[derive(Clone)]
+struct ApiState {
+    database: Database,
+    auction_manager: AuctionManager,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TransferRequest {
+    from_user: String,
+    to_user: String,
+    amount: i64,
+    // Apply this guild's transfer fees; no fee when omitted
+    guild_id: Option<String>,
+}
+
+/// Serve the read-only HTTP API (dry-run simulations) on `bind_addr`
+pub async fn serve(
+    bind_addr: String,
+    token: Option<String>,
+    database: Database,
+    auction_manager: AuctionManager,
+) {
+    let state = ApiState { database, auction_manager, token };
+
+    let app = Router::new()
+        .route("/simulate/transfer", post(simulate_transfer))
+        .route("/simulate/settlement/:voice_channel_id", get(simulate_settlement))
+        .with_state(state);
+
+    let listener = match tokio::net::TcpListener::bind(&bind_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind API server to {}: {}", bind_addr, e);
+            return;
+        }
+    };
+
+    info!("API server listening on {}", bind_addr);
+    if let Err(e) = axum::serve(listener, app).await {
+        error!("API server error: {}", e);
+    }
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    database: Database,
+    auction_manager: AuctionManager,
+}
+
+/// Serve Prometheus metrics at `/metrics` on `bind_addr`
+pub async fn serve_metrics(bind_addr: String, database: Database, auction_manager: AuctionManager) {
+    let state = MetricsState { database, auction_manager };
+
+    let app = Router::new()
+        .route("/metrics", get(scrape_metrics))
+        .with_state(state);
+
+    let listener = match tokio::net::TcpListener::bind(&bind_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind metrics server to {}: {}", bind_addr, e);
+            return;
+        }
+    };
+
+    info!("Metrics server listening on {}", bind_addr);
+    if let Err(e) = axum::serve(listener, app).await {
+        error!("Metrics server error: {}", e);
+    }
+}
+
+async fn scrape_metrics(State(state): State<MetricsState>) -> Response {
+    let gauges = async {
+        let totals = state.database.get_economy_totals().await?;
+        Ok::<_, DatabaseError>(metrics::Gauges {
+            total_supply: totals.minted - totals.burned,
+            registered_users: state.database.count_users().await?,
+            active_auctions: state.auction_manager.active_count().await,
+            balance_cache: state.database.balance_cache_stats(),
+        })
+    };
+
+    match gauges.await {
+        Ok(gauges) => (
+            [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            metrics::render(&gauges),
+        ).into_response(),
+        Err(e) => {
+            error!("Error reading metrics gauges: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn is_authorized(state: &ApiState, headers: &HeaderMap) -> bool {
+    let Some(token) = &state.token else {
+        return true;
+    };
+
+    let provided = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    provided == Some(token.as_str())
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({ "error": "unauthorized" }))).into_response()
+}
+
+fn ledger_error_response(e: LedgerError) -> Response {
+    let status = match e {
+        LedgerError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+
+    if let LedgerError::Database(ref e) = e {
+        error!("Database error during simulation: {}", e);
+    }
+
+    (status, Json(json!({ "error": e.code(), "message": e.to_string() }))).into_response()
+}
+
+async fn simulate_transfer(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<TransferRequest>,
+) -> Response {
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let fees = match &request.guild_id {
+        Some(guild_id) => match FeeSchedule::for_guild(&state.database, guild_id).await {
+            Ok(fees) => fees,
+            Err(e) => return ledger_error_response(LedgerError::Database(e)),
+        },
+        None => FeeSchedule::default(),
+    };
+
+    match ledger::simulate_transfer(&state.database, &request.from_user, &request.to_user, request.amount, fees).await {
+        Ok(preview) => Json(preview).into_response(),
+        Err(e) => ledger_error_response(e),
+    }
+}
+
+async fn simulate_settlement(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(voice_channel_id): Path<u64>,
+) -> Response {
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    if voice_channel_id == 0 {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "no_active_auction" }))).into_response();
+    }
+
+    let Some(auction) = state
+        .auction_manager
+        .get_auction(serenity::ChannelId::new(voice_channel_id))
+        .await
+    else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "no_active_auction" }))).into_response();
+    };
+
+    match ledger::simulate_settlement(&state.database, &auction).await {
+        Ok(preview) => Json(preview).into_response(),
+        Err(e) => ledger_error_response(e),
+    }
+}
@@ -1,9 +1,72 @@
+//! In-memory auction engine with bid deposits held in escrow until settlement.
+
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use poise::serenity_prelude as serenity;
 use chrono::{DateTime, Utc, Duration};
 
+use crate::database::{DatabaseError, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::metrics;
+
+// Holds bid deposits until the auction settles
+pub const AUCTION_ESCROW_ACCOUNT: &str = "AUCTION_ESCROW";
+
+/// Refundable deposit collected on a user's first bid in an auction
+#[derive(Debug, Clone, Copy)]
+pub enum DepositRule {
+    Flat(i64),
+    Percent(i64),
+}
+
+impl DepositRule {
+    pub fn amount_for(&self, bid_amount: i64) -> i64 {
+        match self {
+            DepositRule::Flat(amount) => *amount,
+            // Round up so small bids still pay a deposit
+            DepositRule::Percent(percent) => (bid_amount * percent + 99) / 100,
+        }
+    }
+}
+
+impl std::fmt::Display for DepositRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DepositRule::Flat(amount) => write!(f, "{} Slumcoins", amount),
+            DepositRule::Percent(percent) => write!(f, "{}% of your first bid", percent),
+        }
+    }
+}
+
+/// How much a new bid has to beat the current highest bid by
+#[derive(Debug, Clone, Copy)]
+pub enum BidIncrement {
+    Flat(i64),
+    Percent(i64),
+}
+
+impl BidIncrement {
+    /// Smallest acceptable bid over `current_highest`, always at least one coin more
+    pub fn minimum_bid(&self, current_highest: i64) -> i64 {
+        let step = match self {
+            BidIncrement::Flat(amount) => *amount,
+            // Round up so the step never rounds away to nothing
+            BidIncrement::Percent(percent) => (current_highest * percent + 99) / 100,
+        };
+        current_highest + step.max(1)
+    }
+}
+
+impl std::fmt::Display for BidIncrement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BidIncrement::Flat(amount) => write!(f, "{} Slumcoins", amount),
+            BidIncrement::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AuctionBid {
     pub user_id: serenity::UserId,
@@ -14,35 +77,77 @@ pub struct AuctionBid {
 #[derive(Debug, Clone)]
 pub struct Auction {
     pub voice_channel_id: serenity::ChannelId,
+    pub guild_id: serenity::GuildId,
     pub creator_id: serenity::UserId,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub bids: HashMap<serenity::UserId, AuctionBid>,
     pub base_duration_seconds: i64,
     pub extension_seconds: i64,
+    pub deposit_rule: Option<DepositRule>,
+    pub deposits: HashMap<serenity::UserId, i64>,
+    // Lowest winning bid the auction will settle at
+    pub reserve_price: Option<i64>,
+    // A bid at or above this ends the auction immediately
+    pub buyout_price: Option<i64>,
+    // What is being sold
+    pub item: String,
+    pub image_url: Option<String>,
+    pub min_increment: BidIncrement,
+    // Message showing the live auction embed
+    pub announcement: Option<(serenity::ChannelId, serenity::MessageId)>,
 }
 
 impl Auction {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         voice_channel_id: serenity::ChannelId,
+        guild_id: serenity::GuildId,
         creator_id: serenity::UserId,
         base_duration_seconds: i64,
         extension_seconds: i64,
+        deposit_rule: Option<DepositRule>,
+        reserve_price: Option<i64>,
+        buyout_price: Option<i64>,
+        item: String,
+        image_url: Option<String>,
+        min_increment: BidIncrement,
     ) -> Self {
         let start_time = Utc::now();
         let end_time = start_time + Duration::seconds(base_duration_seconds);
 
         Auction {
             voice_channel_id,
+            guild_id,
             creator_id,
             start_time,
             end_time,
             bids: HashMap::new(),
             base_duration_seconds,
             extension_seconds,
+            deposit_rule,
+            deposits: HashMap::new(),
+            reserve_price,
+            buyout_price,
+            item,
+            image_url,
+            min_increment,
+            announcement: None,
+        }
+    }
+
+    // Deposit the user still owes before this bid is accepted (0 once paid or if none is required)
+    pub fn required_deposit(&self, user_id: serenity::UserId, amount: i64) -> i64 {
+        match self.deposit_rule {
+            Some(rule) if !self.deposits.contains_key(&user_id) => rule.amount_for(amount),
+            _ => 0,
         }
     }
 
+    pub fn deposit_of(&self, user_id: serenity::UserId) -> i64 {
+        self.deposits.get(&user_id).copied().unwrap_or(0)
+    }
+
     pub fn add_or_update_bid(&mut self, user_id: serenity::UserId, amount: i64) -> Result<(), String> {
         let now = Utc::now();
         
@@ -54,20 +159,31 @@ impl Auction {
         // Get current highest bid
         let current_highest = self.get_highest_bid_amount();
         
-        // Require bid to be higher than current highest (minimum increment of 1)
-        if amount <= current_highest {
-            return Err(format!("Bid must be higher than current highest bid of {} Slumcoins", current_highest));
+        // The opening bid can be anything, after that each bid has to clear the minimum increment
+        if self.bids.is_empty() {
+            if amount <= 0 {
+                return Err("Bid must be higher than 0 Slumcoins".to_string());
+            }
+        } else {
+            let minimum = self.minimum_bid();
+            if amount < minimum {
+                return Err(format!(
+                    "Bid must be at least {} Slumcoins (current highest {} + minimum increment of {})",
+                    minimum, current_highest, self.min_increment
+                ));
+            }
         }
         
         // Extend the auction if this is a new bid or higher bid from same user
         let should_extend = !self.bids.contains_key(&user_id) || 
-                           self.bids.get(&user_id).map_or(false, |b| b.amount != amount);
+                           self.bids.get(&user_id).is_some_and(|b| b.amount != amount);
         
         if should_extend {
-            // Only extend if we're close to the end (within 30 seconds)
+            // A bid inside the anti-snipe window leaves everyone that long to answer it. Never
+            // pull the end earlier.
             let time_remaining = self.end_time.signed_duration_since(now).num_seconds();
-            if time_remaining < 30 {
-                self.end_time = now + Duration::seconds(self.extension_seconds);
+            if time_remaining < self.extension_seconds {
+                self.end_time = self.end_time.max(now + Duration::seconds(self.extension_seconds));
             }
         }
 
@@ -76,10 +192,32 @@ impl Auction {
             amount,
             timestamp: now,
         });
+
+        if self.is_bought_out() {
+            self.end_time = now;
+        }
         
         Ok(())
     }
 
+    /// Smallest bid that would currently be accepted
+    pub fn minimum_bid(&self) -> i64 {
+        if self.bids.is_empty() {
+            1
+        } else {
+            self.min_increment.minimum_bid(self.get_highest_bid_amount())
+        }
+    }
+
+    /// Whether the highest bid reached the buyout price
+    pub fn is_bought_out(&self) -> bool {
+        self.buyout_price.is_some_and(|buyout| self.get_highest_bid_amount() >= buyout)
+    }
+
+    pub fn reserve_met(&self) -> bool {
+        self.reserve_price.is_none_or(|reserve| self.get_highest_bid_amount() >= reserve)
+    }
+
     pub fn is_expired(&self) -> bool {
         Utc::now() > self.end_time
     }
@@ -88,8 +226,9 @@ impl Auction {
         self.end_time.signed_duration_since(Utc::now()).num_seconds().max(0)
     }
 
+    // No winner when nobody bid or the highest bid is below the reserve
     pub fn get_winner(&self) -> Option<(serenity::UserId, i64)> {
-        if self.bids.is_empty() {
+        if self.bids.is_empty() || !self.reserve_met() {
             return None;
         }
 
@@ -113,6 +252,15 @@ impl Auction {
     }
 }
 
+/// Result of an accepted bid
+#[derive(Debug, Clone, Copy)]
+pub struct BidOutcome {
+    // The bid hit the buyout price and the auction should be settled now
+    pub bought_out: bool,
+    // Who was leading before this bid, if it was someone else
+    pub outbid: Option<serenity::UserId>,
+}
+
 #[derive(Debug, Clone)]
 pub struct AuctionManager {
     // Map of voice channel ID to active auction
@@ -126,12 +274,20 @@ impl AuctionManager {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn start_auction(
         &self,
         voice_channel_id: serenity::ChannelId,
+        guild_id: serenity::GuildId,
         creator_id: serenity::UserId,
         base_duration_seconds: i64,
         extension_seconds: i64,
+        deposit_rule: Option<DepositRule>,
+        reserve_price: Option<i64>,
+        buyout_price: Option<i64>,
+        item: String,
+        image_url: Option<String>,
+        min_increment: BidIncrement,
     ) -> Result<(), String> {
         let mut auctions = self.auctions.write().await;
 
@@ -141,12 +297,20 @@ impl AuctionManager {
 
         let auction = Auction::new(
             voice_channel_id,
+            guild_id,
             creator_id,
             base_duration_seconds,
             extension_seconds,
+            deposit_rule,
+            reserve_price,
+            buyout_price,
+            item,
+            image_url,
+            min_increment,
         );
 
         auctions.insert(voice_channel_id, auction);
+        metrics::record_auction_started();
         Ok(())
     }
 
@@ -155,81 +319,194 @@ impl AuctionManager {
         voice_channel_id: serenity::ChannelId,
         user_id: serenity::UserId,
         amount: i64,
-    ) -> Result<(), String> {
+        deposit: i64,
+    ) -> Result<BidOutcome, String> {
         let mut auctions = self.auctions.write().await;
 
         match auctions.get_mut(&voice_channel_id) {
             Some(auction) => {
-                auction.add_or_update_bid(user_id, amount)
+                let previous_leader = auction.bids.values().max_by_key(|bid| bid.amount).map(|bid| bid.user_id);
+                auction.add_or_update_bid(user_id, amount)?;
+                if deposit > 0 {
+                    auction.deposits.insert(user_id, deposit);
+                }
+                metrics::record_bid();
+                Ok(BidOutcome {
+                    bought_out: auction.is_bought_out(),
+                    outbid: previous_leader.filter(|leader| *leader != user_id),
+                })
             }
             None => Err("No active auction in this voice channel!".to_string()),
         }
     }
 
+    pub async fn set_announcement(
+        &self,
+        voice_channel_id: serenity::ChannelId,
+        channel_id: serenity::ChannelId,
+        message_id: serenity::MessageId,
+    ) {
+        if let Some(auction) = self.auctions.write().await.get_mut(&voice_channel_id) {
+            auction.announcement = Some((channel_id, message_id));
+        }
+    }
+
     pub async fn get_auction(&self, voice_channel_id: serenity::ChannelId) -> Option<Auction> {
         let auctions = self.auctions.read().await;
         auctions.get(&voice_channel_id).cloned()
     }
 
+    pub async fn active_count(&self) -> usize {
+        self.auctions.read().await.len()
+    }
+
     pub async fn end_auction(&self, voice_channel_id: serenity::ChannelId) -> Option<Auction> {
         let mut auctions = self.auctions.write().await;
         auctions.remove(&voice_channel_id)
     }
     
-    // Process auction completion and handle coin deduction
+    /// Void an auction without a sale, returning every deposit held in escrow
+    pub async fn cancel_auction(
+        &self,
+        voice_channel_id: serenity::ChannelId,
+        database: &crate::database::Database,
+    ) -> Result<Option<Auction>, DatabaseError> {
+        let Some(auction) = self.end_auction(voice_channel_id).await else {
+            return Ok(None);
+        };
+
+        let refunds: Vec<Transaction> = auction
+            .deposits
+            .iter()
+            .map(|(user_id, deposit)| Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                &user_id.to_string(),
+                *deposit,
+                "auction_refund",
+                Some("Auction cancelled, deposit refunded".to_string()),
+            ))
+            .collect();
+        database.release_auction_deposits(&voice_channel_id.to_string(), None, &refunds).await?;
+        metrics::record_auction_cancelled();
+
+        Ok(Some(auction))
+    }
+
+    // Process auction completion: refund losing deposits and charge the winner, all in one batch
     pub async fn process_auction_completion(
         &self, 
         auction: &Auction, 
         database: &crate::database::Database
     ) -> Result<(), String> {
-        if let Some((winner_id, winning_amount)) = auction.get_winner() {
-            let winner_id_str = winner_id.to_string();
-            
-            // Get current balance
-            match database.get_balance(&winner_id_str).await {
-                Ok(current_balance) => {
-                    if current_balance >= winning_amount {
-                        // Deduct the winning bid from winner's balance
-                        let new_balance = current_balance - winning_amount;
-                        match database.update_balance(&winner_id_str, new_balance).await {
-                            Ok(()) => {
-                                // Create transaction record for the auction win
-                                let transaction = crate::database::Transaction {
-                                    id: uuid::Uuid::new_v4().to_string(),
-                                    from_user: winner_id_str,
-                                    to_user: "AUCTION_SYSTEM".to_string(),
-                                    amount: winning_amount,
-                                    transaction_type: "auction_win".to_string(),
-                                    message: Some("Auction win deduction".to_string()),
-                                    nonce: 0,
-                                    signature: "system".to_string(),
-                                    timestamp_unix: chrono::Utc::now().timestamp(),
-                                    created_at: chrono::Utc::now(),
-                                };
-                                
-                                if let Err(e) = database.add_transaction(&transaction).await {
-                                    tracing::error!("Failed to record auction transaction: {}", e);
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("Failed to update winner balance: {}", e);
-                                return Err("Failed to process auction payment".to_string());
-                            }
-                        }
-                    } else {
-                        tracing::warn!("Winner {} has insufficient funds for auction win", winner_id);
-                        return Err("Winner has insufficient funds to pay for auction".to_string());
+        let voice_channel_id = auction.voice_channel_id.to_string();
+        let winner_id = auction.get_winner().map(|(winner_id, _)| winner_id);
+
+        // Losers always get their deposit back
+        let mut entries: Vec<Transaction> = auction
+            .deposits
+            .iter()
+            .filter(|(user_id, _)| Some(**user_id) != winner_id)
+            .map(|(user_id, deposit)| Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                &user_id.to_string(),
+                *deposit,
+                "auction_refund",
+                Some("Auction deposit refund".to_string()),
+            ))
+            .collect();
+
+        let settlement = match crate::ledger::simulate_settlement(database, auction).await {
+            Ok(Some(settlement)) => settlement,
+            Ok(None) => {
+                return match database.release_auction_deposits(&voice_channel_id, None, &entries).await {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        tracing::error!("Failed to refund auction deposits: {}", e);
+                        Err("Failed to refund auction deposits".to_string())
                     }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to get winner balance: {}", e);
-                    return Err("Failed to process auction payment".to_string());
-                }
+                };
+            }
+            Err(crate::ledger::LedgerError::InsufficientFunds { .. }) => {
+                return Self::settle_unpaid(auction, winner_id, entries, database).await;
             }
+            Err(e) => {
+                tracing::error!("Failed to get winner balance: {}", e);
+                return Err("Failed to process auction payment".to_string());
+            }
+        };
+        let refunds = entries.clone();
+
+        let deposit = winner_id.map(|id| auction.deposit_of(id)).unwrap_or(0);
+        if settlement.deposit_applied > 0 {
+            entries.push(Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                TREASURY_ACCOUNT,
+                settlement.deposit_applied,
+                "auction_win",
+                Some("Auction deposit applied to winning bid".to_string()),
+            ));
+        }
+        if deposit > settlement.deposit_applied {
+            entries.push(Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                &settlement.winner,
+                deposit - settlement.deposit_applied,
+                "auction_refund",
+                Some("Auction deposit refund".to_string()),
+            ));
         }
+        if settlement.amount > settlement.deposit_applied {
+            entries.push(Transaction::system(
+                &settlement.winner,
+                TREASURY_ACCOUNT,
+                settlement.amount - settlement.deposit_applied,
+                "auction_win",
+                Some("Auction win deduction".to_string()),
+            ));
+        }
+
+        match database.release_auction_deposits(&voice_channel_id, None, &entries).await {
+            Ok(()) => {}
+            // The winner spent the coins between the check and the batch
+            Err(DatabaseError::InsufficientFunds(_)) => {
+                return Self::settle_unpaid(auction, winner_id, refunds, database).await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to record auction settlement: {}", e);
+                return Err("Failed to process auction payment".to_string());
+            }
+        }
+
+        metrics::record_auction_completed();
         Ok(())
     }
 
+    // Settle an auction whose winner can't pay: refund the losers and forfeit the winner's deposit
+    async fn settle_unpaid(
+        auction: &Auction,
+        winner_id: Option<serenity::UserId>,
+        mut entries: Vec<Transaction>,
+        database: &crate::database::Database,
+    ) -> Result<(), String> {
+        tracing::warn!("Winner has insufficient funds for auction win");
+
+        let deposit = winner_id.map(|id| auction.deposit_of(id)).unwrap_or(0);
+        if deposit > 0 {
+            entries.push(Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                TREASURY_ACCOUNT,
+                deposit,
+                "auction_forfeit",
+                Some("Auction deposit forfeited".to_string()),
+            ));
+        }
+        if let Err(e) = database.release_auction_deposits(&auction.voice_channel_id.to_string(), None, &entries).await {
+            tracing::error!("Failed to settle unpaid auction: {}", e);
+        }
+
+        Err("Winner has insufficient funds to pay for auction".to_string())
+    }
+
     pub async fn cleanup_expired_auctions(&self) -> Vec<(serenity::ChannelId, Auction)> {
         let mut auctions = self.auctions.write().await;
         let mut expired = Vec::new();
@@ -247,6 +524,28 @@ impl AuctionManager {
     }
 }
 
+/// Give back every deposit still recorded against an auction. Auctions only live in memory, so on
+/// startup these are deposits from auctions a restart cut short. Returns how many were refunded.
+pub async fn refund_stranded_deposits(database: &crate::database::Database) -> Result<usize, DatabaseError> {
+    let deposits = database.get_auction_deposits().await?;
+
+    for channel in deposits.chunk_by(|a, b| a.voice_channel_id == b.voice_channel_id) {
+        let refunds: Vec<Transaction> = channel
+            .iter()
+            .map(|deposit| Transaction::system(
+                AUCTION_ESCROW_ACCOUNT,
+                &deposit.discord_id,
+                deposit.amount,
+                "auction_refund",
+                Some("Auction interrupted by a restart, deposit refunded".to_string()),
+            ))
+            .collect();
+        database.release_auction_deposits(&channel[0].voice_channel_id, None, &refunds).await?;
+    }
+
+    Ok(deposits.len())
+}
+
 impl Default for AuctionManager {
     fn default() -> Self {
         Self::new()
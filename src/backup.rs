@@ -0,0 +1,160 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::Database;
+use crate::health::TaskMonitor;
+use crate::Error;
+
+const BACKUP_TICK_SECONDS: u64 = 3600;
+const BACKUP_PREFIX: &str = "slumcoin-";
+const BACKUP_EXTENSION: &str = "db";
+// Backups kept in BACKUP_DIR when BACKUP_KEEP isn't set
+const DEFAULT_KEEP: usize = 14;
+// Every SQLite database file starts with this
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Directory backups are written to, from BACKUP_DIR
+pub fn backup_dir() -> Option<PathBuf> {
+    env::var("BACKUP_DIR").ok().filter(|dir| !dir.trim().is_empty()).map(PathBuf::from)
+}
+
+fn keep() -> usize {
+    env::var("BACKUP_KEEP").ok().and_then(|keep| keep.parse().ok()).unwrap_or(DEFAULT_KEEP).max(1)
+}
+
+// File name for a backup taken now, sorting in the order backups were taken
+fn file_name() -> String {
+    format!("{}{}.{}", BACKUP_PREFIX, Utc::now().format("%Y%m%d-%H%M%S"), BACKUP_EXTENSION)
+}
+
+/// Snapshot the database into `dir`, returning the new file
+pub async fn create(database: &Database, dir: &Path) -> Result<PathBuf, Error> {
+    tokio::fs::create_dir_all(dir).await?;
+    let path = dir.join(file_name());
+    // VACUUM INTO refuses to overwrite, and a leftover file from the same second is stale anyway
+    if tokio::fs::try_exists(&path).await? {
+        tokio::fs::remove_file(&path).await?;
+    }
+    let target = path.to_str().ok_or("backup path isn't valid UTF-8")?;
+    database.backup_into(target).await?;
+    Ok(path)
+}
+
+// Backups in `dir`, oldest first
+async fn list(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut backups = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_backup = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+            && path.extension().is_some_and(|extension| extension == BACKUP_EXTENSION);
+        if is_backup {
+            backups.push(path);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete all but the newest BACKUP_KEEP backups in `dir`, returning how many were removed
+pub async fn prune(dir: &Path) -> std::io::Result<usize> {
+    let backups = list(dir).await?;
+    let excess = backups.len().saturating_sub(keep());
+    for path in &backups[..excess] {
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(excess)
+}
+
+// When the newest backup in `dir` was written
+async fn last_backup_at(dir: &Path) -> std::io::Result<Option<SystemTime>> {
+    match list(dir).await?.last() {
+        Some(path) => Ok(Some(tokio::fs::metadata(path).await?.modified()?)),
+        None => Ok(None),
+    }
+}
+
+async fn run_scheduled(database: &Database, dir: &Path, every: Duration) -> Result<(), Error> {
+    let due = match last_backup_at(dir).await {
+        Ok(Some(last)) => last.elapsed().map_or(true, |elapsed| elapsed >= every),
+        Ok(None) => true,
+        // The directory doesn't exist until the first backup creates it
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+        Err(e) => return Err(e.into()),
+    };
+    if !due {
+        return Ok(());
+    }
+
+    let path = create(database, dir).await?;
+    let removed = prune(dir).await?;
+    info!("Wrote scheduled database backup {} (pruned {})", path.display(), removed);
+    Ok(())
+}
+
+/// Back up into BACKUP_DIR every BACKUP_INTERVAL_HOURS, if both are set. Timing follows the
+/// newest file in the directory, so restarts don't trigger an extra backup.
+pub fn spawn_scheduler(database: Database, monitor: TaskMonitor) {
+    let Some(dir) = backup_dir() else {
+        return;
+    };
+    let hours = env::var("BACKUP_INTERVAL_HOURS").ok().and_then(|hours| hours.parse::<u64>().ok()).unwrap_or(0);
+    if hours == 0 {
+        return;
+    }
+    let every = Duration::from_secs(hours * 3600);
+
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(BACKUP_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("backup", Duration::from_secs(BACKUP_TICK_SECONDS));
+
+            if let Err(e) = run_scheduled(&database, &dir, every).await {
+                error!("Failed to write scheduled database backup: {}", e);
+            }
+        }
+    });
+}
+
+// `sqlite:currency.db`, `sqlite://data/currency.db?mode=rwc` and plain paths all name a file
+fn database_file(database_url: &str) -> PathBuf {
+    let path = database_url.strip_prefix("sqlite:").unwrap_or(database_url);
+    let path = path.strip_prefix("//").unwrap_or(path);
+    PathBuf::from(path.split('?').next().unwrap_or(path))
+}
+
+/// Replace the database file with `backup` before it is opened. The current file is kept next
+/// to it as `<name>.pre-restore-<timestamp>`; migrations bring older backups up to date on connect.
+pub fn restore(database_url: &str, backup: &Path) -> std::io::Result<()> {
+    let mut header = [0u8; 16];
+    std::io::Read::read_exact(&mut std::fs::File::open(backup)?, &mut header)?;
+    if header != SQLITE_HEADER {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "backup isn't a SQLite database"));
+    }
+
+    let database = database_file(database_url);
+    let kept = format!(".pre-restore-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+    // WAL files go along with the old database; left in place they would corrupt the restored one
+    for suffix in ["", "-wal", "-shm"] {
+        let mut current = database.clone().into_os_string();
+        current.push(suffix);
+        if !Path::new(&current).exists() {
+            continue;
+        }
+        let mut moved = database.clone().into_os_string();
+        moved.push(&kept);
+        moved.push(suffix);
+        std::fs::rename(&current, &moved)?;
+        warn!("Moved {} to {}", Path::new(&current).display(), Path::new(&moved).display());
+    }
+
+    std::fs::copy(backup, &database)?;
+    info!("Restored database {} from {}", database.display(), backup.display());
+    Ok(())
+}
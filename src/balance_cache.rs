@@ -0,0 +1,97 @@
+//! In-memory copy of the `balances` table, so balance checks in games and auctions skip SQLite.
+//!
+//! Every cached balance carries the ledger position (`chain_seq` of the newest entry) it reflects.
+//! Writers store the balances they committed at their own entry's position and readers store what
+//! they read at the ledger head they saw, and an entry is only ever replaced by a newer position.
+//! A reader racing a writer therefore can't put a stale balance back after the write lands.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Cached balances past this are dropped wholesale and refilled by the next reads
+const MAX_ENTRIES: usize = 100_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    // None once the balance was changed outside the ledger and has to be read again
+    balance: Option<i64>,
+    seq: i64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    // Newest position among evicted balances; an evicted user can't be filled from an older read
+    floor: i64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    state: Mutex<State>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Shared between every clone of a `Database`
+#[derive(Debug, Clone, Default)]
+pub struct BalanceCache {
+    inner: Arc<Inner>,
+}
+
+impl BalanceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, discord_id: &str) -> Option<i64> {
+        let balance = self.inner.state.lock().ok()?.entries.get(discord_id).and_then(|entry| entry.balance);
+        let counter = if balance.is_some() { &self.inner.hits } else { &self.inner.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        balance
+    }
+
+    /// Store a balance read from the database while the ledger head was at `seq`
+    pub fn fill(&self, discord_id: &str, balance: i64, seq: i64) {
+        self.store(discord_id, Entry { balance: Some(balance), seq }, |existing| seq > existing.seq);
+    }
+
+    /// Store a balance committed by the ledger entry at `seq`
+    pub fn write_through(&self, discord_id: &str, balance: i64, seq: i64) {
+        self.store(discord_id, Entry { balance: Some(balance), seq }, |existing| seq >= existing.seq);
+    }
+
+    /// Drop a balance that changed without a ledger entry, with the ledger head at `seq`. Reads at
+    /// the same head may predate the change, so the balance is only cached again once the ledger moves.
+    pub fn invalidate(&self, discord_id: &str, seq: i64) {
+        self.store(discord_id, Entry { balance: None, seq }, |existing| seq >= existing.seq);
+    }
+
+    /// (hits, misses) since startup
+    pub fn stats(&self) -> (u64, u64) {
+        (self.inner.hits.load(Ordering::Relaxed), self.inner.misses.load(Ordering::Relaxed))
+    }
+
+    fn store(&self, discord_id: &str, entry: Entry, replaces: impl Fn(&Entry) -> bool) {
+        let Ok(mut state) = self.inner.state.lock() else {
+            return;
+        };
+        if let Some(existing) = state.entries.get_mut(discord_id) {
+            if replaces(existing) {
+                *existing = entry;
+            }
+            return;
+        }
+        if entry.seq < state.floor {
+            return;
+        }
+
+        if state.entries.len() >= MAX_ENTRIES {
+            // Invalidated entries are kept: dropping them would let a read from before the change back in
+            let evicted = state.entries.values().filter(|entry| entry.balance.is_some()).map(|entry| entry.seq).max();
+            state.floor = state.floor.max(evicted.unwrap_or(0));
+            state.entries.retain(|_, entry| entry.balance.is_none());
+        }
+        state.entries.insert(discord_id.to_string(), entry);
+    }
+}
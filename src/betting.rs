@@ -0,0 +1,96 @@
+use poise::serenity_prelude as serenity;
+
+use crate::database::{Bet, BetEntry, BetOption, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+
+// Holds stakes until a bet is resolved or cancelled
+pub const BETTING_POOL_ACCOUNT: &str = "BETTING_POOL";
+
+/// Decimal odds on an option: what each coin staked on it would return if it won, stake included
+pub fn odds(pot: i64, pool: i64) -> Option<f64> {
+    (pool > 0).then(|| pot as f64 / pool as f64)
+}
+
+/// Ledger entries paying out a bet. Backers of the winning option split the whole pot in
+/// proportion to their stakes, rounded down, with the remainder going to the treasury.
+/// When the bet is cancelled (`winning_option_id` is None) or nobody backed the winner,
+/// every stake is refunded instead.
+pub fn payouts(bet: &Bet, entries: &[BetEntry], winning_option_id: Option<i64>) -> Vec<Transaction> {
+    let pot: i64 = entries.iter().map(|entry| entry.amount).sum();
+    let winners: Vec<&BetEntry> = entries.iter().filter(|entry| Some(entry.option_id) == winning_option_id).collect();
+    let winning_pool: i64 = winners.iter().map(|entry| entry.amount).sum();
+
+    if winning_pool == 0 {
+        return entries
+            .iter()
+            .filter(|entry| entry.amount > 0)
+            .map(|entry| Transaction::system(
+                BETTING_POOL_ACCOUNT,
+                &entry.discord_id,
+                entry.amount,
+                "bet_refund",
+                Some(format!("Refund for bet #{}", bet.id)),
+            ))
+            .collect();
+    }
+
+    let mut payouts: Vec<Transaction> = winners
+        .iter()
+        .map(|entry| (entry, (i128::from(entry.amount) * i128::from(pot) / i128::from(winning_pool)) as i64))
+        .filter(|(_, share)| *share > 0)
+        .map(|(entry, share)| Transaction::system(
+            BETTING_POOL_ACCOUNT,
+            &entry.discord_id,
+            share,
+            "bet_win",
+            Some(format!("Winnings from bet #{}", bet.id)),
+        ))
+        .collect();
+
+    let remainder = pot - payouts.iter().map(|payout| payout.amount).sum::<i64>();
+    if remainder > 0 {
+        payouts.push(Transaction::system(
+            BETTING_POOL_ACCOUNT,
+            TREASURY_ACCOUNT,
+            remainder,
+            "bet_remainder",
+            Some(format!("Rounding remainder from bet #{}", bet.id)),
+        ));
+    }
+    payouts
+}
+
+pub fn bet_embed(bet: &Bet, options: &[BetOption]) -> serenity::CreateEmbed {
+    let pot: i64 = options.iter().map(|option| option.pool).sum();
+
+    let mut lines = Vec::new();
+    for option in options {
+        let odds = match odds(pot, option.pool) {
+            Some(odds) => format!("x{:.2}", odds),
+            None => "no bets".to_string(),
+        };
+        let marker = if bet.winning_option_id == Some(option.id) { "🏆 " } else { "" };
+        lines.push(format!(
+            "{}**{}. {}**: {} Slumcoins from {} backer(s) · {}",
+            marker, option.position, option.label, option.pool, option.backers, odds
+        ));
+    }
+
+    let status = match bet.status.as_str() {
+        "resolved" => "Resolved".to_string(),
+        "cancelled" => "Cancelled, stakes refunded".to_string(),
+        _ => match bet.closes_at {
+            Some(closes_at) if closes_at <= chrono::Utc::now().timestamp() => "Betting closed, awaiting result".to_string(),
+            Some(closes_at) => format!("Open, closes <t:{}:R>", closes_at),
+            None => "Open until resolved".to_string(),
+        },
+    };
+
+    serenity::CreateEmbed::new()
+        .title(format!("🎲 Bet #{}: {}", bet.id, bet.question))
+        .description(lines.join("\n"))
+        .field("Pot", format!("{} Slumcoins", pot), true)
+        .field("Status", status, true)
+        .footer(serenity::CreateEmbedFooter::new("Odds show the payout per coin staked, if that option wins"))
+        .color(0x9b59b6)
+}
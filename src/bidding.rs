@@ -0,0 +1,394 @@
+use poise::serenity_prelude as serenity;
+use tracing::{debug, error};
+
+use crate::auction::{Auction, AuctionManager, AUCTION_ESCROW_ACCOUNT};
+use crate::commands::{format_duration, AccountFrozen};
+use crate::database::{Database, Transaction};
+use crate::Data;
+
+// Buttons and modals on auction announcements are handled from the event handler
+pub const BUTTON_PREFIX: &str = "auctionbid:";
+
+pub const NOTIFY_OUTBID: &str = "outbid";
+
+/// Store a finished auction in the history table
+pub async fn record_history(
+    database: &Database,
+    auction: &Auction,
+    outcome: &str,
+    winner: Option<(serenity::UserId, i64)>,
+) {
+    let winner_id = winner.map(|(winner_id, _)| winner_id.to_string());
+    if let Err(e) = database.record_auction(
+        &auction.guild_id.to_string(),
+        &auction.voice_channel_id.to_string(),
+        &auction.creator_id.to_string(),
+        &auction.item,
+        outcome,
+        winner_id.as_deref().zip(winner.map(|(_, amount)| amount)),
+        auction.bids.len() as i64,
+        auction.start_time.timestamp(),
+    ).await {
+        error!("Failed to record auction history: {}", e);
+    }
+}
+
+// Announcement for a settled auction
+pub fn auction_result_message(auction: &Auction) -> String {
+    let result = match (auction.get_winner(), auction.reserve_price) {
+        (Some((winner_id, winning_amount)), _) => format!(
+            "Winner: <@{}>\n\
+            Winning bid: **{} Slumcoins**\n\
+            Hope it was worth it bub",
+            winner_id,
+            winning_amount
+        ),
+        (None, Some(reserve)) if !auction.bids.is_empty() => format!(
+            "Auction ended below the reserve of **{} Slumcoins** (highest bid {}). No sale, deposits refunded.",
+            reserve,
+            auction.get_highest_bid_amount()
+        ),
+        (None, _) => "Auction ended with no bids".to_string(),
+    };
+
+    format!("Auction for **{}** is over\n{}", auction.item, result)
+}
+
+// Live status of an auction, edited in place as bids come in
+pub fn auction_embed(auction: &Auction, ended: bool) -> serenity::CreateEmbed {
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🔨 {}", auction.item))
+        .color(if ended { 0x95a5a6 } else { 0xe67e22 });
+
+    if ended {
+        embed = embed.field("Status", "Auction over", true);
+    } else {
+        embed = embed.field("Time remaining", format_duration(auction.time_remaining() as u64), true);
+    }
+
+    embed = match auction.bids.values().max_by_key(|bid| bid.amount) {
+        Some(bid) => embed.field("Highest bid", format!("<@{}>: **{} Slumcoins**", bid.user_id, bid.amount), true),
+        None => embed.field("Highest bid", "No bids yet", true),
+    };
+    if !ended {
+        embed = embed.field("Minimum bid", format!("{} Slumcoins", auction.minimum_bid()), true);
+    }
+
+    if let Some(reserve) = auction.reserve_price {
+        let status = if auction.reserve_met() { "met" } else { "not met" };
+        embed = embed.field("Reserve", format!("{} Slumcoins ({})", reserve, status), true);
+    }
+    if let Some(buyout) = auction.buyout_price {
+        embed = embed.field("Buyout", format!("{} Slumcoins", buyout), true);
+    }
+
+    if !auction.bids.is_empty() {
+        let mut sorted_bids: Vec<_> = auction.bids.values().collect();
+        sorted_bids.sort_by_key(|bid| std::cmp::Reverse(bid.amount));
+        let bidders = sorted_bids
+            .iter()
+            .take(10)
+            .map(|bid| format!("• <@{}>: {} Slumcoins", bid.user_id, bid.amount))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field(format!("Bidders ({})", auction.bids.len()), bidders, false);
+    }
+
+    if let Some(image) = &auction.image_url {
+        embed = embed.image(image);
+    }
+
+    embed.footer(serenity::CreateEmbedFooter::new(if ended {
+        "Thanks for bidding"
+    } else {
+        "Bid with /bid place · updates live"
+    }))
+}
+
+// Re-render the auction's announcement embed, if it has one, dropping the bid button once it's over
+pub async fn refresh_auction_embed(http: &serenity::Http, auction: &Auction, ended: bool) {
+    let Some((channel_id, message_id)) = auction.announcement else {
+        return;
+    };
+
+    let components = if ended { Vec::new() } else { bid_buttons(auction.voice_channel_id) };
+    let edit = serenity::EditMessage::new()
+        .embed(auction_embed(auction, ended))
+        .components(components);
+    if let Err(e) = channel_id.edit_message(http, message_id, edit).await {
+        error!("Failed to update auction embed: {}", e);
+    }
+}
+
+// End the auction in this voice channel, settle it and return the announcement
+pub async fn finish_auction(
+    http: &serenity::Http,
+    auction_manager: &AuctionManager,
+    database: &Database,
+    voice_channel_id: serenity::ChannelId,
+) -> Option<String> {
+    let ended_auction = auction_manager.end_auction(voice_channel_id).await?;
+    refresh_auction_embed(http, &ended_auction, true).await;
+
+    let settled = auction_manager.process_auction_completion(&ended_auction, database).await;
+
+    let highest = ended_auction.bids.values().max_by_key(|bid| bid.amount);
+    let (outcome, winner) = match (&settled, ended_auction.get_winner(), highest) {
+        (Ok(()), Some((winner_id, amount)), _) => ("sold", Some((winner_id, amount))),
+        (Ok(()), None, None) => ("no_bids", None),
+        (Ok(()), None, Some(bid)) => ("reserve_not_met", Some((bid.user_id, bid.amount))),
+        (Err(_), _, bid) => ("unpaid", bid.map(|bid| (bid.user_id, bid.amount))),
+    };
+    record_history(database, &ended_auction, outcome, winner).await;
+
+    match settled {
+        Ok(()) => Some(auction_result_message(&ended_auction)),
+        Err(e) => {
+            error!("Error processing auction: {}", e);
+            Some(format!("Error processing auction: {}", e))
+        }
+    }
+}
+
+/// Place a bid for `user_id` in the auction in `voice_channel_id`, collecting any deposit.
+/// Returns the message to show everyone on success, or the reason the bid was refused.
+pub async fn place_bid(
+    http: &serenity::Http,
+    data: &Data,
+    voice_channel_id: serenity::ChannelId,
+    user_id: serenity::UserId,
+    amount: i64,
+) -> Result<String, String> {
+    let bidder = user_id.to_string();
+
+    match data.database.get_user(&bidder).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err("You're not registered! Use `/register` first.".to_string()),
+        Err(e) => {
+            error!("Database error: {}", e);
+            return Err("Database error occurred.".to_string());
+        }
+    }
+
+    let balance = match data.database.get_balance(&bidder).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            error!("Error getting balance: {}", e);
+            return Err("Error retrieving balance.".to_string());
+        }
+    };
+
+    let (deposit, paid_deposit) = match data.auction_manager.get_auction(voice_channel_id).await {
+        Some(auction) => (auction.required_deposit(user_id, amount), auction.deposit_of(user_id)),
+        None => (0, 0),
+    };
+
+    // A paid deposit counts toward the bid, a new one must be covered now
+    if balance + paid_deposit < amount || balance < deposit {
+        return Err(format!(
+            "insufficient funds! You have {} Slumcoins but need {} to place this bid.",
+            balance + paid_deposit, amount.max(deposit)
+        ));
+    }
+
+    if deposit > 0 {
+        let hold = Transaction::system(
+            &bidder,
+            AUCTION_ESCROW_ACCOUNT,
+            deposit,
+            "auction_deposit",
+            Some("Auction bid deposit".to_string()),
+        );
+        if let Err(e) = data.database.hold_auction_deposit(&voice_channel_id.to_string(), &hold).await {
+            error!("Error collecting auction deposit: {}", e);
+            return Err("Error collecting your deposit. Please try again.".to_string());
+        }
+    }
+
+    match data.auction_manager.place_bid(voice_channel_id, user_id, amount, deposit).await {
+        Ok(outcome) if outcome.bought_out => {
+            let mut message = format!("💥 **Buyout!** <@{}> bid **{} Slumcoins**", bidder, amount);
+            if let Some(result) = finish_auction(http, &data.auction_manager, &data.database, voice_channel_id).await {
+                message.push_str(&format!("\n\n{}", result));
+            }
+            Ok(message)
+        }
+        Ok(outcome) => {
+            if let Some(auction) = data.auction_manager.get_auction(voice_channel_id).await {
+                refresh_auction_embed(http, &auction, false).await;
+                if let Some(outbid) = outcome.outbid {
+                    notify_outbid(http, &data.database, &auction, outbid, amount).await;
+                }
+            }
+
+            let mut message = format!("<@{}> bid **{} Slumcoins**", bidder, amount);
+            if deposit > 0 {
+                message.push_str(&format!(
+                    "\nDeposit of **{} Slumcoins** held, refunded if they don't win.",
+                    deposit
+                ));
+            }
+            Ok(message)
+        }
+        Err(e) => {
+            if deposit > 0 {
+                let refund = Transaction::system(
+                    AUCTION_ESCROW_ACCOUNT,
+                    &bidder,
+                    deposit,
+                    "auction_refund",
+                    Some("Auction deposit refund".to_string()),
+                );
+                if let Err(e) = data.database.release_auction_deposits(&voice_channel_id.to_string(), Some(&bidder), &[refund]).await {
+                    error!("Error refunding rejected bid deposit: {}", e);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+// DM the previous leader that they've been outbid, unless they opted out
+async fn notify_outbid(
+    http: &serenity::Http,
+    database: &Database,
+    auction: &Auction,
+    outbid: serenity::UserId,
+    amount: i64,
+) {
+    match database.is_notification_opted_out(&outbid.to_string(), NOTIFY_OUTBID).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            error!("Error checking notification opt-out: {}", e);
+            return;
+        }
+    }
+
+    let location = match auction.announcement {
+        Some((channel_id, message_id)) => message_id.link(channel_id, Some(auction.guild_id)),
+        None => format!("<#{}>", auction.voice_channel_id),
+    };
+    let message = serenity::CreateMessage::new().content(format!(
+        "You've been outbid on **{}**: the highest bid is now **{} Slumcoins**.\n\
+        Jump back in: {}\n\
+        -# Turn these off with `/notifications outbid enabled:False`",
+        auction.item, amount, location
+    ));
+
+    // Users with DMs closed just don't get the notification
+    if let Err(e) = outbid.direct_message(http, message).await {
+        debug!("Couldn't DM outbid notice to {}: {}", outbid, e);
+    }
+}
+
+pub fn bid_buttons(voice_channel_id: serenity::ChannelId) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}", BUTTON_PREFIX, voice_channel_id))
+            .label("Place Bid")
+            .emoji('🔨')
+            .style(serenity::ButtonStyle::Primary),
+    ])]
+}
+
+// The auction's voice channel, encoded in the button and modal custom IDs
+fn parse_voice_channel(custom_id: &str) -> Option<serenity::ChannelId> {
+    custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|id| id.parse::<u64>().ok())
+        .map(serenity::ChannelId::new)
+}
+
+// Bidders must be in the auction's voice channel, same as `/bid place`
+fn in_voice_channel(
+    ctx: &serenity::Context,
+    guild_id: Option<serenity::GuildId>,
+    user_id: serenity::UserId,
+    voice_channel_id: serenity::ChannelId,
+) -> bool {
+    guild_id
+        .and_then(|guild_id| ctx.cache.guild(guild_id).and_then(|guild| guild.voice_states.get(&user_id).and_then(|vs| vs.channel_id)))
+        == Some(voice_channel_id)
+}
+
+fn ephemeral(content: impl Into<String>) -> serenity::CreateInteractionResponse {
+    serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
+
+/// Open the bid amount modal when someone presses "Place Bid"
+pub async fn handle_button(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), serenity::Error> {
+    let Some(voice_channel_id) = parse_voice_channel(&press.data.custom_id) else {
+        return Ok(());
+    };
+
+    let Some(auction) = data.auction_manager.get_auction(voice_channel_id).await else {
+        return press.create_response(ctx, ephemeral("This auction is over.")).await;
+    };
+
+    if !in_voice_channel(ctx, press.guild_id, press.user.id, voice_channel_id) {
+        return press.create_response(ctx, ephemeral("must be in vc to bid")).await;
+    }
+
+    match data.database.is_frozen(&press.user.id.to_string()).await {
+        Ok(false) => {}
+        Ok(true) => return press.create_response(ctx, ephemeral(AccountFrozen.to_string())).await,
+        Err(e) => {
+            error!("Error checking if {} is frozen: {}", press.user.id, e);
+            return press.create_response(ctx, ephemeral("Database error occurred.")).await;
+        }
+    }
+
+    let amount = serenity::CreateInputText::new(serenity::InputTextStyle::Short, "Amount", "amount")
+        .placeholder(format!("At least {} Slumcoins", auction.minimum_bid()))
+        .required(true);
+    let modal = serenity::CreateModal::new(press.data.custom_id.clone(), format!("Bid on {}", auction.item).chars().take(45).collect::<String>())
+        .components(vec![serenity::CreateActionRow::InputText(amount)]);
+
+    press.create_response(ctx, serenity::CreateInteractionResponse::Modal(modal)).await
+}
+
+/// Place the bid entered in the modal
+pub async fn handle_modal(
+    ctx: &serenity::Context,
+    submit: &serenity::ModalInteraction,
+    data: &Data,
+) -> Result<(), serenity::Error> {
+    let Some(voice_channel_id) = parse_voice_channel(&submit.data.custom_id) else {
+        return Ok(());
+    };
+
+    let entered = submit.data.components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            serenity::ActionRowComponent::InputText(input) if input.custom_id == "amount" => input.value.clone(),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let amount = match entered.trim().parse::<i64>() {
+        Ok(amount) if amount > 0 => amount,
+        _ => return submit.create_response(ctx, ephemeral("have to bid a whole number more than 0")).await,
+    };
+
+    if !in_voice_channel(ctx, submit.guild_id, submit.user.id, voice_channel_id) {
+        return submit.create_response(ctx, ephemeral("must be in vc to bid")).await;
+    }
+
+    match place_bid(&ctx.http, data, voice_channel_id, submit.user.id, amount).await {
+        Ok(message) => {
+            submit.create_response(ctx, serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new().content(message),
+            )).await
+        }
+        Err(message) => submit.create_response(ctx, ephemeral(message)).await,
+    }
+}
@@ -0,0 +1,136 @@
+use rand::seq::SliceRandom;
+
+const SUITS: [char; 4] = ['♠', '♥', '♦', '♣'];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Card {
+    // 1 = ace, 11-13 = jack/queen/king
+    pub rank: u8,
+    pub suit: char,
+}
+
+impl Card {
+    fn value(&self) -> u32 {
+        match self.rank {
+            1 => 11,
+            11..=13 => 10,
+            rank => rank as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let rank = match self.rank {
+            1 => "A".to_string(),
+            11 => "J".to_string(),
+            12 => "Q".to_string(),
+            13 => "K".to_string(),
+            rank => rank.to_string(),
+        };
+        write!(f, "`{}{}`", rank, self.suit)
+    }
+}
+
+/// A freshly shuffled single deck
+pub struct Shoe {
+    cards: Vec<Card>,
+}
+
+impl Default for Shoe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shoe {
+    pub fn new() -> Self {
+        let mut cards: Vec<Card> = SUITS
+            .iter()
+            .flat_map(|suit| (1..=13).map(|rank| Card { rank, suit: *suit }))
+            .collect();
+        cards.shuffle(&mut rand::thread_rng());
+        Shoe { cards }
+    }
+
+    pub fn draw(&mut self) -> Card {
+        // One hand never gets close to using a whole deck, but reshuffle rather than panic
+        if self.cards.is_empty() {
+            *self = Shoe::new();
+        }
+        self.cards.pop().expect("a new shoe has cards")
+    }
+}
+
+/// Best total for a hand, counting aces as 1 where 11 would bust
+pub fn hand_value(cards: &[Card]) -> u32 {
+    let mut total: u32 = cards.iter().map(Card::value).sum();
+    let mut soft_aces = cards.iter().filter(|card| card.rank == 1).count();
+
+    while total > 21 && soft_aces > 0 {
+        total -= 10;
+        soft_aces -= 1;
+    }
+
+    total
+}
+
+pub fn is_blackjack(cards: &[Card]) -> bool {
+    cards.len() == 2 && hand_value(cards) == 21
+}
+
+pub fn format_hand(cards: &[Card]) -> String {
+    cards.iter().map(Card::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// Dealer draws until reaching 17 or more, standing on soft 17
+pub fn play_dealer(shoe: &mut Shoe, dealer: &mut Vec<Card>) {
+    while hand_value(dealer) < 17 {
+        dealer.push(shoe.draw());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+}
+
+impl Outcome {
+    /// Total paid back to the player for a settled wager (0 for a loss)
+    pub fn payout(&self, wager: i64) -> i64 {
+        match self {
+            // Blackjack pays 3:2
+            Outcome::Blackjack => wager + wager * 3 / 2,
+            Outcome::Win => wager * 2,
+            Outcome::Push => wager,
+            Outcome::Lose => 0,
+        }
+    }
+}
+
+pub fn settle(player: &[Card], dealer: &[Card]) -> Outcome {
+    let player_value = hand_value(player);
+    let dealer_value = hand_value(dealer);
+
+    if player_value > 21 {
+        return Outcome::Lose;
+    }
+
+    match (is_blackjack(player), is_blackjack(dealer)) {
+        (true, true) => return Outcome::Push,
+        (true, false) => return Outcome::Blackjack,
+        (false, true) => return Outcome::Lose,
+        (false, false) => {}
+    }
+
+    if dealer_value > 21 || player_value > dealer_value {
+        Outcome::Win
+    } else if player_value == dealer_value {
+        Outcome::Push
+    } else {
+        Outcome::Lose
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use rand::seq::SliceRandom;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::config;
+use crate::database::{CharityMatch, Database, DatabaseError, Transaction};
+use crate::health::TaskMonitor;
+use crate::payroll;
+
+// Holds every guild's donations until they're paid out
+pub const CHARITY_POOL_ACCOUNT: &str = "CHARITY_POOL";
+
+const DISTRIBUTE_TICK_SECONDS: u64 = 300;
+const DAY_SECONDS: i64 = 86400;
+
+/// What the treasury adds to a donation under a match, before what's left of its cap and the treasury's own balance
+pub fn match_amount(amount: i64, charity_match: &CharityMatch) -> i64 {
+    let matched = amount.saturating_mul(charity_match.percent.max(0)) / 100;
+    match charity_match.cap {
+        Some(cap) => matched.min((cap - charity_match.matched).max(0)),
+        None => matched,
+    }
+}
+
+/// When a guild's pool pays out next, counting from `now`
+pub async fn next_distribution_at(database: &Database, guild_id: &str, now: i64) -> Result<i64, DatabaseError> {
+    let interval_hours = config::get_i64(database, guild_id, "charity.distribution_interval_hours").await?.max(1);
+    Ok(now + interval_hours * 3600)
+}
+
+// Registered members of the guild who have used their coins lately
+async fn active_members(http: &serenity::Http, database: &Database, guild_id: &str) -> Result<Vec<String>, String> {
+    let guild = guild_id.parse::<u64>().map_err(|e| e.to_string())?;
+    let active_days = config::get_i64(database, guild_id, "charity.active_days").await.map_err(|e| e.to_string())?;
+    let active: HashSet<String> = database
+        .get_users_active_since(Utc::now().timestamp() - active_days.max(1) * DAY_SECONDS)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .collect();
+    let members = payroll::guild_members(http, serenity::GuildId::new(guild)).await.map_err(|e| e.to_string())?;
+
+    Ok(members
+        .into_iter()
+        .map(|member| member.user.id.to_string())
+        .filter(|discord_id| active.contains(discord_id))
+        .collect())
+}
+
+async fn distribute(http: &serenity::Http, database: &Database, guild_id: &str, balance: i64, due_at: i64) -> Result<(), DatabaseError> {
+    let now = Utc::now().timestamp();
+    let next_at = next_distribution_at(database, guild_id, now).await?;
+
+    // Leave the pool due so it's retried next tick
+    let candidates = match active_members(http, database, guild_id).await {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            warn!("Failed to list active members for charity in guild {}: {}", guild_id, e);
+            return Ok(());
+        }
+    };
+    let recipient = candidates.choose(&mut rand::thread_rng()).cloned();
+
+    // With nothing to give or nobody to give it to, the pool carries over to the next distribution
+    let payout = recipient.as_ref().filter(|_| balance > 0).map(|recipient| {
+        Transaction::system(CHARITY_POOL_ACCOUNT, recipient, balance, "charity_payout", Some("Charity pool distribution".to_string()))
+    });
+    if !database.distribute_charity_pool(guild_id, due_at, balance, next_at, payout.as_ref()).await? {
+        return Ok(());
+    }
+    let Some(payout) = payout else {
+        return Ok(());
+    };
+
+    info!("Paid the charity pool of {} to {} in guild {}", balance, payout.to_user, guild_id);
+
+    let channel_id = config::get(database, guild_id, "charity.channel_id").await?;
+    let Ok(channel_id) = channel_id.parse::<u64>() else {
+        return Ok(());
+    };
+    let message = format!(
+        "💝 **Charity distribution!**\n<@{}> receives the pool of **{} Slumcoins** donated by this server. Next one <t:{}:R>.",
+        payout.to_user, balance, next_at
+    );
+    if let Err(e) = serenity::ChannelId::new(channel_id).say(http, message).await {
+        error!("Failed to announce charity distribution: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Pay every due charity pool to a random active member of its guild
+pub fn spawn_distributor(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(DISTRIBUTE_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("charity", Duration::from_secs(DISTRIBUTE_TICK_SECONDS));
+
+            let due = match database.get_due_charity_pools(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due charity pools: {}", e);
+                    continue;
+                }
+            };
+
+            for (guild_id, balance, due_at) in due {
+                if let Err(e) = distribute(&http, &database, &guild_id, balance, due_at).await {
+                    error!("Failed to distribute charity pool for guild {}: {}", guild_id, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,79 @@
+use std::io::Cursor;
+use chrono::Utc;
+use plotters::prelude::*;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::database::Database;
+use crate::health::TaskMonitor;
+
+const DAY_SECONDS: i64 = 86400;
+const SNAPSHOT_TICK_SECONDS: u64 = 3600;
+const CHART_WIDTH: u32 = 800;
+const CHART_HEIGHT: u32 = 320;
+const GRID_LINES: i64 = 4;
+const LINE_COLOR: RGBColor = RGBColor(0x29, 0x80, 0xb9);
+const GRID_COLOR: RGBColor = RGBColor(0xe0, 0xe0, 0xe0);
+
+/// Midnight UTC of the day `timestamp` falls on
+pub fn day_of(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(DAY_SECONDS)
+}
+
+/// Render `values` as a line chart PNG. There are no fonts to draw with, so the chart has
+/// gridlines but no labels; callers put the range and dates next to it.
+pub fn line_chart(values: &[i64]) -> Result<Vec<u8>, String> {
+    let low = values.iter().copied().min().unwrap_or_default();
+    let high = values.iter().copied().max().unwrap_or_default();
+    // Leave some room above and below, and give a flat line somewhere to sit
+    let padding = ((high - low) / 10).max(1);
+    let (low, high) = (low - padding, high + padding);
+    let last = values.len().saturating_sub(1).max(1);
+
+    let mut buffer = vec![0u8; (CHART_WIDTH * CHART_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (CHART_WIDTH, CHART_HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(16)
+            .build_cartesian_2d(0..last, low..high)
+            .map_err(|e| e.to_string())?;
+
+        for line in 0..=GRID_LINES {
+            let y = low + (high - low) * line / GRID_LINES;
+            chart
+                .draw_series(LineSeries::new([(0, y), (last, y)], GRID_COLOR.stroke_width(1)))
+                .map_err(|e| e.to_string())?;
+        }
+        chart
+            .draw_series(LineSeries::new(values.iter().copied().enumerate(), LINE_COLOR.stroke_width(3)))
+            .map_err(|e| e.to_string())?;
+
+        root.present().map_err(|e| e.to_string())?;
+    }
+
+    let image = image::RgbImage::from_raw(CHART_WIDTH, CHART_HEIGHT, buffer).ok_or("Chart buffer has the wrong size")?;
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageOutputFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png.into_inner())
+}
+
+/// Record today's balance and supply snapshots once a day
+pub fn spawn_snapshotter(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(SNAPSHOT_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("snapshots", Duration::from_secs(SNAPSHOT_TICK_SECONDS));
+
+            let day = day_of(Utc::now().timestamp());
+            match database.record_daily_snapshots(day).await {
+                Ok(true) => info!("Recorded balance snapshots for day {}", day),
+                Ok(false) => {}
+                Err(e) => error!("Failed to record balance snapshots: {}", e),
+            }
+        }
+    });
+}
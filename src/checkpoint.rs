@@ -0,0 +1,182 @@
+//! Signed ledger checkpoints: a digest of the hash chain signed with the bot's own system key.
+
+use chrono::Utc;
+use tracing::info;
+
+use crate::crypto::{CryptoError, CryptoManager};
+use crate::database::{Checkpoint, Database, Transaction, GENESIS_HASH, SYSTEM_ACCOUNT};
+
+// The system private key is stored encrypted like a user's, bound to the SYSTEM account
+pub const SYSTEM_KEY_CONFIG_KEY: &str = "system_signing_key";
+
+/// The bot's own signing key, generated on first use
+pub struct SystemKey {
+    pub public_key: String,
+    private_key: String,
+}
+
+impl SystemKey {
+    /// Load the system key, creating it if this is the first checkpoint. The public key is always
+    /// derived from the private key, so swapping one stored value can't pass off forged checkpoints.
+    pub async fn load(database: &Database, crypto: &CryptoManager) -> Result<Self, CryptoError> {
+        if database.get_system_config(SYSTEM_KEY_CONFIG_KEY).await?.is_none() {
+            let (_, private_key) = crypto.generate_keypair()?;
+            let encrypted = crypto.encrypt_private_key(&private_key, SYSTEM_ACCOUNT)?;
+            if database.insert_system_config(SYSTEM_KEY_CONFIG_KEY, &encrypted).await? {
+                info!("Generated system signing key");
+            }
+        }
+
+        // Re-read so two first starts racing end up with the same key
+        let encrypted = database
+            .get_system_config(SYSTEM_KEY_CONFIG_KEY)
+            .await?
+            .ok_or(CryptoError::InvalidKey)?;
+        let private_key = crypto.decrypt_private_key(&encrypted, SYSTEM_ACCOUNT)?;
+        let public_key = crypto.public_key_for(&private_key)?;
+
+        Ok(SystemKey { public_key, private_key })
+    }
+
+    fn sign(&self, crypto: &CryptoManager, checkpoint: &Checkpoint) -> Result<String, CryptoError> {
+        crypto.sign_transaction(&self.private_key, &signing_payload(checkpoint))
+    }
+
+    fn verify(&self, crypto: &CryptoManager, checkpoint: &Checkpoint) -> bool {
+        crypto.verify_signature(&self.public_key, &checkpoint.signature, &signing_payload(checkpoint))
+    }
+}
+
+fn signing_payload(checkpoint: &Checkpoint) -> String {
+    format!(
+        "checkpoint:{}:{}:{}:{}",
+        checkpoint.transaction_count, checkpoint.last_hash, checkpoint.total_supply, checkpoint.created_at
+    )
+}
+
+/// Chain head hash and net supply after each prefix of the ledger: entry `n` describes the first `n` transactions
+fn chain_prefixes(chain: &[(Transaction, String)]) -> Vec<(String, i64)> {
+    let mut prefixes = Vec::with_capacity(chain.len() + 1);
+    let mut head = GENESIS_HASH.to_string();
+    let mut supply = 0;
+    prefixes.push((head.clone(), supply));
+
+    for (transaction, prev_hash) in chain {
+        head = transaction.chain_hash(prev_hash);
+        if transaction.from_user == SYSTEM_ACCOUNT {
+            supply += transaction.amount;
+        }
+        if transaction.to_user == SYSTEM_ACCOUNT {
+            supply -= transaction.amount;
+        }
+        prefixes.push((head.clone(), supply));
+    }
+
+    prefixes
+}
+
+/// Sign and store a checkpoint of the current ledger. Returns None if nothing changed since the last one.
+pub async fn create(database: &Database, crypto: &CryptoManager) -> Result<Option<Checkpoint>, CryptoError> {
+    let key = SystemKey::load(database, crypto).await?;
+    let chain = database.get_hash_chain().await?;
+    let (last_hash, total_supply) = chain_prefixes(&chain).pop().unwrap_or_default();
+
+    let latest = database.get_latest_checkpoint().await?;
+    if latest.is_some_and(|latest| latest.transaction_count == chain.len() as i64 && latest.last_hash == last_hash) {
+        return Ok(None);
+    }
+
+    let mut checkpoint = Checkpoint {
+        id: 0,
+        transaction_count: chain.len() as i64,
+        last_hash,
+        total_supply,
+        signature: String::new(),
+        created_at: Utc::now().timestamp(),
+    };
+    checkpoint.signature = key.sign(crypto, &checkpoint)?;
+    checkpoint.id = database.record_checkpoint(&checkpoint).await?;
+
+    Ok(Some(checkpoint))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointProblem {
+    InvalidSignature { checkpoint_id: i64 },
+    // The ledger now has fewer entries than the checkpoint saw
+    RolledBack { checkpoint_id: i64, expected: i64, found: i64 },
+    // The first `transaction_count` entries no longer hash to the checkpoint's head
+    HashMismatch { checkpoint_id: i64 },
+    SupplyMismatch { checkpoint_id: i64, expected: i64, found: i64 },
+}
+
+impl std::fmt::Display for CheckpointProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CheckpointProblem::InvalidSignature { checkpoint_id } => {
+                write!(f, "Checkpoint #{} has an invalid signature", checkpoint_id)
+            }
+            CheckpointProblem::RolledBack { checkpoint_id, expected, found } => {
+                write!(f, "Checkpoint #{} covered {} transactions but the ledger only has {}", checkpoint_id, expected, found)
+            }
+            CheckpointProblem::HashMismatch { checkpoint_id } => {
+                write!(f, "Ledger history before checkpoint #{} was changed", checkpoint_id)
+            }
+            CheckpointProblem::SupplyMismatch { checkpoint_id, expected, found } => {
+                write!(f, "Checkpoint #{} recorded a supply of {} but the ledger now gives {}", checkpoint_id, expected, found)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointReport {
+    pub checkpoints_checked: usize,
+    pub problems: Vec<CheckpointProblem>,
+    pub latest: Option<Checkpoint>,
+}
+
+/// Check every stored checkpoint's signature and compare it against the ledger as it is now
+pub async fn verify(database: &Database, crypto: &CryptoManager) -> Result<CheckpointReport, CryptoError> {
+    let checkpoints = database.get_checkpoints().await?;
+    let mut report = CheckpointReport {
+        checkpoints_checked: checkpoints.len(),
+        ..Default::default()
+    };
+    if checkpoints.is_empty() {
+        return Ok(report);
+    }
+
+    let key = SystemKey::load(database, crypto).await?;
+    let chain = database.get_hash_chain().await?;
+    let prefixes = chain_prefixes(&chain);
+
+    for checkpoint in &checkpoints {
+        if !key.verify(crypto, checkpoint) {
+            report.problems.push(CheckpointProblem::InvalidSignature { checkpoint_id: checkpoint.id });
+            continue;
+        }
+
+        let Some((head, supply)) = usize::try_from(checkpoint.transaction_count).ok().and_then(|count| prefixes.get(count)) else {
+            report.problems.push(CheckpointProblem::RolledBack {
+                checkpoint_id: checkpoint.id,
+                expected: checkpoint.transaction_count,
+                found: chain.len() as i64,
+            });
+            continue;
+        };
+
+        if *head != checkpoint.last_hash {
+            report.problems.push(CheckpointProblem::HashMismatch { checkpoint_id: checkpoint.id });
+        } else if *supply != checkpoint.total_supply {
+            report.problems.push(CheckpointProblem::SupplyMismatch {
+                checkpoint_id: checkpoint.id,
+                expected: checkpoint.total_supply,
+                found: *supply,
+            });
+        }
+    }
+
+    report.latest = checkpoints.into_iter().last();
+    Ok(report)
+}
@@ -1,17 +1,43 @@
 use poise::serenity_prelude as serenity;
 use std::env;
 use tracing::error;
-use chrono::Utc;
-use uuid::Uuid;
 
-use crate::{Context, Error, database::Transaction};
-use super::is_admin;
+use crate::{Context, Error, config, database::Transaction};
+use crate::database::{DatabaseError, SYSTEM_ACCOUNT};
+use crate::{health, ledger, registration};
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{
+    admin_audit_entry, author_voice_channel, confirm, format_duration, is_admin, log_admin_action, mention_list, page_buttons,
+    voice_channel_members, database_error_message,
+};
 
-#[poise::command(slash_command)]
+// Maximum number of audit findings listed per section
+const AUDIT_DISPLAY_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum AirdropSource {
+    #[name = "mint"]
+    Mint,
+    #[name = "treasury"]
+    Treasury,
+}
+
+impl AirdropSource {
+    fn account(&self) -> &'static str {
+        match self {
+            AirdropSource::Mint => SYSTEM_ACCOUNT,
+            AirdropSource::Treasury => TREASURY_ACCOUNT,
+        }
+    }
+}
+
+/// Give Slumcoins to a user (admin)
+#[poise::command(slash_command, category = "Admin", guild_only)]
 pub async fn give(
     ctx: Context<'_>,
     #[description = "User to give coins to"] user: serenity::User,
     #[description = "Amount of coins to give"] amount: i64,
+    #[description = "Why the coins are being given"] reason: Option<String>,
 ) -> Result<(), Error> {
     let data = &ctx.data();
 
@@ -30,57 +56,663 @@ pub async fn give(
         return Ok(());
     }
 
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
     let to_user_id = user.id.to_string();
-    let from_user_id = "SYSTEM".to_string();
-
-    // Check if target user is registered
-    match data.database.get_user(&to_user_id).await {
-        Ok(Some(_)) => {
-            // Create a system mint transaction
-            let transaction = Transaction {
-                id: Uuid::new_v4().to_string(),
-                from_user: from_user_id,
-                to_user: to_user_id.clone(),
-                amount,
-                transaction_type: "mint".to_string(),
-                message: Some(format!("Admin grant by {}", ctx.author().name)),
-                nonce: 0,
-                signature: "system".to_string(),
-                timestamp_unix: Utc::now().timestamp(),
-                created_at: Utc::now(),
-            };
-
-            match data.database.add_transaction(&transaction).await {
-                Ok(()) => {
-                    // Update balance
-                    let current_balance = data.database.get_balance(&to_user_id).await.unwrap_or(0);
-                    let new_balance = current_balance + amount;
-
-                    match data.database.update_balance(&to_user_id, new_balance).await {
-                        Ok(()) => {
-                            let response = format!("Gave {} Slumcoins to {}. New balance: {}", amount, user.name, new_balance);
-                            ctx.say(response).await?;
-                        }
-                        Err(e) => {
-                            error!("Error updating balance: {}", e);
-                            ctx.say("Error updating balance.").await?;
-                        }
-                    }
-                }
+    if let Err(e) = data.database.require_user(&to_user_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let threshold = config::get_i64(&data.database, &guild_id, "confirm.threshold").await?;
+    if threshold > 0 && amount > threshold
+        && !confirm(ctx, format!("Mint **{} Slumcoins** for <@{}>?", amount, user.id)).await?
+    {
+        return Ok(());
+    }
+
+    let mint = Transaction::system(SYSTEM_ACCOUNT, &to_user_id, amount, "mint", Some(format!("Admin grant by {}", ctx.author().name)));
+    match data.database.apply_transaction(&mint).await {
+        Ok(()) => {
+            log_admin_action(ctx, "give", to_user_id.clone(), Some(amount), reason).await;
+            let new_balance = data.database.get_balance(&to_user_id).await?;
+            ctx.say(format!("Gave {} Slumcoins to {}. New balance: {}", amount, user.name, new_balance)).await?;
+        }
+        Err(e) => {
+            error!("Error minting coins: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error processing transaction.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Split coins between everyone in your voice channel
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn airdrop(
+    ctx: Context<'_>,
+    #[description = "Total coins to split between the voice channel"] amount: i64,
+    #[description = "Mint new coins or pay from the treasury (default: mint)"] source: Option<AirdropSource>,
+    #[description = "Why the coins are being dropped"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let source = source.unwrap_or(AirdropSource::Mint);
+
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let Some(voice_channel_id) = author_voice_channel(ctx) else {
+        ctx.say("You must be in a voice channel to airdrop to it!").await?;
+        return Ok(());
+    };
+
+    // Bots and unregistered members are skipped
+    let mut recipients = Vec::new();
+    for member in voice_channel_members(ctx, voice_channel_id) {
+        let member = member.to_string();
+        if data.database.get_user(&member).await?.is_some() {
+            recipients.push(member);
+        }
+    }
+
+    if recipients.is_empty() {
+        ctx.say("Nobody in your voice channel is registered.").await?;
+        return Ok(());
+    }
+
+    // Whatever doesn't split evenly stays with the source
+    let share = amount / recipients.len() as i64;
+    if share == 0 {
+        ctx.say(format!("{} people are in the channel, so airdrop at least {} Slumcoins.", recipients.len(), recipients.len())).await?;
+        return Ok(());
+    }
+    let total = share * recipients.len() as i64;
+
+    if source == AirdropSource::Treasury {
+        let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+        if treasury_balance < total {
+            ctx.say(format!("The treasury only holds {} Slumcoins.", treasury_balance)).await?;
+            return Ok(());
+        }
+    }
+
+    let message = format!("Airdrop by {}", ctx.author().name);
+    let entries: Vec<Transaction> = recipients
+        .iter()
+        .map(|recipient| Transaction::system(source.account(), recipient, share, "airdrop", Some(message.clone())))
+        .collect();
+
+    match data.database.apply_transactions(&entries).await {
+        Ok(()) => {
+            log_admin_action(ctx, "airdrop", voice_channel_id.to_string(), Some(total), reason).await;
+            let embed = serenity::CreateEmbed::new()
+                .title("🪂 Airdrop!")
+                .description(format!(
+                    "<@{}> dropped **{} Slumcoins** on {} people in <#{}>",
+                    ctx.author().id, total, recipients.len(), voice_channel_id
+                ))
+                .field("Each got", format!("{} Slumcoins", share), true)
+                .field("Source", if source == AirdropSource::Mint { "Minted" } else { "Treasury" }, true)
+                .field("Recipients", mention_list(&recipients), false);
+            ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => {
+            error!("Error applying airdrop: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Airdrop failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify ledger signatures and balances
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn audit(
+    ctx: Context<'_>,
+    #[description = "Rewrite cached balances from the ledger if discrepancies are found"] repair: Option<bool>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    ctx.defer().await?;
+
+    let report = match ledger::audit(&data.database, &data.crypto).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Error auditing ledger: {}", e);
+            ctx.say("Error running ledger audit.").await?;
+            return Ok(());
+        }
+    };
+
+    let mut response = format!(
+        "**Ledger Audit**\n\
+        Transactions checked: **{}**\n\
+        Signatures verified: **{}**\n\
+        Invalid signatures: **{}**\n\
+        Broken hash chain links: **{}**\n\
+        Balance discrepancies: **{}**\n",
+        report.transactions_checked,
+        report.signatures_verified,
+        report.invalid_signatures.len(),
+        report.broken_links.len(),
+        report.discrepancies.len()
+    );
+    if let Some(head) = &report.chain_head {
+        response.push_str(&format!("Chain head: `{}`\n", head));
+    }
+
+    if report.is_clean() {
+        response.push_str("\nLedger is consistent.");
+        ctx.say(response).await?;
+        return Ok(());
+    }
+
+    if !report.invalid_signatures.is_empty() {
+        response.push_str("\n**Invalid signatures:**\n");
+        for id in report.invalid_signatures.iter().take(AUDIT_DISPLAY_LIMIT) {
+            response.push_str(&format!("• `{}`\n", id));
+        }
+    }
+
+    if !report.broken_links.is_empty() {
+        response.push_str("\n**Broken hash chain links** (entry edited or removed just before):\n");
+        for id in report.broken_links.iter().take(AUDIT_DISPLAY_LIMIT) {
+            response.push_str(&format!("• `{}`\n", id));
+        }
+    }
+
+    if !report.discrepancies.is_empty() {
+        response.push_str("\n**Balance discrepancies:**\n");
+        for discrepancy in report.discrepancies.iter().take(AUDIT_DISPLAY_LIMIT) {
+            response.push_str(&format!(
+                "• <@{}>: stored {} / ledger {}\n",
+                discrepancy.discord_id, discrepancy.stored, discrepancy.computed
+            ));
+        }
+
+        if repair.unwrap_or(false) {
+            match data.database.verify_and_update_balances().await {
+                Ok(()) => response.push_str("\nBalances repaired from the ledger."),
                 Err(e) => {
-                    error!("Error adding transaction: {}", e);
-                    ctx.say("Error processing transaction.").await?;
+                    error!("Error repairing balances: {}", e);
+                    response.push_str("\nFailed to repair balances.");
                 }
             }
         }
+    }
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// View and change server settings
+#[poise::command(slash_command, category = "Admin", rename = "config", guild_only, check = "is_admin", subcommands("config_list", "config_set", "config_reset"))]
+pub async fn server_config(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show every setting and its current value
+#[poise::command(slash_command, rename = "list")]
+pub async fn config_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let mut response = "**Server Settings**\n".to_string();
+    for setting in config::SETTINGS {
+        let value = config::get(&data.database, &guild_id, setting.key).await?;
+        response.push_str(&format!("• `{}` = `{}` - {}\n", setting.key, value, setting.description));
+    }
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Change a server setting
+#[poise::command(slash_command, rename = "set")]
+pub async fn config_set(
+    ctx: Context<'_>,
+    #[description = "Setting key (see /config list)"] key: String,
+    #[description = "New value"] value: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let setting = match config::find_setting(&key) {
+        Some(setting) => setting,
+        None => {
+            ctx.say(format!("Unknown setting `{}`. Use `/config list` to see available settings.", key)).await?;
+            return Ok(());
+        }
+    };
+
+    if !config::validate(setting, &value) {
+        ctx.say(format!("`{}` is not a valid value for `{}` (default is `{}`).", value, key, setting.default)).await?;
+        return Ok(());
+    }
+
+    match data.database.set_guild_setting(&guild_id, setting.key, &value).await {
+        Ok(()) => {
+            log_admin_action(ctx, "config_set", setting.key, None, Some(format!("set to {}", value))).await;
+            ctx.say(format!("Set `{}` to `{}`", setting.key, value)).await?;
+        }
+        Err(e) => {
+            error!("Error saving setting: {}", e);
+            ctx.say("Error saving setting.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Put a setting back to its default
+#[poise::command(slash_command, rename = "reset")]
+pub async fn config_reset(
+    ctx: Context<'_>,
+    #[description = "Setting key (see /config list)"] key: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let setting = match config::find_setting(&key) {
+        Some(setting) => setting,
+        None => {
+            ctx.say(format!("Unknown setting `{}`. Use `/config list` to see available settings.", key)).await?;
+            return Ok(());
+        }
+    };
+
+    match data.database.reset_guild_setting(&guild_id, setting.key).await {
+        Ok(()) => {
+            log_admin_action(ctx, "config_reset", setting.key, None, None).await;
+            ctx.say(format!("Reset `{}` to its default `{}`", setting.key, setting.default)).await?;
+        }
+        Err(e) => {
+            error!("Error resetting setting: {}", e);
+            ctx.say("Error resetting setting.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_account_frozen(ctx: Context<'_>, user: serenity::User, frozen: bool, reason: Option<String>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let action = if frozen { "freeze" } else { "unfreeze" };
+    let entry = admin_audit_entry(ctx, action, user.id.to_string(), None, reason);
+
+    match data.database.set_frozen(&entry.target, frozen, &entry).await {
+        Ok(true) if frozen => {
+            ctx.say(format!("🧊 Froze <@{}>. They can't send, bid or gamble until unfrozen.", user.id)).await?;
+        }
+        Ok(true) => {
+            ctx.say(format!("Unfroze <@{}>.", user.id)).await?;
+        }
+        Ok(false) => match data.database.get_user(&entry.target).await? {
+            Some(_) if frozen => {
+                ctx.say(format!("<@{}> is already frozen.", user.id)).await?;
+            }
+            Some(_) => {
+                ctx.say(format!("<@{}> isn't frozen.", user.id)).await?;
+            }
+            None => {
+                ctx.say(format!("<@{}> is not registered!", user.id)).await?;
+            }
+        },
+        Err(e) => {
+            error!("Error trying to {} account: {}", action, e);
+            ctx.say("Database error occurred.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stop a user from sending, bidding and gambling
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn freeze(
+    ctx: Context<'_>,
+    #[description = "User to freeze"] user: serenity::User,
+    #[description = "Why the account is being frozen"] reason: Option<String>,
+) -> Result<(), Error> {
+    set_account_frozen(ctx, user, true, reason).await
+}
+
+/// Lift a freeze placed with /freeze
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn unfreeze(
+    ctx: Context<'_>,
+    #[description = "User to unfreeze"] user: serenity::User,
+    #[description = "Why the account is being unfrozen"] reason: Option<String>,
+) -> Result<(), Error> {
+    set_account_frozen(ctx, user, false, reason).await
+}
+
+// Users as mentions, system accounts such as TREASURY by name
+fn account_label(account: &str) -> String {
+    if account.parse::<u64>().is_ok() {
+        format!("<@{}>", account)
+    } else {
+        format!("`{}`", account)
+    }
+}
+
+/// Undo a transaction with a compensating ledger entry
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn reverse(
+    ctx: Context<'_>,
+    #[description = "ID of the transaction to undo"] transaction_id: String,
+    #[description = "Why the transaction is being reversed"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let transaction_id = transaction_id.trim();
+
+    let original = match data.database.get_transaction(transaction_id).await {
+        Ok(Some(original)) => original,
         Ok(None) => {
-            ctx.say("Target user is not registered!").await?;
+            ctx.say(format!("No transaction with ID `{}`.", transaction_id)).await?;
+            return Ok(());
         }
         Err(e) => {
-            error!("Database error: {}", e);
+            error!("Error loading transaction {}: {}", transaction_id, e);
             ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    };
+
+    if original.transaction_type == "reversal" {
+        ctx.say("Reversals can't be reversed themselves.").await?;
+        return Ok(());
+    }
+    if let Some(reversal_id) = data.database.get_reversal(&original.id).await? {
+        ctx.say(format!("That transaction was already reversed by `{}`.", reversal_id)).await?;
+        return Ok(());
+    }
+
+    // SYSTEM mints coins out of nothing, so it can always take back or hand back what it recorded
+    if original.to_user != SYSTEM_ACCOUNT {
+        let balance = data.database.get_balance(&original.to_user).await?;
+        if balance < original.amount {
+            ctx.send(poise::CreateReply::default()
+                .content(format!(
+                    "{} only has {} Slumcoins left, so the {} can't be taken back.",
+                    account_label(&original.to_user), balance, original.amount
+                ))
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+            return Ok(());
+        }
+    }
+
+    let entry = admin_audit_entry(ctx, "reverse", original.id.clone(), Some(original.amount), reason);
+    let reversal = Transaction::system(
+        &original.to_user,
+        &original.from_user,
+        original.amount,
+        "reversal",
+        Some(match &entry.reason {
+            Some(reason) => format!("Reversal of {}: {}", original.id, reason),
+            None => format!("Reversal of {}", original.id),
+        }),
+    );
+
+    match data.database.reverse_transaction(&original.id, &reversal, &entry).await {
+        Ok(true) => {
+            ctx.send(poise::CreateReply::default()
+                .content(format!(
+                    "↩️ Reversed the `{}` of **{} Slumcoins** from {} to {}.\nCompensating entry: `{}`",
+                    original.transaction_type,
+                    original.amount,
+                    account_label(&original.from_user),
+                    account_label(&original.to_user),
+                    reversal.id
+                ))
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Ok(false) => {
+            ctx.say("That transaction was already reversed.").await?;
+        }
+        Err(e) => {
+            error!("Error reversing transaction {}: {}", original.id, e);
+            ctx.say(database_error_message(ctx, &e, "Error reversing transaction. No coins were moved.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+const AUDIT_LOG_PAGE_SIZE: u32 = 10;
+
+// One page of the admin audit log (pages start at 1), returning the embed and the page count
+async fn audit_log_page(
+    ctx: Context<'_>,
+    user: Option<&str>,
+    page: u32,
+) -> Result<(serenity::CreateEmbed, u32), DatabaseError> {
+    let database = &ctx.data().database;
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let total = database.count_admin_audit(&guild_id, user).await?.max(0) as u32;
+    let total_pages = total.div_ceil(AUDIT_LOG_PAGE_SIZE).max(1);
+    let page = page.clamp(1, total_pages);
+    let entries = database.get_admin_audit(&guild_id, user, AUDIT_LOG_PAGE_SIZE, (page - 1) * AUDIT_LOG_PAGE_SIZE).await?;
+
+    let mut description = String::new();
+    if entries.is_empty() {
+        description.push_str("No admin actions recorded yet.");
+    }
+    for entry in &entries {
+        description.push_str(&format!(
+            "<t:{}:R> **{}** by <@{}> → {}",
+            entry.created_at, entry.action, entry.actor, account_label(&entry.target)
+        ));
+        if let Some(amount) = entry.amount {
+            description.push_str(&format!(" · {} coins", amount));
+        }
+        if let Some(reason) = &entry.reason {
+            description.push_str(&format!(" · *{}*", reason));
+        }
+        description.push('\n');
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Admin Audit Log")
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(format!("Page {} of {} · {} actions", page, total_pages, total)));
+
+    Ok((embed, total_pages))
+}
+
+/// Browse admin actions taken in this server
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn auditlog(
+    ctx: Context<'_>,
+    #[description = "Only show actions taken by or against this user"] user: Option<serenity::User>,
+    #[description = "Page to start on (default: 1)"] page: Option<u32>,
+) -> Result<(), Error> {
+    let user_id = user.map(|user| user.id.to_string());
+
+    let (mut embed, total_pages) = match audit_log_page(ctx, user_id.as_deref(), page.unwrap_or(1)).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Error loading admin audit log: {}", e);
+            ctx.say("Error loading the audit log.").await?;
+            return Ok(());
+        }
+    };
+    let mut page = page.unwrap_or(1).clamp(1, total_pages);
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(embed.clone())
+        .components(page_buttons(ctx_id, page, total_pages))
+        .ephemeral(true)).await?;
+
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(std::time::Duration::from_secs(120))
+        .await
+    {
+        if press.data.custom_id.ends_with("next") {
+            page += 1;
+        } else {
+            page = page.saturating_sub(1);
+        }
+
+        let total_pages = match audit_log_page(ctx, user_id.as_deref(), page).await {
+            Ok((page_embed, total_pages)) => {
+                embed = page_embed;
+                total_pages
+            }
+            Err(e) => {
+                error!("Error loading admin audit log: {}", e);
+                continue;
+            }
+        };
+        page = page.clamp(1, total_pages);
+
+        press.create_response(
+            ctx.serenity_context(),
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(embed.clone())
+                    .components(page_buttons(ctx_id, page, total_pages)),
+            ),
+        ).await?;
+    }
+
+    reply.edit(ctx, poise::CreateReply::default().embed(embed).components(Vec::new())).await?;
+    Ok(())
+}
+
+/// Post a pinned message with a Register button in this channel
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn registerbutton(
+    ctx: Context<'_>,
+    #[description = "Text shown above the button"] message: Option<String>,
+) -> Result<(), Error> {
+    let content = message
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| "New here? Press **Register** to get a Slumcoin wallet.".to_string());
+
+    let posted = ctx
+        .channel_id()
+        .send_message(&ctx.http(), serenity::CreateMessage::new()
+            .content(content)
+            .components(registration::register_buttons()))
+        .await?;
+
+    // Pinning needs Manage Messages, the button still works without it
+    if let Err(e) = posted.pin(&ctx.http()).await {
+        error!("Failed to pin register message: {}", e);
+    }
+
+    log_admin_action(ctx, "register_button", ctx.channel_id().to_string(), None, None).await;
+    ctx.send(poise::CreateReply::default()
+        .content("Register button posted.")
+        .ephemeral(true)).await?;
+    Ok(())
+}
+
+/// Register every member of this server who isn't registered yet
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn registerall(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    ctx.defer().await?;
+
+    match registration::register_members(ctx.http(), &data.database, &data.crypto, guild_id).await {
+        Ok(outcome) => {
+            log_admin_action(
+                ctx,
+                "register_all",
+                guild_id.to_string(),
+                None,
+                Some(format!("{} created, {} skipped, {} failed", outcome.created, outcome.skipped, outcome.failed)),
+            ).await;
+            ctx.say(format!(
+                "Bulk registration done. bub boils the seeds\n\
+                Created: **{}** · Already registered: **{}** · Failed: **{}**",
+                outcome.created, outcome.skipped, outcome.failed
+            )).await?;
         }
+        Err(e) => {
+            error!("Error registering guild members: {}", e);
+            ctx.say("Bulk registration failed. Make sure the bot has the Server Members intent.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show bot health and resource usage
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn botstats(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    let memory = match health::resident_memory_kb() {
+        Some(kb) => format!("{:.1} MB", kb as f64 / 1024.0),
+        None => "unavailable".to_string(),
+    };
+
+    let latency = ctx.ping().await;
+    let latency = if latency.is_zero() {
+        "not measured yet".to_string()
+    } else {
+        format!("{} ms", latency.as_millis())
+    };
+
+    let (open, idle, max) = data.database.pool_stats();
+
+    let (hits, misses) = data.counterparties.stats();
+    let hit_rate = if hits + misses > 0 {
+        format!("{:.0}% ({} / {})", hits as f64 * 100.0 / (hits + misses) as f64, hits, hits + misses)
+    } else {
+        "no lookups yet".to_string()
+    };
+
+    let mut response = format!(
+        "**Bot Stats**\n\
+        Uptime: **{}**\n\
+        Memory: **{}**\n\
+        Gateway latency: **{}**\n\
+        DB connections: **{}** in use / {} open / {} max\n\
+        Counterparty cache hit rate: **{}**\n\
+        Active auctions: **{}**\n",
+        format_duration(data.started_at.elapsed().as_secs()),
+        memory,
+        latency,
+        open as usize - idle.min(open as usize),
+        open,
+        max,
+        hit_rate,
+        data.auction_manager.active_count().await,
+    );
+
+    response.push_str("\n**Background tasks:**\n");
+    let statuses = data.task_monitor.statuses();
+    if statuses.is_empty() {
+        response.push_str("No background tasks have reported yet.\n");
+    }
+    for status in statuses {
+        response.push_str(&format!(
+            "• {} `{}` - last tick {} ago\n",
+            if status.healthy { "✅" } else { "⚠️" },
+            status.name,
+            format_duration(status.since_last_beat.as_secs())
+        ));
     }
 
+    ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
     Ok(())
 }
@@ -0,0 +1,97 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+
+/// Past auction results and the biggest spenders
+#[poise::command(slash_command, category = "User", guild_only, subcommands("auctionhistory_recent", "auctionhistory_spenders"))]
+pub async fn auctionhistory(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show the most recent auctions in this server
+#[poise::command(slash_command, rename = "recent")]
+pub async fn auctionhistory_recent(
+    ctx: Context<'_>,
+    #[description = "Number of auctions to show (default: 10, max: 25)"] limit: Option<u32>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let limit = limit.unwrap_or(10).clamp(1, 25);
+
+    let auctions = match data.database.get_auction_history(&guild_id, limit).await {
+        Ok(auctions) => auctions,
+        Err(e) => {
+            error!("Error getting auction history: {}", e);
+            ctx.say("Error retrieving auction history.").await?;
+            return Ok(());
+        }
+    };
+
+    if auctions.is_empty() {
+        ctx.say("No auctions have finished in this server yet.").await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for auction in &auctions {
+        let result = match (auction.outcome.as_str(), &auction.winner_id, auction.amount) {
+            ("sold", Some(winner_id), Some(amount)) => format!("won by <@{}> for **{} Slumcoins**", winner_id, amount),
+            ("reserve_not_met", _, Some(amount)) => format!("reserve not met (top bid {})", amount),
+            ("unpaid", Some(winner_id), _) => format!("<@{}> couldn't pay", winner_id),
+            ("cancelled", _, _) => "cancelled".to_string(),
+            _ => "no bids".to_string(),
+        };
+        description.push_str(&format!(
+            "**{}** · {} · {} bid(s) · <t:{}:R>\n",
+            auction.item, result, auction.bid_count, auction.ended_at
+        ));
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Auction History")
+        .description(description);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Show who has spent the most at auctions
+#[poise::command(slash_command, rename = "spenders")]
+pub async fn auctionhistory_spenders(
+    ctx: Context<'_>,
+    #[description = "Number of spenders to show (default: 10, max: 25)"] limit: Option<u32>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let limit = limit.unwrap_or(10).clamp(1, 25);
+
+    let spenders = match data.database.get_top_auction_spenders(&guild_id, limit).await {
+        Ok(spenders) => spenders,
+        Err(e) => {
+            error!("Error getting auction spenders: {}", e);
+            ctx.say("Error retrieving auction spenders.").await?;
+            return Ok(());
+        }
+    };
+
+    if spenders.is_empty() {
+        ctx.say("Nobody has won an auction in this server yet.").await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for (rank, (winner_id, total, wins)) in spenders.iter().enumerate() {
+        description.push_str(&format!(
+            "**{}. <@{}> : ``{}``** across {} win(s)\n",
+            rank + 1, winner_id, total, wins
+        ));
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Top Auction Spenders")
+        .description(description);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::backup as snapshots;
+use super::{is_admin, log_admin_action};
+
+// Discord rejects bot uploads above this size
+const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Snapshot the database, saving it to the backup directory and/or uploading it here
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin", ephemeral)]
+pub async fn backup(
+    ctx: Context<'_>,
+    #[description = "Also upload the backup here (always on when no backup directory is set)"] upload: Option<bool>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let backup_dir = snapshots::backup_dir();
+    let upload = backup_dir.is_none() || upload.unwrap_or(false);
+
+    ctx.defer_ephemeral().await?;
+
+    // Without a backup directory the snapshot only lives long enough to be uploaded
+    let dir = backup_dir.clone().unwrap_or_else(|| std::env::temp_dir().join(format!("backup-{}", ctx.id())));
+    let path = match snapshots::create(&data.database, &dir).await {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Error backing up database: {}", e);
+            ctx.say("Error backing up the database.").await?;
+            return Ok(());
+        }
+    };
+    let bytes = tokio::fs::metadata(&path).await.map(|metadata| metadata.len()).unwrap_or(0);
+    let filename = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+
+    let mut response = match &backup_dir {
+        Some(dir) => {
+            if let Err(e) = snapshots::prune(dir).await {
+                error!("Error pruning old backups: {}", e);
+            }
+            format!("Saved `{}` ({} KB) to the backup directory.", filename, bytes / 1024)
+        }
+        None => format!("Backed up the database ({} KB).", bytes / 1024),
+    };
+
+    let mut attachment = None;
+    if upload && bytes > MAX_ATTACHMENT_BYTES {
+        response.push_str(&format!(
+            "\nThe backup is {} MB, too big to upload. Set `BACKUP_DIR` to keep backups on the server instead.",
+            bytes / (1024 * 1024)
+        ));
+    } else if upload {
+        match tokio::fs::File::open(&path).await {
+            Ok(file) => match serenity::CreateAttachment::file(&file, filename.as_str()).await {
+                Ok(file) => attachment = Some(file),
+                Err(e) => error!("Error attaching database backup: {}", e),
+            },
+            Err(e) => error!("Error opening database backup: {}", e),
+        }
+        if attachment.is_none() {
+            response.push_str("\nError attaching the backup.");
+        }
+    }
+
+    if backup_dir.is_none() {
+        if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+            error!("Failed to remove temporary backup {}: {}", dir.display(), e);
+        }
+    }
+
+    log_admin_action(ctx, "backup", filename, None, Some(format!("{} bytes", bytes))).await;
+    response.push_str("\nTo restore, start the bot with `DATABASE_RESTORE_FROM` pointing at the file.");
+    let mut reply = poise::CreateReply::default().content(response);
+    if let Some(attachment) = attachment {
+        reply = reply.attachment(attachment);
+    }
+    ctx.send(reply).await?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::betting::{self, BETTING_POOL_ACCOUNT};
+use crate::database::{Bet, Transaction};
+use super::{database_error_message, gamble_refusal, is_admin, log_admin_action, not_frozen};
+
+const MAX_QUESTION_LENGTH: usize = 200;
+const MAX_OPTION_LENGTH: usize = 80;
+
+/// Bet on the outcome of custom questions
+#[poise::command(
+    slash_command,
+    category = "Games",
+    guild_only,
+    subcommands("bet_list", "bet_info", "bet_place", "bet_create", "bet_resolve", "bet_cancel")
+)]
+pub async fn bet(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+// Load a bet from this guild, replying if there isn't one
+async fn find_bet(ctx: Context<'_>, bet_id: i64) -> Result<Option<Bet>, Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    match ctx.data().database.get_bet(bet_id).await? {
+        Some(bet) if bet.guild_id == guild_id => Ok(Some(bet)),
+        _ => {
+            ctx.say(format!("No bet #{} in this server.", bet_id)).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// List the bets you can still get in on
+#[poise::command(slash_command, rename = "list")]
+pub async fn bet_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let bets = data.database.get_open_bets(&guild_id).await?;
+    if bets.is_empty() {
+        ctx.say("No open bets right now.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("🎲 **Open bets**\n");
+    for bet in bets.iter().take(20) {
+        let pot: i64 = data.database.get_bet_options(bet.id).await?.iter().map(|option| option.pool).sum();
+        response.push_str(&format!("• **#{}** {} · pot {} Slumcoins\n", bet.id, bet.question, pot));
+    }
+    response.push_str("See the options and odds with `/bet info`");
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Show a bet's options, pot and odds
+#[poise::command(slash_command, rename = "info")]
+pub async fn bet_info(
+    ctx: Context<'_>,
+    #[description = "Bet number"] id: i64,
+) -> Result<(), Error> {
+    let Some(bet) = find_bet(ctx, id).await? else {
+        return Ok(());
+    };
+    let options = ctx.data().database.get_bet_options(bet.id).await?;
+    ctx.send(poise::CreateReply::default().embed(betting::bet_embed(&bet, &options))).await?;
+    Ok(())
+}
+
+/// Stake coins on an option
+#[poise::command(slash_command, rename = "place", check = "not_frozen")]
+pub async fn bet_place(
+    ctx: Context<'_>,
+    #[description = "Bet number"] id: i64,
+    #[description = "Option number, as shown by /bet info"] option: i64,
+    #[description = "Coins to stake"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    let Some(bet) = find_bet(ctx, id).await? else {
+        return Ok(());
+    };
+    if bet.status != "open" || bet.closes_at.is_some_and(|closes_at| closes_at <= chrono::Utc::now().timestamp()) {
+        ctx.say(format!("Betting on #{} has closed.", bet.id)).await?;
+        return Ok(());
+    }
+
+    let options = data.database.get_bet_options(bet.id).await?;
+    let Some(picked) = options.iter().find(|candidate| candidate.position == option) else {
+        ctx.say(format!("Bet #{} has options 1 to {}.", bet.id, options.len())).await?;
+        return Ok(());
+    };
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+    if let Some(reason) = gamble_refusal(&data.database, &user_id, amount).await? {
+        ctx.say(reason).await?;
+        return Ok(());
+    }
+
+    let stake = Transaction::system(
+        &user_id,
+        BETTING_POOL_ACCOUNT,
+        amount,
+        "bet_stake",
+        Some(format!("Bet #{} on {}", bet.id, picked.label)),
+    );
+    match data.database.place_bet(bet.id, picked.id, &stake).await {
+        Ok(true) => {
+            let options = data.database.get_bet_options(bet.id).await?;
+            let pot: i64 = options.iter().map(|option| option.pool).sum();
+            let pool = options.iter().find(|option| option.id == picked.id).map(|option| option.pool).unwrap_or(amount);
+            let odds = betting::odds(pot, pool).unwrap_or(1.0);
+            ctx.say(format!(
+                "Staked **{} Slumcoins** on **{}**. Pot: {} Slumcoins · current odds x{:.2}",
+                amount, picked.label, pot, odds
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say("You've already backed another option on this bet, or betting just closed.").await?;
+        }
+        Err(e) => {
+            error!("Error placing bet: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error placing bet. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Open a betting pool on a question
+#[poise::command(slash_command, rename = "create", check = "is_admin")]
+#[allow(clippy::too_many_arguments)]
+pub async fn bet_create(
+    ctx: Context<'_>,
+    #[description = "What people are betting on"] question: String,
+    #[description = "First option"] option1: String,
+    #[description = "Second option"] option2: String,
+    #[description = "Third option"] option3: Option<String>,
+    #[description = "Fourth option"] option4: Option<String>,
+    #[description = "Fifth option"] option5: Option<String>,
+    #[description = "Sixth option"] option6: Option<String>,
+    #[description = "Minutes until betting closes (default: open until resolved)"] minutes: Option<u32>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let question = question.trim().to_string();
+
+    let options: Vec<String> = [Some(option1), Some(option2), option3, option4, option5, option6]
+        .into_iter()
+        .flatten()
+        .map(|option| option.trim().to_string())
+        .filter(|option| !option.is_empty())
+        .collect();
+
+    if question.is_empty() || question.chars().count() > MAX_QUESTION_LENGTH {
+        ctx.say(format!("Questions need 1 to {} characters.", MAX_QUESTION_LENGTH)).await?;
+        return Ok(());
+    }
+    if options.len() < 2 {
+        ctx.say("A bet needs at least two options.").await?;
+        return Ok(());
+    }
+    if options.iter().any(|option| option.chars().count() > MAX_OPTION_LENGTH) {
+        ctx.say(format!("Options can be at most {} characters.", MAX_OPTION_LENGTH)).await?;
+        return Ok(());
+    }
+    if minutes == Some(0) {
+        ctx.say("Betting needs to stay open for at least a minute.").await?;
+        return Ok(());
+    }
+
+    let closes_at = minutes.map(|minutes| chrono::Utc::now().timestamp() + i64::from(minutes) * 60);
+    let bet_id = match data.database.create_bet(&guild_id, &question, &options, &ctx.author().id.to_string(), closes_at).await {
+        Ok(bet_id) => bet_id,
+        Err(e) => {
+            error!("Error creating bet: {}", e);
+            ctx.say("Error creating the bet. Please try again.").await?;
+            return Ok(());
+        }
+    };
+
+    log_admin_action(ctx, "bet_create", format!("bet #{}", bet_id), None, Some(question)).await;
+    let (Some(bet), options) = (data.database.get_bet(bet_id).await?, data.database.get_bet_options(bet_id).await?) else {
+        return Ok(());
+    };
+    ctx.send(poise::CreateReply::default()
+        .content(format!("Bets are open! Use `/bet place id:{} option:<number> amount:<coins>`", bet_id))
+        .embed(betting::bet_embed(&bet, &options))).await?;
+    Ok(())
+}
+
+// Pay out or refund a bet and post the result
+async fn close(ctx: Context<'_>, bet: &Bet, winning_option_id: Option<i64>) -> Result<bool, Error> {
+    let data = &ctx.data();
+    let entries = data.database.get_bet_entries(bet.id).await?;
+    let staked: i64 = entries.iter().map(|entry| entry.amount).sum();
+    let payouts = betting::payouts(bet, &entries, winning_option_id);
+
+    match data.database.close_bet(bet.id, winning_option_id, staked, &payouts).await {
+        Ok(true) => Ok(true),
+        Ok(false) => {
+            ctx.say(format!("Bet #{} changed in the meantime. Please try again.", bet.id)).await?;
+            Ok(false)
+        }
+        Err(e) => {
+            error!("Error closing bet {}: {}", bet.id, e);
+            ctx.say(database_error_message(ctx, &e, "Error paying out the bet. Nothing was moved.")).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// Settle a bet, splitting the pot between everyone who backed the winning option
+#[poise::command(slash_command, rename = "resolve", check = "is_admin")]
+pub async fn bet_resolve(
+    ctx: Context<'_>,
+    #[description = "Bet number"] id: i64,
+    #[description = "Number of the option that won"] option: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let Some(bet) = find_bet(ctx, id).await? else {
+        return Ok(());
+    };
+    if bet.status != "open" {
+        ctx.say(format!("Bet #{} is already {}.", bet.id, bet.status)).await?;
+        return Ok(());
+    }
+    let options = data.database.get_bet_options(bet.id).await?;
+    let Some(winner) = options.iter().find(|candidate| candidate.position == option) else {
+        ctx.say(format!("Bet #{} has options 1 to {}.", bet.id, options.len())).await?;
+        return Ok(());
+    };
+
+    if !close(ctx, &bet, Some(winner.id)).await? {
+        return Ok(());
+    }
+    log_admin_action(ctx, "bet_resolve", format!("bet #{}", bet.id), None, Some(format!("winner: {}", winner.label))).await;
+
+    let pot: i64 = options.iter().map(|option| option.pool).sum();
+    let response = if winner.pool > 0 {
+        format!(
+            "🏆 **{}** wins bet #{}! {} backer(s) split the pot of **{} Slumcoins** (x{:.2}).",
+            winner.label, bet.id, winner.backers, pot, betting::odds(pot, winner.pool).unwrap_or(1.0)
+        )
+    } else {
+        format!("🏆 **{}** wins bet #{}, but nobody backed it. All {} Slumcoins were refunded.", winner.label, bet.id, pot)
+    };
+    let (Some(bet), options) = (data.database.get_bet(bet.id).await?, data.database.get_bet_options(bet.id).await?) else {
+        return Ok(());
+    };
+    ctx.send(poise::CreateReply::default().content(response).embed(betting::bet_embed(&bet, &options))).await?;
+    Ok(())
+}
+
+/// Call off a bet and refund every stake
+#[poise::command(slash_command, rename = "cancel", check = "is_admin")]
+pub async fn bet_cancel(
+    ctx: Context<'_>,
+    #[description = "Bet number"] id: i64,
+    #[description = "Reason for the audit log"] reason: Option<String>,
+) -> Result<(), Error> {
+    let Some(bet) = find_bet(ctx, id).await? else {
+        return Ok(());
+    };
+    if bet.status != "open" {
+        ctx.say(format!("Bet #{} is already {}.", bet.id, bet.status)).await?;
+        return Ok(());
+    }
+
+    if !close(ctx, &bet, None).await? {
+        return Ok(());
+    }
+    log_admin_action(ctx, "bet_cancel", format!("bet #{}", bet.id), None, reason).await;
+    ctx.say(format!("Bet #{} is cancelled and every stake was refunded.", bet.id)).await?;
+    Ok(())
+}
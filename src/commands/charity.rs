@@ -0,0 +1,227 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::charity::{self, CHARITY_POOL_ACCOUNT};
+use crate::database::{CharityMatch, DatabaseError, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{database_error_message, is_admin, log_admin_action, not_frozen, say_private};
+
+const LEADERBOARD_SIZE: i64 = 10;
+const MAX_MATCH_PERCENT: i64 = 1000;
+// Matches run and get scheduled at most a month out
+const MAX_MATCH_HOURS: i64 = 720;
+
+// "1:1" for 100%, "1:0.5" for 50% and so on
+fn match_ratio(percent: i64) -> String {
+    format!("1:{}", percent as f64 / 100.0)
+}
+
+fn describe_match(charity_match: &CharityMatch) -> String {
+    let cap = match charity_match.cap {
+        Some(cap) => format!(", {} of {} coins matched so far", charity_match.matched, cap),
+        None => format!(", {} coins matched so far", charity_match.matched),
+    };
+    format!(
+        "the treasury matches donations {} from <t:{}:f> to <t:{}:f>{}",
+        match_ratio(charity_match.percent), charity_match.starts_at, charity_match.ends_at, cap
+    )
+}
+
+/// Give coins to the server's charity pool
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
+pub async fn donate(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to donate"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let now = Utc::now().timestamp();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&user_id).await {
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    // The treasury can only match what it holds
+    let running = data.database
+        .get_charity_matches(&guild_id, now)
+        .await?
+        .into_iter()
+        .find(|charity_match| charity_match.starts_at <= now);
+    let treasury = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    let contribution = running.as_ref().and_then(|charity_match| {
+        let amount = charity::match_amount(amount, charity_match).min(treasury);
+        (amount > 0).then(|| (charity_match.id, Transaction::system(
+            TREASURY_ACCOUNT,
+            CHARITY_POOL_ACCOUNT,
+            amount,
+            "charity_match",
+            Some(format!("Treasury match for {}'s donation", user_id)),
+        )))
+    });
+
+    let donation = Transaction::system(&user_id, CHARITY_POOL_ACCOUNT, amount, "charity_donation", None);
+    let first_distribution_at = charity::next_distribution_at(&data.database, &guild_id, now).await?;
+    let matching = contribution.as_ref().map(|(match_id, contribution)| (*match_id, contribution));
+    match data.database.donate_to_charity(&guild_id, &donation, matching, first_distribution_at).await {
+        Ok(matched) => {
+            let mut response = format!("💝 <@{}> donated **{} Slumcoins** to charity", user_id, amount);
+            if matched > 0 {
+                response.push_str(&format!(" and the treasury matched **{}**", matched));
+            }
+            if let Some((balance, next_at)) = data.database.get_charity_pool(&guild_id).await? {
+                response.push_str(&format!("!\nThe pool holds {} coins and goes to a random active member <t:{}:R>.", balance, next_at));
+            }
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error recording donation: {}", e);
+            }
+            ctx.say(database_error_message(ctx, &e, "Donation failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The charity pool, its donors and treasury matching
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("charity_info", "charity_leaderboard", "charity_match", "charity_stopmatch")
+)]
+pub async fn charity(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show the pool, the next distribution and any matching
+#[poise::command(slash_command, rename = "info")]
+pub async fn charity_info(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let now = Utc::now().timestamp();
+
+    let mut response = String::from("💝 **Charity pool**\n");
+    match data.database.get_charity_pool(&guild_id).await? {
+        Some((balance, next_at)) => response.push_str(&format!(
+            "Holds **{} Slumcoins**, paid to a random active member <t:{}:R>.\n", balance, next_at
+        )),
+        None => response.push_str("Nobody has donated yet. Start it off with `/donate`.\n"),
+    }
+    for charity_match in data.database.get_charity_matches(&guild_id, now).await? {
+        let when = if charity_match.starts_at <= now { "Now" } else { "Coming up" };
+        response.push_str(&format!("{}: {}\n", when, describe_match(&charity_match)));
+    }
+    if let Some((recipient, amount, at)) = data.database.get_last_charity_distribution(&guild_id).await? {
+        response.push_str(&format!("Last distribution: {} coins to <@{}> <t:{}:R>", amount, recipient, at));
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Rank this server's donors by what they've given
+#[poise::command(slash_command, rename = "leaderboard")]
+pub async fn charity_leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let donors = ctx.data().database.get_charity_donors(&guild_id, LEADERBOARD_SIZE).await?;
+    if donors.is_empty() {
+        ctx.say("No donations yet. Be the first with `/donate`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("💝 **Top donors**\n");
+    for (rank, (discord_id, donated, matched)) in donors.iter().enumerate() {
+        response.push_str(&format!("**{}.** <@{}> · {} Slumcoins", rank + 1, discord_id, donated));
+        if *matched > 0 {
+            response.push_str(&format!(" (+{} matched)", matched));
+        }
+        response.push('\n');
+    }
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Have the treasury match donations for a while
+#[poise::command(slash_command, rename = "match", check = "is_admin")]
+pub async fn charity_match(
+    ctx: Context<'_>,
+    #[description = "Coins the treasury adds per 100 donated (100 = 1:1)"] percent: i64,
+    #[description = "How many hours the match runs"] hours: i64,
+    #[description = "Most the treasury adds in total (default: no cap)"] cap: Option<i64>,
+    #[description = "Hours from now until it starts (default: now)"] starts_in_hours: Option<i64>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let starts_in_hours = starts_in_hours.unwrap_or(0);
+
+    if !(1..=MAX_MATCH_PERCENT).contains(&percent) {
+        say_private(ctx, format!("Matching goes from 1% to {}%.", MAX_MATCH_PERCENT)).await?;
+        return Ok(());
+    }
+    if !(1..=MAX_MATCH_HOURS).contains(&hours) || !(0..=MAX_MATCH_HOURS).contains(&starts_in_hours) {
+        say_private(ctx, format!("Matches run for 1 to {} hours and start within {} hours.", MAX_MATCH_HOURS, MAX_MATCH_HOURS)).await?;
+        return Ok(());
+    }
+    if cap.is_some_and(|cap| cap <= 0) {
+        say_private(ctx, "The cap must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let starts_at = Utc::now().timestamp() + starts_in_hours * 3600;
+    let mut charity_match = CharityMatch {
+        id: 0,
+        guild_id: guild_id.clone(),
+        percent,
+        cap,
+        matched: 0,
+        starts_at,
+        ends_at: starts_at + hours * 3600,
+        created_by: ctx.author().id.to_string(),
+    };
+    match ctx.data().database.create_charity_match(&charity_match).await? {
+        Some(match_id) => {
+            charity_match.id = match_id;
+            log_admin_action(ctx, "charity_match", format!("charity match #{}", match_id), cap, Some(format!("{}% for {} hours", percent, hours))).await;
+            ctx.say(format!("💝 Donation matching! For every donation, {}.", describe_match(&charity_match))).await?;
+        }
+        None => {
+            say_private(ctx, "That overlaps another match. Stop it first with `/charity stopmatch`.").await?;
+        }
+    }
+    Ok(())
+}
+
+/// End the running match, or call off the next one
+#[poise::command(slash_command, rename = "stopmatch", check = "is_admin")]
+pub async fn charity_stopmatch(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let now = Utc::now().timestamp();
+
+    let Some(charity_match) = data.database.get_charity_matches(&guild_id, now).await?.into_iter().next() else {
+        say_private(ctx, "No match is running or coming up.").await?;
+        return Ok(());
+    };
+
+    if data.database.end_charity_match(charity_match.id, now).await? {
+        log_admin_action(ctx, "charity_stopmatch", format!("charity match #{}", charity_match.id), Some(charity_match.matched), None).await;
+        ctx.say(format!("💝 Donation matching stopped. The treasury matched {} coins.", charity_match.matched)).await?;
+    } else {
+        say_private(ctx, "That match already ended.").await?;
+    }
+    Ok(())
+}
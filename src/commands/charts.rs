@@ -0,0 +1,94 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::charts;
+use super::{is_admin, say_private};
+
+const DAY_SECONDS: i64 = 86400;
+const MAX_DAYS: u32 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum ChartKind {
+    #[name = "balance"]
+    Balance,
+    #[name = "net worth"]
+    NetWorth,
+    #[name = "supply"]
+    Supply,
+}
+
+/// Chart a balance, net worth or the coin supply over time
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn chart(
+    ctx: Context<'_>,
+    #[description = "User to chart (default: you)"] user: Option<serenity::User>,
+    #[description = "Days of history (default: 30)"] days: Option<u32>,
+    #[description = "What to chart (default: balance)"] show: Option<ChartKind>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let target = user.unwrap_or_else(|| ctx.author().clone());
+    let target_id = target.id.to_string();
+    let days = days.unwrap_or(30).clamp(2, MAX_DAYS);
+    let show = show.unwrap_or(ChartKind::Balance);
+    let since = charts::day_of(Utc::now().timestamp()) - i64::from(days - 1) * DAY_SECONDS;
+
+    // Other people's balances follow the same rule as /balance @user
+    if show != ChartKind::Supply && target.id != ctx.author().id {
+        let preferences = data.database.get_user_preferences(&target_id).await?;
+        if !preferences.public_balance && !is_admin(ctx).await? {
+            say_private(ctx, format!("{} keeps their balance private.", target.name)).await?;
+            return Ok(());
+        }
+    }
+
+    let (title, points): (String, Vec<(i64, i64)>) = match show {
+        ChartKind::Supply => ("Circulating supply".to_string(), data.database.get_supply_history(since).await?),
+        kind => {
+            let history = data.database.get_balance_history(&target_id, since).await?;
+            let points = history
+                .iter()
+                .map(|snapshot| (snapshot.day, if kind == ChartKind::NetWorth { snapshot.net_worth } else { snapshot.balance }))
+                .collect();
+            let label = if kind == ChartKind::NetWorth { "net worth" } else { "balance" };
+            (format!("{}'s {}", target.name, label), points)
+        }
+    };
+
+    if points.len() < 2 {
+        say_private(ctx, "Not enough history to chart yet. Snapshots are taken once a day.").await?;
+        return Ok(());
+    }
+
+    let values: Vec<i64> = points.iter().map(|(_, value)| *value).collect();
+    let png = match charts::line_chart(&values) {
+        Ok(png) => png,
+        Err(e) => {
+            error!("Error rendering chart: {}", e);
+            say_private(ctx, "Error drawing the chart.").await?;
+            return Ok(());
+        }
+    };
+
+    let (first_day, first) = points[0];
+    let (last_day, last) = points[points.len() - 1];
+    let embed = serenity::CreateEmbed::new()
+        .title(title)
+        .image("attachment://chart.png")
+        .field("From", format!("<t:{}:D> · {} coins", first_day, first), true)
+        .field("To", format!("<t:{}:D> · {} coins", last_day, last), true)
+        .field(
+            "Range",
+            format!("{} – {} coins", values.iter().min().unwrap_or(&0), values.iter().max().unwrap_or(&0)),
+            true,
+        )
+        .color(0x2980b9);
+
+    let private = data.database.get_user_preferences(&ctx.author().id.to_string()).await.map(|preferences| preferences.private_replies).unwrap_or(true);
+    ctx.send(poise::CreateReply::default()
+        .embed(embed)
+        .attachment(serenity::CreateAttachment::bytes(png, "chart.png"))
+        .ephemeral(private)).await?;
+    Ok(())
+}
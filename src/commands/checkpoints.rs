@@ -0,0 +1,94 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use super::{is_admin, log_admin_action};
+
+// Maximum number of checkpoint problems listed
+const PROBLEM_DISPLAY_LIMIT: usize = 10;
+
+/// Signed ledger checkpoints
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("checkpoint_verify", "checkpoint_create")
+)]
+pub async fn checkpoint(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Check every signed checkpoint against the ledger as it is now
+#[poise::command(slash_command, rename = "verify")]
+pub async fn checkpoint_verify(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    ctx.defer().await?;
+
+    let report = match crate::checkpoint::verify(&data.database, &data.crypto).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Error verifying checkpoints: {}", e);
+            ctx.say("Error verifying checkpoints.").await?;
+            return Ok(());
+        }
+    };
+
+    let Some(latest) = &report.latest else {
+        ctx.say("No checkpoints yet. The first one is signed within the hour, or use `/checkpoint create`.").await?;
+        return Ok(());
+    };
+
+    let mut response = format!(
+        "**Checkpoint Verification**\n\
+        Checkpoints checked: **{}**\n\
+        Problems: **{}**\n\
+        Latest: `#{}` <t:{}:R> · {} transactions · supply {} · head `{}`\n",
+        report.checkpoints_checked,
+        report.problems.len(),
+        latest.id,
+        latest.created_at,
+        latest.transaction_count,
+        latest.total_supply,
+        latest.last_hash
+    );
+
+    if report.problems.is_empty() {
+        response.push_str("\nLedger matches every checkpoint.");
+    } else {
+        response.push('\n');
+        for problem in report.problems.iter().take(PROBLEM_DISPLAY_LIMIT) {
+            response.push_str(&format!("• {}\n", problem));
+        }
+        if report.problems.len() > PROBLEM_DISPLAY_LIMIT {
+            response.push_str(&format!("...and {} more\n", report.problems.len() - PROBLEM_DISPLAY_LIMIT));
+        }
+    }
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Sign a checkpoint of the ledger right now
+#[poise::command(slash_command, rename = "create")]
+pub async fn checkpoint_create(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    match crate::checkpoint::create(&data.database, &data.crypto).await {
+        Ok(Some(checkpoint)) => {
+            log_admin_action(ctx, "checkpoint_create", format!("checkpoint #{}", checkpoint.id), None, None).await;
+            ctx.say(format!(
+                "Signed checkpoint `#{}` at {} transactions · head `{}`",
+                checkpoint.id, checkpoint.transaction_count, checkpoint.last_hash
+            )).await?;
+        }
+        Ok(None) => {
+            ctx.say("Nothing has changed since the last checkpoint.").await?;
+        }
+        Err(e) => {
+            error!("Error creating checkpoint: {}", e);
+            ctx.say("Error creating checkpoint.").await?;
+        }
+    }
+
+    Ok(())
+}
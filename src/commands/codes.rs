@@ -0,0 +1,235 @@
+use chrono::Utc;
+use rand::Rng;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::{GiftCode, Transaction, SYSTEM_ACCOUNT};
+use super::{is_admin, log_admin_action};
+
+// No 0/O or 1/I so codes survive being read aloud or retyped
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_GROUPS: usize = 3;
+const CODE_GROUP_LENGTH: usize = 4;
+// 32^12 possible codes make a collision very unlikely, but retry a few times just in case
+const MAX_CODE_ATTEMPTS: usize = 5;
+
+/// Random code such as `K7QM-2XHD-94PB`
+fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_GROUPS)
+        .map(|_| {
+            (0..CODE_GROUP_LENGTH)
+                .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Canonical form of a code as typed by a user, e.g. `k7qm 2xhd94pb` becomes `K7QM-2XHD-94PB`
+fn normalize_code(input: &str) -> String {
+    let chars: Vec<char> = input
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    chars
+        .chunks(CODE_GROUP_LENGTH)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Codes are secret until redeemed, so everything about them is only shown to the caller
+async fn say_ephemeral(ctx: Context<'_>, content: impl Into<String>) -> Result<(), Error> {
+    ctx.send(poise::CreateReply::default().content(content).ephemeral(true)).await?;
+    Ok(())
+}
+
+/// Create and list redeemable gift codes
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("code_create", "code_list")
+)]
+pub async fn code(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Create a code that credits coins to each user who redeems it
+#[poise::command(slash_command, rename = "create")]
+pub async fn code_create(
+    ctx: Context<'_>,
+    #[description = "Coins each redemption is worth"] amount: i64,
+    #[description = "How many users can redeem it (default: 1)"] uses: Option<u32>,
+    #[description = "Hours until it expires (default: never)"] expiry: Option<u32>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if amount <= 0 {
+        say_ephemeral(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+    let max_uses = uses.unwrap_or(1);
+    if max_uses == 0 {
+        say_ephemeral(ctx, "A code needs at least one use.").await?;
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp();
+    let mut gift_code = GiftCode {
+        code: String::new(),
+        guild_id,
+        amount,
+        max_uses: i64::from(max_uses),
+        uses: 0,
+        created_by: ctx.author().id.to_string(),
+        created_at: now,
+        expires_at: expiry.map(|hours| now + i64::from(hours) * 3600),
+    };
+
+    for _ in 0..MAX_CODE_ATTEMPTS {
+        gift_code.code = generate_code();
+        match data.database.create_gift_code(&gift_code).await {
+            Ok(true) => {
+                log_admin_action(ctx, "code_create", format!("code {}", gift_code.code), Some(amount), Some(format!("{} use(s)", max_uses))).await;
+                let mut response = format!(
+                    "🎁 Created code `{}` worth **{} Slumcoins**, redeemable {} time(s) with `/redeem`",
+                    gift_code.code, amount, max_uses
+                );
+                if let Some(expires_at) = gift_code.expires_at {
+                    response.push_str(&format!(". Expires <t:{}:R>", expires_at));
+                }
+                say_ephemeral(ctx, response).await?;
+                return Ok(());
+            }
+            Ok(false) => continue,
+            Err(e) => {
+                error!("Error creating gift code: {}", e);
+                say_ephemeral(ctx, "Error creating code. Please try again.").await?;
+                return Ok(());
+            }
+        }
+    }
+
+    error!("Gave up generating a unique gift code after {} attempts", MAX_CODE_ATTEMPTS);
+    say_ephemeral(ctx, "Error creating code. Please try again.").await?;
+    Ok(())
+}
+
+/// Show codes in this server that can still be redeemed
+#[poise::command(slash_command, rename = "list")]
+pub async fn code_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let codes = match data.database.get_active_gift_codes(&guild_id, Utc::now().timestamp()).await {
+        Ok(codes) => codes,
+        Err(e) => {
+            error!("Error loading gift codes: {}", e);
+            say_ephemeral(ctx, "Error loading codes.").await?;
+            return Ok(());
+        }
+    };
+
+    if codes.is_empty() {
+        say_ephemeral(ctx, "There are no active codes. Create one with `/code create`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("**Active Codes**\n");
+    for code in codes.iter().take(20) {
+        response.push_str(&format!(
+            "• `{}` - **{}** coins · {}/{} used · by <@{}>",
+            code.code, code.amount, code.uses, code.max_uses, code.created_by
+        ));
+        if let Some(expires_at) = code.expires_at {
+            response.push_str(&format!(" · expires <t:{}:R>", expires_at));
+        }
+        response.push('\n');
+    }
+    if codes.len() > 20 {
+        response.push_str(&format!("*{} more not shown*", codes.len() - 20));
+    }
+
+    say_ephemeral(ctx, response).await?;
+    Ok(())
+}
+
+/// Redeem a gift code for Slumcoins
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn redeem(
+    ctx: Context<'_>,
+    #[description = "The code you were given"] code: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            say_ephemeral(ctx, "You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            say_ephemeral(ctx, "Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let code = normalize_code(&code);
+    let gift_code = match data.database.get_gift_code(&code).await {
+        Ok(Some(gift_code)) if gift_code.guild_id == guild_id => gift_code,
+        Ok(_) => {
+            say_ephemeral(ctx, "That code doesn't exist.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Error loading gift code: {}", e);
+            say_ephemeral(ctx, "Database error occurred.").await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now().timestamp();
+    if gift_code.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        say_ephemeral(ctx, "That code has expired.").await?;
+        return Ok(());
+    }
+    if gift_code.uses >= gift_code.max_uses {
+        say_ephemeral(ctx, "That code has already been used up.").await?;
+        return Ok(());
+    }
+    if data.database.has_redeemed_code(&code, &user_id).await? {
+        say_ephemeral(ctx, "You've already redeemed that code.").await?;
+        return Ok(());
+    }
+
+    let credit = Transaction::system(
+        SYSTEM_ACCOUNT,
+        &user_id,
+        gift_code.amount,
+        "redeem",
+        Some(format!("Redeemed code {}", code)),
+    );
+
+    match data.database.redeem_gift_code(&code, now, &credit).await {
+        Ok(true) => {
+            say_ephemeral(ctx, format!("🎁 Redeemed! **{} Slumcoins** have been added to your balance.", gift_code.amount)).await?;
+        }
+        Ok(false) => {
+            say_ephemeral(ctx, "That code can't be redeemed anymore.").await?;
+        }
+        Err(e) => {
+            error!("Error redeeming gift code: {}", e);
+            say_ephemeral(ctx, "Redeeming failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
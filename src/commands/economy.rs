@@ -0,0 +1,335 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+use chrono::Utc;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::{Context, Error, config, database::Transaction};
+use crate::database::{ShopItem, SYSTEM_ACCOUNT};
+use crate::ledger::{self, TREASURY_ACCOUNT};
+use crate::lottery::{self, LOTTERY_POT_ACCOUNT};
+use super::{cooldown, not_frozen};
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+// How long the wheel spins before the prize is revealed
+const SPIN_REVEAL_SECONDS: u64 = 2;
+
+// Wheel segments, each weighted by `spin.weight_<key>`
+const SPIN_SEGMENTS: [(&str, &str); 5] = [
+    ("coins", "💰 Coins"),
+    ("big_coins", "💎 Big win"),
+    ("nothing", "💨 Nothing"),
+    ("item", "🎁 Shop item"),
+    ("ticket", "🎟️ Lottery ticket"),
+];
+
+/// Claim a few free Slumcoins from the treasury
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn faucet(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = match ctx.guild_id() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+
+    if !config::get_bool(&data.database, &guild_id, "faucet.enabled").await? {
+        ctx.say("The faucet is turned off in this server.").await?;
+        return Ok(());
+    }
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let amount = config::get_i64(&data.database, &guild_id, "faucet.amount").await?;
+    let cooldown_hours = config::get_i64(&data.database, &guild_id, "faucet.user_cooldown_hours").await?;
+    let guild_hourly_limit = config::get_i64(&data.database, &guild_id, "faucet.guild_hourly_limit").await?;
+    let treasury_floor = config::get_i64(&data.database, &guild_id, "faucet.treasury_floor").await?;
+    let now = Utc::now().timestamp();
+
+    // Per-user rate limit
+    if let Some(last_claim) = data.database.get_last_faucet_claim(&user_id).await? {
+        let next_claim = last_claim + cooldown_hours * 3600;
+        if now < next_claim {
+            ctx.say(format!("The faucet is dry for you. Try again <t:{}:R>.", next_claim)).await?;
+            return Ok(());
+        }
+    }
+
+    // Per-guild rate limit
+    let recent_claims = data.database.count_guild_faucet_claims_since(&guild_id, now - 3600).await?;
+    if recent_claims >= guild_hourly_limit {
+        ctx.say("The faucet has been drained for this hour. Try again later.").await?;
+        return Ok(());
+    }
+
+    // Never take the treasury below its floor
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if amount <= 0 || treasury_balance - amount < treasury_floor {
+        ctx.say("The treasury is running low, so the faucet is closed for now.").await?;
+        return Ok(());
+    }
+
+    let transaction = Transaction::system(
+        TREASURY_ACCOUNT,
+        &user_id,
+        amount,
+        "faucet",
+        Some("Faucet claim".to_string()),
+    );
+
+    match data.database.apply_transaction(&transaction).await {
+        Ok(()) => {
+            if let Err(e) = data.database.record_faucet_claim(&user_id, &guild_id, amount).await {
+                error!("Failed to record faucet claim: {}", e);
+            }
+            ctx.say(format!("drip drip. You got **{} Slumcoins** from the faucet", amount)).await?;
+        }
+        Err(e) => {
+            error!("Error applying faucet transaction: {}", e);
+            ctx.say("Faucet claim failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Claim your daily reward (streaks earn a bonus)
+#[poise::command(slash_command, category = "User", guild_only, check = "cooldown")]
+pub async fn daily(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let now = Utc::now().timestamp();
+    let previous_claim = data.database.get_claim(&user_id).await?;
+
+    // A streak continues if the last claim was between 24 and 48 hours ago
+    let streak = match previous_claim {
+        Some((last_claim, _)) if now - last_claim < DAY_SECONDS => {
+            ctx.say(format!("Already claimed. Come back <t:{}:R>.", last_claim + DAY_SECONDS)).await?;
+            return Ok(());
+        }
+        Some((last_claim, streak)) if now - last_claim < 2 * DAY_SECONDS => streak + 1,
+        _ => 1,
+    };
+
+    let base_amount = config::get_i64(&data.database, &guild_id, "daily.base_amount").await?;
+    let streak_bonus = config::get_i64(&data.database, &guild_id, "daily.streak_bonus").await?;
+    let max_streak_bonus = config::get_i64(&data.database, &guild_id, "daily.max_streak_bonus").await?;
+    let bonus = (streak_bonus * (streak - 1)).min(max_streak_bonus);
+    let amount = base_amount + bonus;
+
+    let transaction = Transaction::system(
+        SYSTEM_ACCOUNT,
+        &user_id,
+        amount,
+        "daily",
+        Some(format!("Daily reward (day {} streak)", streak)),
+    );
+
+    match data.database.apply_transaction(&transaction).await {
+        Ok(()) => {
+            if let Err(e) = data.database.update_claim(&user_id, now, streak).await {
+                error!("Failed to update daily claim: {}", e);
+            }
+
+            let mut response = format!("Daily claimed: **{} Slumcoins**", amount);
+            if bonus > 0 {
+                response.push_str(&format!("\n🔥 {} day streak (+{} bonus)", streak, bonus));
+            }
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            error!("Error applying daily transaction: {}", e);
+            ctx.say("Daily claim failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show coin supply, transfer volume and wealth distribution
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn economy(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    let totals = match data.database.get_economy_totals().await {
+        Ok(totals) => totals,
+        Err(e) => {
+            error!("Error getting economy totals: {}", e);
+            ctx.say("Error retrieving economy statistics.").await?;
+            return Ok(());
+        }
+    };
+
+    let users = data.database.count_users().await?;
+    let treasury = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    let (transfers, volume) = data.database
+        .get_transfer_volume_since(Utc::now().timestamp() - 7 * DAY_SECONDS)
+        .await?;
+    let gini = ledger::gini(&data.database.get_user_balances().await?);
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Slumcoin Economy")
+        .field("In circulation", format!("{} Slumcoins", totals.circulating), true)
+        .field("Treasury", format!("{} Slumcoins", treasury), true)
+        .field("Registered users", users.to_string(), true)
+        .field("Total minted", format!("{} Slumcoins", totals.minted), true)
+        .field("Total burned", format!("{} Slumcoins", totals.burned), true)
+        .field("Net supply", format!("{} Slumcoins", totals.minted - totals.burned), true)
+        .field("Transfers (7 days)", format!("{} worth {} Slumcoins", transfers, volume), true)
+        .field("Gini coefficient", format!("{:.3}", gini), true)
+        .footer(serenity::CreateEmbedFooter::new("Gini: 0 = perfectly equal, 1 = one user holds everything"));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+// Pay out the segment the wheel landed on, returning what to tell the user
+async fn pay_spin_prize(ctx: Context<'_>, landed: &str, items: &[ShopItem]) -> Result<String, Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let item = items.choose(&mut rand::thread_rng());
+    let coins_segment = match landed {
+        "nothing" => return Ok("Nothing this time. Better luck tomorrow!".to_string()),
+        "item" => match item {
+            Some(item) if data.database.award_shop_item(item, &user_id).await? => {
+                return Ok(format!("You won a **{}**! Check `/inventory`.", item.name));
+            }
+            // Sold out since the shop was loaded, so pay the coins prize instead
+            _ => "coins",
+        },
+        "ticket" => {
+            let round = lottery::current_round(&data.database, &guild_id).await?;
+            let funding = Transaction::system(
+                SYSTEM_ACCOUNT,
+                LOTTERY_POT_ACCOUNT,
+                round.ticket_price,
+                "spin",
+                Some(format!("Free ticket for {} from the wheel of fortune", user_id)),
+            );
+            data.database.grant_lottery_ticket(round.id, &user_id, &funding).await?;
+            return Ok("You won a free ticket in the current lottery round! 🎟️".to_string());
+        }
+        segment => segment,
+    };
+
+    let amount = config::get_i64(&data.database, &guild_id, &format!("spin.{}_prize", coins_segment)).await?;
+    let prize = Transaction::system(SYSTEM_ACCOUNT, &user_id, amount, "spin", Some("Wheel of fortune prize".to_string()));
+    data.database.apply_transaction(&prize).await?;
+    Ok(format!("You won **{} Slumcoins**!", amount))
+}
+
+fn wheel_embed(segments: &[(&str, &str, i64)], landed: Option<&str>, status: &str) -> serenity::CreateEmbed {
+    let total: i64 = segments.iter().map(|(_, _, weight)| weight).sum();
+    let lines: Vec<String> = segments
+        .iter()
+        .filter(|(_, _, weight)| *weight > 0)
+        .map(|(key, label, weight)| {
+            let marker = if landed == Some(*key) { "▶ " } else { "" };
+            format!("{}{} · {:.0}%", marker, label, *weight as f64 * 100.0 / total as f64)
+        })
+        .collect();
+
+    serenity::CreateEmbed::new()
+        .title("🎡 Wheel of Fortune")
+        .field("Segments", lines.join("\n"), false)
+        .description(status)
+        .color(0xe91e63)
+}
+
+/// Spin the wheel of fortune once a day for a prize
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
+pub async fn spin(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    // Role items need granting through /buy, so the wheel only hands out plain items in stock
+    let items: Vec<_> = data.database.get_shop_items(&guild_id).await?
+        .into_iter()
+        .filter(|item| item.role_id.is_none() && item.stock != Some(0))
+        .collect();
+
+    let mut segments = Vec::new();
+    for (key, label) in SPIN_SEGMENTS {
+        let weight = config::get_i64(&data.database, &guild_id, &format!("spin.weight_{}", key)).await?.max(0);
+        let weight = if key == "item" && items.is_empty() { 0 } else { weight };
+        segments.push((key, label, weight));
+    }
+    let total: i64 = segments.iter().map(|(_, _, weight)| weight).sum();
+    if total <= 0 {
+        ctx.say("The wheel is turned off in this server.").await?;
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp();
+    if !data.database.claim_spin(&user_id, now, DAY_SECONDS).await? {
+        let next = data.database.get_last_spin(&user_id).await?.unwrap_or(now) + DAY_SECONDS;
+        ctx.say(format!("Already spun today. Come back <t:{}:R>.", next)).await?;
+        return Ok(());
+    }
+
+    let mut roll = rand::thread_rng().gen_range(0..total);
+    let landed = segments
+        .iter()
+        .find(|(_, _, weight)| {
+            if roll < *weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })
+        .map(|(key, _, _)| *key)
+        .unwrap_or("nothing");
+
+    let reply = ctx.send(poise::CreateReply::default().embed(wheel_embed(&segments, None, "Spinning..."))).await?;
+    tokio::time::sleep(std::time::Duration::from_secs(SPIN_REVEAL_SECONDS)).await;
+
+    let status = pay_spin_prize(ctx, landed, &items).await.unwrap_or_else(|e| {
+        error!("Error paying spin prize to {}: {}", user_id, e);
+        "You won, but paying the prize failed. Ask an admin to check the ledger.".to_string()
+    });
+    reply.edit(ctx, poise::CreateReply::default().embed(wheel_embed(&segments, Some(landed), &status))).await?;
+    Ok(())
+}
@@ -0,0 +1,280 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, config, database::{Escrow, Transaction}};
+use crate::escrow::{self, ESCROW_ACCOUNT, STATUS_DISPUTED, STATUS_OPEN, STATUS_REFUNDED, STATUS_RELEASED};
+use super::{is_admin, log_admin_action, not_frozen, database_error_message};
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum EscrowRuling {
+    #[name = "seller"]
+    Seller,
+    #[name = "buyer"]
+    Buyer,
+}
+
+// Load an escrow the caller is a party to, replying if it doesn't exist or isn't theirs
+async fn find_own_escrow(ctx: Context<'_>, escrow_id: i64) -> Result<Option<Escrow>, Error> {
+    let user_id = ctx.author().id.to_string();
+
+    match ctx.data().database.get_escrow(escrow_id).await? {
+        Some(escrow) if escrow.buyer == user_id || escrow.seller == user_id => Ok(Some(escrow)),
+        _ => {
+            ctx.say(format!("You don't have an escrow `#{}`.", escrow_id)).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Lock coins for a deal until it's done
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("escrow_create", "escrow_list", "escrow_release", "escrow_dispute", "escrow_disputes", "escrow_resolve")
+)]
+pub async fn escrow(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Lock up coins for someone until you release them
+#[poise::command(slash_command, rename = "create", check = "not_frozen")]
+pub async fn escrow_create(
+    ctx: Context<'_>,
+    #[description = "User you're paying once the deal is done"] user: serenity::User,
+    #[description = "Amount of coins to lock up"] amount: i64,
+    #[description = "What the deal is for"] description: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let buyer_id = ctx.author().id.to_string();
+    let seller_id = user.id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let description = description.trim().to_string();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if buyer_id == seller_id || user.bot {
+        ctx.say("Escrows need another registered user on the other side.").await?;
+        return Ok(());
+    }
+
+    for (discord_id, who) in [(&buyer_id, "You're"), (&seller_id, "They're")] {
+        match data.database.get_user(discord_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                ctx.say(format!("{} not registered! Use `/register` first.", who)).await?;
+                return Ok(());
+            }
+            Err(e) => {
+                error!("Database error: {}", e);
+                ctx.say("Database error occurred.").await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let balance = data.database.get_balance(&buyer_id).await?;
+    if balance < amount {
+        ctx.say(format!("UR BROKE BUB! You have {} Slumcoins", balance)).await?;
+        return Ok(());
+    }
+
+    let expiry_hours = config::get_i64(&data.database, &guild_id, "escrow.expiry_hours").await?.max(1);
+    let expires_at = Utc::now().timestamp() + expiry_hours * 3600;
+    let funding = Transaction::system(
+        &buyer_id,
+        ESCROW_ACCOUNT,
+        amount,
+        "escrow_lock",
+        Some(format!("Escrow with {}: {}", seller_id, description)),
+    );
+
+    match data.database.create_escrow(&guild_id, &seller_id, &description, expires_at, &funding).await {
+        Ok(escrow_id) => {
+            ctx.say(format!(
+                "🔒 Escrow `#{}` opened: **{} Slumcoins** from <@{}> to <@{}> for *{}*\n\
+                The buyer releases the funds with `/escrow release`, or the seller can release them back. \
+                Either side can `/escrow dispute`. Refunded automatically <t:{}:R> if nobody acts.",
+                escrow_id, amount, buyer_id, seller_id, description, expires_at
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error creating escrow: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error creating escrow. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show your open escrows
+#[poise::command(slash_command, rename = "list")]
+pub async fn escrow_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let escrows = data.database.get_active_escrows(&user_id).await?;
+    if escrows.is_empty() {
+        ctx.say("You have no open escrows.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Your Escrows**\n".to_string();
+    for escrow in &escrows {
+        response.push_str(&format!(
+            "`#{}` **{} Slumcoins** <@{}> → <@{}> · *{}* · {}",
+            escrow.id, escrow.amount, escrow.buyer, escrow.seller, escrow.description, escrow.status
+        ));
+        if escrow.status == STATUS_OPEN {
+            response.push_str(&format!(" · expires <t:{}:R>", escrow.expires_at));
+        }
+        response.push('\n');
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Pay out an escrow once the deal is done
+#[poise::command(slash_command, rename = "release")]
+pub async fn escrow_release(
+    ctx: Context<'_>,
+    #[description = "Escrow number"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some(escrow) = find_own_escrow(ctx, id).await? else {
+        return Ok(());
+    };
+
+    // The buyer releases to the seller; a seller releasing backs out and refunds the buyer
+    let (payout, status, message) = if escrow.buyer == user_id {
+        (escrow::release_payout(&escrow), STATUS_RELEASED, format!("paid **{} Slumcoins** to <@{}>", escrow.amount, escrow.seller))
+    } else {
+        (escrow::refund_payout(&escrow), STATUS_REFUNDED, format!("refunded **{} Slumcoins** to <@{}>", escrow.amount, escrow.buyer))
+    };
+
+    match data.database.transition_escrow(escrow.id, STATUS_OPEN, status, Some(&user_id), Some(&payout)).await {
+        Ok(true) => {
+            ctx.say(format!("🔓 Escrow `#{}` closed: {}", escrow.id, message)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Escrow `#{}` is {} and can't be released.", escrow.id, escrow.status)).await?;
+        }
+        Err(e) => {
+            error!("Error releasing escrow: {}", e);
+            ctx.say("Error releasing escrow. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Flag an escrow for an admin to resolve
+#[poise::command(slash_command, rename = "dispute")]
+pub async fn escrow_dispute(
+    ctx: Context<'_>,
+    #[description = "Escrow number"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some(escrow) = find_own_escrow(ctx, id).await? else {
+        return Ok(());
+    };
+
+    match data.database.transition_escrow(escrow.id, STATUS_OPEN, STATUS_DISPUTED, None, None).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "⚠️ <@{}> disputed escrow `#{}`. Funds stay locked until an admin resolves it.",
+                user_id, escrow.id
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Escrow `#{}` is {} and can't be disputed.", escrow.id, escrow.status)).await?;
+        }
+        Err(e) => {
+            error!("Error disputing escrow: {}", e);
+            ctx.say("Error disputing escrow. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List disputed escrows
+#[poise::command(slash_command, rename = "disputes", check = "is_admin")]
+pub async fn escrow_disputes(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let escrows = data.database.get_disputed_escrows(&guild_id).await?;
+    if escrows.is_empty() {
+        ctx.say("No disputed escrows.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Disputed Escrows**\n".to_string();
+    for escrow in &escrows {
+        response.push_str(&format!(
+            "`#{}` **{} Slumcoins** buyer <@{}> → seller <@{}> · *{}*\n",
+            escrow.id, escrow.amount, escrow.buyer, escrow.seller, escrow.description
+        ));
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Settle a disputed escrow
+#[poise::command(slash_command, rename = "resolve", check = "is_admin")]
+pub async fn escrow_resolve(
+    ctx: Context<'_>,
+    #[description = "Escrow number"] id: i64,
+    #[description = "Who gets the funds"] ruling: EscrowRuling,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let admin_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let escrow = match data.database.get_escrow(id).await? {
+        Some(escrow) if escrow.guild_id == guild_id => escrow,
+        _ => {
+            ctx.say(format!("No escrow `#{}` in this server.", id)).await?;
+            return Ok(());
+        }
+    };
+
+    let (payout, status, recipient) = match ruling {
+        EscrowRuling::Seller => (escrow::release_payout(&escrow), STATUS_RELEASED, &escrow.seller),
+        EscrowRuling::Buyer => (escrow::refund_payout(&escrow), STATUS_REFUNDED, &escrow.buyer),
+    };
+
+    match data.database.transition_escrow(escrow.id, STATUS_DISPUTED, status, Some(&admin_id), Some(&payout)).await {
+        Ok(true) => {
+            log_admin_action(ctx, "escrow_resolve", format!("escrow #{}", escrow.id), Some(escrow.amount), Some(format!("awarded to {}", recipient))).await;
+            ctx.say(format!(
+                "⚖️ Escrow `#{}` resolved by <@{}>: **{} Slumcoins** to <@{}>.",
+                escrow.id, admin_id, escrow.amount, recipient
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Escrow `#{}` is {}, only disputed escrows can be resolved.", escrow.id, escrow.status)).await?;
+        }
+        Err(e) => {
+            error!("Error resolving escrow: {}", e);
+            ctx.say("Error resolving escrow. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
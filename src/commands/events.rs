@@ -0,0 +1,297 @@
+use chrono::{NaiveDateTime, Utc};
+use tracing::error;
+
+use crate::{Context, Error, database::Transaction};
+use crate::events;
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{is_admin, log_admin_action, database_error_message};
+
+/// Autocomplete names of this server's upcoming events
+pub async fn autocomplete_event(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_scheduled_events(&guild_id).await {
+        Ok(events) => events
+            .into_iter()
+            .map(|event| event.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading events: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Buy event tickets and check in to earn attendance bonuses
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("event_list", "event_create", "event_buy", "event_checkin", "event_cancel")
+)]
+pub async fn event(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show upcoming events
+#[poise::command(slash_command, rename = "list")]
+pub async fn event_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let events = match data.database.get_scheduled_events(&guild_id).await {
+        Ok(events) => events,
+        Err(e) => {
+            error!("Error loading events: {}", e);
+            ctx.say("Error loading events.").await?;
+            return Ok(());
+        }
+    };
+
+    if events.is_empty() {
+        ctx.say("No upcoming events.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Upcoming Events**\n".to_string();
+    for event in &events {
+        let remaining = data.database.get_shop_item(event.item_id).await?
+            .and_then(|item| item.stock)
+            .unwrap_or(0);
+        response.push_str(&format!(
+            "• **{}** <t:{}:F> - {} Slumcoins · {}/{} tickets left",
+            event.name, event.starts_at, event.ticket_price, remaining, event.capacity
+        ));
+        if event.attendance_bonus > 0 {
+            response.push_str(&format!(" · {} coin attendance bonus", event.attendance_bonus));
+        }
+        response.push('\n');
+    }
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Schedule a ticketed event
+#[poise::command(slash_command, rename = "create", check = "is_admin")]
+pub async fn event_create(
+    ctx: Context<'_>,
+    #[description = "Event name"] name: String,
+    #[description = "Start time in UTC, e.g. 2024-06-01 20:00"] time: String,
+    #[description = "Ticket price in Slumcoins"] ticket_price: i64,
+    #[description = "Number of tickets available"] capacity: i64,
+    #[description = "Coins paid to each attendee who checks in (default: 0)"] attendance_bonus: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let name = name.trim().to_string();
+    let attendance_bonus = attendance_bonus.unwrap_or(0);
+
+    if name.is_empty() || ticket_price <= 0 || capacity <= 0 || attendance_bonus < 0 {
+        ctx.say("Events need a name, a positive ticket price and capacity, and a non-negative bonus.").await?;
+        return Ok(());
+    }
+
+    let starts_at = match NaiveDateTime::parse_from_str(time.trim(), "%Y-%m-%d %H:%M") {
+        Ok(time) => time.and_utc().timestamp(),
+        Err(_) => {
+            ctx.say("Time must look like `2024-06-01 20:00` (UTC).").await?;
+            return Ok(());
+        }
+    };
+
+    if starts_at <= Utc::now().timestamp() {
+        ctx.say("Events have to start in the future.").await?;
+        return Ok(());
+    }
+
+    if data.database.get_scheduled_event_by_name(&guild_id, &name).await?.is_some() {
+        ctx.say(format!("There's already an upcoming event called **{}**.", name)).await?;
+        return Ok(());
+    }
+
+    let author_id = ctx.author().id.to_string();
+    match data.database.create_event(&guild_id, &name, starts_at, ticket_price, capacity, attendance_bonus, &author_id).await {
+        Ok(event) => {
+            log_admin_action(ctx, "event_create", format!("event {}", event.name), Some(event.ticket_price), None).await;
+            ctx.say(format!(
+                "🎫 **{}** scheduled for <t:{}:F>\n{} tickets at {} Slumcoins each. Get yours with `/event buy`.",
+                event.name, event.starts_at, event.capacity, event.ticket_price
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error creating event: {}", e);
+            ctx.say("Error creating event.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Buy a ticket for an event
+#[poise::command(slash_command, rename = "buy")]
+pub async fn event_buy(
+    ctx: Context<'_>,
+    #[description = "Event to buy a ticket for"]
+    #[autocomplete = "autocomplete_event"]
+    name: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let event = match data.database.get_scheduled_event_by_name(&guild_id, &name).await? {
+        Some(event) => event,
+        None => {
+            ctx.say(format!("No upcoming event called **{}**.", name)).await?;
+            return Ok(());
+        }
+    };
+
+    let Some(ticket) = data.database.get_shop_item(event.item_id).await? else {
+        ctx.say("This event has no tickets.").await?;
+        return Ok(());
+    };
+
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < ticket.price {
+        ctx.say(format!(
+            "UR BROKE BUB! A ticket costs {} Slumcoins and you have {}",
+            ticket.price, balance
+        )).await?;
+        return Ok(());
+    }
+
+    let transaction = Transaction::system(
+        &user_id,
+        TREASURY_ACCOUNT,
+        ticket.price,
+        "event_ticket",
+        Some(format!("Ticket for {}", event.name)),
+    );
+
+    match data.database.purchase_item(&ticket, &transaction).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "🎫 Bought a ticket to **{}** for {} Slumcoins. Check in with `/event checkin` when it starts <t:{}:R>.",
+                event.name, ticket.price, event.starts_at
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("**{}** is sold out.", event.name)).await?;
+        }
+        Err(e) => {
+            error!("Error buying event ticket: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Ticket purchase failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check in to an event you have a ticket for
+#[poise::command(slash_command, rename = "checkin")]
+pub async fn event_checkin(
+    ctx: Context<'_>,
+    #[description = "Event you're attending"]
+    #[autocomplete = "autocomplete_event"]
+    name: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let event = match data.database.get_scheduled_event_by_name(&guild_id, &name).await? {
+        Some(event) => event,
+        None => {
+            ctx.say(format!("No upcoming event called **{}**.", name)).await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now().timestamp();
+    if !events::checkin_open(&event, now) {
+        let opens_at = event.starts_at - events::CHECKIN_OPENS_BEFORE_SECONDS;
+        ctx.say(format!("Check-in for **{}** opens <t:{}:R>.", event.name, opens_at)).await?;
+        return Ok(());
+    }
+
+    if data.database.get_event_checkins(event.id).await?.contains(&user_id) {
+        ctx.say(format!("You're already checked in to **{}**.", event.name)).await?;
+        return Ok(());
+    }
+
+    match data.database.check_in_event(&event, &user_id).await {
+        Ok(true) => {
+            let mut response = format!("✅ <@{}> checked in to **{}**", user_id, event.name);
+            if event.attendance_bonus > 0 {
+                response.push_str(&format!(
+                    "\nYou'll get **{} Slumcoins** for attending when the event closes.",
+                    event.attendance_bonus
+                ));
+            }
+            ctx.say(response).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("You don't have a ticket to **{}**. Buy one with `/event buy`.", event.name)).await?;
+        }
+        Err(e) => {
+            error!("Error checking in to event: {}", e);
+            ctx.say("Check-in failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Cancel an event and refund its tickets
+#[poise::command(slash_command, rename = "cancel", check = "is_admin")]
+pub async fn event_cancel(
+    ctx: Context<'_>,
+    #[description = "Event to cancel"]
+    #[autocomplete = "autocomplete_event"]
+    name: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let event = match data.database.get_scheduled_event_by_name(&guild_id, &name).await? {
+        Some(event) => event,
+        None => {
+            ctx.say(format!("No upcoming event called **{}**.", name)).await?;
+            return Ok(());
+        }
+    };
+
+    match data.database.cancel_event(&event, TREASURY_ACCOUNT).await {
+        Ok(refunded) => {
+            log_admin_action(ctx, "event_cancel", format!("event {}", event.name), Some(refunded * event.ticket_price), None).await;
+            ctx.say(format!(
+                "Cancelled **{}**. Refunded {} ticket(s) ({} Slumcoins).",
+                event.name, refunded, refunded * event.ticket_price
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error cancelling event: {}", e);
+            ctx.say("Error cancelling event.").await?;
+        }
+    }
+
+    Ok(())
+}
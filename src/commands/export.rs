@@ -0,0 +1,180 @@
+use chrono::{NaiveDate, Utc};
+use futures::StreamExt;
+use poise::serenity_prelude as serenity;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::{Transaction, TransactionFilter};
+use super::{is_admin, log_admin_action};
+
+// Discord rejects bot uploads above this size
+const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum ExportFormat {
+    #[name = "csv"]
+    Csv,
+    #[name = "json"]
+    Json,
+}
+
+const CSV_HEADER: &str = "id,from_user,to_user,amount,transaction_type,message,nonce,signature,timestamp_unix,created_at\n";
+
+// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(transaction: &Transaction) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{},{},{}\n",
+        csv_field(&transaction.id),
+        csv_field(&transaction.from_user),
+        csv_field(&transaction.to_user),
+        transaction.amount,
+        csv_field(&transaction.transaction_type),
+        csv_field(transaction.message.as_deref().unwrap_or_default()),
+        transaction.nonce,
+        csv_field(&transaction.signature),
+        transaction.timestamp_unix,
+        transaction.created_at.to_rfc3339(),
+    )
+}
+
+// Start of a `YYYY-MM-DD` day in UTC, as a unix timestamp
+fn parse_day(day: &str) -> Option<i64> {
+    NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc().timestamp())
+}
+
+/// Download the ledger as a CSV or JSON file
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "File format"] format: ExportFormat,
+    #[description = "First day to include, like 2024-06-01 (UTC)"] from: Option<String>,
+    #[description = "Last day to include, like 2024-06-30 (UTC)"] to: Option<String>,
+    #[description = "Only transactions sent or received by this user"] user: Option<serenity::User>,
+    #[description = "Only transactions between `user` and this user"] counterparty: Option<serenity::User>,
+    #[description = "Only this transaction type, like transfer or mint"]
+    #[rename = "type"]
+    transaction_type: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    let from_ts = match from.as_deref().map(parse_day) {
+        Some(None) => {
+            ctx.send(poise::CreateReply::default().content("`from` must look like `2024-06-01`.").ephemeral(true)).await?;
+            return Ok(());
+        }
+        Some(Some(ts)) => Some(ts),
+        None => None,
+    };
+    // The whole of the `to` day is included
+    let to_ts = match to.as_deref().map(parse_day) {
+        Some(None) => {
+            ctx.send(poise::CreateReply::default().content("`to` must look like `2024-06-30`.").ephemeral(true)).await?;
+            return Ok(());
+        }
+        Some(Some(ts)) => Some(ts + 86400),
+        None => None,
+    };
+
+    ctx.defer_ephemeral().await?;
+
+    let filter = TransactionFilter {
+        user: user.as_ref().map(|user| user.id.to_string()),
+        counterparty: counterparty.as_ref().map(|user| user.id.to_string()),
+        transaction_type: transaction_type.map(|transaction_type| transaction_type.trim().to_lowercase()),
+        from: from_ts,
+        to: to_ts,
+    };
+    let extension = match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Json => "json",
+    };
+    let filename = format!("ledger-{}.{}", Utc::now().format("%Y%m%d-%H%M%S"), extension);
+    let path = std::env::temp_dir().join(format!("{}-{}", ctx.id(), filename));
+
+    let written = write_export(data, &path, format, &filter).await;
+    let result = match written {
+        Ok((count, bytes)) if bytes > MAX_ATTACHMENT_BYTES => {
+            ctx.say(format!(
+                "The export has {} transactions ({} MB), too big to upload. Narrow it down with `from`, `to`, `user` or `type`.",
+                count,
+                bytes / (1024 * 1024)
+            )).await.map(|_| ())
+        }
+        Ok((count, _)) => match attach(&path, &filename).await {
+            Ok(attachment) => {
+                log_admin_action(ctx, "export", format!("{} transactions", count), None, filter.user.as_ref().map(|id| format!("user {}", id))).await;
+                ctx.send(poise::CreateReply::default()
+                    .content(format!("Exported {} transactions.", count))
+                    .attachment(attachment)).await.map(|_| ())
+            }
+            Err(e) => {
+                error!("Error attaching ledger export: {}", e);
+                ctx.say("Error attaching the export.").await.map(|_| ())
+            }
+        },
+        Err(e) => {
+            error!("Error exporting ledger: {}", e);
+            ctx.say("Error exporting the ledger.").await.map(|_| ())
+        }
+    };
+
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        error!("Failed to remove ledger export {}: {}", path.display(), e);
+    }
+    result?;
+    Ok(())
+}
+
+async fn attach(path: &std::path::Path, filename: &str) -> Result<serenity::CreateAttachment, serenity::Error> {
+    let file = tokio::fs::File::open(path).await?;
+    serenity::CreateAttachment::file(&file, filename).await
+}
+
+// Stream matching transactions into `path`, returning the number written and the file size
+async fn write_export(
+    data: &crate::Data,
+    path: &std::path::Path,
+    format: ExportFormat,
+    filter: &TransactionFilter,
+) -> Result<(u64, u64), Error> {
+    let mut file = BufWriter::new(tokio::fs::File::create(path).await?);
+    let mut transactions = data.database.stream_transactions(filter);
+    let mut count = 0u64;
+
+    match format {
+        ExportFormat::Csv => file.write_all(CSV_HEADER.as_bytes()).await?,
+        ExportFormat::Json => file.write_all(b"[").await?,
+    }
+
+    while let Some(transaction) = transactions.next().await {
+        let transaction = transaction?;
+        match format {
+            ExportFormat::Csv => file.write_all(csv_row(&transaction).as_bytes()).await?,
+            ExportFormat::Json => {
+                file.write_all(if count == 0 { b"\n  " } else { b",\n  " }).await?;
+                file.write_all(&serde_json::to_vec(&transaction)?).await?;
+            }
+        }
+        count += 1;
+    }
+
+    if format == ExportFormat::Json {
+        file.write_all(b"\n]\n").await?;
+    }
+    file.flush().await?;
+
+    let bytes = file.get_ref().metadata().await?.len();
+    Ok((count, bytes))
+}
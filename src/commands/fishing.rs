@@ -0,0 +1,188 @@
+use rand::seq::SliceRandom;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, InventoryItem, Transaction, SYSTEM_ACCOUNT};
+use super::{cooldown, database_error_message, not_frozen};
+
+struct Catch {
+    name: &'static str,
+    emoji: &'static str,
+    // Slumcoins /sell pays, before `fish.value_percent`
+    value: i64,
+    weight: u32,
+}
+
+// Catches are stored as hidden shop items under this prefix so they can't clash with real ones
+const CATCH_PREFIX: &str = "Catch: ";
+// Chance out of the total weight that nothing bites
+const ESCAPE_WEIGHT: u32 = 15;
+const CATCHES: [Catch; 9] = [
+    Catch { name: "Old Boot", emoji: "🥾", value: 1, weight: 15 },
+    Catch { name: "Seaweed", emoji: "🌿", value: 2, weight: 15 },
+    Catch { name: "Minnow", emoji: "🐟", value: 5, weight: 25 },
+    Catch { name: "Trout", emoji: "🐟", value: 15, weight: 18 },
+    Catch { name: "Salmon", emoji: "🐠", value: 30, weight: 10 },
+    Catch { name: "Pufferfish", emoji: "🐡", value: 50, weight: 6 },
+    Catch { name: "Lobster", emoji: "🦞", value: 80, weight: 4 },
+    Catch { name: "Shark", emoji: "🦈", value: 250, weight: 2 },
+    Catch { name: "Golden Koi", emoji: "✨", value: 1000, weight: 1 },
+];
+
+/// Autocomplete items from the caller's inventory that `/sell` will buy
+pub async fn autocomplete_sellable_item(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_inventory(&user_id, &guild_id).await {
+        Ok(items) => items
+            .into_iter()
+            .filter(|item| item.sell_value.is_some())
+            .map(|item| item.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading inventory: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Cast a line and keep whatever you reel in
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn fish(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let outcomes: Vec<(Option<&Catch>, u32)> = std::iter::once((None, ESCAPE_WEIGHT))
+        .chain(CATCHES.iter().map(|catch| (Some(catch), catch.weight)))
+        .collect();
+    let Some(catch) = outcomes
+        .choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight)
+        .ok()
+        .and_then(|(catch, _)| *catch)
+    else {
+        ctx.say("🎣 Something tugged at the line, but it got away.").await?;
+        return Ok(());
+    };
+
+    let value_percent = config::get_i64(&data.database, &guild_id, "fish.value_percent").await?.max(0);
+    let value = (catch.value * value_percent / 100).max(1);
+    let name = format!("{}{}", CATCH_PREFIX, catch.name);
+
+    match data.database.record_catch(&guild_id, &name, value, &user_id).await {
+        Ok(_) => {
+            ctx.say(format!(
+                "🎣 You caught a **{}** {}! It's worth **{} Slumcoins**. It's in your `/inventory` until you `/sell` it.",
+                catch.name, catch.emoji, value
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error recording catch: {}", e);
+            ctx.say("The catch slipped away. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Sell `quantity` of an item, returning the coins it paid or None if the seller no longer has them
+async fn sell_item(ctx: Context<'_>, item: &InventoryItem, quantity: i64) -> Result<Option<i64>, DatabaseError> {
+    let user_id = ctx.author().id.to_string();
+    let amount = item.sell_value.unwrap_or_default() * quantity;
+    let payment = Transaction::system(
+        SYSTEM_ACCOUNT,
+        &user_id,
+        amount,
+        "sell",
+        Some(format!("Sold {}x {}", quantity, item.name)),
+    );
+
+    Ok(ctx.data().database.sell_inventory_item(item.item_id, quantity, &payment).await?.then_some(amount))
+}
+
+/// Sell catches and other sellable items for Slumcoins
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen")]
+pub async fn sell(
+    ctx: Context<'_>,
+    #[description = "Item to sell (default: everything sellable)"]
+    #[autocomplete = "autocomplete_sellable_item"]
+    item: Option<String>,
+    #[description = "How many to sell (default: all of them)"] quantity: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if quantity.is_some_and(|quantity| quantity <= 0) {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    if quantity.is_some() && item.is_none() {
+        ctx.say("Pick which item to sell that many of.").await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&user_id).await {
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let sellable: Vec<InventoryItem> = data.database
+        .get_inventory(&user_id, &guild_id)
+        .await?
+        .into_iter()
+        .filter(|owned| owned.sell_value.is_some())
+        .filter(|owned| item.as_ref().is_none_or(|name| owned.name.eq_ignore_ascii_case(name)))
+        .collect();
+
+    if sellable.is_empty() {
+        let response = match &item {
+            Some(name) => format!("You don't have any **{}** to sell.", name),
+            None => "You don't have anything to sell. Try `/fish`!".to_string(),
+        };
+        ctx.say(response).await?;
+        return Ok(());
+    }
+
+    let mut sold = Vec::new();
+    let mut total = 0;
+    for owned in &sellable {
+        let count = quantity.unwrap_or(owned.quantity);
+        if count > owned.quantity {
+            ctx.say(format!("You only have {}x **{}**.", owned.quantity, owned.name)).await?;
+            return Ok(());
+        }
+
+        match sell_item(ctx, owned, count).await {
+            Ok(Some(amount)) => {
+                sold.push(format!("{}x {}", count, owned.name.trim_start_matches(CATCH_PREFIX)));
+                total += amount;
+            }
+            // Given or sold away in the meantime
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error selling {}: {}", owned.name, e);
+                ctx.say(database_error_message(ctx, &e, "Error selling. Please try again.")).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    if sold.is_empty() {
+        ctx.say("Nothing was sold. Check your `/inventory` and try again.").await?;
+        return Ok(());
+    }
+    ctx.say(format!("Sold {} for **{} Slumcoins**.", sold.join(", "), total)).await?;
+    Ok(())
+}
@@ -0,0 +1,1227 @@
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use tracing::error;
+
+use crate::{Context, Error, config, database::{Database, DatabaseError, Transaction}};
+use crate::blackjack::{self, Card, Outcome, Shoe};
+use crate::ledger::{DAILY_LIMIT_WINDOW_SECONDS, TREASURY_ACCOUNT};
+use super::{cooldown, ephemeral_response, not_frozen, database_error_message, AccountFrozen};
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum CoinSide {
+    #[name = "heads"]
+    Heads,
+    #[name = "tails"]
+    Tails,
+}
+
+impl std::fmt::Display for CoinSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CoinSide::Heads => write!(f, "heads"),
+            CoinSide::Tails => write!(f, "tails"),
+        }
+    }
+}
+
+/// Why `discord_id` can't wager `amount` right now under their self-exclusion or daily
+/// gambling limit (`/gamblimit`), or None if they can. Every wager has to pass this first.
+pub async fn gamble_refusal(database: &Database, discord_id: &str, amount: i64) -> Result<Option<String>, DatabaseError> {
+    let limits = database.get_gamble_limits(discord_id).await?;
+    let now = chrono::Utc::now().timestamp();
+
+    if let Some(until) = limits.excluded_until.filter(|until| *until > now) {
+        return Ok(Some(format!("<@{}> is taking a break from gambling until <t:{}:f>.", discord_id, until)));
+    }
+    if let Some(limit) = limits.daily_limit {
+        let wagered = database.get_wagered_total(discord_id, now - DAILY_LIMIT_WINDOW_SECONDS).await?;
+        if wagered + amount > limit {
+            return Ok(Some(format!(
+                "That would take <@{}> past their daily gambling limit of {} Slumcoins ({} left in the last 24 hours).",
+                discord_id, limit, (limit - wagered).max(0)
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Check the player can cover `amount` and take it as a wager into the treasury.
+/// Replies to the user and returns `false` when the wager can't be placed.
+pub async fn take_wager(ctx: Context<'_>, amount: i64, game: &str) -> Result<bool, Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(false);
+    }
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(false);
+    }
+
+    if let Some(reason) = gamble_refusal(&data.database, &user_id, amount).await? {
+        ctx.say(reason).await?;
+        return Ok(false);
+    }
+
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < amount {
+        ctx.say(format!("UR BROKE BUB! You have {} Slumcoins", balance)).await?;
+        return Ok(false);
+    }
+
+    // The house has to be able to cover the winnings
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if treasury_balance < amount {
+        ctx.say("The house can't cover a bet that big right now.").await?;
+        return Ok(false);
+    }
+
+    let wager = Transaction::system(&user_id, TREASURY_ACCOUNT, amount, "gamble", Some(format!("{} wager", game)));
+    if let Err(e) = data.database.apply_transaction(&wager).await {
+        error!("Error taking wager: {}", e);
+        ctx.say(database_error_message(ctx, &e, "Error placing bet. Please try again.")).await?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Pay winnings from the treasury to the player
+pub async fn pay_winnings(ctx: Context<'_>, payout: i64, game: &str) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let winnings = Transaction::system(TREASURY_ACCOUNT, &user_id, payout, "gamble", Some(format!("{} payout", game)));
+    if let Err(e) = data.database.apply_transaction(&winnings).await {
+        error!("Error paying {} winnings to {}: {}", game, user_id, e);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+// Holds every guild's jackpot; the jackpots table tracks each guild's share
+pub const JACKPOT_ACCOUNT: &str = "JACKPOT";
+
+/// Feed a slice of what the player lost into the guild's jackpot, then give them a small
+/// chance at winning the whole thing. House games call this once when a round ends, with
+/// `lost` as 0 when the player won. Jackpot errors are logged rather than failing the game.
+pub async fn jackpot_round(ctx: Context<'_>, lost: i64, game: &str) -> Result<(), Error> {
+    let data = &ctx.data();
+    let Some(guild_id) = ctx.guild_id().map(|id| id.to_string()) else {
+        return Ok(());
+    };
+    let user_id = ctx.author().id.to_string();
+
+    let loss_percent = config::get_i64(&data.database, &guild_id, "jackpot.loss_percent").await?.clamp(0, 100);
+    let cut = lost * loss_percent / 100;
+    if cut > 0 {
+        let feed = Transaction::system(TREASURY_ACCOUNT, JACKPOT_ACCOUNT, cut, "jackpot_feed", Some(format!("{} loss", game)));
+        if let Err(e) = data.database.feed_jackpot(&guild_id, &feed).await {
+            error!("Error feeding jackpot for guild {}: {}", guild_id, e);
+        }
+    }
+
+    let odds = config::get_i64(&data.database, &guild_id, "jackpot.odds").await?;
+    if odds <= 0 || rand::thread_rng().gen_range(0..odds) != 0 {
+        return Ok(());
+    }
+
+    let jackpot = data.database.get_jackpot(&guild_id).await?;
+    if jackpot.pot <= 0 {
+        return Ok(());
+    }
+    let payout = Transaction::system(JACKPOT_ACCOUNT, &user_id, jackpot.pot, "jackpot_win", Some(format!("Hit the jackpot playing {}", game)));
+    match data.database.claim_jackpot(&guild_id, &payout).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "🎰 **JACKPOT!** <@{}> hit the jackpot playing {} and won **{} Slumcoins**!",
+                user_id, game, jackpot.pot
+            )).await?;
+        }
+        Ok(false) => {}
+        Err(e) => error!("Error paying jackpot to {}: {}", user_id, e),
+    }
+
+    Ok(())
+}
+
+/// Show this server's progressive jackpot
+#[poise::command(slash_command, category = "Games", guild_only)]
+pub async fn jackpot(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let jackpot = data.database.get_jackpot(&guild_id).await?;
+    let loss_percent = config::get_i64(&data.database, &guild_id, "jackpot.loss_percent").await?.clamp(0, 100);
+    let odds = config::get_i64(&data.database, &guild_id, "jackpot.odds").await?;
+
+    let chance = if odds > 0 { format!("1 in {} per game", odds) } else { "Disabled".to_string() };
+    let mut embed = serenity::CreateEmbed::new()
+        .title("🎰 Jackpot")
+        .description(format!("**{} Slumcoins**", jackpot.pot))
+        .field("Chance to win", chance, true)
+        .field("Fed by", format!("{}% of every gambling loss", loss_percent), true)
+        .color(0xe67e22);
+
+    if let (Some(winner), Some(payout), Some(won_at)) = (&jackpot.last_winner_id, jackpot.last_payout, jackpot.last_won_at) {
+        embed = embed.field("Last won", format!("<@{}> took {} Slumcoins <t:{}:R>", winner, payout, won_at), false);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Flip a coin for double or nothing
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn coinflip(
+    ctx: Context<'_>,
+    #[description = "Amount of Slumcoins to wager"] amount: i64,
+    #[description = "Side to call (default: heads)"] side: Option<CoinSide>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let call = side.unwrap_or(CoinSide::Heads);
+
+    if !take_wager(ctx, amount, "Coinflip").await? {
+        return Ok(());
+    }
+
+    let result = if rand::thread_rng().gen_bool(0.5) { CoinSide::Heads } else { CoinSide::Tails };
+
+    if result != call {
+        ctx.say(format!("🪙 It landed on **{}**. You lost **{} Slumcoins**.", result, amount)).await?;
+        jackpot_round(ctx, amount, "Coinflip").await?;
+        return Ok(());
+    }
+
+    // Double the wager, minus the house cut which stays in the treasury
+    let house_cut_percent = config::get_i64(&data.database, &guild_id, "coinflip.house_cut_percent").await?;
+    let house_cut = amount * 2 * house_cut_percent.clamp(0, 100) / 100;
+    let payout = amount * 2 - house_cut;
+
+    pay_winnings(ctx, payout, "Coinflip").await?;
+
+    ctx.say(format!(
+        "🪙 It landed on **{}**! You won **{} Slumcoins** (house cut: {}).",
+        result, payout, house_cut
+    )).await?;
+    jackpot_round(ctx, 0, "Coinflip").await?;
+
+    Ok(())
+}
+
+// Pockets on a single-zero wheel that are red; every other pocket but 0 is black.
+// The green zero is the house edge: color and number bets both lose on it.
+const RED_POCKETS: [u8; 18] = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36];
+// How long the wheel spins before the result is revealed
+const ROULETTE_SPIN_SECONDS: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RouletteBet {
+    Red,
+    Black,
+    Number(u8),
+}
+
+impl RouletteBet {
+    fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "red" => Some(RouletteBet::Red),
+            "black" => Some(RouletteBet::Black),
+            number => number.parse::<u8>().ok().filter(|number| *number <= 36).map(RouletteBet::Number),
+        }
+    }
+
+    // What a winning bet returns per coin staked, stake included: 1:1 on colors, 35:1 straight up
+    fn multiplier(self) -> i64 {
+        match self {
+            RouletteBet::Red | RouletteBet::Black => 2,
+            RouletteBet::Number(_) => 36,
+        }
+    }
+
+    fn wins(self, pocket: u8) -> bool {
+        match self {
+            RouletteBet::Red => RED_POCKETS.contains(&pocket),
+            RouletteBet::Black => pocket != 0 && !RED_POCKETS.contains(&pocket),
+            RouletteBet::Number(number) => number == pocket,
+        }
+    }
+}
+
+impl std::fmt::Display for RouletteBet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RouletteBet::Red => write!(f, "red"),
+            RouletteBet::Black => write!(f, "black"),
+            RouletteBet::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+fn pocket_label(pocket: u8) -> String {
+    if pocket == 0 {
+        "🟢 0".to_string()
+    } else if RED_POCKETS.contains(&pocket) {
+        format!("🔴 {}", pocket)
+    } else {
+        format!("⚫ {}", pocket)
+    }
+}
+
+/// Spin the roulette wheel: red or black pays 1:1, a single number pays 35:1
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn roulette(
+    ctx: Context<'_>,
+    #[description = "Amount of Slumcoins to wager"] amount: i64,
+    #[description = "red, black, or a number from 0 to 36"] bet: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    let Some(bet) = RouletteBet::parse(&bet) else {
+        ctx.say("Bet on `red`, `black`, or a number from 0 to 36.").await?;
+        return Ok(());
+    };
+
+    // take_wager only checks the house can match the stake, a straight-up win pays far more
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if amount > 0 && treasury_balance < amount.saturating_mul(bet.multiplier() - 1) {
+        ctx.say("The house can't cover a bet that big right now.").await?;
+        return Ok(());
+    }
+
+    if !take_wager(ctx, amount, "Roulette").await? {
+        return Ok(());
+    }
+
+    let reply = ctx.say(format!("🎡 The wheel is spinning... **{} Slumcoins** on **{}**", amount, bet)).await?;
+    tokio::time::sleep(std::time::Duration::from_secs(ROULETTE_SPIN_SECONDS)).await;
+
+    let pocket = rand::thread_rng().gen_range(0..=36u8);
+    let lost = if bet.wins(pocket) { 0 } else { amount };
+    let response = if bet.wins(pocket) {
+        let payout = amount * bet.multiplier();
+        pay_winnings(ctx, payout, "Roulette").await?;
+        format!("🎡 The ball lands on **{}**! You won **{} Slumcoins**.", pocket_label(pocket), payout)
+    } else {
+        format!("🎡 The ball lands on **{}**. You lost **{} Slumcoins**.", pocket_label(pocket), amount)
+    };
+    reply.edit(ctx, poise::CreateReply::default().content(response)).await?;
+    jackpot_round(ctx, lost, "Roulette").await?;
+
+    Ok(())
+}
+
+// Unanswered blackjack hands are forfeited after this long
+const BLACKJACK_TIMEOUT_SECONDS: u64 = 60;
+
+fn blackjack_embed(
+    player: &[Card],
+    dealer: &[Card],
+    wager: i64,
+    reveal_dealer: bool,
+    status: &str,
+) -> serenity::CreateEmbed {
+    let dealer_field = if reveal_dealer {
+        format!("{} ({})", blackjack::format_hand(dealer), blackjack::hand_value(dealer))
+    } else {
+        format!("{} `??`", dealer[0])
+    };
+
+    serenity::CreateEmbed::new()
+        .title(format!("🃏 Blackjack · {} Slumcoins", wager))
+        .field(
+            "Your hand",
+            format!("{} ({})", blackjack::format_hand(player), blackjack::hand_value(player)),
+            false,
+        )
+        .field("Dealer", dealer_field, false)
+        .description(status)
+}
+
+fn blackjack_buttons(ctx_id: u64, can_double: bool) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}hit", ctx_id))
+            .label("Hit")
+            .style(serenity::ButtonStyle::Primary),
+        serenity::CreateButton::new(format!("{}stand", ctx_id))
+            .label("Stand")
+            .style(serenity::ButtonStyle::Secondary),
+        serenity::CreateButton::new(format!("{}double", ctx_id))
+            .label("Double")
+            .style(serenity::ButtonStyle::Success)
+            .disabled(!can_double),
+    ])]
+}
+
+fn outcome_message(outcome: Outcome, wager: i64) -> String {
+    match outcome {
+        Outcome::Blackjack => format!("Blackjack! You won **{} Slumcoins**.", outcome.payout(wager) - wager),
+        Outcome::Win => format!("You won **{} Slumcoins**.", wager),
+        Outcome::Push => "Push. Your wager was returned.".to_string(),
+        Outcome::Lose => format!("You lost **{} Slumcoins**.", wager),
+    }
+}
+
+/// Play a hand of blackjack against the house
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn blackjack(
+    ctx: Context<'_>,
+    #[description = "Amount of Slumcoins to wager"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    // take_wager only checks the house can match the stake, a natural pays 3:2 on top of it
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if amount > 0 && treasury_balance < amount.saturating_mul(3) / 2 {
+        ctx.say("The house can't cover a bet that big right now.").await?;
+        return Ok(());
+    }
+
+    if !take_wager(ctx, amount, "Blackjack").await? {
+        return Ok(());
+    }
+
+    let mut shoe = Shoe::new();
+    let mut player = vec![shoe.draw(), shoe.draw()];
+    let mut dealer = vec![shoe.draw(), shoe.draw()];
+    let mut wager = amount;
+
+    // Naturals settle immediately
+    if blackjack::is_blackjack(&player) || blackjack::is_blackjack(&dealer) {
+        let outcome = blackjack::settle(&player, &dealer);
+        if outcome.payout(wager) > 0 {
+            pay_winnings(ctx, outcome.payout(wager), "Blackjack").await?;
+        }
+        let embed = blackjack_embed(&player, &dealer, wager, true, &outcome_message(outcome, wager));
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        jackpot_round(ctx, if outcome == Outcome::Lose { wager } else { 0 }, "Blackjack").await?;
+        return Ok(());
+    }
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(blackjack_embed(&player, &dealer, wager, false, "Hit, stand or double down?"))
+        .components(blackjack_buttons(ctx_id, true))).await?;
+
+    let author_id = ctx.author().id;
+    let last_press = loop {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == author_id)
+            .timeout(std::time::Duration::from_secs(BLACKJACK_TIMEOUT_SECONDS))
+            .await;
+
+        let Some(press) = press else {
+            // Walking away from the table forfeits the wager
+            let embed = blackjack_embed(&player, &dealer, wager, true, &format!(
+                "Timed out. You forfeited **{} Slumcoins**.", wager
+            ));
+            reply.edit(ctx, poise::CreateReply::default().embed(embed).components(Vec::new())).await?;
+            jackpot_round(ctx, wager, "Blackjack").await?;
+            return Ok(());
+        };
+
+        let action = press.data.custom_id.trim_start_matches(&ctx_id.to_string()).to_string();
+        match action.as_str() {
+            "hit" => {
+                player.push(shoe.draw());
+            }
+            "double" if player.len() == 2 => {
+                if let Some(reason) = gamble_refusal(&data.database, &user_id, wager).await? {
+                    press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::Message(
+                        serenity::CreateInteractionResponseMessage::new()
+                            .content(reason)
+                            .ephemeral(true),
+                    )).await?;
+                    continue;
+                }
+
+                // The treasury already holds the first wager and takes the second, then a win pays back
+                // twice both of them
+                let balance = data.database.get_balance(&user_id).await?;
+                let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+                if balance < wager || treasury_balance < wager.saturating_mul(3) {
+                    press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::Message(
+                        serenity::CreateInteractionResponseMessage::new()
+                            .content("You can't cover a double down right now.")
+                            .ephemeral(true),
+                    )).await?;
+                    continue;
+                }
+
+                let extra = Transaction::system(&user_id, TREASURY_ACCOUNT, wager, "gamble", Some("Blackjack double down".to_string()));
+                if let Err(e) = data.database.apply_transaction(&extra).await {
+                    error!("Error taking double down wager: {}", e);
+                    press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::Message(
+                        serenity::CreateInteractionResponseMessage::new()
+                            .content(database_error_message(ctx, &e, "Error placing bet. Please try again."))
+                            .ephemeral(true),
+                    )).await?;
+                    continue;
+                }
+
+                wager *= 2;
+                player.push(shoe.draw());
+            }
+            _ => {}
+        }
+
+        // Doubling takes exactly one card, standing or busting ends the hand
+        let finished = action != "hit" || blackjack::hand_value(&player) >= 21;
+        if !finished {
+            press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(blackjack_embed(&player, &dealer, wager, false, "Hit or stand?"))
+                    .components(blackjack_buttons(ctx_id, false)),
+            )).await?;
+            continue;
+        }
+
+        break press;
+    };
+
+    if blackjack::hand_value(&player) <= 21 {
+        blackjack::play_dealer(&mut shoe, &mut dealer);
+    }
+
+    let outcome = blackjack::settle(&player, &dealer);
+    if outcome.payout(wager) > 0 {
+        pay_winnings(ctx, outcome.payout(wager), "Blackjack").await?;
+    }
+
+    let embed = blackjack_embed(&player, &dealer, wager, true, &outcome_message(outcome, wager));
+    last_press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+        serenity::CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .components(Vec::new()),
+    )).await?;
+    jackpot_round(ctx, if outcome == Outcome::Lose { wager } else { 0 }, "Blackjack").await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum DiceDirection {
+    #[name = "over"]
+    Over,
+    #[name = "under"]
+    Under,
+}
+
+impl std::fmt::Display for DiceDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DiceDirection::Over => write!(f, "over"),
+            DiceDirection::Under => write!(f, "under"),
+        }
+    }
+}
+
+// Dice bets have to win between 5% and 95% of the time
+const DICE_MIN_CHANCE: i64 = 5;
+const DICE_MAX_CHANCE: i64 = 95;
+
+/// Roll 1-100 and bet on landing over or under a target. Longer odds pay more
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn dice(
+    ctx: Context<'_>,
+    #[description = "Amount of Slumcoins to wager"] amount: i64,
+    #[description = "Number the roll has to beat"] target: i64,
+    #[description = "Win when the roll is over or under the target (default: over)"] direction: Option<DiceDirection>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let direction = direction.unwrap_or(DiceDirection::Over);
+
+    // Out of the 100 possible rolls, how many win
+    let winning_rolls = match direction {
+        DiceDirection::Over => 100 - target,
+        DiceDirection::Under => target - 1,
+    };
+    if !(DICE_MIN_CHANCE..=DICE_MAX_CHANCE).contains(&winning_rolls) {
+        ctx.say(format!(
+            "Pick a target that gives between a {}% and {}% chance to win.",
+            DICE_MIN_CHANCE, DICE_MAX_CHANCE
+        )).await?;
+        return Ok(());
+    }
+
+    // A fair payout is 100 / winning_rolls times the wager, the house edge comes off the top
+    let house_edge_percent = config::get_i64(&data.database, &guild_id, "dice.house_edge_percent").await?.clamp(0, 100);
+    let payout = (i128::from(amount) * i128::from(100 - house_edge_percent) / i128::from(winning_rolls)) as i64;
+
+    // At high chances or a steep edge a win would pay back no more than the wager
+    if amount > 0 && payout <= amount {
+        ctx.say(format!(
+            "With a {}% house edge, a {}% chance wouldn't pay more than your wager. Pick a riskier target or a bigger bet.",
+            house_edge_percent, winning_rolls
+        )).await?;
+        return Ok(());
+    }
+
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if amount > 0 && treasury_balance < payout - amount {
+        ctx.say("The house can't cover a bet that big right now.").await?;
+        return Ok(());
+    }
+
+    if !take_wager(ctx, amount, "Dice").await? {
+        return Ok(());
+    }
+
+    let roll = rand::thread_rng().gen_range(1..=100i64);
+    let won = match direction {
+        DiceDirection::Over => roll > target,
+        DiceDirection::Under => roll < target,
+    };
+
+    if !won {
+        ctx.say(format!(
+            "🎲 Rolled **{}** (needed {} {}). You lost **{} Slumcoins**.",
+            roll, direction, target, amount
+        )).await?;
+        jackpot_round(ctx, amount, "Dice").await?;
+        return Ok(());
+    }
+
+    if payout > 0 {
+        pay_winnings(ctx, payout, "Dice").await?;
+    }
+    ctx.say(format!(
+        "🎲 Rolled **{}** (needed {} {})! You won **{} Slumcoins** ({}% chance).",
+        roll, direction, target, payout, winning_rolls
+    )).await?;
+    jackpot_round(ctx, 0, "Dice").await?;
+
+    Ok(())
+}
+
+// Unanswered high-low rounds end after this long
+const HIGHLOW_TIMEOUT_SECONDS: u64 = 60;
+
+// Pot after a correct guess. `favorable` of the 13 ranks win (ties lose), so a fair
+// multiplier is 13 / favorable, and the house edge comes off each step.
+fn highlow_step(pot: i64, favorable: i64, house_edge_percent: i64) -> i64 {
+    (i128::from(pot) * 13 * i128::from(100 - house_edge_percent) / (100 * i128::from(favorable))) as i64
+}
+
+fn highlow_embed(card: Card, wager: i64, pot: i64, streak: u32, status: &str) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(format!("🃏 High-Low · {} Slumcoins", wager))
+        .field("Card", card.to_string(), true)
+        .field("Streak", streak.to_string(), true)
+        .field("Pot", format!("{} Slumcoins", pot), true)
+        .description(status)
+}
+
+fn highlow_buttons(ctx_id: u64, card: Card, pot: i64, house_edge_percent: i64, can_cash_out: bool) -> Vec<serenity::CreateActionRow> {
+    let higher = i64::from(13 - card.rank);
+    let lower = i64::from(card.rank - 1);
+    let label = |direction: &str, favorable: i64| {
+        if favorable == 0 {
+            direction.to_string()
+        } else {
+            format!("{} ({})", direction, highlow_step(pot, favorable, house_edge_percent))
+        }
+    };
+
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}higher", ctx_id))
+            .label(label("Higher", higher))
+            .emoji('⬆')
+            .style(serenity::ButtonStyle::Primary)
+            .disabled(higher == 0),
+        serenity::CreateButton::new(format!("{}lower", ctx_id))
+            .label(label("Lower", lower))
+            .emoji('⬇')
+            .style(serenity::ButtonStyle::Primary)
+            .disabled(lower == 0),
+        serenity::CreateButton::new(format!("{}cashout", ctx_id))
+            .label(format!("Cash out ({})", pot))
+            .style(serenity::ButtonStyle::Success)
+            .disabled(!can_cash_out),
+    ])]
+}
+
+/// Guess whether the next card is higher or lower. Every right guess grows the pot, cash out any time
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn highlow(
+    ctx: Context<'_>,
+    #[description = "Amount of Slumcoins to wager"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let house_edge_percent = config::get_i64(&data.database, &guild_id, "highlow.house_edge_percent").await?.clamp(0, 100);
+
+    if !take_wager(ctx, amount, "High-Low").await? {
+        return Ok(());
+    }
+
+    // Every card comes from a fresh deck, so each rank is always a 1 in 13 draw
+    let mut card = Shoe::new().draw();
+    let mut pot = amount;
+    let mut streak = 0;
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(highlow_embed(card, amount, pot, streak, "Higher or lower? Ties lose."))
+        .components(highlow_buttons(ctx_id, card, pot, house_edge_percent, false))).await?;
+
+    let author_id = ctx.author().id;
+    loop {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == author_id)
+            .timeout(std::time::Duration::from_secs(HIGHLOW_TIMEOUT_SECONDS))
+            .await;
+
+        let Some(press) = press else {
+            // Walking away keeps whatever has been won so far
+            let status = if streak > 0 {
+                pay_winnings(ctx, pot, "High-Low").await?;
+                format!("Timed out, so you cashed out **{} Slumcoins**.", pot)
+            } else {
+                format!("Timed out. You forfeited **{} Slumcoins**.", amount)
+            };
+            reply.edit(ctx, poise::CreateReply::default()
+                .embed(highlow_embed(card, amount, pot, streak, &status))
+                .components(Vec::new())).await?;
+            jackpot_round(ctx, if streak > 0 { 0 } else { amount }, "High-Low").await?;
+            return Ok(());
+        };
+
+        let action = press.data.custom_id.trim_start_matches(&ctx_id.to_string()).to_string();
+        let favorable = match action.as_str() {
+            "higher" => i64::from(13 - card.rank),
+            "lower" => i64::from(card.rank - 1),
+            "cashout" if streak > 0 => {
+                pay_winnings(ctx, pot, "High-Low").await?;
+                let status = format!("Cashed out **{} Slumcoins** after {} right guess(es).", pot, streak);
+                press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .embed(highlow_embed(card, amount, pot, streak, &status))
+                        .components(Vec::new()),
+                )).await?;
+                jackpot_round(ctx, 0, "High-Low").await?;
+                return Ok(());
+            }
+            _ => continue,
+        };
+        if favorable == 0 {
+            continue;
+        }
+
+        // The house has to be able to cover the pot this guess would grow to
+        let next_pot = highlow_step(pot, favorable, house_edge_percent);
+        let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+        if treasury_balance < next_pot {
+            press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content("The house can't cover a pot that big right now. Cash out instead.")
+                    .ephemeral(true),
+            )).await?;
+            continue;
+        }
+
+        let next = Shoe::new().draw();
+        let won = match action.as_str() {
+            "higher" => next.rank > card.rank,
+            _ => next.rank < card.rank,
+        };
+        card = next;
+
+        if !won {
+            let status = format!("Wrong guess! You lost **{} Slumcoins**.", amount);
+            press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(highlow_embed(card, amount, pot, streak, &status))
+                    .components(Vec::new()),
+            )).await?;
+            jackpot_round(ctx, amount, "High-Low").await?;
+            return Ok(());
+        }
+
+        pot = next_pot;
+        streak += 1;
+        press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .embed(highlow_embed(card, amount, pot, streak, "Right! Keep going or cash out."))
+                .components(highlow_buttons(ctx_id, card, pot, house_edge_percent, true)),
+        )).await?;
+    }
+}
+
+// Holds both duel stakes between acceptance and payout
+pub const DUEL_ESCROW_ACCOUNT: &str = "DUEL_ESCROW";
+
+// Unanswered challenges expire after this long
+const DUEL_EXPIRY_SECONDS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum DuelGame {
+    #[name = "coinflip"]
+    Coinflip,
+    #[name = "dice"]
+    Dice,
+}
+
+impl std::fmt::Display for DuelGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DuelGame::Coinflip => write!(f, "coinflip"),
+            DuelGame::Dice => write!(f, "dice"),
+        }
+    }
+}
+
+fn duel_buttons(ctx_id: u64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}accept", ctx_id))
+            .label("Accept")
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{}decline", ctx_id))
+            .label("Decline")
+            .style(serenity::ButtonStyle::Danger),
+    ])]
+}
+
+/// Challenge someone to a winner-takes-all wager
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn duel(
+    ctx: Context<'_>,
+    #[description = "User to challenge"] user: serenity::User,
+    #[description = "Amount each player puts in"] amount: i64,
+    #[description = "How the winner is picked (default: coinflip)"] game: Option<DuelGame>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let challenger = ctx.author().clone();
+    let challenger_id = challenger.id.to_string();
+    let opponent_id = user.id.to_string();
+    let game = game.unwrap_or(DuelGame::Coinflip);
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if challenger.id == user.id || user.bot {
+        ctx.say("Pick a real opponent.").await?;
+        return Ok(());
+    }
+
+    for (discord_id, who) in [(&challenger_id, "You're"), (&opponent_id, "They're")] {
+        match data.database.get_user(discord_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                ctx.say(format!("{} not registered! Use `/register` first.", who)).await?;
+                return Ok(());
+            }
+            Err(e) => {
+                error!("Database error: {}", e);
+                ctx.say("Database error occurred.").await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let balance = data.database.get_balance(&challenger_id).await?;
+    if balance < amount {
+        ctx.say(format!("UR BROKE BUB! You have {} Slumcoins", balance)).await?;
+        return Ok(());
+    }
+
+    for discord_id in [&challenger_id, &opponent_id] {
+        if let Some(reason) = gamble_refusal(&data.database, discord_id, amount).await? {
+            ctx.send(poise::CreateReply::default()
+                .content(reason)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+            return Ok(());
+        }
+    }
+
+    if data.database.is_frozen(&opponent_id).await? {
+        ctx.say("Their account is frozen, so they can't duel right now.").await?;
+        return Ok(());
+    }
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(format!(
+            "⚔️ <@{}> challenges <@{}> to a **{}** duel for **{} Slumcoins** each!\n\
+            Expires <t:{}:R>.",
+            challenger.id, user.id, game, amount,
+            chrono::Utc::now().timestamp() + DUEL_EXPIRY_SECONDS as i64
+        ))
+        .components(duel_buttons(ctx_id))).await?;
+
+    // Only the opponent can answer, anyone else pressing is told so
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(DUEL_EXPIRY_SECONDS);
+    let press = loop {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+            .timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+            .await;
+        match press {
+            Some(press) if press.user.id != user.id => {
+                press.create_response(ctx.serenity_context(), ephemeral_response(format!("Only <@{}> can answer this duel.", user.id))).await?;
+            }
+            press => break press,
+        }
+    };
+
+    let Some(press) = press else {
+        reply.edit(ctx, poise::CreateReply::default()
+            .content(format!("⚔️ <@{}> didn't answer in time. The duel expired.", user.id))
+            .components(Vec::new())).await?;
+        return Ok(());
+    };
+
+    let respond = |content: String| {
+        serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(Vec::new()),
+        )
+    };
+
+    if press.data.custom_id.ends_with("decline") {
+        press.create_response(ctx.serenity_context(), respond(format!("⚔️ <@{}> declined the duel.", user.id))).await?;
+        return Ok(());
+    }
+
+    // Balances, limits and freezes may have changed while the challenge was open
+    for discord_id in [&challenger_id, &opponent_id] {
+        if data.database.is_frozen(discord_id).await? {
+            press.create_response(ctx.serenity_context(), respond(format!(
+                "⚔️ Duel called off: <@{}>'s account is frozen.", discord_id
+            ))).await?;
+            return Ok(());
+        }
+        let balance = data.database.get_balance(discord_id).await?;
+        if balance < amount {
+            press.create_response(ctx.serenity_context(), respond(format!(
+                "⚔️ Duel called off: <@{}> can't cover the {} Slumcoin stake anymore.", discord_id, amount
+            ))).await?;
+            return Ok(());
+        }
+        if let Some(reason) = gamble_refusal(&data.database, discord_id, amount).await? {
+            press.create_response(ctx.serenity_context(), respond(format!("⚔️ Duel called off. {}", reason))).await?;
+            return Ok(());
+        }
+    }
+
+    let (winner, loser, detail) = match game {
+        DuelGame::Coinflip => {
+            if rand::thread_rng().gen_bool(0.5) {
+                (&challenger_id, &opponent_id, "The coin picked a side.".to_string())
+            } else {
+                (&opponent_id, &challenger_id, "The coin picked a side.".to_string())
+            }
+        }
+        DuelGame::Dice => {
+            let mut rng = rand::thread_rng();
+            let (mut challenger_roll, mut opponent_roll) = (0, 0);
+            while challenger_roll == opponent_roll {
+                challenger_roll = rng.gen_range(1..=100);
+                opponent_roll = rng.gen_range(1..=100);
+            }
+            let detail = format!("🎲 <@{}> rolled **{}**, <@{}> rolled **{}**.", challenger_id, challenger_roll, opponent_id, opponent_roll);
+            if challenger_roll > opponent_roll {
+                (&challenger_id, &opponent_id, detail)
+            } else {
+                (&opponent_id, &challenger_id, detail)
+            }
+        }
+    };
+
+    let entries = [
+        Transaction::system(&challenger_id, DUEL_ESCROW_ACCOUNT, amount, "duel_stake", Some("Duel stake".to_string())),
+        Transaction::system(&opponent_id, DUEL_ESCROW_ACCOUNT, amount, "duel_stake", Some("Duel stake".to_string())),
+        Transaction::system(DUEL_ESCROW_ACCOUNT, winner, amount * 2, "duel_win", Some(format!("Won a duel against {}", loser))),
+    ];
+
+    if let Err(e) = data.database.apply_transactions(&entries).await {
+        error!("Error settling duel: {}", e);
+        press.create_response(ctx.serenity_context(), respond(database_error_message(ctx, &e, "Error settling the duel. No coins were moved."))).await?;
+        return Ok(());
+    }
+
+    press.create_response(ctx.serenity_context(), respond(format!(
+        "⚔️ **Duel!** <@{}> vs <@{}> for {} Slumcoins each\n{}\n🏆 <@{}> takes the pot of **{} Slumcoins**!",
+        challenger_id, opponent_id, amount, detail, winner, amount * 2
+    ))).await?;
+
+    Ok(())
+}
+
+// Rock-paper-scissors stakes pass through here in the same batch that pays them out
+pub const RPS_ESCROW_ACCOUNT: &str = "RPS_ESCROW";
+
+// Both players have this long to pick before the challenge expires
+const RPS_TIMEOUT_SECONDS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RpsMove {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl RpsMove {
+    const ALL: [RpsMove; 3] = [RpsMove::Rock, RpsMove::Paper, RpsMove::Scissors];
+
+    fn id(self) -> &'static str {
+        match self {
+            RpsMove::Rock => "rock",
+            RpsMove::Paper => "paper",
+            RpsMove::Scissors => "scissors",
+        }
+    }
+
+    fn emoji(self) -> char {
+        match self {
+            RpsMove::Rock => '🪨',
+            RpsMove::Paper => '📄',
+            RpsMove::Scissors => '✂',
+        }
+    }
+
+    fn beats(self, other: RpsMove) -> bool {
+        matches!(
+            (self, other),
+            (RpsMove::Rock, RpsMove::Scissors) | (RpsMove::Paper, RpsMove::Rock) | (RpsMove::Scissors, RpsMove::Paper)
+        )
+    }
+}
+
+impl std::fmt::Display for RpsMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.emoji(), self.id())
+    }
+}
+
+fn rps_buttons(ctx_id: u64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(
+        RpsMove::ALL
+            .iter()
+            .map(|choice| {
+                let mut label = choice.id().to_string();
+                label[..1].make_ascii_uppercase();
+                serenity::CreateButton::new(format!("{}{}", ctx_id, choice.id()))
+                    .label(label)
+                    .emoji(choice.emoji())
+                    .style(serenity::ButtonStyle::Primary)
+            })
+            .collect(),
+    )]
+}
+
+/// Challenge someone to rock-paper-scissors for coins. Both of you pick in secret
+#[poise::command(slash_command, category = "Games", guild_only, check = "not_frozen", check = "cooldown")]
+pub async fn rps(
+    ctx: Context<'_>,
+    #[description = "User to challenge"] user: serenity::User,
+    #[description = "Amount each player puts in"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let challenger = ctx.author().clone();
+    let challenger_id = challenger.id.to_string();
+    let opponent_id = user.id.to_string();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if challenger.id == user.id || user.bot {
+        ctx.say("Pick a real opponent.").await?;
+        return Ok(());
+    }
+
+    for (discord_id, who) in [(&challenger_id, "You're"), (&opponent_id, "They're")] {
+        match data.database.get_user(discord_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                ctx.say(format!("{} not registered! Use `/register` first.", who)).await?;
+                return Ok(());
+            }
+            Err(e) => {
+                error!("Database error: {}", e);
+                ctx.say("Database error occurred.").await?;
+                return Ok(());
+            }
+        }
+    }
+
+    if data.database.is_frozen(&opponent_id).await? {
+        ctx.say("Their account is frozen, so they can't play right now.").await?;
+        return Ok(());
+    }
+
+    for discord_id in [&challenger_id, &opponent_id] {
+        if let Some(reason) = gamble_refusal(&data.database, discord_id, amount).await? {
+            ctx.send(poise::CreateReply::default()
+                .content(reason)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+            return Ok(());
+        }
+    }
+
+    // No stakes are taken until both have picked, so an abandoned game never leaves coins in escrow
+    for (discord_id, who) in [(&challenger_id, "You don't"), (&opponent_id, "They don't")] {
+        if data.database.get_balance(discord_id).await? < amount {
+            ctx.say(format!("{} have {} Slumcoins to stake.", who, amount)).await?;
+            return Ok(());
+        }
+    }
+    let stake = |discord_id: &str| Transaction::system(discord_id, RPS_ESCROW_ACCOUNT, amount, "rps_stake", Some("Rock-paper-scissors stake".to_string()));
+    let refund = |discord_id: &str| Transaction::system(RPS_ESCROW_ACCOUNT, discord_id, amount, "rps_refund", Some("Rock-paper-scissors refund".to_string()));
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(RPS_TIMEOUT_SECONDS);
+    let challenge = format!(
+        "✊ <@{}> challenges <@{}> to rock-paper-scissors for **{} Slumcoins** each!\n\
+        Pick your move below, it stays secret until both of you have picked. Expires <t:{}:R>.",
+        challenger.id, user.id, amount,
+        chrono::Utc::now().timestamp() + RPS_TIMEOUT_SECONDS as i64
+    );
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(challenge.clone())
+        .components(rps_buttons(ctx_id))).await?;
+
+    let players = [challenger.id, user.id];
+    let mut challenger_move: Option<RpsMove> = None;
+    let mut opponent_move: Option<RpsMove> = None;
+
+    let last_press = loop {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+            .timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+            .await;
+
+        let Some(press) = press else {
+            let waiting_on = if challenger_move.is_none() { challenger.id } else { user.id };
+            reply.edit(ctx, poise::CreateReply::default()
+                .content(format!("✊ <@{}> didn't pick in time. The challenge expired and nobody paid anything.", waiting_on))
+                .components(Vec::new())).await?;
+            return Ok(());
+        };
+
+        if !players.contains(&press.user.id) {
+            press.create_response(ctx.serenity_context(), ephemeral_response("This isn't your game.")).await?;
+            continue;
+        }
+
+        let action = press.data.custom_id.trim_start_matches(&ctx_id.to_string()).to_string();
+        let Some(choice) = RpsMove::ALL.into_iter().find(|choice| choice.id() == action) else {
+            continue;
+        };
+        let is_challenger = press.user.id == challenger.id;
+        let slot = if is_challenger { &mut challenger_move } else { &mut opponent_move };
+        if let Some(picked) = slot {
+            press.create_response(ctx.serenity_context(), ephemeral_response(format!("You already picked {}.", picked))).await?;
+            continue;
+        }
+
+        // Either player may have been frozen, hit a limit or spent the coins since the challenge
+        let presser = press.user.id.to_string();
+        if data.database.is_frozen(&presser).await? {
+            press.create_response(ctx.serenity_context(), ephemeral_response(AccountFrozen.to_string())).await?;
+            continue;
+        }
+        if let Some(reason) = gamble_refusal(&data.database, &presser, amount).await? {
+            press.create_response(ctx.serenity_context(), ephemeral_response(reason)).await?;
+            continue;
+        }
+        if data.database.get_balance(&presser).await? < amount {
+            press.create_response(ctx.serenity_context(), ephemeral_response(format!("You don't have {} Slumcoins to stake.", amount))).await?;
+            continue;
+        }
+        *slot = Some(choice);
+
+        if challenger_move.is_some() && opponent_move.is_some() {
+            break press;
+        }
+
+        let picked = if is_challenger { challenger.id } else { user.id };
+        press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(format!("{}\n✅ <@{}> has picked.", challenge, picked)),
+        )).await?;
+        press.create_followup(ctx.serenity_context(), serenity::CreateInteractionResponseFollowup::new()
+            .content(format!("You picked {}.", choice))
+            .ephemeral(true)).await?;
+    };
+
+    let (Some(challenger_move), Some(opponent_move)) = (challenger_move, opponent_move) else {
+        return Ok(());
+    };
+    let reveal = format!("<@{}> threw {} · <@{}> threw {}", challenger.id, challenger_move, user.id, opponent_move);
+
+    // Both stakes and the outcome are written together, so a failure moves no coins at all
+    let mut entries = vec![stake(&challenger_id), stake(&opponent_id)];
+    let result = if challenger_move == opponent_move {
+        entries.extend([refund(&challenger_id), refund(&opponent_id)]);
+        "It's a tie! Both stakes were refunded.".to_string()
+    } else {
+        let (winner, loser) = if challenger_move.beats(opponent_move) {
+            (&challenger_id, &opponent_id)
+        } else {
+            (&opponent_id, &challenger_id)
+        };
+        let payout = Transaction::system(
+            RPS_ESCROW_ACCOUNT,
+            winner,
+            amount * 2,
+            "rps_win",
+            Some(format!("Won rock-paper-scissors against {}", loser)),
+        );
+        entries.push(payout);
+        format!("🏆 <@{}> wins **{} Slumcoins**!", winner, amount * 2)
+    };
+
+    // The first to pick may have been frozen or hit a limit while waiting on the other
+    let mut called_off = None;
+    for discord_id in [&challenger_id, &opponent_id] {
+        if data.database.is_frozen(discord_id).await? {
+            called_off = Some(format!("<@{}>'s account is frozen.", discord_id));
+        } else if let Some(reason) = gamble_refusal(&data.database, discord_id, amount).await? {
+            called_off = Some(reason);
+        }
+        if called_off.is_some() {
+            break;
+        }
+    }
+
+    let settled = match called_off {
+        Some(reason) => Err(reason),
+        None => data.database.apply_transactions(&entries).await.map_err(|e| match e {
+            DatabaseError::InsufficientFunds(overdraft) => format!(
+                "<@{}> can't cover the {} Slumcoin stake anymore.", overdraft.discord_id, amount
+            ),
+            e => {
+                error!("Error settling rps game: {}", e);
+                "Error settling the game.".to_string()
+            }
+        }),
+    };
+    let content = match settled {
+        Ok(()) => format!("✊ {}\n{}", reveal, result),
+        Err(reason) => format!("✊ {}\nGame called off: {} No coins were moved.", reveal, reason),
+    };
+    last_press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .components(Vec::new()),
+    )).await?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::giveaways::{enter_buttons, giveaway_embed};
+use super::{database_error_message, is_admin, log_admin_action};
+
+// Longest a giveaway can run, a week
+const MAX_GIVEAWAY_MINUTES: u32 = 7 * 24 * 60;
+const MAX_PRIZE_LENGTH: usize = 200;
+
+/// Run giveaways that members enter with a button
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("giveaway_start", "giveaway_reroll")
+)]
+pub async fn giveaway(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Post a giveaway in this channel and draw a winner when time is up
+#[poise::command(slash_command, rename = "start")]
+pub async fn giveaway_start(
+    ctx: Context<'_>,
+    #[description = "What the winner gets"] prize: String,
+    #[description = "Minutes until the winner is drawn (max one week)"] duration: u32,
+    #[description = "Coins each entry costs, paid to the winner (default: free)"] entry_cost: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let prize = prize.trim().to_string();
+    let entry_cost = entry_cost.unwrap_or(0);
+
+    if prize.is_empty() || prize.chars().count() > MAX_PRIZE_LENGTH {
+        ctx.send(poise::CreateReply::default().content(format!("Prizes need 1 to {} characters.", MAX_PRIZE_LENGTH)).ephemeral(true)).await?;
+        return Ok(());
+    }
+    if entry_cost < 0 {
+        ctx.send(poise::CreateReply::default().content("nice try bub").ephemeral(true)).await?;
+        return Ok(());
+    }
+    if duration == 0 || duration > MAX_GIVEAWAY_MINUTES {
+        ctx.send(poise::CreateReply::default().content(format!("Giveaways run for 1 to {} minutes.", MAX_GIVEAWAY_MINUTES)).ephemeral(true)).await?;
+        return Ok(());
+    }
+
+    let ends_at = chrono::Utc::now().timestamp() + i64::from(duration) * 60;
+    let giveaway = match data.database.create_giveaway(
+        &guild_id,
+        &ctx.channel_id().to_string(),
+        &ctx.author().id.to_string(),
+        &prize,
+        entry_cost,
+        ends_at,
+    ).await {
+        Ok(giveaway) => giveaway,
+        Err(e) => {
+            error!("Error creating giveaway: {}", e);
+            ctx.say("Error creating the giveaway. Please try again.").await?;
+            return Ok(());
+        }
+    };
+
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(giveaway_embed(&giveaway, 0))
+        .components(enter_buttons(giveaway.id))).await?;
+    let message = reply.message().await?;
+    if let Err(e) = data.database.set_giveaway_message(giveaway.id, &message.id.to_string()).await {
+        error!("Error saving giveaway {} message: {}", giveaway.id, e);
+    }
+
+    log_admin_action(ctx, "giveaway_start", format!("giveaway #{}", giveaway.id), Some(entry_cost), Some(prize)).await;
+    Ok(())
+}
+
+/// Draw a new winner for an ended giveaway
+#[poise::command(slash_command, rename = "reroll")]
+pub async fn giveaway_reroll(
+    ctx: Context<'_>,
+    #[description = "Giveaway number, shown on its embed"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let giveaway = match data.database.get_giveaway(id).await? {
+        Some(giveaway) if giveaway.guild_id == guild_id => giveaway,
+        _ => {
+            ctx.say(format!("No giveaway #{} in this server.", id)).await?;
+            return Ok(());
+        }
+    };
+    if giveaway.status == "open" {
+        ctx.say(format!("Giveaway #{} hasn't been drawn yet. It ends <t:{}:R>.", id, giveaway.ends_at)).await?;
+        return Ok(());
+    }
+
+    match crate::giveaways::reroll(&ctx.serenity_context().http, &data.database, &giveaway).await {
+        Ok(Some(winner)) => {
+            log_admin_action(ctx, "giveaway_reroll", format!("giveaway #{}", id), Some(giveaway.pot), Some(format!("new winner {}", winner))).await;
+            let mut response = format!("Rerolled giveaway #{}: <@{}> is the new winner.", id, winner);
+            if giveaway.pot > 0 && giveaway.winner_id.is_some() {
+                response.push_str(&format!(" The {} Slumcoin pot moved to them.", giveaway.pot));
+            }
+            ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+        }
+        Ok(None) => {
+            ctx.say("Nobody else entered, so there's no one to reroll to.").await?;
+        }
+        Err(e) => {
+            error!("Error rerolling giveaway {}: {}", id, e);
+            ctx.say(database_error_message(ctx, &e, "Error rerolling the giveaway.")).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{Database, DatabaseError, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{database_error_message, gamble_refusal, guild_cooldown, not_frozen, AccountFrozen};
+
+// Holds the crew's stakes while the join window is open
+pub const HEIST_ESCROW_ACCOUNT: &str = "HEIST_ESCROW";
+
+// How long the crew has to sign up
+const HEIST_JOIN_SECONDS: u64 = 60;
+// Success chance for a solo heist, what each extra member adds, and the most it can reach
+const HEIST_BASE_CHANCE: u32 = 25;
+const HEIST_CHANCE_PER_MEMBER: u32 = 5;
+const HEIST_MAX_CHANCE: u32 = 60;
+// Even when the heist works, each member has this chance of getting caught and losing their cut
+const HEIST_CAUGHT_CHANCE: f64 = 0.2;
+
+fn success_chance(crew: usize) -> u32 {
+    let extra = crew.saturating_sub(1) as u32;
+    (HEIST_BASE_CHANCE + extra * HEIST_CHANCE_PER_MEMBER).min(HEIST_MAX_CHANCE)
+}
+
+fn heist_buttons(ctx_id: u64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}join", ctx_id))
+            .label("Join the crew")
+            .emoji('💰')
+            .style(serenity::ButtonStyle::Success),
+    ])]
+}
+
+fn planning_message(planner: serenity::UserId, amount: i64, crew: &[String], closes_at: i64) -> String {
+    let members: Vec<String> = crew.iter().map(|member| format!("<@{}>", member)).collect();
+    format!(
+        "💰 <@{}> is planning a heist on the house! Stake: **{} Slumcoins** each.\n\
+        Crew ({}): {}\nSuccess chance: **{}%** (more crew, better odds). Join closes <t:{}:R>.",
+        planner, amount, crew.len(), members.join(", "), success_chance(crew.len()), closes_at
+    )
+}
+
+// Take `discord_id`'s stake into escrow, or say why they can't join
+async fn join_crew(ctx: Context<'_>, database: &Database, discord_id: &str, amount: i64) -> Result<Result<(), String>, Error> {
+    if let Err(e) = database.require_user(discord_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        return Ok(Err(database_error_message(ctx, &e, "Database error occurred.")));
+    }
+    if database.is_frozen(discord_id).await? {
+        return Ok(Err(AccountFrozen.to_string()));
+    }
+    if let Some(reason) = gamble_refusal(database, discord_id, amount).await? {
+        return Ok(Err(reason));
+    }
+
+    let stake = Transaction::system(discord_id, HEIST_ESCROW_ACCOUNT, amount, "heist_stake", Some("Heist stake".to_string()));
+    if let Err(e) = database.apply_transaction(&stake).await {
+        if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+            error!("Error taking heist stake from {}: {}", discord_id, e);
+        }
+        return Ok(Err(database_error_message(ctx, &e, "Error placing bet. Please try again.")));
+    }
+    Ok(Ok(()))
+}
+
+/// Rob the house together with the rest of the server
+#[poise::command(slash_command, category = "Games", guild_only, subcommands("heist_start"))]
+pub async fn heist(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Plan a heist that anyone can join by putting in the same stake
+#[poise::command(slash_command, rename = "start", check = "not_frozen", check = "guild_cooldown")]
+pub async fn heist_start(
+    ctx: Context<'_>,
+    #[description = "Stake each crew member puts in"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let planner = ctx.author().id;
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if let Err(reason) = join_crew(ctx, &data.database, &planner.to_string(), amount).await? {
+        ctx.say(reason).await?;
+        return Ok(());
+    }
+    let mut crew = vec![planner.to_string()];
+
+    let closes_at = chrono::Utc::now().timestamp() + HEIST_JOIN_SECONDS as i64;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(HEIST_JOIN_SECONDS);
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(planning_message(planner, amount, &crew, closes_at))
+        .components(heist_buttons(ctx_id))).await?;
+
+    loop {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+            .timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+            .await;
+        let Some(press) = press else {
+            break;
+        };
+
+        let ephemeral = |content: String| {
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+            )
+        };
+        let member_id = press.user.id.to_string();
+        if crew.contains(&member_id) {
+            press.create_response(ctx.serenity_context(), ephemeral("You're already in the crew.".to_string())).await?;
+            continue;
+        }
+        if let Err(reason) = join_crew(ctx, &data.database, &member_id, amount).await? {
+            press.create_response(ctx.serenity_context(), ephemeral(reason)).await?;
+            continue;
+        }
+
+        crew.push(member_id);
+        press.create_response(ctx.serenity_context(), serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(planning_message(planner, amount, &crew, closes_at)),
+        )).await?;
+    }
+
+    // Everything staked goes to the house, which pays the survivors back out if it works
+    let pot = amount * crew.len() as i64;
+    let chance = success_chance(crew.len());
+    let succeeded = rand::thread_rng().gen_range(0..100) < chance;
+    let (survivors, caught): (Vec<String>, Vec<String>) = if succeeded {
+        crew.iter().cloned().partition(|_| !rand::thread_rng().gen_bool(HEIST_CAUGHT_CHANCE))
+    } else {
+        (Vec::new(), crew.clone())
+    };
+
+    let mut entries = vec![Transaction::system(HEIST_ESCROW_ACCOUNT, TREASURY_ACCOUNT, pot, "heist_pot", Some("Heist stakes".to_string()))];
+    let mut share = 0;
+    if !survivors.is_empty() {
+        // The house adds loot on top of the stakes, as much as the treasury can spare
+        let loot_percent = config::get_i64(&data.database, &guild_id, "heist.loot_percent").await?.max(0);
+        let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+        let loot = pot + (pot * loot_percent / 100).min(treasury_balance.max(0));
+        share = loot / survivors.len() as i64;
+        entries.extend(survivors.iter().map(|survivor| Transaction::system(
+            TREASURY_ACCOUNT,
+            survivor,
+            share,
+            "heist_loot",
+            Some(format!("Heist loot with a crew of {}", crew.len())),
+        )));
+    }
+
+    let mention = |members: &[String]| members.iter().map(|member| format!("<@{}>", member)).collect::<Vec<_>>().join(", ");
+    let mut result = if !succeeded {
+        format!("🚨 The heist failed ({}% chance)! The crew lost their **{} Slumcoin** stakes to the house.", chance, amount)
+    } else if survivors.is_empty() {
+        "🚨 The heist worked, but the whole crew got caught on the way out! The stakes went to the house.".to_string()
+    } else {
+        format!("💰 The heist worked ({}% chance)! {} got away with **{} Slumcoins** each.", chance, mention(&survivors), share)
+    };
+    if succeeded && !survivors.is_empty() && !caught.is_empty() {
+        result.push_str(&format!("\n🚓 Caught and left with nothing: {}", mention(&caught)));
+    }
+
+    if let Err(e) = data.database.apply_transactions(&entries).await {
+        error!("Error settling heist in guild {}: {}", guild_id, e);
+        result = "Error paying out the heist. An admin can check the ledger.".to_string();
+    }
+
+    reply.edit(ctx, poise::CreateReply::default()
+        .content(format!("{}\nCrew: {}", result, mention(&crew)))
+        .components(Vec::new())).await?;
+    Ok(())
+}
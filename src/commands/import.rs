@@ -0,0 +1,96 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::importer;
+use super::{confirm, is_admin, log_admin_action};
+
+// Largest file /import will download
+const MAX_IMPORT_BYTES: u32 = 5 * 1024 * 1024;
+
+/// Bring balances over from another economy bot (UnbelievaBoat-style CSV or JSON export)
+#[poise::command(slash_command, category = "Admin", guild_only, check = "is_admin")]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "CSV or JSON export with a user ID and a total, balance or cash/bank column"] file: serenity::Attachment,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    if file.size > MAX_IMPORT_BYTES {
+        ctx.send(poise::CreateReply::default()
+            .content(format!("Imports are limited to {} MB.", MAX_IMPORT_BYTES / (1024 * 1024)))
+            .ephemeral(true)).await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let contents = match file.download().await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => {
+            error!("Error downloading import file: {}", e);
+            ctx.say("Couldn't download the file.").await?;
+            return Ok(());
+        }
+    };
+
+    let balances = match importer::parse(&file.filename, &contents) {
+        Ok(balances) => balances,
+        Err(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+    };
+    let found = balances.len();
+
+    let balances = match importer::pending(&data.database, balances).await {
+        Ok(balances) => balances,
+        Err(e) => {
+            error!("Error checking previous imports: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    };
+    if balances.is_empty() {
+        ctx.say(format!(
+            "Nothing to import: found {} positive balances, all of them already imported.",
+            found
+        )).await?;
+        return Ok(());
+    }
+
+    let total = balances.iter().fold(0i64, |total, balance| total.saturating_add(balance.amount));
+    let prompt = format!(
+        "Import **{}** balances totalling **{} Slumcoins** from `{}`? Unregistered users are registered and every balance is minted.{}",
+        balances.len(),
+        total,
+        file.filename,
+        if found > balances.len() { format!("\n{} users were imported before and are skipped.", found - balances.len()) } else { String::new() }
+    );
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+
+    match importer::import_balances(&data.database, &data.crypto, &balances, &file.filename).await {
+        Ok(outcome) => {
+            log_admin_action(
+                ctx,
+                "import",
+                file.filename.clone(),
+                Some(outcome.total),
+                Some(format!("{} credited, {} registered, {} failed", outcome.credited, outcome.registered, outcome.failed)),
+            ).await;
+            ctx.say(format!(
+                "Import done. bub boils the seeds\n\
+                Credited: **{}** users with **{} Slumcoins** · Newly registered: **{}** · Failed: **{}**",
+                outcome.credited, outcome.total, outcome.registered, outcome.failed
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error importing balances: {}", e);
+            ctx.say("Import failed, no balances were credited.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error, database::InventoryItem};
+use super::{autocomplete_shop_item, is_admin, log_admin_action};
+
+/// Autocomplete item names from the caller's inventory
+pub async fn autocomplete_inventory_item(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_inventory(&user_id, &guild_id).await {
+        Ok(items) => items
+            .into_iter()
+            .map(|item| item.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading inventory: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// Find an item the caller owns by name, replying if they don't have it
+async fn find_owned_item(ctx: Context<'_>, name: &str) -> Result<Option<InventoryItem>, Error> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let owned = ctx.data().database
+        .get_inventory(&user_id, &guild_id)
+        .await?
+        .into_iter()
+        .find(|item| item.name.eq_ignore_ascii_case(name));
+
+    if owned.is_none() {
+        ctx.say(format!("You don't have any **{}**.", name)).await?;
+    }
+
+    Ok(owned)
+}
+
+/// See, use and trade your items
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("inventory_show", "inventory_use", "inventory_give", "inventory_grant")
+)]
+pub async fn inventory(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show the items you own
+#[poise::command(slash_command, rename = "show")]
+pub async fn inventory_show(
+    ctx: Context<'_>,
+    #[description = "User whose inventory to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let target = user.as_ref().unwrap_or_else(|| ctx.author());
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let items = match data.database.get_inventory(&target.id.to_string(), &guild_id).await {
+        Ok(items) => items,
+        Err(e) => {
+            error!("Error loading inventory: {}", e);
+            ctx.say("Error loading inventory.").await?;
+            return Ok(());
+        }
+    };
+
+    if items.is_empty() {
+        ctx.say(format!("{} doesn't own anything yet.", target.name)).await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for item in &items {
+        description.push_str(&format!("**{}** x{}", item.name, item.quantity));
+        if item.consumable {
+            description.push_str(" · consumable");
+        }
+        if item.tradeable {
+            description.push_str(" · tradeable");
+        }
+        description.push('\n');
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Inventory", target.name))
+        .description(description);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Use one of your items
+#[poise::command(slash_command, rename = "use")]
+pub async fn inventory_use(
+    ctx: Context<'_>,
+    #[description = "Item to use"]
+    #[autocomplete = "autocomplete_inventory_item"]
+    item: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some(owned) = find_owned_item(ctx, &item).await? else {
+        return Ok(());
+    };
+
+    if !owned.consumable {
+        ctx.say(format!("**{}** can't be used up.", owned.name)).await?;
+        return Ok(());
+    }
+
+    match data.database.remove_inventory_item(&user_id, owned.item_id, 1).await {
+        Ok(true) => {
+            ctx.say(format!("<@{}> used **{}** ({} left)", user_id, owned.name, owned.quantity - 1)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("You don't have any **{}**.", owned.name)).await?;
+        }
+        Err(e) => {
+            error!("Error using inventory item: {}", e);
+            ctx.say("Error using item.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Give one of your items to someone else
+#[poise::command(slash_command, rename = "give")]
+pub async fn inventory_give(
+    ctx: Context<'_>,
+    #[description = "User to give the item to"] user: serenity::User,
+    #[description = "Item to give"]
+    #[autocomplete = "autocomplete_inventory_item"]
+    item: String,
+    #[description = "How many to give (default: 1)"] quantity: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let to_user_id = user.id.to_string();
+    let quantity = quantity.unwrap_or(1);
+
+    if quantity <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if to_user_id == user_id {
+        ctx.say("You can't give items to yourself.").await?;
+        return Ok(());
+    }
+
+    match data.database.get_user(&to_user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("Target user is not registered!").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let Some(owned) = find_owned_item(ctx, &item).await? else {
+        return Ok(());
+    };
+
+    if !owned.tradeable {
+        ctx.say(format!("**{}** can't be traded.", owned.name)).await?;
+        return Ok(());
+    }
+
+    match data.database.transfer_inventory_item(&user_id, &to_user_id, owned.item_id, quantity).await {
+        Ok(true) => {
+            ctx.say(format!("<@{}> gave {}x **{}** to <@{}>", user_id, quantity, owned.name, to_user_id)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("You only have {}x **{}**.", owned.quantity, owned.name)).await?;
+        }
+        Err(e) => {
+            error!("Error transferring inventory item: {}", e);
+            ctx.say("Error giving item.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Grant an item to a user
+#[poise::command(slash_command, rename = "grant", check = "is_admin")]
+pub async fn inventory_grant(
+    ctx: Context<'_>,
+    #[description = "User to grant the item to"] user: serenity::User,
+    #[description = "Shop item to grant"]
+    #[autocomplete = "autocomplete_shop_item"]
+    item: String,
+    #[description = "How many to grant (default: 1)"] quantity: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let to_user_id = user.id.to_string();
+    let quantity = quantity.unwrap_or(1);
+
+    if quantity <= 0 {
+        ctx.say("Quantity must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let shop_item = match data.database.get_shop_item_by_name(&guild_id, &item).await? {
+        Some(shop_item) => shop_item,
+        None => {
+            ctx.say(format!("No item called **{}** in the shop.", item)).await?;
+            return Ok(());
+        }
+    };
+
+    match data.database.add_inventory_item(&to_user_id, shop_item.id, quantity).await {
+        Ok(()) => {
+            log_admin_action(ctx, "inventory_grant", &to_user_id, None, Some(format!("{}x {}", quantity, shop_item.name))).await;
+            ctx.say(format!("Granted {}x **{}** to <@{}>", quantity, shop_item.name, to_user_id)).await?;
+        }
+        Err(e) => {
+            error!("Error granting inventory item: {}", e);
+            ctx.say("Error granting item.").await?;
+        }
+    }
+
+    Ok(())
+}
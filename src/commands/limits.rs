@@ -0,0 +1,276 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::ledger::{self, DAILY_LIMIT_WINDOW_SECONDS};
+use super::{confirm, is_admin, log_admin_action, say_private};
+
+/// Override how much a user can send per day
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("transferlimit_show", "transferlimit_set", "transferlimit_clear")
+)]
+pub async fn transferlimit(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show a user's daily transfer limit and what they've sent today
+#[poise::command(slash_command, rename = "show")]
+pub async fn transferlimit_show(
+    ctx: Context<'_>,
+    #[description = "User to look up"] user: serenity::User,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let user_id = user.id.to_string();
+
+    let overridden = data.database.get_transfer_limit_override(&guild_id, &user_id).await?.is_some();
+    let limit = ledger::daily_limit_for(&data.database, &guild_id, &user_id).await?;
+    let sent = data.database
+        .get_outgoing_transfer_total(&user_id, Utc::now().timestamp() - DAILY_LIMIT_WINDOW_SECONDS)
+        .await?;
+
+    let limit = if limit > 0 { format!("{} Slumcoins", limit) } else { "none".to_string() };
+    let source = if overridden { "override" } else { "server default" };
+    ctx.send(poise::CreateReply::default()
+        .content(format!(
+            "<@{}> · daily limit: **{}** ({})\nSent in the last 24 hours: {} Slumcoins",
+            user.id, limit, source, sent
+        ))
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Give a user their own daily transfer limit
+#[poise::command(slash_command, rename = "set")]
+pub async fn transferlimit_set(
+    ctx: Context<'_>,
+    #[description = "User to set the limit for"] user: serenity::User,
+    #[description = "Most coins they can send in 24 hours (0 = no limit)"] limit: i64,
+    #[description = "Why the limit is being changed"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let user_id = user.id.to_string();
+
+    if limit < 0 {
+        ctx.say("The limit can't be negative.").await?;
+        return Ok(());
+    }
+
+    match data.database.set_transfer_limit_override(&guild_id, &user_id, limit, &ctx.author().id.to_string()).await {
+        Ok(()) => {
+            log_admin_action(ctx, "transferlimit_set", &user_id, Some(limit), reason).await;
+            let response = if limit > 0 {
+                format!("<@{}> can now send up to **{} Slumcoins** per day.", user.id, limit)
+            } else {
+                format!("<@{}> can now send any amount.", user.id)
+            };
+            ctx.send(poise::CreateReply::default()
+                .content(response)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => {
+            error!("Error saving transfer limit: {}", e);
+            ctx.say("Error saving transfer limit.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Put a user back on the server's default daily limit
+#[poise::command(slash_command, rename = "clear")]
+pub async fn transferlimit_clear(
+    ctx: Context<'_>,
+    #[description = "User to reset"] user: serenity::User,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let user_id = user.id.to_string();
+
+    let response = match data.database.remove_transfer_limit_override(&guild_id, &user_id).await {
+        Ok(true) => {
+            log_admin_action(ctx, "transferlimit_clear", &user_id, None, None).await;
+            format!("<@{}> is back on the server's default daily limit.", user.id)
+        }
+        Ok(false) => format!("<@{}> doesn't have their own limit.", user.id),
+        Err(e) => {
+            error!("Error removing transfer limit: {}", e);
+            "Error removing transfer limit.".to_string()
+        }
+    };
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+// Longest self-exclusion /gamblimit exclude accepts
+const MAX_EXCLUSION_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum GambleLimitPeriod {
+    #[name = "daily"]
+    Daily,
+}
+
+// Parse a break length like `12h`, `7d` or `2w` into seconds
+fn parse_break_length(input: &str) -> Option<i64> {
+    let input = input.trim().to_lowercase();
+    let (number, unit) = input.split_at(input.find(|c: char| !c.is_ascii_digit())?);
+    let number: i64 = number.parse().ok()?;
+    let unit_seconds = match unit.trim() {
+        "h" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86400,
+        "w" | "week" | "weeks" => 7 * 86400,
+        _ => return None,
+    };
+    number.checked_mul(unit_seconds).filter(|seconds| *seconds > 0)
+}
+
+/// Put limits on your own gambling. Only an admin can loosen or lift them early
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("gamblimit_show", "gamblimit_set", "gamblimit_exclude", "gamblimit_clear")
+)]
+pub async fn gamblimit(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show your gambling limits and what you've wagered today
+#[poise::command(slash_command, rename = "show")]
+pub async fn gamblimit_show(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let now = Utc::now().timestamp();
+
+    let limits = data.database.get_gamble_limits(&user_id).await?;
+    let wagered = data.database.get_wagered_total(&user_id, now - DAILY_LIMIT_WINDOW_SECONDS).await?;
+
+    let limit = match limits.daily_limit {
+        Some(limit) => format!("**{} Slumcoins**", limit),
+        None => "none".to_string(),
+    };
+    let mut response = format!("Daily gambling limit: {}\nWagered in the last 24 hours: {} Slumcoins", limit, wagered);
+    if let Some(until) = limits.excluded_until.filter(|until| *until > now) {
+        response.push_str(&format!("\nTaking a break from gambling until <t:{}:f>", until));
+    }
+    say_private(ctx, response).await?;
+    Ok(())
+}
+
+/// Cap how much you can wager. You can lower it any time, raising it takes an admin
+#[poise::command(slash_command, rename = "set")]
+pub async fn gamblimit_set(
+    ctx: Context<'_>,
+    #[description = "Which limit to set"] period: GambleLimitPeriod,
+    #[description = "Most coins you can wager"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        say_private(ctx, "The limit has to be at least 1 Slumcoin. To stop gambling altogether, use `/gamblimit exclude`.").await?;
+        return Ok(());
+    }
+
+    let limits = data.database.get_gamble_limits(&user_id).await?;
+    match period {
+        GambleLimitPeriod::Daily => {
+            if let Some(current) = limits.daily_limit.filter(|current| amount > *current) {
+                say_private(ctx, format!(
+                    "Your daily limit is {} Slumcoins. You can only lower it yourself; ask an admin to raise it.",
+                    current
+                )).await?;
+                return Ok(());
+            }
+
+            match data.database.set_gamble_daily_limit(&user_id, Some(amount)).await {
+                Ok(()) => say_private(ctx, format!("You can now wager up to **{} Slumcoins** per day.", amount)).await?,
+                Err(e) => {
+                    error!("Error saving gambling limit: {}", e);
+                    say_private(ctx, "Error saving your limit.").await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Take a break from all gambling. It can't be ended early except by an admin
+#[poise::command(slash_command, rename = "exclude")]
+pub async fn gamblimit_exclude(
+    ctx: Context<'_>,
+    #[description = "How long, like 12h, 7d or 2w"] duration: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some(seconds) = parse_break_length(&duration).filter(|seconds| *seconds <= MAX_EXCLUSION_DAYS * 86400) else {
+        say_private(ctx, format!("Give a length like `12h`, `7d` or `2w`, up to {} days.", MAX_EXCLUSION_DAYS)).await?;
+        return Ok(());
+    };
+    let until = Utc::now().timestamp() + seconds;
+
+    let limits = data.database.get_gamble_limits(&user_id).await?;
+    if let Some(current) = limits.excluded_until.filter(|current| *current >= until) {
+        say_private(ctx, format!("You're already taking a break until <t:{}:f>.", current)).await?;
+        return Ok(());
+    }
+
+    let prompt = format!(
+        "Stop all gambling until <t:{}:f>? Games, duels, bets and lottery tickets will be blocked, and only an admin can lift this early.",
+        until
+    );
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+
+    if let Err(e) = data.database.set_gamble_excluded_until(&user_id, Some(until)).await {
+        error!("Error saving gambling exclusion: {}", e);
+        say_private(ctx, "Error saving your break.").await?;
+        return Ok(());
+    }
+    say_private(ctx, format!("You're taking a break from gambling until <t:{}:f>. Take care!", until)).await?;
+    Ok(())
+}
+
+/// Lift a user's gambling limit and break
+#[poise::command(slash_command, rename = "clear", check = "is_admin")]
+pub async fn gamblimit_clear(
+    ctx: Context<'_>,
+    #[description = "User to clear"] user: serenity::User,
+    #[description = "Why the limits are being lifted"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = user.id.to_string();
+
+    let cleared = tokio::try_join!(
+        data.database.set_gamble_daily_limit(&user_id, None),
+        data.database.set_gamble_excluded_until(&user_id, None),
+    );
+    let response = match cleared {
+        Ok(_) => {
+            log_admin_action(ctx, "gamblimit_clear", &user_id, None, reason).await;
+            format!("Lifted <@{}>'s gambling limit and break.", user.id)
+        }
+        Err(e) => {
+            error!("Error clearing gambling limits: {}", e);
+            "Error clearing gambling limits.".to_string()
+        }
+    };
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
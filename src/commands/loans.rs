@@ -0,0 +1,305 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::Loan;
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::loans::{self, STATUS_ACTIVE, STATUS_DEFAULTED, STATUS_PENDING};
+use super::{is_admin, log_admin_action, say_private, database_error_message};
+
+/// Borrow coins from the treasury and pay them back over time
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("loan_request", "loan_status", "loan_repay", "loan_pending", "loan_approve", "loan_deny")
+)]
+pub async fn loan(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Ask the admins for a loan
+#[poise::command(slash_command, rename = "request")]
+pub async fn loan_request(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to borrow"] amount: i64,
+    #[description = "What the loan is for"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let reason = reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let max_amount = config::get_i64(&data.database, &guild_id, "loan.max_amount").await?;
+    if amount > max_amount {
+        ctx.say(format!("Loans are capped at {} Slumcoins.", max_amount)).await?;
+        return Ok(());
+    }
+
+    if let Some(open) = data.database.get_open_loan(&guild_id, &user_id).await? {
+        ctx.say(format!("You already have a {} loan (`#{}`). Check it with `/loan status`.", open.status, open.id)).await?;
+        return Ok(());
+    }
+
+    let interest_percent = config::get_i64(&data.database, &guild_id, "loan.interest_percent").await?;
+    let installments = config::get_i64(&data.database, &guild_id, "loan.installments").await?.max(1);
+    let interval_hours = config::get_i64(&data.database, &guild_id, "loan.payment_interval_hours").await?.max(1);
+
+    let total_due = loans::total_due(amount, interest_percent);
+    let mut loan = Loan {
+        id: 0,
+        guild_id,
+        borrower: user_id.clone(),
+        principal: amount,
+        total_due,
+        repaid: 0,
+        installment: loans::installment(total_due, installments),
+        payment_interval_seconds: interval_hours * 3600,
+        reason,
+        status: STATUS_PENDING.to_string(),
+        requested_at: Utc::now().timestamp(),
+        approved_by: None,
+        next_payment_at: None,
+        missed_payments: 0,
+    };
+
+    match data.database.create_loan(&loan).await {
+        Ok(loan_id) => {
+            loan.id = loan_id;
+            let mut response = format!(
+                "🏦 <@{}> requested loan `#{}` for **{} Slumcoins**\n\
+                Repay **{}** in total ({}% interest) as {} payments of up to {} every {}h.\n\
+                An admin can approve it with `/loan approve {}`.",
+                user_id, loan.id, amount, total_due, interest_percent, installments, loan.installment, interval_hours, loan.id
+            );
+            if let Some(reason) = &loan.reason {
+                response.push_str(&format!("\nReason: *{}*", reason));
+            }
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            error!("Error creating loan: {}", e);
+            ctx.say("Error requesting loan. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show your current loan
+#[poise::command(slash_command, rename = "status")]
+pub async fn loan_status(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let Some(loan) = data.database.get_open_loan(&guild_id, &user_id).await? else {
+        say_private(ctx, "You don't have a loan. Ask for one with `/loan request`.").await?;
+        return Ok(());
+    };
+
+    let mut response = format!(
+        "**Loan `#{}`** · {}\nBorrowed: {} · Repaid: {} / {} · Outstanding: **{}**",
+        loan.id, loan.status, loan.principal, loan.repaid, loan.total_due, loan.outstanding()
+    );
+    if loan.status == STATUS_ACTIVE {
+        if let Some(next_payment_at) = loan.next_payment_at {
+            response.push_str(&format!(
+                "\nNext payment of {} <t:{}:R>",
+                loan.installment.min(loan.outstanding()),
+                next_payment_at
+            ));
+        }
+    }
+    if loan.missed_payments > 0 {
+        response.push_str(&format!("\nMissed payments: {}", loan.missed_payments));
+    }
+
+    say_private(ctx, response).await?;
+    Ok(())
+}
+
+/// Pay off some or all of your loan early
+#[poise::command(slash_command, rename = "repay")]
+pub async fn loan_repay(
+    ctx: Context<'_>,
+    #[description = "Amount to repay (default: everything outstanding)"] amount: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let loan = match data.database.get_open_loan(&guild_id, &user_id).await? {
+        Some(loan) if loan.status == STATUS_ACTIVE || loan.status == STATUS_DEFAULTED => loan,
+        _ => {
+            ctx.say("You don't have a loan to repay.").await?;
+            return Ok(());
+        }
+    };
+
+    let amount = amount.unwrap_or(loan.outstanding());
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    if amount > loan.outstanding() {
+        ctx.say(format!("You only owe {} Slumcoins.", loan.outstanding())).await?;
+        return Ok(());
+    }
+
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < amount {
+        ctx.say(format!("UR BROKE BUB! You have {} Slumcoins", balance)).await?;
+        return Ok(());
+    }
+
+    match data.database.repay_loan(loan.id, &loans::repayment(&loan, amount)).await {
+        Ok(true) if amount == loan.outstanding() => {
+            ctx.say(format!("✅ <@{}> paid off loan `#{}`!", user_id, loan.id)).await?;
+        }
+        Ok(true) => {
+            ctx.say(format!(
+                "Repaid **{} Slumcoins** on loan `#{}`. {} still outstanding.",
+                amount, loan.id, loan.outstanding() - amount
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say("Your loan changed in the meantime. Check `/loan status` and try again.").await?;
+        }
+        Err(e) => {
+            error!("Error repaying loan: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Repayment failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List loan requests waiting for approval
+#[poise::command(slash_command, rename = "pending", check = "is_admin")]
+pub async fn loan_pending(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let pending = data.database.get_loans_by_status(&guild_id, STATUS_PENDING).await?;
+    if pending.is_empty() {
+        ctx.say("No loan requests are waiting.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Pending Loans**\n".to_string();
+    for loan in &pending {
+        response.push_str(&format!(
+            "`#{}` <@{}> · **{} Slumcoins** (repays {}) · requested <t:{}:R>",
+            loan.id, loan.borrower, loan.principal, loan.total_due, loan.requested_at
+        ));
+        if let Some(reason) = &loan.reason {
+            response.push_str(&format!(" · *{}*", reason));
+        }
+        response.push('\n');
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Approve a loan request and pay it out of the treasury
+#[poise::command(slash_command, rename = "approve", check = "is_admin")]
+pub async fn loan_approve(
+    ctx: Context<'_>,
+    #[description = "Loan number"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let admin_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let loan = match data.database.get_loan(id).await? {
+        Some(loan) if loan.guild_id == guild_id => loan,
+        _ => {
+            ctx.say(format!("No loan `#{}` in this server.", id)).await?;
+            return Ok(());
+        }
+    };
+
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if treasury_balance < loan.principal {
+        ctx.say(format!("The treasury only holds {} Slumcoins.", treasury_balance)).await?;
+        return Ok(());
+    }
+
+    let next_payment_at = Utc::now().timestamp() + loan.payment_interval_seconds;
+    match data.database.approve_loan(loan.id, &admin_id, next_payment_at, &loans::disbursement(&loan)).await {
+        Ok(true) => {
+            log_admin_action(ctx, "loan_approve", format!("loan #{}", loan.id), Some(loan.principal), None).await;
+            ctx.say(format!(
+                "✅ Loan `#{}` approved: **{} Slumcoins** paid to <@{}>. First payment of {} is due <t:{}:R>.",
+                loan.id, loan.principal, loan.borrower, loan.installment, next_payment_at
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Loan `#{}` is {}, only pending loans can be approved.", loan.id, loan.status)).await?;
+        }
+        Err(e) => {
+            error!("Error approving loan: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error approving loan. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Turn down a loan request
+#[poise::command(slash_command, rename = "deny", check = "is_admin")]
+pub async fn loan_deny(
+    ctx: Context<'_>,
+    #[description = "Loan number"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let admin_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let loan = match data.database.get_loan(id).await? {
+        Some(loan) if loan.guild_id == guild_id => loan,
+        _ => {
+            ctx.say(format!("No loan `#{}` in this server.", id)).await?;
+            return Ok(());
+        }
+    };
+
+    match data.database.deny_loan(loan.id, &admin_id).await {
+        Ok(true) => {
+            log_admin_action(ctx, "loan_deny", format!("loan #{}", loan.id), Some(loan.principal), None).await;
+            ctx.say(format!("Loan `#{}` for <@{}> was denied.", loan.id, loan.borrower)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Loan `#{}` is {}, only pending loans can be denied.", loan.id, loan.status)).await?;
+        }
+        Err(e) => {
+            error!("Error denying loan: {}", e);
+            ctx.say("Error denying loan. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
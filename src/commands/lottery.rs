@@ -0,0 +1,105 @@
+use tracing::error;
+
+use crate::{Context, Error, database::{DatabaseError, Transaction}};
+use crate::lottery::{self, LOTTERY_POT_ACCOUNT};
+use super::{gamble_refusal, not_frozen, database_error_message};
+
+/// Buy lottery tickets and check the pot
+#[poise::command(slash_command, category = "Games", guild_only, subcommands("lottery_buy", "lottery_info"))]
+pub async fn lottery(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Buy tickets for the current draw
+#[poise::command(slash_command, rename = "buy", check = "not_frozen")]
+pub async fn lottery_buy(
+    ctx: Context<'_>,
+    #[description = "Number of tickets to buy"] count: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if count <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let round = lottery::current_round(&data.database, &guild_id).await?;
+    let cost = round.ticket_price * count;
+
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < cost {
+        ctx.say(format!(
+            "UR BROKE BUB! {} tickets cost {} Slumcoins and you have {}",
+            count, cost, balance
+        )).await?;
+        return Ok(());
+    }
+
+    if let Some(reason) = gamble_refusal(&data.database, &user_id, cost).await? {
+        ctx.say(reason).await?;
+        return Ok(());
+    }
+
+    let transaction = Transaction::system(
+        &user_id,
+        LOTTERY_POT_ACCOUNT,
+        cost,
+        "lottery_ticket",
+        Some(format!("{} lottery ticket(s) for round {}", count, round.id)),
+    );
+
+    match data.database.buy_lottery_tickets(round.id, &transaction, count).await {
+        Ok(()) => {
+            let pot = data.database.get_lottery_pot(round.id).await?;
+            ctx.say(format!(
+                "Bought **{}** ticket(s) for {} Slumcoins\nPot: **{} Slumcoins** · Draw <t:{}:R>",
+                count, cost, pot, round.draw_at
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error buying lottery tickets: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Ticket purchase failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the pot and when the next draw is
+#[poise::command(slash_command, rename = "info")]
+pub async fn lottery_info(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let round = lottery::current_round(&data.database, &guild_id).await?;
+    let pot = data.database.get_lottery_pot(round.id).await?;
+    let tickets = data.database.get_lottery_tickets(round.id).await?;
+    let total_tickets: i64 = tickets.iter().map(|(_, count)| count).sum();
+    let own_tickets = tickets
+        .iter()
+        .find(|(discord_id, _)| *discord_id == user_id)
+        .map(|(_, count)| *count)
+        .unwrap_or(0);
+
+    ctx.say(format!(
+        "🎟️ **Lottery round {}**\n\
+        Pot: **{} Slumcoins**\n\
+        Ticket price: {} Slumcoins\n\
+        Tickets sold: {} (you hold {})\n\
+        Draw <t:{}:R>",
+        round.id, pot, round.ticket_price, total_tickets, own_tickets, round.draw_at
+    )).await?;
+
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::{DatabaseError, Marriage, Transaction};
+use crate::marriage::{self, JOINT_ACCOUNT, PROPOSAL_EXPIRY_SECONDS};
+use super::{confirm, database_error_message, not_frozen, say_private};
+
+const DAY_SECONDS: i64 = 86400;
+
+fn proposal_buttons(ctx_id: u64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}accept", ctx_id))
+            .label("I do")
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{}decline", ctx_id))
+            .label("No thanks")
+            .style(serenity::ButtonStyle::Secondary),
+    ])]
+}
+
+// The author's marriage in this guild, replying if they aren't married
+async fn own_marriage(ctx: Context<'_>) -> Result<Option<Marriage>, Error> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let marriage = ctx.data().database.get_marriage(&guild_id, &user_id).await?;
+    if marriage.is_none() {
+        say_private(ctx, "You're not married. Propose with `/marry`.").await?;
+    }
+    Ok(marriage)
+}
+
+/// Propose to someone
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn marry(
+    ctx: Context<'_>,
+    #[description = "Who to propose to"] user: serenity::User,
+    #[description = "Open a joint account you can both pay into and draw from (default: no)"] joint_account: Option<bool>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let proposer_id = ctx.author().id.to_string();
+    let partner_id = user.id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let joint_account = joint_account.unwrap_or(false);
+
+    if user.id == ctx.author().id || user.bot {
+        say_private(ctx, "Pick a real partner.").await?;
+        return Ok(());
+    }
+    for discord_id in [&proposer_id, &partner_id] {
+        if let Err(e) = data.database.require_user(discord_id).await {
+            say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+            return Ok(());
+        }
+    }
+    for (discord_id, who) in [(&proposer_id, "You're"), (&partner_id, "They're")] {
+        if data.database.get_marriage(&guild_id, discord_id).await?.is_some() {
+            say_private(ctx, format!("{} already married.", who)).await?;
+            return Ok(());
+        }
+    }
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(format!(
+            "💍 <@{}> is proposing to <@{}>{}!\nExpires <t:{}:R>.",
+            proposer_id,
+            partner_id,
+            if joint_account { ", with a joint account" } else { "" },
+            Utc::now().timestamp() + PROPOSAL_EXPIRY_SECONDS as i64
+        ))
+        .components(proposal_buttons(ctx_id))).await?;
+
+    let partner = user.id;
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == partner)
+        .timeout(std::time::Duration::from_secs(PROPOSAL_EXPIRY_SECONDS))
+        .await;
+
+    let Some(press) = press else {
+        reply.edit(ctx, poise::CreateReply::default()
+            .content(format!("💍 <@{}> didn't answer in time. The proposal expired.", partner_id))
+            .components(Vec::new())).await?;
+        return Ok(());
+    };
+
+    let respond = |content: String| {
+        serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(Vec::new()),
+        )
+    };
+
+    if press.data.custom_id.ends_with("decline") {
+        press.create_response(ctx.serenity_context(), respond(format!("💔 <@{}> said no.", partner_id))).await?;
+        return Ok(());
+    }
+
+    let content = match data.database.create_marriage(&guild_id, &proposer_id, &partner_id, joint_account, Utc::now().timestamp()).await {
+        Ok(Some(_)) => format!(
+            "💍 <@{}> and <@{}> are married!{}",
+            proposer_id,
+            partner_id,
+            if joint_account { " Share your joint account with `/marriage deposit`." } else { "" }
+        ),
+        Ok(None) => "💍 One of you got married in the meantime.".to_string(),
+        Err(e) => {
+            error!("Error creating marriage: {}", e);
+            "💍 Something went wrong at the altar. Please try again.".to_string()
+        }
+    };
+    press.create_response(ctx.serenity_context(), respond(content)).await?;
+    Ok(())
+}
+
+/// End your marriage, splitting any joint account between you
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn divorce(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some(married) = own_marriage(ctx).await? else {
+        return Ok(());
+    };
+    let partner_id = married.partner_of(&user_id).to_string();
+    let prompt = if married.joint_balance > 0 {
+        format!("Divorce <@{}>? Your joint account's {} Slumcoins get split between you.", partner_id, married.joint_balance)
+    } else {
+        format!("Divorce <@{}>?", partner_id)
+    };
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+
+    // The joint account may have moved while the prompt was up
+    let Some(married) = data.database.get_marriage(&married.guild_id, &user_id).await?.filter(|current| current.id == married.id) else {
+        return Ok(());
+    };
+    let (filer_share, partner_share) = marriage::split(married.joint_balance);
+    let splits: Vec<Transaction> = [(&user_id, filer_share), (&partner_id, partner_share)]
+        .into_iter()
+        .filter(|(_, share)| *share > 0)
+        .map(|(discord_id, share)| Transaction::system(JOINT_ACCOUNT, discord_id, share, "divorce_split", Some("Joint account split on divorce".to_string())))
+        .collect();
+
+    match data.database.divorce(married.id, Utc::now().timestamp(), &splits).await {
+        Ok(true) => {
+            let mut response = format!("💔 <@{}> and <@{}> are divorced.", user_id, partner_id);
+            if married.joint_balance > 0 {
+                response.push_str(&format!(
+                    " The joint account was split: {} Slumcoins to <@{}>, {} to <@{}>.",
+                    filer_share, user_id, partner_share, partner_id
+                ));
+            }
+            ctx.say(response).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, "The joint account changed in the meantime. Please try again.").await?;
+        }
+        Err(e) => {
+            error!("Error settling divorce {}: {}", married.id, e);
+            say_private(ctx, database_error_message(ctx, &e, "Error settling the divorce. Please try again.")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Your marriage and joint account
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("marriage_info", "marriage_deposit", "marriage_withdraw", "marriage_joint")
+)]
+pub async fn marriage(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show a marriage and its next anniversary
+#[poise::command(slash_command, rename = "info")]
+pub async fn marriage_info(
+    ctx: Context<'_>,
+    #[description = "User to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let target = user.unwrap_or_else(|| ctx.author().clone());
+    let target_id = target.id.to_string();
+    let author_id = ctx.author().id.to_string();
+
+    let Some(married) = data.database.get_marriage(&guild_id, &target_id).await? else {
+        say_private(ctx, format!("{} isn't married.", target.name)).await?;
+        return Ok(());
+    };
+
+    let now = Utc::now().timestamp();
+    let mut response = format!(
+        "💍 <@{}> and <@{}>, married <t:{}:D> ({} days)",
+        married.partner_a,
+        married.partner_b,
+        married.married_at,
+        (now - married.married_at) / DAY_SECONDS
+    );
+    if let Some((years, at)) = marriage::next_anniversary(married.married_at, now) {
+        response.push_str(&format!("\nAnniversary #{} <t:{}:R>", years, at));
+    }
+    // Only the couple sees what's in the joint account
+    if married.has_joint_account && (married.partner_a == author_id || married.partner_b == author_id) {
+        response.push_str(&format!("\nJoint account: {} Slumcoins", married.joint_balance));
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Move coins from your wallet into your joint account
+#[poise::command(slash_command, rename = "deposit", check = "not_frozen")]
+pub async fn marriage_deposit(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to put in"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+    let Some(married) = own_marriage(ctx).await? else {
+        return Ok(());
+    };
+    if !married.has_joint_account {
+        say_private(ctx, "You don't have a joint account. Open one with `/marriage joint`.").await?;
+        return Ok(());
+    }
+
+    let deposit = Transaction::system(&user_id, JOINT_ACCOUNT, amount, "joint_deposit", None);
+    match data.database.joint_deposit(married.id, &deposit).await {
+        Ok(true) => {
+            say_private(ctx, format!(
+                "💍 Put **{} Slumcoins** in your joint account. It now holds {} coins.",
+                amount, married.joint_balance + amount
+            )).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, "Your marriage ended in the meantime.").await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error depositing into joint account: {}", e);
+            }
+            say_private(ctx, database_error_message(ctx, &e, "Deposit failed. Please try again.")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Take coins out of your joint account
+#[poise::command(slash_command, rename = "withdraw", check = "not_frozen")]
+pub async fn marriage_withdraw(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to take out"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+    let Some(married) = own_marriage(ctx).await? else {
+        return Ok(());
+    };
+
+    let withdrawal = Transaction::system(JOINT_ACCOUNT, &user_id, amount, "joint_withdraw", None);
+    match data.database.joint_withdraw(married.id, &withdrawal).await {
+        Ok(true) => {
+            say_private(ctx, format!(
+                "💍 Took **{} Slumcoins** out of your joint account. It now holds {} coins.",
+                amount, married.joint_balance - amount
+            )).await?;
+        }
+        Ok(false) => {
+            let balance = data.database
+                .get_marriage(&married.guild_id, &user_id)
+                .await?
+                .map(|married| married.joint_balance)
+                .unwrap_or(0);
+            say_private(ctx, format!("Your joint account only holds {} Slumcoins.", balance)).await?;
+        }
+        Err(e) => {
+            error!("Error withdrawing from joint account: {}", e);
+            say_private(ctx, "Withdrawal failed. Please try again.").await?;
+        }
+    }
+    Ok(())
+}
+
+/// Open a joint account with your partner
+#[poise::command(slash_command, rename = "joint")]
+pub async fn marriage_joint(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+
+    let Some(married) = own_marriage(ctx).await? else {
+        return Ok(());
+    };
+    if ctx.data().database.open_joint_account(married.id).await? {
+        ctx.say(format!(
+            "💍 <@{}> opened a joint account with <@{}>. Pay in with `/marriage deposit`.",
+            user_id,
+            married.partner_of(&user_id)
+        )).await?;
+    } else {
+        say_private(ctx, "You already have a joint account.").await?;
+    }
+    Ok(())
+}
@@ -1,10 +1,158 @@
 pub mod admin;
+pub mod auctions;
+pub mod backups;
+pub mod bets;
+pub mod charity;
+pub mod charts;
+pub mod checkpoints;
+pub mod codes;
+pub mod economy;
+pub mod escrow;
+pub mod events;
+pub mod fishing;
+pub mod export;
+pub mod games;
+pub mod giveaways;
+pub mod heist;
+pub mod import;
+pub mod inventory;
+pub mod limits;
+pub mod loans;
+pub mod lottery;
+pub mod marriage;
+pub mod notifications;
+pub mod paper;
+pub mod payments;
+pub mod payroll;
+pub mod perks;
+pub mod privacy;
+pub mod profile;
+pub mod properties;
+pub mod referrals;
+pub mod seasons;
+pub mod shop;
+pub mod stocks;
+pub mod teams;
+pub mod treasury;
+pub mod trivia;
+pub mod triggers;
 pub mod user;
 pub mod utility;
+pub mod vault;
+pub mod wallet;
+pub mod webhooks;
 
 use std::env;
+use poise::serenity_prelude as serenity;
 
 use crate::{Context, Error};
+use crate::database::{AuditEntry, DatabaseError};
+
+/// Human-readable length of time such as `2m 0s` or `1d 4h 30m`
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, seconds % 60)
+    }
+}
+
+/// Previous/Next buttons for a paginated reply, keyed by the invoking context's id
+pub fn page_buttons(ctx_id: u64, page: u32, total_pages: u32) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}prev", ctx_id))
+            .label("Previous")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(page <= 1),
+        serenity::CreateButton::new(format!("{}next", ctx_id))
+            .label("Next")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(page >= total_pages),
+    ])]
+}
+
+/// Private reply to a button press that leaves the message as it is
+pub fn ephemeral_response(content: impl Into<String>) -> serenity::CreateInteractionResponse {
+    serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+    )
+}
+
+/// Reply about the user's own account, privately unless they've opted into public replies
+pub async fn say_private(ctx: Context<'_>, content: impl Into<String>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let private = match ctx.data().database.get_user_preferences(&user_id).await {
+        Ok(preferences) => preferences.private_replies,
+        Err(e) => {
+            tracing::error!("Error loading user preferences: {}", e);
+            true
+        }
+    };
+
+    ctx.send(poise::CreateReply::default().content(content).ephemeral(private)).await?;
+    Ok(())
+}
+
+/// What to tell the user about a database error they caused, or None for actual failures
+pub fn user_error_message(ctx: Context<'_>, e: &DatabaseError) -> Option<String> {
+    let is_author = |discord_id: &str| discord_id == ctx.author().id.to_string();
+    match e {
+        DatabaseError::InsufficientFunds(overdraft) if is_author(&overdraft.discord_id) => Some(format!(
+            "You only have {} Slumcoins but {} are needed. Nothing was moved.",
+            overdraft.balance, overdraft.required
+        )),
+        DatabaseError::InsufficientFunds(overdraft) => Some(format!(
+            "`{}` only has {} Slumcoins but {} are needed. Nothing was moved.",
+            overdraft.discord_id, overdraft.balance, overdraft.required
+        )),
+        DatabaseError::NotRegistered(discord_id) if is_author(discord_id) => {
+            Some("You're not registered! Use `/register` first.".to_string())
+        }
+        DatabaseError::NotRegistered(discord_id) => Some(format!("`{}` isn't registered.", discord_id)),
+        DatabaseError::Conflict(_) => Some("Something changed in the meantime. Please try again.".to_string()),
+        DatabaseError::Io(_) | DatabaseError::Sqlx(_) => None,
+    }
+}
+
+/// Reply for a failed database call: errors the user can act on are explained, anything else gets `fallback`
+pub fn database_error_message(ctx: Context<'_>, e: &DatabaseError, fallback: &str) -> String {
+    user_error_message(ctx, e).unwrap_or_else(|| fallback.to_string())
+}
+
+/// Space-separated mentions that fit in an embed field, ending with "and N more" when cut short
+pub fn mention_list(user_ids: &[String]) -> String {
+    let mut mentions = String::new();
+    for (i, user_id) in user_ids.iter().enumerate() {
+        let mention = format!("<@{}> ", user_id);
+        if mentions.len() + mention.len() > 1000 {
+            mentions.push_str(&format!("and {} more", user_ids.len() - i));
+            break;
+        }
+        mentions.push_str(&mention);
+    }
+    mentions
+}
+
+/// The voice channel the invoking user is currently connected to, if any
+pub fn author_voice_channel(ctx: Context<'_>) -> Option<serenity::ChannelId> {
+    ctx.guild()?.voice_states.get(&ctx.author().id).and_then(|vs| vs.channel_id)
+}
+
+/// Everyone currently connected to a voice channel in the invoking guild
+pub fn voice_channel_members(ctx: Context<'_>, channel_id: serenity::ChannelId) -> Vec<serenity::UserId> {
+    match ctx.guild() {
+        Some(guild) => guild
+            .voice_states
+            .values()
+            .filter(|vs| vs.channel_id == Some(channel_id))
+            .map(|vs| vs.user_id)
+            .collect(),
+        None => Vec::new(),
+    }
+}
 
 /// Check if user is an admin (bot owner, has admin role, or has ADMINISTRATOR permission)
 pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
@@ -23,10 +171,15 @@ pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
     if let Some(guild_id) = ctx.guild_id() {
         // Check if user has ADMINISTRATOR permission
         if let Some(member) = ctx.author_member().await {
-            if let Ok(perms) = member.permissions(&ctx.cache()) {
-                if perms.administrator() {
-                    return Ok(true);
-                }
+            // Guild-wide permissions, so it works the same in threads and uncached channels
+            let administrator = ctx.guild().is_some_and(|guild| {
+                guild.owner_id == user_id
+                    || member.roles.iter().chain([&guild_id.everyone_role()])
+                        .filter_map(|role_id| guild.roles.get(role_id))
+                        .any(|role| role.permissions.administrator())
+            });
+            if administrator {
+                return Ok(true);
             }
         }
         
@@ -35,10 +188,14 @@ pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
             .unwrap_or_else(|_| "Currency Admin".to_string());
             
         if let Ok(guild) = guild_id.to_partial_guild(&ctx.http()).await {
+            if guild.owner_id == user_id {
+                return Ok(true);
+            }
             if let Ok(member) = guild.member(&ctx.http(), user_id).await {
-                for role_id in &member.roles {
+                for role_id in member.roles.iter().chain([&guild_id.everyone_role()]) {
                     if let Some(role) = guild.roles.get(role_id) {
-                        if role.name == admin_role_name {
+                        // The guild isn't always cached, so Administrator is checked here too
+                        if role.name == admin_role_name || role.permissions.administrator() {
                             return Ok(true);
                         }
                     }
@@ -50,13 +207,255 @@ pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
     Ok(false)
 }
 
+/// How long `confirm` waits for the invoker to click a button
+const CONFIRM_TIMEOUT_SECONDS: u64 = 30;
+
+/// Ask the invoker to confirm an action with a button, returning whether they clicked Confirm in time
+pub async fn confirm(ctx: Context<'_>, prompt: impl Into<String>) -> Result<bool, Error> {
+    let prompt = prompt.into();
+    let ctx_id = ctx.id();
+    let author_id = ctx.author().id;
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}confirm", ctx_id))
+            .label("Confirm")
+            .style(serenity::ButtonStyle::Danger),
+        serenity::CreateButton::new(format!("{}cancel", ctx_id))
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Secondary),
+    ])];
+
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(format!("{}\nConfirm within {} seconds.", prompt, CONFIRM_TIMEOUT_SECONDS))
+        .components(buttons)
+        .ephemeral(true)).await?;
+
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == author_id)
+        .timeout(std::time::Duration::from_secs(CONFIRM_TIMEOUT_SECONDS))
+        .await;
+
+    let Some(press) = press else {
+        reply.edit(ctx, poise::CreateReply::default()
+            .content(format!("{}\n⌛ Timed out, nothing happened.", prompt))
+            .components(Vec::new())).await?;
+        return Ok(false);
+    };
+
+    let confirmed = press.data.custom_id.ends_with("confirm");
+    let outcome = if confirmed { "✅ Confirmed." } else { "❌ Cancelled, nothing happened." };
+    press.create_response(
+        ctx.serenity_context(),
+        serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(format!("{}\n{}", prompt, outcome))
+                .components(Vec::new()),
+        ),
+    ).await?;
+
+    Ok(confirmed)
+}
+
+/// Audit log entry for an action the invoking admin is taking in this guild
+pub fn admin_audit_entry(
+    ctx: Context<'_>,
+    action: &str,
+    target: impl Into<String>,
+    amount: Option<i64>,
+    reason: Option<String>,
+) -> AuditEntry {
+    AuditEntry {
+        id: 0,
+        guild_id: ctx.guild_id().map(|id| id.to_string()).unwrap_or_default(),
+        actor: ctx.author().id.to_string(),
+        action: action.to_string(),
+        target: target.into(),
+        amount,
+        reason: reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty()),
+        created_at: chrono::Utc::now().timestamp(),
+    }
+}
+
+/// Record an admin action that has already been carried out. A failed write is only logged, the action stands.
+pub async fn log_admin_action(
+    ctx: Context<'_>,
+    action: &str,
+    target: impl Into<String>,
+    amount: Option<i64>,
+    reason: Option<String>,
+) {
+    let entry = admin_audit_entry(ctx, action, target, amount, reason);
+    if let Err(e) = ctx.data().database.log_admin_action(&entry).await {
+        tracing::error!("Failed to record admin action {} by {}: {}", entry.action, entry.actor, e);
+    }
+}
+
+/// Raised by the `not_frozen` check so `on_error` can tell the user why the command was refused
+#[derive(Debug)]
+pub struct AccountFrozen;
+
+impl std::fmt::Display for AccountFrozen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "🧊 Your account is frozen. Sending, bidding and gambling are disabled until an admin unfreezes it.")
+    }
+}
+
+impl std::error::Error for AccountFrozen {}
+
+/// Command check that refuses sending, bidding and gambling for frozen accounts
+pub async fn not_frozen(ctx: Context<'_>) -> Result<bool, Error> {
+    if ctx.data().database.is_frozen(&ctx.author().id.to_string()).await? {
+        return Err(Box::new(AccountFrozen));
+    }
+    Ok(true)
+}
+
+/// Raised by the `cooldown` check so `on_error` can tell the user when to try again
+#[derive(Debug)]
+pub struct CooldownActive {
+    pub command: String,
+    pub remaining: std::time::Duration,
+}
+
+impl std::fmt::Display for CooldownActive {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "⏳ Slow down, you can use `/{}` again in {}s.", self.command, self.remaining.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for CooldownActive {}
+
+/// Command check for per-user cooldowns, configured per guild with `cooldown.<command>_seconds`.
+/// Admins are exempt. Put it after any other checks so refused invocations don't start a cooldown.
+pub async fn cooldown(ctx: Context<'_>) -> Result<bool, Error> {
+    enforce_cooldown(ctx, "seconds", false).await
+}
+
+/// Like `cooldown`, but one use starts the cooldown for the whole guild. Configured with
+/// `cooldown.<command>_guild_seconds`, for commands everyone joins in on like `/heist`.
+pub async fn guild_cooldown(ctx: Context<'_>) -> Result<bool, Error> {
+    enforce_cooldown(ctx, "guild_seconds", true).await
+}
+
+async fn enforce_cooldown(ctx: Context<'_>, suffix: &str, per_guild: bool) -> Result<bool, Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+
+    // Subcommands share their parent's setting, e.g. `/baltop show` uses `cooldown.baltop_seconds`
+    let command = ctx.command().qualified_name.split(' ').next().unwrap_or_default().to_string();
+    let key = format!("cooldown.{}_{}", command, suffix);
+    let seconds = crate::config::get_i64(&ctx.data().database, &guild_id.to_string(), &key).await?;
+    if seconds <= 0 {
+        return Ok(true);
+    }
+
+    let duration = Some(std::time::Duration::from_secs(seconds as u64));
+    let config = if per_guild {
+        poise::CooldownConfig { guild: duration, ..Default::default() }
+    } else {
+        poise::CooldownConfig { member: duration, ..Default::default() }
+    };
+    let remaining = ctx.command()
+        .cooldowns
+        .lock()
+        .map_err(|_| "cooldown tracker poisoned")?
+        .remaining_cooldown(ctx.cooldown_context(), &config);
+
+    if let Some(remaining) = remaining {
+        if !is_admin(ctx).await? {
+            return Err(Box::new(CooldownActive { command, remaining }));
+        }
+    }
+
+    if let Ok(mut cooldowns) = ctx.command().cooldowns.lock() {
+        cooldowns.start_cooldown(ctx.cooldown_context());
+    }
+    Ok(true)
+}
+
 /// Check if user can register others (stricter admin check)
 pub async fn can_register_others(ctx: Context<'_>) -> Result<bool, Error> {
     // For now, same as admin check, but could be made more restrictive
     is_admin(ctx).await
 }
 
+/// Autocomplete the caller's most frequent and most recent transfer partners
+pub async fn autocomplete_counterparty(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+    let data = ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let partial = partial.to_lowercase();
+
+    match data.counterparties.get(&data.database, &user_id).await {
+        Ok(counterparties) => counterparties
+            .into_iter()
+            .filter(|(_, username)| username.to_lowercase().contains(&partial))
+            .map(|(discord_id, username)| serenity::AutocompleteChoice::new(username, discord_id))
+            .collect(),
+        Err(e) => {
+            tracing::error!("Error loading counterparties: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Resolve a target given either as a user option or as a counterparty picked from autocomplete
+pub async fn resolve_target_user(
+    ctx: Context<'_>,
+    user: Option<serenity::User>,
+    recent: Option<String>,
+) -> Result<Option<serenity::User>, Error> {
+    if let Some(user) = user {
+        return Ok(Some(user));
+    }
+
+    let Some(user_id) = recent.and_then(|id| id.parse::<u64>().ok()).filter(|id| *id != 0) else {
+        return Ok(None);
+    };
+
+    Ok(serenity::UserId::new(user_id).to_user(ctx).await.ok())
+}
+
 // Re-export all commands
 pub use admin::*;
+pub use auctions::*;
+pub use backups::*;
+pub use bets::*;
+pub use charity::*;
+pub use charts::*;
+pub use checkpoints::*;
+pub use codes::*;
+pub use economy::*;
+pub use escrow::*;
+pub use events::*;
+pub use fishing::*;
+pub use export::*;
+pub use games::*;
+pub use giveaways::*;
+pub use heist::*;
+pub use import::*;
+pub use inventory::*;
+pub use limits::*;
+pub use loans::*;
+pub use lottery::*;
+pub use marriage::*;
+pub use notifications::*;
+pub use paper::*;
+pub use payments::*;
+pub use payroll::*;
+pub use perks::*;
+pub use privacy::*;
+pub use profile::*;
+pub use properties::*;
+pub use referrals::*;
+pub use seasons::*;
+pub use shop::*;
+pub use stocks::*;
+pub use teams::*;
+pub use treasury::*;
+pub use trivia::*;
+pub use triggers::*;
 pub use user::*;
 pub use utility::*;
+pub use vault::*;
+pub use wallet::*;
+pub use webhooks::*;
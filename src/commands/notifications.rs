@@ -0,0 +1,37 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::bidding::NOTIFY_OUTBID;
+
+/// Choose which DMs the bot sends you
+#[poise::command(slash_command, category = "User", guild_only, subcommands("notifications_outbid"))]
+pub async fn notifications(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn outbid DMs on or off
+#[poise::command(slash_command, rename = "outbid")]
+pub async fn notifications_outbid(
+    ctx: Context<'_>,
+    #[description = "DM me when someone outbids me in an auction"] enabled: bool,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    match data.database.set_notification_opt_out(&user_id, NOTIFY_OUTBID, !enabled).await {
+        Ok(()) => {
+            let response = if enabled {
+                "You'll get a DM when someone outbids you."
+            } else {
+                "You won't get DMs when someone outbids you."
+            };
+            ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+        }
+        Err(e) => {
+            error!("Error updating notification settings: {}", e);
+            ctx.say("Error updating notification settings.").await?;
+        }
+    }
+
+    Ok(())
+}
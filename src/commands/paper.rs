@@ -0,0 +1,216 @@
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, Transaction, SYSTEM_ACCOUNT};
+use crate::paper::{self, Asset};
+use super::{database_error_message, gamble_refusal, not_frozen, say_private};
+
+const OFFLINE_MESSAGE: &str = "Live prices aren't available right now, so paper trading is paused.";
+
+/// Trade Slumcoins against real BTC and ETH prices
+#[poise::command(
+    slash_command,
+    category = "Games",
+    guild_only,
+    subcommands("paper_long", "paper_short", "paper_close", "paper_positions", "paper_prices")
+)]
+pub async fn paper(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn open_position(ctx: Context<'_>, asset: Asset, direction: &str, amount: i64, leverage: Option<i64>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let leverage = leverage.unwrap_or(1);
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    let max_leverage = config::get_i64(&data.database, &guild_id, "paper.max_leverage").await?.max(1);
+    if !(1..=max_leverage).contains(&leverage) {
+        ctx.say(format!("Leverage goes from 1x to {}x here.", max_leverage)).await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&user_id).await {
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+    if let Some(reason) = gamble_refusal(&data.database, &user_id, amount).await? {
+        ctx.say(reason).await?;
+        return Ok(());
+    }
+    let Some(price_cents) = paper::live_price(&data.database, asset).await? else {
+        ctx.say(OFFLINE_MESSAGE).await?;
+        return Ok(());
+    };
+
+    let stake = Transaction::system(
+        &user_id,
+        SYSTEM_ACCOUNT,
+        amount,
+        "paper_stake",
+        Some(format!("Paper {} {} at {}", direction, asset, paper::format_usd(price_cents))),
+    );
+    match data.database.open_paper_position(&guild_id, asset.key(), direction, leverage, price_cents, &stake).await {
+        Ok(position_id) => {
+            let mut response = format!(
+                "📊 Opened paper position **#{}**: {} **{}** with {} Slumcoins at {}x, entry {}.",
+                position_id, direction, asset, amount, leverage, paper::format_usd(price_cents)
+            );
+            if let Some(position) = data.database.get_paper_position(position_id).await? {
+                if let Some(liquidation) = paper::liquidation_price(&position) {
+                    response.push_str(&format!("\nLiquidated at {}.", paper::format_usd(liquidation)));
+                }
+            }
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error opening paper position: {}", e);
+            }
+            ctx.say(database_error_message(ctx, &e, "Error opening the position. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Bet that the price goes up
+#[poise::command(slash_command, rename = "long", check = "not_frozen")]
+pub async fn paper_long(
+    ctx: Context<'_>,
+    #[description = "Asset to trade"] asset: Asset,
+    #[description = "Slumcoins to put in"] amount: i64,
+    #[description = "Leverage multiplier (default: 1x)"] leverage: Option<i64>,
+) -> Result<(), Error> {
+    open_position(ctx, asset, "long", amount, leverage).await
+}
+
+/// Bet that the price goes down
+#[poise::command(slash_command, rename = "short", check = "not_frozen")]
+pub async fn paper_short(
+    ctx: Context<'_>,
+    #[description = "Asset to trade"] asset: Asset,
+    #[description = "Slumcoins to put in"] amount: i64,
+    #[description = "Leverage multiplier (default: 1x)"] leverage: Option<i64>,
+) -> Result<(), Error> {
+    open_position(ctx, asset, "short", amount, leverage).await
+}
+
+/// Close a position at the live price and collect what it's worth
+#[poise::command(slash_command, rename = "close", check = "not_frozen")]
+pub async fn paper_close(
+    ctx: Context<'_>,
+    #[description = "Position number, shown by /paper positions"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let position = data.database
+        .get_paper_position(id)
+        .await?
+        .filter(|position| position.discord_id == user_id && position.guild_id == guild_id);
+    let Some(position) = position else {
+        ctx.say(format!("You have no paper position #{} here.", id)).await?;
+        return Ok(());
+    };
+    if position.status != "open" {
+        ctx.say(format!("Position #{} is already {}.", id, position.status)).await?;
+        return Ok(());
+    }
+    let Some(asset) = Asset::ALL.into_iter().find(|asset| asset.key() == position.asset) else {
+        return Ok(());
+    };
+    let Some(price_cents) = paper::live_price(&data.database, asset).await? else {
+        ctx.say(OFFLINE_MESSAGE).await?;
+        return Ok(());
+    };
+
+    let value = paper::position_value(&position, price_cents);
+    let payout = (value > 0).then(|| Transaction::system(
+        SYSTEM_ACCOUNT,
+        &user_id,
+        value,
+        "paper_payout",
+        Some(format!("Closed paper position #{} at {}", id, paper::format_usd(price_cents))),
+    ));
+    let status = if value > 0 { "closed" } else { "liquidated" };
+
+    match data.database.close_paper_position(id, status, price_cents, Utc::now().timestamp(), payout.as_ref()).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "📊 Closed #{} ({} {}) at {}: **{} Slumcoins** back on a {} stake ({:+}).",
+                id, position.direction, asset, paper::format_usd(price_cents), value, position.stake, value - position.stake
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("Position #{} was settled in the meantime.", id)).await?;
+        }
+        Err(e) => {
+            error!("Error closing paper position {}: {}", id, e);
+            ctx.say(database_error_message(ctx, &e, "Error closing the position. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show your open positions and what they're worth now
+#[poise::command(slash_command, rename = "positions")]
+pub async fn paper_positions(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let positions = data.database.get_open_paper_positions(Some((&guild_id, &user_id))).await?;
+    if positions.is_empty() {
+        say_private(ctx, "You have no open paper positions. Open one with `/paper long` or `/paper short`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("📊 **Your paper positions**\n");
+    for position in &positions {
+        let asset = Asset::ALL.into_iter().find(|asset| asset.key() == position.asset);
+        let price = match asset {
+            Some(asset) => paper::live_price(&data.database, asset).await?,
+            None => None,
+        };
+        let value = match price {
+            Some(price_cents) => format!("worth {} now", paper::position_value(position, price_cents)),
+            None => "price unavailable".to_string(),
+        };
+        response.push_str(&format!(
+            "• **#{}** {} {} · {} Slumcoins at {}x from {} · {}\n",
+            position.id,
+            position.direction,
+            position.asset.to_uppercase(),
+            position.stake,
+            position.leverage,
+            paper::format_usd(position.entry_price_cents),
+            value
+        ));
+    }
+
+    say_private(ctx, response).await?;
+    Ok(())
+}
+
+/// Show the live prices positions settle against
+#[poise::command(slash_command, rename = "prices")]
+pub async fn paper_prices(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    let mut response = String::from("📊 **Live prices**\n");
+    for asset in Asset::ALL {
+        match paper::live_price(&data.database, asset).await? {
+            Some(price_cents) => response.push_str(&format!("• **{}** {}\n", asset, paper::format_usd(price_cents))),
+            None => response.push_str(&format!("• **{}** unavailable\n", asset)),
+        }
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::activity::MAX_WINDOW_SECONDS;
+use crate::ledger::{self, FeeSchedule, LedgerError};
+use crate::payments;
+use super::{mention_list, not_frozen};
+
+/// Ask someone to pay you with a Pay button
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn request(
+    ctx: Context<'_>,
+    #[description = "User you're requesting coins from"] user: serenity::User,
+    #[description = "Amount of coins to request"] amount: i64,
+    #[description = "What it's for"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let requester_id = ctx.author().id.to_string();
+    let payer_id = user.id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let reason = reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    if requester_id == payer_id || user.bot {
+        ctx.say("why?").await?;
+        return Ok(());
+    }
+
+    for (discord_id, who) in [(&requester_id, "You're"), (&payer_id, "They're")] {
+        match data.database.get_user(discord_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                ctx.say(format!("{} not registered! Use `/register` first.", who)).await?;
+                return Ok(());
+            }
+            Err(e) => {
+                error!("Database error: {}", e);
+                ctx.say("Database error occurred.").await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let expiry_hours = config::get_i64(&data.database, &guild_id, "request.expiry_hours").await?.max(1);
+    let expires_at = Utc::now().timestamp() + expiry_hours * 3600;
+
+    let request_id = match data.database
+        .create_payment_request(&guild_id, &requester_id, &payer_id, amount, reason.as_deref(), expires_at)
+        .await
+    {
+        Ok(request_id) => request_id,
+        Err(e) => {
+            error!("Error creating payment request: {}", e);
+            ctx.say("Error creating payment request. Please try again.").await?;
+            return Ok(());
+        }
+    };
+
+    let Some(request) = data.database.get_payment_request(request_id).await? else {
+        ctx.say("Error creating payment request. Please try again.").await?;
+        return Ok(());
+    };
+
+    ctx.send(poise::CreateReply::default()
+        .content(format!("<@{}>", payer_id))
+        .embed(payments::request_embed(&request, &format!("⏳ Pending · expires <t:{}:R>", expires_at)))
+        .components(payments::request_buttons(request_id))).await?;
+
+    Ok(())
+}
+
+/// Split coins evenly between everyone who's chatted here recently
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
+pub async fn rain(
+    ctx: Context<'_>,
+    #[description = "Total coins to split between recent chatters"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if amount <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+
+    let window_minutes = config::get_i64(&data.database, &guild_id, "rain.window_minutes")
+        .await?
+        .clamp(1, MAX_WINDOW_SECONDS / 60);
+    let since = Utc::now().timestamp() - window_minutes * 60;
+
+    // Only registered chatters can receive coins; the tracker never records bots
+    let mut recipients = Vec::new();
+    for chatter in data.activity.active_since(ctx.channel_id(), since).await {
+        let chatter = chatter.to_string();
+        if chatter != user_id && data.database.get_user(&chatter).await?.is_some() {
+            recipients.push(chatter);
+        }
+    }
+
+    if recipients.is_empty() {
+        ctx.say(format!("Nobody else has chatted here in the last {} minutes.", window_minutes)).await?;
+        return Ok(());
+    }
+    if amount < recipients.len() as i64 {
+        ctx.say(format!(
+            "{} people are around, so you need to rain at least {} Slumcoins.",
+            recipients.len(),
+            recipients.len()
+        )).await?;
+        return Ok(());
+    }
+
+    match ledger::check_daily_limit(&data.database, &guild_id, &user_id, amount).await {
+        Ok(()) => {}
+        Err(LedgerError::DailyLimitExceeded { limit, remaining }) => {
+            ctx.say(format!("That's over your daily limit of {} Slumcoins. You can send {} more today.", limit, remaining)).await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let fees = FeeSchedule::for_guild(&data.database, &guild_id).await?;
+    let message = Some(format!("Rain from {}", ctx.author().name));
+    match ledger::execute_rain(&data.database, &data.crypto, &user_id, &recipients, amount, fees, message).await {
+        Ok(rain) => {
+            let mut ids: Vec<&str> = vec![&user_id];
+            ids.extend(rain.recipients.iter().map(String::as_str));
+            data.counterparties.invalidate(&ids).await;
+
+            let mut footer = format!("New balance: {} Slumcoins", rain.sender_balance_after);
+            if rain.fee > 0 {
+                footer = format!("Fee: {} Slumcoins · {}", rain.fee, footer);
+            }
+
+            let embed = serenity::CreateEmbed::new()
+                .title("🌧️ It's raining Slumcoins!")
+                .description(format!(
+                    "<@{}> made it rain **{} Slumcoins** on {} people",
+                    user_id,
+                    rain.total(),
+                    rain.recipients.len()
+                ))
+                .field("Each got", format!("{} Slumcoins", rain.share), true)
+                .field("Recipients", mention_list(&rain.recipients), false)
+                .footer(serenity::CreateEmbedFooter::new(footer));
+            ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        }
+        Err(LedgerError::NotRegistered(id)) if id == user_id => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+        }
+        Err(LedgerError::InsufficientFunds { balance, .. }) => {
+            ctx.say(format!("UR BROKE BUB! You have {} Slumcoins", balance)).await?;
+        }
+        Err(e) => {
+            error!("Error making it rain: {}", e);
+            ctx.say("Rain failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
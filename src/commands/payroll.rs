@@ -0,0 +1,159 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::Payroll;
+use super::{format_duration, is_admin, log_admin_action};
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum PayInterval {
+    #[name = "hourly"]
+    Hourly,
+    #[name = "daily"]
+    Daily,
+    #[name = "weekly"]
+    Weekly,
+}
+
+impl PayInterval {
+    const ALL: [PayInterval; 3] = [PayInterval::Hourly, PayInterval::Daily, PayInterval::Weekly];
+
+    fn key(&self) -> &'static str {
+        match self {
+            PayInterval::Hourly => "hourly",
+            PayInterval::Daily => "daily",
+            PayInterval::Weekly => "weekly",
+        }
+    }
+
+    fn seconds(&self) -> i64 {
+        match self {
+            PayInterval::Hourly => 3600,
+            PayInterval::Daily => 86400,
+            PayInterval::Weekly => 604800,
+        }
+    }
+}
+
+/// Pay members of a role a recurring salary
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("payroll_set", "payroll_remove", "payroll_list")
+)]
+pub async fn payroll(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Pay everyone with a role a salary on a schedule
+#[poise::command(slash_command, rename = "set")]
+pub async fn payroll_set(
+    ctx: Context<'_>,
+    #[description = "Role whose members get paid"] role: serenity::Role,
+    #[description = "Coins each member gets per payout"] amount: i64,
+    #[description = "How often to pay out"] interval: PayInterval,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let payroll = Payroll {
+        guild_id,
+        role_id: role.id.to_string(),
+        amount,
+        interval_seconds: interval.seconds(),
+        next_payout_at: Utc::now().timestamp() + interval.seconds(),
+        created_by: ctx.author().id.to_string(),
+    };
+
+    match data.database.set_payroll(&payroll).await {
+        Ok(()) => {
+            log_admin_action(ctx, "payroll_set", format!("role {}", role.id), Some(amount), Some(format!("paid {}", interval.key()))).await;
+            let response = format!(
+                "💼 Members of <@&{}> will be paid **{} Slumcoins** {}, starting <t:{}:R>.\n\
+                Members who aren't registered are skipped.",
+                role.id, amount, interval.key(), payroll.next_payout_at
+            );
+            ctx.send(poise::CreateReply::default()
+                .content(response)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => {
+            error!("Error saving payroll: {}", e);
+            ctx.say("Error saving payroll.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stop paying a role's salary
+#[poise::command(slash_command, rename = "remove")]
+pub async fn payroll_remove(
+    ctx: Context<'_>,
+    #[description = "Role to stop paying"] role: serenity::Role,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.remove_payroll(&guild_id, &role.id.to_string()).await {
+        Ok(true) => {
+            log_admin_action(ctx, "payroll_remove", format!("role {}", role.id), None, None).await;
+            ctx.say(format!("Stopped paying **{}**.", role.name)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("**{}** isn't on the payroll.", role.name)).await?;
+        }
+        Err(e) => {
+            error!("Error removing payroll: {}", e);
+            ctx.say("Error removing payroll.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show every role on the payroll
+#[poise::command(slash_command, rename = "list")]
+pub async fn payroll_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let payrolls = match data.database.get_payrolls(&guild_id).await {
+        Ok(payrolls) => payrolls,
+        Err(e) => {
+            error!("Error loading payroll: {}", e);
+            ctx.say("Error loading payroll.").await?;
+            return Ok(());
+        }
+    };
+
+    if payrolls.is_empty() {
+        ctx.say("No roles are on the payroll. Add one with `/payroll set`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("**Payroll**\n");
+    for payroll in payrolls {
+        let schedule = match PayInterval::ALL.iter().find(|i| i.seconds() == payroll.interval_seconds) {
+            Some(interval) => interval.key().to_string(),
+            None => format!("every {}", format_duration(payroll.interval_seconds as u64)),
+        };
+        response.push_str(&format!(
+            "• <@&{}> - **{}** coins {} · next payout <t:{}:R>\n",
+            payroll.role_id, payroll.amount, schedule, payroll.next_payout_at
+        ));
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, ShopItem, TopicPerk, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::perks::{self, Perk, PerkError, MAX_NICKNAME_LENGTH, MAX_TOPIC_LENGTH, TOPIC_SECONDS};
+use super::{database_error_message, not_frozen, say_private};
+
+const CONSENT_SECONDS: u64 = 60;
+
+// The shop item selling a perk here, replying if there's none or the author can't afford it
+async fn perk_item(ctx: Context<'_>, perk: Perk) -> Result<Option<ShopItem>, Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(None);
+    }
+    let Some(item) = data.database.get_perk_item(&guild_id, perk.key()).await? else {
+        say_private(ctx, format!("The {} perk isn't for sale here. An admin can sell it with `/shop perk`.", perk.key())).await?;
+        return Ok(None);
+    };
+    if item.stock == Some(0) {
+        say_private(ctx, format!("**{}** is sold out.", item.name)).await?;
+        return Ok(None);
+    }
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < item.price {
+        say_private(ctx, format!("UR BROKE BUB! **{}** costs {} Slumcoins and you have {}", item.name, item.price, balance)).await?;
+        return Ok(None);
+    }
+    Ok(Some(item))
+}
+
+// Pay for a perk, replying if the purchase didn't go through
+async fn charge(ctx: Context<'_>, item: &ShopItem) -> Result<bool, Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let transaction = Transaction::system(&user_id, TREASURY_ACCOUNT, item.price, "purchase", Some(format!("Bought {}", item.name)));
+    match data.database.purchase_item(item, &transaction).await {
+        Ok(true) => {}
+        Ok(false) => {
+            say_private(ctx, format!("**{}** is sold out.", item.name)).await?;
+            return Ok(false);
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error purchasing perk: {}", e);
+            }
+            say_private(ctx, database_error_message(ctx, &e, "Purchase failed. Please try again.")).await?;
+            return Ok(false);
+        }
+    }
+
+    // Perks are used up on the spot rather than kept
+    data.database.remove_inventory_item(&user_id, item.id, 1).await?;
+    Ok(true)
+}
+
+// Undo a charge: give back what the perk cost and the unit of stock it used up
+async fn refund_purchase(ctx: Context<'_>, item: &ShopItem) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let refund = Transaction::system(TREASURY_ACCOUNT, &user_id, item.price, "refund", Some(format!("Refund for {}", item.name)));
+    data.database.apply_transaction(&refund).await?;
+    data.database.restock_item(item.id, 1).await?;
+    Ok(())
+}
+
+// Refund a perk that couldn't be fulfilled, and say why
+async fn refund(ctx: Context<'_>, perk: Perk, item: &ShopItem, e: PerkError) -> Result<(), Error> {
+    error!("Failed to fulfil the {} perk for {}: {}", perk.key(), ctx.author().id, e);
+    refund_purchase(ctx, item).await?;
+
+    let response = match e {
+        PerkError::MissingPermissions => format!(
+            "I'm not allowed to do that (I need **{}**). Your {} Slumcoins were refunded, please let an admin know.",
+            perk.permission(), item.price
+        ),
+        PerkError::Http(_) => format!("I couldn't fulfil **{}**. Your {} Slumcoins were refunded.", item.name, item.price),
+    };
+    say_private(ctx, response).await?;
+    Ok(())
+}
+
+// A message ID, or a message link pointing into `channel_id`
+fn parse_message(input: &str, channel_id: serenity::ChannelId) -> Option<serenity::MessageId> {
+    let mut parts = input.trim().trim_end_matches('/').rsplit('/');
+    let message_id = parts.next()?.parse::<u64>().ok().filter(|id| *id != 0)?;
+    if let Some(channel) = parts.next() {
+        if channel.parse::<u64>().ok()? != channel_id.get() {
+            return None;
+        }
+    }
+    Some(serenity::MessageId::new(message_id))
+}
+
+// Ask `target` whether they agree to something, returning their answer
+async fn consent(ctx: Context<'_>, target: serenity::UserId, prompt: String) -> Result<bool, Error> {
+    let ctx_id = ctx.id();
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}accept", ctx_id))
+            .label("Go ahead")
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{}decline", ctx_id))
+            .label("No thanks")
+            .style(serenity::ButtonStyle::Secondary),
+    ])];
+
+    let reply = ctx.send(poise::CreateReply::default()
+        .content(format!("{}\nExpires <t:{}:R>.", prompt, Utc::now().timestamp() + CONSENT_SECONDS as i64))
+        .components(buttons)
+        .allowed_mentions(serenity::CreateAllowedMentions::new().users(vec![target]))).await?;
+
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()) && press.user.id == target)
+        .timeout(std::time::Duration::from_secs(CONSENT_SECONDS))
+        .await;
+
+    let Some(press) = press else {
+        reply.edit(ctx, poise::CreateReply::default()
+            .content(format!("{}\n⌛ <@{}> didn't answer in time.", prompt, target))
+            .components(Vec::new())).await?;
+        return Ok(false);
+    };
+
+    let accepted = press.data.custom_id.ends_with("accept");
+    let outcome = if accepted { "✅ They agreed." } else { "❌ They said no." };
+    press.create_response(
+        ctx.serenity_context(),
+        serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(format!("{}\n{}", prompt, outcome))
+                .components(Vec::new()),
+        ),
+    ).await?;
+    Ok(accepted)
+}
+
+/// Buy perks the bot carries out for you
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("perk_nickname", "perk_pin", "perk_topic")
+)]
+pub async fn perk(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Buy a nickname for yourself or, if they agree, another member
+#[poise::command(slash_command, rename = "nickname", check = "not_frozen")]
+pub async fn perk_nickname(
+    ctx: Context<'_>,
+    #[description = "The new nickname"] nickname: String,
+    #[description = "Member to rename (default: you)"] member: Option<serenity::User>,
+) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let author = ctx.author().clone();
+    let target = member.unwrap_or_else(|| author.clone());
+    let nickname = nickname.trim().to_string();
+
+    if nickname.is_empty() || nickname.chars().count() > MAX_NICKNAME_LENGTH {
+        say_private(ctx, format!("Nicknames are 1 to {} characters.", MAX_NICKNAME_LENGTH)).await?;
+        return Ok(());
+    }
+    if target.bot {
+        say_private(ctx, "Bots keep their names.").await?;
+        return Ok(());
+    }
+    let Some(item) = perk_item(ctx, Perk::Nickname).await? else {
+        return Ok(());
+    };
+
+    // Renaming someone else takes their say-so
+    if target.id != author.id {
+        let prompt = format!(
+            "✏️ <@{}> wants to rename <@{}> to **{}** for {} Slumcoins. <@{}>, is that okay?",
+            author.id, target.id, nickname, item.price, target.id
+        );
+        if !consent(ctx, target.id, prompt).await? {
+            return Ok(());
+        }
+    }
+
+    if !charge(ctx, &item).await? {
+        return Ok(());
+    }
+    let reason = format!("Nickname perk bought by {}", author.name);
+    match perks::set_nickname(ctx.http(), guild_id, target.id, &nickname, &reason).await {
+        Ok(()) => {
+            let response = if target.id == author.id {
+                format!("✏️ <@{}> is now **{}**.", author.id, nickname)
+            } else {
+                format!("✏️ <@{}> renamed <@{}> to **{}**.", author.id, target.id, nickname)
+            };
+            ctx.send(poise::CreateReply::default()
+                .content(response)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => refund(ctx, Perk::Nickname, &item, e).await?,
+    }
+    Ok(())
+}
+
+/// Buy a pin for a message in this channel
+#[poise::command(slash_command, rename = "pin", check = "not_frozen")]
+pub async fn perk_pin(
+    ctx: Context<'_>,
+    #[description = "Link or ID of a message in this channel"] message: String,
+) -> Result<(), Error> {
+    let channel_id = ctx.channel_id();
+
+    let Some(message_id) = parse_message(&message, channel_id) else {
+        say_private(ctx, "That's not a message in this channel. Paste its link or ID.").await?;
+        return Ok(());
+    };
+    let message = match channel_id.message(ctx.http(), message_id).await {
+        Ok(message) => message,
+        Err(_) => {
+            say_private(ctx, "Couldn't find that message in this channel.").await?;
+            return Ok(());
+        }
+    };
+    if message.pinned {
+        say_private(ctx, "That message is already pinned.").await?;
+        return Ok(());
+    }
+    let Some(item) = perk_item(ctx, Perk::Pin).await? else {
+        return Ok(());
+    };
+
+    if !charge(ctx, &item).await? {
+        return Ok(());
+    }
+    let reason = format!("Pin perk bought by {}", ctx.author().name);
+    match perks::pin(ctx.http(), channel_id, message_id, &reason).await {
+        Ok(()) => {
+            ctx.send(poise::CreateReply::default()
+                .content(format!("📌 <@{}> pinned {}", ctx.author().id, message.link()))
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => refund(ctx, Perk::Pin, &item, e).await?,
+    }
+    Ok(())
+}
+
+/// Set the auction channel's topic for a day
+#[poise::command(slash_command, rename = "topic", check = "not_frozen")]
+pub async fn perk_topic(
+    ctx: Context<'_>,
+    #[description = "The new topic"] topic: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let topic = topic.trim().to_string();
+
+    if topic.is_empty() || topic.chars().count() > MAX_TOPIC_LENGTH {
+        say_private(ctx, format!("Topics are 1 to {} characters.", MAX_TOPIC_LENGTH)).await?;
+        return Ok(());
+    }
+    let channel_id = config::get(&data.database, &guild_id, "auction.channel_id").await?;
+    let Ok(channel_id) = channel_id.parse::<u64>() else {
+        say_private(ctx, "This server has no auction channel set (`auction.channel_id`).").await?;
+        return Ok(());
+    };
+    let channel = serenity::ChannelId::new(channel_id);
+
+    if let Some(current) = data.database.get_topic_perk(&channel.to_string()).await? {
+        say_private(ctx, format!("<@{}> already bought the topic of <#{}> until <t:{}:f>.", current.discord_id, channel, current.expires_at)).await?;
+        return Ok(());
+    }
+    let Some(item) = perk_item(ctx, Perk::Topic).await? else {
+        return Ok(());
+    };
+    let original_topic = match perks::current_topic(ctx.http(), channel).await {
+        Ok(original_topic) => original_topic,
+        Err(e) => {
+            error!("Failed to read the topic of channel {}: {}", channel, e);
+            say_private(ctx, format!("I can't see <#{}> right now. Please let an admin know.", channel)).await?;
+            return Ok(());
+        }
+    };
+
+    if !charge(ctx, &item).await? {
+        return Ok(());
+    }
+    let bought = TopicPerk {
+        id: 0,
+        guild_id,
+        channel_id: channel.to_string(),
+        discord_id: user_id.clone(),
+        topic: topic.clone(),
+        original_topic,
+        expires_at: Utc::now().timestamp() + TOPIC_SECONDS,
+    };
+    let Some(perk_id) = data.database.claim_topic_perk(&bought).await? else {
+        refund_purchase(ctx, &item).await?;
+        say_private(ctx, format!("Someone bought the topic of <#{}> in the meantime. Your {} Slumcoins were refunded.", channel, item.price)).await?;
+        return Ok(());
+    };
+
+    let reason = format!("Topic perk bought by {}", ctx.author().name);
+    match perks::set_topic(ctx.http(), channel, &topic, &reason).await {
+        Ok(()) => {
+            ctx.send(poise::CreateReply::default()
+                .content(format!("📝 <@{}> set the topic of <#{}> until <t:{}:f>:\n> {}", user_id, channel, bought.expires_at, topic))
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => {
+            data.database.remove_topic_perk(perk_id).await?;
+            refund(ctx, Perk::Topic, &item, e).await?;
+        }
+    }
+    Ok(())
+}
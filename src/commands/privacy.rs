@@ -0,0 +1,90 @@
+use tracing::error;
+
+use crate::{Context, Error};
+
+/// Choose who can see the bot's replies about your account
+#[poise::command(slash_command, category = "User", guild_only, subcommands("privacy_replies", "privacy_balance", "privacy_baltop"))]
+pub async fn privacy(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show your balance, ledger and registration replies only to you, or to the channel
+#[poise::command(slash_command, rename = "replies")]
+pub async fn privacy_replies(
+    ctx: Context<'_>,
+    #[description = "Only show those replies to me (default: on)"] private: bool,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    match data.database.set_private_replies(&user_id, private).await {
+        Ok(()) => {
+            let response = if private {
+                "Your balance, ledger and registration replies are now only visible to you."
+            } else {
+                "Your balance, ledger and registration replies are now posted in the channel."
+            };
+            ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+        }
+        Err(e) => {
+            error!("Error updating privacy settings: {}", e);
+            ctx.say("Error updating privacy settings.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Let other users check your balance with /balance @user
+#[poise::command(slash_command, rename = "balance")]
+pub async fn privacy_balance(
+    ctx: Context<'_>,
+    #[description = "Allow anyone to see my balance (default: off)"] public: bool,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    match data.database.set_public_balance(&user_id, public).await {
+        Ok(()) => {
+            let response = if public {
+                "Anyone can now check your balance with `/balance @you`."
+            } else {
+                "Only you and admins can see your balance now."
+            };
+            ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+        }
+        Err(e) => {
+            error!("Error updating privacy settings: {}", e);
+            ctx.say("Error updating privacy settings.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show or hide yourself on /baltop
+#[poise::command(slash_command, rename = "baltop")]
+pub async fn privacy_baltop(
+    ctx: Context<'_>,
+    #[description = "List me on the leaderboard (default: on)"] shown: bool,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    match data.database.set_hide_from_leaderboard(&user_id, !shown).await {
+        Ok(()) => {
+            let response = if shown {
+                "You're listed on `/baltop` again."
+            } else {
+                "You're hidden from `/baltop`. Your coins still count towards the server's totals."
+            };
+            ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+        }
+        Err(e) => {
+            error!("Error updating privacy settings: {}", e);
+            ctx.say("Error updating privacy settings.").await?;
+        }
+    }
+
+    Ok(())
+}
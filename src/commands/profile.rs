@@ -0,0 +1,153 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::{ProfileStats, RankInfo};
+use super::{is_admin, say_private};
+
+const MAX_TITLE_LENGTH: usize = 40;
+
+// Badges shown on a profile, worked out from the user's history rather than stored.
+// `rank` is None when the user hides from the leaderboard.
+fn achievements(stats: &ProfileStats, rank: Option<&RankInfo>) -> Vec<&'static str> {
+    let mut earned = Vec::new();
+    match rank.map(|rank| rank.rank) {
+        Some(1) => earned.push("👑 Top Dog"),
+        Some(rank) if rank <= 10 => earned.push("🥇 Top 10"),
+        _ => {}
+    }
+    if Utc::now().signed_duration_since(stats.registered_at).num_days() >= 365 {
+        earned.push("🎂 Veteran");
+    }
+    if stats.auctions_won >= 10 {
+        earned.push("🦈 Auction Shark");
+    } else if stats.auctions_won > 0 {
+        earned.push("🔨 Collector");
+    }
+    if stats.lottery_wins > 0 {
+        earned.push("🎟️ Lucky");
+    }
+    if stats.sent >= 1_000 {
+        earned.push("🎁 Generous");
+    }
+    if stats.received >= 10_000 {
+        earned.push("💰 High Roller");
+    }
+    if stats.transactions >= 100 {
+        earned.push("📒 Regular");
+    }
+    earned
+}
+
+/// Show your profile, or someone else's
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn profile(
+    ctx: Context<'_>,
+    #[description = "User to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let target = user.unwrap_or_else(|| ctx.author().clone());
+    let target_id = target.id.to_string();
+    let is_self = target.id == ctx.author().id;
+
+    let (stats, preferences) = match tokio::try_join!(
+        data.database.get_profile_stats(&target_id),
+        data.database.get_user_preferences(&target_id),
+    ) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Error loading profile: {}", e);
+            say_private(ctx, "Error loading the profile. Please try again.").await?;
+            return Ok(());
+        }
+    };
+    let Some(stats) = stats else {
+        let response = if is_self { "You're not registered! Use `/register` first.".to_string() } else { format!("{} isn't registered.", target.name) };
+        say_private(ctx, response).await?;
+        return Ok(());
+    };
+
+    // Balance, rank and totals follow the same rule as /balance @user
+    let show_money = is_self || preferences.public_balance || is_admin(ctx).await?;
+    let rank = data.database.get_rank(&target_id).await?;
+    let listed_rank = rank.as_ref().filter(|_| !preferences.hide_from_leaderboard);
+
+    let mut embed = serenity::CreateEmbed::new()
+        .author(serenity::CreateEmbedAuthor::new(&target.name).icon_url(target.face()))
+        .title(stats.title.clone().unwrap_or_else(|| "Slumcoin profile".to_string()))
+        .field("Registered", format!("<t:{}:D>", stats.registered_at.timestamp()), true)
+        .field("Auctions won", stats.auctions_won.to_string(), true)
+        .field("Transactions", stats.transactions.to_string(), true);
+
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    if let Some(married) = data.database.get_marriage(&guild_id, &target_id).await? {
+        embed = embed.field(
+            "Married to",
+            format!("<@{}> since <t:{}:D>", married.partner_of(&target_id), married.married_at),
+            false,
+        );
+    }
+
+    if show_money {
+        if let Some(rank) = &rank {
+            let (vault, stocks) = tokio::try_join!(
+                data.database.get_vault_balance(&guild_id, &target_id),
+                data.database.get_portfolio_value(Some(&guild_id), &target_id),
+            )?;
+            embed = embed
+                .field("Balance", format!("{} coins", rank.balance), true)
+                .field("Net worth", format!("{} coins", rank.balance + vault + stocks), true)
+                .field("Rank", format!("#{} of {}", rank.rank, rank.total_users), true);
+        }
+        embed = embed
+            .field("Sent", format!("{} coins", stats.sent), true)
+            .field("Received", format!("{} coins", stats.received), true);
+    } else {
+        embed = embed.footer(serenity::CreateEmbedFooter::new(format!("{} keeps their balance private", target.name)));
+    }
+
+    let earned = achievements(&stats, listed_rank);
+    let earned = if earned.is_empty() { "None yet".to_string() } else { earned.join("\n") };
+    embed = embed.field("Achievements", earned, false);
+
+    let private = data.database.get_user_preferences(&ctx.author().id.to_string()).await.map(|preferences| preferences.private_replies).unwrap_or(true);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(private)).await?;
+    Ok(())
+}
+
+/// Set the title shown on your /profile, or clear it
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn title(
+    ctx: Context<'_>,
+    #[description = "Your new title (leave out to clear it)"] text: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let text = text.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+
+    if data.database.get_user(&user_id).await?.is_none() {
+        say_private(ctx, "You're not registered! Use `/register` first.").await?;
+        return Ok(());
+    }
+    if text.as_ref().is_some_and(|text| text.chars().count() > MAX_TITLE_LENGTH) {
+        say_private(ctx, format!("Titles can be at most {} characters.", MAX_TITLE_LENGTH)).await?;
+        return Ok(());
+    }
+
+    match data.database.set_profile_title(&user_id, text.as_deref()).await {
+        Ok(()) => {
+            let response = match &text {
+                Some(text) => format!("Your profile title is now **{}**.", text),
+                None => "Cleared your profile title.".to_string(),
+            };
+            say_private(ctx, response).await?;
+        }
+        Err(e) => {
+            error!("Error setting profile title: {}", e);
+            say_private(ctx, "Error updating your title.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::properties::{self, PropertyKind, MAX_TIER, STORAGE_SECONDS};
+use super::{database_error_message, not_frozen};
+
+/// Buy properties that earn Slumcoins while you're away
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("property_catalog", "property_show", "property_buy", "property_upgrade")
+)]
+pub async fn property(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// See what each property costs and earns
+#[poise::command(slash_command, rename = "catalog")]
+pub async fn property_catalog(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let income_percent = config::get_i64(&ctx.data().database, &guild_id, "property.income_percent").await?;
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title("🏘️ Properties for sale")
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Each tier up to {} adds the base income again. Income piles up for at most {} hours between collections.",
+            MAX_TIER,
+            STORAGE_SECONDS / 3600
+        )));
+    for kind in PropertyKind::ALL {
+        embed = embed.field(
+            kind.label(),
+            format!(
+                "Price: **{} Slumcoins**\nEarns {} Slumcoins/hour at tier 1, {} at tier {}",
+                kind.price(),
+                kind.hourly_income(1, income_percent),
+                kind.hourly_income(MAX_TIER, income_percent),
+                MAX_TIER
+            ),
+            true,
+        );
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Show the properties you own and what they've earned
+#[poise::command(slash_command, rename = "show")]
+pub async fn property_show(
+    ctx: Context<'_>,
+    #[description = "User whose properties to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let target = user.as_ref().unwrap_or_else(|| ctx.author());
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let owned = data.database.get_properties(&guild_id, &target.id.to_string()).await?;
+    if owned.is_empty() {
+        ctx.say(format!("{} doesn't own any property yet. See `/property catalog`.", target.name)).await?;
+        return Ok(());
+    }
+
+    let income_percent = config::get_i64(&data.database, &guild_id, "property.income_percent").await?;
+    let now = Utc::now().timestamp();
+    let mut description = String::new();
+    for property in &owned {
+        let Some(kind) = PropertyKind::from_key(&property.kind) else {
+            continue;
+        };
+        description.push_str(&format!(
+            "**{}** · tier {} · {} Slumcoins/hour · {} waiting to collect\n",
+            kind.label(),
+            property.tier,
+            kind.hourly_income(property.tier, income_percent),
+            properties::pending_income(property, income_percent, now)
+        ));
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("{}'s Properties", target.name))
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Buy a property at tier 1
+#[poise::command(slash_command, rename = "buy", check = "not_frozen")]
+pub async fn property_buy(
+    ctx: Context<'_>,
+    #[description = "Property to buy"] kind: PropertyKind,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        if !matches!(e, DatabaseError::NotRegistered(_)) {
+            error!("Database error: {}", e);
+        }
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let payment = Transaction::system(
+        &user_id,
+        TREASURY_ACCOUNT,
+        kind.price(),
+        "property_purchase",
+        Some(format!("Bought a {}", kind.key())),
+    );
+    match data.database.buy_property(&guild_id, kind.key(), &payment).await {
+        Ok(true) => {
+            let income_percent = config::get_i64(&data.database, &guild_id, "property.income_percent").await?;
+            ctx.say(format!(
+                "Bought a **{}** for {} Slumcoins! It earns {} Slumcoins an hour. Use `/collect` to cash in.",
+                kind.label(),
+                kind.price(),
+                kind.hourly_income(1, income_percent)
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("You already own a **{}**. Try `/property upgrade` instead.", kind.label())).await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error buying property: {}", e);
+            }
+            ctx.say(database_error_message(ctx, &e, "Purchase failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Raise one of your properties a tier to earn more
+#[poise::command(slash_command, rename = "upgrade", check = "not_frozen")]
+pub async fn property_upgrade(
+    ctx: Context<'_>,
+    #[description = "Property to upgrade"] kind: PropertyKind,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let Some(property) = data.database
+        .get_properties(&guild_id, &user_id)
+        .await?
+        .into_iter()
+        .find(|property| property.kind == kind.key())
+    else {
+        ctx.say(format!("You don't own a **{}**. Buy one with `/property buy`.", kind.label())).await?;
+        return Ok(());
+    };
+    if property.tier >= MAX_TIER {
+        ctx.say(format!("Your **{}** is already at the top tier.", kind.label())).await?;
+        return Ok(());
+    }
+
+    // Income earned so far is paid at the old tier before the new one starts counting
+    let income_percent = config::get_i64(&data.database, &guild_id, "property.income_percent").await?;
+    let cost = kind.upgrade_cost(property.tier);
+    let payment = Transaction::system(
+        &user_id,
+        TREASURY_ACCOUNT,
+        cost,
+        "property_upgrade",
+        Some(format!("Upgraded a {} to tier {}", kind.key(), property.tier + 1)),
+    );
+    let pending = properties::pending_income(&property, income_percent, payment.timestamp_unix);
+    let income = (pending > 0).then(|| properties::income_transaction(&property, pending));
+
+    match data.database.upgrade_property(&property, &payment, income.as_ref()).await {
+        Ok(true) => {
+            let mut response = format!(
+                "Upgraded your **{}** to tier {} for {} Slumcoins. It now earns {} Slumcoins an hour.",
+                kind.label(),
+                property.tier + 1,
+                cost,
+                kind.hourly_income(property.tier + 1, income_percent)
+            );
+            if pending > 0 {
+                response.push_str(&format!("\nCollected the {} Slumcoins it had earned first.", pending));
+            }
+            ctx.say(response).await?;
+        }
+        Ok(false) => {
+            ctx.say("Your property changed in the meantime. Please try again.").await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error upgrading property: {}", e);
+            }
+            ctx.say(database_error_message(ctx, &e, "Upgrade failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the income your properties have earned
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
+pub async fn collect(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let owned = data.database.get_properties(&guild_id, &user_id).await?;
+    if owned.is_empty() {
+        ctx.say("You don't own any property yet. See `/property catalog`.").await?;
+        return Ok(());
+    }
+
+    let income_percent = config::get_i64(&data.database, &guild_id, "property.income_percent").await?;
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for property in &owned {
+        match properties::collect(&data.database, property, income_percent).await {
+            Ok(Some(amount)) => {
+                let label = PropertyKind::from_key(&property.kind).map(PropertyKind::label).unwrap_or("Property");
+                lines.push(format!("{} · {} Slumcoins", label, amount));
+                total += amount;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error collecting property {}: {}", property.id, e);
+                ctx.say(database_error_message(ctx, &e, "Collecting failed. Please try again.")).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        ctx.say("Nothing to collect yet. Check back later.").await?;
+        return Ok(());
+    }
+    ctx.say(format!("💰 Collected **{} Slumcoins**\n{}", total, lines.join("\n"))).await?;
+    Ok(())
+}
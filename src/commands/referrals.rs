@@ -0,0 +1,96 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{Transaction, SYSTEM_ACCOUNT};
+use super::{database_error_message, not_frozen, say_private};
+
+const DAY_SECONDS: i64 = 86400;
+
+/// Name the member who invited you to this server; you both get a bonus
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
+pub async fn refer(
+    ctx: Context<'_>,
+    #[description = "Who invited you"] inviter: serenity::User,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let inviter_id = inviter.id.to_string();
+    let Some(guild) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let guild_id = guild.to_string();
+    let now = Utc::now().timestamp();
+
+    if inviter.id == ctx.author().id || inviter.bot {
+        say_private(ctx, "Name the real person who invited you.").await?;
+        return Ok(());
+    }
+    for discord_id in [&user_id, &inviter_id] {
+        if let Err(e) = data.database.require_user(discord_id).await {
+            say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+            return Ok(());
+        }
+    }
+    if let Some(referrer) = data.database.get_referrer(&guild_id, &user_id).await? {
+        say_private(ctx, format!("You already named <@{}> as your inviter here.", referrer)).await?;
+        return Ok(());
+    }
+    if data.database.is_frozen(&inviter_id).await? {
+        say_private(ctx, "Their account is frozen, so they can't earn referral bonuses right now.").await?;
+        return Ok(());
+    }
+
+    // Only members who joined recently can claim, and only naming someone who was here first
+    let window_days = config::get_i64(&data.database, &guild_id, "referral.window_days").await?;
+    let joined_at = ctx.author_member().await.and_then(|member| member.joined_at);
+    let Some(joined_at) = joined_at.map(|at| at.unix_timestamp()) else {
+        say_private(ctx, "Couldn't tell when you joined this server. Please try again.").await?;
+        return Ok(());
+    };
+    if now - joined_at > window_days * DAY_SECONDS {
+        say_private(ctx, format!("Referrals can only be claimed within {} days of joining the server.", window_days)).await?;
+        return Ok(());
+    }
+    let inviter_joined_at = guild.member(ctx, inviter.id).await.ok().and_then(|member| member.joined_at).map(|at| at.unix_timestamp());
+    if inviter_joined_at.is_none_or(|at| at >= joined_at) {
+        say_private(ctx, format!("{} has to have been in the server before you joined.", inviter.name)).await?;
+        return Ok(());
+    }
+
+    // Fresh throwaway accounts can't farm the bonus
+    let min_age_days = config::get_i64(&data.database, &guild_id, "referral.min_account_age_days").await?;
+    for (account, who) in [(ctx.author(), "Your"), (&inviter, "Their")] {
+        if now - account.id.created_at().unix_timestamp() < min_age_days * DAY_SECONDS {
+            say_private(ctx, format!("{} Discord account has to be at least {} days old to use referrals.", who, min_age_days)).await?;
+            return Ok(());
+        }
+    }
+
+    let inviter_bonus = config::get_i64(&data.database, &guild_id, "referral.inviter_bonus").await?.max(0);
+    let new_member_bonus = config::get_i64(&data.database, &guild_id, "referral.new_member_bonus").await?.max(0);
+    let inviter_payment = Transaction::system(SYSTEM_ACCOUNT, &inviter_id, inviter_bonus, "referral", Some(format!("Referred {}", user_id)));
+    let new_member_payment = Transaction::system(SYSTEM_ACCOUNT, &user_id, new_member_bonus, "referral", Some(format!("Referred by {}", inviter_id)));
+
+    match data.database.record_referral(&guild_id, &inviter_payment, &new_member_payment).await {
+        Ok(true) => {
+            let referrals = data.database.count_referrals(&guild_id, &inviter_id).await?;
+            ctx.say(format!(
+                "🤝 <@{}> was invited by <@{}>! **{} Slumcoins** for <@{}> and **{} Slumcoins** for <@{}>. \
+                That's {} referral{} for <@{}> here.",
+                user_id, inviter_id, new_member_bonus, user_id, inviter_bonus, inviter_id,
+                referrals, if referrals == 1 { "" } else { "s" }, inviter_id
+            )).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, format!("You and {} have already earned a referral bonus together.", inviter.name)).await?;
+        }
+        Err(e) => {
+            error!("Error recording referral: {}", e);
+            say_private(ctx, database_error_message(ctx, &e, "Referral failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use super::{confirm, is_admin, log_admin_action};
+
+// Longest season name, so it fits in leaderboard titles
+const MAX_SEASON_NAME_LENGTH: usize = 50;
+
+/// Autocomplete season names, newest first
+pub async fn autocomplete_season(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_seasons().await {
+        Ok(seasons) => seasons
+            .into_iter()
+            .map(|season| season.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading seasons: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Leaderboard seasons
+#[poise::command(slash_command, category = "User", guild_only, subcommands("season_list", "season_start"))]
+pub async fn season(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List past and current seasons
+#[poise::command(slash_command, rename = "list")]
+pub async fn season_list(ctx: Context<'_>) -> Result<(), Error> {
+    let seasons = match ctx.data().database.get_seasons().await {
+        Ok(seasons) => seasons,
+        Err(e) => {
+            error!("Error loading seasons: {}", e);
+            ctx.say("Error loading seasons. Please try again.").await?;
+            return Ok(());
+        }
+    };
+
+    if seasons.is_empty() {
+        ctx.say("No seasons yet. The leaderboard has been running since the start.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("🏆 **Seasons**\n");
+    for season in seasons.iter().take(20) {
+        match season.ended_at {
+            Some(ended_at) => response.push_str(&format!("• **{}**: <t:{}:d> to <t:{}:d>\n", season.name, season.started_at, ended_at)),
+            None => response.push_str(&format!("• **{}**: running since <t:{}:d>\n", season.name, season.started_at)),
+        }
+    }
+    response.push_str("View final standings with `/baltop show season:<name>`");
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// End the current season, archiving the leaderboard, and start a new one
+#[poise::command(slash_command, rename = "start", check = "is_admin")]
+pub async fn season_start(
+    ctx: Context<'_>,
+    #[description = "Name of the new season"] name: String,
+    #[description = "Reset every wallet balance to this amount (default: keep balances)"] seed: Option<i64>,
+    #[description = "Reason for the audit log"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let name = name.trim().to_string();
+
+    if name.is_empty() || name.chars().count() > MAX_SEASON_NAME_LENGTH {
+        ctx.say(format!("Season names need 1 to {} characters.", MAX_SEASON_NAME_LENGTH)).await?;
+        return Ok(());
+    }
+    if seed.is_some_and(|seed| seed < 0) {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    if data.database.get_season_by_name(&name).await?.is_some() {
+        ctx.say(format!("There's already a season called **{}**.", name)).await?;
+        return Ok(());
+    }
+
+    let ending = match data.database.get_current_season().await? {
+        Some(season) => format!("End **{}**", season.name),
+        None => "Archive the leaderboard so far as **Preseason**".to_string(),
+    };
+    let prompt = match seed {
+        Some(seed) => format!("{} and start **{}**, resetting every wallet to **{} Slumcoins**? Vault savings are kept.", ending, name, seed),
+        None => format!("{} and start **{}**? Balances carry over.", ending, name),
+    };
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+
+    let change = match data.database.start_season(&name, &ctx.author().id.to_string(), seed).await {
+        Ok(change) => change,
+        Err(e) => {
+            error!("Error starting season: {}", e);
+            ctx.say("Error starting the season. Nothing was changed.").await?;
+            return Ok(());
+        }
+    };
+
+    let details = match seed {
+        Some(seed) => format!("seed {}, {} balance(s) reset", seed, change.reset),
+        None => "balances kept".to_string(),
+    };
+    log_admin_action(ctx, "season_start", change.started.name.clone(), seed, reason.or(Some(details))).await;
+
+    let mut response = format!(
+        "🏁 **{}** is over. Archived {} balance(s); see them with `/baltop show season:{}`.\n**{}** starts now!",
+        change.ended.name, change.archived, change.ended.name, change.started.name
+    );
+    if let Some(seed) = seed {
+        response.push_str(&format!(" Everyone starts with **{} Slumcoins**.", seed));
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
@@ -0,0 +1,391 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, database::Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::perks::Perk;
+use crate::roles::{self, RoleError};
+use super::{is_admin, log_admin_action, database_error_message};
+
+/// Autocomplete item names from this server's shop
+pub async fn autocomplete_shop_item(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_shop_items(&guild_id).await {
+        Ok(items) => items
+            .into_iter()
+            .map(|item| item.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading shop items: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Browse and manage items for sale
+#[poise::command(slash_command, category = "User", guild_only, subcommands("shop_list", "shop_add", "shop_role", "shop_perk", "shop_remove"))]
+pub async fn shop(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Browse items for sale
+#[poise::command(slash_command, rename = "list")]
+pub async fn shop_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let items = match data.database.get_shop_items(&guild_id).await {
+        Ok(items) => items,
+        Err(e) => {
+            error!("Error loading shop items: {}", e);
+            ctx.say("Error loading the shop.").await?;
+            return Ok(());
+        }
+    };
+
+    if items.is_empty() {
+        ctx.say("The shop is empty.").await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for item in &items {
+        description.push_str(&format!("**{}** - {} Slumcoins", item.name, item.price));
+        if let Some(role_id) = &item.role_id {
+            description.push_str(&format!(" · grants <@&{}>", role_id));
+            if let Some(hours) = item.role_duration_hours {
+                description.push_str(&format!(" for {}h", hours));
+            }
+        }
+        if let Some(perk) = item.perk.as_deref().and_then(Perk::from_key) {
+            description.push_str(&format!(" · buy with `{}`", perk.command()));
+        }
+        match item.stock {
+            Some(0) => description.push_str(" · *sold out*"),
+            Some(stock) => description.push_str(&format!(" · {} left", stock)),
+            None => {}
+        }
+        if item.consumable {
+            description.push_str(" · consumable");
+        }
+        if item.tradeable {
+            description.push_str(" · tradeable");
+        }
+        description.push('\n');
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Slumshop")
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new("Use /buy to purchase an item"));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Add an item to the shop
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, rename = "add", check = "is_admin")]
+pub async fn shop_add(
+    ctx: Context<'_>,
+    #[description = "Item name"] name: String,
+    #[description = "Price in Slumcoins"] price: i64,
+    #[description = "Role granted on purchase"] role: Option<serenity::Role>,
+    #[description = "Hours until the granted role is removed (default: permanent)"] role_hours: Option<i64>,
+    #[description = "Number available (default: unlimited)"] stock: Option<i64>,
+    #[description = "Item is used up with /inventory use (default: false)"] consumable: Option<bool>,
+    #[description = "Item can be given to other users (default: false)"] tradeable: Option<bool>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let name = name.trim().to_string();
+
+    if name.is_empty() || price <= 0 || stock.is_some_and(|stock| stock < 0) || role_hours.is_some_and(|hours| hours <= 0) {
+        ctx.say("Items need a name, a positive price, a non-negative stock and a positive role duration.").await?;
+        return Ok(());
+    }
+
+    if data.database.get_shop_item_by_name(&guild_id, &name).await?.is_some() {
+        ctx.say(format!("**{}** is already in the shop.", name)).await?;
+        return Ok(());
+    }
+
+    let role_id = role.as_ref().map(|role| role.id.to_string());
+    match data.database.create_shop_item(
+        &guild_id,
+        &name,
+        price,
+        role_id.as_deref(),
+        role_hours,
+        stock,
+        consumable.unwrap_or(false),
+        tradeable.unwrap_or(false),
+    ).await {
+        Ok(_) => {
+            log_admin_action(ctx, "shop_add", format!("item {}", name), Some(price), None).await;
+            ctx.say(format!("Added **{}** to the shop for {} Slumcoins.", name, price)).await?;
+        }
+        Err(e) => {
+            error!("Error creating shop item: {}", e);
+            ctx.say("Error adding item to the shop.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a shop item grant a role
+#[poise::command(slash_command, rename = "role", check = "is_admin")]
+pub async fn shop_role(
+    ctx: Context<'_>,
+    #[description = "Item to change"]
+    #[autocomplete = "autocomplete_shop_item"]
+    item: String,
+    #[description = "Role granted on purchase (leave empty to stop granting a role)"] role: Option<serenity::Role>,
+    #[description = "Hours until the granted role is removed (default: permanent)"] hours: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if hours.is_some_and(|hours| hours <= 0) {
+        ctx.say("Role duration must be a positive number of hours.").await?;
+        return Ok(());
+    }
+
+    let Some(shop_item) = data.database.get_shop_item_by_name(&guild_id, &item).await? else {
+        ctx.say(format!("No item called **{}** in the shop.", item)).await?;
+        return Ok(());
+    };
+
+    if role.is_some() && shop_item.perk.is_some() {
+        ctx.say(format!("**{}** is a perk, and perks can't grant roles.", shop_item.name)).await?;
+        return Ok(());
+    }
+
+    let role_id = role.as_ref().map(|role| role.id.to_string());
+    match data.database.set_shop_item_role(shop_item.id, role_id.as_deref(), hours).await {
+        Ok(()) => {
+            let change = role.as_ref().map(|role| format!("grants role {}", role.id));
+            log_admin_action(ctx, "shop_role", format!("item {}", shop_item.name), None, change).await;
+            let response = match (&role, hours) {
+                (Some(role), Some(hours)) => format!("**{}** now grants <@&{}> for {} hours.", shop_item.name, role.id, hours),
+                (Some(role), None) => format!("**{}** now grants <@&{}>.", shop_item.name, role.id),
+                (None, _) => format!("**{}** no longer grants a role.", shop_item.name),
+            };
+            ctx.send(poise::CreateReply::default()
+                .content(response)
+                .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+        }
+        Err(e) => {
+            error!("Error updating shop item role: {}", e);
+            ctx.say("Error updating item.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sell a perk through a shop item
+#[poise::command(slash_command, rename = "perk", check = "is_admin")]
+pub async fn shop_perk(
+    ctx: Context<'_>,
+    #[description = "Item to change"]
+    #[autocomplete = "autocomplete_shop_item"]
+    item: String,
+    #[description = "Perk the item buys (leave empty to make it an ordinary item)"] perk: Option<Perk>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let Some(shop_item) = data.database.get_shop_item_by_name(&guild_id, &item).await? else {
+        ctx.say(format!("No item called **{}** in the shop.", item)).await?;
+        return Ok(());
+    };
+    if let Some(perk) = perk {
+        if shop_item.role_id.is_some() {
+            ctx.say(format!("**{}** grants a role. Perks can't grant roles, so clear it with `/shop role` first.", shop_item.name)).await?;
+            return Ok(());
+        }
+        if let Some(seller) = data.database.get_perk_item(&guild_id, perk.key()).await?.filter(|seller| seller.id != shop_item.id) {
+            ctx.say(format!("**{}** already sells the {} perk.", seller.name, perk.key())).await?;
+            return Ok(());
+        }
+    }
+
+    match data.database.set_shop_item_perk(shop_item.id, perk.map(Perk::key)).await {
+        Ok(()) => {
+            let change = perk.map(|perk| format!("sells the {} perk", perk.key()));
+            log_admin_action(ctx, "shop_perk", format!("item {}", shop_item.name), None, change).await;
+            let response = match perk {
+                Some(perk) => format!("**{}** is now bought with `{}`.", shop_item.name, perk.command()),
+                None => format!("**{}** is no longer a perk.", shop_item.name),
+            };
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            error!("Error updating shop item perk: {}", e);
+            ctx.say("Error updating item.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Take an item off the shop
+#[poise::command(slash_command, rename = "remove", check = "is_admin")]
+pub async fn shop_remove(
+    ctx: Context<'_>,
+    #[description = "Item to remove"]
+    #[autocomplete = "autocomplete_shop_item"]
+    item: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.get_shop_item_by_name(&guild_id, &item).await? {
+        Some(shop_item) => {
+            data.database.deactivate_shop_item(shop_item.id).await?;
+            log_admin_action(ctx, "shop_remove", format!("item {}", shop_item.name), None, None).await;
+            ctx.say(format!("Removed **{}** from the shop.", shop_item.name)).await?;
+        }
+        None => {
+            ctx.say(format!("No item called **{}** in the shop.", item)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Buy an item from the shop
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn buy(
+    ctx: Context<'_>,
+    #[description = "Item to buy"]
+    #[autocomplete = "autocomplete_shop_item"]
+    item: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = match ctx.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let shop_item = match data.database.get_shop_item_by_name(&guild_id.to_string(), &item).await? {
+        Some(shop_item) => shop_item,
+        None => {
+            ctx.say(format!("No item called **{}** in the shop.", item)).await?;
+            return Ok(());
+        }
+    };
+
+    // Perks need details only their own command asks for
+    if let Some(perk) = shop_item.perk.as_deref().and_then(Perk::from_key) {
+        ctx.say(format!("**{}** is a perk. Buy it with `{}`.", shop_item.name, perk.command())).await?;
+        return Ok(());
+    }
+
+    let balance = data.database.get_balance(&user_id).await?;
+    if balance < shop_item.price {
+        ctx.say(format!(
+            "UR BROKE BUB! **{}** costs {} Slumcoins and you have {}",
+            shop_item.name, shop_item.price, balance
+        )).await?;
+        return Ok(());
+    }
+
+    let transaction = Transaction::system(
+        &user_id,
+        TREASURY_ACCOUNT,
+        shop_item.price,
+        "purchase",
+        Some(format!("Bought {}", shop_item.name)),
+    );
+
+    match data.database.purchase_item(&shop_item, &transaction).await {
+        Ok(true) => {}
+        Ok(false) => {
+            ctx.say(format!("**{}** is sold out.", shop_item.name)).await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Error purchasing item: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Purchase failed. Please try again.")).await?;
+            return Ok(());
+        }
+    }
+
+    let mut response = format!("Bought **{}** for {} Slumcoins.", shop_item.name, shop_item.price);
+
+    if let Some(role_id) = shop_item.role_id.as_ref().and_then(|id| id.parse::<u64>().ok()) {
+        let role_id = serenity::RoleId::new(role_id);
+        match roles::grant(ctx.http(), guild_id, ctx.author().id, role_id, "Shop purchase").await {
+            Ok(()) => {
+                response.push_str(&format!("\nYou now have <@&{}>", role_id));
+                match shop_item.role_duration_hours {
+                    Some(hours) => {
+                        let expires_at = Utc::now().timestamp() + hours * 3600;
+                        data.database.add_role_grant(
+                            &guild_id.to_string(),
+                            &user_id,
+                            &role_id.to_string(),
+                            shop_item.id,
+                            expires_at,
+                        ).await?;
+                        response.push_str(&format!(" until <t:{}:f>.", expires_at));
+                    }
+                    None => response.push('.'),
+                }
+            }
+            Err(e) => {
+                error!("Failed to grant role {} to {}: {}", role_id, user_id, e);
+
+                // The role is what they paid for, so undo the purchase
+                let refund = Transaction::system(
+                    TREASURY_ACCOUNT,
+                    &user_id,
+                    shop_item.price,
+                    "refund",
+                    Some(format!("Refund for {}", shop_item.name)),
+                );
+                data.database.apply_transaction(&refund).await?;
+                data.database.remove_inventory_item(&user_id, shop_item.id, 1).await?;
+
+                response = match e {
+                    RoleError::MissingPermissions => format!(
+                        "I don't have permission to give out <@&{}> (I need **Manage Roles** and a role above it). \
+                        Your {} Slumcoins were refunded, please let an admin know.",
+                        role_id, shop_item.price
+                    ),
+                    RoleError::Http(_) => format!(
+                        "I couldn't grant the role for **{}**. Your {} Slumcoins were refunded.",
+                        shop_item.name, shop_item.price
+                    ),
+                };
+            }
+        }
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
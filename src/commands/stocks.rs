@@ -0,0 +1,275 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::{DatabaseError, Stock, Transaction, SYSTEM_ACCOUNT};
+use crate::stocks::{self, HISTORY_SECONDS};
+use super::{database_error_message, not_frozen, say_private};
+
+const DAY_SECONDS: i64 = 86400;
+const CHART_WIDTH: usize = 30;
+
+pub async fn autocomplete_stock(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match stocks::market(&ctx.data().database, &guild_id).await {
+        Ok(market) => market
+            .into_iter()
+            .map(|stock| stock.symbol)
+            .filter(|symbol| symbol.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading stocks: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// Load a stock from this guild's market, replying if there isn't one
+async fn find_stock(ctx: Context<'_>, symbol: &str) -> Result<Option<Stock>, Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    stocks::market(&ctx.data().database, &guild_id).await?;
+
+    let stock = ctx.data().database.get_stock_by_symbol(&guild_id, symbol.trim()).await?;
+    if stock.is_none() {
+        ctx.say(format!("No stock called **{}**. See `/stocks list`.", symbol)).await?;
+    }
+    Ok(stock)
+}
+
+// Price change over the last day, from the oldest price still in that window
+async fn day_change(ctx: Context<'_>, stock: &Stock) -> Result<f64, DatabaseError> {
+    let history = ctx.data().database.get_stock_history(stock.id, Utc::now().timestamp() - DAY_SECONDS).await?;
+    let opening = history.first().map(|(_, price)| *price).unwrap_or(stock.price);
+    Ok(stocks::change_percent(opening, stock.price))
+}
+
+/// Trade shares in this server's made-up stock market
+#[poise::command(
+    slash_command,
+    category = "Games",
+    guild_only,
+    subcommands("stocks_list", "stocks_info", "stocks_buy", "stocks_sell")
+)]
+pub async fn stocks(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show every stock's price and how it moved today
+#[poise::command(slash_command, rename = "list")]
+pub async fn stocks_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let market = stocks::market(&ctx.data().database, &guild_id).await?;
+
+    let mut response = String::from("📈 **Stock market**\n");
+    for stock in &market {
+        response.push_str(&format!(
+            "• **{}** {} · {} Slumcoins ({:+.1}% today)\n",
+            stock.symbol,
+            stock.name,
+            stock.price,
+            day_change(ctx, stock).await?
+        ));
+    }
+    response.push_str("See a chart with `/stocks info`");
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Show a stock's price chart
+#[poise::command(slash_command, rename = "info")]
+pub async fn stocks_info(
+    ctx: Context<'_>,
+    #[description = "Stock symbol"]
+    #[autocomplete = "autocomplete_stock"]
+    symbol: String,
+    #[description = "Days of history to chart (default: 1)"] days: Option<u32>,
+) -> Result<(), Error> {
+    let Some(stock) = find_stock(ctx, &symbol).await? else {
+        return Ok(());
+    };
+
+    let days = days.unwrap_or(1).clamp(1, (HISTORY_SECONDS / DAY_SECONDS) as u32);
+    let since = Utc::now().timestamp() - i64::from(days) * DAY_SECONDS;
+    let prices: Vec<i64> = ctx.data().database
+        .get_stock_history(stock.id, since)
+        .await?
+        .into_iter()
+        .map(|(_, price)| price)
+        .collect();
+    let opening = prices.first().copied().unwrap_or(stock.price);
+    let low = prices.iter().copied().min().unwrap_or(stock.price);
+    let high = prices.iter().copied().max().unwrap_or(stock.price);
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("{} · {}", stock.symbol, stock.name))
+        .description(format!("`{}`", stocks::sparkline(&prices, CHART_WIDTH)))
+        .field("Price", format!("{} Slumcoins", stock.price), true)
+        .field("Change", format!("{:+.1}%", stocks::change_percent(opening, stock.price)), true)
+        .field("Range", format!("{} – {}", low, high), true)
+        .footer(serenity::CreateEmbedFooter::new(format!("Last {} day(s)", days)))
+        .color(0x2ecc71);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Buy shares at the current price
+#[poise::command(slash_command, rename = "buy", check = "not_frozen")]
+pub async fn stocks_buy(
+    ctx: Context<'_>,
+    #[description = "Stock symbol"]
+    #[autocomplete = "autocomplete_stock"]
+    symbol: String,
+    #[description = "Number of shares"] shares: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if shares <= 0 {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&user_id).await {
+        ctx.say(database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+    let Some(stock) = find_stock(ctx, &symbol).await? else {
+        return Ok(());
+    };
+
+    let Some(cost) = stock.price.checked_mul(shares) else {
+        ctx.say("That's more shares than exist.").await?;
+        return Ok(());
+    };
+    let payment = Transaction::system(
+        &user_id,
+        SYSTEM_ACCOUNT,
+        cost,
+        "stock_buy",
+        Some(format!("Bought {} {} at {}", shares, stock.symbol, stock.price)),
+    );
+
+    match data.database.buy_stock(&stock, shares, &payment).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "Bought **{} {}** at {} Slumcoins each for **{} Slumcoins**.",
+                shares, stock.symbol, stock.price, cost
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("The price of **{}** just moved. Please try again.", stock.symbol)).await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error buying stock: {}", e);
+            }
+            ctx.say(database_error_message(ctx, &e, "Purchase failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sell shares at the current price
+#[poise::command(slash_command, rename = "sell", check = "not_frozen")]
+pub async fn stocks_sell(
+    ctx: Context<'_>,
+    #[description = "Stock symbol"]
+    #[autocomplete = "autocomplete_stock"]
+    symbol: String,
+    #[description = "Number of shares (default: all of them)"] shares: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if shares.is_some_and(|shares| shares <= 0) {
+        ctx.say("nice try bub").await?;
+        return Ok(());
+    }
+    let Some(stock) = find_stock(ctx, &symbol).await? else {
+        return Ok(());
+    };
+
+    let held = data.database
+        .get_portfolio(&guild_id, &user_id)
+        .await?
+        .into_iter()
+        .find(|holding| holding.stock_id == stock.id)
+        .map(|holding| holding.shares)
+        .unwrap_or(0);
+    let shares = shares.unwrap_or(held);
+    if held == 0 || shares > held {
+        ctx.say(format!("You hold {} shares of **{}**.", held, stock.symbol)).await?;
+        return Ok(());
+    }
+
+    let proceeds = stock.price * shares;
+    let payout = Transaction::system(
+        SYSTEM_ACCOUNT,
+        &user_id,
+        proceeds,
+        "stock_sell",
+        Some(format!("Sold {} {} at {}", shares, stock.symbol, stock.price)),
+    );
+
+    match data.database.sell_stock(&stock, shares, &payout).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "Sold **{} {}** at {} Slumcoins each for **{} Slumcoins**.",
+                shares, stock.symbol, stock.price, proceeds
+            )).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("The price of **{}** just moved. Please try again.", stock.symbol)).await?;
+        }
+        Err(e) => {
+            error!("Error selling stock: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Sale failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the shares you hold and how they're doing
+#[poise::command(slash_command, category = "Games", guild_only)]
+pub async fn portfolio(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let holdings = ctx.data().database.get_portfolio(&guild_id, &user_id).await?;
+    if holdings.is_empty() {
+        say_private(ctx, "You don't hold any shares. See `/stocks list`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("💼 **Your portfolio**\n");
+    let mut total_value = 0;
+    let mut total_cost = 0;
+    for holding in &holdings {
+        let value = holding.shares * holding.price;
+        response.push_str(&format!(
+            "• **{}** {} · {} shares worth {} Slumcoins ({:+.1}%)\n",
+            holding.symbol,
+            holding.name,
+            holding.shares,
+            value,
+            stocks::change_percent(holding.cost_basis, value)
+        ));
+        total_value += value;
+        total_cost += holding.cost_basis;
+    }
+    response.push_str(&format!(
+        "Total: **{} Slumcoins**, {:+} on what you paid",
+        total_value,
+        total_value - total_cost
+    ));
+
+    say_private(ctx, response).await?;
+    Ok(())
+}
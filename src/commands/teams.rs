@@ -0,0 +1,509 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, Team, Transaction};
+use crate::teams::{TeamRole, MAX_NAME_LENGTH, TEAM_BANK_ACCOUNT};
+use super::{confirm, database_error_message, mention_list, not_frozen, say_private};
+
+const LEADERBOARD_SIZE: i64 = 10;
+
+pub async fn autocomplete_team(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_team_leaderboard(&guild_id, i64::MAX).await {
+        Ok(teams) => teams
+            .into_iter()
+            .map(|(team, _)| team.name)
+            .filter(|name| name.to_lowercase().contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading teams: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// The author's team and role in this guild, replying if they aren't in one
+async fn own_team(ctx: Context<'_>) -> Result<Option<(Team, TeamRole)>, Error> {
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let membership = ctx.data().database.get_user_team(&guild_id, &user_id).await?;
+    let Some((team, member)) = membership else {
+        say_private(ctx, "You're not in a team. Create one with `/team create` or ask an officer for an invite.").await?;
+        return Ok(None);
+    };
+    Ok(TeamRole::parse(&member.role).map(|role| (team, role)))
+}
+
+// Someone's current role in `team`, if they're in it or invited
+async fn member_role(ctx: Context<'_>, team: &Team, discord_id: &str) -> Result<Option<TeamRole>, DatabaseError> {
+    Ok(ctx.data().database
+        .get_team_members(team.id)
+        .await?
+        .into_iter()
+        .find(|member| member.discord_id == discord_id)
+        .and_then(|member| TeamRole::parse(&member.role)))
+}
+
+/// Pool coins with other players in a shared team bank
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands(
+        "team_create", "team_invite", "team_join", "team_leave", "team_kick", "team_promote",
+        "team_demote", "team_deposit", "team_pay", "team_disband", "team_info", "team_leaderboard"
+    )
+)]
+pub async fn team(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Start a team and lead it
+#[poise::command(slash_command, rename = "create")]
+pub async fn team_create(
+    ctx: Context<'_>,
+    #[description = "Team name"] name: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let name = name.trim();
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        say_private(ctx, format!("Team names are 1 to {} characters.", MAX_NAME_LENGTH)).await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&user_id).await {
+        say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+    if let Some((team, _)) = data.database.get_user_team(&guild_id, &user_id).await? {
+        say_private(ctx, format!("You're already in **{}**. Leave it first.", team.name)).await?;
+        return Ok(());
+    }
+
+    match data.database.create_team(&guild_id, name, &user_id, Utc::now().timestamp()).await? {
+        Some(_) => {
+            ctx.say(format!("🛡️ <@{}> founded **{}**. Invite people with `/team invite`.", user_id, name)).await?;
+        }
+        None => {
+            say_private(ctx, format!("There's already a team called **{}**.", name)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Invite someone to your team (officers and the leader)
+#[poise::command(slash_command, rename = "invite")]
+pub async fn team_invite(
+    ctx: Context<'_>,
+    #[description = "Who to invite"] user: serenity::User,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let target_id = user.id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if !role.can_manage() {
+        say_private(ctx, "Only officers and the leader can invite people.").await?;
+        return Ok(());
+    }
+    if user.bot {
+        say_private(ctx, "Bots can't join teams.").await?;
+        return Ok(());
+    }
+    if let Some((other, _)) = data.database.get_user_team(&guild_id, &target_id).await? {
+        say_private(ctx, format!("{} is already in **{}**.", user.name, other.name)).await?;
+        return Ok(());
+    }
+
+    let max_members = config::get_i64(&data.database, &guild_id, "team.max_members").await?;
+    if data.database.invite_team_member(&team, &target_id, max_members, Utc::now().timestamp()).await? {
+        ctx.say(format!(
+            "🛡️ <@{}>, you've been invited to **{}**. Accept with `/team join {}`.",
+            target_id, team.name, team.name
+        )).await?;
+    } else {
+        say_private(ctx, format!(
+            "{} is already invited, or **{}** is full ({} members and invites at most).",
+            user.name, team.name, max_members
+        )).await?;
+    }
+    Ok(())
+}
+
+/// Accept an invite to a team
+#[poise::command(slash_command, rename = "join")]
+pub async fn team_join(
+    ctx: Context<'_>,
+    #[description = "Team that invited you"]
+    #[autocomplete = "autocomplete_team"]
+    name: String,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if let Err(e) = data.database.require_user(&user_id).await {
+        say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+    let Some(team) = data.database.get_team_by_name(&guild_id, name.trim()).await? else {
+        say_private(ctx, format!("No team called **{}**.", name)).await?;
+        return Ok(());
+    };
+
+    if data.database.join_team(&team, &user_id, Utc::now().timestamp()).await? {
+        ctx.say(format!("🛡️ <@{}> joined **{}**.", user_id, team.name)).await?;
+    } else if let Some((current, _)) = data.database.get_user_team(&guild_id, &user_id).await? {
+        say_private(ctx, format!("You're already in **{}**. Leave it first.", current.name)).await?;
+    } else {
+        say_private(ctx, format!("**{}** hasn't invited you.", team.name)).await?;
+    }
+    Ok(())
+}
+
+/// Leave your team
+#[poise::command(slash_command, rename = "leave")]
+pub async fn team_leave(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if role == TeamRole::Leader {
+        say_private(ctx, "Leaders can't leave. Promote an officer to leader first, or `/team disband`.").await?;
+        return Ok(());
+    }
+
+    if ctx.data().database.remove_team_member(team.id, &user_id, role.key()).await? {
+        say_private(ctx, format!("You left **{}**.", team.name)).await?;
+    } else {
+        say_private(ctx, "Your role changed in the meantime. Please try again.").await?;
+    }
+    Ok(())
+}
+
+/// Remove a member or withdraw an invite (officers and the leader)
+#[poise::command(slash_command, rename = "kick")]
+pub async fn team_kick(
+    ctx: Context<'_>,
+    #[description = "Who to remove"] user: serenity::User,
+) -> Result<(), Error> {
+    let target_id = user.id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    let Some(target_role) = member_role(ctx, &team, &target_id).await? else {
+        say_private(ctx, format!("{} isn't in **{}**.", user.name, team.name)).await?;
+        return Ok(());
+    };
+    if !role.can_remove(target_role) {
+        say_private(ctx, format!("You can't remove the team's {}.", target_role)).await?;
+        return Ok(());
+    }
+
+    if ctx.data().database.remove_team_member(team.id, &target_id, target_role.key()).await? {
+        ctx.say(format!("🛡️ <@{}> was removed from **{}**.", target_id, team.name)).await?;
+    } else {
+        say_private(ctx, format!("{}'s role changed in the meantime. Please try again.", user.name)).await?;
+    }
+    Ok(())
+}
+
+/// Promote a member to officer, or an officer to leader (leader only)
+#[poise::command(slash_command, rename = "promote")]
+pub async fn team_promote(
+    ctx: Context<'_>,
+    #[description = "Who to promote"] user: serenity::User,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let target_id = user.id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if role != TeamRole::Leader {
+        say_private(ctx, "Only the leader can promote people.").await?;
+        return Ok(());
+    }
+
+    let (promoted, new_role) = match member_role(ctx, &team, &target_id).await? {
+        Some(TeamRole::Member) => (
+            data.database.set_team_role(team.id, &target_id, "member", "officer").await?,
+            "an officer of",
+        ),
+        // Promoting an officer hands over leadership, and the old leader stays on as an officer
+        Some(TeamRole::Officer) => (
+            data.database.transfer_team_leadership(team.id, &user_id, &target_id).await?,
+            "the leader of",
+        ),
+        Some(TeamRole::Leader) => {
+            say_private(ctx, "You already lead the team.").await?;
+            return Ok(());
+        }
+        Some(TeamRole::Invited) | None => {
+            say_private(ctx, format!("{} isn't a member of **{}**.", user.name, team.name)).await?;
+            return Ok(());
+        }
+    };
+
+    if promoted {
+        ctx.say(format!("🛡️ <@{}> is now {} **{}**.", target_id, new_role, team.name)).await?;
+    } else {
+        say_private(ctx, "Someone's role changed in the meantime. Please try again.").await?;
+    }
+    Ok(())
+}
+
+/// Demote an officer to member (leader only)
+#[poise::command(slash_command, rename = "demote")]
+pub async fn team_demote(
+    ctx: Context<'_>,
+    #[description = "Who to demote"] user: serenity::User,
+) -> Result<(), Error> {
+    let target_id = user.id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if role != TeamRole::Leader {
+        say_private(ctx, "Only the leader can demote people.").await?;
+        return Ok(());
+    }
+
+    if ctx.data().database.set_team_role(team.id, &target_id, "officer", "member").await? {
+        ctx.say(format!("🛡️ <@{}> is no longer an officer of **{}**.", target_id, team.name)).await?;
+    } else {
+        say_private(ctx, format!("{} isn't an officer of **{}**.", user.name, team.name)).await?;
+    }
+    Ok(())
+}
+
+/// Move coins from your wallet into your team's bank
+#[poise::command(slash_command, rename = "deposit", check = "not_frozen")]
+pub async fn team_deposit(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to put in the bank"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+    let Some((team, _)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+
+    let deposit = Transaction::system(
+        &user_id,
+        TEAM_BANK_ACCOUNT,
+        amount,
+        "team_deposit",
+        Some(format!("Deposit to team {}", team.name)),
+    );
+    match data.database.team_deposit(team.id, &deposit).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "🛡️ <@{}> put **{} Slumcoins** in **{}**'s bank. It now holds {} coins.",
+                user_id, amount, team.name, team.balance + amount
+            )).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, format!("**{}** was disbanded in the meantime.", team.name)).await?;
+        }
+        Err(e) => {
+            if !matches!(e, DatabaseError::InsufficientFunds(_)) {
+                error!("Error depositing into team bank: {}", e);
+            }
+            say_private(ctx, database_error_message(ctx, &e, "Deposit failed. Please try again.")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Pay someone out of your team's bank (officers and the leader)
+#[poise::command(slash_command, rename = "pay", check = "not_frozen")]
+pub async fn team_pay(
+    ctx: Context<'_>,
+    #[description = "Who to pay"] user: serenity::User,
+    #[description = "Amount of coins"] amount: i64,
+    #[description = "What it's for"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let target_id = user.id.to_string();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if !role.can_manage() {
+        say_private(ctx, "Only officers and the leader can spend from the bank.").await?;
+        return Ok(());
+    }
+    if let Err(e) = data.database.require_user(&target_id).await {
+        say_private(ctx, database_error_message(ctx, &e, "Database error occurred.")).await?;
+        return Ok(());
+    }
+
+    let description = match &reason {
+        Some(reason) => format!("Team {} payment by {}: {}", team.name, user_id, reason),
+        None => format!("Team {} payment by {}", team.name, user_id),
+    };
+    let payment = Transaction::system(TEAM_BANK_ACCOUNT, &target_id, amount, "team_payout", Some(description));
+    match data.database.team_spend(team.id, &payment).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "🛡️ <@{}> paid <@{}> **{} Slumcoins** from **{}**'s bank{}.",
+                user_id,
+                target_id,
+                amount,
+                team.name,
+                reason.map(|reason| format!(" for {}", reason)).unwrap_or_default()
+            )).await?;
+        }
+        Ok(false) => {
+            let balance = data.database.get_team(team.id).await?.map(|team| team.balance).unwrap_or(0);
+            say_private(ctx, format!("**{}**'s bank only holds {} Slumcoins.", team.name, balance)).await?;
+        }
+        Err(e) => {
+            error!("Error paying from team bank: {}", e);
+            say_private(ctx, database_error_message(ctx, &e, "Payment failed. Please try again.")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Disband your team, paying what's left in the bank to you (leader only)
+#[poise::command(slash_command, rename = "disband")]
+pub async fn team_disband(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let Some((team, role)) = own_team(ctx).await? else {
+        return Ok(());
+    };
+    if role != TeamRole::Leader {
+        say_private(ctx, "Only the leader can disband the team.").await?;
+        return Ok(());
+    }
+    if !confirm(ctx, format!(
+        "Disband **{}**? Its bank's {} Slumcoins go to you.",
+        team.name, team.balance
+    )).await? {
+        return Ok(());
+    }
+
+    // The bank may have moved while the prompt was up
+    let Some(team) = data.database.get_team(team.id).await? else {
+        return Ok(());
+    };
+    let payout = (team.balance > 0).then(|| Transaction::system(
+        TEAM_BANK_ACCOUNT,
+        &user_id,
+        team.balance,
+        "team_disband",
+        Some(format!("Team {} disbanded", team.name)),
+    ));
+    match data.database.disband_team(team.id, payout.as_ref()).await {
+        Ok(true) => {
+            ctx.say(format!("🛡️ **{}** was disbanded. <@{}> got its {} Slumcoins.", team.name, user_id, team.balance)).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, "The bank changed in the meantime. Please try again.").await?;
+        }
+        Err(e) => {
+            error!("Error disbanding team {}: {}", team.id, e);
+            say_private(ctx, database_error_message(ctx, &e, "Error disbanding the team. Please try again.")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Show a team's bank and members
+#[poise::command(slash_command, rename = "info")]
+pub async fn team_info(
+    ctx: Context<'_>,
+    #[description = "Team (default: yours)"]
+    #[autocomplete = "autocomplete_team"]
+    name: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let team = match name {
+        Some(name) => match data.database.get_team_by_name(&guild_id, name.trim()).await? {
+            Some(team) => team,
+            None => {
+                say_private(ctx, format!("No team called **{}**.", name)).await?;
+                return Ok(());
+            }
+        },
+        None => match own_team(ctx).await? {
+            Some((team, _)) => team,
+            None => return Ok(()),
+        },
+    };
+
+    let members = data.database.get_team_members(team.id).await?;
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🛡️ {}", team.name))
+        .field("Bank", format!("{} Slumcoins", team.balance), true)
+        .field("Founded", format!("<t:{}:D>", team.created_at), true)
+        .color(0x8e44ad);
+    for (role, label) in [("leader", "Leader"), ("officer", "Officers"), ("member", "Members"), ("invited", "Invited")] {
+        let ids: Vec<String> = members
+            .iter()
+            .filter(|member| member.role == role)
+            .map(|member| member.discord_id.clone())
+            .collect();
+        if !ids.is_empty() {
+            embed = embed.field(label, mention_list(&ids), false);
+        }
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Rank this server's teams by what's in their banks
+#[poise::command(slash_command, rename = "leaderboard")]
+pub async fn team_leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let teams = ctx.data().database.get_team_leaderboard(&guild_id, LEADERBOARD_SIZE).await?;
+    if teams.is_empty() {
+        ctx.say("No teams yet. Start one with `/team create`.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("🛡️ **Team leaderboard**\n");
+    for (rank, (team, members)) in teams.iter().enumerate() {
+        response.push_str(&format!(
+            "**{}.** {} · {} Slumcoins · {} member{}\n",
+            rank + 1,
+            team.name,
+            team.balance,
+            members,
+            if *members == 1 { "" } else { "s" }
+        ));
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tracing::error;
+
+use crate::{Context, Error, config, database::Transaction};
+use crate::database::SYSTEM_ACCOUNT;
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{is_admin, log_admin_action, database_error_message};
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum SpendCategory {
+    #[name = "events"]
+    Events,
+    #[name = "prizes"]
+    Prizes,
+    #[name = "operations"]
+    Operations,
+}
+
+impl SpendCategory {
+    const ALL: [SpendCategory; 3] = [SpendCategory::Events, SpendCategory::Prizes, SpendCategory::Operations];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SpendCategory::Events => "events",
+            SpendCategory::Prizes => "prizes",
+            SpendCategory::Operations => "operations",
+        }
+    }
+
+    fn budget_setting(&self) -> String {
+        format!("treasury.budget.{}", self.key())
+    }
+}
+
+/// See and manage the shared treasury
+#[poise::command(
+    slash_command,
+    category = "User",
+    guild_only,
+    subcommands("treasury_balance", "treasury_spend", "treasury_redistribute", "treasury_budget", "treasury_fund")
+)]
+pub async fn treasury(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// See how much the shared treasury holds
+#[poise::command(slash_command, rename = "balance")]
+pub async fn treasury_balance(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    match data.database.get_balance(TREASURY_ACCOUNT).await {
+        Ok(balance) => {
+            ctx.say(format!(
+                "🏦 The treasury holds **{} Slumcoins**\n\
+                It collects game wagers, shop and ticket sales and auction proceeds.",
+                balance
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error getting treasury balance: {}", e);
+            ctx.say("Error retrieving treasury balance.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pay someone out of the treasury
+#[poise::command(slash_command, rename = "spend", check = "is_admin")]
+pub async fn treasury_spend(
+    ctx: Context<'_>,
+    #[description = "User to pay from the treasury"] user: serenity::User,
+    #[description = "Amount of coins to pay"] amount: i64,
+    #[description = "Budget category for this spend"] category: SpendCategory,
+    #[description = "What the payment is for"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let to_user_id = user.id.to_string();
+
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    match data.database.get_user(&to_user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            ctx.say("Target user is not registered!").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            ctx.say("Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if treasury_balance < amount {
+        ctx.say(format!("The treasury only holds {} Slumcoins.", treasury_balance)).await?;
+        return Ok(());
+    }
+
+    let message = match &reason {
+        Some(reason) => format!("Treasury {} spend by {}: {}", category.key(), ctx.author().name, reason),
+        None => format!("Treasury {} spend by {}", category.key(), ctx.author().name),
+    };
+    let transaction = Transaction::system(TREASURY_ACCOUNT, &to_user_id, amount, "treasury_spend", Some(message));
+
+    match data.database.record_treasury_spend(&transaction, &guild_id, category.key(), &ctx.author().id.to_string()).await {
+        Ok(()) => {
+            log_admin_action(ctx, "treasury_spend", to_user_id, Some(amount), reason).await;
+            let mut response = format!(
+                "Paid **{} Slumcoins** to <@{}> from the treasury ({})",
+                amount, user.id, category.key()
+            );
+
+            // Show how much of this month's budget is left for the category
+            let budget = config::get_i64(&data.database, &guild_id, &category.budget_setting()).await?;
+            if budget > 0 {
+                let month = Utc::now().format("%Y-%m").to_string();
+                let spent = data.database
+                    .get_treasury_spending_by_category(&guild_id, &month)
+                    .await?
+                    .into_iter()
+                    .find(|(key, _)| key == category.key())
+                    .map(|(_, total)| total)
+                    .unwrap_or(0);
+                response.push_str(&format!("\n{} budget: {} / {} used this month", category.key(), spent, budget));
+                if spent > budget {
+                    response.push_str(" ⚠️ over budget");
+                }
+            }
+
+            ctx.say(response).await?;
+        }
+        Err(e) => {
+            error!("Error recording treasury spend: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error processing treasury spend.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Split treasury coins evenly between all users
+#[poise::command(slash_command, rename = "redistribute", check = "is_admin")]
+pub async fn treasury_redistribute(
+    ctx: Context<'_>,
+    #[description = "Total coins to split evenly between all registered users"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let users = data.database.get_all_users().await?;
+    if users.is_empty() {
+        ctx.say("There are no registered users to pay.").await?;
+        return Ok(());
+    }
+
+    // Any remainder that doesn't split evenly stays in the treasury
+    let share = amount / users.len() as i64;
+    if share == 0 {
+        ctx.say(format!("{} Slumcoins isn't enough to give each of the {} users a coin.", amount, users.len())).await?;
+        return Ok(());
+    }
+
+    let total = share * users.len() as i64;
+    let treasury_balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+    if treasury_balance < total {
+        ctx.say(format!("The treasury only holds {} Slumcoins.", treasury_balance)).await?;
+        return Ok(());
+    }
+
+    let message = format!("Treasury redistribution by {}", ctx.author().name);
+    let payouts: Vec<Transaction> = users
+        .iter()
+        .map(|user| Transaction::system(TREASURY_ACCOUNT, &user.discord_id, share, "treasury_redistribute", Some(message.clone())))
+        .collect();
+
+    match data.database.apply_transactions(&payouts).await {
+        Ok(()) => {
+            log_admin_action(ctx, "treasury_redistribute", TREASURY_ACCOUNT, Some(total), None).await;
+            ctx.say(format!(
+                "Redistributed **{} Slumcoins** from the treasury: {} each to {} users.",
+                total, share, users.len()
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error redistributing treasury: {}", e);
+            ctx.say(database_error_message(ctx, &e, "Error processing redistribution. No coins were moved.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show treasury spending against each category's monthly budget
+#[poise::command(slash_command, rename = "budget", check = "is_admin")]
+pub async fn treasury_budget(
+    ctx: Context<'_>,
+    #[description = "Month to report as YYYY-MM (default: current month)"] month: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let month = month.unwrap_or_else(|| Utc::now().format("%Y-%m").to_string());
+
+    if chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_err() {
+        ctx.say("Month must be formatted as YYYY-MM.").await?;
+        return Ok(());
+    }
+
+    let spending = match data.database.get_treasury_spending_by_category(&guild_id, &month).await {
+        Ok(spending) => spending,
+        Err(e) => {
+            error!("Error getting treasury spending: {}", e);
+            ctx.say("Error retrieving treasury budget.").await?;
+            return Ok(());
+        }
+    };
+
+    let mut response = format!("**Treasury Budget for {}**\n", month);
+    let mut total = 0;
+    for category in SpendCategory::ALL {
+        let spent = spending
+            .iter()
+            .find(|(key, _)| key == category.key())
+            .map(|(_, total)| *total)
+            .unwrap_or(0);
+        let budget = config::get_i64(&data.database, &guild_id, &category.budget_setting()).await?;
+        total += spent;
+
+        if budget > 0 {
+            response.push_str(&format!("• {}: **{}** / {}\n", category.key(), spent, budget));
+        } else {
+            response.push_str(&format!("• {}: **{}**\n", category.key(), spent));
+        }
+    }
+    response.push_str(&format!("\nTotal spent: **{} Slumcoins**", total));
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Mint new coins into the treasury
+#[poise::command(slash_command, rename = "fund", check = "is_admin")]
+pub async fn treasury_fund(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to mint into the treasury"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    if amount <= 0 {
+        ctx.say("Amount must be greater than 0.").await?;
+        return Ok(());
+    }
+
+    let transaction = Transaction::system(
+        SYSTEM_ACCOUNT,
+        TREASURY_ACCOUNT,
+        amount,
+        "mint",
+        Some(format!("Treasury funded by {}", ctx.author().name)),
+    );
+
+    match data.database.apply_transaction(&transaction).await {
+        Ok(()) => {
+            log_admin_action(ctx, "treasury_fund", TREASURY_ACCOUNT, Some(amount), None).await;
+            let balance = data.database.get_balance(TREASURY_ACCOUNT).await?;
+            ctx.say(format!("Minted {} Slumcoins into the treasury. Treasury balance: {}", amount, balance)).await?;
+        }
+        Err(e) => {
+            error!("Error funding treasury: {}", e);
+            ctx.say("Error processing transaction.").await?;
+        }
+    }
+
+    Ok(())
+}
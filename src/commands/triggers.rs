@@ -0,0 +1,116 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{Context, Error};
+use super::{is_admin, log_admin_action};
+
+// Discord message limit is 2000; leave room for the list formatting
+const MAX_RESPONSE_LENGTH: usize = 1000;
+
+/// Manage automatic replies to phrases
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("trigger_list", "trigger_add", "trigger_remove")
+)]
+pub async fn trigger(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show this server's triggers
+#[poise::command(slash_command, rename = "list")]
+pub async fn trigger_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let triggers = match data.database.get_triggers(&guild_id).await {
+        Ok(triggers) => triggers,
+        Err(e) => {
+            error!("Error loading triggers: {}", e);
+            ctx.say("Error loading triggers.").await?;
+            return Ok(());
+        }
+    };
+
+    if triggers.is_empty() {
+        ctx.say("No triggers set up. Add one with `/trigger add`.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Triggers**\n".to_string();
+    for trigger in &triggers {
+        response.push_str(&format!("`#{}` \"{}\" → \"{}\"", trigger.id, trigger.phrase, trigger.response));
+        if let Some(user_id) = &trigger.user_id {
+            response.push_str(&format!(" (only <@{}>)", user_id));
+        }
+        response.push('\n');
+    }
+
+    ctx.send(poise::CreateReply::default()
+        .content(response)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())).await?;
+    Ok(())
+}
+
+/// Reply automatically whenever a phrase is said
+#[poise::command(slash_command, rename = "add")]
+pub async fn trigger_add(
+    ctx: Context<'_>,
+    #[description = "Phrase to look for in messages (case-insensitive)"] phrase: String,
+    #[description = "What the bot replies with"] response: String,
+    #[description = "Only trigger on messages from this user"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let phrase = phrase.trim().to_string();
+    let response = response.trim().to_string();
+
+    if phrase.is_empty() || response.is_empty() || response.len() > MAX_RESPONSE_LENGTH {
+        ctx.say(format!("Triggers need a phrase and a response of at most {} characters.", MAX_RESPONSE_LENGTH)).await?;
+        return Ok(());
+    }
+
+    let user_id = user.as_ref().map(|user| user.id.to_string());
+    match data.database.add_trigger(&guild_id, &phrase, &response, user_id.as_deref(), &ctx.author().id.to_string()).await {
+        Ok(trigger_id) => {
+            data.triggers.invalidate(&guild_id).await;
+            log_admin_action(ctx, "trigger_add", format!("trigger #{}", trigger_id), None, Some(phrase.clone())).await;
+            ctx.say(format!("Added trigger `#{}` for \"{}\".", trigger_id, phrase)).await?;
+        }
+        Err(e) => {
+            error!("Error adding trigger: {}", e);
+            ctx.say("Error adding trigger.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete a trigger
+#[poise::command(slash_command, rename = "remove")]
+pub async fn trigger_remove(
+    ctx: Context<'_>,
+    #[description = "Trigger number from /trigger list"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.remove_trigger(&guild_id, id).await {
+        Ok(true) => {
+            data.triggers.invalidate(&guild_id).await;
+            log_admin_action(ctx, "trigger_remove", format!("trigger #{}", id), None, None).await;
+            ctx.say(format!("Removed trigger `#{}`.", id)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("No trigger `#{}` in this server.", id)).await?;
+        }
+        Err(e) => {
+            error!("Error removing trigger: {}", e);
+            ctx.say("Error removing trigger.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use tracing::error;
+
+use crate::{Context, Error, config};
+use crate::database::{DatabaseError, Transaction};
+use crate::ledger::TREASURY_ACCOUNT;
+use super::{database_error_message, guild_cooldown, is_admin, log_admin_action, AccountFrozen};
+
+// How long a round takes answers
+const TRIVIA_ANSWER_SECONDS: u64 = 20;
+// Share of `trivia.reward` paid to the first, second and third right answers
+const TRIVIA_PAYOUT_PERCENTS: [i64; 3] = [100, 50, 25];
+const MAX_CATEGORY_LENGTH: usize = 30;
+const MAX_QUESTION_LENGTH: usize = 250;
+// Discord's limit for button labels, less room for the letter
+const MAX_ANSWER_LENGTH: usize = 76;
+const LETTERS: [char; 4] = ['A', 'B', 'C', 'D'];
+
+pub async fn autocomplete_trivia_category(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let partial = partial.to_lowercase();
+
+    match ctx.data().database.get_trivia_categories(&guild_id).await {
+        Ok(categories) => categories
+            .into_iter()
+            .map(|(category, _)| category)
+            .filter(|category| category.contains(&partial))
+            .take(25)
+            .collect(),
+        Err(e) => {
+            error!("Error loading trivia categories: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Multiple-choice trivia with coin prizes for the fastest right answers
+#[poise::command(
+    slash_command,
+    category = "Games",
+    guild_only,
+    subcommands("trivia_play", "trivia_categories", "trivia_add", "trivia_remove")
+)]
+pub async fn trivia(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn answer_buttons(ctx_id: u64, choices: &[String], disabled: bool) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(
+        choices
+            .iter()
+            .zip(LETTERS)
+            .enumerate()
+            .map(|(index, (choice, letter))| {
+                serenity::CreateButton::new(format!("{}answer{}", ctx_id, index))
+                    .label(format!("{}. {}", letter, choice))
+                    .style(serenity::ButtonStyle::Primary)
+                    .disabled(disabled)
+            })
+            .collect(),
+    )]
+}
+
+/// Ask a trivia question in this channel
+#[poise::command(slash_command, rename = "play", check = "guild_cooldown")]
+pub async fn trivia_play(
+    ctx: Context<'_>,
+    #[description = "Category to draw from (default: any)"]
+    #[autocomplete = "autocomplete_trivia_category"]
+    category: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let category = category.map(|category| category.trim().to_lowercase()).filter(|category| !category.is_empty());
+
+    let Some(question) = data.database.get_random_trivia_question(&guild_id, category.as_deref()).await? else {
+        ctx.say("No trivia questions in that category. See `/trivia categories`.").await?;
+        return Ok(());
+    };
+    let reward = config::get_i64(&data.database, &guild_id, "trivia.reward").await?.max(0);
+
+    let mut choices = question.wrong_answers.clone();
+    choices.push(question.answer.clone());
+    choices.shuffle(&mut rand::thread_rng());
+    let correct = choices.iter().position(|choice| *choice == question.answer).unwrap_or_default();
+
+    let closes_at = chrono::Utc::now().timestamp() + TRIVIA_ANSWER_SECONDS as i64;
+    let prizes: Vec<String> = TRIVIA_PAYOUT_PERCENTS.iter().map(|percent| (reward * percent / 100).to_string()).collect();
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("❓ Trivia · {}", question.category))
+        .description(format!("**{}**\n\nAnswers close <t:{}:R>. One answer each!", question.question, closes_at))
+        .footer(serenity::CreateEmbedFooter::new(format!("Fastest right answers win {} Slumcoins", prizes.join(" / "))))
+        .color(0x1abc9c);
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(embed.clone())
+        .components(answer_buttons(ctx_id, &choices, false))).await?;
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(TRIVIA_ANSWER_SECONDS);
+    let mut answered = HashSet::new();
+    let mut winners: Vec<String> = Vec::new();
+
+    // Answers stay open for the full window, unless every prize is already taken
+    while winners.len() < TRIVIA_PAYOUT_PERCENTS.len() {
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+            .timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+            .await;
+        let Some(press) = press else {
+            break;
+        };
+
+        let ephemeral = |content: String| {
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+            )
+        };
+        let user_id = press.user.id.to_string();
+        let Some(picked) = press.data.custom_id
+            .trim_start_matches(&ctx_id.to_string())
+            .strip_prefix("answer")
+            .and_then(|index| index.parse::<usize>().ok())
+        else {
+            continue;
+        };
+
+        if answered.contains(&user_id) {
+            press.create_response(ctx.serenity_context(), ephemeral("You've already answered this one.".to_string())).await?;
+            continue;
+        }
+        if let Err(e) = data.database.require_user(&user_id).await {
+            if !matches!(e, DatabaseError::NotRegistered(_)) {
+                error!("Database error: {}", e);
+            }
+            press.create_response(ctx.serenity_context(), ephemeral(database_error_message(ctx, &e, "Database error occurred."))).await?;
+            continue;
+        }
+        if data.database.is_frozen(&user_id).await? {
+            press.create_response(ctx.serenity_context(), ephemeral(AccountFrozen.to_string())).await?;
+            continue;
+        }
+
+        answered.insert(user_id.clone());
+        let response = if picked == correct {
+            winners.push(user_id);
+            format!("✅ Right! You were #{} to get it.", winners.len())
+        } else {
+            "❌ Not quite. The answer is revealed when the round ends.".to_string()
+        };
+        press.create_response(ctx.serenity_context(), ephemeral(response)).await?;
+    }
+
+    let payouts: Vec<Transaction> = winners
+        .iter()
+        .zip(TRIVIA_PAYOUT_PERCENTS)
+        .map(|(winner, percent)| (winner, reward * percent / 100))
+        .filter(|(_, prize)| *prize > 0)
+        .map(|(winner, prize)| Transaction::system(
+            TREASURY_ACCOUNT,
+            winner,
+            prize,
+            "trivia_reward",
+            Some(format!("Trivia question #{}", question.id)),
+        ))
+        .collect();
+
+    let mut results = format!("The answer was **{}. {}**", LETTERS[correct], question.answer);
+    if winners.is_empty() {
+        results.push_str("\nNobody got it this time.");
+    }
+    for (place, winner) in winners.iter().enumerate() {
+        let prize = reward * TRIVIA_PAYOUT_PERCENTS[place] / 100;
+        results.push_str(&format!("\n{}. <@{}> · {} Slumcoins", place + 1, winner, prize));
+    }
+    if let Err(e) = data.database.apply_transactions(&payouts).await {
+        error!("Error paying trivia rewards: {}", e);
+        results.push_str("\nThe treasury couldn't pay out the prizes this time.");
+    }
+
+    reply.edit(ctx, poise::CreateReply::default()
+        .embed(embed
+            .description(format!("**{}**", question.question))
+            .field("Results", results, false))
+        .components(answer_buttons(ctx_id, &choices, true))).await?;
+    Ok(())
+}
+
+/// List the trivia categories you can play
+#[poise::command(slash_command, rename = "categories")]
+pub async fn trivia_categories(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let categories = ctx.data().database.get_trivia_categories(&guild_id).await?;
+
+    if categories.is_empty() {
+        ctx.say("There are no trivia questions yet.").await?;
+        return Ok(());
+    }
+
+    let mut response = String::from("❓ **Trivia categories**\n");
+    for (category, questions) in categories {
+        response.push_str(&format!("• **{}** · {} question(s)\n", category, questions));
+    }
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Add a custom trivia question for this server
+#[poise::command(slash_command, rename = "add", check = "is_admin")]
+#[allow(clippy::too_many_arguments)]
+pub async fn trivia_add(
+    ctx: Context<'_>,
+    #[description = "Category, new or existing"]
+    #[autocomplete = "autocomplete_trivia_category"]
+    category: String,
+    #[description = "The question"] question: String,
+    #[description = "The right answer"] answer: String,
+    #[description = "A wrong answer"] wrong1: String,
+    #[description = "Another wrong answer"] wrong2: String,
+    #[description = "A third wrong answer"] wrong3: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let category = category.trim().to_lowercase();
+    let question = question.trim().to_string();
+    let answer = answer.trim().to_string();
+    let wrong_answers: Vec<String> = [Some(wrong1), Some(wrong2), wrong3]
+        .into_iter()
+        .flatten()
+        .map(|wrong| wrong.trim().to_string())
+        .filter(|wrong| !wrong.is_empty())
+        .collect();
+
+    if category.is_empty() || category.chars().count() > MAX_CATEGORY_LENGTH {
+        ctx.say(format!("Categories need 1 to {} characters.", MAX_CATEGORY_LENGTH)).await?;
+        return Ok(());
+    }
+    if question.is_empty() || question.chars().count() > MAX_QUESTION_LENGTH {
+        ctx.say(format!("Questions need 1 to {} characters.", MAX_QUESTION_LENGTH)).await?;
+        return Ok(());
+    }
+    if wrong_answers.len() < 2 {
+        ctx.say("A question needs at least two wrong answers.").await?;
+        return Ok(());
+    }
+
+    let mut seen = HashSet::new();
+    let all_answers = std::iter::once(&answer).chain(&wrong_answers);
+    for choice in all_answers {
+        if choice.is_empty() || choice.chars().count() > MAX_ANSWER_LENGTH {
+            ctx.say(format!("Answers need 1 to {} characters.", MAX_ANSWER_LENGTH)).await?;
+            return Ok(());
+        }
+        if !seen.insert(choice.to_lowercase()) {
+            ctx.say("Every answer has to be different.").await?;
+            return Ok(());
+        }
+    }
+
+    match data.database.add_trivia_question(&guild_id, &category, &question, &answer, &wrong_answers, &ctx.author().id.to_string()).await {
+        Ok(question_id) => {
+            log_admin_action(ctx, "trivia_add", format!("trivia question #{}", question_id), None, Some(question)).await;
+            ctx.say(format!("Added question #{} to **{}**.", question_id, category)).await?;
+        }
+        Err(e) => {
+            error!("Error adding trivia question: {}", e);
+            ctx.say("Error adding the question.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove one of this server's custom trivia questions
+#[poise::command(slash_command, rename = "remove", check = "is_admin")]
+pub async fn trivia_remove(
+    ctx: Context<'_>,
+    #[description = "Question number, shown when it was added"] id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match ctx.data().database.remove_trivia_question(&guild_id, id).await {
+        Ok(true) => {
+            log_admin_action(ctx, "trivia_remove", format!("trivia question #{}", id), None, None).await;
+            ctx.say(format!("Removed question #{}.", id)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("This server has no custom question #{}.", id)).await?;
+        }
+        Err(e) => {
+            error!("Error removing trivia question: {}", e);
+            ctx.say("Error removing the question.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -3,13 +3,21 @@ use poise::serenity_prelude as serenity;
 use tracing::error;
 use chrono::Utc;
 use tokio::time::{sleep, Duration as TokioDuration};
-use uuid::Uuid;
 
-use crate::{Context, Error, database::User};
-use crate::database::Transaction;
-use super::can_register_others;
-
-#[poise::command(slash_command)]
+use crate::{Context, Error, config};
+use crate::auction::{BidIncrement, DepositRule};
+use crate::database::{DatabaseError, PinnedLeaderboard, Transaction, TransactionFilter};
+use crate::ledger::{self, FeeSchedule, LedgerError};
+use crate::leaderboard;
+use crate::bidding;
+use crate::registration;
+use super::{
+    admin_audit_entry, author_voice_channel, autocomplete_counterparty, autocomplete_season, can_register_others, confirm, cooldown, format_duration, is_admin,
+    ephemeral_response, not_frozen, page_buttons, resolve_target_user, say_private, voice_channel_members,
+};
+
+/// Register yourself (or someone else, as an admin) for Slumcoins
+#[poise::command(slash_command, category = "User", guild_only)]
 pub async fn register(
     ctx: Context<'_>,
     #[description = "User to register (admin only)"] user: Option<serenity::User>,
@@ -18,7 +26,7 @@ pub async fn register(
     let (target_user, is_registering_other) = match user {
         Some(mentioned_user) => {
             if !can_register_others(ctx).await? {
-                ctx.say("You don't have permission to register other users.\n\
+                say_private(ctx, "You don't have permission to register other users.\n\
                         **Required:** Bot owner, Administrator permission, or 'Currency Admin' role").await?;
                 return Ok(());
             }
@@ -30,111 +38,270 @@ pub async fn register(
     let user_id = target_user.id.to_string();
     let username = target_user.name.clone();
 
-    match data.database.get_user(&user_id).await {
-        Ok(Some(_)) => {
+    match registration::register_user(&data.database, &data.crypto, &user_id, &username).await {
+        Ok(true) => {
+            let response = if is_registering_other {
+                format!(
+                    "registered {} successfully. bub boils the seed\n\
+                    Starting balance: 0 coins.\n\
+                    {} can now use `/balance` and receive coins.",
+                    username, username
+                )
+            } else {
+                "Registration successful. bub boils the seed".to_string()
+            };
+            say_private(ctx, response).await?;
+        }
+        Ok(false) => {
             let response = if is_registering_other {
                 format!("{} is already registered", username)
             } else {
                 "You're already registered".to_string()
             };
-            ctx.say(response).await?;
+            say_private(ctx, response).await?;
         }
-        Ok(None) => {
-            // Generate new keypair for user
-            match data.crypto.generate_keypair() {
-                Ok((public_key, private_key)) => {
-                    // Encrypt private key
-                    match data.crypto.encrypt_private_key(&private_key, &user_id) {
-                        Ok(encrypted_private_key) => {
-                            let user = User {
-                                discord_id: user_id.clone(),
-                                username: username.clone(),
-                                public_key,
-                                encrypted_private_key,
-                                nonce: 0,
-                                created_at: Utc::now(),
-                                updated_at: Utc::now(),
-                            };
-
-                            match data.database.create_user(&user).await {
-                                Ok(()) => {
-                                    let response = if is_registering_other {
-                                        format!(
-                                            "registered {} successfully. bub boils the seed\n\
-                                            Starting balance: 0 coins.\n\
-                                            {} can now use `/balance` and receive coins.",
-                                            username, username
-                                        )
-                                    } else {
-                                        "Registration successful. bub boils the seed".to_string()
-                                    };
-                                    ctx.say(response).await?;
-                                }
-                                Err(e) => {
-                                    error!("Database error creating user: {}", e);
-                                    ctx.say("Registration failed. Please try again.").await?;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error encrypting private key: {}", e);
-                            ctx.say("Registration failed. Please try again.").await?;
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Error generating keypair: {}", e);
-                    ctx.say("Registration failed. Please try again.").await?;
-                }
+        Err(e) => {
+            error!("Error registering user: {}", e);
+            say_private(ctx, "Registration failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete your Slumcoin account (or someone else's, as an admin). Any balance goes to the treasury.
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn unregister(
+    ctx: Context<'_>,
+    #[description = "User to unregister (admin only)"] user: Option<serenity::User>,
+    #[description = "Why they're being removed (admin only, shown in /auditlog)"] reason: Option<String>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let (target_user, is_unregistering_other) = match user.filter(|user| user.id != ctx.author().id) {
+        Some(mentioned_user) => {
+            if !is_admin(ctx).await? {
+                say_private(ctx, "You don't have permission to unregister other users.").await?;
+                return Ok(());
             }
+            (mentioned_user, true)
+        }
+        None => (ctx.author().clone(), false),
+    };
+    let user_id = target_user.id.to_string();
+    let who = if is_unregistering_other { format!("{} isn't", target_user.name) } else { "You're not".to_string() };
+
+    if data.database.get_user(&user_id).await?.is_none() {
+        say_private(ctx, format!("{} registered.", who)).await?;
+        return Ok(());
+    }
+
+    // Anything still owed or locked up has to be settled first
+    let blocker = if !data.database.get_active_escrows(&user_id).await?.is_empty() {
+        Some("open escrows")
+    } else if data.database.has_open_loan(&user_id).await? {
+        Some("an unsettled loan")
+    } else if data.database.get_total_vault_balance(&user_id).await? > 0 {
+        Some("coins in the vault")
+    } else {
+        data.database.get_unregister_blocker(&user_id).await?
+    };
+    if let Some(blocker) = blocker {
+        say_private(ctx, format!("Can't unregister an account with {}. Settle that first.", blocker)).await?;
+        return Ok(());
+    }
+
+    let balance = data.database.get_balance(&user_id).await?;
+    let prompt = if is_unregistering_other {
+        format!("Unregister <@{}>? Their **{} Slumcoins** go to the treasury.", user_id, balance)
+    } else {
+        format!("Unregister your account? Your **{} Slumcoins** go to the treasury.", balance)
+    };
+    if !confirm(ctx, prompt).await? {
+        return Ok(());
+    }
+
+    let sweep = (balance > 0).then(|| Transaction::system(
+        &user_id,
+        ledger::TREASURY_ACCOUNT,
+        balance,
+        "unregister",
+        Some(format!("Balance of unregistered account {}", target_user.name)),
+    ));
+    let entry = is_unregistering_other.then(|| admin_audit_entry(ctx, "unregister", user_id.clone(), Some(balance), reason));
+
+    match data.database.archive_user(&user_id, &ctx.author().id.to_string(), sweep.as_ref(), entry.as_ref()).await {
+        Ok(true) => {
+            data.counterparties.invalidate(&[&user_id]).await;
+            let response = if is_unregistering_other {
+                format!("Unregistered {}. {} Slumcoins went to the treasury.", target_user.name, balance)
+            } else {
+                "Your account is archived. Use `/register` to come back.".to_string()
+            };
+            say_private(ctx, response).await?;
+        }
+        Ok(false) => {
+            say_private(ctx, "The balance changed while you were deciding. Please try again.").await?;
         }
         Err(e) => {
-            error!("Database error checking user: {}", e);
-            ctx.say("Registration failed. Please try again.").await?;
+            error!("Error archiving user {}: {}", user_id, e);
+            say_private(ctx, "Unregistering failed. Please try again.").await?;
         }
     }
 
     Ok(())
 }
 
-#[poise::command(slash_command)]
-pub async fn balance(ctx: Context<'_>) -> Result<(), Error> {
+// Vault savings in this server, or across every server when used in DMs
+async fn vault_balance(ctx: Context<'_>, discord_id: &str) -> Result<i64, DatabaseError> {
+    match ctx.guild_id() {
+        Some(guild_id) => ctx.data().database.get_vault_balance(&guild_id.to_string(), discord_id).await,
+        None => ctx.data().database.get_total_vault_balance(discord_id).await,
+    }
+}
+
+// Wallet, vault and the current value of any shares, in this server or across every server in DMs
+async fn holdings(ctx: Context<'_>, discord_id: &str) -> Result<(i64, i64, i64), DatabaseError> {
+    let guild_id = ctx.guild_id().map(|id| id.to_string());
+    tokio::try_join!(
+        ctx.data().database.get_balance(discord_id),
+        vault_balance(ctx, discord_id),
+        ctx.data().database.get_portfolio_value(guild_id.as_deref(), discord_id),
+    )
+}
+
+/// Check your Slumcoin balance, or someone else's if they've made it public
+#[poise::command(slash_command, category = "User")]
+pub async fn balance(
+    ctx: Context<'_>,
+    #[description = "User to check (only works if they've made their balance public)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
     let data = &ctx.data();
+
+    if let Some(target) = user.filter(|user| user.id != ctx.author().id) {
+        let target_id = target.id.to_string();
+
+        // Check permission before registration so private users don't leak whether they're registered
+        let public_balance = match data.database.get_user_preferences(&target_id).await {
+            Ok(preferences) => preferences.public_balance,
+            Err(e) => {
+                error!("Database error: {}", e);
+                say_private(ctx, "Database error occurred.").await?;
+                return Ok(());
+            }
+        };
+        if !public_balance && !is_admin(ctx).await? {
+            say_private(ctx, format!("{} keeps their balance private.", target.name)).await?;
+            return Ok(());
+        }
+
+        match data.database.get_user(&target_id).await {
+            Ok(Some(_)) => {
+                match holdings(ctx, &target_id).await {
+                    Ok((balance, vault, stocks)) => {
+                        say_private(ctx, format!(
+                            "{}'s balance: {} coins\nVault: {} coins\nStocks: {} coins\nNet worth: {} coins",
+                            target.name, balance, vault, stocks, balance + vault + stocks
+                        )).await?;
+                    }
+                    Err(e) => {
+                        error!("Error getting balance: {}", e);
+                        say_private(ctx, "Error retrieving balance.").await?;
+                    }
+                }
+            }
+            Ok(None) => {
+                say_private(ctx, format!("{} isn't registered.", target.name)).await?;
+            }
+            Err(e) => {
+                error!("Database error: {}", e);
+                say_private(ctx, "Database error occurred.").await?;
+            }
+        }
+        return Ok(());
+    }
+
     let user_id = ctx.author().id.to_string();
 
     match data.database.get_user(&user_id).await {
         Ok(Some(_)) => {
-            match data.database.get_balance(&user_id).await {
-                Ok(balance) => {
-                    let response = format!("Your balance: {} coins", balance);
-                    ctx.say(response).await?;
+            match holdings(ctx, &user_id).await {
+                Ok((balance, vault, stocks)) => {
+                    let response = format!(
+                        "Your balance: {} coins\nVault: {} coins\nStocks: {} coins\nNet worth: {} coins",
+                        balance, vault, stocks, balance + vault + stocks
+                    );
+                    say_private(ctx, response).await?;
                 }
                 Err(e) => {
                     error!("Error getting balance: {}", e);
-                    ctx.say("Error retrieving balance.").await?;
+                    say_private(ctx, "Error retrieving balance.").await?;
                 }
             }
         }
         Ok(None) => {
-            ctx.say("You're not registered! Use `/register` first.").await?;
+            say_private(ctx, "You're not registered! Use `/register` first.").await?;
         }
         Err(e) => {
             error!("Database error: {}", e);
-            ctx.say("Database error occurred.").await?;
+            say_private(ctx, "Database error occurred.").await?;
         }
     }
 
     Ok(())
 }
 
-#[poise::command(slash_command)]
+/// See your leaderboard position and how far the next rank is
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn rank(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    match data.database.get_rank(&user_id).await {
+        Ok(Some(info)) => {
+            let mut response = format!(
+                "You're **#{}** of {} with {} coins",
+                info.rank, info.total_users, info.balance
+            );
+            match info.next_balance {
+                Some(next_balance) => response.push_str(&format!(
+                    "\n{} more to reach #{}",
+                    next_balance - info.balance,
+                    info.rank - 1
+                )),
+                None => response.push_str("\nNobody is ahead of you 👑"),
+            }
+            ctx.say(response).await?;
+        }
+        Ok(None) => {
+            ctx.say("You're not registered! Use `/register` first.").await?;
+        }
+        Err(e) => {
+            error!("Error getting rank: {}", e);
+            ctx.say("Error retrieving your rank.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Send Slumcoins to another user
+#[poise::command(slash_command, category = "User", guild_only, check = "not_frozen")]
 pub async fn send(
     ctx: Context<'_>,
-    #[description = "User to send coins to"] user: serenity::User,
     #[description = "Amount of coins to send"] amount: i64,
+    #[description = "User to send coins to"] user: Option<serenity::User>,
+    #[description = "Pick someone you've traded with recently"]
+    #[autocomplete = "autocomplete_counterparty"]
+    recent: Option<String>,
 ) -> Result<(), Error> {
+    let user = match resolve_target_user(ctx, user, recent).await? {
+        Some(user) => user,
+        None => {
+            ctx.say("Pick a `user` or one of your `recent` contacts to send to.").await?;
+            return Ok(());
+        }
+    };
 
-    
     let data = &ctx.data();
     let from_user_id = ctx.author().id.to_string();
     let to_user_id = user.id.to_string();
@@ -157,162 +324,280 @@ pub async fn send(
         return Ok(());
     }
 
-    // Check if sender is registered
-    match data.database.get_user(&from_user_id).await {
-        Ok(Some(_)) => {
-            match data.database.get_user(&to_user_id).await {
-                Ok(Some(_)) => {
-                    match data.database.get_balance(&from_user_id).await {
-                        Ok(sender_balance) => {
-                            if sender_balance < amount {
-                                ctx.say(format!(
-                                    "UR BROKE BUB! You have {} Slumcoins",
-                                    sender_balance
-                                )).await?;
-                                return Ok(());
-                            }
-
-                            match data.database.get_balance(&to_user_id).await {
-                                Ok(recipient_balance) => {
-                                    let new_sender_balance = sender_balance - amount;
-                                    let new_recipient_balance = recipient_balance + amount;
-
-                                    // Update both balances
-                                    match data.database.update_balance(&from_user_id, new_sender_balance).await {
-                                        Ok(()) => {
-                                            match data.database.update_balance(&to_user_id, new_recipient_balance).await {
-                                                Ok(()) => {
-                                                    // Log the transaction
-                                                    let transaction = Transaction {
-                                                        id: Uuid::new_v4().to_string(),
-                                                        from_user: from_user_id.clone(),
-                                                        to_user: to_user_id.clone(),
-                                                        amount,
-                                                        transaction_type: "transfer".to_string(),
-                                                        message: Some(format!("Sent by {}", ctx.author().name)),
-                                                        nonce: 0,
-                                                        signature: String::new(),
-                                                        timestamp_unix: Utc::now().timestamp(),
-                                                        created_at: Utc::now(),
-                                                    };
-
-                                                    if let Err(e) = data.database.add_transaction(&transaction).await {
-                                                        error!("Failed to log transaction: {}", e);
-                                                    }
-
-                                                    ctx.say(format!(
-                                                        "sent **{} Slumcoins** to <@{}>\n\
-                                                         new balance: {} Slumcoins",
-                                                        amount, user.id, new_sender_balance
-                                                    )).await?;
-                                                }
-                                                Err(e) => {
-                                                    error!("Error updating recipient balance: {}", e);
-                                                    // Rollback sender balance
-                                                    let _ = data.database.update_balance(&from_user_id, sender_balance).await;
-                                                    ctx.say("Transfer failed. Please try again.").await?;
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            error!("Error updating sender balance: {}", e);
-                                            ctx.say("Transfer failed. Please try again.").await?;
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Error getting recipient balance: {}", e);
-                                    ctx.say("Error retrieving recipient balance.").await?;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error getting sender balance: {}", e);
-                            ctx.say("Error retrieving your balance.").await?;
-                        }
-                    }
-                }
-                Ok(None) => {
-                    ctx.say(format!("<@{}> is not registered. They need to use `/register` first.", user.id)).await?;
-                }
-                Err(e) => {
-                    error!("Database error checking recipient: {}", e);
-                    ctx.say("Database error occurred.").await?;
-                }
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    match ledger::check_daily_limit(&data.database, &guild_id, &from_user_id, amount).await {
+        Ok(()) => {}
+        Err(LedgerError::DailyLimitExceeded { limit, remaining }) => {
+            ctx.say(format!("That's over your daily limit of {} Slumcoins. You can send {} more today.", limit, remaining)).await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let fees = FeeSchedule::for_guild(&data.database, &guild_id).await?;
+
+    // Big transfers show their fee and what's left before going ahead. One that can't go
+    // through skips the prompt and gets its error from execute_transfer below.
+    let threshold = config::get_i64(&data.database, &guild_id, "confirm.threshold").await?;
+    if threshold > 0 && amount > threshold {
+        if let Ok(preview) = ledger::simulate_transfer(&data.database, &from_user_id, &to_user_id, amount, fees).await {
+            let mut prompt = format!("Send **{} Slumcoins** to <@{}>?
+", amount, user.id);
+            if preview.fee > 0 {
+                prompt.push_str(&format!("fee: {} Slumcoins
+", preview.fee));
+            }
+            prompt.push_str(&format!("balance afterwards: {} Slumcoins", preview.sender_balance_after));
+            if !confirm(ctx, prompt).await? {
+                return Ok(());
             }
         }
-        Ok(None) => {
+    }
+
+    // Validate, sign and record the transfer and its fee in one step
+    let message = Some(format!("Sent by {}", ctx.author().name));
+    match ledger::execute_transfer(&data.database, &data.crypto, &from_user_id, &to_user_id, amount, fees, message).await {
+        Ok(preview) => {
+            data.counterparties.invalidate(&[&from_user_id, &to_user_id]).await;
+
+            let mut response = format!("sent **{} Slumcoins** to <@{}>\n", amount, user.id);
+            if preview.fee > 0 {
+                response.push_str(&format!("fee: {} Slumcoins\n", preview.fee));
+            }
+            response.push_str(&format!("new balance: {} Slumcoins", preview.sender_balance_after));
+            ctx.say(response).await?;
+        }
+        Err(LedgerError::NotRegistered(id)) if id == from_user_id => {
             ctx.say("You're not registered! Use `/register` first.").await?;
         }
+        Err(LedgerError::NotRegistered(_)) => {
+            ctx.say(format!("<@{}> is not registered. They need to use `/register` first.", user.id)).await?;
+        }
+        Err(LedgerError::InsufficientFunds { balance, .. }) => {
+            ctx.say(format!(
+                "UR BROKE BUB! You have {} Slumcoins",
+                balance
+            )).await?;
+        }
         Err(e) => {
-            error!("Database error checking sender: {}", e);
-            ctx.say("Database error occurred.").await?;
+            error!("Error executing transfer: {}", e);
+            ctx.say("Transfer failed. Please try again.").await?;
         }
     }
 
     Ok(())
 }
 
-#[poise::command(slash_command)]
-pub async fn baltop(ctx: Context<'_>) -> Result<(), Error> {
+/// Show the Slumcoin leaderboard
+#[poise::command(slash_command, category = "User", guild_only, subcommands("baltop_show", "baltop_pin", "baltop_unpin"))]
+pub async fn baltop(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Page through the leaderboard
+#[poise::command(slash_command, rename = "show", check = "cooldown")]
+pub async fn baltop_show(
+    ctx: Context<'_>,
+    #[description = "Page to start on (default: 1)"] page: Option<u32>,
+    #[description = "Users per page (default: 10, max 25)"] limit: Option<u32>,
+    #[description = "Past season to show final standings for (default: current balances)"]
+    #[autocomplete = "autocomplete_season"]
+    season: Option<String>,
+) -> Result<(), Error> {
     let data = &ctx.data();
+    let per_page = limit.unwrap_or(10).clamp(1, 25);
 
-    match data.database.get_all_users_with_balances(None).await {
-        Ok(users_with_balances) => {
-            if users_with_balances.is_empty() {
-                ctx.say("No registered users found!").await?;
+    let season = match season {
+        Some(name) => match data.database.get_season_by_name(name.trim()).await? {
+            Some(season) => Some(season),
+            None => {
+                ctx.say(format!("No season called **{}**. See `/season list`.", name)).await?;
                 return Ok(());
             }
+        },
+        None => None,
+    };
 
-            let mut response = "Slumbank Leaderboard\n".to_string();
-            
-            for (rank, (username, balance)) in users_with_balances.iter().enumerate() {
-                
-                response.push_str(&format!(
-                   "**{}. {} : ``{}``**\n",
-                    rank + 1,
-                    username,
-                    balance
-                ));
+    let (mut embed, total_pages) = match leaderboard::build_page_embed(&data.database, season.as_ref(), page.unwrap_or(1), per_page).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Error getting leaderboard: {}", e);
+            ctx.say("Error retrieving leaderboard. Please try again.").await?;
+            return Ok(());
+        }
+    };
+    let mut page = page.unwrap_or(1).clamp(1, total_pages);
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(poise::CreateReply::default()
+        .embed(embed.clone())
+        .components(page_buttons(ctx_id, page, total_pages))).await?;
+
+    // Page through the leaderboard until nobody has pressed a button for a while
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(std::time::Duration::from_secs(120))
+        .await
+    {
+        if press.data.custom_id.ends_with("next") {
+            page += 1;
+        } else {
+            page = page.saturating_sub(1);
+        }
+
+        let total_pages = match leaderboard::build_page_embed(&data.database, season.as_ref(), page, per_page).await {
+            Ok((page_embed, total_pages)) => {
+                embed = page_embed;
+                total_pages
             }
+            Err(e) => {
+                error!("Error getting leaderboard: {}", e);
+                press.create_response(ctx.serenity_context(), ephemeral_response("Error retrieving leaderboard. Please try again.")).await?;
+                continue;
+            }
+        };
+        page = page.clamp(1, total_pages);
+
+        press.create_response(
+            ctx.serenity_context(),
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(embed.clone())
+                    .components(page_buttons(ctx_id, page, total_pages)),
+            ),
+        ).await?;
+    }
 
-            ctx.say(response).await?;
-        }
+    // Drop the buttons once the collector times out
+    reply.edit(ctx, poise::CreateReply::default().embed(embed).components(Vec::new())).await?;
+    Ok(())
+}
+
+/// Post an auto-updating leaderboard in this channel
+#[poise::command(slash_command, rename = "pin", guild_only, check = "is_admin")]
+pub async fn baltop_pin(
+    ctx: Context<'_>,
+    #[description = "Minutes between refreshes (default: 10)"] interval: Option<i64>,
+    #[description = "Number of users to show (default: 10, max 25)"] limit: Option<u32>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let interval_minutes = interval.unwrap_or(10).max(1);
+    let limit = limit.unwrap_or(10).clamp(1, 25);
+
+    let embed = match leaderboard::build_embed(&data.database, limit).await {
+        Ok(embed) => embed,
         Err(e) => {
-            error!("Error getting leaderboard: {}", e);
+            error!("Error building leaderboard: {}", e);
             ctx.say("Error retrieving leaderboard. Please try again.").await?;
+            return Ok(());
+        }
+    };
+
+    let message = ctx
+        .channel_id()
+        .send_message(&ctx.http(), serenity::CreateMessage::new().embed(embed))
+        .await?;
+
+    // Pinning needs Manage Messages, the leaderboard still refreshes without it
+    if let Err(e) = message.pin(&ctx.http()).await {
+        error!("Failed to pin leaderboard message: {}", e);
+    }
+
+    let pin = PinnedLeaderboard {
+        guild_id,
+        channel_id: ctx.channel_id().to_string(),
+        message_id: message.id.to_string(),
+        interval_minutes,
+        limit,
+        last_refreshed: Utc::now().timestamp(),
+    };
+
+    match data.database.add_pinned_leaderboard(&pin).await {
+        Ok(()) => {
+            ctx.send(poise::CreateReply::default()
+                .content(format!("Leaderboard pinned, refreshing every {} minute(s).", interval_minutes))
+                .ephemeral(true)).await?;
+        }
+        Err(e) => {
+            error!("Error saving pinned leaderboard: {}", e);
+            ctx.say("Error saving pinned leaderboard.").await?;
         }
     }
 
     Ok(())
 }
 
-#[poise::command(slash_command)]
+/// Stop updating the pinned leaderboard
+#[poise::command(slash_command, rename = "unpin", guild_only, check = "is_admin")]
+pub async fn baltop_unpin(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+
+    match data.database.remove_pinned_leaderboards_in_channel(&ctx.channel_id().to_string()).await {
+        Ok(0) => {
+            ctx.say("No auto-updating leaderboard in this channel.").await?;
+        }
+        Ok(count) => {
+            ctx.say(format!("Stopped updating {} leaderboard(s) in this channel.", count)).await?;
+        }
+        Err(e) => {
+            error!("Error removing pinned leaderboard: {}", e);
+            ctx.say("Error removing pinned leaderboard.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show your recent transactions
+#[poise::command(slash_command, category = "User")]
 pub async fn ledger(
     ctx: Context<'_>,
-    #[description = "Number of recent transactions to show (default: 10)"] limit: Option<usize>,
+    #[description = "Number of transactions per page (default: 10, max 25)"] limit: Option<u32>,
+    #[description = "Page to show (default: 1, the most recent)"] page: Option<u32>,
+    #[description = "Only transactions with this user"] with: Option<serenity::User>,
+    #[description = "Only this transaction type, like transfer or daily"]
+    #[rename = "type"]
+    transaction_type: Option<String>,
 ) -> Result<(), Error> {
     let data = &ctx.data();
     let user_id = ctx.author().id.to_string();
+    let filter = TransactionFilter {
+        counterparty: with.map(|user| user.id.to_string()),
+        transaction_type: transaction_type.map(|transaction_type| transaction_type.trim().to_lowercase()),
+        ..TransactionFilter::for_user(&user_id)
+    };
+    let limit = limit.unwrap_or(10).clamp(1, 25);
+    let page = page.unwrap_or(1).max(1);
+    let first = (page - 1).saturating_mul(limit);
 
     match data.database.get_user(&user_id).await {
         Ok(Some(_)) => {
-            match data.database.get_user_transactions(&user_id).await {
-                Ok(transactions) => {
+            let result = match data.database.count_transactions(&filter).await {
+                Ok(total) => data.database
+                    .get_transactions(&filter, limit, first)
+                    .await
+                    .map(|transactions| (total, transactions)),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok((total, transactions)) => {
+                    if total == 0 {
+                        say_private(ctx, "No transactions found in your history.").await?;
+                        return Ok(());
+                    }
+                    let total_pages = (total as u32).div_ceil(limit);
                     if transactions.is_empty() {
-                        ctx.say("No transactions found in your history.").await?;
+                        say_private(ctx, format!("There are only {} page(s) of transactions.", total_pages)).await?;
                         return Ok(());
                     }
 
-                    let limit = limit.unwrap_or(10).min(25);
-                    let display_transactions: Vec<_> = transactions.iter().take(limit).collect();
-
                     let mut response = format!(
-                        "**Transaction History** (showing {} most recent)\n\n",
-                        display_transactions.len()
+                        "**Transaction History** (page {} of {}, {} transactions)\n\n",
+                        page, total_pages, total
                     );
 
-                    for (i, tx) in display_transactions.iter().enumerate() {
+                    for (i, tx) in transactions.iter().enumerate() {
                         let is_incoming = tx.to_user == user_id;
                         let other_user = if is_incoming { &tx.from_user } else { &tx.to_user };
                         
@@ -322,7 +607,7 @@ pub async fn ledger(
                         
                         response.push_str(&format!(
                             "{}. {} **{}{} coins** {} <@{}>\n",
-                            i + 1, emoji, direction, tx.amount, action, other_user
+                            first as usize + i + 1, emoji, direction, tx.amount, action, other_user
                         ));
 
                         if let Some(msg) = &tx.message {
@@ -332,61 +617,53 @@ pub async fn ledger(
                         response.push_str(&format!("   <t:{}:R>\n\n", tx.timestamp_unix));
                     }
 
-                    if transactions.len() > limit {
-                        response.push_str(&format!(
-                            "*{} more transaction(s) not shown*",
-                            transactions.len() - limit
-                        ));
+                    if page < total_pages {
+                        response.push_str(&format!("*Use `page: {}` for older transactions*", page + 1));
                     }
 
-                    ctx.say(response).await?;
+                    say_private(ctx, response).await?;
                 }
                 Err(e) => {
                     error!("Error getting transactions: {}", e);
-                    ctx.say("Error retrieving transaction history.").await?;
+                    say_private(ctx, "Error retrieving transaction history.").await?;
                 }
             }
         }
         Ok(None) => {
-            ctx.say("You're not registered! Use `/register` first.").await?;
+            say_private(ctx, "You're not registered! Use `/register` first.").await?;
         }
         Err(e) => {
             error!("Database error: {}", e);
-            ctx.say("Database error occurred.").await?;
+            say_private(ctx, "Database error occurred.").await?;
         }
     }
 
     Ok(())
 }
 
-#[poise::command(slash_command, subcommands("bid_start", "bid_place", "bid_status", "bid_end"))]
+const DEFAULT_AUCTION_SECONDS: i64 = 120;
+const DEFAULT_ANTI_SNIPE_SECONDS: i64 = 15;
+const EMBED_REFRESH_SECONDS: i64 = 15;
+
+/// Run and bid on voice channel auctions
+#[poise::command(slash_command, category = "User", guild_only, subcommands("bid_start", "bid_place", "bid_status", "bid_end", "bid_cancel"))]
 pub async fn bid(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-#[poise::command(slash_command, rename = "place")]
+/// Bid on the auction in your voice channel
+#[poise::command(slash_command, rename = "place", check = "not_frozen")]
 pub async fn bid_place(
     ctx: Context<'_>,
     #[description = "Amount of Slumcoins to bid"] amount: i64,
 ) -> Result<(), Error> {
-    let guild_id = match ctx.guild_id() {
-        Some(id) => id,
-        None => {
-            ctx.say("can only be used in slumfields").await?;
-            return Ok(());
-        }
-    };
+    if ctx.guild_id().is_none() {
+        ctx.say("can only be used in slumfields").await?;
+        return Ok(());
+    }
 
     // Get the user's current voice channel
-    let voice_channel_id = match ctx.guild() {
-        Some(guild) => {
-            guild
-                .voice_states
-                .get(&ctx.author().id)
-                .and_then(|vs| vs.channel_id)
-        }
-        None => None,
-    };
+    let voice_channel_id = author_voice_channel(ctx);
 
     let voice_channel_id = match voice_channel_id {
         Some(id) => id,
@@ -403,73 +680,39 @@ pub async fn bid_place(
     }
 
     let data = ctx.data();
-    let user_id = ctx.author().id.to_string();
 
-    // Check if user is registered
-    match data.database.get_user(&user_id).await {
-        Ok(Some(_)) => {
-            // Check user's balance
-            match data.database.get_balance(&user_id).await {
-                Ok(balance) => {
-                    if balance < amount {
-                        ctx.say(format!(
-                            "insufficient funds! You have {} Slumcoins but need {} to place this bid.",
-                            balance, amount
-                        )).await?;
-                        return Ok(());
-                    }
-
-                    // Try to place the bid
-                    match data.auction_manager.place_bid(voice_channel_id, ctx.author().id, amount).await {
-                        Ok(()) => {
-                            ctx.say(format!(
-                                "bid placed for **{} Slumcoins**\nUse `/bid status` to see current standings.",
-                                amount
-                            )).await?;
-                        }
-                        Err(e) => {
-                            ctx.say(format!(" {}", e)).await?;
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Error getting balance: {}", e);
-                    ctx.say("Error retrieving balance.").await?;
-                }
-            }
-        }
-        Ok(None) => {
-            ctx.say("You're not registered! Use `/register` first.").await?;
-        }
-        Err(e) => {
-            error!("Database error: {}", e);
-            ctx.say("Database error occurred.").await?;
+    match bidding::place_bid(ctx.http(), data, voice_channel_id, ctx.author().id, amount).await {
+        Ok(message) | Err(message) => {
+            ctx.say(message).await?;
         }
     }
 
     Ok(())
 }
 
+/// Start an auction in your voice channel
+#[allow(clippy::too_many_arguments)]
 #[poise::command(slash_command, rename = "start")]
-pub async fn bid_start(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = match ctx.guild_id() {
-        Some(id) => id,
-        None => {
-            ctx.say("This command can only be used in a server!").await?;
-            return Ok(());
-        }
+pub async fn bid_start(
+    ctx: Context<'_>,
+    #[description = "What you're auctioning"] item: String,
+    #[description = "Link to a picture of the item"] image: Option<String>,
+    #[description = "Refundable flat deposit required with each bidder's first bid"] deposit: Option<i64>,
+    #[description = "Refundable deposit as a percent of each bidder's first bid"] deposit_percent: Option<i64>,
+    #[description = "Minimum winning bid; below this the auction ends with no sale"] reserve: Option<i64>,
+    #[description = "Bid that immediately wins and ends the auction"] buyout: Option<i64>,
+    #[description = "Auction length in seconds (default: 120)"] duration: Option<i64>,
+    #[description = "A bid this close to the end pushes it back to this many seconds (default: 15)"] anti_snipe: Option<i64>,
+    #[description = "Coins each bid must beat the last by (default: server setting)"] increment: Option<i64>,
+    #[description = "Percent each bid must beat the last by (default: server setting)"] increment_percent: Option<i64>,
+) -> Result<(), Error> {
+    let Some(server_id) = ctx.guild_id() else {
+        ctx.say("This command can only be used in a server!").await?;
+        return Ok(());
     };
 
     // Get the user's current voice channel
-    let voice_channel_id = match ctx.guild() {
-        Some(guild) => {
-            guild
-                .voice_states
-                .get(&ctx.author().id)
-                .and_then(|vs| vs.channel_id)
-        }
-        None => None,
-    };
+    let voice_channel_id = author_voice_channel(ctx);
 
     let voice_channel_id = match voice_channel_id {
         Some(id) => id,
@@ -479,29 +722,88 @@ pub async fn bid_start(ctx: Context<'_>) -> Result<(), Error> {
         }
     };
 
+    let deposit_rule = match (deposit, deposit_percent) {
+        (Some(_), Some(_)) => {
+            ctx.say("Use either `deposit` or `deposit_percent`, not both").await?;
+            return Ok(());
+        }
+        (Some(amount), None) if amount > 0 => Some(DepositRule::Flat(amount)),
+        (None, Some(percent)) if (1..=100).contains(&percent) => Some(DepositRule::Percent(percent)),
+        (None, None) => None,
+        _ => {
+            ctx.say("Deposit must be a positive amount or a percent between 1 and 100").await?;
+            return Ok(());
+        }
+    };
+
+    let item = item.trim().to_string();
+    if item.is_empty() {
+        ctx.say("Say what you're auctioning").await?;
+        return Ok(());
+    }
+
+    let image = image.map(|image| image.trim().to_string()).filter(|image| !image.is_empty());
+    if image.as_ref().is_some_and(|image| !image.starts_with("https://") && !image.starts_with("http://")) {
+        ctx.say("Image must be an http(s) link").await?;
+        return Ok(());
+    }
+
+    if reserve.is_some_and(|reserve| reserve <= 0) || buyout.is_some_and(|buyout| buyout <= 0) {
+        ctx.say("Reserve and buyout prices must be greater than 0").await?;
+        return Ok(());
+    }
+
+    if let (Some(reserve), Some(buyout)) = (reserve, buyout) {
+        if buyout < reserve {
+            ctx.say("Buyout price can't be below the reserve").await?;
+            return Ok(());
+        }
+    }
+
     let data = ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    // Both lengths must stay inside this server's configured bounds
+    let duration = duration.unwrap_or(DEFAULT_AUCTION_SECONDS);
+    let anti_snipe = anti_snipe.unwrap_or(DEFAULT_ANTI_SNIPE_SECONDS);
+    for (value, name, min_key, max_key) in [
+        (duration, "Duration", "auction.min_duration_seconds", "auction.max_duration_seconds"),
+        (anti_snipe, "Anti-snipe extension", "auction.min_anti_snipe_seconds", "auction.max_anti_snipe_seconds"),
+    ] {
+        let min = config::get_i64(&data.database, &guild_id, min_key).await?;
+        let max = config::get_i64(&data.database, &guild_id, max_key).await?;
+        if value < min || value > max {
+            ctx.say(format!("{} must be between {} and {} seconds", name, min, max)).await?;
+            return Ok(());
+        }
+    }
+    
+    // Fall back to the server's increment when the auction doesn't set its own
+    let min_increment = match (increment, increment_percent) {
+        (Some(_), Some(_)) => {
+            ctx.say("Use either `increment` or `increment_percent`, not both").await?;
+            return Ok(());
+        }
+        (Some(amount), None) if amount > 0 => BidIncrement::Flat(amount),
+        (None, Some(percent)) if (1..=100).contains(&percent) => BidIncrement::Percent(percent),
+        (None, None) => {
+            let percent = config::get_i64(&data.database, &guild_id, "auction.min_increment_percent").await?;
+            if percent > 0 {
+                BidIncrement::Percent(percent.min(100))
+            } else {
+                BidIncrement::Flat(config::get_i64(&data.database, &guild_id, "auction.min_increment").await?.max(1))
+            }
+        }
+        _ => {
+            ctx.say("Increment must be a positive amount or a percent between 1 and 100").await?;
+            return Ok(());
+        }
+    };
     
-    // Start the auction (2 minute base, 15 second extensions)
-    match data.auction_manager.start_auction(voice_channel_id, ctx.author().id, 120, 15).await {
+    match data.auction_manager.start_auction(voice_channel_id, server_id, ctx.author().id, duration, anti_snipe, deposit_rule, reserve, buyout, item.clone(), image, min_increment).await {
         Ok(()) => {
             // Get all members in the voice channel
-            let members_in_vc = match ctx.http().get_channel(voice_channel_id).await {
-                Ok(serenity::Channel::Guild(channel)) => {
-                    // Get the guild to access voice states
-                    if let Some(guild) = ctx.guild() {
-                        let mut members = Vec::new();
-                        for (user_id, voice_state) in &guild.voice_states {
-                            if voice_state.channel_id == Some(voice_channel_id) {
-                                members.push(*user_id);
-                            }
-                        }
-                        members
-                    } else {
-                        Vec::new()
-                    }
-                }
-                _ => Vec::new(),
-            };
+            let members_in_vc = voice_channel_members(ctx, voice_channel_id);
 
             // Create mention string for all VC members
             let mentions = if members_in_vc.is_empty() {
@@ -514,60 +816,77 @@ pub async fn bid_start(ctx: Context<'_>) -> Result<(), Error> {
                     .join(" ")
             };
 
-            ctx.say(format!(
-                "{} has started a bidding war\n\n\
+            let mut terms = match deposit_rule {
+                Some(rule) => format!("Refundable deposit: **{}**\n", rule),
+                None => String::new(),
+            };
+            if let Some(reserve) = reserve {
+                terms.push_str(&format!("Reserve price: **{} Slumcoins**\n", reserve));
+            }
+            if let Some(buyout) = buyout {
+                terms.push_str(&format!("Buyout: bid **{} Slumcoins** to win instantly\n", buyout));
+            }
+            terms.push_str(&format!("Minimum raise: **{}**\n", min_increment));
+
+            let announcement = format!(
+                "{} has started a bidding war for **{}**\n\n\
                 {}\n\n\
-                place bids using `/bid place [amount]`\n\
-                Auction ends in **2 minutes** (extends by 15s on new bids)\n\
-                Use `/bid status` to check current highest bid",
+                place bids with the button below or `/bid place [amount]`\n\
+                {}\
+                Auction ends in **{}** (bids in the last {}s extend it)",
                 ctx.author().name,
-                mentions
-            )).await?;
+                item,
+                mentions,
+                terms,
+                format_duration(duration as u64),
+                anti_snipe
+            );
+
+            // The embed is edited on every bid and every few seconds until the auction ends
+            let Some(auction) = data.auction_manager.get_auction(voice_channel_id).await else {
+                return Ok(());
+            };
+            let reply = ctx.send(poise::CreateReply::default()
+                .content(announcement)
+                .embed(bidding::auction_embed(&auction, false))
+                .components(bidding::bid_buttons(voice_channel_id))).await?;
+            let message = reply.message().await?;
+            data.auction_manager.set_announcement(voice_channel_id, message.channel_id, message.id).await;
 
             // Clone the data we need before spawning the task
             let auction_manager = data.auction_manager.clone();
             let database = data.database.clone();
             let ctx_clone = ctx.serenity_context().clone();
             let channel_id = ctx.channel_id();
+            let started_at = auction.start_time;
             
             tokio::spawn(async move {
-                // Wait for the auction to expire
-                sleep(TokioDuration::from_secs(120)).await;
-                
-                // Check and handle expired auction
-                if let Some(auction) = auction_manager.get_auction(voice_channel_id).await {
-                    if auction.is_expired() {
-                        if let Some(ended_auction) = auction_manager.end_auction(voice_channel_id).await {
-                            match auction_manager.process_auction_completion(&ended_auction, &database).await {
-                                Ok(()) => {
-                                    // Process coin deduction
-                                    let message = match ended_auction.get_winner() {
-                                        Some((winner_id, winning_amount)) => {
-                                            format!(
-                                                "Winner: <@{}>\n\
-                                                Winning bid: **{} Slumcoins**\n\
-                                                Hope it was worth it bub",
-                                                winner_id,
-                                                winning_amount
-                                            )
-                                        }
-                                        None => "Auction ended with no bids".to_string(),
-                                    };
-                                    let _ = channel_id.say(&ctx_clone.http, message).await;
-                                }
-                                Err(e) => {
-                                    // Log the error instead of using ctx.say
-                                    eprintln!("Error processing auction: {}", e);
-                                    let _ = channel_id.say(&ctx_clone.http, format!("Error processing auction: {}", e)).await;
-                                }
-                            }
-                        }
+                let mut wait = duration.min(EMBED_REFRESH_SECONDS);
+                loop {
+                    sleep(TokioDuration::from_secs(wait.max(1) as u64)).await;
+
+                    // Stop if it was ended early or replaced by a newer auction
+                    let auction = match auction_manager.get_auction(voice_channel_id).await {
+                        Some(auction) if auction.start_time == started_at => auction,
+                        _ => break,
+                    };
+
+                    // Late bids push the end time back, so keep counting down until it actually passes
+                    if !auction.is_expired() {
+                        bidding::refresh_auction_embed(&ctx_clone.http, &auction, false).await;
+                        wait = (auction.time_remaining() + 1).min(EMBED_REFRESH_SECONDS);
+                        continue;
                     }
+
+                    if let Some(message) = bidding::finish_auction(&ctx_clone.http, &auction_manager, &database, voice_channel_id).await {
+                        let _ = channel_id.say(&ctx_clone.http, message).await;
+                    }
+                    break;
                 }
             });
         }
         Err(e) => {
-            ctx.say(format!("{}", e)).await?;
+            ctx.say(e).await?;
         }
     }
 
@@ -575,26 +894,16 @@ pub async fn bid_start(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 
+/// Show the auction running in your voice channel
 #[poise::command(slash_command, rename = "status")]
 pub async fn bid_status(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = match ctx.guild_id() {
-        Some(id) => id,
-        None => {
-            ctx.say("This command can only be used in a server").await?;
-            return Ok(());
-        }
-    };
+    if ctx.guild_id().is_none() {
+        ctx.say("This command can only be used in a server").await?;
+        return Ok(());
+    }
 
     // Get the user's current voice channel
-    let voice_channel_id = match ctx.guild() {
-        Some(guild) => {
-            guild
-                .voice_states
-                .get(&ctx.author().id)
-                .and_then(|vs| vs.channel_id)
-        }
-        None => None,
-    };
+    let voice_channel_id = author_voice_channel(ctx);
 
     let voice_channel_id = match voice_channel_id {
         Some(id) => id,
@@ -613,15 +922,27 @@ pub async fn bid_status(ctx: Context<'_>) -> Result<(), Error> {
                 return Ok(());
             }
 
-            let highest_bid = auction.get_highest_bid_amount();
             let mut response = format!(
                 "
+                Item: **{}**\n\
                 Time remaining: **{}s**\n\
                 Total bids: **{}**\n\n",
+                auction.item,
                 auction.time_remaining(),
                 auction.bids.len()
             );
 
+            if let Some(reserve) = auction.reserve_price {
+                let status = if auction.reserve_met() { "met" } else { "not met" };
+                response.push_str(&format!("Reserve: **{} Slumcoins** ({})\n", reserve, status));
+            }
+            if let Some(buyout) = auction.buyout_price {
+                response.push_str(&format!("Buyout: **{} Slumcoins**\n", buyout));
+            }
+            if auction.reserve_price.is_some() || auction.buyout_price.is_some() {
+                response.push('\n');
+            }
+
             if auction.bids.is_empty() {
                 response.push_str("No bids yet. Use `/bid place [amount]` to place a bid.");
             } else {
@@ -635,7 +956,7 @@ pub async fn bid_status(ctx: Context<'_>) -> Result<(), Error> {
                 
                 response.push_str("**All bids:**\n");
                 let mut sorted_bids: Vec<_> = auction.bids.values().collect();
-                sorted_bids.sort_by(|a, b| b.amount.cmp(&a.amount));
+                sorted_bids.sort_by_key(|bid| std::cmp::Reverse(bid.amount));
                 
                 for bid in sorted_bids {
                     response.push_str(&format!(
@@ -645,7 +966,11 @@ pub async fn bid_status(ctx: Context<'_>) -> Result<(), Error> {
                 }
             }
 
-            ctx.say(response).await?;
+            let mut reply = poise::CreateReply::default().content(response);
+            if let Some(image) = &auction.image_url {
+                reply = reply.embed(serenity::CreateEmbed::new().title(&auction.item).image(image));
+            }
+            ctx.send(reply).await?;
         }
         None => {
             ctx.say("No active auction in this voice channel! Use `/bid start` to begin one.").await?;
@@ -655,26 +980,16 @@ pub async fn bid_status(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// End the auction in your voice channel now
 #[poise::command(slash_command, rename = "end")]
 pub async fn bid_end(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = match ctx.guild_id() {
-        Some(id) => id,
-        None => {
-            ctx.say("This command can only be used in a server").await?;
-            return Ok(());
-        }
-    };
+    if ctx.guild_id().is_none() {
+        ctx.say("This command can only be used in a server").await?;
+        return Ok(());
+    }
 
     // Get the user's current voice channel
-    let voice_channel_id = match ctx.guild() {
-        Some(guild) => {
-            guild
-                .voice_states
-                .get(&ctx.author().id)
-                .and_then(|vs| vs.channel_id)
-        }
-        None => None,
-    };
+    let voice_channel_id = author_voice_channel(ctx);
 
     let voice_channel_id = match voice_channel_id {
         Some(id) => id,
@@ -694,31 +1009,8 @@ pub async fn bid_end(ctx: Context<'_>) -> Result<(), Error> {
                 return Ok(());
             }
 
-            if let Some(ended_auction) = data.auction_manager.end_auction(voice_channel_id).await {
-                // Process the auction completion and handle coin deduction
-                match data.auction_manager.process_auction_completion(&ended_auction, &data.database).await {
-                    Ok(()) => {
-                        let message = match ended_auction.get_winner() {
-                            Some((winner_id, winning_amount)) => {
-                                format!(
-                                    "
-                                    Winner: <@{}>\n\
-                                    Winning bid: **{} Slumcoins**\n\
-                                    Hope it was worth it bub
-                                    ",
-                                    winner_id,
-                                    winning_amount
-                                )
-                            }
-                            None => "Auction ended with no bids".to_string(),
-                        };
-                        
-                        ctx.say(message).await?;
-                    }
-                    Err(e) => {
-                        ctx.say(format!("Error processing auction: {}", e)).await?;
-                    }
-                }
+            if let Some(message) = bidding::finish_auction(ctx.http(), &data.auction_manager, &data.database, voice_channel_id).await {
+                ctx.say(message).await?;
             }
         }
         None => {
@@ -728,3 +1020,62 @@ pub async fn bid_end(ctx: Context<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Cancel the auction in your voice channel and refund all bids
+#[poise::command(slash_command, rename = "cancel")]
+pub async fn bid_cancel(ctx: Context<'_>) -> Result<(), Error> {
+    if ctx.guild_id().is_none() {
+        ctx.say("This command can only be used in a server").await?;
+        return Ok(());
+    }
+
+    // Get the user's current voice channel
+    let voice_channel_id = author_voice_channel(ctx);
+
+    let voice_channel_id = match voice_channel_id {
+        Some(id) => id,
+        None => {
+            ctx.say("You must be in a voice channel to cancel an auction").await?;
+            return Ok(());
+        }
+    };
+
+    let data = ctx.data();
+
+    let Some(auction) = data.auction_manager.get_auction(voice_channel_id).await else {
+        ctx.say("No active auction in this voice channel").await?;
+        return Ok(());
+    };
+
+    // The creator or an admin can void the auction
+    if auction.creator_id != ctx.author().id && !is_admin(ctx).await? {
+        ctx.say("Only the auction creator or an admin can cancel it").await?;
+        return Ok(());
+    }
+
+    match data.auction_manager.cancel_auction(voice_channel_id, &data.database).await {
+        Ok(Some(cancelled)) => {
+            bidding::refresh_auction_embed(ctx.http(), &cancelled, true).await;
+            bidding::record_history(&data.database, &cancelled, "cancelled", None).await;
+
+            let refunded: i64 = cancelled.deposits.values().sum();
+            let mut response = format!(
+                "🚫 Auction for **{}** was cancelled by <@{}>. Nobody was charged.",
+                cancelled.item, ctx.author().id
+            );
+            if refunded > 0 {
+                response.push_str(&format!("\nRefunded {} Slumcoins in deposits.", refunded));
+            }
+            ctx.say(response).await?;
+        }
+        Ok(None) => {
+            ctx.say("No active auction in this voice channel").await?;
+        }
+        Err(e) => {
+            error!("Error refunding cancelled auction deposits: {}", e);
+            ctx.say("The auction was cancelled but refunding deposits failed. An admin should check `/audit`.").await?;
+        }
+    }
+
+    Ok(())
+}
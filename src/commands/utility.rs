@@ -1,17 +1,257 @@
-use crate::{Context, Error};
-
-#[poise::command(slash_command)]
-pub async fn info(ctx: Context<'_>) -> Result<(), Error> {
-    let response = format!(
-        "
-        • `/register` - Register yourself for Slumcoins\n\
-        • `/register @user` - Register another user (admin)\n\
-        • `/balance` - Check your Slumcoin balance\n\
-        • `/give @user amount` - Give Slumcoins to a user (admin)\n\
-        • `/baltop` - Show Slumcoin leaderboard\n\
-        • `/info` - Show this message\n\
-        "
-    );
-    ctx.say(response).await?;
+use poise::serenity_prelude as serenity;
+
+use crate::{Context, Data, Error};
+use super::page_buttons;
+
+type Command = poise::Command<Data, Error>;
+
+/// Order the help pages are shown in; commands without a category go last
+const CATEGORIES: [&str; 3] = ["User", "Games", "Admin"];
+const COMMANDS_PER_PAGE: usize = 12;
+
+/// One line of the help overview, e.g. "`/bid start` - Start an auction (admin)"
+struct HelpEntry {
+    usage: String,
+    description: String,
+    admin: bool,
+}
+
+fn is_admin_only(command: &Command, parent_admin: bool) -> bool {
+    parent_admin || !command.checks.is_empty() || command.category.as_deref() == Some("Admin")
+}
+
+/// Flatten a command into one entry per runnable (sub)command
+fn collect_entries(command: &Command, parent_admin: bool, entries: &mut Vec<HelpEntry>) {
+    let admin = is_admin_only(command, parent_admin);
+    if command.subcommands.is_empty() {
+        entries.push(HelpEntry {
+            usage: usage(command),
+            description: command.description.clone().unwrap_or_default(),
+            admin,
+        });
+    }
+    for subcommand in &command.subcommands {
+        if !subcommand.hide_in_help {
+            collect_entries(subcommand, admin, entries);
+        }
+    }
+}
+
+/// Slash syntax for a command with its options, e.g. "/send user amount [message]"
+fn usage(command: &Command) -> String {
+    let mut usage = format!("/{}", command.qualified_name);
+    for parameter in &command.parameters {
+        if parameter.required {
+            usage.push_str(&format!(" {}", parameter.name));
+        } else {
+            usage.push_str(&format!(" [{}]", parameter.name));
+        }
+    }
+    usage
+}
+
+/// Split the overview into embeds, one category at a time
+fn build_pages(commands: &[Command]) -> Vec<serenity::CreateEmbed> {
+    let category_rank = |command: &Command| {
+        command
+            .category
+            .as_deref()
+            .and_then(|category| CATEGORIES.iter().position(|c| *c == category))
+            .unwrap_or(CATEGORIES.len())
+    };
+    let mut commands: Vec<&Command> = commands.iter().filter(|c| !c.hide_in_help).collect();
+    commands.sort_by_key(|c| category_rank(c));
+
+    let mut chunks: Vec<(String, Vec<HelpEntry>)> = Vec::new();
+    for command in commands {
+        let category = command.category.clone().unwrap_or_else(|| "Other".to_string());
+        let mut entries = Vec::new();
+        collect_entries(command, false, &mut entries);
+
+        for entry in entries {
+            match chunks.last_mut() {
+                Some((current, page)) if *current == category && page.len() < COMMANDS_PER_PAGE => page.push(entry),
+                _ => chunks.push((category.clone(), vec![entry])),
+            }
+        }
+    }
+
+    let total_pages = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, (category, entries))| {
+            let lines: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    let admin = if entry.admin && category != "Admin" { " (admin)" } else { "" };
+                    format!("• `{}` - {}{}", entry.usage, entry.description, admin)
+                })
+                .collect();
+            serenity::CreateEmbed::new()
+                .title(format!("{} Commands", category))
+                .description(lines.join("\n"))
+                .color(0x00ff00)
+                .footer(serenity::CreateEmbedFooter::new(format!(
+                    "Page {}/{} • /help <command> for details",
+                    i + 1,
+                    total_pages
+                )))
+        })
+        .collect()
+}
+
+/// Look up a command by its full name, e.g. "bid start"; returns whether it's admin-only too
+fn find_command<'a>(commands: &'a [Command], name: &str) -> Option<(&'a Command, bool)> {
+    let mut words = name.trim().trim_start_matches('/').split_whitespace();
+    let first = words.next()?.to_lowercase();
+    let mut command = commands.iter().find(|c| c.name == first)?;
+    let mut admin = is_admin_only(command, false);
+
+    for word in words {
+        let word = word.to_lowercase();
+        command = command.subcommands.iter().find(|c| c.name == word)?;
+        admin = is_admin_only(command, admin);
+    }
+    Some((command, admin))
+}
+
+fn command_embed(command: &Command, admin: bool) -> serenity::CreateEmbed {
+    let mut description = command.description.clone().unwrap_or_default();
+    if let Some(help_text) = &command.help_text {
+        description.push_str(&format!("\n\n{}", help_text));
+    }
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("/{}", command.qualified_name))
+        .description(description)
+        .color(0x00ff00);
+
+    if command.subcommands.is_empty() {
+        embed = embed.field("Usage", format!("`{}`", usage(command)), false);
+    } else {
+        let subcommands: Vec<String> = command
+            .subcommands
+            .iter()
+            .filter(|c| !c.hide_in_help)
+            .map(|c| {
+                let admin = if !admin && is_admin_only(c, false) { " (admin)" } else { "" };
+                format!("• `{}` - {}{}", usage(c), c.description.as_deref().unwrap_or_default(), admin)
+            })
+            .collect();
+        embed = embed.field("Subcommands", subcommands.join("\n"), false);
+    }
+
+    if !command.parameters.is_empty() {
+        let options: Vec<String> = command
+            .parameters
+            .iter()
+            .map(|p| {
+                let optional = if p.required { "" } else { " (optional)" };
+                format!("• `{}`{} - {}", p.name, optional, p.description.as_deref().unwrap_or_default())
+            })
+            .collect();
+        embed = embed.field("Options", options.join("\n"), false);
+    }
+
+    let mut notes = Vec::new();
+    if admin {
+        notes.push("Admin only");
+    }
+    if command.guild_only {
+        notes.push("Server only");
+    }
+    if !notes.is_empty() {
+        embed = embed.footer(serenity::CreateEmbedFooter::new(notes.join(" • ")));
+    }
+    embed
+}
+
+pub async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    let mut names = Vec::new();
+    for command in &ctx.framework().options().commands {
+        if command.hide_in_help {
+            continue;
+        }
+        names.push(command.qualified_name.clone());
+        names.extend(command.subcommands.iter().map(|c| c.qualified_name.clone()));
+    }
+    names.retain(|name| name.contains(&partial));
+    names.truncate(25);
+    names
+}
+
+/// List every command, or show the details of one
+#[poise::command(slash_command, category = "User")]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "Command to show details for, e.g. \"bid start\""]
+    #[autocomplete = "autocomplete_command"]
+    command: Option<String>,
+) -> Result<(), Error> {
+    let commands = &ctx.framework().options().commands;
+
+    if let Some(name) = command {
+        match find_command(commands, &name) {
+            Some((command, admin)) => {
+                ctx.send(poise::CreateReply::default().embed(command_embed(command, admin))).await?;
+            }
+            None => {
+                ctx.say(format!("There's no `/{}` command. Use `/help` to see them all.", name.trim())).await?;
+            }
+        }
+        return Ok(());
+    }
+
+    let pages = build_pages(commands);
+    let total_pages = pages.len() as u32;
+    let mut page = 1;
+
+    let mut overview = poise::CreateReply::default();
+    if ctx.guild_id().is_none() {
+        let dm_commands: Vec<String> = commands
+            .iter()
+            .filter(|c| !c.guild_only && !c.hide_in_help)
+            .map(|c| format!("`/{}`", c.name))
+            .collect();
+        overview = overview.content(format!(
+            "Only {} work in DMs; everything else has to be used in a server.",
+            dm_commands.join(", ")
+        ));
+    }
+
+    let ctx_id = ctx.id();
+    let reply = ctx.send(overview
+        .embed(pages[0].clone())
+        .components(page_buttons(ctx_id, page, total_pages))).await?;
+
+    // Page through the overview until nobody has pressed a button for a while
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(std::time::Duration::from_secs(120))
+        .await
+    {
+        if press.data.custom_id.ends_with("next") {
+            page += 1;
+        } else {
+            page = page.saturating_sub(1);
+        }
+        page = page.clamp(1, total_pages);
+
+        press.create_response(
+            ctx.serenity_context(),
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .embed(pages[page as usize - 1].clone())
+                    .components(page_buttons(ctx_id, page, total_pages)),
+            ),
+        ).await?;
+    }
+
+    // Drop the buttons once the collector times out
+    reply.edit(ctx, poise::CreateReply::default()
+        .embed(pages[page as usize - 1].clone())
+        .components(Vec::new())).await?;
     Ok(())
 }
@@ -0,0 +1,101 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::database::Transaction;
+use crate::vault::{INTEREST_PERIOD_SECONDS, VAULT_ACCOUNT};
+use super::{say_private, database_error_message};
+
+/// Move coins from your wallet into your interest-earning vault
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn deposit(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to put in your vault"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            say_private(ctx, "You're not registered! Use `/register` first.").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            say_private(ctx, "Database error occurred.").await?;
+            return Ok(());
+        }
+    }
+
+    let wallet = data.database.get_balance(&user_id).await?;
+    if wallet < amount {
+        say_private(ctx, format!("UR BROKE BUB! You have {} Slumcoins", wallet)).await?;
+        return Ok(());
+    }
+
+    let deposit = Transaction::system(&user_id, VAULT_ACCOUNT, amount, "vault_deposit", None);
+    match data.database.vault_deposit(&guild_id, &deposit).await {
+        Ok(()) => {
+            let vault = data.database.get_vault_balance(&guild_id, &user_id).await?;
+            say_private(ctx, format!(
+                "🏦 Deposited **{} Slumcoins** into your vault.\n\
+                Wallet: {} coins · Vault: {} coins\n\
+                Your next interest payment is <t:{}:R>.",
+                amount,
+                wallet - amount,
+                vault,
+                deposit.timestamp_unix + INTEREST_PERIOD_SECONDS
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error depositing into vault: {}", e);
+            say_private(ctx, database_error_message(ctx, &e, "Deposit failed. Please try again.")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Move coins from your vault back into your wallet
+#[poise::command(slash_command, category = "User", guild_only)]
+pub async fn withdraw(
+    ctx: Context<'_>,
+    #[description = "Amount of coins to take out of your vault"] amount: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let user_id = ctx.author().id.to_string();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    if amount <= 0 {
+        say_private(ctx, "nice try bub").await?;
+        return Ok(());
+    }
+
+    let withdrawal = Transaction::system(VAULT_ACCOUNT, &user_id, amount, "vault_withdraw", None);
+    match data.database.vault_withdraw(&guild_id, &withdrawal).await {
+        Ok(true) => {
+            let wallet = data.database.get_balance(&user_id).await?;
+            let vault = data.database.get_vault_balance(&guild_id, &user_id).await?;
+            say_private(ctx, format!(
+                "🏦 Withdrew **{} Slumcoins** from your vault.\nWallet: {} coins · Vault: {} coins",
+                amount, wallet, vault
+            )).await?;
+        }
+        Ok(false) => {
+            let vault = data.database.get_vault_balance(&guild_id, &user_id).await?;
+            say_private(ctx, format!("Your vault only holds {} Slumcoins.", vault)).await?;
+        }
+        Err(e) => {
+            error!("Error withdrawing from vault: {}", e);
+            say_private(ctx, "Withdrawal failed. Please try again.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use poise::serenity_prelude as serenity;
+use poise::Modal;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::{ApplicationContext, Context, Error};
+
+// Shortest passphrase accepted for a wallet backup
+const MIN_PASSPHRASE_LENGTH: usize = 12;
+// How long the passphrase modal stays open
+const MODAL_TIMEOUT_SECONDS: u64 = 300;
+
+#[derive(Debug, Modal)]
+#[name = "Encrypt your wallet backup"]
+struct BackupPassphrase {
+    #[name = "Passphrase"]
+    #[placeholder = "At least 12 characters. It can't be recovered."]
+    #[min_length = 12]
+    #[max_length = 256]
+    passphrase: String,
+    #[name = "Repeat passphrase"]
+    #[min_length = 12]
+    #[max_length = 256]
+    confirm: String,
+}
+
+/// Your Slumcoin keys
+#[poise::command(slash_command, category = "User", subcommands("wallet_export"))]
+pub async fn wallet(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// DM yourself your public key and a passphrase-encrypted backup of your private key
+#[poise::command(slash_command, rename = "export")]
+pub async fn wallet_export(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let user_id = ctx.author().id.to_string();
+
+    let user = match data.database.get_user(&user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            ctx.send(poise::CreateReply::default().content("You're not registered. Use `/register` first.").ephemeral(true)).await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Error loading user {}: {}", user_id, e);
+            ctx.send(poise::CreateReply::default().content("Database error occurred.").ephemeral(true)).await?;
+            return Ok(());
+        }
+    };
+
+    let Some(form) = poise::execute_modal::<_, _, BackupPassphrase>(ctx, None, Some(Duration::from_secs(MODAL_TIMEOUT_SECONDS))).await? else {
+        return Ok(());
+    };
+
+    if form.passphrase != form.confirm {
+        ctx.send(poise::CreateReply::default().content("The passphrases didn't match, nothing was exported.").ephemeral(true)).await?;
+        return Ok(());
+    }
+    if form.passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        ctx.send(poise::CreateReply::default()
+            .content(format!("Use a passphrase of at least {} characters.", MIN_PASSPHRASE_LENGTH))
+            .ephemeral(true)).await?;
+        return Ok(());
+    }
+
+    let backup = data.crypto
+        .decrypt_private_key(&user.encrypted_private_key, &user_id)
+        .and_then(|private_key| data.crypto.encrypt_backup(&private_key, &user_id, &form.passphrase));
+    let backup = match backup {
+        Ok(backup) => backup,
+        Err(e) => {
+            error!("Error creating wallet backup for {}: {}", user_id, e);
+            ctx.send(poise::CreateReply::default().content("Couldn't create the backup.").ephemeral(true)).await?;
+            return Ok(());
+        }
+    };
+
+    let dm = serenity::CreateMessage::new().content(format!(
+        "**Slumcoin Wallet Backup**\n\
+        Public key:\n```{}```\n\
+        Encrypted private key:\n```{}```\n\
+        Keep this message and your passphrase somewhere safe. The backup only opens with the passphrase \
+        you just entered, and nobody can recover it for you.",
+        user.public_key, backup
+    ));
+
+    let response = match ctx.author().direct_message(ctx.serenity_context(), dm).await {
+        Ok(_) => {
+            info!("Exported wallet backup for {}", user_id);
+            "📬 Sent your wallet backup in DMs."
+        }
+        Err(e) => {
+            error!("Couldn't DM wallet backup to {}: {}", user_id, e);
+            "Couldn't DM you. Allow direct messages from server members and try again."
+        }
+    };
+
+    ctx.send(poise::CreateReply::default().content(response).ephemeral(true)).await?;
+    Ok(())
+}
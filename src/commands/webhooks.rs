@@ -0,0 +1,171 @@
+use tracing::error;
+
+use crate::{Context, Error};
+use crate::webhooks::{AUCTION_END, LARGE_TRANSACTION, MINT};
+use super::{is_admin, log_admin_action};
+
+// Default large_transaction threshold when none is given
+const DEFAULT_MIN_AMOUNT: i64 = 1000;
+
+// Webhook URLs carry their secret in the path, so only the host is ever shown back
+fn display_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "invalid url".to_string())
+}
+
+/// Send notable transactions, auction results and mints to outside services
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    check = "is_admin",
+    subcommands("webhook_list", "webhook_add", "webhook_remove", "webhook_retry")
+)]
+pub async fn webhook(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show this server's webhooks and their delivery queues
+#[poise::command(slash_command, rename = "list", ephemeral)]
+pub async fn webhook_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    let webhooks = match data.database.get_webhooks(&guild_id).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            error!("Error loading webhooks: {}", e);
+            ctx.say("Error loading webhooks.").await?;
+            return Ok(());
+        }
+    };
+
+    if webhooks.is_empty() {
+        ctx.say("No webhooks set up. Add one with `/webhook add`.").await?;
+        return Ok(());
+    }
+
+    let mut response = "**Webhooks**\n".to_string();
+    for webhook in &webhooks {
+        response.push_str(&format!("`#{}` {} · {}", webhook.id, display_host(&webhook.url), webhook.events.join(", ")));
+        if webhook.events.iter().any(|event| event == LARGE_TRANSACTION) {
+            response.push_str(&format!(" (≥ {} coins)", webhook.min_amount));
+        }
+        response.push_str(&format!(" · {} queued", webhook.pending));
+        if webhook.failed > 0 {
+            response.push_str(&format!(" · ⚠️ {} failed", webhook.failed));
+        }
+        response.push('\n');
+    }
+
+    ctx.say(response).await?;
+    Ok(())
+}
+
+/// Send events to a Discord webhook or any HTTP endpoint that accepts JSON
+#[poise::command(slash_command, rename = "add", ephemeral)]
+pub async fn webhook_add(
+    ctx: Context<'_>,
+    #[description = "Discord webhook URL or HTTPS endpoint"] url: String,
+    #[description = "Send transactions at or above min_amount (default: on)"] large_transactions: Option<bool>,
+    #[description = "Send auction results from this server (default: on)"] auctions: Option<bool>,
+    #[description = "Send newly minted coins (default: on)"] mints: Option<bool>,
+    #[description = "Smallest transaction that counts as large (default: 1000)"]
+    #[min = 1]
+    min_amount: Option<i64>,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+    let url = url.trim().to_string();
+
+    if !reqwest::Url::parse(&url).is_ok_and(|url| url.scheme() == "https" || url.scheme() == "http") {
+        ctx.say("That isn't a valid http(s) URL.").await?;
+        return Ok(());
+    }
+
+    let events: Vec<&str> = [
+        (LARGE_TRANSACTION, large_transactions),
+        (AUCTION_END, auctions),
+        (MINT, mints),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| enabled.unwrap_or(true))
+    .map(|(event, _)| event)
+    .collect();
+    if events.is_empty() {
+        ctx.say("Pick at least one kind of event to send.").await?;
+        return Ok(());
+    }
+
+    let min_amount = min_amount.unwrap_or(DEFAULT_MIN_AMOUNT);
+    match data.database.add_webhook(&guild_id, &url, &events, min_amount, &ctx.author().id.to_string()).await {
+        Ok(webhook_id) => {
+            log_admin_action(ctx, "webhook_add", format!("webhook #{}", webhook_id), None, Some(display_host(&url))).await;
+            ctx.say(format!(
+                "Added webhook `#{}` to {} for {}. New events are sent within a few seconds.",
+                webhook_id,
+                display_host(&url),
+                events.join(", ")
+            )).await?;
+        }
+        Err(e) => {
+            error!("Error adding webhook: {}", e);
+            ctx.say("Error adding webhook.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete a webhook and anything still queued for it
+#[poise::command(slash_command, rename = "remove", ephemeral)]
+pub async fn webhook_remove(
+    ctx: Context<'_>,
+    #[description = "Webhook number from /webhook list"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.remove_webhook(&guild_id, id).await {
+        Ok(true) => {
+            log_admin_action(ctx, "webhook_remove", format!("webhook #{}", id), None, None).await;
+            ctx.say(format!("Removed webhook `#{}`.", id)).await?;
+        }
+        Ok(false) => {
+            ctx.say(format!("No webhook `#{}` in this server.", id)).await?;
+        }
+        Err(e) => {
+            error!("Error removing webhook: {}", e);
+            ctx.say("Error removing webhook.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Queue deliveries that ran out of retries again, e.g. after fixing the endpoint
+#[poise::command(slash_command, rename = "retry", ephemeral)]
+pub async fn webhook_retry(
+    ctx: Context<'_>,
+    #[description = "Webhook number from /webhook list"] id: i64,
+) -> Result<(), Error> {
+    let data = &ctx.data();
+    let guild_id = ctx.guild_id().map(|id| id.to_string()).unwrap_or_default();
+
+    match data.database.retry_failed_webhook_deliveries(&guild_id, id).await {
+        Ok(0) => {
+            ctx.say(format!("Webhook `#{}` has no failed deliveries.", id)).await?;
+        }
+        Ok(count) => {
+            ctx.say(format!("Retrying {} failed deliveries for webhook `#{}`.", count, id)).await?;
+        }
+        Err(e) => {
+            error!("Error retrying webhook deliveries: {}", e);
+            ctx.say("Error retrying webhook deliveries.").await?;
+        }
+    }
+
+    Ok(())
+}
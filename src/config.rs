@@ -0,0 +1,126 @@
+//! Per-guild settings with defaults, overridable through the `guild_settings` table.
+
+use crate::database::{Database, DatabaseError};
+
+/// A per-guild setting that admins can change with `/config`
+pub struct Setting {
+    pub key: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting { key: "register.on_join", default: "false", description: "Register new members automatically when they join the server (needs the Server Members intent, takes effect after a restart)" },
+    Setting { key: "faucet.enabled", default: "true", description: "Allow /faucet in this server" },
+    Setting { key: "faucet.amount", default: "5", description: "Coins granted per faucet claim" },
+    Setting { key: "faucet.user_cooldown_hours", default: "24", description: "Hours between claims for one user" },
+    Setting { key: "faucet.guild_hourly_limit", default: "5", description: "Maximum faucet claims per hour in this server" },
+    Setting { key: "faucet.treasury_floor", default: "1000", description: "Treasury balance the faucet will not dip below" },
+    Setting { key: "daily.base_amount", default: "50", description: "Coins granted by /daily" },
+    Setting { key: "daily.streak_bonus", default: "10", description: "Extra coins per consecutive day of /daily" },
+    Setting { key: "daily.max_streak_bonus", default: "100", description: "Cap on the /daily streak bonus" },
+    Setting { key: "spin.coins_prize", default: "50", description: "Slumcoins won on the wheel's coins segment" },
+    Setting { key: "spin.big_coins_prize", default: "500", description: "Slumcoins won on the wheel's big win segment" },
+    Setting { key: "spin.weight_coins", default: "45", description: "Weight of the coins segment on /spin (0 = removed)" },
+    Setting { key: "spin.weight_big_coins", default: "5", description: "Weight of the big win segment on /spin (0 = removed)" },
+    Setting { key: "spin.weight_nothing", default: "35", description: "Weight of the nothing segment on /spin (0 = removed)" },
+    Setting { key: "spin.weight_item", default: "5", description: "Weight of the shop item segment on /spin, skipped while the shop has no plain items in stock" },
+    Setting { key: "spin.weight_ticket", default: "10", description: "Weight of the free lottery ticket segment on /spin (0 = removed)" },
+    Setting { key: "coinflip.house_cut_percent", default: "5", description: "Percent of coinflip winnings kept by the treasury" },
+    Setting { key: "dice.house_edge_percent", default: "2", description: "Percent taken off fair /dice payouts, kept by the treasury" },
+    Setting { key: "highlow.house_edge_percent", default: "3", description: "Percent taken off each /highlow multiplier step, kept by the treasury" },
+    Setting { key: "jackpot.loss_percent", default: "5", description: "Percent of every coinflip, roulette, dice, high-low and blackjack loss that feeds the jackpot" },
+    Setting { key: "jackpot.odds", default: "500", description: "Each finished game has a 1 in this many chance to win the jackpot (0 = never)" },
+    Setting { key: "heist.loot_percent", default: "100", description: "Percent of the crew's stakes the treasury adds as loot when a /heist succeeds" },
+    Setting { key: "trivia.reward", default: "50", description: "Slumcoins the treasury pays the fastest right /trivia answer (second and third get half and a quarter)" },
+    Setting { key: "fish.value_percent", default: "100", description: "Percent of each /fish catch's base value that /sell pays (at least 1 Slumcoin)" },
+    Setting { key: "property.income_percent", default: "100", description: "Percent of each property's base hourly income it earns" },
+    Setting { key: "property.auto_collect_hours", default: "24", description: "Hours after which uncollected property income is paid out automatically (0 = only /collect)" },
+    Setting { key: "stocks.volatility_percent", default: "5", description: "Most a stock's price moves either way each 15-minute tick, in percent" },
+    Setting { key: "paper.max_leverage", default: "10", description: "Highest leverage allowed on /paper positions (1 = no leverage)" },
+    Setting { key: "team.max_members", default: "10", description: "Most members a team can have, pending invites included" },
+    Setting { key: "treasury.budget.events", default: "0", description: "Monthly treasury budget for events (0 = no budget)" },
+    Setting { key: "treasury.budget.prizes", default: "0", description: "Monthly treasury budget for prizes (0 = no budget)" },
+    Setting { key: "treasury.budget.operations", default: "0", description: "Monthly treasury budget for operations (0 = no budget)" },
+    Setting { key: "lottery.ticket_price", default: "10", description: "Price of one lottery ticket" },
+    Setting { key: "lottery.draw_interval_hours", default: "168", description: "Hours between lottery draws" },
+    Setting { key: "lottery.channel_id", default: "", description: "Channel ID where lottery results are announced" },
+    Setting { key: "charity.distribution_interval_hours", default: "168", description: "Hours between charity pool payouts to a random active member" },
+    Setting { key: "charity.active_days", default: "7", description: "Days since their last transaction within which members can receive the charity pool" },
+    Setting { key: "charity.channel_id", default: "", description: "Channel ID where charity pool payouts are announced" },
+    Setting { key: "referral.new_member_bonus", default: "100", description: "Coins a new member gets for naming their inviter with /refer" },
+    Setting { key: "referral.inviter_bonus", default: "100", description: "Coins the inviter gets for each /refer naming them" },
+    Setting { key: "referral.window_days", default: "7", description: "Days after joining the server within which /refer can be claimed" },
+    Setting { key: "referral.min_account_age_days", default: "30", description: "Minimum age of both Discord accounts for a /refer claim" },
+    Setting { key: "auction.min_duration_seconds", default: "30", description: "Shortest auction /bid start allows" },
+    Setting { key: "auction.max_duration_seconds", default: "900", description: "Longest auction /bid start allows" },
+    Setting { key: "auction.min_anti_snipe_seconds", default: "5", description: "Smallest anti-snipe extension /bid start allows" },
+    Setting { key: "auction.max_anti_snipe_seconds", default: "60", description: "Largest anti-snipe extension /bid start allows" },
+    Setting { key: "auction.min_increment", default: "1", description: "Coins each auction bid must beat the last by" },
+    Setting { key: "auction.min_increment_percent", default: "0", description: "Percent each auction bid must beat the last by (overrides the flat increment when above 0)" },
+    Setting { key: "auction.channel_id", default: "", description: "Channel ID of the auction channel, whose topic members can buy for a day with /perk topic" },
+    Setting { key: "transfer.daily_limit", default: "0", description: "Most coins a user can send to others in any 24 hours (0 = no limit)" },
+    Setting { key: "confirm.threshold", default: "1000", description: "Amounts above this make /send and /give ask for confirmation first (0 = never)" },
+    Setting { key: "fees.flat", default: "0", description: "Flat fee in coins charged to the sender of each transfer" },
+    Setting { key: "fees.percent", default: "0", description: "Percent of each transfer charged to the sender as a fee" },
+    Setting { key: "escrow.expiry_hours", default: "72", description: "Hours before an undisputed escrow is refunded to the buyer" },
+    Setting { key: "loan.max_amount", default: "1000", description: "Largest loan /loan request accepts" },
+    Setting { key: "loan.interest_percent", default: "10", description: "Interest added on top of each loan's principal" },
+    Setting { key: "loan.installments", default: "4", description: "Number of payments a loan is repaid in" },
+    Setting { key: "loan.payment_interval_hours", default: "24", description: "Hours between loan payments" },
+    Setting { key: "loan.max_missed_payments", default: "3", description: "Missed payments before a loan defaults" },
+    Setting { key: "vault.daily_interest_bps", default: "10", description: "Daily interest paid on vault savings, in basis points (100 = 1%)" },
+    Setting { key: "tax.percent", default: "0", description: "Percent of each balance above the threshold collected by the wealth tax (0 = off)" },
+    Setting { key: "tax.threshold", default: "1000", description: "Balances at or below this are never taxed" },
+    Setting { key: "tax.interval_hours", default: "168", description: "Hours between wealth tax collections" },
+    Setting { key: "tax.channel_id", default: "", description: "Channel ID where wealth tax reports are posted" },
+    Setting { key: "rain.window_minutes", default: "10", description: "How far back /rain looks for people who've chatted in the channel (max 60)" },
+    Setting { key: "request.expiry_hours", default: "24", description: "Hours a /request stays payable" },
+    Setting { key: "fraud.channel_id", default: "", description: "Channel ID where suspicious transfer patterns are reported" },
+    Setting { key: "audit.channel_id", default: "", description: "Channel ID where ledger verification alerts are posted" },
+    Setting { key: "cooldown.baltop_seconds", default: "10", description: "Seconds a user waits between /baltop pages (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.coinflip_seconds", default: "3", description: "Seconds a user waits between coinflips (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.blackjack_seconds", default: "5", description: "Seconds a user waits between blackjack hands (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.roulette_seconds", default: "5", description: "Seconds a user waits between roulette spins (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.dice_seconds", default: "3", description: "Seconds a user waits between dice rolls (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.highlow_seconds", default: "5", description: "Seconds a user waits between high-low rounds (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.duel_seconds", default: "10", description: "Seconds a user waits between duel challenges (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.rps_seconds", default: "10", description: "Seconds a user waits between rock-paper-scissors challenges (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.heist_guild_seconds", default: "1800", description: "Seconds after a /heist before anyone in the server can start another (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.trivia_guild_seconds", default: "30", description: "Seconds after a /trivia round before anyone in the server can start another (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.fish_seconds", default: "300", description: "Seconds a user waits between casts of /fish (0 = no cooldown, admins exempt)" },
+    Setting { key: "cooldown.daily_seconds", default: "5", description: "Seconds a user waits between /daily attempts (0 = no cooldown, admins exempt)" },
+    Setting { key: "errors.channel_id", default: "", description: "Channel ID where command errors are posted for admins" },
+    Setting { key: "digest.channel_id", default: "", description: "Channel ID where the weekly economy digest is posted every Monday" },
+];
+
+pub fn find_setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.key == key)
+}
+
+/// Check that a value parses the same way as the setting's default
+pub fn validate(setting: &Setting, value: &str) -> bool {
+    if setting.default.parse::<bool>().is_ok() {
+        value.parse::<bool>().is_ok()
+    } else if setting.default.parse::<i64>().is_ok() {
+        value.parse::<i64>().is_ok()
+    } else {
+        true
+    }
+}
+
+pub async fn get(database: &Database, guild_id: &str, key: &str) -> Result<String, DatabaseError> {
+    if let Some(value) = database.get_guild_setting(guild_id, key).await? {
+        return Ok(value);
+    }
+
+    Ok(find_setting(key).map(|setting| setting.default.to_string()).unwrap_or_default())
+}
+
+pub async fn get_i64(database: &Database, guild_id: &str, key: &str) -> Result<i64, DatabaseError> {
+    Ok(get(database, guild_id, key).await?.parse().unwrap_or(0))
+}
+
+pub async fn get_bool(database: &Database, guild_id: &str, key: &str) -> Result<bool, DatabaseError> {
+    Ok(get(database, guild_id, key).await?.parse().unwrap_or(false))
+}
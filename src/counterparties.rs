@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::database::{Database, DatabaseError};
+
+// How long a user's suggestions are served from memory before re-querying the ledger
+const CACHE_TTL: Duration = Duration::from_secs(60);
+const MAX_SUGGESTIONS: u32 = 25;
+
+// (discord_id, username) pairs
+type Counterparties = Vec<(String, String)>;
+
+/// Per-user cache of the people they transact with most, used for target-user autocomplete
+#[derive(Debug, Clone, Default)]
+pub struct CounterpartyCache {
+    entries: Arc<RwLock<HashMap<String, (Instant, Counterparties)>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl CounterpartyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counterparties ordered by how often and how recently the user traded with them
+    pub async fn get(&self, database: &Database, discord_id: &str) -> Result<Counterparties, DatabaseError> {
+        if let Some((fetched_at, counterparties)) = self.entries.read().await.get(discord_id) {
+            if fetched_at.elapsed() < CACHE_TTL {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(counterparties.clone());
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let counterparties = database.get_recent_counterparties(discord_id, MAX_SUGGESTIONS).await?;
+        self.entries
+            .write()
+            .await
+            .insert(discord_id.to_string(), (Instant::now(), counterparties.clone()));
+
+        Ok(counterparties)
+    }
+
+    pub async fn invalidate(&self, discord_ids: &[&str]) {
+        let mut entries = self.entries.write().await;
+        for discord_id in discord_ids {
+            entries.remove(*discord_id);
+        }
+    }
+
+    /// (hits, misses) since startup
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
@@ -1,8 +1,29 @@
+//! Ed25519 user keypairs, encrypted at rest with a PBKDF2-derived master key that can be rotated.
+
 use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
 use ring::rand::SystemRandom;
+use ring::rand::SecureRandom;
 use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use ring::pbkdf2;
+use std::num::NonZeroU32;
 use base64::{Engine as _, engine::general_purpose};
-use tracing::{info, error};
+use tracing::{info, error, warn};
+
+use crate::checkpoint::SYSTEM_KEY_CONFIG_KEY;
+use crate::database::{Database, DatabaseError};
+
+// PBKDF2-HMAC-SHA256 parameters for deriving the master key
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const KDF_SALT_CONFIG_KEY: &str = "kdf_salt";
+
+// Wallet backups: prefix, then base64 of salt || nonce || AES-256-GCM ciphertext
+const BACKUP_PREFIX: &str = "slumcoin-backup-v1:";
+const NONCE_LEN: usize = 12;
+
+// Stored private keys: prefix, then base64 of nonce || AES-256-GCM ciphertext. Keys without the
+// prefix were sealed with an all-zero nonce and are resealed the next time the manager loads.
+const SEALED_KEY_PREFIX: &str = "v2:";
 
 #[derive(Debug)]
 pub enum CryptoError {
@@ -13,6 +34,7 @@ pub enum CryptoError {
     InvalidKey,
     Base64Error(base64::DecodeError),
     Utf8Error(std::string::FromUtf8Error),
+    Database(DatabaseError),
 }
 
 impl std::fmt::Display for CryptoError {
@@ -25,6 +47,7 @@ impl std::fmt::Display for CryptoError {
             CryptoError::InvalidKey => write!(f, "Invalid key"),
             CryptoError::Base64Error(e) => write!(f, "Base64 error: {}", e),
             CryptoError::Utf8Error(e) => write!(f, "UTF-8 error: {}", e),
+            CryptoError::Database(e) => write!(f, "Database error: {}", e),
         }
     }
 }
@@ -43,6 +66,12 @@ impl From<std::string::FromUtf8Error> for CryptoError {
     }
 }
 
+impl From<DatabaseError> for CryptoError {
+    fn from(err: DatabaseError) -> Self {
+        CryptoError::Database(err)
+    }
+}
+
 pub struct CryptoManager {
     master_key: LessSafeKey,
     rng: SystemRandom,
@@ -58,15 +87,33 @@ impl std::fmt::Debug for CryptoManager {
 }
 
 impl CryptoManager {
-    pub fn new(master_password: &str) -> Result<Self, CryptoError> {
-        // Derive a key from the master password (in production, use proper key derivation)
+    pub fn new(master_password: &str, salt: &[u8]) -> Result<Self, CryptoError> {
+        // Derive the AES key from the master password with PBKDF2
+        let mut key_bytes = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+            salt,
+            master_password.as_bytes(),
+            &mut key_bytes,
+        );
+
+        Self::from_key_bytes(&key_bytes)
+    }
+
+    // Key derivation used before PBKDF2 was introduced, kept only to migrate old records
+    fn legacy(master_password: &str) -> Result<Self, CryptoError> {
         let mut key_bytes = [0u8; 32];
         let password_bytes = master_password.as_bytes();
         for (i, &byte) in password_bytes.iter().cycle().take(32).enumerate() {
             key_bytes[i] = byte;
         }
-        
-        let unbound_key = UnboundKey::new(&AES_256_GCM, &key_bytes)
+
+        Self::from_key_bytes(&key_bytes)
+    }
+
+    fn from_key_bytes(key_bytes: &[u8; 32]) -> Result<Self, CryptoError> {
+        let unbound_key = UnboundKey::new(&AES_256_GCM, key_bytes)
             .map_err(|_| CryptoError::KeyGeneration)?;
         let master_key = LessSafeKey::new(unbound_key);
         let rng = SystemRandom::new();
@@ -74,6 +121,93 @@ impl CryptoManager {
         Ok(CryptoManager { master_key, rng })
     }
 
+    /// Load the crypto manager using the salt stored in the database.
+    ///
+    /// On first start after the switch to PBKDF2 no salt exists yet: a new one is
+    /// generated and every `encrypted_private_key` is re-encrypted from the legacy
+    /// key to the derived key in a single transaction.
+    pub async fn load(master_password: &str, database: &Database) -> Result<Self, CryptoError> {
+        if let Some(salt_b64) = database.get_system_config(KDF_SALT_CONFIG_KEY).await? {
+            let salt = general_purpose::STANDARD.decode(salt_b64)?;
+            let crypto = Self::new(master_password, &salt)?;
+            crypto.reseal_legacy_nonces(database, &salt).await?;
+            return Ok(crypto);
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| CryptoError::KeyGeneration)?;
+
+        let legacy = Self::legacy(master_password)?;
+        let crypto = Self::new(master_password, &salt)?;
+        let count = Self::reencrypt_all(database, &legacy, &crypto, &salt).await?;
+
+        info!("Migrated {} private keys to PBKDF2-derived master key", count);
+        Ok(crypto)
+    }
+
+    /// Re-encrypt every stored private key from the current master key to `new_password` under a fresh salt,
+    /// in a single transaction. Nothing is written unless every key decrypts with the old master key.
+    ///
+    /// Starting again with the old key still configured after a rotation went through is detected and
+    /// skipped, so the bot keeps running on the new key.
+    pub async fn rotate(old_password: &str, new_password: &str, database: &Database) -> Result<Self, CryptoError> {
+        let old = Self::load(old_password, database).await?;
+
+        let keys = database.get_encrypted_keys(&[SYSTEM_KEY_CONFIG_KEY]).await?;
+        if let Some(key) = keys.first() {
+            if old.decrypt_private_key(&key.encrypted, &key.key_id).is_err() {
+                let current = Self::load(new_password, database).await?;
+                current.decrypt_private_key(&key.encrypted, &key.key_id)?;
+                warn!("Private keys are already encrypted with the new master key, skipping rotation");
+                return Ok(current);
+            }
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| CryptoError::KeyGeneration)?;
+
+        let new = Self::new(new_password, &salt)?;
+        let count = Self::reencrypt_all(database, &old, &new, &salt).await?;
+
+        info!("Rotated master key, re-encrypted {} private keys", count);
+        Ok(new)
+    }
+
+    // Give every key still sealed with the shared zero nonce its own random one. Keys that don't
+    // open with this master key are left for `rotate` to sort out.
+    async fn reseal_legacy_nonces(&self, database: &Database, salt: &[u8]) -> Result<(), CryptoError> {
+        let keys = database.get_encrypted_keys(&[SYSTEM_KEY_CONFIG_KEY]).await?;
+        if keys.iter().all(|key| key.encrypted.starts_with(SEALED_KEY_PREFIX)) {
+            return Ok(());
+        }
+
+        match Self::reencrypt_all(database, self, self, salt).await {
+            Ok(count) => info!("Resealed {} private keys with per-key nonces", count),
+            Err(CryptoError::Decryption) => warn!("Private keys don't open with this master key, not resealing them"),
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    async fn reencrypt_all(database: &Database, from: &Self, to: &Self, salt: &[u8]) -> Result<usize, CryptoError> {
+        let mut keys = database.get_encrypted_keys(&[SYSTEM_KEY_CONFIG_KEY]).await?;
+        for key in &mut keys {
+            let private_key = from.decrypt_private_key(&key.encrypted, &key.key_id)?;
+            key.encrypted = to.encrypt_private_key(&private_key, &key.key_id)?;
+        }
+
+        let salt_b64 = general_purpose::STANDARD.encode(salt);
+        database
+            .replace_encrypted_private_keys(&keys, KDF_SALT_CONFIG_KEY, &salt_b64)
+            .await?;
+
+        Ok(keys.len())
+    }
+
     pub fn generate_keypair(&self) -> Result<(String, String), CryptoError> {
         // Generate Ed25519 keypair
         let keypair_bytes = Ed25519KeyPair::generate_pkcs8(&self.rng)
@@ -92,31 +226,99 @@ impl CryptoManager {
         Ok((public_key, private_key))
     }
 
+    /// Public key matching a base64 PKCS#8 private key
+    pub fn public_key_for(&self, private_key_b64: &str) -> Result<String, CryptoError> {
+        let private_key_bytes = general_purpose::STANDARD.decode(private_key_b64)?;
+        let keypair = Ed25519KeyPair::from_pkcs8(&private_key_bytes)
+            .map_err(|_| CryptoError::InvalidKey)?;
+
+        Ok(general_purpose::STANDARD.encode(keypair.public_key().as_ref()))
+    }
+
+    /// Seal a private key under the master key with a fresh random nonce, bound to `user_id`
     pub fn encrypt_private_key(&self, private_key: &str, user_id: &str) -> Result<String, CryptoError> {
         let mut data = private_key.as_bytes().to_vec();
-        let nonce_bytes = [0u8; 12]; // In production, use random nonce
-        let nonce = Nonce::assume_unique_for_key(nonce_bytes);
-        
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce_bytes).map_err(|_| CryptoError::Encryption)?;
+
         self.master_key.seal_in_place_append_tag(
-            nonce,
+            Nonce::assume_unique_for_key(nonce_bytes),
             Aad::from(user_id.as_bytes()),
             &mut data,
         ).map_err(|_| CryptoError::Encryption)?;
-        
-        Ok(general_purpose::STANDARD.encode(&data))
+
+        let mut blob = nonce_bytes.to_vec();
+        blob.extend_from_slice(&data);
+        Ok(format!("{}{}", SEALED_KEY_PREFIX, general_purpose::STANDARD.encode(&blob)))
     }
 
+    /// Open a key sealed by `encrypt_private_key`, or one from before per-key nonces
     pub fn decrypt_private_key(&self, encrypted_key: &str, user_id: &str) -> Result<String, CryptoError> {
-        let mut data = general_purpose::STANDARD.decode(encrypted_key)?;
-        let nonce_bytes = [0u8; 12]; // Same nonce used for encryption
-        let nonce = Nonce::assume_unique_for_key(nonce_bytes);
-        
+        let (nonce, mut data) = match encrypted_key.strip_prefix(SEALED_KEY_PREFIX) {
+            Some(sealed) => {
+                let mut blob = general_purpose::STANDARD.decode(sealed)?;
+                if blob.len() < NONCE_LEN {
+                    return Err(CryptoError::InvalidKey);
+                }
+                let data = blob.split_off(NONCE_LEN);
+                let nonce = Nonce::try_assume_unique_for_key(&blob).map_err(|_| CryptoError::InvalidKey)?;
+                (nonce, data)
+            }
+            None => (Nonce::assume_unique_for_key([0u8; NONCE_LEN]), general_purpose::STANDARD.decode(encrypted_key)?),
+        };
+
         let decrypted = self.master_key.open_in_place(
             nonce,
             Aad::from(user_id.as_bytes()),
             &mut data,
         ).map_err(|_| CryptoError::Decryption)?;
-        
+
+        Ok(String::from_utf8(decrypted.to_vec())?)
+    }
+
+    /// Encrypt a private key under a user-chosen passphrase, independent of the master key, so the
+    /// backup still opens after the database or the master key is lost. Bound to the user's id.
+    pub fn encrypt_backup(&self, private_key: &str, user_id: &str, passphrase: &str) -> Result<String, CryptoError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        self.rng.fill(&mut salt).map_err(|_| CryptoError::KeyGeneration)?;
+        self.rng.fill(&mut nonce_bytes).map_err(|_| CryptoError::KeyGeneration)?;
+
+        let backup_key = Self::new(passphrase, &salt)?.master_key;
+        let mut data = private_key.as_bytes().to_vec();
+        backup_key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(user_id.as_bytes()),
+            &mut data,
+        ).map_err(|_| CryptoError::Encryption)?;
+
+        let mut blob = Vec::with_capacity(SALT_LEN + NONCE_LEN + data.len());
+        blob.extend_from_slice(&salt);
+        blob.extend_from_slice(&nonce_bytes);
+        blob.extend_from_slice(&data);
+        Ok(format!("{}{}", BACKUP_PREFIX, general_purpose::STANDARD.encode(blob)))
+    }
+
+    /// Recover the private key from a backup made by `encrypt_backup`
+    pub fn decrypt_backup(backup: &str, user_id: &str, passphrase: &str) -> Result<String, CryptoError> {
+        let blob = backup.trim().strip_prefix(BACKUP_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        let blob = general_purpose::STANDARD.decode(blob)?;
+        if blob.len() < SALT_LEN + NONCE_LEN {
+            return Err(CryptoError::InvalidKey);
+        }
+
+        let (salt, rest) = blob.split_at(SALT_LEN);
+        let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes).map_err(|_| CryptoError::InvalidKey)?;
+
+        let backup_key = Self::new(passphrase, salt)?.master_key;
+        let mut data = ciphertext.to_vec();
+        let decrypted = backup_key.open_in_place(
+            nonce,
+            Aad::from(user_id.as_bytes()),
+            &mut data,
+        ).map_err(|_| CryptoError::Decryption)?;
+
         Ok(String::from_utf8(decrypted.to_vec())?)
     }
 
@@ -151,3 +353,51 @@ impl CryptoManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    // How keys were sealed before each one got its own nonce
+    fn seal_with_zero_nonce(crypto: &CryptoManager, private_key: &str, user_id: &str) -> String {
+        let mut data = private_key.as_bytes().to_vec();
+        crypto.master_key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key([0u8; NONCE_LEN]),
+            Aad::from(user_id.as_bytes()),
+            &mut data,
+        ).unwrap();
+        general_purpose::STANDARD.encode(&data)
+    }
+
+    #[test]
+    fn each_encryption_gets_its_own_nonce() {
+        let crypto = CryptoManager::new("password", b"salt").unwrap();
+        let first = crypto.encrypt_private_key("secret", "alice").unwrap();
+        let second = crypto.encrypt_private_key("secret", "alice").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(crypto.decrypt_private_key(&first, "alice").unwrap(), "secret");
+        assert_eq!(crypto.decrypt_private_key(&second, "alice").unwrap(), "secret");
+        assert!(crypto.decrypt_private_key(&first, "bob").is_err());
+    }
+
+    #[tokio::test]
+    async fn zero_nonce_keys_are_resealed_on_load() {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 0).await.unwrap();
+
+        let mut keys = database.get_encrypted_keys(&[]).await.unwrap();
+        let private_key = crypto.decrypt_private_key(&keys[0].encrypted, "alice").unwrap();
+        keys[0].encrypted = seal_with_zero_nonce(&crypto, &private_key, "alice");
+        let salt = database.get_system_config(KDF_SALT_CONFIG_KEY).await.unwrap().unwrap();
+        database.replace_encrypted_private_keys(&keys, KDF_SALT_CONFIG_KEY, &salt).await.unwrap();
+        assert_eq!(crypto.decrypt_private_key(&keys[0].encrypted, "alice").unwrap(), private_key);
+
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        let resealed = &database.get_encrypted_keys(&[]).await.unwrap()[0].encrypted;
+        assert!(resealed.starts_with(SEALED_KEY_PREFIX));
+        assert_eq!(crypto.decrypt_private_key(resealed, "alice").unwrap(), private_key);
+    }
+}
@@ -1,9 +1,28 @@
-use sqlx::{SqlitePool, Row};
+//! SQLite storage for users, the transaction ledger, cached balances and subsystem state.
+
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Row};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 use tracing::info;
 
+use crate::balance_cache::BalanceCache;
+use crate::metrics;
+
+// Mint source for admin grants; it has no balance row of its own
+pub const SYSTEM_ACCOUNT: &str = "SYSTEM";
+
+pub const WEEK_SECONDS: i64 = 7 * 86400;
+// 1970-01-05 00:00 UTC, the first Monday after the epoch; weeks start on Mondays
+const FIRST_MONDAY_UNIX: i64 = 4 * 86400;
+
+// prev_hash of the first entry in the ledger's hash chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub discord_id: String,
@@ -15,354 +34,7172 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Transaction {
-    pub id: String,
-    pub from_user: String,
-    pub to_user: String,
-    pub amount: i64,
-    pub transaction_type: String,
-    pub message: Option<String>,
-    pub nonce: i64,
-    pub signature: String,
-    pub timestamp_unix: i64,
-    pub created_at: DateTime<Utc>,
-}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id: String,
+    pub from_user: String,
+    pub to_user: String,
+    pub amount: i64,
+    pub transaction_type: String,
+    pub message: Option<String>,
+    pub nonce: i64,
+    pub signature: String,
+    pub timestamp_unix: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Narrows ledger queries down; every field left as `None` matches all entries
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    /// Entries sent or received by this user
+    pub user: Option<String>,
+    /// Entries between `user` and this account, in either direction (or involving it at all without `user`)
+    pub counterparty: Option<String>,
+    pub transaction_type: Option<String>,
+    /// Unix timestamp of the earliest entry, inclusive
+    pub from: Option<i64>,
+    /// Unix timestamp of the latest entry, exclusive
+    pub to: Option<i64>,
+}
+
+impl TransactionFilter {
+    pub fn for_user(discord_id: &str) -> Self {
+        TransactionFilter { user: Some(discord_id.to_string()), ..Default::default() }
+    }
+}
+
+// WHERE clause shared by the filtered ledger queries, binding a TransactionFilter as ?1 to ?5
+const TRANSACTION_FILTER: &str = r#"
+    (?1 IS NULL OR from_user = ?1 OR to_user = ?1)
+    AND (?2 IS NULL OR from_user = ?2 OR to_user = ?2)
+    AND (?3 IS NULL OR transaction_type = ?3)
+    AND (?4 IS NULL OR timestamp_unix >= ?4)
+    AND (?5 IS NULL OR timestamp_unix < ?5)
+"#;
+
+#[derive(Debug, Clone)]
+pub struct Balance {
+    pub discord_id: String,
+    pub balance: i64,
+    pub last_updated: DateTime<Utc>,
+}
+
+impl Transaction {
+    // Unsigned ledger entry created by the bot itself (mints, payouts, game settlements)
+    pub fn system(from_user: &str, to_user: &str, amount: i64, transaction_type: &str, message: Option<String>) -> Self {
+        let now = Utc::now();
+        Transaction {
+            id: uuid::Uuid::new_v4().to_string(),
+            from_user: from_user.to_string(),
+            to_user: to_user.to_string(),
+            amount,
+            transaction_type: transaction_type.to_string(),
+            message,
+            nonce: 0,
+            signature: "system".to_string(),
+            timestamp_unix: now.timestamp(),
+            created_at: now,
+        }
+    }
+
+    // Canonical message signed by the sender and checked by the audit
+    pub fn signing_payload(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{}",
+            self.id, self.from_user, self.to_user, self.amount, self.transaction_type, self.nonce, self.timestamp_unix
+        )
+    }
+
+    // Hex SHA-256 committing to this entry and, through `prev_hash`, to every entry before it
+    pub fn chain_hash(&self, prev_hash: &str) -> String {
+        let payload = format!(
+            "{}|{}|{}|{}",
+            prev_hash,
+            self.signing_payload(),
+            self.message.as_deref().unwrap_or_default(),
+            self.signature
+        );
+        ring::digest::digest(&ring::digest::SHA256, payload.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn is_user_signed(&self) -> bool {
+        !self.signature.is_empty() && self.signature != "system"
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PinnedLeaderboard {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub message_id: String,
+    pub interval_minutes: i64,
+    pub limit: u32,
+    pub last_refreshed: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LotteryRound {
+    pub id: i64,
+    pub guild_id: String,
+    pub ticket_price: i64,
+    pub draw_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Giveaway {
+    pub id: i64,
+    pub guild_id: String,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    pub host_id: String,
+    pub prize: String,
+    pub entry_cost: i64,
+    pub pot: i64,
+    // open or drawn
+    pub status: String,
+    pub winner_id: Option<String>,
+    pub ends_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bet {
+    pub id: i64,
+    pub guild_id: String,
+    pub question: String,
+    pub created_by: String,
+    pub closes_at: Option<i64>,
+    // open, resolved or cancelled
+    pub status: String,
+    pub winning_option_id: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BetOption {
+    pub id: i64,
+    // 1-based, as users pick it
+    pub position: i64,
+    pub label: String,
+    // Total staked on this option
+    pub pool: i64,
+    pub backers: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BetEntry {
+    pub option_id: i64,
+    pub discord_id: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TriviaQuestion {
+    pub id: i64,
+    // None for the built-in bank
+    pub guild_id: Option<String>,
+    pub category: String,
+    pub question: String,
+    pub answer: String,
+    pub wrong_answers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Jackpot {
+    pub pot: i64,
+    pub last_winner_id: Option<String>,
+    pub last_payout: Option<i64>,
+    pub last_won_at: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub id: i64,
+    pub guild_id: String,
+    pub discord_id: String,
+    // farm, mine or arcade
+    pub kind: String,
+    pub tier: i64,
+    pub purchased_at: i64,
+    pub last_collected_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stock {
+    pub id: i64,
+    pub guild_id: String,
+    pub symbol: String,
+    pub name: String,
+    pub price: i64,
+    // Where the random walk drifts back to
+    pub base_price: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StockHolding {
+    pub stock_id: i64,
+    pub symbol: String,
+    pub name: String,
+    pub shares: i64,
+    // What was paid for the shares still held
+    pub cost_basis: i64,
+    pub price: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaperPosition {
+    pub id: i64,
+    pub guild_id: String,
+    pub discord_id: String,
+    // btc or eth
+    pub asset: String,
+    // long or short
+    pub direction: String,
+    pub stake: i64,
+    pub leverage: i64,
+    pub entry_price_cents: i64,
+    pub opened_at: i64,
+    // open, closed or liquidated
+    pub status: String,
+    pub exit_price_cents: Option<i64>,
+    pub payout: Option<i64>,
+    pub closed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BalanceSnapshot {
+    // Midnight UTC, as unix seconds
+    pub day: i64,
+    pub balance: i64,
+    pub net_worth: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Team {
+    pub id: i64,
+    pub guild_id: String,
+    pub name: String,
+    pub balance: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamMember {
+    pub team_id: i64,
+    pub discord_id: String,
+    // leader, officer, member or invited
+    pub role: String,
+    pub joined_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Marriage {
+    pub id: i64,
+    pub guild_id: String,
+    // Whoever proposed
+    pub partner_a: String,
+    pub partner_b: String,
+    pub married_at: i64,
+    pub has_joint_account: bool,
+    pub joint_balance: i64,
+    pub divorced_at: Option<i64>,
+}
+
+impl Marriage {
+    pub fn partner_of(&self, discord_id: &str) -> &str {
+        if self.partner_a == discord_id { &self.partner_b } else { &self.partner_a }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharityMatch {
+    pub id: i64,
+    pub guild_id: String,
+    pub percent: i64,
+    // None means no cap
+    pub cap: Option<i64>,
+    pub matched: i64,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub created_by: String,
+}
+
+// A bid deposit held in escrow for a running auction
+#[derive(Debug, Clone)]
+pub struct AuctionDeposit {
+    pub voice_channel_id: String,
+    pub discord_id: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TopicPerk {
+    pub id: i64,
+    pub guild_id: String,
+    pub channel_id: String,
+    pub discord_id: String,
+    pub topic: String,
+    pub original_topic: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShopItem {
+    pub id: i64,
+    pub guild_id: String,
+    pub name: String,
+    pub price: i64,
+    pub role_id: Option<String>,
+    pub role_duration_hours: Option<i64>,
+    pub stock: Option<i64>,
+    pub consumable: bool,
+    pub tradeable: bool,
+    // Fulfilled by /perk rather than kept in the inventory
+    pub perk: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InventoryItem {
+    pub item_id: i64,
+    pub name: String,
+    pub quantity: i64,
+    pub consumable: bool,
+    pub tradeable: bool,
+    pub sell_value: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub id: i64,
+    pub guild_id: String,
+    pub name: String,
+    pub item_id: i64,
+    pub starts_at: i64,
+    pub ticket_price: i64,
+    pub capacity: i64,
+    pub attendance_bonus: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub id: i64,
+    pub phrase: String,
+    pub response: String,
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoleGrant {
+    pub id: i64,
+    pub guild_id: String,
+    pub discord_id: String,
+    pub role_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Escrow {
+    pub id: i64,
+    pub guild_id: String,
+    pub buyer: String,
+    pub seller: String,
+    pub amount: i64,
+    pub description: String,
+    pub status: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EconomyTotals {
+    pub circulating: i64,
+    pub minted: i64,
+    pub burned: i64,
+}
+
+// Where an encrypted private key is stored
+#[derive(Debug, Clone)]
+pub enum KeyLocation {
+    User(String),
+    Archived(i64),
+    SystemConfig(String),
+}
+
+// A private key encrypted under the master key, with the id it's bound to
+#[derive(Debug, Clone)]
+pub struct EncryptedKey {
+    pub location: KeyLocation,
+    pub key_id: String,
+    pub encrypted: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub id: i64,
+    pub transaction_count: i64,
+    pub last_hash: String,
+    pub total_supply: i64,
+    pub signature: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    pub events: Vec<String>,
+    pub min_amount: i64,
+    pub pending: i64,
+    pub failed: i64,
+}
+
+/// Something that happened, to be queued for every webhook subscribed to `event`
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub event: String,
+    // Compared against each webhook's min_amount for large_transaction events
+    pub amount: i64,
+    // Only webhooks from this guild get the event; ledger events go to every guild
+    pub guild_id: Option<String>,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub url: String,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Season {
+    pub id: i64,
+    pub name: String,
+    pub started_at: i64,
+    // None while the season is running
+    pub ended_at: Option<i64>,
+}
+
+/// What `Database::start_season` did
+#[derive(Debug, Clone)]
+pub struct SeasonChange {
+    pub ended: Season,
+    pub started: Season,
+    pub archived: i64,
+    // Balances moved to the seed amount
+    pub reset: i64,
+}
+
+/// Lifetime activity of one user, for /profile
+#[derive(Debug, Clone)]
+pub struct ProfileStats {
+    pub registered_at: DateTime<Utc>,
+    // Ledger totals, leaving out moves between a user's wallet and their own vault
+    pub sent: i64,
+    pub received: i64,
+    pub transactions: i64,
+    pub auctions_won: i64,
+    pub lottery_wins: i64,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RankInfo {
+    pub rank: i64,
+    pub total_users: i64,
+    pub balance: i64,
+    // Balance held by the user one place above, if anyone is ahead
+    pub next_balance: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Loan {
+    pub id: i64,
+    pub guild_id: String,
+    pub borrower: String,
+    pub principal: i64,
+    pub total_due: i64,
+    pub repaid: i64,
+    pub installment: i64,
+    pub payment_interval_seconds: i64,
+    pub reason: Option<String>,
+    pub status: String,
+    pub requested_at: i64,
+    pub approved_by: Option<String>,
+    pub next_payment_at: Option<i64>,
+    pub missed_payments: i64,
+}
+
+impl Loan {
+    pub fn outstanding(&self) -> i64 {
+        (self.total_due - self.repaid).max(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub guild_id: String,
+    pub actor: String,
+    // e.g. "give", "freeze" or "config_set"
+    pub action: String,
+    // A user ID, transaction ID, setting key or whatever else the action applied to
+    pub target: String,
+    pub amount: Option<i64>,
+    pub reason: Option<String>,
+    pub created_at: i64,
+}
+
+/// Ledger activity in one week
+#[derive(Debug, Clone, Default)]
+pub struct WeeklyStats {
+    pub week_start: i64,
+    pub transactions: i64,
+    // Coins moved between accounts, leaving out mints and burns
+    pub volume: i64,
+    pub minted: i64,
+    pub burned: i64,
+}
+
+impl WeeklyStats {
+    pub fn supply_change(&self) -> i64 {
+        self.minted - self.burned
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuctionSummary {
+    pub held: i64,
+    pub sold: i64,
+    // Winning bids of sold auctions
+    pub raised: i64,
+}
+
+/// Start (Monday 00:00 UTC) of the week containing `unix`
+pub fn week_start(unix: i64) -> i64 {
+    unix - (unix - FIRST_MONDAY_UNIX).rem_euclid(WEEK_SECONDS)
+}
+
+#[derive(Debug, Clone)]
+pub struct TaxRun {
+    pub guild_id: String,
+    pub percent: i64,
+    pub threshold: i64,
+    pub taxpayers: i64,
+    pub collected: i64,
+    pub ran_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Payroll {
+    pub guild_id: String,
+    pub role_id: String,
+    pub amount: i64,
+    pub interval_seconds: i64,
+    pub next_payout_at: i64,
+    pub created_by: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GiftCode {
+    pub code: String,
+    pub guild_id: String,
+    pub amount: i64,
+    pub max_uses: i64,
+    pub uses: i64,
+    pub created_by: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserPreferences {
+    // Show balance, ledger and registration replies only to the user
+    pub private_replies: bool,
+    // Let other users see this user's balance with /balance @user
+    pub public_balance: bool,
+    // Leave this user off /baltop
+    pub hide_from_leaderboard: bool,
+}
+
+// Self-imposed gambling controls, None when unset
+#[derive(Debug, Clone, Default)]
+pub struct GambleLimits {
+    // Most a user can wager in 24 hours
+    pub daily_limit: Option<i64>,
+    // No wagers at all until this unix time
+    pub excluded_until: Option<i64>,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self { private_replies: true, public_balance: false, hide_from_leaderboard: false }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuctionRecord {
+    pub item: String,
+    pub outcome: String,
+    pub winner_id: Option<String>,
+    pub amount: Option<i64>,
+    pub bid_count: i64,
+    pub ended_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub id: i64,
+    pub guild_id: String,
+    pub requester: String,
+    pub payer: String,
+    pub amount: i64,
+    pub reason: Option<String>,
+    pub status: String,
+    pub expires_at: i64,
+}
+
+/// A write that would have taken an account below zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsufficientFunds {
+    pub discord_id: String,
+    pub balance: i64,
+    pub required: i64,
+}
+
+/// Everything a `Database` method can fail with
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// The account has no `users` row
+    NotRegistered(String),
+    InsufficientFunds(InsufficientFunds),
+    /// A uniqueness or ordering rule refused the write, usually because another write got there first
+    Conflict(String),
+    Io(std::io::Error),
+    Sqlx(sqlx::Error),
+}
+
+impl DatabaseError {
+    fn insufficient_funds(discord_id: &str, balance: i64, required: i64) -> Self {
+        DatabaseError::InsufficientFunds(InsufficientFunds { discord_id: discord_id.to_string(), balance, required })
+    }
+}
+
+impl std::fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DatabaseError::NotRegistered(discord_id) => write!(f, "{} is not registered", discord_id),
+            DatabaseError::InsufficientFunds(overdraft) => write!(
+                f,
+                "Insufficient funds: {} has {} but {} is required",
+                overdraft.discord_id, overdraft.balance, overdraft.required
+            ),
+            DatabaseError::Conflict(message) => write!(f, "Conflicting write: {}", message),
+            DatabaseError::Io(e) => write!(f, "Database I/O error: {}", e),
+            DatabaseError::Sqlx(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatabaseError::Io(e) => Some(e),
+            DatabaseError::Sqlx(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for DatabaseError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::Io(e) => DatabaseError::Io(e),
+            // Unique indexes and the nonce trigger from migration 022
+            sqlx::Error::Database(e) if e.is_unique_violation() || e.message().contains("nonce out of order") => {
+                DatabaseError::Conflict(e.message().to_string())
+            }
+            err => DatabaseError::Sqlx(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for DatabaseError {
+    fn from(err: std::io::Error) -> Self {
+        DatabaseError::Io(err)
+    }
+}
+
+// Whether SQLite refused a write through the overdraft triggers (see migration 027)
+fn is_overdraft_trigger(err: &sqlx::Error) -> bool {
+    err.as_database_error().is_some_and(|e| e.message() == "insufficient funds")
+}
+
+/// How `Database::connect` sets up the SQLite pool
+#[derive(Debug, Clone)]
+pub struct DatabaseOptions {
+    /// Write-ahead logging, so reads carry on while a write is in progress
+    pub wal: bool,
+    /// How long a connection waits on another's lock before failing with `database is locked`
+    pub busy_timeout: Duration,
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        DatabaseOptions {
+            wal: true,
+            busy_timeout: Duration::from_secs(5),
+            max_connections: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Database {
+    pool: SqlitePool,
+    balances: BalanceCache,
+}
+
+/// A database transaction that writes ledger entries. The balances it changed reach the balance
+/// cache only once it commits; dropping it rolls back and leaves the cache alone.
+pub(crate) struct LedgerTx {
+    tx: sqlx::Transaction<'static, Sqlite>,
+    cache: BalanceCache,
+    // (discord_id, balance, chain_seq) for every balance written so far
+    written: Vec<(String, i64, i64)>,
+}
+
+impl LedgerTx {
+    pub(crate) async fn commit(self) -> Result<(), DatabaseError> {
+        self.tx.commit().await?;
+        for (discord_id, balance, seq) in &self.written {
+            self.cache.write_through(discord_id, *balance, *seq);
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for LedgerTx {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        &self.tx
+    }
+}
+
+impl std::ops::DerefMut for LedgerTx {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        &mut self.tx
+    }
+}
+
+impl Database {
+    pub async fn new(database_url: &str) -> Result<Self, DatabaseError> {
+        Self::connect(database_url, DatabaseOptions::default()).await
+    }
+
+    pub async fn connect(database_url: &str, options: DatabaseOptions) -> Result<Self, DatabaseError> {
+        // Ensure the database directory exists
+        if let Some(parent) = Path::new(database_url).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let journal_mode = if options.wal { SqliteJournalMode::Wal } else { SqliteJournalMode::Delete };
+        let connect_options = SqliteConnectOptions::from_str(database_url)?
+            .journal_mode(journal_mode)
+            // NORMAL only risks the last commits on power loss under WAL, never corruption
+            .synchronous(if options.wal { SqliteSynchronous::Normal } else { SqliteSynchronous::Full })
+            .busy_timeout(options.busy_timeout);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(options.max_connections.max(1))
+            .connect_with(connect_options)
+            .await?;
+
+        Self::open(pool).await
+    }
+
+    /// A fresh, migrated database held in memory, for exercising the engine and command logic
+    /// without a database file (see `fixtures`). It is gone once the last clone is dropped.
+    pub async fn in_memory() -> Result<Self, DatabaseError> {
+        // sqlx names each in-memory database and shares it between the pool's connections, but
+        // SQLite drops it when the last one closes, so one connection is never allowed to expire
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
+            .await?;
+
+        Self::open(pool).await
+    }
+
+    async fn open(pool: SqlitePool) -> Result<Self, DatabaseError> {
+        // Apply any pending schema migrations from migrations/
+        sqlx::migrate!("./migrations").run(&pool).await.map_err(sqlx::Error::from)?;
+        
+        info!("Database connected and migrations applied");
+
+        let database = Database { pool, balances: BalanceCache::new() };
+        database.link_unchained_transactions().await?;
+        Ok(database)
+    }
+
+    // Begin a database transaction whose balance changes are written through to the cache on commit
+    async fn begin_ledger(&self) -> Result<LedgerTx, DatabaseError> {
+        Ok(LedgerTx { tx: self.pool.begin().await?, cache: self.balances.clone(), written: Vec::new() })
+    }
+
+    /// (hits, misses) of the in-memory balance cache since startup
+    pub fn balance_cache_stats(&self) -> (u64, u64) {
+        self.balances.stats()
+    }
+
+    /// (open connections, idle connections, max connections) of the pool
+    pub fn pool_stats(&self) -> (u32, usize, u32) {
+        (self.pool.size(), self.pool.num_idle(), self.pool.options().get_max_connections())
+    }
+
+    /// Write a consistent copy of the whole database to `path`, which must not exist yet
+    pub async fn backup_into(&self, path: &str) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("backup_into");
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // User management
+    pub async fn create_user(&self, user: &User) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("create_user");
+        sqlx::query(
+            "INSERT INTO users (discord_id, username, public_key, encrypted_private_key, nonce) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&user.discord_id)
+        .bind(&user.username)
+        .bind(&user.public_key)
+        .bind(&user.encrypted_private_key)
+        .bind(user.nonce)
+        .execute(&self.pool)
+        .await?;
+
+        // Initialize balance
+        sqlx::query("INSERT INTO balances (discord_id, balance) VALUES (?, 0)")
+            .bind(&user.discord_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user(&self, discord_id: &str) -> Result<Option<User>, DatabaseError> {
+        let _timer = metrics::query_timer("get_user");
+        let row = sqlx::query(
+            "SELECT discord_id, username, public_key, encrypted_private_key, nonce, created_at, updated_at FROM users WHERE discord_id = ?"
+        )
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(User {
+                discord_id: row.get("discord_id"),
+                username: row.get("username"),
+                public_key: row.get("public_key"),
+                encrypted_private_key: row.get("encrypted_private_key"),
+                nonce: row.get("nonce"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Like `get_user`, failing with `DatabaseError::NotRegistered` when there's no such user
+    pub async fn require_user(&self, discord_id: &str) -> Result<User, DatabaseError> {
+        self.get_user(discord_id).await?.ok_or_else(|| DatabaseError::NotRegistered(discord_id.to_string()))
+    }
+
+    pub async fn get_all_users(&self) -> Result<Vec<User>, DatabaseError> {
+        let _timer = metrics::query_timer("get_all_users");
+        let rows = sqlx::query(
+            "SELECT discord_id, username, public_key, encrypted_private_key, nonce, created_at, updated_at FROM users"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut users = Vec::new();
+        for row in rows {
+            users.push(User {
+                discord_id: row.get("discord_id"),
+                username: row.get("username"),
+                public_key: row.get("public_key"),
+                encrypted_private_key: row.get("encrypted_private_key"),
+                nonce: row.get("nonce"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            });
+        }
+
+        Ok(users)
+    }
+
+    // Public key for verifying a user's signatures, falling back to their archived key if they've unregistered
+    pub async fn get_public_key(&self, discord_id: &str) -> Result<Option<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_public_key");
+        let row = sqlx::query(
+            r#"
+            SELECT public_key FROM users WHERE discord_id = ?
+            UNION ALL
+            SELECT public_key FROM (SELECT public_key FROM archived_users WHERE discord_id = ? ORDER BY id DESC LIMIT 1)
+            LIMIT 1
+            "#
+        )
+        .bind(discord_id)
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| row.get("public_key")))
+    }
+
+    /// The first thing of `discord_id`'s that unregistering would orphan, from systems that keep
+    /// their own records outside the balance: shares, teams, marriages, properties, paper trades,
+    /// open bets, lottery tickets and auction deposits. `None` when there's nothing.
+    pub async fn get_unregister_blocker(&self, discord_id: &str) -> Result<Option<&'static str>, DatabaseError> {
+        let _timer = metrics::query_timer("get_unregister_blocker");
+        let checks = [
+            ("shares in the stock market", "SELECT 1 FROM stock_holdings WHERE discord_id = ? AND shares > 0 LIMIT 1"),
+            ("a team membership", "SELECT 1 FROM team_members WHERE discord_id = ? LIMIT 1"),
+            ("a marriage", "SELECT 1 FROM marriages WHERE divorced_at IS NULL AND (partner_a = ?1 OR partner_b = ?1) LIMIT 1"),
+            ("properties", "SELECT 1 FROM properties WHERE discord_id = ? LIMIT 1"),
+            ("open paper trades", "SELECT 1 FROM paper_positions WHERE discord_id = ? AND status = 'open' LIMIT 1"),
+            (
+                "stakes in an open bet",
+                "SELECT 1 FROM bet_entries JOIN bets ON bets.id = bet_entries.bet_id WHERE bet_entries.discord_id = ? AND bets.status = 'open' LIMIT 1",
+            ),
+            (
+                "tickets in an undrawn lottery",
+                "SELECT 1 FROM lottery_tickets JOIN lottery_rounds ON lottery_rounds.id = lottery_tickets.round_id WHERE lottery_tickets.discord_id = ? AND lottery_rounds.drawn = 0 LIMIT 1",
+            ),
+            ("a deposit in a running auction", "SELECT 1 FROM auction_deposits WHERE discord_id = ? LIMIT 1"),
+        ];
+
+        for (blocker, query) in checks {
+            if sqlx::query(query).bind(discord_id).fetch_optional(&self.pool).await?.is_some() {
+                return Ok(Some(blocker));
+            }
+        }
+        Ok(None)
+    }
+
+    // Move a user into archived_users, sweeping their balance with `sweep` and dropping them from users and balances.
+    // Returns false if they aren't registered or their balance no longer matches the sweep.
+    pub async fn archive_user(
+        &self,
+        discord_id: &str,
+        archived_by: &str,
+        sweep: Option<&Transaction>,
+        entry: Option<&AuditEntry>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("archive_user");
+        let mut tx = self.begin_ledger().await?;
+
+        let balance: i64 = sqlx::query("SELECT COALESCE((SELECT balance FROM balances WHERE discord_id = ?), 0) as balance")
+            .bind(discord_id)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("balance");
+        if balance != sweep.map(|sweep| sweep.amount).unwrap_or(0) {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO archived_users
+            (discord_id, username, public_key, encrypted_private_key, nonce, frozen, balance, transaction_count,
+                registered_at, archived_by, archived_at)
+            SELECT discord_id, username, public_key, encrypted_private_key, nonce, frozen, ?,
+                (SELECT COUNT(*) FROM transactions WHERE from_user = users.discord_id OR to_user = users.discord_id),
+                created_at, ?, ?
+            FROM users WHERE discord_id = ?
+            "#
+        )
+        .bind(balance)
+        .bind(archived_by)
+        .bind(Utc::now().timestamp())
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(sweep) = sweep {
+            Self::write_transaction(&mut tx, sweep).await?;
+        }
+
+        for query in ["DELETE FROM balances WHERE discord_id = ?", "DELETE FROM users WHERE discord_id = ?"] {
+            sqlx::query(query).bind(discord_id).execute(&mut *tx).await?;
+        }
+
+        if let Some(entry) = entry {
+            Self::write_admin_audit(&mut tx, entry).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Bring back a user's most recently archived account with its original keys and nonce, starting from a zero balance.
+    // Returns false if they have nothing archived or are already registered.
+    pub async fn restore_archived_user(&self, discord_id: &str, username: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("restore_archived_user");
+        let mut tx = self.pool.begin().await?;
+
+        let Some(row) = sqlx::query("SELECT id FROM archived_users WHERE discord_id = ? ORDER BY id DESC LIMIT 1")
+            .bind(discord_id)
+            .fetch_optional(&mut *tx)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let archive_id: i64 = row.get("id");
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO users (discord_id, username, public_key, encrypted_private_key, nonce, frozen)
+            SELECT discord_id, ?, public_key, encrypted_private_key, nonce, frozen
+            FROM archived_users
+            WHERE id = ? AND NOT EXISTS (SELECT 1 FROM users WHERE discord_id = ?)
+            "#
+        )
+        .bind(username)
+        .bind(archive_id)
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("INSERT INTO balances (discord_id, balance) VALUES (?, 0)")
+            .bind(discord_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM archived_users WHERE id = ?")
+            .bind(archive_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Every private key encrypted under the master key: users, archived users and the given system config keys,
+    // which are bound to the SYSTEM account
+    pub async fn get_encrypted_keys(&self, system_config_keys: &[&str]) -> Result<Vec<EncryptedKey>, DatabaseError> {
+        let _timer = metrics::query_timer("get_encrypted_keys");
+        let mut keys = Vec::new();
+
+        for row in sqlx::query("SELECT discord_id, encrypted_private_key FROM users").fetch_all(&self.pool).await? {
+            let discord_id: String = row.get("discord_id");
+            keys.push(EncryptedKey {
+                location: KeyLocation::User(discord_id.clone()),
+                key_id: discord_id,
+                encrypted: row.get("encrypted_private_key"),
+            });
+        }
+
+        for row in sqlx::query("SELECT id, discord_id, encrypted_private_key FROM archived_users").fetch_all(&self.pool).await? {
+            keys.push(EncryptedKey {
+                location: KeyLocation::Archived(row.get("id")),
+                key_id: row.get("discord_id"),
+                encrypted: row.get("encrypted_private_key"),
+            });
+        }
+
+        for &config_key in system_config_keys {
+            if let Some(encrypted) = self.get_system_config(config_key).await? {
+                keys.push(EncryptedKey {
+                    location: KeyLocation::SystemConfig(config_key.to_string()),
+                    key_id: SYSTEM_ACCOUNT.to_string(),
+                    encrypted,
+                });
+            }
+        }
+
+        Ok(keys)
+    }
+
+    // Rewrite encrypted private keys and record the config value that marks the change, atomically
+    pub async fn replace_encrypted_private_keys(
+        &self,
+        keys: &[EncryptedKey],
+        config_key: &str,
+        config_value: &str,
+    ) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("replace_encrypted_private_keys");
+        let mut tx = self.pool.begin().await?;
+
+        for key in keys {
+            let query = match &key.location {
+                KeyLocation::User(discord_id) => sqlx::query("UPDATE users SET encrypted_private_key = ? WHERE discord_id = ?")
+                    .bind(&key.encrypted)
+                    .bind(discord_id),
+                KeyLocation::Archived(id) => sqlx::query("UPDATE archived_users SET encrypted_private_key = ? WHERE id = ?")
+                    .bind(&key.encrypted)
+                    .bind(id),
+                KeyLocation::SystemConfig(name) => sqlx::query("UPDATE system_config SET value = ? WHERE key = ?")
+                    .bind(&key.encrypted)
+                    .bind(name),
+            };
+            query.execute(&mut *tx).await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO system_config (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+        )
+        .bind(config_key)
+        .bind(config_value)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Transaction management
+    pub async fn add_transaction(&self, transaction: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("add_transaction");
+        let mut tx = self.pool.begin().await?;
+        Self::insert_transaction(&mut tx, transaction).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Append a ledger entry to the end of the hash chain. The insert takes the write lock first,
+    // so the entry it links to can't change underneath it.
+    async fn insert_transaction(conn: &mut SqliteConnection, transaction: &Transaction) -> Result<i64, DatabaseError> {
+        let chain_seq: i64 = sqlx::query(
+            r#"
+            INSERT INTO transactions
+            (id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, chain_seq)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(chain_seq), 0) + 1 FROM transactions))
+            RETURNING chain_seq
+            "#
+        )
+        .bind(&transaction.id)
+        .bind(&transaction.from_user)
+        .bind(&transaction.to_user)
+        .bind(transaction.amount)
+        .bind(&transaction.transaction_type)
+        .bind(&transaction.message)
+        .bind(transaction.nonce)
+        .bind(&transaction.signature)
+        .bind(transaction.timestamp_unix)
+        .fetch_one(&mut *conn)
+        .await?
+        .get("chain_seq");
+
+        let previous = sqlx::query(&format!("SELECT {} FROM transactions WHERE chain_seq = ?", Self::CHAIN_COLUMNS))
+            .bind(chain_seq - 1)
+            .fetch_optional(&mut *conn)
+            .await?;
+        let prev_hash = match previous {
+            Some(row) => Self::transaction_from_row(&row).chain_hash(&row.get::<String, _>("prev_hash")),
+            None => GENESIS_HASH.to_string(),
+        };
+
+        sqlx::query("UPDATE transactions SET prev_hash = ? WHERE chain_seq = ?")
+            .bind(prev_hash)
+            .bind(chain_seq)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(chain_seq)
+    }
+
+    // Link entries written before the hash chain existed onto the end of it, in the order they were inserted
+    async fn link_unchained_transactions(&self) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+
+        let unchained = sqlx::query(&format!(
+            "SELECT rowid, {} FROM transactions WHERE chain_seq IS NULL ORDER BY rowid ASC",
+            Self::CHAIN_COLUMNS
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        if unchained.is_empty() {
+            return Ok(());
+        }
+
+        let head = sqlx::query(&format!(
+            "SELECT {} FROM transactions WHERE chain_seq IS NOT NULL ORDER BY chain_seq DESC LIMIT 1",
+            Self::CHAIN_COLUMNS
+        ))
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (mut chain_seq, mut prev_hash): (i64, String) = match head {
+            Some(row) => (row.get("chain_seq"), Self::transaction_from_row(&row).chain_hash(&row.get::<String, _>("prev_hash"))),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+
+        for row in &unchained {
+            chain_seq += 1;
+            sqlx::query("UPDATE transactions SET chain_seq = ?, prev_hash = ? WHERE rowid = ?")
+                .bind(chain_seq)
+                .bind(&prev_hash)
+                .bind(row.get::<i64, _>("rowid"))
+                .execute(&mut *tx)
+                .await?;
+            prev_hash = Self::transaction_from_row(row).chain_hash(&prev_hash);
+        }
+
+        tx.commit().await?;
+        info!("Linked {} existing transactions into the hash chain", unchained.len());
+        Ok(())
+    }
+
+    const CHAIN_COLUMNS: &'static str =
+        "id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at, chain_seq, prev_hash";
+
+    fn transaction_from_row(row: &sqlx::sqlite::SqliteRow) -> Transaction {
+        Transaction {
+            id: row.get("id"),
+            from_user: row.get("from_user"),
+            to_user: row.get("to_user"),
+            amount: row.get("amount"),
+            transaction_type: row.get("transaction_type"),
+            message: row.get("message"),
+            nonce: row.get("nonce"),
+            signature: row.get("signature"),
+            timestamp_unix: row.get("timestamp_unix"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    // Every ledger entry in chain order with the prev_hash stored alongside it
+    pub async fn get_hash_chain(&self) -> Result<Vec<(Transaction, String)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_hash_chain");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transactions ORDER BY chain_seq ASC",
+            Self::CHAIN_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (Self::transaction_from_row(row), row.get::<Option<String>, _>("prev_hash").unwrap_or_default()))
+            .collect())
+    }
+
+    /// Ledger entries matching `filter` in chain order, streamed so exports and audits of a large
+    /// ledger never hold it all in memory
+    pub fn stream_transactions<'a>(&'a self, filter: &'a TransactionFilter) -> BoxStream<'a, Result<Transaction, DatabaseError>> {
+        // TRANSACTION_FILTER spelled out: the stream borrows the SQL, so it has to be a literal
+        sqlx::query(
+            r#"
+            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
+            FROM transactions
+            WHERE (?1 IS NULL OR from_user = ?1 OR to_user = ?1)
+              AND (?2 IS NULL OR from_user = ?2 OR to_user = ?2)
+              AND (?3 IS NULL OR transaction_type = ?3)
+              AND (?4 IS NULL OR timestamp_unix >= ?4)
+              AND (?5 IS NULL OR timestamp_unix < ?5)
+            ORDER BY chain_seq ASC
+            "#
+        )
+        .bind(filter.user.as_deref())
+        .bind(filter.counterparty.as_deref())
+        .bind(filter.transaction_type.as_deref())
+        .bind(filter.from)
+        .bind(filter.to)
+        .fetch(&self.pool)
+        .map(|row| row.map(|row| Self::transaction_from_row(&row)).map_err(DatabaseError::from))
+        .boxed()
+    }
+
+    // Record a transaction and move its amount between the two balances in one database transaction
+    pub async fn apply_transaction(&self, transaction: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("apply_transaction");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, transaction).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Record several ledger entries atomically: either all of them apply or none do
+    pub async fn apply_transactions(&self, transactions: &[Transaction]) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("apply_transactions");
+        let mut tx = self.begin_ledger().await?;
+        for transaction in transactions {
+            Self::write_transaction(&mut tx, transaction).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Insert a ledger entry and adjust both balances inside a ledger transaction
+    async fn write_transaction(tx: &mut LedgerTx, transaction: &Transaction) -> Result<(), DatabaseError> {
+        let seq = Self::insert_transaction(tx, transaction).await?;
+
+        for (discord_id, delta) in [
+            (&transaction.from_user, -transaction.amount),
+            (&transaction.to_user, transaction.amount),
+        ] {
+            if discord_id == SYSTEM_ACCOUNT {
+                continue;
+            }
+
+            let balance: i64 = if delta < 0 {
+                // Debits only apply if they leave the account at zero or above
+                let debited = sqlx::query(
+                    r#"
+                    UPDATE balances SET balance = balance + ?1, last_updated = CURRENT_TIMESTAMP
+                    WHERE discord_id = ?2 AND balance + ?1 >= 0
+                    RETURNING balance
+                    "#
+                )
+                .bind(delta)
+                .bind(discord_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+                match debited {
+                    Some(row) => row.get("balance"),
+                    None => {
+                        let balance = sqlx::query("SELECT balance FROM balances WHERE discord_id = ?")
+                            .bind(discord_id)
+                            .fetch_optional(&mut **tx)
+                            .await?
+                            .map(|row| row.get("balance"))
+                            .unwrap_or(0);
+                        return Err(DatabaseError::insufficient_funds(discord_id, balance, -delta));
+                    }
+                }
+            } else {
+                sqlx::query(
+                    r#"
+                    INSERT INTO balances (discord_id, balance) 
+                    VALUES (?, ?)
+                    ON CONFLICT(discord_id) 
+                    DO UPDATE SET balance = balance + excluded.balance, last_updated = CURRENT_TIMESTAMP
+                    RETURNING balance
+                    "#
+                )
+                .bind(discord_id)
+                .bind(delta)
+                .fetch_one(&mut **tx)
+                .await?
+                .get("balance")
+            };
+            tx.written.push((discord_id.clone(), balance, seq));
+        }
+
+        Ok(())
+    }
+
+    /// One page of ledger entries matching `filter`, newest first
+    pub async fn get_transactions(&self, filter: &TransactionFilter, limit: u32, offset: u32) -> Result<Vec<Transaction>, DatabaseError> {
+        let _timer = metrics::query_timer("get_transactions");
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
+            FROM transactions
+            WHERE {}
+            ORDER BY timestamp_unix DESC, chain_seq DESC
+            LIMIT ?6 OFFSET ?7
+            "#,
+            TRANSACTION_FILTER
+        ))
+        .bind(filter.user.as_deref())
+        .bind(filter.counterparty.as_deref())
+        .bind(filter.transaction_type.as_deref())
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::transaction_from_row).collect())
+    }
+
+    pub async fn count_transactions(&self, filter: &TransactionFilter) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_transactions");
+        let row = sqlx::query(&format!("SELECT COUNT(*) AS count FROM transactions WHERE {}", TRANSACTION_FILTER))
+            .bind(filter.user.as_deref())
+            .bind(filter.counterparty.as_deref())
+            .bind(filter.transaction_type.as_deref())
+            .bind(filter.from)
+            .bind(filter.to)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    pub async fn get_transaction(&self, id: &str) -> Result<Option<Transaction>, DatabaseError> {
+        let _timer = metrics::query_timer("get_transaction");
+        let row = sqlx::query(
+            r#"
+            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
+            FROM transactions
+            WHERE id = ?
+            "#
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::transaction_from_row))
+    }
+
+    // Registered users this user has transferred with, most frequent and most recent first
+    pub async fn get_recent_counterparties(&self, discord_id: &str, limit: u32) -> Result<Vec<(String, String)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_recent_counterparties");
+        let rows = sqlx::query(
+            r#"
+            SELECT u.discord_id, u.username, COUNT(*) as times, MAX(c.timestamp_unix) as last_seen
+            FROM (
+                SELECT to_user as counterparty, timestamp_unix FROM transactions WHERE from_user = ? AND transaction_type = 'transfer'
+                UNION ALL
+                SELECT from_user as counterparty, timestamp_unix FROM transactions WHERE to_user = ? AND transaction_type = 'transfer'
+            ) c
+            JOIN users u ON u.discord_id = c.counterparty
+            GROUP BY u.discord_id, u.username
+            ORDER BY times DESC, last_seen DESC
+            LIMIT ?
+            "#
+        )
+        .bind(discord_id)
+        .bind(discord_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("username"))).collect())
+    }
+
+    // Every ledger entry recorded at or after `since`, oldest first
+    pub async fn get_transactions_since(&self, since: i64) -> Result<Vec<Transaction>, DatabaseError> {
+        let _timer = metrics::query_timer("get_transactions_since");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
+            FROM transactions
+            WHERE timestamp_unix >= ?
+            ORDER BY timestamp_unix ASC
+            "#
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::transaction_from_row).collect())
+    }
+
+    pub async fn get_users_registered_since(&self, since: i64) -> Result<Vec<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_users_registered_since");
+        let rows = sqlx::query("SELECT discord_id FROM users WHERE created_at >= datetime(?, 'unixepoch')")
+            .bind(since)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("discord_id")).collect())
+    }
+
+    // A random sample of user-signed transactions for background verification
+    pub async fn sample_signed_transactions(&self, limit: u32) -> Result<Vec<Transaction>, DatabaseError> {
+        let _timer = metrics::query_timer("sample_signed_transactions");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
+            FROM transactions
+            WHERE signature != '' AND signature != 'system'
+            ORDER BY RANDOM()
+            LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::transaction_from_row).collect())
+    }
+
+    // (sender, nonce) for every user-signed transaction carrying a nonce, in nonce order per sender
+    pub async fn get_signed_nonces(&self) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_signed_nonces");
+        let rows = sqlx::query(
+            r#"
+            SELECT from_user, nonce
+            FROM transactions
+            WHERE signature != '' AND signature != 'system' AND nonce > 0
+            ORDER BY from_user ASC, nonce ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("from_user"), row.get("nonce"))).collect())
+    }
+
+    // Balance management
+    pub async fn get_balance(&self, discord_id: &str) -> Result<i64, DatabaseError> {
+        if let Some(balance) = self.balances.get(discord_id) {
+            return Ok(balance);
+        }
+
+        let _timer = metrics::query_timer("get_balance");
+        // The ledger head is read in the same statement, so both come from one snapshot
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE((SELECT balance FROM balances WHERE discord_id = ?1), 0) as balance,
+                (SELECT COALESCE(MAX(chain_seq), 0) FROM transactions) as seq
+            "#
+        )
+        .bind(discord_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let balance: i64 = row.get("balance");
+        self.balances.fill(discord_id, balance, row.get("seq"));
+        Ok(balance)
+    }
+
+    pub async fn update_balance(&self, discord_id: &str, new_balance: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("update_balance");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO balances (discord_id, balance) 
+            VALUES (?, ?)
+            ON CONFLICT(discord_id) 
+            DO UPDATE SET balance = ?, last_updated = CURRENT_TIMESTAMP
+            "#
+        )
+        .bind(discord_id)
+        .bind(new_balance)
+        .bind(new_balance)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            if !is_overdraft_trigger(&e) {
+                return Err(e.into());
+            }
+            let balance = self.get_balance(discord_id).await?;
+            return Err(DatabaseError::insufficient_funds(discord_id, balance, balance.saturating_sub(new_balance)));
+        }
+
+        // Set outside the ledger, so there's no position to write through at
+        self.balances.invalidate(discord_id, self.get_latest_chain_seq().await?);
+        Ok(())
+    }
+
+    pub async fn get_all_balances(&self) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_all_balances");
+        let rows = sqlx::query("SELECT discord_id, balance FROM balances")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("balance"))).collect())
+    }
+
+    // Utility functions
+    pub async fn calculate_balance_from_transactions(&self, discord_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("calculate_balance_from_transactions");
+        let row = sqlx::query(
+            r#"
+            SELECT 
+                COALESCE(SUM(CASE WHEN to_user = ? THEN amount ELSE 0 END), 0) -
+                COALESCE(SUM(CASE WHEN from_user = ? THEN amount ELSE 0 END), 0) as balance
+            FROM transactions
+            WHERE from_user = ? OR to_user = ?
+            "#
+        )
+        .bind(discord_id)
+        .bind(discord_id)
+        .bind(discord_id)
+        .bind(discord_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("balance"))
+    }
+
+    pub async fn verify_and_update_balances(&self) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("verify_and_update_balances");
+        info!("Verifying and updating all balances from transaction ledger");
+        
+        let rows = sqlx::query("SELECT discord_id FROM users")
+            .fetch_all(&self.pool)
+            .await?;
+
+        for row in rows {
+            let discord_id: String = row.get("discord_id");
+            let calculated_balance = self.calculate_balance_from_transactions(&discord_id).await?;
+            self.update_balance(&discord_id, calculated_balance).await?;
+        }
+
+        info!("Balance verification complete");
+        Ok(())
+    }
+
+    // Users with their balances for the leaderboard, richest first, and whether they have a defaulted loan.
+    // `limit: None` returns everyone after `offset`.
+    pub async fn get_all_users_with_balances(&self, limit: Option<u32>, offset: u32) -> Result<Vec<(String, i64, bool)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_all_users_with_balances");
+        let rows = sqlx::query(
+            r#"
+            SELECT u.username, COALESCE(b.balance, 0) as balance,
+                EXISTS(SELECT 1 FROM loans l WHERE l.borrower = u.discord_id AND l.status = 'defaulted') as defaulted
+            FROM users u
+            LEFT JOIN balances b ON u.discord_id = b.discord_id
+            LEFT JOIN user_preferences p ON u.discord_id = p.discord_id
+            WHERE COALESCE(p.hide_from_leaderboard, 0) = 0
+            ORDER BY COALESCE(b.balance, 0) DESC
+            LIMIT ? OFFSET ?
+            "#
+        )
+        // SQLite treats a negative LIMIT as no limit
+        .bind(limit.map(i64::from).unwrap_or(-1))
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut users_with_balances = Vec::new();
+        for row in rows {
+            let username: String = row.get("username");
+            let balance: i64 = row.get("balance");
+            let defaulted: bool = row.get("defaulted");
+            users_with_balances.push((username, balance, defaulted));
+        }
+
+        Ok(users_with_balances)
+    }
+
+    // Leaderboard position of a registered user, ties sharing the better rank
+    pub async fn get_rank(&self, discord_id: &str) -> Result<Option<RankInfo>, DatabaseError> {
+        let _timer = metrics::query_timer("get_rank");
+        let row = sqlx::query(
+            r#"
+            WITH ranked AS (
+                SELECT u.discord_id, COALESCE(b.balance, 0) as balance
+                FROM users u
+                LEFT JOIN balances b ON u.discord_id = b.discord_id
+            ),
+            me AS (SELECT balance FROM ranked WHERE discord_id = ?)
+            SELECT
+                me.balance as balance,
+                (SELECT COUNT(*) FROM ranked WHERE ranked.balance > me.balance) + 1 as rank,
+                (SELECT COUNT(*) FROM ranked) as total_users,
+                (SELECT MIN(ranked.balance) FROM ranked WHERE ranked.balance > me.balance) as next_balance
+            FROM me
+            "#
+        )
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| RankInfo {
+            rank: row.get("rank"),
+            total_users: row.get("total_users"),
+            balance: row.get("balance"),
+            next_balance: row.get("next_balance"),
+        }))
+    }
+
+    // None if the user isn't registered
+    pub async fn get_profile_stats(&self, discord_id: &str) -> Result<Option<ProfileStats>, DatabaseError> {
+        let _timer = metrics::query_timer("get_profile_stats");
+        let row = sqlx::query(
+            r#"
+            WITH own AS (
+                SELECT from_user, to_user, amount, transaction_type FROM transactions
+                WHERE (from_user = ?1 OR to_user = ?1) AND transaction_type NOT IN ('vault_deposit', 'vault_withdraw')
+            )
+            SELECT
+                u.created_at,
+                (SELECT COALESCE(SUM(amount), 0) FROM own WHERE from_user = ?1) as sent,
+                (SELECT COALESCE(SUM(amount), 0) FROM own WHERE to_user = ?1) as received,
+                (SELECT COUNT(*) FROM own) as transactions,
+                (SELECT COUNT(*) FROM auction_history WHERE winner_id = ?1 AND outcome = 'sold') as auctions_won,
+                (SELECT COUNT(*) FROM own WHERE to_user = ?1 AND transaction_type = 'lottery_win') as lottery_wins,
+                p.title
+            FROM users u
+            LEFT JOIN user_preferences p ON u.discord_id = p.discord_id
+            WHERE u.discord_id = ?1
+            "#
+        )
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ProfileStats {
+            registered_at: row.get("created_at"),
+            sent: row.get("sent"),
+            received: row.get("received"),
+            transactions: row.get("transactions"),
+            auctions_won: row.get("auctions_won"),
+            lottery_wins: row.get("lottery_wins"),
+            title: row.get("title"),
+        }))
+    }
+
+    pub async fn count_users(&self) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_users");
+        let row = sqlx::query("SELECT COUNT(*) as count FROM users")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    // Users shown on the leaderboard, i.e. everyone who hasn't opted out
+    pub async fn count_leaderboard_users(&self) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_leaderboard_users");
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count
+            FROM users u
+            LEFT JOIN user_preferences p ON u.discord_id = p.discord_id
+            WHERE COALESCE(p.hide_from_leaderboard, 0) = 0
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("count"))
+    }
+
+    // Coins held by users, minted out of SYSTEM and burned back into it
+    pub async fn get_economy_totals(&self) -> Result<EconomyTotals, DatabaseError> {
+        let _timer = metrics::query_timer("get_economy_totals");
+        let row = sqlx::query(
+            r#"
+            SELECT
+                (SELECT COALESCE(SUM(b.balance), 0) FROM balances b JOIN users u ON u.discord_id = b.discord_id)
+                    + (SELECT COALESCE(SUM(balance), 0) FROM vault_balances) as circulating,
+                (SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE from_user = ?) as minted,
+                (SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE to_user = ?) as burned
+            "#
+        )
+        .bind(SYSTEM_ACCOUNT)
+        .bind(SYSTEM_ACCOUNT)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(EconomyTotals {
+            circulating: row.get("circulating"),
+            minted: row.get("minted"),
+            burned: row.get("burned"),
+        })
+    }
+
+    // Number and total value of user-to-user transfers since a point in time
+    pub async fn get_transfer_volume_since(&self, since_unix: i64) -> Result<(i64, i64), DatabaseError> {
+        let _timer = metrics::query_timer("get_transfer_volume_since");
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count, COALESCE(SUM(amount), 0) as volume
+            FROM transactions
+            WHERE transaction_type = 'transfer' AND timestamp_unix >= ?
+            "#
+        )
+        .bind(since_unix)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((row.get("count"), row.get("volume")))
+    }
+
+    // Ledger activity per week (see `week_start`) for weeks starting at or after `since_unix`, oldest first
+    pub async fn get_weekly_stats(&self, since_unix: i64) -> Result<Vec<WeeklyStats>, DatabaseError> {
+        let _timer = metrics::query_timer("get_weekly_stats");
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                timestamp_unix - (timestamp_unix - ?2) % ?3 as week_start,
+                COUNT(*) as transactions,
+                COALESCE(SUM(CASE WHEN from_user != ?1 AND to_user != ?1 THEN amount END), 0) as volume,
+                COALESCE(SUM(CASE WHEN from_user = ?1 THEN amount END), 0) as minted,
+                COALESCE(SUM(CASE WHEN to_user = ?1 THEN amount END), 0) as burned
+            FROM transactions
+            WHERE timestamp_unix >= ?4
+            GROUP BY week_start
+            ORDER BY week_start
+            "#
+        )
+        .bind(SYSTEM_ACCOUNT)
+        .bind(FIRST_MONDAY_UNIX)
+        .bind(WEEK_SECONDS)
+        .bind(week_start(since_unix))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| WeeklyStats {
+                week_start: row.get("week_start"),
+                transactions: row.get("transactions"),
+                volume: row.get("volume"),
+                minted: row.get("minted"),
+                burned: row.get("burned"),
+            })
+            .collect())
+    }
+
+    // Registered users who gained the most over a time range, net of what they paid out.
+    // Users hidden from the leaderboard are left out.
+    pub async fn get_top_earners(&self, from_unix: i64, to_unix: i64, limit: i64) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_top_earners");
+        let rows = sqlx::query(
+            r#"
+            SELECT u.discord_id, SUM(f.delta) as net
+            FROM (
+                SELECT to_user as discord_id, amount as delta FROM transactions
+                WHERE timestamp_unix >= ?1 AND timestamp_unix < ?2
+                UNION ALL
+                SELECT from_user, -amount FROM transactions
+                WHERE timestamp_unix >= ?1 AND timestamp_unix < ?2
+            ) f
+            JOIN users u ON u.discord_id = f.discord_id
+            LEFT JOIN user_preferences p ON u.discord_id = p.discord_id
+            WHERE COALESCE(p.hide_from_leaderboard, 0) = 0
+            GROUP BY u.discord_id
+            HAVING net > 0
+            ORDER BY net DESC
+            LIMIT ?3
+            "#
+        )
+        .bind(from_unix)
+        .bind(to_unix)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("net"))).collect())
+    }
+
+    // Registered users who paid out the most over a time range, leaving out users hidden from the leaderboard
+    pub async fn get_top_spenders(&self, from_unix: i64, to_unix: i64, limit: i64) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_top_spenders");
+        let rows = sqlx::query(
+            r#"
+            SELECT u.discord_id, SUM(t.amount) as spent
+            FROM transactions t
+            JOIN users u ON u.discord_id = t.from_user
+            LEFT JOIN user_preferences p ON u.discord_id = p.discord_id
+            WHERE t.timestamp_unix >= ?1 AND t.timestamp_unix < ?2
+                AND COALESCE(p.hide_from_leaderboard, 0) = 0
+            GROUP BY u.discord_id
+            ORDER BY spent DESC
+            LIMIT ?3
+            "#
+        )
+        .bind(from_unix)
+        .bind(to_unix)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("spent"))).collect())
+    }
+
+    // Auctions in a guild that ended within a time range
+    pub async fn get_auction_summary(&self, guild_id: &str, from_unix: i64, to_unix: i64) -> Result<AuctionSummary, DatabaseError> {
+        let _timer = metrics::query_timer("get_auction_summary");
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as held,
+                COALESCE(SUM(outcome = 'sold'), 0) as sold,
+                COALESCE(SUM(CASE WHEN outcome = 'sold' THEN amount END), 0) as raised
+            FROM auction_history
+            WHERE guild_id = ?1 AND ended_at >= ?2 AND ended_at < ?3
+            "#
+        )
+        .bind(guild_id)
+        .bind(from_unix)
+        .bind(to_unix)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(AuctionSummary { held: row.get("held"), sold: row.get("sold"), raised: row.get("raised") })
+    }
+
+    // Snapshot every registered user's balance and net worth and the circulating supply for `day`.
+    // Returns false if that day was already recorded.
+    pub async fn record_daily_snapshots(&self, day: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("record_daily_snapshots");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO supply_snapshots (day, circulating, users)
+            SELECT ?,
+                (SELECT COALESCE(SUM(b.balance), 0) FROM balances b JOIN users u ON u.discord_id = b.discord_id)
+                    + (SELECT COALESCE(SUM(balance), 0) FROM vault_balances),
+                (SELECT COUNT(*) FROM users)
+            ON CONFLICT(day) DO NOTHING
+            "#
+        )
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO balance_snapshots (discord_id, day, balance, net_worth)
+            SELECT u.discord_id, ?1, COALESCE(b.balance, 0),
+                COALESCE(b.balance, 0)
+                    + (SELECT COALESCE(SUM(v.balance), 0) FROM vault_balances v WHERE v.discord_id = u.discord_id)
+                    + (SELECT COALESCE(SUM(h.shares * s.price), 0) FROM stock_holdings h JOIN stocks s ON s.id = h.stock_id WHERE h.discord_id = u.discord_id)
+            FROM users u
+            LEFT JOIN balances b ON u.discord_id = b.discord_id
+            -- Without a WHERE, SQLite reads the upsert's ON CONFLICT as part of the join
+            WHERE 1
+            ON CONFLICT(discord_id, day) DO NOTHING
+            "#
+        )
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // A user's daily snapshots since `since_day`, oldest first
+    pub async fn get_balance_history(&self, discord_id: &str, since_day: i64) -> Result<Vec<BalanceSnapshot>, DatabaseError> {
+        let _timer = metrics::query_timer("get_balance_history");
+        let rows = sqlx::query(
+            "SELECT day, balance, net_worth FROM balance_snapshots WHERE discord_id = ? AND day >= ? ORDER BY day ASC"
+        )
+        .bind(discord_id)
+        .bind(since_day)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| BalanceSnapshot {
+                day: row.get("day"),
+                balance: row.get("balance"),
+                net_worth: row.get("net_worth"),
+            })
+            .collect())
+    }
+
+    // Circulating supply as (day, circulating) since `since_day`, oldest first
+    pub async fn get_supply_history(&self, since_day: i64) -> Result<Vec<(i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_supply_history");
+        let rows = sqlx::query("SELECT day, circulating FROM supply_snapshots WHERE day >= ? ORDER BY day ASC")
+            .bind(since_day)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("day"), row.get("circulating"))).collect())
+    }
+
+    // Balances of every registered user, including those who never received coins
+    pub async fn get_user_balances(&self) -> Result<Vec<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("get_user_balances");
+        let rows = sqlx::query(
+            r#"
+            SELECT COALESCE(b.balance, 0) as balance
+            FROM users u
+            LEFT JOIN balances b ON u.discord_id = b.discord_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| row.get("balance")).collect())
+    }
+
+    // System config
+    pub async fn get_system_config(&self, key: &str) -> Result<Option<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_system_config");
+        let row = sqlx::query("SELECT value FROM system_config WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| r.get("value")))
+    }
+
+    // Store a system config value unless one is already set. Returns false if it was already set.
+    pub async fn insert_system_config(&self, key: &str, value: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("insert_system_config");
+        let result = sqlx::query("INSERT OR IGNORE INTO system_config (key, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Guild settings
+    pub async fn get_guild_setting(&self, guild_id: &str, key: &str) -> Result<Option<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_guild_setting");
+        let row = sqlx::query("SELECT value FROM guild_settings WHERE guild_id = ? AND key = ?")
+            .bind(guild_id)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| r.get("value")))
+    }
+
+    pub async fn set_guild_setting(&self, guild_id: &str, key: &str, value: &str) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_guild_setting");
+        sqlx::query(
+            r#"
+            INSERT INTO guild_settings (guild_id, key, value)
+            VALUES (?, ?, ?)
+            ON CONFLICT(guild_id, key)
+            DO UPDATE SET value = excluded.value
+            "#
+        )
+        .bind(guild_id)
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Every guild that has overridden `key`, with its value
+    pub async fn get_guild_settings_for_key(&self, key: &str) -> Result<Vec<(String, String)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_guild_settings_for_key");
+        let rows = sqlx::query("SELECT guild_id, value FROM guild_settings WHERE key = ?")
+            .bind(key)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("guild_id"), row.get("value"))).collect())
+    }
+
+    pub async fn reset_guild_setting(&self, guild_id: &str, key: &str) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("reset_guild_setting");
+        sqlx::query("DELETE FROM guild_settings WHERE guild_id = ? AND key = ?")
+            .bind(guild_id)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Faucet claims
+    pub async fn record_faucet_claim(&self, discord_id: &str, guild_id: &str, amount: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("record_faucet_claim");
+        sqlx::query("INSERT INTO faucet_claims (discord_id, guild_id, amount, claimed_at) VALUES (?, ?, ?, ?)")
+            .bind(discord_id)
+            .bind(guild_id)
+            .bind(amount)
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_last_faucet_claim(&self, discord_id: &str) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("get_last_faucet_claim");
+        let row = sqlx::query("SELECT MAX(claimed_at) as claimed_at FROM faucet_claims WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("claimed_at"))
+    }
+
+    pub async fn count_guild_faucet_claims_since(&self, guild_id: &str, since_unix: i64) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_guild_faucet_claims_since");
+        let row = sqlx::query("SELECT COUNT(*) as count FROM faucet_claims WHERE guild_id = ? AND claimed_at >= ?")
+            .bind(guild_id)
+            .bind(since_unix)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    // Daily claims
+    pub async fn get_claim(&self, discord_id: &str) -> Result<Option<(i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_claim");
+        let row = sqlx::query("SELECT last_claim_unix, streak FROM claims WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| (r.get("last_claim_unix"), r.get("streak"))))
+    }
+
+    pub async fn update_claim(&self, discord_id: &str, last_claim_unix: i64, streak: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("update_claim");
+        sqlx::query(
+            r#"
+            INSERT INTO claims (discord_id, last_claim_unix, streak)
+            VALUES (?, ?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET last_claim_unix = excluded.last_claim_unix, streak = excluded.streak
+            "#
+        )
+        .bind(discord_id)
+        .bind(last_claim_unix)
+        .bind(streak)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_last_spin(&self, discord_id: &str) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("get_last_spin");
+        let row = sqlx::query("SELECT last_spin_unix FROM spins WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("last_spin_unix")))
+    }
+
+    // Record a spin at `now`, unless the last one was less than `cooldown_seconds` ago.
+    // Returns false when it's too soon, so two spins racing can't both go through.
+    pub async fn claim_spin(&self, discord_id: &str, now: i64, cooldown_seconds: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("claim_spin");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO spins (discord_id, last_spin_unix)
+            VALUES (?1, ?2)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET last_spin_unix = excluded.last_spin_unix
+            WHERE last_spin_unix <= ?2 - ?3
+            "#
+        )
+        .bind(discord_id)
+        .bind(now)
+        .bind(cooldown_seconds)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Pinned leaderboards
+    pub async fn add_pinned_leaderboard(&self, pin: &PinnedLeaderboard) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("add_pinned_leaderboard");
+        sqlx::query(
+            "INSERT INTO pinned_leaderboards (message_id, guild_id, channel_id, interval_minutes, display_limit, last_refreshed) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&pin.message_id)
+        .bind(&pin.guild_id)
+        .bind(&pin.channel_id)
+        .bind(pin.interval_minutes)
+        .bind(pin.limit)
+        .bind(pin.last_refreshed)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_due_pinned_leaderboards(&self, now_unix: i64) -> Result<Vec<PinnedLeaderboard>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_pinned_leaderboards");
+        let rows = sqlx::query(
+            r#"
+            SELECT message_id, guild_id, channel_id, interval_minutes, display_limit, last_refreshed
+            FROM pinned_leaderboards
+            WHERE last_refreshed + interval_minutes * 60 <= ?
+            "#
+        )
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| PinnedLeaderboard {
+                guild_id: row.get("guild_id"),
+                channel_id: row.get("channel_id"),
+                message_id: row.get("message_id"),
+                interval_minutes: row.get("interval_minutes"),
+                limit: row.get("display_limit"),
+                last_refreshed: row.get("last_refreshed"),
+            })
+            .collect())
+    }
+
+    pub async fn touch_pinned_leaderboard(&self, message_id: &str, refreshed_unix: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("touch_pinned_leaderboard");
+        sqlx::query("UPDATE pinned_leaderboards SET last_refreshed = ? WHERE message_id = ?")
+            .bind(refreshed_unix)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_pinned_leaderboard(&self, message_id: &str) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("remove_pinned_leaderboard");
+        sqlx::query("DELETE FROM pinned_leaderboards WHERE message_id = ?")
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_pinned_leaderboards_in_channel(&self, channel_id: &str) -> Result<u64, DatabaseError> {
+        let _timer = metrics::query_timer("remove_pinned_leaderboards_in_channel");
+        let result = sqlx::query("DELETE FROM pinned_leaderboards WHERE channel_id = ?")
+            .bind(channel_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Treasury spending
+    pub async fn record_treasury_spend(
+        &self,
+        transaction: &Transaction,
+        guild_id: &str,
+        category: &str,
+        spent_by: &str,
+    ) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("record_treasury_spend");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, transaction).await?;
+
+        sqlx::query("INSERT INTO treasury_spends (transaction_id, guild_id, category, spent_by) VALUES (?, ?, ?, ?)")
+            .bind(&transaction.id)
+            .bind(guild_id)
+            .bind(category)
+            .bind(spent_by)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Total treasury spending per category for a month formatted as YYYY-MM
+    pub async fn get_treasury_spending_by_category(&self, guild_id: &str, month: &str) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_treasury_spending_by_category");
+        let rows = sqlx::query(
+            r#"
+            SELECT s.category, COALESCE(SUM(t.amount), 0) as total
+            FROM treasury_spends s
+            JOIN transactions t ON t.id = s.transaction_id
+            WHERE s.guild_id = ? AND strftime('%Y-%m', t.timestamp_unix, 'unixepoch') = ?
+            GROUP BY s.category
+            "#
+        )
+        .bind(guild_id)
+        .bind(month)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("category"), row.get("total"))).collect())
+    }
+
+    // Lottery
+    pub async fn get_open_lottery_round(&self, guild_id: &str) -> Result<Option<LotteryRound>, DatabaseError> {
+        let _timer = metrics::query_timer("get_open_lottery_round");
+        let row = sqlx::query(
+            "SELECT id, guild_id, ticket_price, draw_at FROM lottery_rounds WHERE guild_id = ? AND drawn = 0 ORDER BY id DESC LIMIT 1"
+        )
+        .bind(guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| LotteryRound {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            ticket_price: row.get("ticket_price"),
+            draw_at: row.get("draw_at"),
+        }))
+    }
+
+    pub async fn create_lottery_round(&self, guild_id: &str, ticket_price: i64, draw_at: i64) -> Result<LotteryRound, DatabaseError> {
+        let _timer = metrics::query_timer("create_lottery_round");
+        let result = sqlx::query(
+            "INSERT INTO lottery_rounds (guild_id, ticket_price, started_at, draw_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(guild_id)
+        .bind(ticket_price)
+        .bind(Utc::now().timestamp())
+        .bind(draw_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(LotteryRound {
+            id: result.last_insert_rowid(),
+            guild_id: guild_id.to_string(),
+            ticket_price,
+            draw_at,
+        })
+    }
+
+    // Pay for tickets and add them to the round in one database transaction
+    pub async fn buy_lottery_tickets(&self, round_id: i64, transaction: &Transaction, count: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("buy_lottery_tickets");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, transaction).await?;
+        Self::write_lottery_tickets(&mut tx, round_id, &transaction.from_user, count, transaction.amount).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Give `discord_id` a ticket they didn't pay for; `funding` puts its price into the pot
+    pub async fn grant_lottery_ticket(&self, round_id: i64, discord_id: &str, funding: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("grant_lottery_ticket");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, funding).await?;
+        Self::write_lottery_tickets(&mut tx, round_id, discord_id, 1, funding.amount).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn write_lottery_tickets(conn: &mut SqliteConnection, round_id: i64, discord_id: &str, count: i64, price: i64) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO lottery_tickets (round_id, discord_id, count)
+            VALUES (?, ?, ?)
+            ON CONFLICT(round_id, discord_id)
+            DO UPDATE SET count = count + excluded.count
+            "#
+        )
+        .bind(round_id)
+        .bind(discord_id)
+        .bind(count)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("UPDATE lottery_rounds SET pot = pot + ? WHERE id = ?")
+            .bind(price)
+            .bind(round_id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_lottery_tickets(&self, round_id: i64) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_lottery_tickets");
+        let rows = sqlx::query("SELECT discord_id, count FROM lottery_tickets WHERE round_id = ?")
+            .bind(round_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("count"))).collect())
+    }
+
+    pub async fn get_lottery_pot(&self, round_id: i64) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_lottery_pot");
+        let row = sqlx::query("SELECT pot FROM lottery_rounds WHERE id = ?")
+            .bind(round_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("pot"))
+    }
+
+    pub async fn get_due_lottery_rounds(&self, now_unix: i64) -> Result<Vec<LotteryRound>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_lottery_rounds");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, ticket_price, draw_at FROM lottery_rounds WHERE drawn = 0 AND draw_at <= ?"
+        )
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| LotteryRound {
+                id: row.get("id"),
+                guild_id: row.get("guild_id"),
+                ticket_price: row.get("ticket_price"),
+                draw_at: row.get("draw_at"),
+            })
+            .collect())
+    }
+
+    // Mark a round drawn and pay out the pot (if there is a winner) in one database transaction
+    pub async fn complete_lottery_round(&self, round_id: i64, payout: Option<&Transaction>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("complete_lottery_round");
+        let mut tx = self.begin_ledger().await?;
+
+        if let Some(payout) = payout {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+
+        sqlx::query("UPDATE lottery_rounds SET drawn = 1, winner = ? WHERE id = ?")
+            .bind(payout.map(|p| p.to_user.clone()))
+            .bind(round_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Giveaways
+    fn giveaway_from_row(row: &sqlx::sqlite::SqliteRow) -> Giveaway {
+        Giveaway {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            channel_id: row.get("channel_id"),
+            message_id: row.get("message_id"),
+            host_id: row.get("host_id"),
+            prize: row.get("prize"),
+            entry_cost: row.get("entry_cost"),
+            pot: row.get("pot"),
+            status: row.get("status"),
+            winner_id: row.get("winner_id"),
+            ends_at: row.get("ends_at"),
+        }
+    }
+
+    pub async fn create_giveaway(
+        &self,
+        guild_id: &str,
+        channel_id: &str,
+        host_id: &str,
+        prize: &str,
+        entry_cost: i64,
+        ends_at: i64,
+    ) -> Result<Giveaway, DatabaseError> {
+        let _timer = metrics::query_timer("create_giveaway");
+        let row = sqlx::query(
+            r#"
+            INSERT INTO giveaways (guild_id, channel_id, host_id, prize, entry_cost, created_at, ends_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING id, guild_id, channel_id, message_id, host_id, prize, entry_cost, pot, status, winner_id, ends_at
+            "#
+        )
+        .bind(guild_id)
+        .bind(channel_id)
+        .bind(host_id)
+        .bind(prize)
+        .bind(entry_cost)
+        .bind(Utc::now().timestamp())
+        .bind(ends_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Self::giveaway_from_row(&row))
+    }
+
+    pub async fn set_giveaway_message(&self, giveaway_id: i64, message_id: &str) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_giveaway_message");
+        sqlx::query("UPDATE giveaways SET message_id = ? WHERE id = ?")
+            .bind(message_id)
+            .bind(giveaway_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_giveaway(&self, giveaway_id: i64) -> Result<Option<Giveaway>, DatabaseError> {
+        let _timer = metrics::query_timer("get_giveaway");
+        let row = sqlx::query(
+            "SELECT id, guild_id, channel_id, message_id, host_id, prize, entry_cost, pot, status, winner_id, ends_at FROM giveaways WHERE id = ?"
+        )
+        .bind(giveaway_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::giveaway_from_row))
+    }
+
+    pub async fn get_due_giveaways(&self, now: i64) -> Result<Vec<Giveaway>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_giveaways");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, channel_id, message_id, host_id, prize, entry_cost, pot, status, winner_id, ends_at FROM giveaways WHERE status = 'open' AND ends_at <= ?"
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::giveaway_from_row).collect())
+    }
+
+    pub async fn count_giveaway_entries(&self, giveaway_id: i64) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_giveaway_entries");
+        let row = sqlx::query("SELECT COUNT(*) as count FROM giveaway_entries WHERE giveaway_id = ?")
+            .bind(giveaway_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    pub async fn get_giveaway_entrants(&self, giveaway_id: i64) -> Result<Vec<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_giveaway_entrants");
+        let rows = sqlx::query("SELECT discord_id FROM giveaway_entries WHERE giveaway_id = ? ORDER BY entered_at")
+            .bind(giveaway_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("discord_id")).collect())
+    }
+
+    // Enter a user, paying the entry cost into the pot if there is one.
+    // Returns false if they already entered or the giveaway has ended.
+    pub async fn enter_giveaway(&self, giveaway_id: i64, discord_id: &str, payment: Option<&Transaction>) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("enter_giveaway");
+        let mut tx = self.begin_ledger().await?;
+        let now = Utc::now().timestamp();
+
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO giveaway_entries (giveaway_id, discord_id, entered_at)
+            SELECT id, ?, ? FROM giveaways WHERE id = ? AND status = 'open' AND ends_at > ?
+            "#
+        )
+        .bind(discord_id)
+        .bind(now)
+        .bind(giveaway_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(payment) = payment {
+            Self::write_transaction(&mut tx, payment).await?;
+            sqlx::query("UPDATE giveaways SET pot = pot + ? WHERE id = ?")
+                .bind(payment.amount)
+                .bind(giveaway_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Record the winner of a drawn giveaway, or a reroll's new winner, along with the pot moving to them.
+    // `previous` is the winner the caller replaced; returns false if someone else changed it first.
+    pub async fn settle_giveaway(
+        &self,
+        giveaway_id: i64,
+        previous: Option<&str>,
+        winner: Option<&str>,
+        payout: Option<&Transaction>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("settle_giveaway");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE giveaways SET status = 'drawn', winner_id = ?1
+            WHERE id = ?2 AND (winner_id IS ?3) AND (status = 'open' OR ?3 IS NOT NULL)
+            "#
+        )
+        .bind(winner)
+        .bind(giveaway_id)
+        .bind(previous)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(payout) = payout {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Betting pools
+    fn bet_from_row(row: &sqlx::sqlite::SqliteRow) -> Bet {
+        Bet {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            question: row.get("question"),
+            created_by: row.get("created_by"),
+            closes_at: row.get("closes_at"),
+            status: row.get("status"),
+            winning_option_id: row.get("winning_option_id"),
+        }
+    }
+
+    pub async fn create_bet(
+        &self,
+        guild_id: &str,
+        question: &str,
+        options: &[String],
+        created_by: &str,
+        closes_at: Option<i64>,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("create_bet");
+        let mut tx = self.pool.begin().await?;
+
+        let bet_id: i64 = sqlx::query(
+            "INSERT INTO bets (guild_id, question, created_by, created_at, closes_at) VALUES (?, ?, ?, ?, ?) RETURNING id"
+        )
+        .bind(guild_id)
+        .bind(question)
+        .bind(created_by)
+        .bind(Utc::now().timestamp())
+        .bind(closes_at)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("id");
+
+        for (index, label) in options.iter().enumerate() {
+            sqlx::query("INSERT INTO bet_options (bet_id, position, label) VALUES (?, ?, ?)")
+                .bind(bet_id)
+                .bind(index as i64 + 1)
+                .bind(label)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(bet_id)
+    }
+
+    pub async fn get_bet(&self, bet_id: i64) -> Result<Option<Bet>, DatabaseError> {
+        let _timer = metrics::query_timer("get_bet");
+        let row = sqlx::query(
+            "SELECT id, guild_id, question, created_by, closes_at, status, winning_option_id FROM bets WHERE id = ?"
+        )
+        .bind(bet_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::bet_from_row))
+    }
+
+    // Unresolved bets in a guild, oldest first
+    pub async fn get_open_bets(&self, guild_id: &str) -> Result<Vec<Bet>, DatabaseError> {
+        let _timer = metrics::query_timer("get_open_bets");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, question, created_by, closes_at, status, winning_option_id FROM bets WHERE guild_id = ? AND status = 'open' ORDER BY id"
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::bet_from_row).collect())
+    }
+
+    // A bet's options in order, with how much is staked on each
+    pub async fn get_bet_options(&self, bet_id: i64) -> Result<Vec<BetOption>, DatabaseError> {
+        let _timer = metrics::query_timer("get_bet_options");
+        let rows = sqlx::query(
+            r#"
+            SELECT o.id, o.position, o.label, COALESCE(SUM(e.amount), 0) as pool, COUNT(e.discord_id) as backers
+            FROM bet_options o
+            LEFT JOIN bet_entries e ON e.option_id = o.id
+            WHERE o.bet_id = ?
+            GROUP BY o.id
+            ORDER BY o.position
+            "#
+        )
+        .bind(bet_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| BetOption {
+                id: row.get("id"),
+                position: row.get("position"),
+                label: row.get("label"),
+                pool: row.get("pool"),
+                backers: row.get("backers"),
+            })
+            .collect())
+    }
+
+    pub async fn get_bet_entries(&self, bet_id: i64) -> Result<Vec<BetEntry>, DatabaseError> {
+        let _timer = metrics::query_timer("get_bet_entries");
+        let rows = sqlx::query("SELECT option_id, discord_id, amount FROM bet_entries WHERE bet_id = ?")
+            .bind(bet_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| BetEntry { option_id: row.get("option_id"), discord_id: row.get("discord_id"), amount: row.get("amount") })
+            .collect())
+    }
+
+    // Stake coins on an option, adding to an earlier stake on the same option.
+    // Returns false if betting has closed or the user already backs a different option.
+    pub async fn place_bet(&self, bet_id: i64, option_id: i64, stake: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("place_bet");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO bet_entries (bet_id, option_id, discord_id, amount)
+            SELECT id, ?2, ?3, ?4 FROM bets
+            WHERE id = ?1 AND status = 'open' AND (closes_at IS NULL OR closes_at > ?5)
+            ON CONFLICT(bet_id, discord_id)
+            DO UPDATE SET amount = amount + excluded.amount WHERE option_id = excluded.option_id
+            "#
+        )
+        .bind(bet_id)
+        .bind(option_id)
+        .bind(&stake.from_user)
+        .bind(stake.amount)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, stake).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Close an open bet as resolved (with the winning option) or cancelled (None), paying out from the pool.
+    // Returns false if it was already closed, or if stakes no longer add up to `staked`, the total the payouts were worked out from.
+    pub async fn close_bet(
+        &self,
+        bet_id: i64,
+        winning_option_id: Option<i64>,
+        staked: i64,
+        payouts: &[Transaction],
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("close_bet");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE bets SET status = ?, winning_option_id = ?, resolved_at = ? WHERE id = ? AND status = 'open'"
+        )
+        .bind(if winning_option_id.is_some() { "resolved" } else { "cancelled" })
+        .bind(winning_option_id)
+        .bind(Utc::now().timestamp())
+        .bind(bet_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let total: i64 = sqlx::query("SELECT COALESCE(SUM(amount), 0) as total FROM bet_entries WHERE bet_id = ?")
+            .bind(bet_id)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("total");
+        if total != staked {
+            return Ok(false);
+        }
+
+        for payout in payouts {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Jackpots
+    // A guild that hasn't fed its jackpot yet has an empty one
+    pub async fn get_jackpot(&self, guild_id: &str) -> Result<Jackpot, DatabaseError> {
+        let _timer = metrics::query_timer("get_jackpot");
+        let row = sqlx::query("SELECT pot, last_winner_id, last_payout, last_won_at FROM jackpots WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| Jackpot {
+            pot: row.get("pot"),
+            last_winner_id: row.get("last_winner_id"),
+            last_payout: row.get("last_payout"),
+            last_won_at: row.get("last_won_at"),
+        }).unwrap_or_default())
+    }
+
+    // Move `feed` into the jackpot ledger account and add it to the guild's pot
+    pub async fn feed_jackpot(&self, guild_id: &str, feed: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("feed_jackpot");
+        let mut tx = self.begin_ledger().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO jackpots (guild_id, pot) VALUES (?, ?)
+            ON CONFLICT(guild_id) DO UPDATE SET pot = pot + excluded.pot
+            "#
+        )
+        .bind(guild_id)
+        .bind(feed.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::write_transaction(&mut tx, feed).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Empty the guild's pot into `payout`, whose amount has to be the whole pot.
+    // Returns false if the pot changed since it was read, e.g. someone else won it first.
+    pub async fn claim_jackpot(&self, guild_id: &str, payout: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("claim_jackpot");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE jackpots SET pot = 0, last_winner_id = ?, last_payout = pot, last_won_at = ?
+            WHERE guild_id = ? AND pot = ? AND pot > 0
+            "#
+        )
+        .bind(&payout.to_user)
+        .bind(Utc::now().timestamp())
+        .bind(guild_id)
+        .bind(payout.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, payout).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Trivia
+    fn trivia_question_from_row(row: &sqlx::sqlite::SqliteRow) -> TriviaQuestion {
+        let wrong3: Option<String> = row.get("wrong3");
+        TriviaQuestion {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            category: row.get("category"),
+            question: row.get("question"),
+            answer: row.get("answer"),
+            wrong_answers: [Some(row.get("wrong1")), Some(row.get("wrong2")), wrong3].into_iter().flatten().collect(),
+        }
+    }
+
+    // Categories a guild can play, built-in and custom, with how many questions each has
+    pub async fn get_trivia_categories(&self, guild_id: &str) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_trivia_categories");
+        let rows = sqlx::query(
+            r#"
+            SELECT category, COUNT(*) as questions
+            FROM trivia_questions
+            WHERE guild_id IS NULL OR guild_id = ?
+            GROUP BY category
+            ORDER BY category
+            "#
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("category"), row.get("questions"))).collect())
+    }
+
+    // A random question for the guild, from one category or any
+    pub async fn get_random_trivia_question(&self, guild_id: &str, category: Option<&str>) -> Result<Option<TriviaQuestion>, DatabaseError> {
+        let _timer = metrics::query_timer("get_random_trivia_question");
+        let row = sqlx::query(
+            r#"
+            SELECT id, guild_id, category, question, answer, wrong1, wrong2, wrong3
+            FROM trivia_questions
+            WHERE (guild_id IS NULL OR guild_id = ?1) AND (?2 IS NULL OR category = ?2)
+            ORDER BY RANDOM()
+            LIMIT 1
+            "#
+        )
+        .bind(guild_id)
+        .bind(category)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::trivia_question_from_row))
+    }
+
+    // `wrong_answers` needs two or three entries
+    pub async fn add_trivia_question(
+        &self,
+        guild_id: &str,
+        category: &str,
+        question: &str,
+        answer: &str,
+        wrong_answers: &[String],
+        created_by: &str,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("add_trivia_question");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO trivia_questions (guild_id, category, question, answer, wrong1, wrong2, wrong3, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(category)
+        .bind(question)
+        .bind(answer)
+        .bind(wrong_answers.first())
+        .bind(wrong_answers.get(1))
+        .bind(wrong_answers.get(2))
+        .bind(created_by)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    // Only removes the guild's own questions, never the built-in bank
+    pub async fn remove_trivia_question(&self, guild_id: &str, question_id: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_trivia_question");
+        let result = sqlx::query("DELETE FROM trivia_questions WHERE id = ? AND guild_id = ?")
+            .bind(question_id)
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Properties
+    fn property_from_row(row: &sqlx::sqlite::SqliteRow) -> Property {
+        Property {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            discord_id: row.get("discord_id"),
+            kind: row.get("kind"),
+            tier: row.get("tier"),
+            purchased_at: row.get("purchased_at"),
+            last_collected_at: row.get("last_collected_at"),
+        }
+    }
+
+    pub async fn get_properties(&self, guild_id: &str, discord_id: &str) -> Result<Vec<Property>, DatabaseError> {
+        let _timer = metrics::query_timer("get_properties");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, discord_id, kind, tier, purchased_at, last_collected_at FROM properties WHERE guild_id = ? AND discord_id = ? ORDER BY purchased_at ASC"
+        )
+        .bind(guild_id)
+        .bind(discord_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::property_from_row).collect())
+    }
+
+    // Every property not collected since `collected_before`, for the auto-collector
+    pub async fn get_properties_uncollected_since(&self, collected_before: i64) -> Result<Vec<Property>, DatabaseError> {
+        let _timer = metrics::query_timer("get_properties_uncollected_since");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, discord_id, kind, tier, purchased_at, last_collected_at FROM properties WHERE last_collected_at <= ?"
+        )
+        .bind(collected_before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::property_from_row).collect())
+    }
+
+    /// Record a tier 1 property bought with `payment`, its income accruing from the payment's time.
+    /// Returns `false` without writing anything if the buyer already owns one of that kind.
+    pub async fn buy_property(&self, guild_id: &str, kind: &str, payment: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("buy_property");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO properties (guild_id, discord_id, kind, purchased_at, last_collected_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id, discord_id, kind) DO NOTHING
+            "#
+        )
+        .bind(guild_id)
+        .bind(&payment.from_user)
+        .bind(kind)
+        .bind(payment.timestamp_unix)
+        .bind(payment.timestamp_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, payment).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Pay out a property's income up to `collected_at`. Returns `false` without writing
+    /// anything if it was collected or upgraded since `property` was loaded.
+    pub async fn collect_property(&self, property: &Property, collected_at: i64, income: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("collect_property");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE properties SET last_collected_at = ? WHERE id = ? AND tier = ? AND last_collected_at = ?"
+        )
+        .bind(collected_at)
+        .bind(property.id)
+        .bind(property.tier)
+        .bind(property.last_collected_at)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, income).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Raise a property one tier for `payment`, first paying out what it earned at the old tier.
+    /// Returns `false` without writing anything if it changed since `property` was loaded.
+    pub async fn upgrade_property(&self, property: &Property, payment: &Transaction, income: Option<&Transaction>) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("upgrade_property");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE properties SET tier = tier + 1, last_collected_at = ? WHERE id = ? AND tier = ? AND last_collected_at = ?"
+        )
+        .bind(payment.timestamp_unix)
+        .bind(property.id)
+        .bind(property.tier)
+        .bind(property.last_collected_at)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(income) = income {
+            Self::write_transaction(&mut tx, income).await?;
+        }
+        Self::write_transaction(&mut tx, payment).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Stocks
+    fn stock_from_row(row: &sqlx::sqlite::SqliteRow) -> Stock {
+        Stock {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            symbol: row.get("symbol"),
+            name: row.get("name"),
+            price: row.get("price"),
+            base_price: row.get("base_price"),
+        }
+    }
+
+    pub async fn get_stocks(&self, guild_id: &str) -> Result<Vec<Stock>, DatabaseError> {
+        let _timer = metrics::query_timer("get_stocks");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, symbol, name, price, base_price FROM stocks WHERE guild_id = ? ORDER BY symbol ASC"
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::stock_from_row).collect())
+    }
+
+    // Every guild's stocks, for the price ticker
+    pub async fn get_all_stocks(&self) -> Result<Vec<Stock>, DatabaseError> {
+        let _timer = metrics::query_timer("get_all_stocks");
+        let rows = sqlx::query("SELECT id, guild_id, symbol, name, price, base_price FROM stocks")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(Self::stock_from_row).collect())
+    }
+
+    pub async fn get_stock_by_symbol(&self, guild_id: &str, symbol: &str) -> Result<Option<Stock>, DatabaseError> {
+        let _timer = metrics::query_timer("get_stock_by_symbol");
+        let row = sqlx::query(
+            "SELECT id, guild_id, symbol, name, price, base_price FROM stocks WHERE guild_id = ? AND symbol = ? COLLATE NOCASE"
+        )
+        .bind(guild_id)
+        .bind(symbol)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::stock_from_row))
+    }
+
+    // List (symbol, name, price) in a guild, skipping symbols it already has, each with a first price point
+    pub async fn list_stocks(&self, guild_id: &str, listings: &[(&str, &str, i64)], now: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("list_stocks");
+        let mut tx = self.pool.begin().await?;
+
+        for (symbol, name, price) in listings {
+            let row = sqlx::query(
+                r#"
+                INSERT INTO stocks (guild_id, symbol, name, price, base_price)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(guild_id, symbol) DO NOTHING
+                RETURNING id
+                "#
+            )
+            .bind(guild_id)
+            .bind(symbol)
+            .bind(name)
+            .bind(price)
+            .bind(price)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(row) = row {
+                sqlx::query("INSERT INTO stock_prices (stock_id, recorded_at, price) VALUES (?, ?, ?)")
+                    .bind(row.get::<i64, _>("id"))
+                    .bind(now)
+                    .bind(price)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Move each (stock id, price) to its new price, record it in the history and drop history older than `prune_before`
+    pub async fn record_stock_prices(&self, prices: &[(i64, i64)], now: i64, prune_before: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("record_stock_prices");
+        let mut tx = self.pool.begin().await?;
+
+        for (stock_id, price) in prices {
+            sqlx::query("UPDATE stocks SET price = ? WHERE id = ?")
+                .bind(price)
+                .bind(stock_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query("INSERT INTO stock_prices (stock_id, recorded_at, price) VALUES (?, ?, ?)")
+                .bind(stock_id)
+                .bind(now)
+                .bind(price)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM stock_prices WHERE recorded_at < ?")
+            .bind(prune_before)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // A stock's (recorded_at, price) history since `since`, oldest first
+    pub async fn get_stock_history(&self, stock_id: i64, since: i64) -> Result<Vec<(i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_stock_history");
+        let rows = sqlx::query(
+            "SELECT recorded_at, price FROM stock_prices WHERE stock_id = ? AND recorded_at >= ? ORDER BY recorded_at ASC"
+        )
+        .bind(stock_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("recorded_at"), row.get("price"))).collect())
+    }
+
+    // Whether the stock still trades at `price`, so nobody trades at a quote the ticker has moved on from
+    async fn stock_price_is(conn: &mut SqliteConnection, stock_id: i64, price: i64) -> Result<bool, DatabaseError> {
+        let row = sqlx::query("SELECT 1 FROM stocks WHERE id = ? AND price = ?")
+            .bind(stock_id)
+            .bind(price)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Add `shares` of a stock to the payer's holdings for `payment`.
+    /// Returns `false` without writing anything if the price moved off `stock.price`.
+    pub async fn buy_stock(&self, stock: &Stock, shares: i64, payment: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("buy_stock");
+        let mut tx = self.begin_ledger().await?;
+
+        if !Self::stock_price_is(&mut tx, stock.id, stock.price).await? {
+            return Ok(false);
+        }
+        Self::write_transaction(&mut tx, payment).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO stock_holdings (discord_id, stock_id, shares, cost_basis)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(discord_id, stock_id)
+            DO UPDATE SET shares = shares + excluded.shares, cost_basis = cost_basis + excluded.cost_basis
+            "#
+        )
+        .bind(&payment.from_user)
+        .bind(stock.id)
+        .bind(shares)
+        .bind(payment.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Take `shares` of a stock out of the payee's holdings for `payout`, and its share of the cost basis with them.
+    /// Returns `false` without writing anything if they hold too few or the price moved off `stock.price`.
+    pub async fn sell_stock(&self, stock: &Stock, shares: i64, payout: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("sell_stock");
+        let mut tx = self.begin_ledger().await?;
+
+        if !Self::stock_price_is(&mut tx, stock.id, stock.price).await? {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"
+            UPDATE stock_holdings
+            SET cost_basis = cost_basis - cost_basis * ?1 / shares, shares = shares - ?1
+            WHERE discord_id = ?2 AND stock_id = ?3 AND shares >= ?1
+            "#
+        )
+        .bind(shares)
+        .bind(&payout.to_user)
+        .bind(stock.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM stock_holdings WHERE discord_id = ? AND stock_id = ? AND shares <= 0")
+            .bind(&payout.to_user)
+            .bind(stock.id)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::write_transaction(&mut tx, payout).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn get_portfolio(&self, guild_id: &str, discord_id: &str) -> Result<Vec<StockHolding>, DatabaseError> {
+        let _timer = metrics::query_timer("get_portfolio");
+        let rows = sqlx::query(
+            r#"
+            SELECT h.stock_id, s.symbol, s.name, h.shares, h.cost_basis, s.price
+            FROM stock_holdings h
+            JOIN stocks s ON s.id = h.stock_id
+            WHERE s.guild_id = ? AND h.discord_id = ?
+            ORDER BY s.symbol ASC
+            "#
+        )
+        .bind(guild_id)
+        .bind(discord_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| StockHolding {
+                stock_id: row.get("stock_id"),
+                symbol: row.get("symbol"),
+                name: row.get("name"),
+                shares: row.get("shares"),
+                cost_basis: row.get("cost_basis"),
+                price: row.get("price"),
+            })
+            .collect())
+    }
+
+    // What a user's shares are worth at current prices, in one guild or across all of them
+    pub async fn get_portfolio_value(&self, guild_id: Option<&str>, discord_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_portfolio_value");
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(h.shares * s.price), 0) as value
+            FROM stock_holdings h
+            JOIN stocks s ON s.id = h.stock_id
+            WHERE h.discord_id = ?1 AND (?2 IS NULL OR s.guild_id = ?2)
+            "#
+        )
+        .bind(discord_id)
+        .bind(guild_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("value"))
+    }
+
+    // Paper trading
+    pub async fn set_crypto_price(&self, asset: &str, price_cents: i64, updated_at: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_crypto_price");
+        sqlx::query(
+            r#"
+            INSERT INTO crypto_prices (asset, price_cents, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(asset)
+            DO UPDATE SET price_cents = excluded.price_cents, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(asset)
+        .bind(price_cents)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // An asset's latest (price in cents, updated_at), if the price feed has ever run
+    pub async fn get_crypto_price(&self, asset: &str) -> Result<Option<(i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_crypto_price");
+        let row = sqlx::query("SELECT price_cents, updated_at FROM crypto_prices WHERE asset = ?")
+            .bind(asset)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| (row.get("price_cents"), row.get("updated_at"))))
+    }
+
+    fn paper_position_from_row(row: &sqlx::sqlite::SqliteRow) -> PaperPosition {
+        PaperPosition {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            discord_id: row.get("discord_id"),
+            asset: row.get("asset"),
+            direction: row.get("direction"),
+            stake: row.get("stake"),
+            leverage: row.get("leverage"),
+            entry_price_cents: row.get("entry_price_cents"),
+            opened_at: row.get("opened_at"),
+            status: row.get("status"),
+            exit_price_cents: row.get("exit_price_cents"),
+            payout: row.get("payout"),
+            closed_at: row.get("closed_at"),
+        }
+    }
+
+    // Record a position and take its stake together. Returns the position id.
+    pub async fn open_paper_position(
+        &self,
+        guild_id: &str,
+        asset: &str,
+        direction: &str,
+        leverage: i64,
+        entry_price_cents: i64,
+        stake: &Transaction,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("open_paper_position");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, stake).await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO paper_positions (guild_id, discord_id, asset, direction, stake, leverage, entry_price_cents, opened_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(&stake.from_user)
+        .bind(asset)
+        .bind(direction)
+        .bind(stake.amount)
+        .bind(leverage)
+        .bind(entry_price_cents)
+        .bind(stake.timestamp_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_paper_position(&self, position_id: i64) -> Result<Option<PaperPosition>, DatabaseError> {
+        let _timer = metrics::query_timer("get_paper_position");
+        let row = sqlx::query(
+            r#"
+            SELECT id, guild_id, discord_id, asset, direction, stake, leverage, entry_price_cents, opened_at,
+                   status, exit_price_cents, payout, closed_at
+            FROM paper_positions WHERE id = ?
+            "#
+        )
+        .bind(position_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::paper_position_from_row))
+    }
+
+    // A user's open positions in a guild, or every open position for the liquidation check
+    pub async fn get_open_paper_positions(&self, owner: Option<(&str, &str)>) -> Result<Vec<PaperPosition>, DatabaseError> {
+        let _timer = metrics::query_timer("get_open_paper_positions");
+        let (guild_id, discord_id) = owner.unzip();
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, discord_id, asset, direction, stake, leverage, entry_price_cents, opened_at,
+                   status, exit_price_cents, payout, closed_at
+            FROM paper_positions
+            WHERE status = 'open' AND (?1 IS NULL OR guild_id = ?1) AND (?2 IS NULL OR discord_id = ?2)
+            ORDER BY opened_at ASC
+            "#
+        )
+        .bind(guild_id)
+        .bind(discord_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::paper_position_from_row).collect())
+    }
+
+    /// Settle an open position as `status` at `exit_price_cents`, paying out `payout` if anything is left.
+    /// Returns `false` without writing anything if it was already settled.
+    pub async fn close_paper_position(
+        &self,
+        position_id: i64,
+        status: &str,
+        exit_price_cents: i64,
+        closed_at: i64,
+        payout: Option<&Transaction>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("close_paper_position");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE paper_positions SET status = ?, exit_price_cents = ?, payout = ?, closed_at = ?
+            WHERE id = ? AND status = 'open'
+            "#
+        )
+        .bind(status)
+        .bind(exit_price_cents)
+        .bind(payout.map(|payout| payout.amount).unwrap_or(0))
+        .bind(closed_at)
+        .bind(position_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(payout) = payout {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Teams
+    fn team_from_row(row: &sqlx::sqlite::SqliteRow) -> Team {
+        Team {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            name: row.get("name"),
+            balance: row.get("balance"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    fn team_member_from_row(row: &sqlx::sqlite::SqliteRow) -> TeamMember {
+        TeamMember {
+            team_id: row.get("team_id"),
+            discord_id: row.get("discord_id"),
+            role: row.get("role"),
+            joined_at: row.get("joined_at"),
+        }
+    }
+
+    /// Create a team led by `leader`. Returns `None` if the name is taken in the guild or the
+    /// leader already belongs to a team there.
+    pub async fn create_team(&self, guild_id: &str, name: &str, leader: &str, created_at: i64) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("create_team");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO teams (guild_id, name, created_at)
+            SELECT ?1, ?2, ?3
+            WHERE NOT EXISTS (SELECT 1 FROM team_members WHERE guild_id = ?1 AND discord_id = ?4 AND role != 'invited')
+            ON CONFLICT(guild_id, name) DO NOTHING
+            "#
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(created_at)
+        .bind(leader)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let team_id = result.last_insert_rowid();
+
+        // Any invites the leader had are moot now
+        sqlx::query("DELETE FROM team_members WHERE guild_id = ? AND discord_id = ?")
+            .bind(guild_id)
+            .bind(leader)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO team_members (team_id, guild_id, discord_id, role, joined_at) VALUES (?, ?, ?, 'leader', ?)")
+            .bind(team_id)
+            .bind(guild_id)
+            .bind(leader)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(team_id))
+    }
+
+    pub async fn get_team(&self, team_id: i64) -> Result<Option<Team>, DatabaseError> {
+        let _timer = metrics::query_timer("get_team");
+        let row = sqlx::query("SELECT id, guild_id, name, balance, created_at FROM teams WHERE id = ?")
+            .bind(team_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::team_from_row))
+    }
+
+    // Names are matched case-insensitively
+    pub async fn get_team_by_name(&self, guild_id: &str, name: &str) -> Result<Option<Team>, DatabaseError> {
+        let _timer = metrics::query_timer("get_team_by_name");
+        let row = sqlx::query("SELECT id, guild_id, name, balance, created_at FROM teams WHERE guild_id = ? AND name = ?")
+            .bind(guild_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::team_from_row))
+    }
+
+    // The team a user belongs to in a guild and their place in it, ignoring invites
+    pub async fn get_user_team(&self, guild_id: &str, discord_id: &str) -> Result<Option<(Team, TeamMember)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_user_team");
+        let row = sqlx::query(
+            r#"
+            SELECT t.id, t.guild_id, t.name, t.balance, t.created_at, m.team_id, m.discord_id, m.role, m.joined_at
+            FROM team_members m
+            JOIN teams t ON t.id = m.team_id
+            WHERE m.guild_id = ? AND m.discord_id = ? AND m.role != 'invited'
+            "#
+        )
+        .bind(guild_id)
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (Self::team_from_row(&row), Self::team_member_from_row(&row))))
+    }
+
+    // Everyone in a team including pending invites, leader first
+    pub async fn get_team_members(&self, team_id: i64) -> Result<Vec<TeamMember>, DatabaseError> {
+        let _timer = metrics::query_timer("get_team_members");
+        let rows = sqlx::query(
+            r#"
+            SELECT team_id, discord_id, role, joined_at FROM team_members
+            WHERE team_id = ?
+            ORDER BY CASE role WHEN 'leader' THEN 0 WHEN 'officer' THEN 1 WHEN 'member' THEN 2 ELSE 3 END, joined_at ASC
+            "#
+        )
+        .bind(team_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::team_member_from_row).collect())
+    }
+
+    /// Invite a user to a team. Returns `false` if they're already in or invited to it,
+    /// or the team has `max_members` members and invites between them.
+    pub async fn invite_team_member(&self, team: &Team, discord_id: &str, max_members: i64, invited_at: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("invite_team_member");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO team_members (team_id, guild_id, discord_id, role, joined_at)
+            SELECT ?1, ?2, ?3, 'invited', ?4
+            WHERE (SELECT COUNT(*) FROM team_members WHERE team_id = ?1) < ?5
+            ON CONFLICT(team_id, discord_id) DO NOTHING
+            "#
+        )
+        .bind(team.id)
+        .bind(&team.guild_id)
+        .bind(discord_id)
+        .bind(invited_at)
+        .bind(max_members)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Accept an invite, dropping any other invites the user had in the guild.
+    /// Returns `false` if they weren't invited or already joined a team.
+    pub async fn join_team(&self, team: &Team, discord_id: &str, joined_at: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("join_team");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE team_members SET role = 'member', joined_at = ?1
+            WHERE team_id = ?2 AND discord_id = ?3 AND role = 'invited'
+              AND NOT EXISTS (SELECT 1 FROM team_members WHERE guild_id = ?4 AND discord_id = ?3 AND role != 'invited')
+            "#
+        )
+        .bind(joined_at)
+        .bind(team.id)
+        .bind(discord_id)
+        .bind(&team.guild_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM team_members WHERE guild_id = ? AND discord_id = ? AND role = 'invited'")
+            .bind(&team.guild_id)
+            .bind(discord_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Change a member's role. Returns `false` if their role is no longer `from_role`.
+    pub async fn set_team_role(&self, team_id: i64, discord_id: &str, from_role: &str, to_role: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("set_team_role");
+        let result = sqlx::query("UPDATE team_members SET role = ? WHERE team_id = ? AND discord_id = ? AND role = ?")
+            .bind(to_role)
+            .bind(team_id)
+            .bind(discord_id)
+            .bind(from_role)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Hand leadership to an officer, making the old leader an officer.
+    /// Returns `false` if either of them changed role in the meantime.
+    pub async fn transfer_team_leadership(&self, team_id: i64, leader: &str, officer: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("transfer_team_leadership");
+        let mut tx = self.pool.begin().await?;
+
+        for (discord_id, from_role, to_role) in [(leader, "leader", "officer"), (officer, "officer", "leader")] {
+            let result = sqlx::query("UPDATE team_members SET role = ? WHERE team_id = ? AND discord_id = ? AND role = ?")
+                .bind(to_role)
+                .bind(team_id)
+                .bind(discord_id)
+                .bind(from_role)
+                .execute(&mut *tx)
+                .await?;
+
+            if result.rows_affected() == 0 {
+                return Ok(false);
+            }
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Remove a member or withdraw an invite. Returns `false` if their role is no longer `role`.
+    pub async fn remove_team_member(&self, team_id: i64, discord_id: &str, role: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_team_member");
+        let result = sqlx::query("DELETE FROM team_members WHERE team_id = ? AND discord_id = ? AND role = ?")
+            .bind(team_id)
+            .bind(discord_id)
+            .bind(role)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a wallet-to-team transfer and credit the team's bank together.
+    /// Returns `false` without writing anything if the team was disbanded.
+    pub async fn team_deposit(&self, team_id: i64, deposit: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("team_deposit");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query("UPDATE teams SET balance = balance + ? WHERE id = ?")
+            .bind(deposit.amount)
+            .bind(team_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, deposit).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Debit a team's bank and record the payment out of it together.
+    /// Returns `false` if the bank holds too little or the team was disbanded.
+    pub async fn team_spend(&self, team_id: i64, payment: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("team_spend");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query("UPDATE teams SET balance = balance - ? WHERE id = ? AND balance >= ?")
+            .bind(payment.amount)
+            .bind(team_id)
+            .bind(payment.amount)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, payment).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Delete a team and its members, paying what's left in the bank out with `payout`.
+    /// Returns `false` without writing anything if the bank no longer holds exactly that much.
+    pub async fn disband_team(&self, team_id: i64, payout: Option<&Transaction>) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("disband_team");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query("DELETE FROM teams WHERE id = ? AND balance = ?")
+            .bind(team_id)
+            .bind(payout.map(|payout| payout.amount).unwrap_or(0))
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM team_members WHERE team_id = ?")
+            .bind(team_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(payout) = payout {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // A guild's teams with their member counts, richest bank first
+    pub async fn get_team_leaderboard(&self, guild_id: &str, limit: i64) -> Result<Vec<(Team, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_team_leaderboard");
+        let rows = sqlx::query(
+            r#"
+            SELECT t.id, t.guild_id, t.name, t.balance, t.created_at,
+                   (SELECT COUNT(*) FROM team_members m WHERE m.team_id = t.id AND m.role != 'invited') as members
+            FROM teams t
+            WHERE t.guild_id = ?
+            ORDER BY t.balance DESC, t.created_at ASC
+            LIMIT ?
+            "#
+        )
+        .bind(guild_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (Self::team_from_row(row), row.get("members"))).collect())
+    }
+
+    // Marriages
+    fn marriage_from_row(row: &sqlx::sqlite::SqliteRow) -> Marriage {
+        Marriage {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            partner_a: row.get("partner_a"),
+            partner_b: row.get("partner_b"),
+            married_at: row.get("married_at"),
+            has_joint_account: row.get("has_joint_account"),
+            joint_balance: row.get("joint_balance"),
+            divorced_at: row.get("divorced_at"),
+        }
+    }
+
+    /// Marry two users. Returns `None` if either of them is already married in the guild.
+    pub async fn create_marriage(
+        &self,
+        guild_id: &str,
+        partner_a: &str,
+        partner_b: &str,
+        has_joint_account: bool,
+        married_at: i64,
+    ) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("create_marriage");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO marriages (guild_id, partner_a, partner_b, married_at, has_joint_account)
+            SELECT ?1, ?2, ?3, ?4, ?5
+            WHERE NOT EXISTS (
+                SELECT 1 FROM marriages
+                WHERE guild_id = ?1 AND divorced_at IS NULL
+                  AND (partner_a IN (?2, ?3) OR partner_b IN (?2, ?3))
+            )
+            "#
+        )
+        .bind(guild_id)
+        .bind(partner_a)
+        .bind(partner_b)
+        .bind(married_at)
+        .bind(has_joint_account)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+    }
+
+    // A user's current marriage in a guild, if they're married
+    pub async fn get_marriage(&self, guild_id: &str, discord_id: &str) -> Result<Option<Marriage>, DatabaseError> {
+        let _timer = metrics::query_timer("get_marriage");
+        let row = sqlx::query(
+            r#"
+            SELECT id, guild_id, partner_a, partner_b, married_at, has_joint_account, joint_balance, divorced_at
+            FROM marriages
+            WHERE guild_id = ?1 AND divorced_at IS NULL AND (partner_a = ?2 OR partner_b = ?2)
+            "#
+        )
+        .bind(guild_id)
+        .bind(discord_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::marriage_from_row))
+    }
+
+    /// Returns `false` if the couple already has a joint account or has divorced
+    pub async fn open_joint_account(&self, marriage_id: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("open_joint_account");
+        let result = sqlx::query(
+            "UPDATE marriages SET has_joint_account = 1 WHERE id = ? AND has_joint_account = 0 AND divorced_at IS NULL"
+        )
+        .bind(marriage_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a wallet-to-joint-account transfer and credit the joint balance together.
+    /// Returns `false` without writing anything if the couple has no joint account or has divorced.
+    pub async fn joint_deposit(&self, marriage_id: i64, deposit: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("joint_deposit");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE marriages SET joint_balance = joint_balance + ? WHERE id = ? AND has_joint_account = 1 AND divorced_at IS NULL"
+        )
+        .bind(deposit.amount)
+        .bind(marriage_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, deposit).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Debit the joint balance and record the transfer out of it together.
+    /// Returns `false` if it holds too little or the couple has divorced.
+    pub async fn joint_withdraw(&self, marriage_id: i64, withdrawal: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("joint_withdraw");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE marriages SET joint_balance = joint_balance - ? WHERE id = ? AND joint_balance >= ? AND divorced_at IS NULL"
+        )
+        .bind(withdrawal.amount)
+        .bind(marriage_id)
+        .bind(withdrawal.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, withdrawal).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// End a marriage, paying the joint balance out with `splits`. Returns `false` without writing
+    /// anything if it already ended or the splits no longer add up to the joint balance.
+    pub async fn divorce(&self, marriage_id: i64, divorced_at: i64, splits: &[Transaction]) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("divorce");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE marriages SET divorced_at = ?, joint_balance = 0 WHERE id = ? AND divorced_at IS NULL AND joint_balance = ?"
+        )
+        .bind(divorced_at)
+        .bind(marriage_id)
+        .bind(splits.iter().map(|split| split.amount).sum::<i64>())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        for split in splits {
+            Self::write_transaction(&mut tx, split).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Charity
+    fn charity_match_from_row(row: &sqlx::sqlite::SqliteRow) -> CharityMatch {
+        CharityMatch {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            percent: row.get("percent"),
+            cap: row.get("cap"),
+            matched: row.get("matched"),
+            starts_at: row.get("starts_at"),
+            ends_at: row.get("ends_at"),
+            created_by: row.get("created_by"),
+        }
+    }
+
+    // A guild's pool as (balance, next_distribution_at), if anyone has donated there
+    pub async fn get_charity_pool(&self, guild_id: &str) -> Result<Option<(i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_charity_pool");
+        let row = sqlx::query("SELECT balance, next_distribution_at FROM charity_pools WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| (row.get("balance"), row.get("next_distribution_at"))))
+    }
+
+    // Matches running at `now` or scheduled after it, soonest first
+    pub async fn get_charity_matches(&self, guild_id: &str, now: i64) -> Result<Vec<CharityMatch>, DatabaseError> {
+        let _timer = metrics::query_timer("get_charity_matches");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, percent, cap, matched, starts_at, ends_at, created_by
+            FROM charity_matches
+            WHERE guild_id = ? AND ends_at > ?
+            ORDER BY starts_at ASC
+            "#
+        )
+        .bind(guild_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::charity_match_from_row).collect())
+    }
+
+    /// Schedule a match. Returns `None` if it overlaps another match in the guild.
+    pub async fn create_charity_match(&self, charity_match: &CharityMatch) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("create_charity_match");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO charity_matches (guild_id, percent, cap, starts_at, ends_at, created_by)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6
+            WHERE NOT EXISTS (
+                SELECT 1 FROM charity_matches WHERE guild_id = ?1 AND starts_at < ?5 AND ends_at > ?4
+            )
+            "#
+        )
+        .bind(&charity_match.guild_id)
+        .bind(charity_match.percent)
+        .bind(charity_match.cap)
+        .bind(charity_match.starts_at)
+        .bind(charity_match.ends_at)
+        .bind(&charity_match.created_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+    }
+
+    /// End a running match now, or call off one that hasn't started. Returns `false` if it was already over.
+    pub async fn end_charity_match(&self, match_id: i64, now: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("end_charity_match");
+        let result = sqlx::query("UPDATE charity_matches SET ends_at = MAX(starts_at, ?1) WHERE id = ?2 AND ends_at > ?1")
+            .bind(now)
+            .bind(match_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a donation into the guild's pool, along with the treasury's match if one is given.
+    /// A pool starting with this donation is first paid out at `first_distribution_at`.
+    /// Returns what the treasury added, which is 0 if the match ended or hit its cap in the meantime.
+    pub async fn donate_to_charity(
+        &self,
+        guild_id: &str,
+        donation: &Transaction,
+        matching: Option<(i64, &Transaction)>,
+        first_distribution_at: i64,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("donate_to_charity");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, donation).await?;
+
+        let mut matched = 0;
+        if let Some((match_id, contribution)) = matching {
+            let result = sqlx::query(
+                r#"
+                UPDATE charity_matches SET matched = matched + ?1
+                WHERE id = ?2 AND starts_at <= ?3 AND ends_at > ?3 AND (cap IS NULL OR matched + ?1 <= cap)
+                "#
+            )
+            .bind(contribution.amount)
+            .bind(match_id)
+            .bind(donation.timestamp_unix)
+            .execute(&mut *tx)
+            .await?;
+
+            if result.rows_affected() > 0 {
+                Self::write_transaction(&mut tx, contribution).await?;
+                matched = contribution.amount;
+            }
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO charity_pools (guild_id, balance, next_distribution_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(guild_id) DO UPDATE SET balance = balance + excluded.balance
+            "#
+        )
+        .bind(guild_id)
+        .bind(donation.amount + matched)
+        .bind(first_distribution_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO charity_donations (guild_id, discord_id, amount, matched, donated_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(guild_id)
+            .bind(&donation.from_user)
+            .bind(donation.amount)
+            .bind(matched)
+            .bind(donation.timestamp_unix)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(matched)
+    }
+
+    // Donors ranked by total given as (discord_id, donated, matched on top)
+    pub async fn get_charity_donors(&self, guild_id: &str, limit: i64) -> Result<Vec<(String, i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_charity_donors");
+        let rows = sqlx::query(
+            r#"
+            SELECT discord_id, SUM(amount) as donated, SUM(matched) as matched
+            FROM charity_donations
+            WHERE guild_id = ?
+            GROUP BY discord_id
+            ORDER BY donated DESC
+            LIMIT ?
+            "#
+        )
+        .bind(guild_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("donated"), row.get("matched"))).collect())
+    }
+
+    // (guild_id, balance, next_distribution_at) of pools due a payout at `now`
+    pub async fn get_due_charity_pools(&self, now: i64) -> Result<Vec<(String, i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_charity_pools");
+        let rows = sqlx::query("SELECT guild_id, balance, next_distribution_at FROM charity_pools WHERE next_distribution_at <= ?")
+            .bind(now)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("guild_id"), row.get("balance"), row.get("next_distribution_at"))).collect())
+    }
+
+    /// Pay out a due pool with `payout`, or just move it to `next_distribution_at` without one. Returns `false`
+    /// without writing anything if the pool was paid out or donated to since `due_at` and `balance` were loaded.
+    pub async fn distribute_charity_pool(
+        &self,
+        guild_id: &str,
+        due_at: i64,
+        balance: i64,
+        next_distribution_at: i64,
+        payout: Option<&Transaction>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("distribute_charity_pool");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE charity_pools SET balance = balance - ?, next_distribution_at = ?
+            WHERE guild_id = ? AND next_distribution_at = ? AND balance = ?
+            "#
+        )
+        .bind(payout.map(|payout| payout.amount).unwrap_or(0))
+        .bind(next_distribution_at)
+        .bind(guild_id)
+        .bind(due_at)
+        .bind(balance)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(payout) = payout {
+            sqlx::query("INSERT INTO charity_distributions (guild_id, recipient, amount, distributed_at) VALUES (?, ?, ?, ?)")
+                .bind(guild_id)
+                .bind(&payout.to_user)
+                .bind(payout.amount)
+                .bind(payout.timestamp_unix)
+                .execute(&mut *tx)
+                .await?;
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // The guild's most recent payout as (recipient, amount, distributed_at)
+    pub async fn get_last_charity_distribution(&self, guild_id: &str) -> Result<Option<(String, i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_last_charity_distribution");
+        let row = sqlx::query(
+            "SELECT recipient, amount, distributed_at FROM charity_distributions WHERE guild_id = ? ORDER BY distributed_at DESC, id DESC LIMIT 1"
+        )
+        .bind(guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (row.get("recipient"), row.get("amount"), row.get("distributed_at"))))
+    }
+
+    // Registered users with a ledger entry to or from them since `since`
+    pub async fn get_users_active_since(&self, since: i64) -> Result<Vec<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_users_active_since");
+        let rows = sqlx::query(
+            r#"
+            SELECT discord_id FROM users u
+            WHERE EXISTS (SELECT 1 FROM transactions WHERE from_user = u.discord_id AND timestamp_unix >= ?1)
+               OR EXISTS (SELECT 1 FROM transactions WHERE to_user = u.discord_id AND timestamp_unix >= ?1)
+            "#
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| row.get("discord_id")).collect())
+    }
+
+    // Referrals
+    /// Record a referral and pay its bonuses together. Returns `false` without paying anything if the referred
+    /// member already named an inviter in the guild or the two of them already share a referral anywhere.
+    pub async fn record_referral(
+        &self,
+        guild_id: &str,
+        referrer_bonus: &Transaction,
+        referred_bonus: &Transaction,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("record_referral");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO referrals (guild_id, referrer_id, referred_id, referrer_bonus, referred_bonus, claimed_at)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6
+            WHERE NOT EXISTS (
+                SELECT 1 FROM referrals
+                WHERE (referrer_id = ?2 AND referred_id = ?3) OR (referrer_id = ?3 AND referred_id = ?2)
+            )
+            ON CONFLICT(guild_id, referred_id) DO NOTHING
+            "#
+        )
+        .bind(guild_id)
+        .bind(&referrer_bonus.to_user)
+        .bind(&referred_bonus.to_user)
+        .bind(referrer_bonus.amount)
+        .bind(referred_bonus.amount)
+        .bind(referred_bonus.timestamp_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        for bonus in [referrer_bonus, referred_bonus] {
+            if bonus.amount > 0 {
+                Self::write_transaction(&mut tx, bonus).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // The inviter a member named in a guild, if they've claimed a referral there
+    pub async fn get_referrer(&self, guild_id: &str, referred_id: &str) -> Result<Option<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_referrer");
+        let row = sqlx::query("SELECT referrer_id FROM referrals WHERE guild_id = ? AND referred_id = ?")
+            .bind(guild_id)
+            .bind(referred_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("referrer_id")))
+    }
+
+    pub async fn count_referrals(&self, guild_id: &str, referrer_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_referrals");
+        let row = sqlx::query("SELECT COUNT(*) as count FROM referrals WHERE guild_id = ? AND referrer_id = ?")
+            .bind(guild_id)
+            .bind(referrer_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    // Topic perks
+    fn topic_perk_from_row(row: &sqlx::sqlite::SqliteRow) -> TopicPerk {
+        TopicPerk {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            channel_id: row.get("channel_id"),
+            discord_id: row.get("discord_id"),
+            topic: row.get("topic"),
+            original_topic: row.get("original_topic"),
+            expires_at: row.get("expires_at"),
+        }
+    }
+
+    /// Record a bought channel topic and return its id.
+    /// Returns `None` if the channel already has one that hasn't been put back yet.
+    pub async fn claim_topic_perk(&self, perk: &TopicPerk) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("claim_topic_perk");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO topic_perks (guild_id, channel_id, discord_id, topic, original_topic, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(channel_id) DO NOTHING
+            "#
+        )
+        .bind(&perk.guild_id)
+        .bind(&perk.channel_id)
+        .bind(&perk.discord_id)
+        .bind(&perk.topic)
+        .bind(&perk.original_topic)
+        .bind(perk.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+    }
+
+    pub async fn get_topic_perk(&self, channel_id: &str) -> Result<Option<TopicPerk>, DatabaseError> {
+        let _timer = metrics::query_timer("get_topic_perk");
+        let row = sqlx::query(
+            "SELECT id, guild_id, channel_id, discord_id, topic, original_topic, expires_at FROM topic_perks WHERE channel_id = ?"
+        )
+        .bind(channel_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::topic_perk_from_row))
+    }
+
+    pub async fn get_expired_topic_perks(&self, now_unix: i64) -> Result<Vec<TopicPerk>, DatabaseError> {
+        let _timer = metrics::query_timer("get_expired_topic_perks");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, channel_id, discord_id, topic, original_topic, expires_at FROM topic_perks WHERE expires_at <= ?"
+        )
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::topic_perk_from_row).collect())
+    }
+
+    pub async fn remove_topic_perk(&self, perk_id: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("remove_topic_perk");
+        sqlx::query("DELETE FROM topic_perks WHERE id = ?")
+            .bind(perk_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Shop
+    fn shop_item_from_row(row: &sqlx::sqlite::SqliteRow) -> ShopItem {
+        ShopItem {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            name: row.get("name"),
+            price: row.get("price"),
+            role_id: row.get("role_id"),
+            role_duration_hours: row.get("role_duration_hours"),
+            stock: row.get("stock"),
+            consumable: row.get("consumable"),
+            tradeable: row.get("tradeable"),
+            perk: row.get("perk"),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_shop_item(
+        &self,
+        guild_id: &str,
+        name: &str,
+        price: i64,
+        role_id: Option<&str>,
+        role_duration_hours: Option<i64>,
+        stock: Option<i64>,
+        consumable: bool,
+        tradeable: bool,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("create_shop_item");
+        // Re-adding a removed item reactivates it so past purchases keep pointing at the same row
+        let row = sqlx::query(
+            r#"
+            INSERT INTO shop_items (guild_id, name, price, role_id, role_duration_hours, stock, consumable, tradeable)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id, name)
+            DO UPDATE SET price = excluded.price, role_id = excluded.role_id,
+                role_duration_hours = excluded.role_duration_hours, stock = excluded.stock,
+                consumable = excluded.consumable, tradeable = excluded.tradeable, active = 1
+            RETURNING id
+            "#
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(price)
+        .bind(role_id)
+        .bind(role_duration_hours)
+        .bind(stock)
+        .bind(consumable)
+        .bind(tradeable)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("id"))
+    }
+
+    pub async fn get_shop_items(&self, guild_id: &str) -> Result<Vec<ShopItem>, DatabaseError> {
+        let _timer = metrics::query_timer("get_shop_items");
+        let rows = sqlx::query(
+            "SELECT id, guild_id, name, price, role_id, role_duration_hours, stock, consumable, tradeable, perk FROM shop_items WHERE guild_id = ? AND active = 1 ORDER BY price ASC"
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::shop_item_from_row).collect())
+    }
+
+    pub async fn get_shop_item_by_name(&self, guild_id: &str, name: &str) -> Result<Option<ShopItem>, DatabaseError> {
+        let _timer = metrics::query_timer("get_shop_item_by_name");
+        let row = sqlx::query(
+            "SELECT id, guild_id, name, price, role_id, role_duration_hours, stock, consumable, tradeable, perk FROM shop_items WHERE guild_id = ? AND active = 1 AND name = ? COLLATE NOCASE"
+        )
+        .bind(guild_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::shop_item_from_row))
+    }
+
+    pub async fn set_shop_item_role(&self, item_id: i64, role_id: Option<&str>, role_duration_hours: Option<i64>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_shop_item_role");
+        sqlx::query("UPDATE shop_items SET role_id = ?, role_duration_hours = ? WHERE id = ?")
+            .bind(role_id)
+            .bind(role_duration_hours)
+            .bind(item_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_shop_item_perk(&self, item_id: i64, perk: Option<&str>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_shop_item_perk");
+        sqlx::query("UPDATE shop_items SET perk = ? WHERE id = ?")
+            .bind(perk)
+            .bind(item_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // The active item selling a perk in a guild
+    pub async fn get_perk_item(&self, guild_id: &str, perk: &str) -> Result<Option<ShopItem>, DatabaseError> {
+        let _timer = metrics::query_timer("get_perk_item");
+        let row = sqlx::query(
+            "SELECT id, guild_id, name, price, role_id, role_duration_hours, stock, consumable, tradeable, perk FROM shop_items WHERE guild_id = ? AND active = 1 AND perk = ? ORDER BY id ASC LIMIT 1"
+        )
+        .bind(guild_id)
+        .bind(perk)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::shop_item_from_row))
+    }
+
+    pub async fn deactivate_shop_item(&self, item_id: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("deactivate_shop_item");
+        sqlx::query("UPDATE shop_items SET active = 0 WHERE id = ?")
+            .bind(item_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Put `quantity` units back into a limited item's stock, e.g. after refunding a purchase.
+    /// Items with unlimited stock are left alone.
+    pub async fn restock_item(&self, item_id: i64, quantity: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("restock_item");
+        sqlx::query("UPDATE shop_items SET stock = stock + ? WHERE id = ? AND stock IS NOT NULL")
+            .bind(quantity)
+            .bind(item_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Take one unit of stock, record the payment and the purchase atomically.
+    /// Returns `false` without writing anything if the item is sold out.
+    pub async fn purchase_item(&self, item: &ShopItem, transaction: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("purchase_item");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE shop_items SET stock = stock - 1 WHERE id = ? AND stock IS NOT NULL AND stock > 0"
+        )
+        .bind(item.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if item.stock.is_some() && result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, transaction).await?;
+
+        sqlx::query("INSERT INTO purchases (item_id, guild_id, discord_id, transaction_id, price, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(item.id)
+            .bind(&item.guild_id)
+            .bind(&transaction.from_user)
+            .bind(&transaction.id)
+            .bind(transaction.amount)
+            .bind(transaction.timestamp_unix)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::write_inventory_add(&mut tx, &transaction.from_user, item.id, 1).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Inventory
+    async fn write_inventory_add(conn: &mut SqliteConnection, discord_id: &str, item_id: i64, quantity: i64) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO inventories (discord_id, item_id, quantity)
+            VALUES (?, ?, ?)
+            ON CONFLICT(discord_id, item_id)
+            DO UPDATE SET quantity = quantity + excluded.quantity
+            "#
+        )
+        .bind(discord_id)
+        .bind(item_id)
+        .bind(quantity)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Give a user one of a shop item for free, taking it from stock if stock is limited.
+    /// Returns `false` without writing anything if it's sold out.
+    pub async fn award_shop_item(&self, item: &ShopItem, discord_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("award_shop_item");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE shop_items SET stock = stock - 1 WHERE id = ? AND stock IS NOT NULL AND stock > 0"
+        )
+        .bind(item.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if item.stock.is_some() && result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_inventory_add(&mut tx, discord_id, item.id, 1).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Returns false if the user holds fewer than `quantity` of the item
+    async fn write_inventory_remove(conn: &mut SqliteConnection, discord_id: &str, item_id: i64, quantity: i64) -> Result<bool, DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE inventories SET quantity = quantity - ? WHERE discord_id = ? AND item_id = ? AND quantity >= ?"
+        )
+        .bind(quantity)
+        .bind(discord_id)
+        .bind(item_id)
+        .bind(quantity)
+        .execute(&mut *conn)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM inventories WHERE discord_id = ? AND item_id = ? AND quantity <= 0")
+            .bind(discord_id)
+            .bind(item_id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(true)
+    }
+
+    pub async fn add_inventory_item(&self, discord_id: &str, item_id: i64, quantity: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("add_inventory_item");
+        let mut conn = self.pool.acquire().await?;
+        Self::write_inventory_add(&mut conn, discord_id, item_id, quantity).await
+    }
+
+    pub async fn remove_inventory_item(&self, discord_id: &str, item_id: i64, quantity: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_inventory_item");
+        let mut conn = self.pool.acquire().await?;
+        Self::write_inventory_remove(&mut conn, discord_id, item_id, quantity).await
+    }
+
+    /// Move items between two inventories atomically.
+    /// Returns `false` without writing anything if the sender holds too few.
+    pub async fn transfer_inventory_item(&self, from_id: &str, to_id: &str, item_id: i64, quantity: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("transfer_inventory_item");
+        let mut tx = self.pool.begin().await?;
+
+        if !Self::write_inventory_remove(&mut tx, from_id, item_id, quantity).await? {
+            return Ok(false);
+        }
+        Self::write_inventory_add(&mut tx, to_id, item_id, quantity).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Items a user owns in one guild, including items since removed from the shop
+    pub async fn get_inventory(&self, discord_id: &str, guild_id: &str) -> Result<Vec<InventoryItem>, DatabaseError> {
+        let _timer = metrics::query_timer("get_inventory");
+        let rows = sqlx::query(
+            r#"
+            SELECT i.item_id, s.name, i.quantity, s.consumable, s.tradeable, s.sell_value
+            FROM inventories i
+            JOIN shop_items s ON s.id = i.item_id
+            WHERE i.discord_id = ? AND s.guild_id = ? AND i.quantity > 0
+            ORDER BY s.name ASC
+            "#
+        )
+        .bind(discord_id)
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| InventoryItem {
+                item_id: row.get("item_id"),
+                name: row.get("name"),
+                quantity: row.get("quantity"),
+                consumable: row.get("consumable"),
+                tradeable: row.get("tradeable"),
+                sell_value: row.get("sell_value"),
+            })
+            .collect())
+    }
+
+    /// Put a fishing catch in a user's inventory. Catches are hidden shop items,
+    /// created the first time anyone in the guild lands one. Returns the item id.
+    pub async fn record_catch(&self, guild_id: &str, name: &str, sell_value: i64, discord_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("record_catch");
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO shop_items (guild_id, name, price, consumable, tradeable, active, sell_value)
+            VALUES (?, ?, ?, 0, 1, 0, ?)
+            ON CONFLICT(guild_id, name)
+            DO UPDATE SET sell_value = excluded.sell_value
+            RETURNING id
+            "#
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(sell_value)
+        .bind(sell_value)
+        .fetch_one(&mut *tx)
+        .await?;
+        let item_id: i64 = row.get("id");
+
+        Self::write_inventory_add(&mut tx, discord_id, item_id, 1).await?;
+
+        tx.commit().await?;
+        Ok(item_id)
+    }
+
+    /// Take items out of the seller's inventory and pay them with `payment` atomically.
+    /// Returns `false` without writing anything if they hold too few.
+    pub async fn sell_inventory_item(&self, item_id: i64, quantity: i64, payment: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("sell_inventory_item");
+        let mut tx = self.begin_ledger().await?;
+
+        if !Self::write_inventory_remove(&mut tx, &payment.to_user, item_id, quantity).await? {
+            return Ok(false);
+        }
+        Self::write_transaction(&mut tx, payment).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Events
+    fn event_from_row(row: &sqlx::sqlite::SqliteRow) -> Event {
+        Event {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            name: row.get("name"),
+            item_id: row.get("item_id"),
+            starts_at: row.get("starts_at"),
+            ticket_price: row.get("ticket_price"),
+            capacity: row.get("capacity"),
+            attendance_bonus: row.get("attendance_bonus"),
+        }
+    }
+
+    /// Create an event and its ticket item. The ticket is kept out of the shop so it can only
+    /// be bought through `/event buy`, with the event capacity as its stock.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_event(
+        &self,
+        guild_id: &str,
+        name: &str,
+        starts_at: i64,
+        ticket_price: i64,
+        capacity: i64,
+        attendance_bonus: i64,
+        created_by: &str,
+    ) -> Result<Event, DatabaseError> {
+        let _timer = metrics::query_timer("create_event");
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO shop_items (guild_id, name, price, stock, consumable, tradeable, active)
+            VALUES (?, ?, ?, ?, 0, 1, 0)
+            ON CONFLICT(guild_id, name)
+            DO UPDATE SET price = excluded.price, stock = excluded.stock, consumable = 0, tradeable = 1, active = 0
+            RETURNING id
+            "#
+        )
+        .bind(guild_id)
+        .bind(format!("Ticket: {}", name))
+        .bind(ticket_price)
+        .bind(capacity)
+        .fetch_one(&mut *tx)
+        .await?;
+        let item_id: i64 = row.get("id");
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO events (guild_id, name, item_id, starts_at, ticket_price, capacity, attendance_bonus, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(item_id)
+        .bind(starts_at)
+        .bind(ticket_price)
+        .bind(capacity)
+        .bind(attendance_bonus)
+        .bind(created_by)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Event {
+            id: result.last_insert_rowid(),
+            guild_id: guild_id.to_string(),
+            name: name.to_string(),
+            item_id,
+            starts_at,
+            ticket_price,
+            capacity,
+            attendance_bonus,
+        })
+    }
+
+    pub async fn get_scheduled_event_by_name(&self, guild_id: &str, name: &str) -> Result<Option<Event>, DatabaseError> {
+        let _timer = metrics::query_timer("get_scheduled_event_by_name");
+        let row = sqlx::query(
+            r#"
+            SELECT id, guild_id, name, item_id, starts_at, ticket_price, capacity, attendance_bonus
+            FROM events
+            WHERE guild_id = ? AND status = 'scheduled' AND name = ? COLLATE NOCASE
+            "#
+        )
+        .bind(guild_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::event_from_row))
+    }
+
+    pub async fn get_scheduled_events(&self, guild_id: &str) -> Result<Vec<Event>, DatabaseError> {
+        let _timer = metrics::query_timer("get_scheduled_events");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, name, item_id, starts_at, ticket_price, capacity, attendance_bonus
+            FROM events
+            WHERE guild_id = ? AND status = 'scheduled'
+            ORDER BY starts_at ASC
+            "#
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::event_from_row).collect())
+    }
+
+    // Scheduled events that started at or before `started_before`
+    pub async fn get_due_events(&self, started_before: i64) -> Result<Vec<Event>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_events");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, name, item_id, starts_at, ticket_price, capacity, attendance_bonus
+            FROM events
+            WHERE status = 'scheduled' AND starts_at <= ?
+            "#
+        )
+        .bind(started_before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::event_from_row).collect())
+    }
+
+    pub async fn get_shop_item(&self, item_id: i64) -> Result<Option<ShopItem>, DatabaseError> {
+        let _timer = metrics::query_timer("get_shop_item");
+        let row = sqlx::query(
+            "SELECT id, guild_id, name, price, role_id, role_duration_hours, stock, consumable, tradeable, perk FROM shop_items WHERE id = ?"
+        )
+        .bind(item_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::shop_item_from_row))
+    }
+
+    pub async fn get_event_checkins(&self, event_id: i64) -> Result<Vec<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_event_checkins");
+        let rows = sqlx::query("SELECT discord_id FROM event_checkins WHERE event_id = ? ORDER BY checked_in_at ASC")
+            .bind(event_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("discord_id")).collect())
+    }
+
+    /// Use up one of the user's tickets and record their attendance atomically.
+    /// Returns `false` without writing anything if they hold no ticket.
+    pub async fn check_in_event(&self, event: &Event, discord_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("check_in_event");
+        let mut tx = self.pool.begin().await?;
+
+        if !Self::write_inventory_remove(&mut tx, discord_id, event.item_id, 1).await? {
+            return Ok(false);
+        }
+
+        sqlx::query("INSERT INTO event_checkins (event_id, discord_id, checked_in_at) VALUES (?, ?, ?)")
+            .bind(event.id)
+            .bind(discord_id)
+            .bind(Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Close an event: pay attendance bonuses and void tickets that were never checked in
+    pub async fn complete_event(&self, event: &Event, payouts: &[Transaction]) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("complete_event");
+        let mut tx = self.begin_ledger().await?;
+
+        for payout in payouts {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+
+        sqlx::query("DELETE FROM inventories WHERE item_id = ?")
+            .bind(event.item_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE events SET status = 'completed' WHERE id = ?")
+            .bind(event.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Cancel an event, refunding the ticket price to everyone holding or having used a ticket.
+    /// Refunds are paid from `refund_from`. Returns the number of tickets refunded.
+    pub async fn cancel_event(&self, event: &Event, refund_from: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("cancel_event");
+        let mut tx = self.begin_ledger().await?;
+
+        let mut holders: Vec<(String, i64)> = sqlx::query("SELECT discord_id, quantity FROM inventories WHERE item_id = ? AND quantity > 0")
+            .bind(event.item_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| (row.get("discord_id"), row.get("quantity")))
+            .collect();
+
+        let attendees = sqlx::query("SELECT discord_id FROM event_checkins WHERE event_id = ?")
+            .bind(event.id)
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in &attendees {
+            holders.push((row.get("discord_id"), 1));
+        }
+
+        let mut refunded = 0;
+        for (discord_id, quantity) in &holders {
+            let refund = Transaction::system(
+                refund_from,
+                discord_id,
+                event.ticket_price * quantity,
+                "event_refund",
+                Some(format!("Refund for cancelled event {}", event.name)),
+            );
+            Self::write_transaction(&mut tx, &refund).await?;
+            refunded += quantity;
+        }
+
+        sqlx::query("DELETE FROM inventories WHERE item_id = ?")
+            .bind(event.item_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE events SET status = 'cancelled' WHERE id = ?")
+            .bind(event.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(refunded)
+    }
+
+    // Triggers
+    pub async fn add_trigger(
+        &self,
+        guild_id: &str,
+        phrase: &str,
+        response: &str,
+        user_id: Option<&str>,
+        created_by: &str,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("add_trigger");
+        let result = sqlx::query(
+            "INSERT INTO triggers (guild_id, phrase, response, user_id, created_by) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(guild_id)
+        .bind(phrase)
+        .bind(response)
+        .bind(user_id)
+        .bind(created_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn remove_trigger(&self, guild_id: &str, trigger_id: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_trigger");
+        let result = sqlx::query("DELETE FROM triggers WHERE guild_id = ? AND id = ?")
+            .bind(guild_id)
+            .bind(trigger_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_triggers(&self, guild_id: &str) -> Result<Vec<Trigger>, DatabaseError> {
+        let _timer = metrics::query_timer("get_triggers");
+        let rows = sqlx::query("SELECT id, phrase, response, user_id FROM triggers WHERE guild_id = ? ORDER BY id ASC")
+            .bind(guild_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Trigger {
+                id: row.get("id"),
+                phrase: row.get("phrase"),
+                response: row.get("response"),
+                user_id: row.get("user_id"),
+            })
+            .collect())
+    }
+
+    // Timed role grants
+    pub async fn add_role_grant(&self, guild_id: &str, discord_id: &str, role_id: &str, item_id: i64, expires_at: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("add_role_grant");
+        // Buying the same role again extends the existing grant instead of stacking a second one
+        let result = sqlx::query(
+            "UPDATE role_grants SET expires_at = MAX(expires_at, ?) WHERE guild_id = ? AND discord_id = ? AND role_id = ?"
+        )
+        .bind(expires_at)
+        .bind(guild_id)
+        .bind(discord_id)
+        .bind(role_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            sqlx::query("INSERT INTO role_grants (guild_id, discord_id, role_id, item_id, expires_at) VALUES (?, ?, ?, ?, ?)")
+                .bind(guild_id)
+                .bind(discord_id)
+                .bind(role_id)
+                .bind(item_id)
+                .bind(expires_at)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_expired_role_grants(&self, now_unix: i64) -> Result<Vec<RoleGrant>, DatabaseError> {
+        let _timer = metrics::query_timer("get_expired_role_grants");
+        let rows = sqlx::query("SELECT id, guild_id, discord_id, role_id FROM role_grants WHERE expires_at <= ?")
+            .bind(now_unix)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RoleGrant {
+                id: row.get("id"),
+                guild_id: row.get("guild_id"),
+                discord_id: row.get("discord_id"),
+                role_id: row.get("role_id"),
+            })
+            .collect())
+    }
+
+    pub async fn remove_role_grant(&self, grant_id: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("remove_role_grant");
+        sqlx::query("DELETE FROM role_grants WHERE id = ?")
+            .bind(grant_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Auction deposits
+    // Take a bidder's deposit into escrow and record it against the auction in one database transaction
+    pub async fn hold_auction_deposit(&self, voice_channel_id: &str, hold: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("hold_auction_deposit");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, hold).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO auction_deposits (voice_channel_id, discord_id, amount)
+            VALUES (?, ?, ?)
+            ON CONFLICT(voice_channel_id, discord_id)
+            DO UPDATE SET amount = amount + excluded.amount
+            "#
+        )
+        .bind(voice_channel_id)
+        .bind(&hold.from_user)
+        .bind(hold.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Write the entries that pay out an auction's deposits and forget the deposits in one database
+    /// transaction. `discord_id` limits it to one bidder's deposit, `None` releases all of them.
+    pub async fn release_auction_deposits(
+        &self,
+        voice_channel_id: &str,
+        discord_id: Option<&str>,
+        entries: &[Transaction],
+    ) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("release_auction_deposits");
+        let mut tx = self.begin_ledger().await?;
+        for entry in entries {
+            Self::write_transaction(&mut tx, entry).await?;
+        }
+
+        sqlx::query("DELETE FROM auction_deposits WHERE voice_channel_id = ?1 AND (?2 IS NULL OR discord_id = ?2)")
+            .bind(voice_channel_id)
+            .bind(discord_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_auction_deposits(&self) -> Result<Vec<AuctionDeposit>, DatabaseError> {
+        let _timer = metrics::query_timer("get_auction_deposits");
+        let rows = sqlx::query("SELECT voice_channel_id, discord_id, amount FROM auction_deposits ORDER BY voice_channel_id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| AuctionDeposit {
+                voice_channel_id: row.get("voice_channel_id"),
+                discord_id: row.get("discord_id"),
+                amount: row.get("amount"),
+            })
+            .collect())
+    }
+
+    // Escrows
+    fn escrow_from_row(row: &sqlx::sqlite::SqliteRow) -> Escrow {
+        Escrow {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            buyer: row.get("buyer"),
+            seller: row.get("seller"),
+            amount: row.get("amount"),
+            description: row.get("description"),
+            status: row.get("status"),
+            expires_at: row.get("expires_at"),
+        }
+    }
+
+    // Lock the buyer's funds and open the escrow in one database transaction
+    pub async fn create_escrow(
+        &self,
+        guild_id: &str,
+        seller: &str,
+        description: &str,
+        expires_at: i64,
+        funding: &Transaction,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("create_escrow");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, funding).await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO escrows (guild_id, buyer, seller, amount, description, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(&funding.from_user)
+        .bind(seller)
+        .bind(funding.amount)
+        .bind(description)
+        .bind(funding.timestamp_unix)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_escrow(&self, escrow_id: i64) -> Result<Option<Escrow>, DatabaseError> {
+        let _timer = metrics::query_timer("get_escrow");
+        let row = sqlx::query(
+            "SELECT id, guild_id, buyer, seller, amount, description, status, expires_at FROM escrows WHERE id = ?"
+        )
+        .bind(escrow_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::escrow_from_row))
+    }
+
+    // Open and disputed escrows the user is part of
+    pub async fn get_active_escrows(&self, discord_id: &str) -> Result<Vec<Escrow>, DatabaseError> {
+        let _timer = metrics::query_timer("get_active_escrows");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, buyer, seller, amount, description, status, expires_at
+            FROM escrows
+            WHERE (buyer = ? OR seller = ?) AND status IN ('open', 'disputed')
+            ORDER BY id DESC
+            "#
+        )
+        .bind(discord_id)
+        .bind(discord_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::escrow_from_row).collect())
+    }
+
+    pub async fn get_disputed_escrows(&self, guild_id: &str) -> Result<Vec<Escrow>, DatabaseError> {
+        let _timer = metrics::query_timer("get_disputed_escrows");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, buyer, seller, amount, description, status, expires_at
+            FROM escrows
+            WHERE guild_id = ? AND status = 'disputed'
+            ORDER BY id ASC
+            "#
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::escrow_from_row).collect())
+    }
+
+    pub async fn get_expired_escrows(&self, now_unix: i64) -> Result<Vec<Escrow>, DatabaseError> {
+        let _timer = metrics::query_timer("get_expired_escrows");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, buyer, seller, amount, description, status, expires_at
+            FROM escrows
+            WHERE status = 'open' AND expires_at <= ?
+            "#
+        )
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::escrow_from_row).collect())
+    }
+
+    /// Move an escrow from `from_status` to `to_status`, paying out `payout` in the same database transaction.
+    /// Returns `false` without writing anything if the escrow is no longer in `from_status`.
+    pub async fn transition_escrow(
+        &self,
+        escrow_id: i64,
+        from_status: &str,
+        to_status: &str,
+        resolved_by: Option<&str>,
+        payout: Option<&Transaction>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("transition_escrow");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query("UPDATE escrows SET status = ?, resolved_by = COALESCE(?, resolved_by) WHERE id = ? AND status = ?")
+            .bind(to_status)
+            .bind(resolved_by)
+            .bind(escrow_id)
+            .bind(from_status)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(payout) = payout {
+            Self::write_transaction(&mut tx, payout).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn create_payment_request(
+        &self,
+        guild_id: &str,
+        requester: &str,
+        payer: &str,
+        amount: i64,
+        reason: Option<&str>,
+        expires_at: i64,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("create_payment_request");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO payment_requests (guild_id, requester, payer, amount, reason, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(requester)
+        .bind(payer)
+        .bind(amount)
+        .bind(reason)
+        .bind(Utc::now().timestamp())
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_payment_request(&self, request_id: i64) -> Result<Option<PaymentRequest>, DatabaseError> {
+        let _timer = metrics::query_timer("get_payment_request");
+        let row = sqlx::query(
+            r#"
+            SELECT id, guild_id, requester, payer, amount, reason, status, expires_at
+            FROM payment_requests
+            WHERE id = ?
+            "#
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| PaymentRequest {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            requester: row.get("requester"),
+            payer: row.get("payer"),
+            amount: row.get("amount"),
+            reason: row.get("reason"),
+            status: row.get("status"),
+            expires_at: row.get("expires_at"),
+        }))
+    }
+
+    // Mark a pending, unexpired request paid and record its transfer entries together. Returns false if it was already closed.
+    pub async fn settle_payment_request(
+        &self,
+        request_id: i64,
+        now_unix: i64,
+        entries: &[Transaction],
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("settle_payment_request");
+        let Some(transfer) = entries.first() else {
+            return Ok(false);
+        };
+
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE payment_requests SET status = 'paid', transaction_id = ? WHERE id = ? AND status = 'pending' AND expires_at > ?"
+        )
+        .bind(&transfer.id)
+        .bind(request_id)
+        .bind(now_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        for entry in entries {
+            Self::write_transaction(&mut tx, entry).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Close a pending request without paying it (declined or expired)
+    pub async fn close_payment_request(&self, request_id: i64, status: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("close_payment_request");
+        let result = sqlx::query("UPDATE payment_requests SET status = ? WHERE id = ? AND status = 'pending'")
+            .bind(status)
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_auction(
+        &self,
+        guild_id: &str,
+        voice_channel_id: &str,
+        creator_id: &str,
+        item: &str,
+        outcome: &str,
+        winner: Option<(&str, i64)>,
+        bid_count: i64,
+        started_at: i64,
+    ) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("record_auction");
+        sqlx::query(
+            r#"
+            INSERT INTO auction_history
+            (guild_id, voice_channel_id, creator_id, item, outcome, winner_id, amount, bid_count, started_at, ended_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(guild_id)
+        .bind(voice_channel_id)
+        .bind(creator_id)
+        .bind(item)
+        .bind(outcome)
+        .bind(winner.map(|(winner_id, _)| winner_id))
+        .bind(winner.map(|(_, amount)| amount))
+        .bind(bid_count)
+        .bind(started_at)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_auction_history(&self, guild_id: &str, limit: u32) -> Result<Vec<AuctionRecord>, DatabaseError> {
+        let _timer = metrics::query_timer("get_auction_history");
+        let rows = sqlx::query(
+            r#"
+            SELECT item, outcome, winner_id, amount, bid_count, ended_at
+            FROM auction_history
+            WHERE guild_id = ?
+            ORDER BY ended_at DESC, id DESC
+            LIMIT ?
+            "#
+        )
+        .bind(guild_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| AuctionRecord {
+            item: row.get("item"),
+            outcome: row.get("outcome"),
+            winner_id: row.get("winner_id"),
+            amount: row.get("amount"),
+            bid_count: row.get("bid_count"),
+            ended_at: row.get("ended_at"),
+        }).collect())
+    }
+
+    // Winners ranked by total spent on auctions they won and paid for: (discord_id, total, wins)
+    pub async fn get_top_auction_spenders(&self, guild_id: &str, limit: u32) -> Result<Vec<(String, i64, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_top_auction_spenders");
+        let rows = sqlx::query(
+            r#"
+            SELECT winner_id, SUM(amount) as total, COUNT(*) as wins
+            FROM auction_history
+            WHERE guild_id = ? AND outcome = 'sold'
+            GROUP BY winner_id
+            ORDER BY total DESC
+            LIMIT ?
+            "#
+        )
+        .bind(guild_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("winner_id"), row.get("total"), row.get("wins"))).collect())
+    }
+
+    pub async fn is_notification_opted_out(&self, discord_id: &str, kind: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("is_notification_opted_out");
+        let row = sqlx::query("SELECT 1 FROM notification_optouts WHERE discord_id = ? AND kind = ?")
+            .bind(discord_id)
+            .bind(kind)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn set_notification_opt_out(&self, discord_id: &str, kind: &str, opted_out: bool) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_notification_opt_out");
+        let query = if opted_out {
+            "INSERT OR IGNORE INTO notification_optouts (discord_id, kind) VALUES (?, ?)"
+        } else {
+            "DELETE FROM notification_optouts WHERE discord_id = ? AND kind = ?"
+        };
+
+        sqlx::query(query)
+            .bind(discord_id)
+            .bind(kind)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user_preferences(&self, discord_id: &str) -> Result<UserPreferences, DatabaseError> {
+        let _timer = metrics::query_timer("get_user_preferences");
+        let row = sqlx::query("SELECT private_replies, public_balance, hide_from_leaderboard FROM user_preferences WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(match row {
+            Some(row) => UserPreferences {
+                private_replies: row.get("private_replies"),
+                public_balance: row.get("public_balance"),
+                hide_from_leaderboard: row.get("hide_from_leaderboard"),
+            },
+            None => UserPreferences::default(),
+        })
+    }
+
+    pub async fn set_private_replies(&self, discord_id: &str, private_replies: bool) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_private_replies");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, private_replies)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET private_replies = excluded.private_replies
+            "#
+        )
+        .bind(discord_id)
+        .bind(private_replies)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_public_balance(&self, discord_id: &str, public_balance: bool) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_public_balance");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, public_balance)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET public_balance = excluded.public_balance
+            "#
+        )
+        .bind(discord_id)
+        .bind(public_balance)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_hide_from_leaderboard(&self, discord_id: &str, hidden: bool) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_hide_from_leaderboard");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, hide_from_leaderboard)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET hide_from_leaderboard = excluded.hide_from_leaderboard
+            "#
+        )
+        .bind(discord_id)
+        .bind(hidden)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_gamble_limits(&self, discord_id: &str) -> Result<GambleLimits, DatabaseError> {
+        let _timer = metrics::query_timer("get_gamble_limits");
+        let row = sqlx::query("SELECT gamble_daily_limit, gamble_excluded_until FROM user_preferences WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| GambleLimits {
+            daily_limit: row.get("gamble_daily_limit"),
+            excluded_until: row.get("gamble_excluded_until"),
+        }).unwrap_or_default())
+    }
+
+    // None removes the limit
+    pub async fn set_gamble_daily_limit(&self, discord_id: &str, limit: Option<i64>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_gamble_daily_limit");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, gamble_daily_limit)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET gamble_daily_limit = excluded.gamble_daily_limit
+            "#
+        )
+        .bind(discord_id)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // None lifts the exclusion
+    pub async fn set_gamble_excluded_until(&self, discord_id: &str, until: Option<i64>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_gamble_excluded_until");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, gamble_excluded_until)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET gamble_excluded_until = excluded.gamble_excluded_until
+            "#
+        )
+        .bind(discord_id)
+        .bind(until)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // None clears the title
+    pub async fn set_profile_title(&self, discord_id: &str, title: Option<&str>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_profile_title");
+        sqlx::query(
+            r#"
+            INSERT INTO user_preferences (discord_id, title)
+            VALUES (?, ?)
+            ON CONFLICT(discord_id)
+            DO UPDATE SET title = excluded.title
+            "#
+        )
+        .bind(discord_id)
+        .bind(title)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Gift codes. Returns false if the code already exists so the caller can pick another.
+    pub async fn create_gift_code(&self, code: &GiftCode) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("create_gift_code");
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO codes (code, guild_id, amount, max_uses, uses, created_by, created_at, expires_at)
+            VALUES (?, ?, ?, ?, 0, ?, ?, ?)
+            "#
+        )
+        .bind(&code.code)
+        .bind(&code.guild_id)
+        .bind(code.amount)
+        .bind(code.max_uses)
+        .bind(&code.created_by)
+        .bind(code.created_at)
+        .bind(code.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_gift_code(&self, code: &str) -> Result<Option<GiftCode>, DatabaseError> {
+        let _timer = metrics::query_timer("get_gift_code");
+        let row = sqlx::query(
+            r#"
+            SELECT code, guild_id, amount, max_uses, uses, created_by, created_at, expires_at
+            FROM codes
+            WHERE code = ?
+            "#
+        )
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| GiftCode {
+            code: row.get("code"),
+            guild_id: row.get("guild_id"),
+            amount: row.get("amount"),
+            max_uses: row.get("max_uses"),
+            uses: row.get("uses"),
+            created_by: row.get("created_by"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        }))
+    }
+
+    // Codes in a guild that can still be redeemed, newest first
+    pub async fn get_active_gift_codes(&self, guild_id: &str, now_unix: i64) -> Result<Vec<GiftCode>, DatabaseError> {
+        let _timer = metrics::query_timer("get_active_gift_codes");
+        let rows = sqlx::query(
+            r#"
+            SELECT code, guild_id, amount, max_uses, uses, created_by, created_at, expires_at
+            FROM codes
+            WHERE guild_id = ? AND uses < max_uses AND (expires_at IS NULL OR expires_at > ?)
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(guild_id)
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| GiftCode {
+            code: row.get("code"),
+            guild_id: row.get("guild_id"),
+            amount: row.get("amount"),
+            max_uses: row.get("max_uses"),
+            uses: row.get("uses"),
+            created_by: row.get("created_by"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        }).collect())
+    }
+
+    pub async fn has_redeemed_code(&self, code: &str, discord_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("has_redeemed_code");
+        let row = sqlx::query("SELECT 1 FROM code_redemptions WHERE code = ? AND discord_id = ?")
+            .bind(code)
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    // Use up one redemption of a live code and credit the user together. Returns false if the code
+    // ran out, expired or was already redeemed by this user in the meantime.
+    pub async fn redeem_gift_code(&self, code: &str, now_unix: i64, credit: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("redeem_gift_code");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE codes SET uses = uses + 1 WHERE code = ? AND uses < max_uses AND (expires_at IS NULL OR expires_at > ?)"
+        )
+        .bind(code)
+        .bind(now_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO code_redemptions (code, discord_id, transaction_id, redeemed_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(code)
+        .bind(&credit.to_user)
+        .bind(&credit.id)
+        .bind(now_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, credit).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Payroll
+    pub async fn set_payroll(&self, payroll: &Payroll) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_payroll");
+        sqlx::query(
+            r#"
+            INSERT INTO payroll (guild_id, role_id, amount, interval_seconds, next_payout_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id, role_id)
+            DO UPDATE SET amount = excluded.amount, interval_seconds = excluded.interval_seconds,
+                next_payout_at = excluded.next_payout_at, created_by = excluded.created_by
+            "#
+        )
+        .bind(&payroll.guild_id)
+        .bind(&payroll.role_id)
+        .bind(payroll.amount)
+        .bind(payroll.interval_seconds)
+        .bind(payroll.next_payout_at)
+        .bind(&payroll.created_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_payroll(&self, guild_id: &str, role_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_payroll");
+        let result = sqlx::query("DELETE FROM payroll WHERE guild_id = ? AND role_id = ?")
+            .bind(guild_id)
+            .bind(role_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_payrolls(&self, guild_id: &str) -> Result<Vec<Payroll>, DatabaseError> {
+        let _timer = metrics::query_timer("get_payrolls");
+        let rows = sqlx::query(
+            r#"
+            SELECT guild_id, role_id, amount, interval_seconds, next_payout_at, created_by
+            FROM payroll
+            WHERE guild_id = ?
+            ORDER BY next_payout_at
+            "#
+        )
+        .bind(guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::payroll_from_row).collect())
+    }
+
+    pub async fn get_due_payrolls(&self, now_unix: i64) -> Result<Vec<Payroll>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_payrolls");
+        let rows = sqlx::query(
+            r#"
+            SELECT guild_id, role_id, amount, interval_seconds, next_payout_at, created_by
+            FROM payroll
+            WHERE next_payout_at <= ?
+            "#
+        )
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::payroll_from_row).collect())
+    }
+
+    fn payroll_from_row(row: &sqlx::sqlite::SqliteRow) -> Payroll {
+        Payroll {
+            guild_id: row.get("guild_id"),
+            role_id: row.get("role_id"),
+            amount: row.get("amount"),
+            interval_seconds: row.get("interval_seconds"),
+            next_payout_at: row.get("next_payout_at"),
+            created_by: row.get("created_by"),
+        }
+    }
+
+    // Pay one round of a payroll and move it to its next payout together. Returns false if the payroll
+    // was changed, removed or already paid since it was loaded.
+    pub async fn record_payroll_run(
+        &self,
+        payroll: &Payroll,
+        next_payout_at: i64,
+        salaries: &[Transaction],
+        skipped: &[String],
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("record_payroll_run");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE payroll SET next_payout_at = ? WHERE guild_id = ? AND role_id = ? AND next_payout_at = ? AND amount = ?"
+        )
+        .bind(next_payout_at)
+        .bind(&payroll.guild_id)
+        .bind(&payroll.role_id)
+        .bind(payroll.next_payout_at)
+        .bind(payroll.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let now = Utc::now().timestamp();
+        for salary in salaries {
+            Self::write_transaction(&mut tx, salary).await?;
+            sqlx::query(
+                r#"
+                INSERT INTO payroll_log (guild_id, role_id, discord_id, amount, status, transaction_id, paid_at)
+                VALUES (?, ?, ?, ?, 'paid', ?, ?)
+                "#
+            )
+            .bind(&payroll.guild_id)
+            .bind(&payroll.role_id)
+            .bind(&salary.to_user)
+            .bind(salary.amount)
+            .bind(&salary.id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for discord_id in skipped {
+            sqlx::query(
+                r#"
+                INSERT INTO payroll_log (guild_id, role_id, discord_id, amount, status, paid_at)
+                VALUES (?, ?, ?, ?, 'skipped', ?)
+                "#
+            )
+            .bind(&payroll.guild_id)
+            .bind(&payroll.role_id)
+            .bind(discord_id)
+            .bind(payroll.amount)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Vault savings
+    pub async fn get_vault_balance(&self, guild_id: &str, discord_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_vault_balance");
+        let row = sqlx::query("SELECT balance FROM vault_balances WHERE guild_id = ? AND discord_id = ?")
+            .bind(guild_id)
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("balance")).unwrap_or(0))
+    }
+
+    // A user's savings across every guild's vault
+    pub async fn get_total_vault_balance(&self, discord_id: &str) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_total_vault_balance");
+        let row = sqlx::query("SELECT COALESCE(SUM(balance), 0) as balance FROM vault_balances WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("balance"))
+    }
+
+    // Record a wallet-to-vault transfer and credit the vault together, restarting the interest clock
+    pub async fn vault_deposit(&self, guild_id: &str, deposit: &Transaction) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("vault_deposit");
+        let mut tx = self.begin_ledger().await?;
+        Self::write_transaction(&mut tx, deposit).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO vault_balances (guild_id, discord_id, balance, last_interest_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(guild_id, discord_id)
+            DO UPDATE SET balance = balance + excluded.balance, last_interest_at = excluded.last_interest_at
+            "#
+        )
+        .bind(guild_id)
+        .bind(&deposit.from_user)
+        .bind(deposit.amount)
+        .bind(deposit.timestamp_unix)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Debit the vault and record the vault-to-wallet transfer together. Returns false if the vault holds too little.
+    pub async fn vault_withdraw(&self, guild_id: &str, withdrawal: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("vault_withdraw");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE vault_balances SET balance = balance - ? WHERE guild_id = ? AND discord_id = ? AND balance >= ?"
+        )
+        .bind(withdrawal.amount)
+        .bind(guild_id)
+        .bind(&withdrawal.to_user)
+        .bind(withdrawal.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, withdrawal).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // (guild_id, discord_id, balance) of vaults that haven't earned interest since `accrued_before`
+    pub async fn get_vaults_due_interest(&self, accrued_before: i64) -> Result<Vec<(String, String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_vaults_due_interest");
+        let rows = sqlx::query(
+            "SELECT guild_id, discord_id, balance FROM vault_balances WHERE balance > 0 AND last_interest_at <= ?"
+        )
+        .bind(accrued_before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("guild_id"), row.get("discord_id"), row.get("balance"))).collect())
+    }
+
+    // Credit one period of interest to a vault, or just restart its clock when the interest rounds to nothing.
+    // Returns false if the vault was paid or deposited into since it was loaded.
+    pub async fn pay_vault_interest(
+        &self,
+        guild_id: &str,
+        discord_id: &str,
+        accrued_before: i64,
+        interest: Option<&Transaction>,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("pay_vault_interest");
+        let mut tx = self.begin_ledger().await?;
+        let now = Utc::now().timestamp();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE vault_balances SET balance = balance + ?, last_interest_at = ?
+            WHERE guild_id = ? AND discord_id = ? AND last_interest_at <= ?
+            "#
+        )
+        .bind(interest.map(|interest| interest.amount).unwrap_or(0))
+        .bind(now)
+        .bind(guild_id)
+        .bind(discord_id)
+        .bind(accrued_before)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if let Some(interest) = interest {
+            Self::write_transaction(&mut tx, interest).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Loans
+    pub async fn create_loan(&self, loan: &Loan) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("create_loan");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO loans (guild_id, borrower, principal, total_due, installment, payment_interval_seconds, reason, requested_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&loan.guild_id)
+        .bind(&loan.borrower)
+        .bind(loan.principal)
+        .bind(loan.total_due)
+        .bind(loan.installment)
+        .bind(loan.payment_interval_seconds)
+        .bind(&loan.reason)
+        .bind(loan.requested_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    const LOAN_COLUMNS: &'static str = "id, guild_id, borrower, principal, total_due, repaid, installment, payment_interval_seconds, \
+        reason, status, requested_at, approved_by, next_payment_at, missed_payments";
+
+    fn loan_from_row(row: &sqlx::sqlite::SqliteRow) -> Loan {
+        Loan {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            borrower: row.get("borrower"),
+            principal: row.get("principal"),
+            total_due: row.get("total_due"),
+            repaid: row.get("repaid"),
+            installment: row.get("installment"),
+            payment_interval_seconds: row.get("payment_interval_seconds"),
+            reason: row.get("reason"),
+            status: row.get("status"),
+            requested_at: row.get("requested_at"),
+            approved_by: row.get("approved_by"),
+            next_payment_at: row.get("next_payment_at"),
+            missed_payments: row.get("missed_payments"),
+        }
+    }
+
+    pub async fn get_loan(&self, loan_id: i64) -> Result<Option<Loan>, DatabaseError> {
+        let _timer = metrics::query_timer("get_loan");
+        let row = sqlx::query(&format!("SELECT {} FROM loans WHERE id = ?", Self::LOAN_COLUMNS))
+            .bind(loan_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::loan_from_row))
+    }
+
+    // The borrower's pending, active or defaulted loan in a guild, if any
+    pub async fn get_open_loan(&self, guild_id: &str, borrower: &str) -> Result<Option<Loan>, DatabaseError> {
+        let _timer = metrics::query_timer("get_open_loan");
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM loans WHERE guild_id = ? AND borrower = ? AND status IN ('pending', 'active', 'defaulted')",
+            Self::LOAN_COLUMNS
+        ))
+        .bind(guild_id)
+        .bind(borrower)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(Self::loan_from_row))
+    }
+
+    // Whether the borrower has a pending, active or defaulted loan in any guild
+    pub async fn has_open_loan(&self, borrower: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("has_open_loan");
+        let row = sqlx::query(
+            "SELECT 1 FROM loans WHERE borrower = ? AND status IN ('pending', 'active', 'defaulted') LIMIT 1"
+        )
+        .bind(borrower)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn get_loans_by_status(&self, guild_id: &str, status: &str) -> Result<Vec<Loan>, DatabaseError> {
+        let _timer = metrics::query_timer("get_loans_by_status");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM loans WHERE guild_id = ? AND status = ? ORDER BY requested_at",
+            Self::LOAN_COLUMNS
+        ))
+        .bind(guild_id)
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::loan_from_row).collect())
+    }
+
+    pub async fn get_due_loans(&self, now_unix: i64) -> Result<Vec<Loan>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_loans");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM loans WHERE status = 'active' AND next_payment_at <= ?",
+            Self::LOAN_COLUMNS
+        ))
+        .bind(now_unix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::loan_from_row).collect())
+    }
+
+    // Activate a pending loan and pay it out together. Returns false if it was already approved or denied.
+    pub async fn approve_loan(
+        &self,
+        loan_id: i64,
+        approved_by: &str,
+        next_payment_at: i64,
+        disbursement: &Transaction,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("approve_loan");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            "UPDATE loans SET status = 'active', approved_by = ?, next_payment_at = ? WHERE id = ? AND status = 'pending'"
+        )
+        .bind(approved_by)
+        .bind(next_payment_at)
+        .bind(loan_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, disbursement).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn deny_loan(&self, loan_id: i64, denied_by: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("deny_loan");
+        let result = sqlx::query("UPDATE loans SET status = 'denied', approved_by = ? WHERE id = ? AND status = 'pending'")
+            .bind(denied_by)
+            .bind(loan_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Record one scheduled installment, collected or missed, and move the loan on. Returns false if the loan
+    // changed since it was loaded.
+    pub async fn record_loan_installment(
+        &self,
+        loan: &Loan,
+        collected: Option<&Transaction>,
+        next_payment_at: i64,
+        status: &str,
+    ) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("record_loan_installment");
+        let mut tx = self.begin_ledger().await?;
+        let now = Utc::now().timestamp();
+        let paid = collected.map(|payment| payment.amount).unwrap_or(0);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE loans
+            SET repaid = repaid + ?, missed_payments = missed_payments + ?, next_payment_at = ?, status = ?
+            WHERE id = ? AND status = 'active' AND repaid = ? AND next_payment_at = ?
+            "#
+        )
+        .bind(paid)
+        .bind(if collected.is_some() { 0 } else { 1 })
+        .bind(next_payment_at)
+        .bind(status)
+        .bind(loan.id)
+        .bind(loan.repaid)
+        .bind(loan.next_payment_at)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        match collected {
+            Some(payment) => {
+                Self::write_transaction(&mut tx, payment).await?;
+                sqlx::query("INSERT INTO loan_payments (loan_id, amount, status, transaction_id, created_at) VALUES (?, ?, 'paid', ?, ?)")
+                    .bind(loan.id)
+                    .bind(payment.amount)
+                    .bind(&payment.id)
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                sqlx::query("INSERT INTO loan_payments (loan_id, amount, status, created_at) VALUES (?, ?, 'missed', ?)")
+                    .bind(loan.id)
+                    .bind(loan.installment.min(loan.outstanding()))
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Pay off part or all of an active or defaulted loan early, closing it once nothing is left.
+    // Returns false if the loan was closed or the payment is more than what's owed.
+    pub async fn repay_loan(&self, loan_id: i64, payment: &Transaction) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("repay_loan");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE loans
+            SET repaid = repaid + ?1, status = CASE WHEN repaid + ?1 >= total_due THEN 'repaid' ELSE status END
+            WHERE id = ?2 AND status IN ('active', 'defaulted') AND total_due - repaid >= ?1
+            "#
+        )
+        .bind(payment.amount)
+        .bind(loan_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_transaction(&mut tx, payment).await?;
+        sqlx::query("INSERT INTO loan_payments (loan_id, amount, status, transaction_id, created_at) VALUES (?, ?, 'paid', ?, ?)")
+            .bind(loan_id)
+            .bind(payment.amount)
+            .bind(&payment.id)
+            .bind(payment.timestamp_unix)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Wealth tax
+    // Registered users whose balance is above `threshold`, richest first
+    pub async fn get_balances_above(&self, threshold: i64) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_balances_above");
+        let rows = sqlx::query(
+            r#"
+            SELECT u.discord_id, b.balance
+            FROM users u
+            JOIN balances b ON u.discord_id = b.discord_id
+            WHERE b.balance > ?
+            ORDER BY b.balance DESC
+            "#
+        )
+        .bind(threshold)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("discord_id"), row.get("balance"))).collect())
+    }
+
+    pub async fn get_last_tax_run(&self, guild_id: &str) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("get_last_tax_run");
+        let row = sqlx::query("SELECT MAX(ran_at) as ran_at FROM tax_runs WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("ran_at"))
+    }
+
+    // Record a tax run and its deductions. Returns false if the guild was taxed after `last_run_at` in the meantime.
+    pub async fn record_tax_run(&self, run: &TaxRun, last_run_at: Option<i64>, taxes: &[Transaction]) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("record_tax_run");
+        let mut tx = self.begin_ledger().await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO tax_runs (guild_id, percent, threshold, taxpayers, collected, ran_at)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6
+            WHERE NOT EXISTS (SELECT 1 FROM tax_runs WHERE guild_id = ?1 AND ran_at > ?7)
+            "#
+        )
+        .bind(&run.guild_id)
+        .bind(run.percent)
+        .bind(run.threshold)
+        .bind(run.taxpayers)
+        .bind(run.collected)
+        .bind(run.ran_at)
+        .bind(last_run_at.unwrap_or(i64::MIN))
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        for tax in taxes {
+            Self::write_transaction(&mut tx, tax).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Leaderboard seasons
+    pub async fn get_current_season(&self) -> Result<Option<Season>, DatabaseError> {
+        let _timer = metrics::query_timer("get_current_season");
+        let row = sqlx::query("SELECT id, name, started_at, ended_at FROM seasons WHERE ended_at IS NULL")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| Self::season_from_row(&row)))
+    }
+
+    pub async fn get_season_by_name(&self, name: &str) -> Result<Option<Season>, DatabaseError> {
+        let _timer = metrics::query_timer("get_season_by_name");
+        let row = sqlx::query("SELECT id, name, started_at, ended_at FROM seasons WHERE name = ? COLLATE NOCASE")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| Self::season_from_row(&row)))
+    }
+
+    // Every season, newest first
+    pub async fn get_seasons(&self) -> Result<Vec<Season>, DatabaseError> {
+        let _timer = metrics::query_timer("get_seasons");
+        let rows = sqlx::query("SELECT id, name, started_at, ended_at FROM seasons ORDER BY started_at DESC, id DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(Self::season_from_row).collect())
+    }
+
+    fn season_from_row(row: &sqlx::sqlite::SqliteRow) -> Season {
+        Season {
+            id: row.get("id"),
+            name: row.get("name"),
+            started_at: row.get("started_at"),
+            ended_at: row.get("ended_at"),
+        }
+    }
+
+    // End the running season, archiving every registered user's balance, and start `name`.
+    // Before the first season everything so far is archived as a "Preseason". With a seed,
+    // wallet balances are then moved to it through the ledger; vault savings are left alone.
+    pub async fn start_season(&self, name: &str, started_by: &str, seed: Option<i64>) -> Result<SeasonChange, DatabaseError> {
+        let _timer = metrics::query_timer("start_season");
+        let mut tx = self.begin_ledger().await?;
+        let now = Utc::now().timestamp();
+
+        let running = sqlx::query("SELECT id FROM seasons WHERE ended_at IS NULL")
+            .fetch_optional(&mut *tx)
+            .await?;
+        let ended_id: i64 = match running {
+            Some(row) => row.get("id"),
+            None => sqlx::query(
+                r#"
+                INSERT INTO seasons (name, started_at, started_by)
+                VALUES ('Preseason', COALESCE((SELECT MIN(timestamp_unix) FROM transactions), ?1), ?2)
+                RETURNING id
+                "#
+            )
+            .bind(now)
+            .bind(started_by)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("id"),
+        };
+
+        let ended = sqlx::query("UPDATE seasons SET ended_at = ? WHERE id = ? RETURNING id, name, started_at, ended_at")
+            .bind(now)
+            .bind(ended_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let ended = Self::season_from_row(&ended);
+
+        let archived = sqlx::query(
+            r#"
+            INSERT INTO season_archive (season_id, discord_id, username, balance)
+            SELECT ?, u.discord_id, u.username, COALESCE(b.balance, 0)
+            FROM users u
+            LEFT JOIN balances b ON u.discord_id = b.discord_id
+            "#
+        )
+        .bind(ended.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i64;
+
+        let started = sqlx::query("INSERT INTO seasons (name, started_at, started_by) VALUES (?, ?, ?) RETURNING id, name, started_at, ended_at")
+            .bind(name)
+            .bind(now)
+            .bind(started_by)
+            .fetch_one(&mut *tx)
+            .await?;
+        let started = Self::season_from_row(&started);
+
+        let mut reset = 0;
+        if let Some(seed) = seed {
+            let rows = sqlx::query(
+                r#"
+                SELECT u.discord_id, COALESCE(b.balance, 0) as balance
+                FROM users u
+                LEFT JOIN balances b ON u.discord_id = b.discord_id
+                WHERE COALESCE(b.balance, 0) != ?
+                "#
+            )
+            .bind(seed)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let message = Some(format!("Reset for season {}", name));
+            for row in rows {
+                let discord_id: String = row.get("discord_id");
+                let balance: i64 = row.get("balance");
+                let adjustment = if balance > seed {
+                    Transaction::system(&discord_id, SYSTEM_ACCOUNT, balance - seed, "season_reset", message.clone())
+                } else {
+                    Transaction::system(SYSTEM_ACCOUNT, &discord_id, seed - balance, "season_reset", message.clone())
+                };
+                Self::write_transaction(&mut tx, &adjustment).await?;
+                reset += 1;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(SeasonChange { ended, started, archived, reset })
+    }
+
+    // One page of a season's final standings as (username, balance), leaving out users now hidden from the leaderboard
+    pub async fn get_season_standings(&self, season_id: i64, limit: u32, offset: u32) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_season_standings");
+        let rows = sqlx::query(
+            r#"
+            SELECT a.username, a.balance
+            FROM season_archive a
+            LEFT JOIN user_preferences p ON a.discord_id = p.discord_id
+            WHERE a.season_id = ? AND COALESCE(p.hide_from_leaderboard, 0) = 0
+            ORDER BY a.balance DESC, a.username
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(season_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("username"), row.get("balance"))).collect())
+    }
+
+    // (listed, total) users in a season's archive, as for count_leaderboard_users and count_users
+    pub async fn count_season_standings(&self, season_id: i64) -> Result<(i64, i64), DatabaseError> {
+        let _timer = metrics::query_timer("count_season_standings");
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(COALESCE(p.hide_from_leaderboard, 0) = 0), 0) as listed, COUNT(*) as total
+            FROM season_archive a
+            LEFT JOIN user_preferences p ON a.discord_id = p.discord_id
+            WHERE a.season_id = ?
+            "#
+        )
+        .bind(season_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((row.get("listed"), row.get("total")))
+    }
+
+    // Weekly digest posts; false if the week was already posted in the guild
+    pub async fn claim_digest(&self, guild_id: &str, week_start: i64, posted_at: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("claim_digest");
+        let result = sqlx::query("INSERT OR IGNORE INTO digest_posts (guild_id, week_start, posted_at) VALUES (?, ?, ?)")
+            .bind(guild_id)
+            .bind(week_start)
+            .bind(posted_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Account freezes
+    pub async fn is_frozen(&self, discord_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("is_frozen");
+        let row = sqlx::query("SELECT frozen FROM users WHERE discord_id = ?")
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some_and(|row| row.get::<bool, _>("frozen")))
+    }
+
+    // Freeze or unfreeze a registered user and log who did it.
+    // Returns false if the user isn't registered or is already in that state.
+    pub async fn set_frozen(&self, discord_id: &str, frozen: bool, entry: &AuditEntry) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("set_frozen");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("UPDATE users SET frozen = ? WHERE discord_id = ? AND frozen != ?")
+            .bind(frozen)
+            .bind(discord_id)
+            .bind(frozen)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::write_admin_audit(&mut tx, entry).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Admin audit log
+    async fn write_admin_audit(conn: &mut SqliteConnection, entry: &AuditEntry) -> Result<(), DatabaseError> {
+        sqlx::query(
+            r#"
+            INSERT INTO admin_audit (guild_id, actor, action, target, amount, reason, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&entry.guild_id)
+        .bind(&entry.actor)
+        .bind(&entry.action)
+        .bind(&entry.target)
+        .bind(entry.amount)
+        .bind(&entry.reason)
+        .bind(entry.created_at)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn log_admin_action(&self, entry: &AuditEntry) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("log_admin_action");
+        let mut conn = self.pool.acquire().await?;
+        Self::write_admin_audit(&mut conn, entry).await
+    }
+
+    fn audit_entry_from_row(row: &sqlx::sqlite::SqliteRow) -> AuditEntry {
+        AuditEntry {
+            id: row.get("id"),
+            guild_id: row.get("guild_id"),
+            actor: row.get("actor"),
+            action: row.get("action"),
+            target: row.get("target"),
+            amount: row.get("amount"),
+            reason: row.get("reason"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    // A guild's admin actions, newest first, optionally only those taken by or against `user`
+    pub async fn get_admin_audit(&self, guild_id: &str, user: Option<&str>, limit: u32, offset: u32) -> Result<Vec<AuditEntry>, DatabaseError> {
+        let _timer = metrics::query_timer("get_admin_audit");
+        let rows = sqlx::query(
+            r#"
+            SELECT id, guild_id, actor, action, target, amount, reason, created_at
+            FROM admin_audit
+            WHERE guild_id = ?1 AND (?2 IS NULL OR actor = ?2 OR target = ?2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?3 OFFSET ?4
+            "#
+        )
+        .bind(guild_id)
+        .bind(user)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::audit_entry_from_row).collect())
+    }
+
+    pub async fn count_admin_audit(&self, guild_id: &str, user: Option<&str>) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("count_admin_audit");
+        let row = sqlx::query(
+            "SELECT COUNT(*) as count FROM admin_audit WHERE guild_id = ?1 AND (?2 IS NULL OR actor = ?2 OR target = ?2)"
+        )
+        .bind(guild_id)
+        .bind(user)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("count"))
+    }
+
+    // Reversals
+    // The compensating entry that reversed a transaction, if it has been reversed
+    pub async fn get_reversal(&self, transaction_id: &str) -> Result<Option<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_reversal");
+        let row = sqlx::query("SELECT reversal_id FROM reversals WHERE transaction_id = ?")
+            .bind(transaction_id)
+            .fetch_optional(&self.pool)
+            .await?;
 
-#[derive(Debug, Clone)]
-pub struct Balance {
-    pub discord_id: String,
-    pub balance: i64,
-    pub last_updated: DateTime<Utc>,
-}
+        Ok(row.map(|row| row.get("reversal_id")))
+    }
 
-#[derive(Debug, Clone)]
-pub struct Database {
-    pool: SqlitePool,
-}
+    // Write the compensating entry for a transaction and log who reversed it.
+    // Returns false if the transaction was already reversed.
+    pub async fn reverse_transaction(&self, transaction_id: &str, reversal: &Transaction, entry: &AuditEntry) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("reverse_transaction");
+        let mut tx = self.begin_ledger().await?;
 
-impl Database {
-    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        // Ensure the database directory exists
-        if let Some(parent) = Path::new(database_url).parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| sqlx::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO reversals (transaction_id, reversal_id, reversed_by, created_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(transaction_id)
+        .bind(&reversal.id)
+        .bind(&entry.actor)
+        .bind(entry.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
         }
 
-        let pool = SqlitePool::connect(database_url).await?;
-        
-        // Create tables if they don't exist
-        Self::create_tables(&pool).await?;
-        
-        info!("Database connected and migrations applied");
-        
-        Ok(Database { pool })
+        Self::write_transaction(&mut tx, reversal).await?;
+        Self::write_admin_audit(&mut tx, entry).await?;
+        tx.commit().await?;
+        Ok(true)
     }
 
-    async fn create_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-        // Create users table
-        sqlx::query(
+    // Transfer limits
+    // Coins a user has sent to other users by /send, payment requests and /rain since `since`, fees excluded
+    pub async fn get_outgoing_transfer_total(&self, discord_id: &str, since: i64) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_outgoing_transfer_total");
+        let row = sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS users (
-                discord_id TEXT PRIMARY KEY,
-                username TEXT NOT NULL,
-                public_key TEXT NOT NULL,
-                encrypted_private_key TEXT NOT NULL,
-                nonce INTEGER NOT NULL DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
+            SELECT COALESCE(SUM(amount), 0) as total
+            FROM transactions
+            WHERE from_user = ? AND timestamp_unix >= ? AND transaction_type IN ('transfer', 'rain')
             "#
         )
-        .execute(pool)
+        .bind(discord_id)
+        .bind(since)
+        .fetch_one(&self.pool)
         .await?;
 
-        // Create transactions table
-        sqlx::query(
+        Ok(row.get("total"))
+    }
+
+    // Everything a user has staked on games, duels, rock-paper-scissors, heists, bets, lottery tickets and paper trades since `since`
+    pub async fn get_wagered_total(&self, discord_id: &str, since: i64) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_wagered_total");
+        let row = sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS transactions (
-                id TEXT PRIMARY KEY,
-                from_user TEXT NOT NULL,
-                to_user TEXT NOT NULL,
-                amount INTEGER NOT NULL,
-                transaction_type TEXT NOT NULL DEFAULT 'transfer',
-                message TEXT,
-                nonce INTEGER NOT NULL,
-                signature TEXT NOT NULL,
-                timestamp_unix INTEGER NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
+            SELECT COALESCE(SUM(amount), 0) as total
+            FROM transactions
+            WHERE from_user = ? AND timestamp_unix >= ?
+              AND transaction_type IN ('gamble', 'duel_stake', 'rps_stake', 'heist_stake', 'bet_stake', 'lottery_ticket', 'paper_stake')
             "#
         )
-        .execute(pool)
+        .bind(discord_id)
+        .bind(since)
+        .fetch_one(&self.pool)
         .await?;
 
-        // Create balances table
+        Ok(row.get("total"))
+    }
+
+    pub async fn get_transfer_limit_override(&self, guild_id: &str, discord_id: &str) -> Result<Option<i64>, DatabaseError> {
+        let _timer = metrics::query_timer("get_transfer_limit_override");
+        let row = sqlx::query("SELECT daily_limit FROM transfer_limit_overrides WHERE guild_id = ? AND discord_id = ?")
+            .bind(guild_id)
+            .bind(discord_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("daily_limit")))
+    }
+
+    pub async fn set_transfer_limit_override(
+        &self,
+        guild_id: &str,
+        discord_id: &str,
+        daily_limit: i64,
+        set_by: &str,
+    ) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("set_transfer_limit_override");
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS balances (
-                discord_id TEXT PRIMARY KEY,
-                balance INTEGER NOT NULL DEFAULT 0,
-                last_updated DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
+            INSERT INTO transfer_limit_overrides (guild_id, discord_id, daily_limit, set_by, set_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(guild_id, discord_id)
+            DO UPDATE SET daily_limit = excluded.daily_limit, set_by = excluded.set_by, set_at = excluded.set_at
             "#
         )
-        .execute(pool)
+        .bind(guild_id)
+        .bind(discord_id)
+        .bind(daily_limit)
+        .bind(set_by)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
         .await?;
 
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_from_user ON transactions(from_user)")
-            .execute(pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_to_user ON transactions(to_user)")
-            .execute(pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_timestamp ON transactions(timestamp_unix)")
-            .execute(pool)
+        Ok(())
+    }
+
+    pub async fn remove_transfer_limit_override(&self, guild_id: &str, discord_id: &str) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_transfer_limit_override");
+        let result = sqlx::query("DELETE FROM transfer_limit_overrides WHERE guild_id = ? AND discord_id = ?")
+            .bind(guild_id)
+            .bind(discord_id)
+            .execute(&self.pool)
             .await?;
 
-        info!("Database tables created successfully");
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 
-    // User management
-    pub async fn create_user(&self, user: &User) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "INSERT INTO users (discord_id, username, public_key, encrypted_private_key, nonce) VALUES (?, ?, ?, ?, ?)"
+    // Ledger checkpoints
+    pub async fn record_checkpoint(&self, checkpoint: &Checkpoint) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("record_checkpoint");
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ledger_checkpoints (transaction_count, last_hash, total_supply, signature, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#
         )
-        .bind(&user.discord_id)
-        .bind(&user.username)
-        .bind(&user.public_key)
-        .bind(&user.encrypted_private_key)
-        .bind(user.nonce)
+        .bind(checkpoint.transaction_count)
+        .bind(&checkpoint.last_hash)
+        .bind(checkpoint.total_supply)
+        .bind(&checkpoint.signature)
+        .bind(checkpoint.created_at)
         .execute(&self.pool)
         .await?;
 
-        // Initialize balance
-        sqlx::query("INSERT INTO balances (discord_id, balance) VALUES (?, 0)")
-            .bind(&user.discord_id)
-            .execute(&self.pool)
+        Ok(result.last_insert_rowid())
+    }
+
+    const CHECKPOINT_COLUMNS: &'static str = "id, transaction_count, last_hash, total_supply, signature, created_at";
+
+    fn checkpoint_from_row(row: &sqlx::sqlite::SqliteRow) -> Checkpoint {
+        Checkpoint {
+            id: row.get("id"),
+            transaction_count: row.get("transaction_count"),
+            last_hash: row.get("last_hash"),
+            total_supply: row.get("total_supply"),
+            signature: row.get("signature"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    pub async fn get_checkpoints(&self) -> Result<Vec<Checkpoint>, DatabaseError> {
+        let _timer = metrics::query_timer("get_checkpoints");
+        let rows = sqlx::query(&format!("SELECT {} FROM ledger_checkpoints ORDER BY id ASC", Self::CHECKPOINT_COLUMNS))
+            .fetch_all(&self.pool)
             .await?;
 
-        Ok(())
+        Ok(rows.iter().map(Self::checkpoint_from_row).collect())
     }
 
-    pub async fn get_user(&self, discord_id: &str) -> Result<Option<User>, sqlx::Error> {
-        let row = sqlx::query(
-            "SELECT discord_id, username, public_key, encrypted_private_key, nonce, created_at, updated_at FROM users WHERE discord_id = ?"
-        )
-        .bind(discord_id)
+    pub async fn get_latest_checkpoint(&self) -> Result<Option<Checkpoint>, DatabaseError> {
+        let _timer = metrics::query_timer("get_latest_checkpoint");
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM ledger_checkpoints ORDER BY id DESC LIMIT 1",
+            Self::CHECKPOINT_COLUMNS
+        ))
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            Ok(Some(User {
-                discord_id: row.get("discord_id"),
-                username: row.get("username"),
-                public_key: row.get("public_key"),
-                encrypted_private_key: row.get("encrypted_private_key"),
-                nonce: row.get("nonce"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(row.as_ref().map(Self::checkpoint_from_row))
     }
 
-    pub async fn update_user_nonce(&self, discord_id: &str, nonce: i64) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE users SET nonce = ? WHERE discord_id = ?")
-            .bind(nonce)
-            .bind(discord_id)
-            .execute(&self.pool)
+    // Outgoing webhooks
+    pub async fn add_webhook(
+        &self,
+        guild_id: &str,
+        url: &str,
+        events: &[&str],
+        min_amount: i64,
+        created_by: &str,
+    ) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("add_webhook");
+        let result = sqlx::query(
+            "INSERT INTO webhooks (guild_id, url, events, min_amount, created_by, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(guild_id)
+        .bind(url)
+        .bind(events.join(","))
+        .bind(min_amount)
+        .bind(created_by)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    // Remove a webhook along with anything still queued for it
+    pub async fn remove_webhook(&self, guild_id: &str, webhook_id: i64) -> Result<bool, DatabaseError> {
+        let _timer = metrics::query_timer("remove_webhook");
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM webhooks WHERE guild_id = ? AND id = ?")
+            .bind(guild_id)
+            .bind(webhook_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?")
+            .bind(webhook_id)
+            .execute(&mut *tx)
             .await?;
 
-        Ok(())
+        tx.commit().await?;
+        Ok(true)
     }
 
-    // Transaction management
-    pub async fn add_transaction(&self, transaction: &Transaction) -> Result<(), sqlx::Error> {
-        sqlx::query(
+    pub async fn get_webhooks(&self, guild_id: &str) -> Result<Vec<Webhook>, DatabaseError> {
+        let _timer = metrics::query_timer("get_webhooks");
+        let rows = sqlx::query(
             r#"
-            INSERT INTO transactions 
-            (id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            SELECT w.id, w.url, w.events, w.min_amount,
+                   COUNT(CASE WHEN d.failed = 0 THEN 1 END) as pending,
+                   COUNT(CASE WHEN d.failed = 1 THEN 1 END) as failed
+            FROM webhooks w
+            LEFT JOIN webhook_deliveries d ON d.webhook_id = w.id
+            WHERE w.guild_id = ?
+            GROUP BY w.id
+            ORDER BY w.id ASC
             "#
         )
-        .bind(&transaction.id)
-        .bind(&transaction.from_user)
-        .bind(&transaction.to_user)
-        .bind(transaction.amount)
-        .bind(&transaction.transaction_type)
-        .bind(&transaction.message)
-        .bind(transaction.nonce)
-        .bind(&transaction.signature)
-        .bind(transaction.timestamp_unix)
-        .execute(&self.pool)
+        .bind(guild_id)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(rows.iter().map(|row| Webhook {
+            id: row.get("id"),
+            url: row.get("url"),
+            events: row.get::<String, _>("events").split(',').map(str::to_string).collect(),
+            min_amount: row.get("min_amount"),
+            pending: row.get("pending"),
+            failed: row.get("failed"),
+        }).collect())
+    }
+
+    // Ledger entries after `chain_seq`, oldest first, with their chain_seq
+    pub async fn get_transactions_after_seq(&self, chain_seq: i64, limit: u32) -> Result<Vec<(i64, Transaction)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_transactions_after_seq");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transactions WHERE chain_seq > ? ORDER BY chain_seq ASC LIMIT ?",
+            Self::CHAIN_COLUMNS
+        ))
+        .bind(chain_seq)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("chain_seq"), Self::transaction_from_row(row))).collect())
     }
 
-    pub async fn get_user_transactions(&self, discord_id: &str) -> Result<Vec<Transaction>, sqlx::Error> {
+    // Everyone who has received a ledger entry of this type
+    pub async fn get_recipients_of_type(&self, transaction_type: &str) -> Result<Vec<String>, DatabaseError> {
+        let _timer = metrics::query_timer("get_recipients_of_type");
+        let rows = sqlx::query("SELECT DISTINCT to_user FROM transactions WHERE transaction_type = ?")
+            .bind(transaction_type)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("to_user")).collect())
+    }
+
+    pub async fn get_latest_chain_seq(&self) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_latest_chain_seq");
+        let row = sqlx::query("SELECT COALESCE(MAX(chain_seq), 0) as chain_seq FROM transactions")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("chain_seq"))
+    }
+
+    // Finished auctions after history id `after_id`, oldest first: (id, guild_id, record)
+    pub async fn get_auctions_after(&self, after_id: i64, limit: u32) -> Result<Vec<(i64, String, AuctionRecord)>, DatabaseError> {
+        let _timer = metrics::query_timer("get_auctions_after");
         let rows = sqlx::query(
             r#"
-            SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at
-            FROM transactions 
-            WHERE from_user = ? OR to_user = ? 
-            ORDER BY timestamp_unix DESC
+            SELECT id, guild_id, item, outcome, winner_id, amount, bid_count, ended_at
+            FROM auction_history
+            WHERE id > ?
+            ORDER BY id ASC
+            LIMIT ?
             "#
         )
-        .bind(discord_id)
-        .bind(discord_id)
+        .bind(after_id)
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        let mut transactions = Vec::new();
-        for row in rows {
-            transactions.push(Transaction {
-                id: row.get("id"),
-                from_user: row.get("from_user"),
-                to_user: row.get("to_user"),
+        Ok(rows.iter().map(|row| (
+            row.get("id"),
+            row.get("guild_id"),
+            AuctionRecord {
+                item: row.get("item"),
+                outcome: row.get("outcome"),
+                winner_id: row.get("winner_id"),
                 amount: row.get("amount"),
-                transaction_type: row.get("transaction_type"),
-                message: row.get("message"),
-                nonce: row.get("nonce"),
-                signature: row.get("signature"),
-                timestamp_unix: row.get("timestamp_unix"),
-                created_at: row.get("created_at"),
-            });
+                bid_count: row.get("bid_count"),
+                ended_at: row.get("ended_at"),
+            },
+        )).collect())
+    }
+
+    pub async fn get_latest_auction_id(&self) -> Result<i64, DatabaseError> {
+        let _timer = metrics::query_timer("get_latest_auction_id");
+        let row = sqlx::query("SELECT COALESCE(MAX(id), 0) as id FROM auction_history")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get("id"))
+    }
+
+    /// Queue each event for every matching webhook and move `cursor_key` past them in the same
+    /// transaction, so a crash between the two can neither drop nor repeat events
+    pub async fn queue_webhook_events(&self, events: &[WebhookEvent], cursor_key: &str, cursor: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("queue_webhook_events");
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now().timestamp();
+
+        for event in events {
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_deliveries (webhook_id, event, payload, next_attempt_at, created_at)
+                SELECT id, ?, ?, ?, ?
+                FROM webhooks
+                WHERE (',' || events || ',') LIKE ('%,' || ? || ',%')
+                  AND (? != 'large_transaction' OR ? >= min_amount)
+                  AND (? IS NULL OR guild_id = ?)
+                "#
+            )
+            .bind(&event.event)
+            .bind(&event.payload)
+            .bind(now)
+            .bind(now)
+            .bind(&event.event)
+            .bind(&event.event)
+            .bind(event.amount)
+            .bind(&event.guild_id)
+            .bind(&event.guild_id)
+            .execute(&mut *tx)
+            .await?;
         }
 
-        Ok(transactions)
+        sqlx::query("INSERT INTO system_config (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
+            .bind(cursor_key)
+            .bind(cursor.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
     }
 
-    pub async fn get_all_transactions(&self) -> Result<Vec<Transaction>, sqlx::Error> {
+    pub async fn get_due_webhook_deliveries(&self, now: i64, limit: u32) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        let _timer = metrics::query_timer("get_due_webhook_deliveries");
         let rows = sqlx::query(
-            "SELECT id, from_user, to_user, amount, transaction_type, message, nonce, signature, timestamp_unix, created_at FROM transactions ORDER BY timestamp_unix ASC"
+            r#"
+            SELECT d.id, d.webhook_id, w.url, d.event, d.payload, d.attempts
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.failed = 0 AND d.next_attempt_at <= ?
+            ORDER BY d.id ASC
+            LIMIT ?
+            "#
         )
+        .bind(now)
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        let mut transactions = Vec::new();
-        for row in rows {
-            transactions.push(Transaction {
-                id: row.get("id"),
-                from_user: row.get("from_user"),
-                to_user: row.get("to_user"),
-                amount: row.get("amount"),
-                transaction_type: row.get("transaction_type"),
-                message: row.get("message"),
-                nonce: row.get("nonce"),
-                signature: row.get("signature"),
-                timestamp_unix: row.get("timestamp_unix"),
-                created_at: row.get("created_at"),
-            });
-        }
-
-        Ok(transactions)
+        Ok(rows.iter().map(|row| WebhookDelivery {
+            id: row.get("id"),
+            webhook_id: row.get("webhook_id"),
+            url: row.get("url"),
+            event: row.get("event"),
+            payload: row.get("payload"),
+            attempts: row.get("attempts"),
+        }).collect())
     }
 
-    // Balance management
-    pub async fn get_balance(&self, discord_id: &str) -> Result<i64, sqlx::Error> {
-        let row = sqlx::query("SELECT balance FROM balances WHERE discord_id = ?")
-            .bind(discord_id)
-            .fetch_optional(&self.pool)
+    pub async fn complete_webhook_delivery(&self, delivery_id: i64) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("complete_webhook_delivery");
+        sqlx::query("DELETE FROM webhook_deliveries WHERE id = ?")
+            .bind(delivery_id)
+            .execute(&self.pool)
             .await?;
 
-        Ok(row.map(|r| r.get("balance")).unwrap_or(0))
+        Ok(())
     }
 
-    pub async fn update_balance(&self, discord_id: &str, new_balance: i64) -> Result<(), sqlx::Error> {
+    // Record a failed attempt; with no next attempt the delivery is given up on
+    pub async fn fail_webhook_delivery(&self, delivery_id: i64, error: &str, next_attempt_at: Option<i64>) -> Result<(), DatabaseError> {
+        let _timer = metrics::query_timer("fail_webhook_delivery");
         sqlx::query(
             r#"
-            INSERT INTO balances (discord_id, balance) 
-            VALUES (?, ?)
-            ON CONFLICT(discord_id) 
-            DO UPDATE SET balance = ?, last_updated = CURRENT_TIMESTAMP
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1, last_error = ?, next_attempt_at = COALESCE(?, next_attempt_at), failed = ?
+            WHERE id = ?
             "#
         )
-        .bind(discord_id)
-        .bind(new_balance)
-        .bind(new_balance)
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(next_attempt_at.is_none())
+        .bind(delivery_id)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    // Utility functions
-    pub async fn calculate_balance_from_transactions(&self, discord_id: &str) -> Result<i64, sqlx::Error> {
-        let row = sqlx::query(
+    // Put deliveries that ran out of retries back in the queue
+    pub async fn retry_failed_webhook_deliveries(&self, guild_id: &str, webhook_id: i64) -> Result<u64, DatabaseError> {
+        let _timer = metrics::query_timer("retry_failed_webhook_deliveries");
+        let result = sqlx::query(
             r#"
-            SELECT 
-                COALESCE(SUM(CASE WHEN to_user = ? THEN amount ELSE 0 END), 0) -
-                COALESCE(SUM(CASE WHEN from_user = ? THEN amount ELSE 0 END), 0) as balance
-            FROM transactions
-            WHERE from_user = ? OR to_user = ?
+            UPDATE webhook_deliveries
+            SET failed = 0, attempts = 0, next_attempt_at = ?
+            WHERE failed = 1 AND webhook_id = (SELECT id FROM webhooks WHERE guild_id = ? AND id = ?)
             "#
         )
-        .bind(discord_id)
-        .bind(discord_id)
-        .bind(discord_id)
-        .bind(discord_id)
-        .fetch_one(&self.pool)
+        .bind(Utc::now().timestamp())
+        .bind(guild_id)
+        .bind(webhook_id)
+        .execute(&self.pool)
         .await?;
 
-        Ok(row.get("balance"))
+        Ok(result.rows_affected())
     }
+}
 
-    pub async fn verify_and_update_balances(&self) -> Result<(), sqlx::Error> {
-        info!("Verifying and updating all balances from transaction ledger");
-        
-        let rows = sqlx::query("SELECT discord_id FROM users")
-            .fetch_all(&self.pool)
-            .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
 
-        for row in rows {
-            let discord_id: String = row.get("discord_id");
-            let calculated_balance = self.calculate_balance_from_transactions(&discord_id).await?;
-            self.update_balance(&discord_id, calculated_balance).await?;
+    // A user-signed entry as far as the database can tell: the nonce trigger skips system entries
+    fn signed(from_user: &str, to_user: &str, amount: i64, nonce: i64) -> Transaction {
+        let mut transaction = Transaction::system(from_user, to_user, amount, "transfer", None);
+        transaction.nonce = nonce;
+        transaction.signature = "signed".to_string();
+        transaction
+    }
+
+    #[tokio::test]
+    async fn overdraft_is_insufficient_funds() {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 50).await.unwrap();
+
+        let overdraft = Transaction::system("alice", "bob", 80, "transfer", None);
+        match database.apply_transaction(&overdraft).await {
+            Err(DatabaseError::InsufficientFunds(overdraft)) => {
+                assert_eq!(overdraft, InsufficientFunds { discord_id: "alice".to_string(), balance: 50, required: 80 });
+            }
+            other => panic!("expected InsufficientFunds, got {:?}", other),
+        }
+        assert_eq!(database.get_balance("alice").await.unwrap(), 50);
+        assert_eq!(database.get_balance("bob").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn unknown_user_is_not_registered() {
+        let database = fixtures::database().await.unwrap();
+
+        match database.require_user("nobody").await {
+            Err(DatabaseError::NotRegistered(discord_id)) => assert_eq!(discord_id, "nobody"),
+            other => panic!("expected NotRegistered, got {:?}", other),
         }
+    }
 
-        info!("Balance verification complete");
-        Ok(())
+    #[tokio::test]
+    async fn reused_or_out_of_order_nonce_is_conflict() {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 100).await.unwrap();
+        fixtures::user(&database, &crypto, "bob", 0).await.unwrap();
+
+        database.apply_transactions(&[signed("alice", "bob", 10, 1)]).await.unwrap();
+
+        // Replaying nonce 1, and skipping ahead to nonce 3, are both refused
+        for nonce in [1, 3] {
+            match database.apply_transactions(&[signed("alice", "bob", 10, nonce)]).await {
+                Err(DatabaseError::Conflict(_)) => {}
+                other => panic!("expected Conflict for nonce {}, got {:?}", nonce, other),
+            }
+        }
+
+        // A conflict anywhere in a batch leaves the entries before it unwritten too
+        let batch = [signed("alice", "bob", 10, 2), signed("alice", "bob", 10, 2)];
+        assert!(matches!(database.apply_transactions(&batch).await, Err(DatabaseError::Conflict(_))));
+        assert_eq!(database.get_balance("alice").await.unwrap(), 90);
+        assert_eq!(database.get_user("alice").await.unwrap().unwrap().nonce, 1);
     }
 
-    // Get all users with their balances for leaderboard
-    pub async fn get_all_users_with_balances(&self, limit: Option<u32>) -> Result<Vec<(String, i64)>, sqlx::Error> {
-        let query = match limit {
-            Some(limit_val) => format!(
-                r#"
-                SELECT u.username, COALESCE(b.balance, 0) as balance
-                FROM users u
-                LEFT JOIN balances b ON u.discord_id = b.discord_id
-                ORDER BY COALESCE(b.balance, 0) DESC
-                LIMIT {}
-                "#,
-                limit_val
-            ),
-            None => r#"
-                SELECT u.username, COALESCE(b.balance, 0) as balance
-                FROM users u
-                LEFT JOIN balances b ON u.discord_id = b.discord_id
-                ORDER BY COALESCE(b.balance, 0) DESC
-                "#.to_string(),
-        };
+    #[tokio::test]
+    async fn unchained_transactions_are_linked_onto_the_chain() {
+        let database = fixtures::database().await.unwrap();
+        for (account, amount) in [("alice", 10), ("bob", 20), ("carol", 30)] {
+            fixtures::fund(&database, account, amount).await.unwrap();
+        }
+        let (_, head) = crate::ledger::verify_chain(&database.get_hash_chain().await.unwrap());
 
-        let rows = sqlx::query(&query)
-            .fetch_all(&self.pool)
-            .await?;
+        // As if the last two were written before the hash chain existed
+        sqlx::query("UPDATE transactions SET chain_seq = NULL, prev_hash = NULL WHERE chain_seq > 1")
+            .execute(&database.pool)
+            .await
+            .unwrap();
+        database.link_unchained_transactions().await.unwrap();
 
-        let mut users_with_balances = Vec::new();
-        for row in rows {
-            let username: String = row.get("username");
-            let balance: i64 = row.get("balance");
-            users_with_balances.push((username, balance));
+        // They go back on in the order they were inserted, giving the same chain as before
+        let chain = database.get_hash_chain().await.unwrap();
+        let amounts: Vec<i64> = chain.iter().map(|(transaction, _)| transaction.amount).collect();
+        assert_eq!(amounts, vec![10, 20, 30]);
+        let (broken, relinked_head) = crate::ledger::verify_chain(&chain);
+        assert!(broken.is_empty());
+        assert_eq!(relinked_head, head);
+
+        // Linking again with nothing unchained changes nothing
+        database.link_unchained_transactions().await.unwrap();
+        assert_eq!(crate::ledger::verify_chain(&database.get_hash_chain().await.unwrap()).1, head);
+    }
+
+    #[tokio::test]
+    async fn auction_deposits_outlive_a_restart() {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 100).await.unwrap();
+        fixtures::user(&database, &crypto, "bob", 100).await.unwrap();
+
+        for bidder in ["alice", "bob"] {
+            let hold = Transaction::system(bidder, crate::auction::AUCTION_ESCROW_ACCOUNT, 10, "auction_deposit", None);
+            database.hold_auction_deposit("voice", &hold).await.unwrap();
         }
+        // A rejected bid only gives back that bidder's deposit
+        let refund = Transaction::system(crate::auction::AUCTION_ESCROW_ACCOUNT, "bob", 10, "auction_refund", None);
+        database.release_auction_deposits("voice", Some("bob"), &[refund]).await.unwrap();
+        assert_eq!(database.get_auction_deposits().await.unwrap().len(), 1);
 
-        Ok(users_with_balances)
+        assert_eq!(crate::auction::refund_stranded_deposits(&database).await.unwrap(), 1);
+        assert!(database.get_auction_deposits().await.unwrap().is_empty());
+        assert_eq!(database.get_balance("alice").await.unwrap(), 100);
+        assert_eq!(database.get_balance("bob").await.unwrap(), 100);
+        assert_eq!(database.get_balance(crate::auction::AUCTION_ESCROW_ACCOUNT).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn unregister_is_blocked_by_open_holdings() {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 100).await.unwrap();
+        assert_eq!(database.get_unregister_blocker("alice").await.unwrap(), None);
+
+        let hold = Transaction::system("alice", crate::auction::AUCTION_ESCROW_ACCOUNT, 10, "auction_deposit", None);
+        database.hold_auction_deposit("voice", &hold).await.unwrap();
+        assert_eq!(database.get_unregister_blocker("alice").await.unwrap(), Some("a deposit in a running auction"));
     }
 }
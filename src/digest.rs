@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::database::{self, Database, DatabaseError, WEEK_SECONDS};
+use crate::health::TaskMonitor;
+
+const DIGEST_TICK_SECONDS: u64 = 3600;
+// Accounts listed under top earners and biggest spenders
+const DIGEST_TOP_ACCOUNTS: i64 = 5;
+
+fn ranked(accounts: &[(String, i64)]) -> String {
+    if accounts.is_empty() {
+        return "Nobody".to_string();
+    }
+    accounts
+        .iter()
+        .enumerate()
+        .map(|(rank, (discord_id, amount))| format!("{}. <@{}> · {}", rank + 1, discord_id, amount))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// "+12%" against the week before, or nothing when there's no week to compare with
+fn trend(current: i64, previous: i64) -> String {
+    if previous <= 0 {
+        return String::new();
+    }
+    let percent = (current - previous) as f64 / previous as f64 * 100.0;
+    format!(" ({:+.0}% vs previous week)", percent)
+}
+
+/// Summarise the week starting at `week_start` (see `database::week_start`) for a guild
+pub async fn build_embed(database: &Database, guild_id: &str, week_start: i64) -> Result<serenity::CreateEmbed, DatabaseError> {
+    let week_end = week_start + WEEK_SECONDS;
+    let weeks = database.get_weekly_stats(week_start - WEEK_SECONDS).await?;
+    let stats_for = |start: i64| weeks.iter().find(|week| week.week_start == start).cloned().unwrap_or_default();
+    let week = stats_for(week_start);
+    let previous = stats_for(week_start - WEEK_SECONDS);
+
+    let earners = database.get_top_earners(week_start, week_end, DIGEST_TOP_ACCOUNTS).await?;
+    let spenders = database.get_top_spenders(week_start, week_end, DIGEST_TOP_ACCOUNTS).await?;
+    let auctions = database.get_auction_summary(guild_id, week_start, week_end).await?;
+    let circulating = database.get_economy_totals().await?.circulating;
+
+    let embed = serenity::CreateEmbed::new()
+        .title("📰 Weekly Economy Digest")
+        .description(format!("<t:{}:D> to <t:{}:D>", week_start, week_end - 1))
+        .field(
+            "Volume",
+            format!("{} Slumcoins over {} transactions{}", week.volume, week.transactions, trend(week.volume, previous.volume)),
+            false,
+        )
+        .field(
+            "Supply",
+            format!("{:+} this week ({} minted, {} burned)\n{} in circulation", week.supply_change(), week.minted, week.burned, circulating),
+            false,
+        )
+        .field(
+            "Auctions",
+            format!("{} held, {} sold for {} Slumcoins", auctions.held, auctions.sold, auctions.raised),
+            false,
+        )
+        .field("Top earners", ranked(&earners), true)
+        .field("Biggest spenders", ranked(&spenders), true)
+        .timestamp(serenity::Timestamp::now())
+        .color(0x3498db);
+
+    Ok(embed)
+}
+
+async fn post(http: &serenity::Http, database: &Database, guild_id: &str, channel_id: u64) -> Result<(), DatabaseError> {
+    let now = Utc::now().timestamp();
+    // The last full week; the current one is still going
+    let week_start = database::week_start(now) - WEEK_SECONDS;
+    if !database.claim_digest(guild_id, week_start, now).await? {
+        return Ok(());
+    }
+
+    let embed = build_embed(database, guild_id, week_start).await?;
+    let message = serenity::CreateMessage::new()
+        .embed(embed)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    match serenity::ChannelId::new(channel_id).send_message(http, message).await {
+        Ok(_) => info!("Posted weekly digest to guild {}", guild_id),
+        Err(e) => error!("Failed to post weekly digest to guild {}: {}", guild_id, e),
+    }
+
+    Ok(())
+}
+
+/// Post last week's economy digest in every guild with a digest channel, once per week
+pub fn spawn_poster(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(DIGEST_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("digest", Duration::from_secs(DIGEST_TICK_SECONDS));
+
+            let guilds = match database.get_guild_settings_for_key("digest.channel_id").await {
+                Ok(guilds) => guilds,
+                Err(e) => {
+                    error!("Failed to load digest channels: {}", e);
+                    continue;
+                }
+            };
+
+            for (guild_id, channel_id) in guilds {
+                let Ok(channel_id) = channel_id.parse::<u64>() else {
+                    continue;
+                };
+
+                if let Err(e) = post(&http, &database, &guild_id, channel_id).await {
+                    error!("Failed to build weekly digest for guild {}: {}", guild_id, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,47 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::{config, Context};
+
+// Longest error text shown in a report; the full error is still in the host logs
+const ERROR_DISPLAY_LIMIT: usize = 1000;
+
+/// Post a command or framework error to the guild's `errors.channel_id`, if one is configured.
+/// Nothing is reported for DMs, and a failed post is only logged.
+pub async fn report(ctx: Context<'_>, title: &str, error: &str) {
+    let Some(guild_id) = ctx.guild_id() else {
+        return;
+    };
+
+    let channel_id = match config::get(&ctx.data().database, &guild_id.to_string(), "errors.channel_id").await {
+        Ok(channel_id) => channel_id,
+        Err(e) => {
+            error!("Error loading error log channel for guild {}: {}", guild_id, e);
+            return;
+        }
+    };
+    let Ok(channel_id) = channel_id.parse::<u64>() else {
+        return;
+    };
+
+    let mut shown: String = error.chars().take(ERROR_DISPLAY_LIMIT).collect();
+    if shown.len() < error.len() {
+        shown.push('…');
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("⚠️ {}", title))
+        .description(format!("```{}```", shown.replace("```", "'''")))
+        .field("Command", format!("`/{}`", ctx.command().qualified_name), true)
+        .field("User", format!("<@{}>", ctx.author().id), true)
+        .field("Channel", format!("<#{}>", ctx.channel_id()), true)
+        .timestamp(serenity::Timestamp::now())
+        .color(0xe74c3c);
+    let message = serenity::CreateMessage::new()
+        .embed(embed)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+
+    if let Err(e) = serenity::ChannelId::new(channel_id).send_message(ctx, message).await {
+        error!("Failed to post error report to guild {}: {}", guild_id, e);
+    }
+}
@@ -0,0 +1,71 @@
+//! Escrowed deals: coins held in the `ESCROW` account until they're released, refunded or expire.
+
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::database::{Database, Escrow, Transaction};
+use crate::health::TaskMonitor;
+
+// Holds escrowed funds until a deal is released, refunded or arbitrated
+pub const ESCROW_ACCOUNT: &str = "ESCROW";
+
+// Lifecycle of an escrow, as stored in `escrows.status`
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_DISPUTED: &str = "disputed";
+pub const STATUS_RELEASED: &str = "released";
+pub const STATUS_REFUNDED: &str = "refunded";
+pub const STATUS_EXPIRED: &str = "expired";
+
+const EXPIRE_TICK_SECONDS: u64 = 300;
+
+/// Pay the escrowed amount to the seller
+pub fn release_payout(escrow: &Escrow) -> Transaction {
+    Transaction::system(
+        ESCROW_ACCOUNT,
+        &escrow.seller,
+        escrow.amount,
+        "escrow_release",
+        Some(format!("Escrow #{} released: {}", escrow.id, escrow.description)),
+    )
+}
+
+/// Return the escrowed amount to the buyer
+pub fn refund_payout(escrow: &Escrow) -> Transaction {
+    Transaction::system(
+        ESCROW_ACCOUNT,
+        &escrow.buyer,
+        escrow.amount,
+        "escrow_refund",
+        Some(format!("Escrow #{} refunded: {}", escrow.id, escrow.description)),
+    )
+}
+
+/// Refund open escrows that nobody released or disputed before they expired
+pub fn spawn_expirer(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(EXPIRE_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("escrow", Duration::from_secs(EXPIRE_TICK_SECONDS));
+
+            let expired = match database.get_expired_escrows(Utc::now().timestamp()).await {
+                Ok(expired) => expired,
+                Err(e) => {
+                    error!("Failed to load expired escrows: {}", e);
+                    continue;
+                }
+            };
+
+            for escrow in expired {
+                let refund = refund_payout(&escrow);
+                match database.transition_escrow(escrow.id, STATUS_OPEN, STATUS_EXPIRED, None, Some(&refund)).await {
+                    Ok(true) => info!("Refunded expired escrow {} in guild {}", escrow.id, escrow.guild_id),
+                    Ok(false) => {}
+                    Err(e) => error!("Failed to refund expired escrow {}: {}", escrow.id, e),
+                }
+            }
+        }
+    });
+}
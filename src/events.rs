@@ -0,0 +1,75 @@
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, DatabaseError, Event, Transaction};
+use crate::health::TaskMonitor;
+use crate::ledger::TREASURY_ACCOUNT;
+
+// Check-in opens a little before the start time and stays open for a while after
+pub const CHECKIN_OPENS_BEFORE_SECONDS: i64 = 30 * 60;
+pub const CHECKIN_CLOSES_AFTER_SECONDS: i64 = 3 * 60 * 60;
+
+const CLOSE_TICK_SECONDS: u64 = 60;
+
+pub fn checkin_open(event: &Event, now_unix: i64) -> bool {
+    now_unix >= event.starts_at - CHECKIN_OPENS_BEFORE_SECONDS
+        && now_unix < event.starts_at + CHECKIN_CLOSES_AFTER_SECONDS
+}
+
+async fn close_event(database: &Database, event: &Event) -> Result<(), DatabaseError> {
+    let attendees = database.get_event_checkins(event.id).await?;
+
+    let mut payouts = Vec::new();
+    if event.attendance_bonus > 0 && !attendees.is_empty() {
+        // Bonuses come from the treasury, skip them rather than overdraw it
+        let total = event.attendance_bonus * attendees.len() as i64;
+        if database.get_balance(TREASURY_ACCOUNT).await? >= total {
+            payouts = attendees
+                .iter()
+                .map(|discord_id| {
+                    Transaction::system(
+                        TREASURY_ACCOUNT,
+                        discord_id,
+                        event.attendance_bonus,
+                        "event_bonus",
+                        Some(format!("Attended {}", event.name)),
+                    )
+                })
+                .collect();
+        } else {
+            warn!("Treasury can't cover {} in attendance bonuses for event {}", total, event.id);
+        }
+    }
+
+    database.complete_event(event, &payouts).await?;
+    info!("Closed event {} for guild {} with {} attendees", event.id, event.guild_id, attendees.len());
+    Ok(())
+}
+
+/// Close events once their check-in window has passed and pay attendance bonuses
+pub fn spawn_closer(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(CLOSE_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("events", Duration::from_secs(CLOSE_TICK_SECONDS));
+
+            let cutoff = Utc::now().timestamp() - CHECKIN_CLOSES_AFTER_SECONDS;
+            let due = match database.get_due_events(cutoff).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due events: {}", e);
+                    continue;
+                }
+            };
+
+            for event in due {
+                if let Err(e) = close_event(&database, &event).await {
+                    error!("Failed to close event {}: {}", event.id, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,67 @@
+//! Throwaway databases and accounts, so the engine and the command logic built on it can be
+//! exercised without a Discord connection or a database file.
+//!
+//! ```
+//! use slumcoin::database::Transaction;
+//! use slumcoin::fixtures;
+//! use slumcoin::ledger::TREASURY_ACCOUNT;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let database = fixtures::database().await?;
+//! let crypto = fixtures::crypto(&database).await?;
+//! fixtures::user(&database, &crypto, "alice", 100).await?;
+//! fixtures::fund(&database, TREASURY_ACCOUNT, 1_000).await?;
+//!
+//! let wager = Transaction::system("alice", TREASURY_ACCOUNT, 25, "gamble", None);
+//! database.apply_transaction(&wager).await?;
+//! assert_eq!(database.get_balance("alice").await?, 75);
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::Utc;
+
+use crate::crypto::{CryptoError, CryptoManager};
+use crate::database::{Database, DatabaseError, Transaction, User, SYSTEM_ACCOUNT};
+
+/// Master password fixture keys are encrypted with
+pub const MASTER_PASSWORD: &str = "fixture master password";
+
+/// A fresh in-memory database with every migration applied
+pub async fn database() -> Result<Database, DatabaseError> {
+    Database::in_memory().await
+}
+
+/// Key manager for `database`, storing its salt there like a first start does
+pub async fn crypto(database: &Database) -> Result<CryptoManager, CryptoError> {
+    CryptoManager::load(MASTER_PASSWORD, database).await
+}
+
+/// Register `discord_id` with a new keypair, as `/register` would, and mint it `balance` coins
+pub async fn user(database: &Database, crypto: &CryptoManager, discord_id: &str, balance: i64) -> Result<User, CryptoError> {
+    let (public_key, private_key) = crypto.generate_keypair()?;
+    let user = User {
+        discord_id: discord_id.to_string(),
+        username: discord_id.to_string(),
+        public_key,
+        encrypted_private_key: crypto.encrypt_private_key(&private_key, discord_id)?,
+        nonce: 0,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    database.create_user(&user).await?;
+    fund(database, discord_id, balance).await?;
+    Ok(user)
+}
+
+/// Mint `amount` coins into `account` through the ledger, so audits still balance. Works for
+/// system accounts such as the treasury as well as users.
+pub async fn fund(database: &Database, account: &str, amount: i64) -> Result<(), DatabaseError> {
+    if amount <= 0 {
+        return Ok(());
+    }
+    let mint = Transaction::system(SYSTEM_ACCOUNT, account, amount, "mint", Some("Fixture funding".to_string()));
+    database.apply_transaction(&mint).await?;
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, DatabaseError, Transaction};
+use crate::health::TaskMonitor;
+
+const ANALYZE_TICK_SECONDS: u64 = 600;
+// Each pass looks back a day, so patterns spread across a few hours are still caught
+const LOOKBACK_SECONDS: i64 = 86400;
+const ALERT_DISPLAY_LIMIT: usize = 5;
+const ID_DISPLAY_LIMIT: usize = 6;
+
+// A cycle counts when the coins coming back are at least this share of what went out
+const CYCLE_RETURN_PERCENT: i64 = 90;
+// Freshly issued coins moved on within the window, above this share of the credit
+const DUMP_WINDOW_SECONDS: i64 = 3600;
+const DUMP_SHARE_PERCENT: i64 = 80;
+const MIN_MINT_AMOUNT: i64 = 100;
+// Accounts registered this recently are "new"
+const NEW_ACCOUNT_AGE_SECONDS: i64 = 7 * 86400;
+const FUNDED_ACCOUNTS_THRESHOLD: usize = 5;
+
+/// Ledger types that put new coins into a user's hands
+const MINT_TYPES: &[&str] = &["mint", "import", "airdrop", "redeem", "faucet", "daily", "treasury_spend", "treasury_redistribute"];
+/// Ledger types where one user moves coins to another
+const TRANSFER_TYPES: &[&str] = &["transfer", "rain"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Suspicion {
+    CircularTransfers { accounts: Vec<String>, transaction_ids: Vec<String> },
+    MintAndDump { discord_id: String, minted: i64, dumped: i64, transaction_ids: Vec<String> },
+    FundingNewAccounts { funder: String, recipients: Vec<String>, transaction_ids: Vec<String> },
+}
+
+impl Suspicion {
+    fn transaction_ids(&self) -> &[String] {
+        match self {
+            Suspicion::CircularTransfers { transaction_ids, .. }
+            | Suspicion::MintAndDump { transaction_ids, .. }
+            | Suspicion::FundingNewAccounts { transaction_ids, .. } => transaction_ids,
+        }
+    }
+}
+
+impl std::fmt::Display for Suspicion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Suspicion::CircularTransfers { accounts, .. } => {
+                let path: Vec<String> = accounts.iter().map(|id| format!("<@{}>", id)).collect();
+                write!(f, "Circular transfers {} → <@{}>", path.join(" → "), accounts[0])?;
+            }
+            Suspicion::MintAndDump { discord_id, minted, dumped, .. } => {
+                write!(
+                    f,
+                    "<@{}> received {} new Slumcoins and sent {} onward within {}",
+                    discord_id, minted, dumped, crate::commands::format_duration(DUMP_WINDOW_SECONDS as u64)
+                )?;
+            }
+            Suspicion::FundingNewAccounts { funder, recipients, .. } => {
+                write!(f, "<@{}> funded {} accounts registered in the last week", funder, recipients.len())?;
+            }
+        }
+
+        let ids = self.transaction_ids();
+        let shown: Vec<String> = ids.iter().take(ID_DISPLAY_LIMIT).map(|id| format!("`{}`", id)).collect();
+        write!(f, "\n  {}", shown.join(" "))?;
+        if ids.len() > ID_DISPLAY_LIMIT {
+            write!(f, " +{} more", ids.len() - ID_DISPLAY_LIMIT)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_transfer(tx: &Transaction) -> bool {
+    TRANSFER_TYPES.contains(&tx.transaction_type.as_str())
+}
+
+fn returns_enough(first: &Transaction, last: &Transaction) -> bool {
+    last.amount * 100 >= first.amount * CYCLE_RETURN_PERCENT
+}
+
+/// Find coins that go around a loop of two or three accounts and come back to where they started.
+/// Transactions must be sorted oldest first; each leg has to happen after the previous one.
+pub fn find_circular_transfers(transactions: &[Transaction]) -> Vec<Suspicion> {
+    let transfers: Vec<&Transaction> = transactions.iter().filter(|tx| is_transfer(tx)).collect();
+    let mut outgoing: HashMap<&str, Vec<&Transaction>> = HashMap::new();
+    for tx in &transfers {
+        outgoing.entry(tx.from_user.as_str()).or_default().push(tx);
+    }
+
+    let later_from = |account: &str, after: &Transaction| -> Vec<&Transaction> {
+        outgoing.get(account)
+            .map(|txs| txs.iter().copied().filter(|tx| tx.timestamp_unix >= after.timestamp_unix && tx.id != after.id).collect())
+            .unwrap_or_default()
+    };
+
+    let mut suspicions = Vec::new();
+    for first in &transfers {
+        let origin = first.from_user.as_str();
+        for second in later_from(&first.to_user, first) {
+            if second.to_user == origin {
+                if returns_enough(first, second) {
+                    suspicions.push(Suspicion::CircularTransfers {
+                        accounts: vec![first.from_user.clone(), first.to_user.clone()],
+                        transaction_ids: vec![first.id.clone(), second.id.clone()],
+                    });
+                }
+                continue;
+            }
+
+            for third in later_from(&second.to_user, second) {
+                if third.to_user == origin && returns_enough(first, third) {
+                    suspicions.push(Suspicion::CircularTransfers {
+                        accounts: vec![first.from_user.clone(), first.to_user.clone(), second.to_user.clone()],
+                        transaction_ids: vec![first.id.clone(), second.id.clone(), third.id.clone()],
+                    });
+                }
+            }
+        }
+    }
+
+    suspicions
+}
+
+/// Find users who pass most of a fresh mint, airdrop or payout on to others right away
+pub fn find_mint_and_dump(transactions: &[Transaction]) -> Vec<Suspicion> {
+    let mut suspicions = Vec::new();
+
+    for mint in transactions.iter().filter(|tx| MINT_TYPES.contains(&tx.transaction_type.as_str())) {
+        if mint.amount < MIN_MINT_AMOUNT {
+            continue;
+        }
+
+        let dumps: Vec<&Transaction> = transactions.iter()
+            .filter(|tx| {
+                is_transfer(tx)
+                    && tx.from_user == mint.to_user
+                    && tx.timestamp_unix >= mint.timestamp_unix
+                    && tx.timestamp_unix <= mint.timestamp_unix + DUMP_WINDOW_SECONDS
+            })
+            .collect();
+        let dumped: i64 = dumps.iter().map(|tx| tx.amount).sum();
+
+        if dumped * 100 >= mint.amount * DUMP_SHARE_PERCENT {
+            let mut transaction_ids = vec![mint.id.clone()];
+            transaction_ids.extend(dumps.iter().map(|tx| tx.id.clone()));
+            suspicions.push(Suspicion::MintAndDump {
+                discord_id: mint.to_user.clone(),
+                minted: mint.amount,
+                dumped,
+                transaction_ids,
+            });
+        }
+    }
+
+    suspicions
+}
+
+/// Find single accounts sending coins to many recently registered accounts
+pub fn find_funding_new_accounts(transactions: &[Transaction], new_accounts: &HashSet<String>) -> Vec<Suspicion> {
+    // Only the first payment to each recipient is kept, so repeat payments don't re-trigger an alert
+    let mut funded: HashMap<&str, Vec<&Transaction>> = HashMap::new();
+    for tx in transactions.iter().filter(|tx| is_transfer(tx) && new_accounts.contains(&tx.to_user)) {
+        let txs = funded.entry(tx.from_user.as_str()).or_default();
+        if !txs.iter().any(|seen| seen.to_user == tx.to_user) {
+            txs.push(tx);
+        }
+    }
+
+    let mut suspicions = Vec::new();
+    for (funder, txs) in funded {
+        if txs.len() >= FUNDED_ACCOUNTS_THRESHOLD {
+            let recipients = txs.iter().map(|tx| tx.to_user.clone()).collect();
+            suspicions.push(Suspicion::FundingNewAccounts {
+                funder: funder.to_string(),
+                recipients,
+                transaction_ids: txs.iter().map(|tx| tx.id.clone()).collect(),
+            });
+        }
+    }
+
+    suspicions
+}
+
+/// Run every check over the last day of ledger activity
+pub async fn analyze(database: &Database) -> Result<Vec<Suspicion>, DatabaseError> {
+    let now = Utc::now().timestamp();
+    let transactions = database.get_transactions_since(now - LOOKBACK_SECONDS).await?;
+    let new_accounts: HashSet<String> = database
+        .get_users_registered_since(now - NEW_ACCOUNT_AGE_SECONDS)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut suspicions = find_circular_transfers(&transactions);
+    suspicions.extend(find_mint_and_dump(&transactions));
+    suspicions.extend(find_funding_new_accounts(&transactions, &new_accounts));
+    Ok(suspicions)
+}
+
+async fn alert(http: &serenity::Http, database: &Database, suspicions: &[Suspicion]) -> Result<(), DatabaseError> {
+    let mut message = String::from("🕵️ **Suspicious activity detected**\n");
+    for suspicion in suspicions.iter().take(ALERT_DISPLAY_LIMIT) {
+        message.push_str(&format!("• {}\n", suspicion));
+    }
+    if suspicions.len() > ALERT_DISPLAY_LIMIT {
+        message.push_str(&format!("...and {} more\n", suspicions.len() - ALERT_DISPLAY_LIMIT));
+    }
+    message.push_str("Use `/reverse` or `/freeze` if action is needed.");
+
+    for (guild_id, channel_id) in database.get_guild_settings_for_key("fraud.channel_id").await? {
+        let Ok(channel_id) = channel_id.parse::<u64>() else {
+            continue;
+        };
+
+        let reply = serenity::CreateMessage::new()
+            .content(&message)
+            .allowed_mentions(serenity::CreateAllowedMentions::new());
+        if let Err(e) = serenity::ChannelId::new(channel_id).send_message(http, reply).await {
+            error!("Failed to post fraud alert to guild {}: {}", guild_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically scan recent transfers for suspicious patterns and alert fraud channels
+pub fn spawn_analyzer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(ANALYZE_TICK_SECONDS));
+        // The lookback overlaps between passes, so each finding is only reported once per run of the bot
+        let mut reported: HashSet<Suspicion> = HashSet::new();
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("fraud", Duration::from_secs(ANALYZE_TICK_SECONDS));
+
+            let mut suspicions = match analyze(&database).await {
+                Ok(suspicions) => suspicions,
+                Err(e) => {
+                    error!("Fraud analysis failed: {}", e);
+                    continue;
+                }
+            };
+
+            suspicions.retain(|suspicion| !reported.contains(suspicion));
+            if suspicions.is_empty() {
+                continue;
+            }
+
+            for suspicion in &suspicions {
+                warn!("Fraud analyzer: {}", suspicion);
+            }
+
+            match alert(&http, &database, &suspicions).await {
+                Ok(()) => {
+                    info!("Reported {} suspicious patterns", suspicions.len());
+                    reported.extend(suspicions);
+                }
+                Err(e) => error!("Failed to send fraud alerts: {}", e),
+            }
+        }
+    });
+}
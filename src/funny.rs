@@ -1,16 +1,75 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use poise::serenity_prelude as serenity;
-use tracing::{error};
+use tokio::sync::RwLock;
+use tracing::error;
 
-const TARGET_USER_ID: u64 = 339829749218017281;
+use crate::database::{Database, DatabaseError, Trigger};
 
-pub async fn handle_slumduke_messages(ctx: &serenity::Context, msg: &serenity::Message) {
-    if msg.author.id.get() != TARGET_USER_ID {
-        return;
+// Triggers are checked on every message, so keep each guild's list in memory for a while
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+type Triggers = Arc<Vec<Trigger>>;
+
+/// Per-guild cache of the `triggers` table
+#[derive(Debug, Clone, Default)]
+pub struct TriggerCache {
+    entries: Arc<RwLock<HashMap<String, (Instant, Triggers)>>>,
+}
+
+impl TriggerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, database: &Database, guild_id: &str) -> Result<Triggers, DatabaseError> {
+        if let Some((fetched_at, triggers)) = self.entries.read().await.get(guild_id) {
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Ok(triggers.clone());
+            }
+        }
+
+        let triggers = Arc::new(database.get_triggers(guild_id).await?);
+        self.entries
+            .write()
+            .await
+            .insert(guild_id.to_string(), (Instant::now(), triggers.clone()));
+
+        Ok(triggers)
     }
 
-    if msg.content.to_lowercase().contains("right agelbub?") {
-        if let Err(e) = msg.channel_id.say(&ctx.http, "yes").await {
-            error!("Failed to send joke response: {}", e);
+    pub async fn invalidate(&self, guild_id: &str) {
+        self.entries.write().await.remove(guild_id);
+    }
+}
+
+/// Find the first trigger whose phrase appears in the message, respecting user restrictions
+pub fn find_match<'a>(triggers: &'a [Trigger], author_id: &str, content: &str) -> Option<&'a Trigger> {
+    let content = content.to_lowercase();
+
+    triggers.iter().find(|trigger| {
+        trigger.user_id.as_deref().is_none_or(|user_id| user_id == author_id)
+            && content.contains(&trigger.phrase.to_lowercase())
+    })
+}
+
+pub async fn handle_triggers(ctx: &serenity::Context, msg: &serenity::Message, database: &Database, cache: &TriggerCache) {
+    let Some(guild_id) = msg.guild_id else {
+        return;
+    };
+
+    let triggers = match cache.get(database, &guild_id.to_string()).await {
+        Ok(triggers) => triggers,
+        Err(e) => {
+            error!("Failed to load triggers: {}", e);
+            return;
+        }
+    };
+
+    if let Some(trigger) = find_match(&triggers, &msg.author.id.to_string(), &msg.content) {
+        if let Err(e) = msg.channel_id.say(&ctx.http, &trigger.response).await {
+            error!("Failed to send trigger response: {}", e);
         }
     }
 }
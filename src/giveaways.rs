@@ -0,0 +1,257 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use rand::seq::SliceRandom;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::commands::AccountFrozen;
+use crate::database::{Database, DatabaseError, Giveaway, Transaction};
+use crate::health::TaskMonitor;
+use crate::Data;
+
+// Holds paid entries until the winner is drawn
+pub const GIVEAWAY_POT_ACCOUNT: &str = "GIVEAWAY_POT";
+// Enter buttons are handled from the event handler so they keep working after restarts
+pub const BUTTON_PREFIX: &str = "giveaway:";
+
+const DRAW_TICK_SECONDS: u64 = 30;
+
+pub fn giveaway_embed(giveaway: &Giveaway, entries: i64) -> serenity::CreateEmbed {
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🎉 Giveaway: {}", giveaway.prize))
+        .field("Hosted by", format!("<@{}>", giveaway.host_id), true)
+        .field("Entries", entries.to_string(), true)
+        .footer(serenity::CreateEmbedFooter::new(format!("Giveaway #{}", giveaway.id)))
+        .color(0xf1c40f);
+
+    if giveaway.entry_cost > 0 {
+        embed = embed.field("Pot", format!("{} Slumcoins", giveaway.pot), true);
+    }
+
+    if giveaway.status == "open" {
+        let how = if giveaway.entry_cost > 0 {
+            format!("Press **Enter** to join for **{} Slumcoins**. Entry fees go to the winner.", giveaway.entry_cost)
+        } else {
+            "Press **Enter** to join for free.".to_string()
+        };
+        embed.description(format!("{}\nDraw <t:{}:R>", how, giveaway.ends_at))
+    } else {
+        let result = match &giveaway.winner_id {
+            Some(winner_id) => format!("Winner: <@{}>", winner_id),
+            None => "Nobody entered.".to_string(),
+        };
+        embed.description(format!("{}\nEnded <t:{}:R>", result, giveaway.ends_at))
+    }
+}
+
+pub fn enter_buttons(giveaway_id: i64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}:enter", BUTTON_PREFIX, giveaway_id))
+            .label("Enter")
+            .emoji('🎉')
+            .style(serenity::ButtonStyle::Primary),
+    ])]
+}
+
+/// Handle an Enter press on a giveaway embed
+pub async fn handle_button(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), serenity::Error> {
+    let Some(giveaway_id) = press.data.custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|rest| rest.strip_suffix(":enter"))
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return Ok(());
+    };
+    let user_id = press.user.id.to_string();
+
+    let giveaway = match data.database.get_giveaway(giveaway_id).await {
+        Ok(Some(giveaway)) => giveaway,
+        Ok(None) => return reply_ephemeral(ctx, press, "This giveaway no longer exists.").await,
+        Err(e) => {
+            error!("Error loading giveaway {}: {}", giveaway_id, e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    };
+    if giveaway.status != "open" || giveaway.ends_at <= Utc::now().timestamp() {
+        return reply_ephemeral(ctx, press, "This giveaway has ended.").await;
+    }
+
+    match data.database.get_user(&user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return reply_ephemeral(ctx, press, "You're not registered! Use `/register` first.").await,
+        Err(e) => {
+            error!("Database error: {}", e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    }
+
+    let payment = (giveaway.entry_cost > 0).then(|| Transaction::system(
+        &user_id,
+        GIVEAWAY_POT_ACCOUNT,
+        giveaway.entry_cost,
+        "giveaway_entry",
+        Some(format!("Entry to giveaway #{}", giveaway.id)),
+    ));
+    if payment.is_some() {
+        match data.database.is_frozen(&user_id).await {
+            Ok(false) => {}
+            Ok(true) => return reply_ephemeral(ctx, press, &AccountFrozen.to_string()).await,
+            Err(e) => {
+                error!("Error checking if {} is frozen: {}", user_id, e);
+                return reply_ephemeral(ctx, press, "Database error occurred.").await;
+            }
+        }
+    }
+
+    match data.database.enter_giveaway(giveaway.id, &user_id, payment.as_ref()).await {
+        Ok(true) => {}
+        Ok(false) => return reply_ephemeral(ctx, press, "You've already entered this giveaway.").await,
+        Err(DatabaseError::InsufficientFunds(overdraft)) => {
+            return reply_ephemeral(ctx, press, &format!("UR BROKE BUB! You have {} Slumcoins", overdraft.balance)).await;
+        }
+        Err(e) => {
+            error!("Error entering giveaway {}: {}", giveaway.id, e);
+            return reply_ephemeral(ctx, press, "Error entering the giveaway. Please try again.").await;
+        }
+    }
+
+    // Show the new entry count and pot on the embed
+    let (giveaway, entries) = match tokio::try_join!(
+        data.database.get_giveaway(giveaway.id),
+        data.database.count_giveaway_entries(giveaway.id),
+    ) {
+        Ok((Some(giveaway), entries)) => (giveaway, entries),
+        Ok((None, _)) => return Ok(()),
+        Err(e) => {
+            error!("Error reloading giveaway {}: {}", giveaway.id, e);
+            return reply_ephemeral(ctx, press, "You're in! 🎉").await;
+        }
+    };
+    press.create_response(ctx, serenity::CreateInteractionResponse::UpdateMessage(
+        serenity::CreateInteractionResponseMessage::new().embed(giveaway_embed(&giveaway, entries)),
+    )).await?;
+    press.create_followup(ctx, serenity::CreateInteractionResponseFollowup::new().content("You're in! 🎉").ephemeral(true)).await?;
+    Ok(())
+}
+
+// Pick a winner from everyone who entered, leaving out `exclude`
+async fn pick_winner(database: &Database, giveaway_id: i64, exclude: Option<&str>) -> Result<Option<String>, DatabaseError> {
+    let entrants = database.get_giveaway_entrants(giveaway_id).await?;
+    let eligible: Vec<&String> = entrants.iter().filter(|entrant| Some(entrant.as_str()) != exclude).collect();
+    Ok(eligible.choose(&mut rand::thread_rng()).map(|winner| winner.to_string()))
+}
+
+// Edit the giveaway's embed to its final state and announce the winner in its channel
+async fn announce(http: &serenity::Http, database: &Database, giveaway: &Giveaway, message: String) -> Result<(), DatabaseError> {
+    let Some(giveaway) = database.get_giveaway(giveaway.id).await? else {
+        return Ok(());
+    };
+    let entries = database.count_giveaway_entries(giveaway.id).await?;
+    let Ok(channel_id) = giveaway.channel_id.parse::<u64>() else {
+        return Ok(());
+    };
+    let channel = serenity::ChannelId::new(channel_id);
+
+    if let Some(message_id) = giveaway.message_id.as_ref().and_then(|id| id.parse::<u64>().ok()) {
+        let edit = serenity::EditMessage::new().embed(giveaway_embed(&giveaway, entries)).components(Vec::new());
+        if let Err(e) = channel.edit_message(http, serenity::MessageId::new(message_id), edit).await {
+            error!("Failed to update giveaway {} message: {}", giveaway.id, e);
+        }
+    }
+    if let Err(e) = channel.say(http, message).await {
+        error!("Failed to announce giveaway {}: {}", giveaway.id, e);
+    }
+    Ok(())
+}
+
+async fn draw(http: &serenity::Http, database: &Database, giveaway: &Giveaway) -> Result<(), DatabaseError> {
+    let winner = pick_winner(database, giveaway.id, None).await?;
+    let payout = winner.as_ref().filter(|_| giveaway.pot > 0).map(|winner| Transaction::system(
+        GIVEAWAY_POT_ACCOUNT,
+        winner,
+        giveaway.pot,
+        "giveaway_win",
+        Some(format!("Won giveaway #{}", giveaway.id)),
+    ));
+    if !database.settle_giveaway(giveaway.id, None, winner.as_deref(), payout.as_ref()).await? {
+        return Ok(());
+    }
+    info!("Drew giveaway {} in guild {}", giveaway.id, giveaway.guild_id);
+
+    let message = match &winner {
+        Some(winner) if giveaway.pot > 0 => format!(
+            "🎉 <@{}> won **{}** and the pot of **{} Slumcoins**! Hosted by <@{}>",
+            winner, giveaway.prize, giveaway.pot, giveaway.host_id
+        ),
+        Some(winner) => format!("🎉 <@{}> won **{}**! Hosted by <@{}>", winner, giveaway.prize, giveaway.host_id),
+        None => format!("🎉 The giveaway for **{}** ended with no entries.", giveaway.prize),
+    };
+    announce(http, database, giveaway, message).await
+}
+
+/// Draw a new winner for an ended giveaway, leaving out the current one. The pot moves from
+/// the old winner to the new one. Returns the new winner, or None if nobody else entered.
+pub async fn reroll(http: &serenity::Http, database: &Database, giveaway: &Giveaway) -> Result<Option<String>, DatabaseError> {
+    let previous = giveaway.winner_id.as_deref();
+    let Some(winner) = pick_winner(database, giveaway.id, previous).await? else {
+        return Ok(None);
+    };
+    let payout = previous.filter(|_| giveaway.pot > 0).map(|previous| Transaction::system(
+        previous,
+        &winner,
+        giveaway.pot,
+        "giveaway_reroll",
+        Some(format!("Giveaway #{} rerolled", giveaway.id)),
+    ));
+    if !database.settle_giveaway(giveaway.id, previous, Some(&winner), payout.as_ref()).await? {
+        return Ok(None);
+    }
+    info!("Rerolled giveaway {} in guild {}", giveaway.id, giveaway.guild_id);
+
+    let message = format!("🎲 Reroll! <@{}> now wins **{}**!", winner, giveaway.prize);
+    announce(http, database, giveaway, message).await?;
+    Ok(Some(winner))
+}
+
+/// Draw every giveaway whose time is up
+pub fn spawn_drawer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(DRAW_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("giveaways", Duration::from_secs(DRAW_TICK_SECONDS));
+
+            let due = match database.get_due_giveaways(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due giveaways: {}", e);
+                    continue;
+                }
+            };
+
+            for giveaway in due {
+                if let Err(e) = draw(&http, &database, &giveaway).await {
+                    error!("Failed to draw giveaway {}: {}", giveaway.id, e);
+                }
+            }
+        }
+    });
+}
+
+async fn reply_ephemeral(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), serenity::Error> {
+    press.create_response(ctx, serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )).await
+}
@@ -0,0 +1,61 @@
+//! Heartbeats of long-running background tasks, so a stalled loop shows up as unhealthy.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Last heartbeat of each background task, used by `/botstats`
+#[derive(Debug, Clone, Default)]
+pub struct TaskMonitor {
+    heartbeats: Arc<RwLock<HashMap<&'static str, (Instant, Duration)>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    pub since_last_beat: Duration,
+    pub healthy: bool,
+}
+
+impl TaskMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `name` is alive and expects to beat again within `interval`
+    pub fn beat(&self, name: &'static str, interval: Duration) {
+        if let Ok(mut heartbeats) = self.heartbeats.write() {
+            heartbeats.insert(name, (Instant::now(), interval));
+        }
+    }
+
+    /// Tasks are unhealthy once they miss two consecutive ticks
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        let Ok(heartbeats) = self.heartbeats.read() else {
+            return Vec::new();
+        };
+
+        let mut statuses: Vec<TaskStatus> = heartbeats
+            .iter()
+            .map(|(name, (last_beat, interval))| {
+                let since_last_beat = last_beat.elapsed();
+                TaskStatus {
+                    name,
+                    since_last_beat,
+                    healthy: since_last_beat <= *interval * 2,
+                }
+            })
+            .collect();
+        statuses.sort_by_key(|status| status.name);
+        statuses
+    }
+}
+
+/// Resident memory of this process in kilobytes, where the platform exposes it
+pub fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
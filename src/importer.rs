@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+use serde_json::Value;
+use tracing::error;
+
+use crate::crypto::CryptoManager;
+use crate::database::{Database, DatabaseError, Transaction, SYSTEM_ACCOUNT};
+use crate::registration;
+
+// Ledger type of the seed-balance mints, also used to skip users who were already imported
+pub const IMPORT_TYPE: &str = "import";
+
+/// One user's balance read from another bot's export
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBalance {
+    pub discord_id: String,
+    pub username: Option<String>,
+    pub amount: i64,
+}
+
+// Header names are compared lowercased with everything but letters and digits removed,
+// so "User ID", "user_id" and "userId" are all the same column
+const ID_COLUMNS: &[&str] = &["userid", "id", "discordid", "memberid"];
+const NAME_COLUMNS: &[&str] = &["username", "user", "name", "tag"];
+const TOTAL_COLUMNS: &[&str] = &["total", "balance", "networth", "amount"];
+const CASH_COLUMN: &str = "cash";
+const BANK_COLUMN: &str = "bank";
+
+fn normalize(header: &str) -> String {
+    header.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+// Balances may be written as `1,234`, `1234.5` or with a currency symbol; fractions are dropped
+fn parse_amount(value: &str) -> Option<i64> {
+    let cleaned: String = value.chars().filter(|c| c.is_ascii_digit() || *c == '-' || *c == '.').collect();
+    cleaned.parse::<i64>().ok().or_else(|| cleaned.parse::<f64>().ok().map(|amount| amount as i64))
+}
+
+fn is_snowflake(value: &str) -> bool {
+    !value.is_empty() && value.len() <= 20 && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Parse a CSV or JSON export, picking the format from the file name or the first character.
+/// Rows without a Discord ID or with a zero or negative balance are dropped; the first row wins for repeated IDs.
+pub fn parse(filename: &str, contents: &str) -> Result<Vec<ImportedBalance>, String> {
+    let is_json = filename.to_lowercase().ends_with(".json") || contents.trim_start().starts_with(['[', '{']);
+    let rows = if is_json { parse_json(contents)? } else { parse_csv(contents)? };
+
+    let mut seen = HashSet::new();
+    Ok(rows
+        .into_iter()
+        .filter(|row| row.amount > 0 && seen.insert(row.discord_id.clone()))
+        .collect())
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<ImportedBalance>, String> {
+    let mut records = csv_records(contents).into_iter();
+    let header: Vec<String> = records.next().ok_or("The file is empty.")?.iter().map(|h| normalize(h)).collect();
+    let column = |names: &[&str]| names.iter().find_map(|name| header.iter().position(|h| h == name));
+
+    let id_column = column(ID_COLUMNS).ok_or("Couldn't find a user ID column.")?;
+    let name_column = column(NAME_COLUMNS);
+    let total_column = column(TOTAL_COLUMNS);
+    let cash_column = column(&[CASH_COLUMN]);
+    let bank_column = column(&[BANK_COLUMN]);
+    if total_column.is_none() && cash_column.is_none() && bank_column.is_none() {
+        return Err("Couldn't find a total, balance, cash or bank column.".to_string());
+    }
+
+    let mut balances = Vec::new();
+    for record in records {
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).map(|value| value.trim());
+        let Some(discord_id) = field(Some(id_column)).filter(|id| is_snowflake(id)) else {
+            continue;
+        };
+        let amount = match field(total_column).and_then(parse_amount) {
+            Some(total) => total,
+            None => field(cash_column).and_then(parse_amount).unwrap_or(0)
+                .saturating_add(field(bank_column).and_then(parse_amount).unwrap_or(0)),
+        };
+
+        balances.push(ImportedBalance {
+            discord_id: discord_id.to_string(),
+            username: field(name_column).filter(|name| !name.is_empty()).map(str::to_string),
+            amount,
+        });
+    }
+
+    Ok(balances)
+}
+
+// Split CSV text into records, honouring quoted fields with embedded commas, quotes and line breaks
+fn csv_records(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                let finished = std::mem::take(&mut record);
+                if finished.iter().any(|value| !value.trim().is_empty()) {
+                    records.push(finished);
+                }
+            }
+            ('\r', false) => {}
+            _ => field.push(c),
+        }
+    }
+
+    record.push(field);
+    if record.iter().any(|value| !value.trim().is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+fn parse_json(contents: &str) -> Result<Vec<ImportedBalance>, String> {
+    let value: Value = serde_json::from_str(contents).map_err(|e| format!("The file isn't valid JSON: {}", e))?;
+
+    // Either a bare list, a list under a well-known key, or an object keyed by user ID
+    let entries: Vec<(Option<String>, &Value)> = match &value {
+        Value::Array(items) => items.iter().map(|item| (None, item)).collect(),
+        Value::Object(object) => match ["users", "leaderboard", "data", "members"].iter().find_map(|key| object.get(*key)) {
+            Some(Value::Array(items)) => items.iter().map(|item| (None, item)).collect(),
+            Some(Value::Object(users)) => users.iter().map(|(id, item)| (Some(id.clone()), item)).collect(),
+            _ => object.iter().map(|(id, item)| (Some(id.clone()), item)).collect(),
+        },
+        _ => return Err("Expected a list of users.".to_string()),
+    };
+
+    let text = |value: &Value| match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    };
+
+    let mut balances = Vec::new();
+    for (key, entry) in entries {
+        let fields: Vec<(String, &Value)> = match entry {
+            Value::Object(object) => object.iter().map(|(name, value)| (normalize(name), value)).collect(),
+            // `{"1234": 500}` style maps of ID to balance
+            other => vec![("total".to_string(), other)],
+        };
+        let field = |names: &[&str]| names.iter().find_map(|name| fields.iter().find(|(n, _)| n == name).and_then(|(_, v)| text(v)));
+
+        let Some(discord_id) = field(ID_COLUMNS).or(key).filter(|id| is_snowflake(id)) else {
+            continue;
+        };
+        let amount = match field(TOTAL_COLUMNS).as_deref().and_then(parse_amount) {
+            Some(total) => total,
+            None => field(&[CASH_COLUMN]).as_deref().and_then(parse_amount).unwrap_or(0)
+                .saturating_add(field(&[BANK_COLUMN]).as_deref().and_then(parse_amount).unwrap_or(0)),
+        };
+
+        balances.push(ImportedBalance { discord_id, username: field(NAME_COLUMNS), amount });
+    }
+
+    Ok(balances)
+}
+
+#[derive(Debug, Default)]
+pub struct ImportOutcome {
+    pub registered: usize,
+    pub credited: usize,
+    pub total: i64,
+    pub failed: usize,
+}
+
+/// Balances from `balances` that haven't been imported before
+pub async fn pending(database: &Database, balances: Vec<ImportedBalance>) -> Result<Vec<ImportedBalance>, DatabaseError> {
+    let imported: HashSet<String> = database.get_recipients_of_type(IMPORT_TYPE).await?.into_iter().collect();
+    Ok(balances.into_iter().filter(|balance| !imported.contains(&balance.discord_id)).collect())
+}
+
+/// Register anyone who isn't yet and mint each imported balance. The mints are written in one
+/// database transaction, so a failure part way leaves no half-imported ledger.
+pub async fn import_balances(
+    database: &Database,
+    crypto: &CryptoManager,
+    balances: &[ImportedBalance],
+    source: &str,
+) -> Result<ImportOutcome, DatabaseError> {
+    let mut outcome = ImportOutcome::default();
+    let mut mints = Vec::new();
+
+    for balance in balances {
+        let username = balance.username.as_deref().unwrap_or(&balance.discord_id);
+        match registration::register_user(database, crypto, &balance.discord_id, username).await {
+            Ok(created) => {
+                outcome.registered += created as usize;
+                outcome.credited += 1;
+                outcome.total = outcome.total.saturating_add(balance.amount);
+                mints.push(Transaction::system(
+                    SYSTEM_ACCOUNT,
+                    &balance.discord_id,
+                    balance.amount,
+                    IMPORT_TYPE,
+                    Some(format!("Imported from {}", source)),
+                ));
+            }
+            Err(e) => {
+                error!("Error registering {} for import: {}", balance.discord_id, e);
+                outcome.failed += 1;
+            }
+        }
+    }
+
+    database.apply_transactions(&mints).await?;
+    Ok(outcome)
+}
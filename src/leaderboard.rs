@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, DatabaseError, Season};
+use crate::health::TaskMonitor;
+
+// How often the refresher wakes up to look for pinned leaderboards that are due
+const REFRESH_TICK_SECONDS: u64 = 60;
+
+/// Render the top `limit` balances as a leaderboard embed
+pub async fn build_embed(database: &Database, limit: u32) -> Result<serenity::CreateEmbed, DatabaseError> {
+    let description = render_ranks(database, limit, 0).await?;
+
+    Ok(serenity::CreateEmbed::new()
+        .title("Slumbank Leaderboard")
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new("Last updated"))
+        .timestamp(serenity::Timestamp::now()))
+}
+
+/// Render one page of the leaderboard (pages start at 1), returning the embed and the page count.
+/// For an ended `season` the page shows its final standings instead of current balances.
+pub async fn build_page_embed(
+    database: &Database,
+    season: Option<&Season>,
+    page: u32,
+    per_page: u32,
+) -> Result<(serenity::CreateEmbed, u32), DatabaseError> {
+    let season = season.filter(|season| season.ended_at.is_some());
+
+    // Pages only cover users who haven't hidden themselves, but the footer counts everyone
+    let (listed_users, total_users) = match season {
+        Some(season) => database.count_season_standings(season.id).await?,
+        None => (database.count_leaderboard_users().await?, database.count_users().await?),
+    };
+    let total_pages = (listed_users.max(0) as u32).div_ceil(per_page).max(1);
+    let page = page.clamp(1, total_pages);
+    let offset = (page - 1) * per_page;
+
+    let (title, description) = match season {
+        Some(season) => (format!("Slumbank Leaderboard · {}", season.name), render_season_ranks(database, season, per_page, offset).await?),
+        None => ("Slumbank Leaderboard".to_string(), render_ranks(database, per_page, offset).await?),
+    };
+    let mut footer = format!("Page {} of {} · {} users", page, total_pages, total_users);
+    if let Some(ended_at) = season.and_then(|season| season.ended_at) {
+        // Footers don't render Discord timestamps
+        let ended = chrono::DateTime::from_timestamp(ended_at, 0).unwrap_or_default();
+        footer.push_str(&format!(" · Final standings from {}", ended.format("%Y-%m-%d")));
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(title)
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(footer));
+
+    Ok((embed, total_pages))
+}
+
+async fn render_season_ranks(database: &Database, season: &Season, limit: u32, offset: u32) -> Result<String, DatabaseError> {
+    let standings = database.get_season_standings(season.id, limit, offset).await?;
+    if standings.is_empty() {
+        return Ok("Nobody was registered that season.".to_string());
+    }
+
+    let mut description = String::new();
+    for (rank, (username, balance)) in standings.iter().enumerate() {
+        description.push_str(&format!("**{}. {} : ``{}``**\n", offset as usize + rank + 1, username, balance));
+    }
+    Ok(description)
+}
+
+async fn render_ranks(database: &Database, limit: u32, offset: u32) -> Result<String, DatabaseError> {
+    let users_with_balances = database.get_all_users_with_balances(Some(limit), offset).await?;
+
+    let mut description = String::new();
+    if users_with_balances.is_empty() {
+        description.push_str("No registered users found!");
+    }
+
+    for (rank, (username, balance, defaulted)) in users_with_balances.iter().enumerate() {
+        description.push_str(&format!("**{}. {} : ``{}``**", offset as usize + rank + 1, username, balance));
+        if *defaulted {
+            description.push_str(" 🚩 defaulted");
+        }
+        description.push('\n');
+    }
+
+    Ok(description)
+}
+
+/// Periodically edit every pinned leaderboard message whose refresh interval has elapsed
+pub fn spawn_refresher(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(REFRESH_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("leaderboard", Duration::from_secs(REFRESH_TICK_SECONDS));
+
+            let due = match database.get_due_pinned_leaderboards(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load pinned leaderboards: {}", e);
+                    continue;
+                }
+            };
+
+            for pin in due {
+                let embed = match build_embed(&database, pin.limit).await {
+                    Ok(embed) => embed,
+                    Err(e) => {
+                        error!("Failed to build leaderboard embed: {}", e);
+                        continue;
+                    }
+                };
+
+                let (Ok(channel_id), Ok(message_id)) = (pin.channel_id.parse::<u64>(), pin.message_id.parse::<u64>()) else {
+                    continue;
+                };
+                let channel_id = serenity::ChannelId::new(channel_id);
+                let message_id = serenity::MessageId::new(message_id);
+                let edit = serenity::EditMessage::new().embed(embed);
+
+                match channel_id.edit_message(&http, message_id, edit).await {
+                    Ok(_) => {
+                        if let Err(e) = database.touch_pinned_leaderboard(&pin.message_id, Utc::now().timestamp()).await {
+                            error!("Failed to update pinned leaderboard: {}", e);
+                        }
+                    }
+                    Err(serenity::Error::Http(e)) if e.status_code() == Some(serenity::StatusCode::NOT_FOUND) => {
+                        // Message or channel was deleted, stop refreshing it
+                        info!("Pinned leaderboard {} no longer exists, removing", pin.message_id);
+                        let _ = database.remove_pinned_leaderboard(&pin.message_id).await;
+                    }
+                    Err(e) => {
+                        warn!("Failed to refresh pinned leaderboard {}: {}", pin.message_id, e);
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,633 @@
+//! Transfer validation, dry-run simulations, transaction signing and full ledger audits.
+
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::auction::Auction;
+use crate::config;
+use crate::crypto::{CryptoError, CryptoManager};
+use crate::database::{Database, DatabaseError, Transaction, TransactionFilter, User, GENESIS_HASH};
+
+// Shared pot that funds the faucet and other system payouts
+pub const TREASURY_ACCOUNT: &str = "TREASURY";
+
+#[derive(Debug)]
+pub enum LedgerError {
+    InvalidAmount,
+    SelfTransfer,
+    NotRegistered(String),
+    InsufficientFunds { balance: i64, required: i64 },
+    DailyLimitExceeded { limit: i64, remaining: i64 },
+    StaleNonce,
+    Signing(CryptoError),
+    Database(DatabaseError),
+}
+
+impl std::fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LedgerError::InvalidAmount => write!(f, "Amount must be greater than 0 and small enough to charge a fee on"),
+            LedgerError::SelfTransfer => write!(f, "Cannot transfer to yourself"),
+            LedgerError::NotRegistered(user) => write!(f, "User {} is not registered", user),
+            LedgerError::InsufficientFunds { balance, required } => {
+                write!(f, "Insufficient funds: balance {} but {} required", balance, required)
+            }
+            LedgerError::DailyLimitExceeded { limit, remaining } => {
+                write!(f, "Daily transfer limit of {} reached, {} left", limit, remaining)
+            }
+            LedgerError::StaleNonce => write!(f, "Another transfer from this sender was recorded first"),
+            LedgerError::Signing(e) => write!(f, "Signing failed: {}", e),
+            LedgerError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+impl From<DatabaseError> for LedgerError {
+    fn from(err: DatabaseError) -> Self {
+        match err {
+            err if is_nonce_conflict(&err) => LedgerError::StaleNonce,
+            DatabaseError::InsufficientFunds(overdraft) => {
+                LedgerError::InsufficientFunds { balance: overdraft.balance, required: overdraft.required }
+            }
+            DatabaseError::NotRegistered(discord_id) => LedgerError::NotRegistered(discord_id),
+            err => LedgerError::Database(err),
+        }
+    }
+}
+
+/// Whether an insert failed because its nonce was out of order or already used (see migration 022)
+pub fn is_nonce_conflict(err: &DatabaseError) -> bool {
+    match err {
+        DatabaseError::Conflict(message) => {
+            message.contains("nonce out of order") || message.contains("transactions.from_user, transactions.nonce")
+        }
+        _ => false,
+    }
+}
+
+impl LedgerError {
+    // Short machine-readable code for API consumers
+    pub fn code(&self) -> &'static str {
+        match self {
+            LedgerError::InvalidAmount => "invalid_amount",
+            LedgerError::SelfTransfer => "self_transfer",
+            LedgerError::NotRegistered(_) => "not_registered",
+            LedgerError::InsufficientFunds { .. } => "insufficient_funds",
+            LedgerError::DailyLimitExceeded { .. } => "daily_limit_exceeded",
+            LedgerError::StaleNonce => "stale_nonce",
+            LedgerError::Signing(_) => "signing_failed",
+            LedgerError::Database(_) => "database_error",
+        }
+    }
+}
+
+/// Per-guild fee charged to the sender of a transfer and credited to the treasury
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FeeSchedule {
+    pub flat: i64,
+    pub percent: i64,
+}
+
+impl FeeSchedule {
+    pub async fn for_guild(database: &Database, guild_id: &str) -> Result<Self, DatabaseError> {
+        Ok(FeeSchedule {
+            flat: config::get_i64(database, guild_id, "fees.flat").await?.max(0),
+            percent: config::get_i64(database, guild_id, "fees.percent").await?.clamp(0, 100),
+        })
+    }
+
+    /// Fee owed on top of `amount`, with the percentage part rounded down. `None` if the amount is
+    /// too large to work the fee out on.
+    pub fn fee_for(&self, amount: i64) -> Option<i64> {
+        self.flat.checked_add(amount.checked_mul(self.percent)? / 100)
+    }
+
+    // Fee and the total the sender pays, or InvalidAmount when either overflows
+    fn charge_for(&self, amount: i64) -> Result<(i64, i64), LedgerError> {
+        let fee = self.fee_for(amount).ok_or(LedgerError::InvalidAmount)?;
+        let required = amount.checked_add(fee).ok_or(LedgerError::InvalidAmount)?;
+        Ok((fee, required))
+    }
+}
+
+// Outgoing transfers are capped over this rolling window
+pub const DAILY_LIMIT_WINDOW_SECONDS: i64 = 86400;
+
+/// A user's daily outgoing transfer cap in a guild: their admin override if they have one, otherwise the guild setting.
+/// 0 means unlimited.
+pub async fn daily_limit_for(database: &Database, guild_id: &str, discord_id: &str) -> Result<i64, DatabaseError> {
+    match database.get_transfer_limit_override(guild_id, discord_id).await? {
+        Some(limit) => Ok(limit),
+        None => config::get_i64(database, guild_id, "transfer.daily_limit").await,
+    }
+}
+
+/// Check that sending `amount` more keeps a user within their daily cap, counting what they sent in the last 24 hours
+pub async fn check_daily_limit(database: &Database, guild_id: &str, from_user: &str, amount: i64) -> Result<(), LedgerError> {
+    let limit = daily_limit_for(database, guild_id, from_user).await?;
+    if limit <= 0 {
+        return Ok(());
+    }
+
+    let since = chrono::Utc::now().timestamp() - DAILY_LIMIT_WINDOW_SECONDS;
+    let sent = database.get_outgoing_transfer_total(from_user, since).await?;
+    if sent + amount > limit {
+        return Err(LedgerError::DailyLimitExceeded {
+            limit,
+            remaining: (limit - sent).max(0),
+        });
+    }
+
+    Ok(())
+}
+
+/// Result of a transfer that has been validated but not written
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferPreview {
+    pub from_user: String,
+    pub to_user: String,
+    pub amount: i64,
+    pub fee: i64,
+    pub sender_balance_before: i64,
+    pub sender_balance_after: i64,
+    pub recipient_balance_before: i64,
+    pub recipient_balance_after: i64,
+}
+
+/// Result of settling an auction's winning bid, computed without writing
+#[derive(Debug, Clone, Serialize)]
+pub struct SettlementPreview {
+    pub winner: String,
+    pub amount: i64,
+    pub deposit_applied: i64,
+    pub winner_balance_before: i64,
+    pub winner_balance_after: i64,
+}
+
+/// Validate a user-to-user transfer and compute the resulting balances without touching the ledger
+pub async fn simulate_transfer(
+    database: &Database,
+    from_user: &str,
+    to_user: &str,
+    amount: i64,
+    fees: FeeSchedule,
+) -> Result<TransferPreview, LedgerError> {
+    if amount <= 0 {
+        return Err(LedgerError::InvalidAmount);
+    }
+
+    if from_user == to_user {
+        return Err(LedgerError::SelfTransfer);
+    }
+
+    if database.get_user(from_user).await?.is_none() {
+        return Err(LedgerError::NotRegistered(from_user.to_string()));
+    }
+
+    if database.get_user(to_user).await?.is_none() {
+        return Err(LedgerError::NotRegistered(to_user.to_string()));
+    }
+
+    let (fee, required) = fees.charge_for(amount)?;
+    let sender_balance = database.get_balance(from_user).await?;
+    if sender_balance < required {
+        return Err(LedgerError::InsufficientFunds {
+            balance: sender_balance,
+            required,
+        });
+    }
+
+    let recipient_balance = database.get_balance(to_user).await?;
+
+    Ok(TransferPreview {
+        from_user: from_user.to_string(),
+        to_user: to_user.to_string(),
+        amount,
+        fee,
+        sender_balance_before: sender_balance,
+        sender_balance_after: sender_balance - required,
+        recipient_balance_before: recipient_balance,
+        recipient_balance_after: recipient_balance + amount,
+    })
+}
+
+/// Compute what settling an auction would charge its winner. Returns `None` when there are no bids.
+pub async fn simulate_settlement(
+    database: &Database,
+    auction: &Auction,
+) -> Result<Option<SettlementPreview>, LedgerError> {
+    let (winner_id, winning_amount) = match auction.get_winner() {
+        Some(winner) => winner,
+        None => return Ok(None),
+    };
+
+    // The winner's deposit counts toward the price
+    let deposit_applied = auction.deposit_of(winner_id).min(winning_amount);
+    let due = winning_amount - deposit_applied;
+
+    let winner = winner_id.to_string();
+    let balance = database.get_balance(&winner).await?;
+    if balance < due {
+        return Err(LedgerError::InsufficientFunds {
+            balance,
+            required: due,
+        });
+    }
+
+    Ok(Some(SettlementPreview {
+        winner,
+        amount: winning_amount,
+        deposit_applied,
+        winner_balance_before: balance,
+        winner_balance_after: balance - due,
+    }))
+}
+
+/// Sign a transaction with the sender's decrypted private key
+pub fn sign_transaction(
+    crypto: &CryptoManager,
+    sender: &User,
+    transaction: &mut Transaction,
+) -> Result<(), CryptoError> {
+    let private_key = crypto.decrypt_private_key(&sender.encrypted_private_key, &sender.discord_id)?;
+    transaction.signature = crypto.sign_transaction(&private_key, &transaction.signing_payload())?;
+    Ok(())
+}
+
+/// Give each entry the sender's next nonce in order and sign it. The database only accepts
+/// signed entries whose nonce follows the sender's last one, so a replayed or stale entry is rejected.
+fn sign_entries(crypto: &CryptoManager, sender: &User, entries: &mut [Transaction]) -> Result<(), LedgerError> {
+    for (nonce, entry) in (sender.nonce + 1..).zip(entries.iter_mut()) {
+        entry.nonce = nonce;
+        sign_transaction(crypto, sender, entry).map_err(LedgerError::Signing)?;
+    }
+    Ok(())
+}
+
+/// Validate a user-to-user transfer and build its ledger entries signed with the sender's key, without writing them.
+/// The transfer comes first, followed by a separate fee entry to the treasury when a fee applies.
+pub async fn prepare_transfer(
+    database: &Database,
+    crypto: &CryptoManager,
+    from_user: &str,
+    to_user: &str,
+    amount: i64,
+    fees: FeeSchedule,
+    message: Option<String>,
+) -> Result<(TransferPreview, Vec<Transaction>), LedgerError> {
+    let preview = simulate_transfer(database, from_user, to_user, amount, fees).await?;
+
+    let sender = database
+        .get_user(from_user)
+        .await?
+        .ok_or_else(|| LedgerError::NotRegistered(from_user.to_string()))?;
+
+    let mut entries = vec![Transaction::system(from_user, to_user, amount, "transfer", message)];
+    if preview.fee > 0 {
+        entries.push(Transaction::system(
+            from_user,
+            TREASURY_ACCOUNT,
+            preview.fee,
+            "transfer_fee",
+            Some(format!("Fee on transfer {}", entries[0].id)),
+        ));
+    }
+
+    sign_entries(crypto, &sender, &mut entries)?;
+
+    Ok((preview, entries))
+}
+
+/// Validate, sign and atomically record a user-to-user transfer and its fee
+pub async fn execute_transfer(
+    database: &Database,
+    crypto: &CryptoManager,
+    from_user: &str,
+    to_user: &str,
+    amount: i64,
+    fees: FeeSchedule,
+    message: Option<String>,
+) -> Result<TransferPreview, LedgerError> {
+    let (preview, entries) = prepare_transfer(database, crypto, from_user, to_user, amount, fees, message).await?;
+    database.apply_transactions(&entries).await?;
+    Ok(preview)
+}
+
+/// Result of raining coins on a group of users
+#[derive(Debug, Clone, Serialize)]
+pub struct RainPreview {
+    pub from_user: String,
+    pub recipients: Vec<String>,
+    // What each recipient gets; any remainder that doesn't split evenly stays with the sender
+    pub share: i64,
+    pub fee: i64,
+    pub sender_balance_after: i64,
+}
+
+impl RainPreview {
+    pub fn total(&self) -> i64 {
+        self.share * self.recipients.len() as i64
+    }
+}
+
+/// Split `amount` evenly between `recipients` with one signed ledger entry each, charging the transfer fee once
+/// on the total, and record everything atomically
+pub async fn execute_rain(
+    database: &Database,
+    crypto: &CryptoManager,
+    from_user: &str,
+    recipients: &[String],
+    amount: i64,
+    fees: FeeSchedule,
+    message: Option<String>,
+) -> Result<RainPreview, LedgerError> {
+    if amount <= 0 || recipients.is_empty() {
+        return Err(LedgerError::InvalidAmount);
+    }
+    let share = amount / recipients.len() as i64;
+    if share == 0 {
+        return Err(LedgerError::InvalidAmount);
+    }
+
+    let sender = database
+        .get_user(from_user)
+        .await?
+        .ok_or_else(|| LedgerError::NotRegistered(from_user.to_string()))?;
+
+    for recipient in recipients {
+        if recipient == from_user {
+            return Err(LedgerError::SelfTransfer);
+        }
+        if database.get_user(recipient).await?.is_none() {
+            return Err(LedgerError::NotRegistered(recipient.to_string()));
+        }
+    }
+
+    let total = share * recipients.len() as i64;
+    let (fee, required) = fees.charge_for(total)?;
+    let sender_balance = database.get_balance(from_user).await?;
+    if sender_balance < required {
+        return Err(LedgerError::InsufficientFunds {
+            balance: sender_balance,
+            required,
+        });
+    }
+
+    let mut entries: Vec<Transaction> = recipients
+        .iter()
+        .map(|recipient| Transaction::system(from_user, recipient, share, "rain", message.clone()))
+        .collect();
+    if fee > 0 {
+        entries.push(Transaction::system(
+            from_user,
+            TREASURY_ACCOUNT,
+            fee,
+            "transfer_fee",
+            Some(format!("Fee on rain {}", entries[0].id)),
+        ));
+    }
+
+    sign_entries(crypto, &sender, &mut entries)?;
+    database.apply_transactions(&entries).await?;
+
+    Ok(RainPreview {
+        from_user: from_user.to_string(),
+        recipients: recipients.to_vec(),
+        share,
+        fee,
+        sender_balance_after: sender_balance - required,
+    })
+}
+
+/// Gini coefficient of a set of balances: 0 when everyone holds the same, approaching 1 when one holder has everything
+pub fn gini(balances: &[i64]) -> f64 {
+    let mut sorted: Vec<f64> = balances.iter().map(|balance| (*balance).max(0) as f64).collect();
+    let total: f64 = sorted.iter().sum();
+    if sorted.len() < 2 || total == 0.0 {
+        return 0.0;
+    }
+
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted.iter().enumerate().map(|(i, balance)| (i as f64 + 1.0) * balance).sum();
+    (2.0 * weighted) / (n * total) - (n + 1.0) / n
+}
+
+#[derive(Debug, Clone)]
+pub struct BalanceDiscrepancy {
+    pub discord_id: String,
+    pub stored: i64,
+    pub computed: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub transactions_checked: usize,
+    pub signatures_verified: usize,
+    pub invalid_signatures: Vec<String>,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+    // Entries whose stored prev_hash doesn't match the entry before them
+    pub broken_links: Vec<String>,
+    // Hash of the newest entry; noting it down lets a later audit catch the newest entries being removed
+    pub chain_head: Option<String>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.invalid_signatures.is_empty() && self.discrepancies.is_empty() && self.broken_links.is_empty()
+    }
+}
+
+/// Recompute the hash chain over the ledger, returning the ids of entries whose link doesn't match
+/// and the hash of the newest entry
+pub fn verify_chain(chain: &[(Transaction, String)]) -> (Vec<String>, Option<String>) {
+    let mut broken = Vec::new();
+    let mut expected = GENESIS_HASH.to_string();
+
+    for (transaction, prev_hash) in chain {
+        if *prev_hash != expected {
+            broken.push(transaction.id.clone());
+        }
+        // Keep following the stored links so one edit is reported once, not for every entry after it
+        expected = transaction.chain_hash(prev_hash);
+    }
+
+    let head = (!chain.is_empty()).then_some(expected);
+    (broken, head)
+}
+
+/// Walk the full ledger, verify user signatures and compare recomputed balances with the `balances` table
+pub async fn audit(database: &Database, crypto: &CryptoManager) -> Result<AuditReport, LedgerError> {
+    let mut report = AuditReport::default();
+
+    let mut public_keys: HashMap<String, Option<String>> = HashMap::new();
+    let mut computed: HashMap<String, i64> = HashMap::new();
+
+    // Streamed, so the audit's memory use doesn't grow with the ledger
+    let filter = TransactionFilter::default();
+    let mut transactions = database.stream_transactions(&filter);
+    while let Some(tx) = transactions.next().await {
+        let tx = tx?;
+        report.transactions_checked += 1;
+        *computed.entry(tx.to_user.clone()).or_insert(0) += tx.amount;
+        *computed.entry(tx.from_user.clone()).or_insert(0) -= tx.amount;
+
+        if !tx.is_user_signed() {
+            continue;
+        }
+
+        if !public_keys.contains_key(&tx.from_user) {
+            let key = database.get_public_key(&tx.from_user).await?;
+            public_keys.insert(tx.from_user.clone(), key);
+        }
+
+        let valid = match &public_keys[&tx.from_user] {
+            Some(public_key) => crypto.verify_signature(public_key, &tx.signature, &tx.signing_payload()),
+            None => false,
+        };
+
+        if valid {
+            report.signatures_verified += 1;
+        } else {
+            report.invalid_signatures.push(tx.id.clone());
+        }
+    }
+
+    for (discord_id, stored) in database.get_all_balances().await? {
+        let computed_balance = computed.get(&discord_id).copied().unwrap_or(0);
+        if computed_balance != stored {
+            report.discrepancies.push(BalanceDiscrepancy {
+                discord_id,
+                stored,
+                computed: computed_balance,
+            });
+        }
+    }
+
+    let (broken_links, chain_head) = verify_chain(&database.get_hash_chain().await?);
+    report.broken_links = broken_links;
+    report.chain_head = chain_head;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    const FEES: FeeSchedule = FeeSchedule { flat: 1, percent: 10 };
+
+    async fn setup() -> (Database, CryptoManager) {
+        let database = fixtures::database().await.unwrap();
+        let crypto = fixtures::crypto(&database).await.unwrap();
+        fixtures::user(&database, &crypto, "alice", 100).await.unwrap();
+        fixtures::user(&database, &crypto, "bob", 0).await.unwrap();
+        (database, crypto)
+    }
+
+    #[tokio::test]
+    async fn transfer_pays_fee_to_treasury() {
+        let (database, crypto) = setup().await;
+
+        let preview = execute_transfer(&database, &crypto, "alice", "bob", 40, FEES, None).await.unwrap();
+        assert_eq!(preview.fee, 5);
+        assert_eq!(preview.sender_balance_after, 55);
+        assert_eq!(preview.recipient_balance_after, 40);
+
+        assert_eq!(database.get_balance("alice").await.unwrap(), 55);
+        assert_eq!(database.get_balance("bob").await.unwrap(), 40);
+        assert_eq!(database.get_balance(TREASURY_ACCOUNT).await.unwrap(), 5);
+        // The transfer and its fee each used one of alice's nonces
+        assert_eq!(database.get_user("alice").await.unwrap().unwrap().nonce, 2);
+    }
+
+    #[tokio::test]
+    async fn oversized_transfer_is_invalid_amount() {
+        let (database, _) = setup().await;
+
+        assert_eq!(FEES.fee_for(i64::MAX), None);
+        for amount in [i64::MAX, i64::MAX - 5] {
+            let result = simulate_transfer(&database, "alice", "bob", amount, FEES).await;
+            assert!(matches!(result, Err(LedgerError::InvalidAmount)), "{:?}", result);
+        }
+        // The fee itself fits, but not with the amount on top
+        let flat_only = FeeSchedule { flat: 10, percent: 0 };
+        let result = simulate_transfer(&database, "alice", "bob", i64::MAX - 5, flat_only).await;
+        assert!(matches!(result, Err(LedgerError::InvalidAmount)), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn transfer_cannot_overdraw_with_its_fee() {
+        let (database, crypto) = setup().await;
+
+        // 95 fits the balance, but not with the 10 coin fee on top
+        match execute_transfer(&database, &crypto, "alice", "bob", 95, FEES, None).await {
+            Err(LedgerError::InsufficientFunds { balance, required }) => assert_eq!((balance, required), (100, 105)),
+            other => panic!("expected InsufficientFunds, got {:?}", other),
+        }
+        assert_eq!(database.get_balance("alice").await.unwrap(), 100);
+        assert_eq!(database.get_balance("bob").await.unwrap(), 0);
+
+        // The database refuses the entry even when it gets past the balance check
+        let overdraft = Transaction::system("alice", "bob", 101, "transfer", None);
+        let result = database.apply_transactions(&[overdraft]).await.map_err(LedgerError::from);
+        assert!(matches!(result, Err(LedgerError::InsufficientFunds { balance: 100, required: 101 })));
+    }
+
+    #[tokio::test]
+    async fn stale_transfer_is_refused() {
+        let (database, crypto) = setup().await;
+
+        // Both are signed with nonce 1, so only the first to be written counts
+        let (_, first) = prepare_transfer(&database, &crypto, "alice", "bob", 10, FeeSchedule::default(), None).await.unwrap();
+        let (_, second) = prepare_transfer(&database, &crypto, "alice", "bob", 10, FeeSchedule::default(), None).await.unwrap();
+        database.apply_transactions(&first).await.unwrap();
+
+        let result = database.apply_transactions(&second).await.map_err(LedgerError::from);
+        assert!(matches!(result, Err(LedgerError::StaleNonce)));
+        assert_eq!(database.get_balance("bob").await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn daily_limit_counts_transfers_sent() {
+        let (database, crypto) = setup().await;
+        database.set_guild_setting("guild", "transfer.daily_limit", "50").await.unwrap();
+
+        execute_transfer(&database, &crypto, "alice", "bob", 30, FEES, None).await.unwrap();
+
+        // The fee doesn't count toward the limit, only what reached bob
+        check_daily_limit(&database, "guild", "alice", 20).await.unwrap();
+        match check_daily_limit(&database, "guild", "alice", 21).await {
+            Err(LedgerError::DailyLimitExceeded { limit, remaining }) => assert_eq!((limit, remaining), (50, 20)),
+            other => panic!("expected DailyLimitExceeded, got {:?}", other),
+        }
+        // Other guilds keep their own, unlimited default
+        check_daily_limit(&database, "other guild", "alice", 1_000).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn audit_is_clean_after_transfer() {
+        let (database, crypto) = setup().await;
+        execute_transfer(&database, &crypto, "alice", "bob", 40, FEES, None).await.unwrap();
+
+        let report = audit(&database, &crypto).await.unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        // Alice's funding mint, then the signed transfer and fee
+        assert_eq!(report.transactions_checked, 3);
+        assert_eq!(report.signatures_verified, 2);
+
+        let mut chain = database.get_hash_chain().await.unwrap();
+        let (broken, head) = verify_chain(&chain);
+        assert!(broken.is_empty());
+        assert_eq!(head, report.chain_head);
+
+        // Editing an entry breaks the link of the one after it, and only that one
+        chain[1].0.amount += 1;
+        let (broken, _) = verify_chain(&chain);
+        assert_eq!(broken, vec![chain[2].0.id.clone()]);
+    }
+}
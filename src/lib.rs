@@ -0,0 +1,75 @@
+//! Slumcoin economy engine.
+//!
+//! The ledger, balances, key management, escrow and auction logic behind the Discord bot, usable
+//! on its own so other bots or a web backend can share the same ledger. The Discord command layer
+//! lives in the `discord-currency-bot` binary and only talks to the engine through this API.
+//!
+//! # API
+//!
+//! - [`database::Database`] owns the SQLite pool and every read and write. Coins only move
+//!   through [`database::Transaction`]s, written with [`Database::apply_transaction`] or
+//!   [`Database::apply_transactions`], which keep the hash chain and cached balances in step.
+//!   Failures are [`database::DatabaseError`]s; check for `InsufficientFunds`, `NotRegistered`
+//!   and `Conflict` rather than matching on messages.
+//! - [`ledger`] validates and executes user transfers ([`ledger::execute_transfer`]) with a
+//!   guild's [`ledger::FeeSchedule`] and daily limit. It previews them without writing
+//!   ([`ledger::simulate_transfer`]) and audits the whole ledger ([`ledger::audit`]). Fees,
+//!   taxes and shop purchases are paid into [`ledger::TREASURY_ACCOUNT`], which funds system
+//!   payouts.
+//! - [`crypto::CryptoManager`] holds the master key and signs transactions with each user's key.
+//! - [`escrow`] builds the payouts that release or refund an escrowed deal. It also runs the
+//!   task that refunds expired ones.
+//! - [`auction::AuctionManager`] runs live auctions with bid deposits held in escrow.
+//! - [`config`] reads per-guild settings and [`checkpoint`] signs digests of the hash chain.
+//! - [`health::TaskMonitor`] and [`metrics`] report on background tasks and query timings.
+//! - [`fixtures`] builds in-memory databases and funded users for tests.
+//!
+//! Background tasks such as [`escrow::spawn_expirer`] need a Tokio runtime. Everything else is
+//! plain async and works on any executor sqlx supports.
+//!
+//! [`Database::apply_transaction`]: database::Database::apply_transaction
+//! [`Database::apply_transactions`]: database::Database::apply_transactions
+//!
+//! ```
+//! use slumcoin::crypto::CryptoManager;
+//! use slumcoin::database::{Database, Transaction};
+//! use slumcoin::{fixtures, ledger};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // A real deployment opens its file with `Database::new("sqlite:currency.db")`
+//! let database = Database::in_memory().await?;
+//! let crypto = CryptoManager::load("master key", &database).await?;
+//! fixtures::user(&database, &crypto, "alice", 100).await?;
+//! fixtures::user(&database, &crypto, "bob", 0).await?;
+//!
+//! // Check a transfer, including this guild's fee, without writing anything
+//! let fees = ledger::FeeSchedule::for_guild(&database, "1234").await?;
+//! let preview = ledger::simulate_transfer(&database, "alice", "bob", 25, fees).await?;
+//! println!("alice would have {} left after a {} coin fee", preview.sender_balance_after, preview.fee);
+//!
+//! // Sign and record it, then pay bob a reward out of the treasury the fee went into
+//! ledger::execute_transfer(&database, &crypto, "alice", "bob", 25, fees, None).await?;
+//! fixtures::fund(&database, ledger::TREASURY_ACCOUNT, 10).await?;
+//! let payout = Transaction::system(ledger::TREASURY_ACCOUNT, "bob", 10, "reward", None);
+//! database.apply_transaction(&payout).await?;
+//! assert_eq!(database.get_balance("bob").await?, 35);
+//!
+//! // Verify every signature and the hash chain, and recompute balances from the ledger
+//! let report = ledger::audit(&database, &crypto).await?;
+//! assert!(report.is_clean());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod auction;
+pub mod balance_cache;
+pub mod checkpoint;
+pub mod config;
+pub mod crypto;
+pub mod database;
+pub mod escrow;
+pub mod fixtures;
+pub mod health;
+pub mod ledger;
+pub mod metrics;
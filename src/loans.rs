@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{debug, error, info};
+
+use crate::config;
+use crate::database::{Database, Loan, Transaction};
+use crate::health::TaskMonitor;
+use crate::ledger::TREASURY_ACCOUNT;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_ACTIVE: &str = "active";
+pub const STATUS_REPAID: &str = "repaid";
+pub const STATUS_DEFAULTED: &str = "defaulted";
+
+const COLLECT_TICK_SECONDS: u64 = 300;
+
+/// Total owed on a loan of `principal` at `interest_percent`, rounded up
+pub fn total_due(principal: i64, interest_percent: i64) -> i64 {
+    principal + (principal * interest_percent.max(0) + 99) / 100
+}
+
+/// Size of each installment so `installments` payments cover the total, rounded up
+pub fn installment(total_due: i64, installments: i64) -> i64 {
+    let installments = installments.max(1);
+    (total_due + installments - 1) / installments
+}
+
+/// Pay the loan out of the treasury to the borrower
+pub fn disbursement(loan: &Loan) -> Transaction {
+    Transaction::system(
+        TREASURY_ACCOUNT,
+        &loan.borrower,
+        loan.principal,
+        "loan",
+        Some(format!("Loan #{}", loan.id)),
+    )
+}
+
+/// A repayment from the borrower back to the treasury
+pub fn repayment(loan: &Loan, amount: i64) -> Transaction {
+    Transaction::system(
+        &loan.borrower,
+        TREASURY_ACCOUNT,
+        amount,
+        "loan_repayment",
+        Some(format!("Repayment on loan #{}", loan.id)),
+    )
+}
+
+async fn notify_borrower(http: &serenity::Http, loan: &Loan, content: String) {
+    let Ok(borrower) = loan.borrower.parse::<u64>() else {
+        return;
+    };
+    let dm = serenity::CreateMessage::new().content(content);
+
+    // Borrowers with DMs closed just don't get the notice
+    if let Err(e) = serenity::UserId::new(borrower).direct_message(http, dm).await {
+        debug!("Couldn't DM loan notice to {}: {}", loan.borrower, e);
+    }
+}
+
+async fn collect(http: &serenity::Http, database: &Database, loan: &Loan) {
+    let Some(due_at) = loan.next_payment_at else {
+        return;
+    };
+    let amount = loan.installment.min(loan.outstanding());
+    let next_payment_at = due_at + loan.payment_interval_seconds;
+
+    let balance = match database.get_balance(&loan.borrower).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            error!("Failed to load balance for loan #{}: {}", loan.id, e);
+            return;
+        }
+    };
+
+    if balance >= amount {
+        let payment = repayment(loan, amount);
+        let status = if loan.repaid + amount >= loan.total_due { STATUS_REPAID } else { STATUS_ACTIVE };
+        match database.record_loan_installment(loan, Some(&payment), next_payment_at, status).await {
+            Ok(true) if status == STATUS_REPAID => {
+                info!("Loan #{} repaid in full", loan.id);
+                notify_borrower(http, loan, format!("✅ Your loan `#{}` is fully repaid. Thanks!", loan.id)).await;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to collect payment on loan #{}: {}", loan.id, e),
+        }
+        return;
+    }
+
+    let max_missed = match config::get_i64(database, &loan.guild_id, "loan.max_missed_payments").await {
+        Ok(max_missed) => max_missed.max(1),
+        Err(e) => {
+            error!("Failed to load loan settings for guild {}: {}", loan.guild_id, e);
+            return;
+        }
+    };
+    let defaulted = loan.missed_payments + 1 >= max_missed;
+    let status = if defaulted { STATUS_DEFAULTED } else { STATUS_ACTIVE };
+
+    match database.record_loan_installment(loan, None, next_payment_at, status).await {
+        Ok(true) if defaulted => {
+            info!("Loan #{} defaulted after {} missed payments", loan.id, loan.missed_payments + 1);
+            notify_borrower(http, loan, format!(
+                "🚩 You've defaulted on loan `#{}` with **{} Slumcoins** still owed. \
+                You'll be marked on the leaderboard until you pay it off with `/loan repay`.",
+                loan.id,
+                loan.outstanding()
+            )).await;
+        }
+        Ok(true) => {
+            notify_borrower(http, loan, format!(
+                "⚠️ You missed a **{} Slumcoin** payment on loan `#{}`. Next attempt <t:{}:R>. \
+                {} more missed payment(s) and the loan defaults.",
+                amount,
+                loan.id,
+                next_payment_at,
+                max_missed - loan.missed_payments - 1
+            )).await;
+        }
+        Ok(false) => {}
+        Err(e) => error!("Failed to record missed payment on loan #{}: {}", loan.id, e),
+    }
+}
+
+/// Collect every loan installment that has come due
+pub fn spawn_collector(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(COLLECT_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("loans", Duration::from_secs(COLLECT_TICK_SECONDS));
+
+            let due = match database.get_due_loans(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due loans: {}", e);
+                    continue;
+                }
+            };
+
+            for loan in due {
+                collect(&http, &database, &loan).await;
+            }
+        }
+    });
+}
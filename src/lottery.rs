@@ -0,0 +1,113 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use rand::Rng;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::config;
+use crate::database::{Database, DatabaseError, LotteryRound, Transaction};
+use crate::health::TaskMonitor;
+
+// Holds ticket sales until the round is drawn
+pub const LOTTERY_POT_ACCOUNT: &str = "LOTTERY_POT";
+
+const DRAW_TICK_SECONDS: u64 = 60;
+
+/// Pick a winner with probability proportional to the number of tickets held
+pub fn pick_winner(tickets: &[(String, i64)]) -> Option<String> {
+    let total: i64 = tickets.iter().map(|(_, count)| count).sum();
+    if total <= 0 {
+        return None;
+    }
+
+    let mut roll = rand::thread_rng().gen_range(0..total);
+    for (discord_id, count) in tickets {
+        if roll < *count {
+            return Some(discord_id.clone());
+        }
+        roll -= count;
+    }
+
+    None
+}
+
+/// Get the guild's open round, starting a new one if none is running
+pub async fn current_round(database: &Database, guild_id: &str) -> Result<LotteryRound, DatabaseError> {
+    if let Some(round) = database.get_open_lottery_round(guild_id).await? {
+        return Ok(round);
+    }
+
+    let ticket_price = config::get_i64(database, guild_id, "lottery.ticket_price").await?;
+    let interval_hours = config::get_i64(database, guild_id, "lottery.draw_interval_hours").await?.max(1);
+    let draw_at = Utc::now().timestamp() + interval_hours * 3600;
+
+    database.create_lottery_round(guild_id, ticket_price, draw_at).await
+}
+
+async fn draw_round(http: &serenity::Http, database: &Database, round: &LotteryRound) -> Result<(), DatabaseError> {
+    let tickets = database.get_lottery_tickets(round.id).await?;
+    let pot = database.get_lottery_pot(round.id).await?;
+    let winner = pick_winner(&tickets);
+
+    let payout = winner.as_ref().map(|winner| {
+        Transaction::system(
+            LOTTERY_POT_ACCOUNT,
+            winner,
+            pot,
+            "lottery_win",
+            Some(format!("Lottery round {} winnings", round.id)),
+        )
+    });
+    database.complete_lottery_round(round.id, payout.as_ref()).await?;
+
+    info!("Drew lottery round {} for guild {}", round.id, round.guild_id);
+
+    let channel_id = config::get(database, &round.guild_id, "lottery.channel_id").await?;
+    let Ok(channel_id) = channel_id.parse::<u64>() else {
+        return Ok(());
+    };
+
+    let message = match winner {
+        Some(winner) => {
+            let total_tickets: i64 = tickets.iter().map(|(_, count)| count).sum();
+            format!(
+                "🎟️ **Lottery draw!**\n<@{}> won the pot of **{} Slumcoins** ({} tickets sold)",
+                winner, pot, total_tickets
+            )
+        }
+        None => "🎟️ **Lottery draw!** No tickets were sold this round.".to_string(),
+    };
+
+    if let Err(e) = serenity::ChannelId::new(channel_id).say(http, message).await {
+        error!("Failed to announce lottery draw: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Draw every lottery round whose draw time has passed
+pub fn spawn_drawer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(DRAW_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("lottery", Duration::from_secs(DRAW_TICK_SECONDS));
+
+            let due = match database.get_due_lottery_rounds(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due lottery rounds: {}", e);
+                    continue;
+                }
+            };
+
+            for round in due {
+                if let Err(e) = draw_round(&http, &database, &round).await {
+                    error!("Failed to draw lottery round {}: {}", round.id, e);
+                }
+            }
+        }
+    });
+}
@@ -1,26 +1,68 @@
 use poise::serenity_prelude as serenity;
 use std::env;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::{error, info};
 
-mod database;
-mod crypto;
 mod commands;
 mod funny;
-mod auction;
+mod api;
+mod counterparties;
+mod leaderboard;
+mod lottery;
+mod verifier;
+mod events;
+mod roles;
+mod blackjack;
+mod payments;
+mod bidding;
+mod activity;
+mod payroll;
+mod vault;
+mod loans;
+mod tax;
+mod fraud;
+mod registration;
+mod errorlog;
+mod webhooks;
+mod importer;
+mod backup;
+mod digest;
+mod giveaways;
+mod betting;
+mod properties;
+mod stocks;
+mod paper;
+mod charts;
+mod teams;
+mod marriage;
+mod charity;
+mod perks;
 
-use database::Database;
+use slumcoin::{auction, checkpoint, config, crypto, database, escrow, health, ledger, metrics};
+use database::{Database, DatabaseError, DatabaseOptions};
 use crypto::CryptoManager;
 use auction::AuctionManager;
+use counterparties::CounterpartyCache;
+use health::TaskMonitor;
+use funny::TriggerCache;
+use activity::ActivityTracker;
 use commands::*;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
+type ApplicationContext<'a> = poise::ApplicationContext<'a, Data, Error>;
 
 #[derive(Debug)]
 pub struct Data {
     database: Database,
-    crypto: CryptoManager,
-    auction_manager: AuctionManager
+    crypto: Arc<CryptoManager>,
+    auction_manager: AuctionManager,
+    counterparties: CounterpartyCache,
+    task_monitor: TaskMonitor,
+    triggers: TriggerCache,
+    activity: ActivityTracker,
+    started_at: Instant,
 }
 
 #[tokio::main]
@@ -35,32 +77,122 @@ async fn main() {
     let database_url = env::var("DATABASE_URL")
         .unwrap_or_else(|_| "sqlite:currency.db".to_string());
 
-    let database = Database::new(&database_url)
+    // Setting DATABASE_RESTORE_FROM to a file from /backup or BACKUP_DIR replaces the database with
+    // it on startup. Unset it once the bot is back up, or every restart restores the same backup.
+    if let Ok(backup_path) = env::var("DATABASE_RESTORE_FROM") {
+        backup::restore(&database_url, std::path::Path::new(&backup_path))
+            .expect("Failed to restore database backup");
+    }
+
+    // DATABASE_WAL=false, DATABASE_BUSY_TIMEOUT_MS and DATABASE_MAX_CONNECTIONS tune the pool
+    let defaults = DatabaseOptions::default();
+    let database_options = DatabaseOptions {
+        wal: env::var("DATABASE_WAL").ok().and_then(|wal| wal.parse().ok()).unwrap_or(defaults.wal),
+        busy_timeout: env::var("DATABASE_BUSY_TIMEOUT_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(std::time::Duration::from_millis)
+            .unwrap_or(defaults.busy_timeout),
+        max_connections: env::var("DATABASE_MAX_CONNECTIONS")
+            .ok()
+            .and_then(|max| max.parse().ok())
+            .unwrap_or(defaults.max_connections),
+    };
+
+    let database = Database::connect(&database_url, database_options)
         .await
         .expect("Failed to connect to database");
 
     let crypto_key = env::var("CRYPTO_MASTER_KEY")
         .unwrap_or_else(|_| "default_dev_key_change_in_production".to_string());
 
-    let crypto = CryptoManager::new(&crypto_key)
-        .expect("Failed to initialize crypto manager");
+    // Setting CRYPTO_NEW_MASTER_KEY re-encrypts every private key with it on startup. Once that's
+    // logged, move the new key into CRYPTO_MASTER_KEY and unset CRYPTO_NEW_MASTER_KEY.
+    let crypto = match env::var("CRYPTO_NEW_MASTER_KEY") {
+        Ok(new_key) => CryptoManager::rotate(&crypto_key, &new_key, &database)
+            .await
+            .expect("Failed to rotate master key"),
+        Err(_) => CryptoManager::load(&crypto_key, &database)
+            .await
+            .expect("Failed to initialize crypto manager"),
+    };
+    let crypto = Arc::new(crypto);
+
+    // Auctions don't survive a restart, so hand back any deposits they were holding
+    match auction::refund_stranded_deposits(&database).await {
+        Ok(0) => {}
+        Ok(count) => info!("Refunded {} auction deposits left over from before the restart", count),
+        Err(e) => error!("Failed to refund leftover auction deposits: {}", e),
+    }
+
+    let member_intent = registration::needs_member_intent(&database).await;
 
     let auction_manager = AuctionManager::new();
+    let counterparties = CounterpartyCache::new();
+    let task_monitor = TaskMonitor::new();
+    let triggers = TriggerCache::new();
+    let activity = ActivityTracker::new();
+    let started_at = Instant::now();
+
+    // Optional HTTP API for external tooling
+    if let Ok(bind_addr) = env::var("API_BIND_ADDR") {
+        let token = env::var("API_TOKEN").ok();
+        tokio::spawn(api::serve(bind_addr, token, database.clone(), auction_manager.clone()));
+    }
+
+    // Optional Prometheus scrape endpoint
+    if let Ok(bind_addr) = env::var("METRICS_BIND_ADDR") {
+        tokio::spawn(api::serve_metrics(bind_addr, database.clone(), auction_manager.clone()));
+    }
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![register(), balance(), give(), baltop(), bid(), send(), ledger()],
+            commands: vec![register(), unregister(), balance(), rank(), profile(), title(), chart(), give(), airdrop(), baltop(), bid(), auctionhistory(), notifications(), privacy(), wallet(), send(), request(), rain(), deposit(), withdraw(), ledger(), help(), audit(), server_config(), faucet(), daily(), refer(), redeem(), spin(), economy(), coinflip(), roulette(), dice(), highlow(), blackjack(), jackpot(), duel(), rps(), heist(), trivia(), fish(), sell(), property(), collect(), stocks(), portfolio(), paper(), team(), marry(), divorce(), marriage(), donate(), charity(), escrow(), treasury(), lottery(), shop(), buy(), perk(), inventory(), event(), trigger(), code(), payroll(), loan(), freeze(), unfreeze(), reverse(), auditlog(), transferlimit(), gamblimit(), registerbutton(), registerall(), checkpoint(), webhook(), export(), import(), backup(), botstats(), season(), giveaway(), bet()],
+            // The `cooldown` check applies cooldowns itself, with per-guild durations and an admin bypass
+            manual_cooldowns: true,
+            pre_command: |ctx| Box::pin(async move {
+                metrics::record_command(&ctx.command().qualified_name);
+            }),
             prefix_options: poise::PrefixFrameworkOptions {
                 prefix: Some("!".into()),
                 ..Default::default()
             },
-            event_handler: |ctx, event, _framework, _data| {
+            event_handler: |ctx, event, _framework, data| {
                 Box::pin(async move {
+                    // ignore agelbub messages to prevent loops
                     match event {
-                        poise::serenity_prelude::FullEvent::Message { new_message } => {
-                            // ignore agelbub messages to prevent loops
-                            if !new_message.author.bot {
-                                funny::handle_slumduke_messages(ctx, new_message).await;
+                        poise::serenity_prelude::FullEvent::Message { new_message } if !new_message.author.bot => {
+                            data.activity.record(new_message.channel_id, new_message.author.id, chrono::Utc::now().timestamp()).await;
+                            funny::handle_triggers(ctx, new_message, &data.database, &data.triggers).await;
+                        }
+                        poise::serenity_prelude::FullEvent::GuildMemberAddition { new_member } => {
+                            registration::handle_member_join(new_member, data).await;
+                        }
+                        poise::serenity_prelude::FullEvent::InteractionCreate { interaction } => {
+                            if let Some(press) = interaction.as_message_component() {
+                                if press.data.custom_id.starts_with(payments::BUTTON_PREFIX) {
+                                    if let Err(e) = payments::handle_button(ctx, press, data).await {
+                                        error!("Error handling payment request button: {}", e);
+                                    }
+                                } else if press.data.custom_id.starts_with(bidding::BUTTON_PREFIX) {
+                                    if let Err(e) = bidding::handle_button(ctx, press, data).await {
+                                        error!("Error handling bid button: {}", e);
+                                    }
+                                } else if press.data.custom_id.starts_with(registration::BUTTON_PREFIX) {
+                                    if let Err(e) = registration::handle_button(ctx, press, data).await {
+                                        error!("Error handling register button: {}", e);
+                                    }
+                                } else if press.data.custom_id.starts_with(giveaways::BUTTON_PREFIX) {
+                                    if let Err(e) = giveaways::handle_button(ctx, press, data).await {
+                                        error!("Error handling giveaway button: {}", e);
+                                    }
+                                }
+                            } else if let Some(submit) = interaction.as_modal_submit() {
+                                if submit.data.custom_id.starts_with(bidding::BUTTON_PREFIX) {
+                                    if let Err(e) = bidding::handle_modal(ctx, submit, data).await {
+                                        error!("Error handling bid modal: {}", e);
+                                    }
+                                }
                             }
                         }
                         _ => {}
@@ -71,11 +203,28 @@ async fn main() {
             on_error: |error| Box::pin(async move {
                 match error {
                     poise::FrameworkError::Command { error, ctx, .. } => {
-                        error!("Error in command '{}': {}", ctx.command().name, error);
+                        // Overdrafts, missing registrations and lost races are the user's to retry, not bugs
+                        if let Some(message) = error.downcast_ref::<DatabaseError>().and_then(|e| user_error_message(ctx, e)) {
+                            if let Err(e) = ctx.send(poise::CreateReply::default().content(message).ephemeral(true)).await {
+                                error!("Failed to send database error message: {}", e);
+                            }
+                        } else {
+                            metrics::record_command_error(&ctx.command().qualified_name);
+                            error!("Error in command '{}': {}", ctx.command().name, error);
+                            errorlog::report(ctx, "Command error", &error.to_string()).await;
+                        }
+                    }
+                    poise::FrameworkError::CommandCheckFailed { error: Some(error), ctx, .. }
+                        if error.is::<AccountFrozen>() || error.is::<CooldownActive>() =>
+                    {
+                        if let Err(e) = ctx.send(poise::CreateReply::default().content(error.to_string()).ephemeral(true)).await {
+                            error!("Failed to send check refusal message: {}", e);
+                        }
                     }
                     poise::FrameworkError::CommandCheckFailed { error, ctx, .. } => {
                         if let Some(error) = error {
                             error!("Command check failed for '{}': {}", ctx.command().name, error);
+                            errorlog::report(ctx, "Command check failed", &error.to_string()).await;
                         } else {
                             let admin_role_name = std::env::var("ADMIN_ROLE_NAME")
                                 .unwrap_or_else(|_| "Slumbanker".to_string());
@@ -90,29 +239,86 @@ async fn main() {
                             }
                         }
                     }
+                    poise::FrameworkError::GuildOnly { ctx, .. } => {
+                        let response = format!("`/{}` only works in a server.", ctx.command().qualified_name);
+                        if let Err(e) = ctx.say(response).await {
+                            error!("Failed to send guild-only message: {}", e);
+                        }
+                    }
                     error => {
                         error!("Framework error: {:?}", error);
+                        // Cooldowns, bad arguments and the like are the user's doing and aren't worth an admin's attention
+                        let breakage = matches!(
+                            error,
+                            poise::FrameworkError::CommandPanic { .. }
+                                | poise::FrameworkError::CommandStructureMismatch { .. }
+                                | poise::FrameworkError::MissingBotPermissions { .. }
+                        );
+                        if let Some(ctx) = error.ctx().filter(|_| breakage) {
+                            errorlog::report(ctx, "Framework error", &error.to_string()).await;
+                        }
                     }
                 }
             }),
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
                 let guild_id = serenity::GuildId::new(1078723086448349365);
-                poise::builtins::register_in_guild(ctx, &framework.options().commands, guild_id).await?;
+                // Commands that also work in DMs are registered globally so they show up there;
+                // everything guild_only stays registered to the server
+                let (dm_commands, guild_commands): (Vec<_>, Vec<_>) = framework.options().commands
+                    .iter()
+                    .partition(|command| !command.guild_only);
+                serenity::Command::set_global_commands(
+                    ctx,
+                    dm_commands.iter().filter_map(|command| command.create_as_slash_command()).collect(),
+                ).await?;
+                guild_id.set_commands(
+                    ctx,
+                    guild_commands.iter().filter_map(|command| command.create_as_slash_command()).collect(),
+                ).await?;
                                 
                 info!("registered commands to Slumfields {}", guild_id);
+
+                leaderboard::spawn_refresher(ctx.http.clone(), database.clone(), task_monitor.clone());
+                lottery::spawn_drawer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                giveaways::spawn_drawer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                charity::spawn_distributor(ctx.http.clone(), database.clone(), task_monitor.clone());
+                verifier::spawn_verifier(ctx.http.clone(), database.clone(), crypto.clone(), task_monitor.clone());
+                verifier::spawn_checkpointer(database.clone(), crypto.clone(), task_monitor.clone());
+                events::spawn_closer(database.clone(), task_monitor.clone());
+                roles::spawn_expirer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                perks::spawn_topic_restorer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                escrow::spawn_expirer(database.clone(), task_monitor.clone());
+                payroll::spawn_payer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                vault::spawn_interest_payer(database.clone(), task_monitor.clone());
+                properties::spawn_collector(database.clone(), task_monitor.clone());
+                stocks::spawn_ticker(database.clone(), task_monitor.clone());
+                charts::spawn_snapshotter(database.clone(), task_monitor.clone());
+                // PAPER_TRADING=true polls live BTC/ETH prices for /paper
+                if env::var("PAPER_TRADING").is_ok_and(|enabled| enabled == "true") {
+                    paper::spawn_price_feed(database.clone(), task_monitor.clone());
+                }
+                loans::spawn_collector(ctx.http.clone(), database.clone(), task_monitor.clone());
+                tax::spawn_collector(ctx.http.clone(), database.clone(), task_monitor.clone());
+                fraud::spawn_analyzer(ctx.http.clone(), database.clone(), task_monitor.clone());
+                webhooks::spawn_dispatcher(database.clone(), task_monitor.clone());
+                backup::spawn_scheduler(database.clone(), task_monitor.clone());
+                digest::spawn_poster(ctx.http.clone(), database.clone(), task_monitor.clone());
                 
-                Ok(Data { database, crypto, auction_manager })
+                Ok(Data { database, crypto, auction_manager, counterparties, task_monitor, triggers, activity, started_at })
             })
         })
         .build();
 
-    let intents = serenity::GatewayIntents::non_privileged() 
+    let mut intents = serenity::GatewayIntents::non_privileged() 
         | serenity::GatewayIntents::MESSAGE_CONTENT
         | serenity::GatewayIntents::GUILDS           
         | serenity::GatewayIntents::GUILD_VOICE_STATES;
+    if member_intent {
+        intents |= serenity::GatewayIntents::GUILD_MEMBERS;
+    }
 
     let client = serenity::ClientBuilder::new(token, intents)
         .framework(framework)
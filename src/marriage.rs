@@ -0,0 +1,27 @@
+use chrono::{DateTime, Months};
+
+// Ledger account holding every couple's joint account
+pub const JOINT_ACCOUNT: &str = "JOINT_ACCOUNT";
+
+// Proposals nobody answers expire after this long
+pub const PROPOSAL_EXPIRY_SECONDS: u64 = 120;
+
+/// The next anniversary after `now` as (which anniversary, unix timestamp). Anniversaries of a
+/// Feb 29 wedding fall on Feb 28 in other years.
+pub fn next_anniversary(married_at: i64, now: i64) -> Option<(u32, i64)> {
+    let married = DateTime::from_timestamp(married_at, 0)?;
+    let mut years = 1;
+    loop {
+        let anniversary = married.checked_add_months(Months::new(12 * years))?.timestamp();
+        if anniversary > now {
+            return Some((years, anniversary));
+        }
+        years += 1;
+    }
+}
+
+/// How a joint balance splits on divorce: halves, with an odd coin going to whoever didn't file
+pub fn split(balance: i64) -> (i64, i64) {
+    let filer = balance / 2;
+    (filer, balance - filer)
+}
@@ -0,0 +1,159 @@
+//! Process-wide counters and timings, rendered in the Prometheus text format.
+//!
+//! Recording is always on and costs an atomic add or a short lock; nothing is exported unless the
+//! bot is started with a metrics server.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Upper bounds in seconds of the query latency histogram buckets
+const QUERY_BUCKETS: [f64; 10] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0];
+
+static COMMANDS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+static COMMAND_ERRORS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+static QUERIES: Mutex<BTreeMap<&'static str, Histogram>> = Mutex::new(BTreeMap::new());
+
+static AUCTIONS_STARTED: AtomicU64 = AtomicU64::new(0);
+static AUCTIONS_COMPLETED: AtomicU64 = AtomicU64::new(0);
+static AUCTIONS_CANCELLED: AtomicU64 = AtomicU64::new(0);
+static BIDS_PLACED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; QUERY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(QUERY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Count one invocation of a command, by its qualified name
+pub fn record_command(command: &str) {
+    increment(&COMMANDS, command);
+}
+
+/// Count one command that returned an error
+pub fn record_command_error(command: &str) {
+    increment(&COMMAND_ERRORS, command);
+}
+
+fn increment(counters: &Mutex<BTreeMap<String, u64>>, key: &str) {
+    if let Ok(mut counters) = counters.lock() {
+        *counters.entry(key.to_string()).or_default() += 1;
+    }
+}
+
+pub(crate) fn record_auction_started() {
+    AUCTIONS_STARTED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_auction_completed() {
+    AUCTIONS_COMPLETED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_auction_cancelled() {
+    AUCTIONS_CANCELLED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_bid() {
+    BIDS_PLACED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Times a database call from creation until dropped, so early returns and `?` are counted too
+pub(crate) struct QueryTimer {
+    query: &'static str,
+    started: Instant,
+}
+
+pub(crate) fn query_timer(query: &'static str) -> QueryTimer {
+    QueryTimer { query, started: Instant::now() }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        let seconds = self.started.elapsed().as_secs_f64();
+        if let Ok(mut queries) = QUERIES.lock() {
+            queries.entry(self.query).or_default().observe(seconds);
+        }
+    }
+}
+
+/// Point-in-time values read when the metrics are scraped
+#[derive(Debug, Clone, Default)]
+pub struct Gauges {
+    pub total_supply: i64,
+    pub registered_users: i64,
+    pub active_auctions: usize,
+    /// (hits, misses) of the database's balance cache
+    pub balance_cache: (u64, u64),
+}
+
+/// Everything recorded so far plus `gauges`, in the Prometheus text exposition format
+pub fn render(gauges: &Gauges) -> String {
+    let mut out = String::new();
+
+    write_counter_family(&mut out, "slumcoin_commands_total", "Commands invoked", "command", &COMMANDS);
+    write_counter_family(&mut out, "slumcoin_command_errors_total", "Commands that returned an error", "command", &COMMAND_ERRORS);
+
+    write_counter(&mut out, "slumcoin_auctions_started_total", "Auctions started", AUCTIONS_STARTED.load(Ordering::Relaxed));
+    write_counter(&mut out, "slumcoin_auctions_completed_total", "Auctions sold and paid for", AUCTIONS_COMPLETED.load(Ordering::Relaxed));
+    write_counter(&mut out, "slumcoin_auctions_cancelled_total", "Auctions cancelled with refunds", AUCTIONS_CANCELLED.load(Ordering::Relaxed));
+    write_counter(&mut out, "slumcoin_bids_total", "Auction bids accepted", BIDS_PLACED.load(Ordering::Relaxed));
+
+    write_counter(&mut out, "slumcoin_balance_cache_hits_total", "Balance lookups answered from memory", gauges.balance_cache.0);
+    write_counter(&mut out, "slumcoin_balance_cache_misses_total", "Balance lookups that went to the database", gauges.balance_cache.1);
+
+    write_gauge(&mut out, "slumcoin_total_supply", "Coins minted minus coins burned", gauges.total_supply as f64);
+    write_gauge(&mut out, "slumcoin_registered_users", "Registered accounts", gauges.registered_users as f64);
+    write_gauge(&mut out, "slumcoin_active_auctions", "Auctions currently running", gauges.active_auctions as f64);
+
+    let _ = writeln!(out, "# HELP slumcoin_db_query_duration_seconds Database call latency");
+    let _ = writeln!(out, "# TYPE slumcoin_db_query_duration_seconds histogram");
+    if let Ok(queries) = QUERIES.lock() {
+        for (query, histogram) in queries.iter() {
+            for (bucket, bound) in histogram.buckets.iter().zip(QUERY_BUCKETS) {
+                let _ = writeln!(out, "slumcoin_db_query_duration_seconds_bucket{{query=\"{}\",le=\"{}\"}} {}", query, bound, bucket);
+            }
+            let _ = writeln!(out, "slumcoin_db_query_duration_seconds_bucket{{query=\"{}\",le=\"+Inf\"}} {}", query, histogram.count);
+            let _ = writeln!(out, "slumcoin_db_query_duration_seconds_sum{{query=\"{}\"}} {}", query, histogram.sum);
+            let _ = writeln!(out, "slumcoin_db_query_duration_seconds_count{{query=\"{}\"}} {}", query, histogram.count);
+        }
+    }
+
+    out
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, value);
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge\n{} {}", name, help, name, name, value);
+}
+
+fn write_counter_family(out: &mut String, name: &str, help: &str, label: &str, counters: &Mutex<BTreeMap<String, u64>>) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+    if let Ok(counters) = counters.lock() {
+        for (key, value) in counters.iter() {
+            let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, escape_label(key), value);
+        }
+    }
+}
+
+// Label values are quoted, so backslashes, quotes and newlines must be escaped
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,163 @@
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, DatabaseError, PaperPosition};
+use crate::health::TaskMonitor;
+
+const PRICE_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin,ethereum&vs_currencies=usd";
+const FEED_TICK_SECONDS: u64 = 60;
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+// Nothing opens or closes at a price older than this
+pub const PRICE_STALE_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Asset {
+    #[name = "btc"]
+    Btc,
+    #[name = "eth"]
+    Eth,
+}
+
+impl Asset {
+    pub const ALL: [Asset; 2] = [Asset::Btc, Asset::Eth];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Asset::Btc => "btc",
+            Asset::Eth => "eth",
+        }
+    }
+
+    // What the price API calls it
+    fn api_id(self) -> &'static str {
+        match self {
+            Asset::Btc => "bitcoin",
+            Asset::Eth => "ethereum",
+        }
+    }
+}
+
+impl std::fmt::Display for Asset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.key().to_uppercase())
+    }
+}
+
+/// A price in cents as dollars, e.g. `$64,210.55`
+pub fn format_usd(cents: i64) -> String {
+    let dollars = (cents / 100).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in dollars.chars().enumerate() {
+        if i > 0 && (dollars.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("${}.{:02}", grouped, cents % 100)
+}
+
+/// What a position is worth at `price_cents`: its stake plus the leveraged gain or loss, never below zero
+pub fn position_value(position: &PaperPosition, price_cents: i64) -> i64 {
+    let sign: i128 = if position.direction == "short" { -1 } else { 1 };
+    let change = sign * (price_cents - position.entry_price_cents) as i128;
+    let profit = position.stake as i128 * position.leverage as i128 * change / position.entry_price_cents.max(1) as i128;
+    (position.stake as i128 + profit).clamp(0, i64::MAX as i128) as i64
+}
+
+/// The price at which a position is worth nothing and gets liquidated, or None if it can't be reached
+pub fn liquidation_price(position: &PaperPosition) -> Option<i64> {
+    let distance = position.entry_price_cents / position.leverage.max(1);
+    if position.direction == "short" {
+        Some(position.entry_price_cents + distance)
+    } else if position.leverage > 1 {
+        Some(position.entry_price_cents - distance)
+    } else {
+        None
+    }
+}
+
+/// The live price in cents, or None if the feed is off or hasn't updated recently
+pub async fn live_price(database: &Database, asset: Asset) -> Result<Option<i64>, DatabaseError> {
+    let now = Utc::now().timestamp();
+    Ok(database
+        .get_crypto_price(asset.key())
+        .await?
+        .filter(|(_, updated_at)| now - updated_at <= PRICE_STALE_SECONDS)
+        .map(|(price_cents, _)| price_cents))
+}
+
+async fn fetch_prices(client: &reqwest::Client) -> Result<Vec<(Asset, i64)>, String> {
+    let response = client
+        .get(PRICE_URL)
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+
+    let body: serde_json::Value = response.json().await.map_err(|e| e.without_url().to_string())?;
+    Ok(Asset::ALL
+        .into_iter()
+        .filter_map(|asset| {
+            let dollars = body[asset.api_id()]["usd"].as_f64()?;
+            (dollars > 0.0).then(|| (asset, (dollars * 100.0).round() as i64))
+        })
+        .collect())
+}
+
+// Close every open position a new price has wiped out
+async fn liquidate(database: &Database, asset: Asset, price_cents: i64) -> Result<(), DatabaseError> {
+    let now = Utc::now().timestamp();
+    for position in database.get_open_paper_positions(None).await? {
+        if position.asset != asset.key() || position_value(&position, price_cents) > 0 {
+            continue;
+        }
+        if database.close_paper_position(position.id, "liquidated", price_cents, now, None).await? {
+            info!("Liquidated {}'s paper position #{} at {}", position.discord_id, position.id, format_usd(price_cents));
+        }
+    }
+    Ok(())
+}
+
+/// Poll live prices for paper trading and liquidate positions they wipe out
+pub fn spawn_price_feed(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to build price feed client, paper trading is disabled: {}", e);
+                return;
+            }
+        };
+
+        let mut ticker = interval(Duration::from_secs(FEED_TICK_SECONDS));
+        loop {
+            ticker.tick().await;
+            monitor.beat("paper", Duration::from_secs(FEED_TICK_SECONDS));
+
+            let prices = match fetch_prices(&client).await {
+                Ok(prices) => prices,
+                Err(e) => {
+                    warn!("Failed to fetch crypto prices: {}", e);
+                    continue;
+                }
+            };
+
+            let now = Utc::now().timestamp();
+            for (asset, price_cents) in prices {
+                if let Err(e) = database.set_crypto_price(asset.key(), price_cents, now).await {
+                    error!("Failed to store {} price: {}", asset, e);
+                    continue;
+                }
+                if let Err(e) = liquidate(&database, asset, price_cents).await {
+                    error!("Failed to liquidate {} positions: {}", asset, e);
+                }
+            }
+        }
+    });
+}
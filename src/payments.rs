@@ -0,0 +1,191 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::commands::AccountFrozen;
+use crate::database::PaymentRequest;
+use crate::ledger::{self, FeeSchedule, LedgerError};
+use crate::Data;
+
+// Buttons on request embeds are handled from the event handler so they keep working after restarts
+pub const BUTTON_PREFIX: &str = "payreq:";
+
+pub fn request_embed(request: &PaymentRequest, status: &str) -> serenity::CreateEmbed {
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("💸 Payment request #{}", request.id))
+        .description(format!(
+            "<@{}> requests **{} Slumcoins** from <@{}>",
+            request.requester, request.amount, request.payer
+        ))
+        .field("Status", status, false)
+        .color(0x2ecc71);
+
+    if let Some(reason) = &request.reason {
+        embed = embed.field("Reason", reason, false);
+    }
+
+    embed
+}
+
+pub fn request_buttons(request_id: i64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}:pay", BUTTON_PREFIX, request_id))
+            .label("Pay")
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{}{}:decline", BUTTON_PREFIX, request_id))
+            .label("Decline")
+            .style(serenity::ButtonStyle::Danger),
+    ])]
+}
+
+/// Handle a Pay or Decline press on a payment request embed
+pub async fn handle_button(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), serenity::Error> {
+    let Some((request_id, action)) = press.data.custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .and_then(|(id, action)| Some((id.parse::<i64>().ok()?, action)))
+    else {
+        return Ok(());
+    };
+
+    let request = match data.database.get_payment_request(request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return reply_ephemeral(ctx, press, "This request no longer exists.").await,
+        Err(e) => {
+            error!("Error loading payment request {}: {}", request_id, e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    };
+
+    if press.user.id.to_string() != request.payer {
+        return reply_ephemeral(ctx, press, "This request isn't addressed to you.").await;
+    }
+
+    if request.status != "pending" {
+        return reply_ephemeral(ctx, press, &format!("This request is already {}.", request.status)).await;
+    }
+
+    let now = Utc::now().timestamp();
+    if request.expires_at <= now {
+        if let Err(e) = data.database.close_payment_request(request.id, "expired").await {
+            error!("Error expiring payment request {}: {}", request.id, e);
+        }
+        return update_request(ctx, press, &request, "⌛ Expired").await;
+    }
+
+    if action == "decline" {
+        return match data.database.close_payment_request(request.id, "declined").await {
+            Ok(_) => update_request(ctx, press, &request, &format!("❌ Declined by <@{}>", request.payer)).await,
+            Err(e) => {
+                error!("Error declining payment request {}: {}", request.id, e);
+                reply_ephemeral(ctx, press, "Database error occurred.").await
+            }
+        };
+    }
+
+    match data.database.is_frozen(&request.payer).await {
+        Ok(false) => {}
+        Ok(true) => return reply_ephemeral(ctx, press, &AccountFrozen.to_string()).await,
+        Err(e) => {
+            error!("Error checking if {} is frozen: {}", request.payer, e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    }
+
+    // Same signed transfer as /send, recorded together with the request's status change
+    let message = Some(match &request.reason {
+        Some(reason) => format!("Payment request #{}: {}", request.id, reason),
+        None => format!("Payment request #{}", request.id),
+    });
+    match ledger::check_daily_limit(&data.database, &request.guild_id, &request.payer, request.amount).await {
+        Ok(()) => {}
+        Err(LedgerError::DailyLimitExceeded { limit, remaining }) => {
+            return reply_ephemeral(ctx, press, &format!(
+                "That's over your daily limit of {} Slumcoins. You can send {} more today.", limit, remaining
+            )).await;
+        }
+        Err(e) => {
+            error!("Error checking daily limit for {}: {}", request.payer, e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    }
+
+    let fees = match FeeSchedule::for_guild(&data.database, &request.guild_id).await {
+        Ok(fees) => fees,
+        Err(e) => {
+            error!("Error loading transfer fees: {}", e);
+            return reply_ephemeral(ctx, press, "Database error occurred.").await;
+        }
+    };
+    let transfer = ledger::prepare_transfer(
+        &data.database,
+        &data.crypto,
+        &request.payer,
+        &request.requester,
+        request.amount,
+        fees,
+        message,
+    ).await;
+
+    let (preview, entries) = match transfer {
+        Ok(prepared) => prepared,
+        Err(LedgerError::NotRegistered(id)) if id == request.payer => {
+            return reply_ephemeral(ctx, press, "You're not registered! Use `/register` first.").await;
+        }
+        Err(LedgerError::NotRegistered(_)) => {
+            return reply_ephemeral(ctx, press, "The requester is no longer registered.").await;
+        }
+        Err(LedgerError::InsufficientFunds { balance, .. }) => {
+            return reply_ephemeral(ctx, press, &format!("UR BROKE BUB! You have {} Slumcoins", balance)).await;
+        }
+        Err(e) => {
+            error!("Error preparing payment for request {}: {}", request.id, e);
+            return reply_ephemeral(ctx, press, "Payment failed. Please try again.").await;
+        }
+    };
+
+    match data.database.settle_payment_request(request.id, now, &entries).await {
+        Ok(true) => {
+            data.counterparties.invalidate(&[&request.payer, &request.requester]).await;
+            let mut status = format!("✅ Paid by <@{}>", request.payer);
+            if preview.fee > 0 {
+                status.push_str(&format!(" · fee: {} Slumcoins", preview.fee));
+            }
+            update_request(ctx, press, &request, &status).await
+        }
+        Ok(false) => reply_ephemeral(ctx, press, "This request was already closed.").await,
+        Err(e) => {
+            error!("Error settling payment request {}: {}", request.id, e);
+            reply_ephemeral(ctx, press, "Payment failed. Please try again.").await
+        }
+    }
+}
+
+async fn update_request(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    request: &PaymentRequest,
+    status: &str,
+) -> Result<(), serenity::Error> {
+    press.create_response(ctx, serenity::CreateInteractionResponse::UpdateMessage(
+        serenity::CreateInteractionResponseMessage::new()
+            .embed(request_embed(request, status))
+            .components(Vec::new()),
+    )).await
+}
+
+async fn reply_ephemeral(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), serenity::Error> {
+    press.create_response(ctx, serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )).await
+}
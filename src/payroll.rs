@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, Payroll, Transaction, SYSTEM_ACCOUNT};
+use crate::health::TaskMonitor;
+
+const PAYROLL_TICK_SECONDS: u64 = 60;
+// Largest page Discord returns when listing guild members
+const MEMBER_PAGE_SIZE: u64 = 1000;
+
+/// Every non-bot member of a guild, fetched a page at a time. Needs the Server Members intent enabled for the bot.
+pub async fn guild_members(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+) -> Result<Vec<serenity::Member>, serenity::Error> {
+    let mut members = Vec::new();
+    let mut after = None;
+
+    loop {
+        let page = guild_id.members(http, Some(MEMBER_PAGE_SIZE), after).await?;
+        let full = page.len() as u64 >= MEMBER_PAGE_SIZE;
+        after = page.last().map(|member| member.user.id);
+        members.extend(page.into_iter().filter(|member| !member.user.bot));
+
+        if !full {
+            return Ok(members);
+        }
+    }
+}
+
+/// Non-bot members of a guild that `keep` accepts
+pub async fn members_where(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    keep: impl Fn(&serenity::Member) -> bool,
+) -> Result<Vec<serenity::UserId>, serenity::Error> {
+    Ok(guild_members(http, guild_id)
+        .await?
+        .iter()
+        .filter(|member| keep(member))
+        .map(|member| member.user.id)
+        .collect())
+}
+
+/// Non-bot members of a guild holding the role
+pub async fn role_members(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    role_id: serenity::RoleId,
+) -> Result<Vec<serenity::UserId>, serenity::Error> {
+    members_where(http, guild_id, |member| member.roles.contains(&role_id)).await
+}
+
+/// When a payroll pays next after a run; a bot that was down pays once and picks the schedule back up from now
+fn next_payout_after(payroll: &Payroll, now_unix: i64) -> i64 {
+    let next = payroll.next_payout_at + payroll.interval_seconds;
+    if next <= now_unix {
+        now_unix + payroll.interval_seconds
+    } else {
+        next
+    }
+}
+
+async fn pay(http: &serenity::Http, database: &Database, payroll: &Payroll) {
+    let (Ok(guild_id), Ok(role_id)) = (payroll.guild_id.parse::<u64>(), payroll.role_id.parse::<u64>()) else {
+        let _ = database.remove_payroll(&payroll.guild_id, &payroll.role_id).await;
+        return;
+    };
+
+    // Leave the payroll due so it's retried next tick
+    let members = match role_members(http, serenity::GuildId::new(guild_id), serenity::RoleId::new(role_id)).await {
+        Ok(members) => members,
+        Err(e) => {
+            warn!("Failed to list members for payroll role {} in guild {}: {}", payroll.role_id, payroll.guild_id, e);
+            return;
+        }
+    };
+
+    let mut salaries = Vec::new();
+    let mut skipped = Vec::new();
+    for member in members {
+        let member = member.to_string();
+        match database.get_user(&member).await {
+            Ok(Some(_)) => salaries.push(Transaction::system(
+                SYSTEM_ACCOUNT,
+                &member,
+                payroll.amount,
+                "salary",
+                Some(format!("Salary for role {}", payroll.role_id)),
+            )),
+            Ok(None) => skipped.push(member),
+            Err(e) => {
+                error!("Failed to check registration for payroll: {}", e);
+                return;
+            }
+        }
+    }
+
+    let next_payout_at = next_payout_after(payroll, Utc::now().timestamp());
+    match database.record_payroll_run(payroll, next_payout_at, &salaries, &skipped).await {
+        Ok(true) => info!(
+            "Paid payroll for role {} in guild {}: {} paid, {} unregistered skipped",
+            payroll.role_id,
+            payroll.guild_id,
+            salaries.len(),
+            skipped.len()
+        ),
+        Ok(false) => {}
+        Err(e) => error!("Failed to record payroll for role {}: {}", payroll.role_id, e),
+    }
+}
+
+/// Pay every payroll whose next payout has come due
+pub fn spawn_payer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(PAYROLL_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("payroll", Duration::from_secs(PAYROLL_TICK_SECONDS));
+
+            let due = match database.get_due_payrolls(Utc::now().timestamp()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load due payrolls: {}", e);
+                    continue;
+                }
+            };
+
+            for payroll in due {
+                pay(&http, &database, &payroll).await;
+            }
+        }
+    });
+}
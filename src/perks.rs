@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::{Database, TopicPerk};
+use crate::health::TaskMonitor;
+
+// Discord's own limits
+pub const MAX_NICKNAME_LENGTH: usize = 32;
+pub const MAX_TOPIC_LENGTH: usize = 1024;
+
+// A bought channel topic stays up this long
+pub const TOPIC_SECONDS: i64 = 86400;
+
+const RESTORE_TICK_SECONDS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Perk {
+    #[name = "nickname"]
+    Nickname,
+    #[name = "pin"]
+    Pin,
+    #[name = "topic"]
+    Topic,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 3] = [Perk::Nickname, Perk::Pin, Perk::Topic];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Perk::Nickname => "nickname",
+            Perk::Pin => "pin",
+            Perk::Topic => "topic",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Perk> {
+        Self::ALL.into_iter().find(|perk| perk.key() == key)
+    }
+
+    pub fn command(self) -> &'static str {
+        match self {
+            Perk::Nickname => "/perk nickname",
+            Perk::Pin => "/perk pin",
+            Perk::Topic => "/perk topic",
+        }
+    }
+
+    // What the bot needs to fulfil it
+    pub fn permission(self) -> &'static str {
+        match self {
+            Perk::Nickname => "Manage Nicknames and a role above the member",
+            Perk::Pin => "Manage Messages in that channel",
+            Perk::Topic => "Manage Channels on the auction channel",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PerkError {
+    // The bot lacks the permission the perk needs, or the member outranks it
+    MissingPermissions,
+    Http(serenity::Error),
+}
+
+impl std::fmt::Display for PerkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PerkError::MissingPermissions => write!(f, "Missing permissions to fulfil the perk"),
+            PerkError::Http(e) => write!(f, "Discord error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PerkError {}
+
+impl From<serenity::Error> for PerkError {
+    fn from(err: serenity::Error) -> Self {
+        match err {
+            serenity::Error::Http(ref e) if e.status_code() == Some(serenity::StatusCode::FORBIDDEN) => {
+                PerkError::MissingPermissions
+            }
+            err => PerkError::Http(err),
+        }
+    }
+}
+
+pub async fn set_nickname(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    nickname: &str,
+    reason: &str,
+) -> Result<(), PerkError> {
+    guild_id.edit_member(http, user_id, serenity::EditMember::new().nickname(nickname).audit_log_reason(reason)).await?;
+    Ok(())
+}
+
+pub async fn pin(
+    http: &serenity::Http,
+    channel_id: serenity::ChannelId,
+    message_id: serenity::MessageId,
+    reason: &str,
+) -> Result<(), PerkError> {
+    http.pin_message(channel_id, message_id, Some(reason)).await?;
+    Ok(())
+}
+
+pub async fn set_topic(
+    http: &serenity::Http,
+    channel_id: serenity::ChannelId,
+    topic: &str,
+    reason: &str,
+) -> Result<(), PerkError> {
+    channel_id.edit(http, serenity::EditChannel::new().topic(topic).audit_log_reason(reason)).await?;
+    Ok(())
+}
+
+/// A channel's current topic, empty if it has none
+pub async fn current_topic(http: &serenity::Http, channel_id: serenity::ChannelId) -> Result<String, PerkError> {
+    let channel = channel_id.to_channel(http).await?;
+    Ok(channel.guild().and_then(|channel| channel.topic).unwrap_or_default())
+}
+
+async fn restore(http: &serenity::Http, perk: &TopicPerk) -> Result<(), PerkError> {
+    let Ok(channel_id) = perk.channel_id.parse::<u64>() else {
+        return Ok(());
+    };
+    let channel_id = serenity::ChannelId::new(channel_id);
+
+    // Leave the topic alone if someone has already replaced the bought one
+    if current_topic(http, channel_id).await? != perk.topic {
+        return Ok(());
+    }
+    set_topic(http, channel_id, &perk.original_topic, "Bought topic expired").await
+}
+
+/// Put channel topics bought with the topic perk back once their day is up
+pub fn spawn_topic_restorer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(RESTORE_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("perks", Duration::from_secs(RESTORE_TICK_SECONDS));
+
+            let expired = match database.get_expired_topic_perks(Utc::now().timestamp()).await {
+                Ok(expired) => expired,
+                Err(e) => {
+                    error!("Failed to load expired topic perks: {}", e);
+                    continue;
+                }
+            };
+
+            for perk in expired {
+                match restore(&http, &perk).await {
+                    Ok(()) => info!("Restored the topic of channel {}", perk.channel_id),
+                    Err(PerkError::MissingPermissions) => {
+                        // Keep the perk so it's retried once permissions are fixed
+                        warn!("Missing permissions to restore the topic of channel {} in guild {}", perk.channel_id, perk.guild_id);
+                        continue;
+                    }
+                    Err(e) => warn!("Failed to restore the topic of channel {}: {}", perk.channel_id, e),
+                }
+
+                if let Err(e) = database.remove_topic_perk(perk.id).await {
+                    error!("Failed to clear topic perk {}: {}", perk.id, e);
+                }
+            }
+        }
+    });
+}
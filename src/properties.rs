@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::config;
+use crate::database::{Database, DatabaseError, Property, Transaction, SYSTEM_ACCOUNT};
+use crate::health::TaskMonitor;
+
+pub const MAX_TIER: i64 = 5;
+// Uncollected income stops piling up after this long
+pub const STORAGE_SECONDS: i64 = 2 * 86400;
+
+const COLLECT_TICK_SECONDS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum PropertyKind {
+    #[name = "farm"]
+    Farm,
+    #[name = "mine"]
+    Mine,
+    #[name = "arcade"]
+    Arcade,
+}
+
+impl PropertyKind {
+    pub const ALL: [PropertyKind; 3] = [PropertyKind::Farm, PropertyKind::Mine, PropertyKind::Arcade];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            PropertyKind::Farm => "farm",
+            PropertyKind::Mine => "mine",
+            PropertyKind::Arcade => "arcade",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<PropertyKind> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PropertyKind::Farm => "🌾 Farm",
+            PropertyKind::Mine => "⛏️ Mine",
+            PropertyKind::Arcade => "🕹️ Arcade",
+        }
+    }
+
+    pub fn price(self) -> i64 {
+        match self {
+            PropertyKind::Farm => 500,
+            PropertyKind::Mine => 2_000,
+            PropertyKind::Arcade => 5_000,
+        }
+    }
+
+    // Slumcoins an hour at tier 1, before `property.income_percent`
+    fn base_income(self) -> i64 {
+        match self {
+            PropertyKind::Farm => 5,
+            PropertyKind::Mine => 22,
+            PropertyKind::Arcade => 60,
+        }
+    }
+
+    /// What it costs to raise a property from `tier` to the next one
+    pub fn upgrade_cost(self, tier: i64) -> i64 {
+        self.price() * tier
+    }
+
+    /// Slumcoins an hour at `tier`, scaled by the guild's income percent
+    pub fn hourly_income(self, tier: i64, income_percent: i64) -> i64 {
+        self.base_income() * tier * income_percent.max(0) / 100
+    }
+}
+
+impl std::fmt::Display for PropertyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Income a property has earned since it was last collected, capped at `STORAGE_SECONDS` worth
+pub fn pending_income(property: &Property, income_percent: i64, now: i64) -> i64 {
+    let Some(kind) = PropertyKind::from_key(&property.kind) else {
+        return 0;
+    };
+    let elapsed = (now - property.last_collected_at).clamp(0, STORAGE_SECONDS);
+    elapsed * kind.hourly_income(property.tier, income_percent) / 3600
+}
+
+/// The ledger entry minting `amount` of a property's income to its owner
+pub fn income_transaction(property: &Property, amount: i64) -> Transaction {
+    let label = PropertyKind::from_key(&property.kind).map(PropertyKind::label).unwrap_or("Property");
+    Transaction::system(
+        SYSTEM_ACCOUNT,
+        &property.discord_id,
+        amount,
+        "property_income",
+        Some(format!("{} income (tier {})", label, property.tier)),
+    )
+}
+
+/// Collect a property's pending income. Returns what was paid, or None if there was nothing
+/// to pay yet or it was collected in the meantime.
+pub async fn collect(database: &Database, property: &Property, income_percent: i64) -> Result<Option<i64>, DatabaseError> {
+    let now = Utc::now().timestamp();
+    let amount = pending_income(property, income_percent, now);
+    // Leave the clock running until at least a coin has built up
+    if amount <= 0 {
+        return Ok(None);
+    }
+
+    let collected = database.collect_property(property, now, &income_transaction(property, amount)).await?;
+    Ok(collected.then_some(amount))
+}
+
+/// Collect income for owners who haven't for `property.auto_collect_hours`
+pub fn spawn_collector(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(COLLECT_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("properties", Duration::from_secs(COLLECT_TICK_SECONDS));
+
+            let now = Utc::now().timestamp();
+            let due = match database.get_properties_uncollected_since(now - COLLECT_TICK_SECONDS as i64).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load properties to collect: {}", e);
+                    continue;
+                }
+            };
+
+            // (auto_collect_hours, income_percent) per guild
+            let mut settings: HashMap<String, (i64, i64)> = HashMap::new();
+            for property in due {
+                if !settings.contains_key(&property.guild_id) {
+                    let loaded = async {
+                        let hours = config::get_i64(&database, &property.guild_id, "property.auto_collect_hours").await?;
+                        let percent = config::get_i64(&database, &property.guild_id, "property.income_percent").await?;
+                        Ok::<_, DatabaseError>((hours, percent))
+                    }.await;
+                    match loaded {
+                        Ok(loaded) => settings.insert(property.guild_id.clone(), loaded),
+                        Err(e) => {
+                            error!("Failed to load property settings for guild {}: {}", property.guild_id, e);
+                            continue;
+                        }
+                    };
+                }
+                let (hours, percent) = settings[&property.guild_id];
+                if hours <= 0 || now - property.last_collected_at < hours * 3600 {
+                    continue;
+                }
+
+                match collect(&database, &property, percent).await {
+                    Ok(Some(amount)) => info!("Auto-collected {} from {}'s {} in guild {}", amount, property.discord_id, property.kind, property.guild_id),
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to auto-collect property {}: {}", property.id, e),
+                }
+            }
+        }
+    });
+}
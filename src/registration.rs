@@ -0,0 +1,158 @@
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use std::collections::HashSet;
+use tracing::{error, info};
+
+use crate::config;
+use crate::crypto::{CryptoError, CryptoManager};
+use crate::database::{Database, User};
+use crate::{payroll, Data, Error};
+
+// The Register button is handled from the event handler so pinned messages keep working after restarts
+pub const BUTTON_PREFIX: &str = "register:";
+
+/// Generate and store a keypair for a new user, or restore their archived account.
+/// Returns false if they were already registered.
+pub async fn register_user(
+    database: &Database,
+    crypto: &CryptoManager,
+    discord_id: &str,
+    username: &str,
+) -> Result<bool, CryptoError> {
+    if database.get_user(discord_id).await?.is_some() {
+        return Ok(false);
+    }
+
+    // Unregistered accounts come back with their old keys so their past signatures still verify
+    if database.restore_archived_user(discord_id, username).await? {
+        return Ok(true);
+    }
+
+    let (public_key, private_key) = crypto.generate_keypair()?;
+    let encrypted_private_key = crypto.encrypt_private_key(&private_key, discord_id)?;
+    let user = User {
+        discord_id: discord_id.to_string(),
+        username: username.to_string(),
+        public_key,
+        encrypted_private_key,
+        nonce: 0,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    database.create_user(&user).await?;
+    Ok(true)
+}
+
+#[derive(Debug, Default)]
+pub struct BulkRegistration {
+    pub created: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// Register every member of a guild who isn't registered yet
+pub async fn register_members(
+    http: &serenity::Http,
+    database: &Database,
+    crypto: &CryptoManager,
+    guild_id: serenity::GuildId,
+) -> Result<BulkRegistration, Error> {
+    let registered: HashSet<String> = database.get_all_users().await?
+        .into_iter()
+        .map(|user| user.discord_id)
+        .collect();
+    let mut outcome = BulkRegistration::default();
+
+    for member in payroll::guild_members(http, guild_id).await? {
+        let user_id = member.user.id.to_string();
+        if registered.contains(&user_id) {
+            outcome.skipped += 1;
+            continue;
+        }
+
+        match register_user(database, crypto, &user_id, &member.user.name).await {
+            Ok(true) => outcome.created += 1,
+            Ok(false) => outcome.skipped += 1,
+            Err(e) => {
+                error!("Error registering {} in bulk: {}", user_id, e);
+                outcome.failed += 1;
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+pub fn register_buttons() -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}join", BUTTON_PREFIX))
+            .label("Register")
+            .emoji('🪙')
+            .style(serenity::ButtonStyle::Success),
+    ])]
+}
+
+/// Handle a press on the pinned Register button
+pub async fn handle_button(
+    ctx: &serenity::Context,
+    press: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), serenity::Error> {
+    let user_id = press.user.id.to_string();
+
+    let response = match register_user(&data.database, &data.crypto, &user_id, &press.user.name).await {
+        Ok(true) => "Registration successful. bub boils the seed",
+        Ok(false) => "You're already registered",
+        Err(e) => {
+            error!("Error registering {} from button: {}", user_id, e);
+            "Registration failed. Please try again."
+        }
+    };
+
+    press.create_response(ctx, serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(response)
+            .ephemeral(true),
+    )).await
+}
+
+/// Whether to ask Discord for the privileged Server Members intent, which join events need. Only
+/// when REGISTER_ON_JOIN=true or a server has turned on `register.on_join`, so bots without the
+/// intent enabled in the developer portal can still connect. Read once at startup.
+pub async fn needs_member_intent(database: &Database) -> bool {
+    if std::env::var("REGISTER_ON_JOIN").is_ok_and(|enabled| enabled == "true") {
+        return true;
+    }
+    match database.get_guild_settings_for_key("register.on_join").await {
+        Ok(settings) => settings.iter().any(|(_, value)| value.parse().unwrap_or(false)),
+        Err(e) => {
+            error!("Error loading register.on_join settings: {}", e);
+            false
+        }
+    }
+}
+
+/// Register new members as they join, in servers that have turned it on
+pub async fn handle_member_join(member: &serenity::Member, data: &Data) {
+    if member.user.bot {
+        return;
+    }
+
+    let guild_id = member.guild_id.to_string();
+    match config::get_bool(&data.database, &guild_id, "register.on_join").await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!("Error loading register.on_join for guild {}: {}", guild_id, e);
+            return;
+        }
+    }
+
+    let user_id = member.user.id.to_string();
+    match register_user(&data.database, &data.crypto, &user_id, &member.user.name).await {
+        Ok(true) => info!("Registered {} on joining guild {}", user_id, guild_id),
+        Ok(false) => {}
+        Err(e) => error!("Error registering {} on join: {}", user_id, e),
+    }
+}
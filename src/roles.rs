@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::database::Database;
+use crate::health::TaskMonitor;
+
+const EXPIRE_TICK_SECONDS: u64 = 300;
+
+#[derive(Debug)]
+pub enum RoleError {
+    // The bot lacks Manage Roles, or the role sits above the bot's highest role
+    MissingPermissions,
+    Http(serenity::Error),
+}
+
+impl std::fmt::Display for RoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RoleError::MissingPermissions => write!(f, "Missing Manage Roles permission or role is above the bot's role"),
+            RoleError::Http(e) => write!(f, "Discord error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RoleError {}
+
+impl From<serenity::Error> for RoleError {
+    fn from(err: serenity::Error) -> Self {
+        match err {
+            serenity::Error::Http(ref e) if e.status_code() == Some(serenity::StatusCode::FORBIDDEN) => {
+                RoleError::MissingPermissions
+            }
+            err => RoleError::Http(err),
+        }
+    }
+}
+
+pub async fn grant(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    role_id: serenity::RoleId,
+    reason: &str,
+) -> Result<(), RoleError> {
+    http.add_member_role(guild_id, user_id, role_id, Some(reason)).await?;
+    Ok(())
+}
+
+pub async fn revoke(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    role_id: serenity::RoleId,
+    reason: &str,
+) -> Result<(), RoleError> {
+    http.remove_member_role(guild_id, user_id, role_id, Some(reason)).await?;
+    Ok(())
+}
+
+/// Remove purchased roles once their duration runs out
+pub fn spawn_expirer(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(EXPIRE_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("roles", Duration::from_secs(EXPIRE_TICK_SECONDS));
+
+            let expired = match database.get_expired_role_grants(Utc::now().timestamp()).await {
+                Ok(expired) => expired,
+                Err(e) => {
+                    error!("Failed to load expired role grants: {}", e);
+                    continue;
+                }
+            };
+
+            for grant in expired {
+                let (Ok(guild_id), Ok(user_id), Ok(role_id)) = (
+                    grant.guild_id.parse::<u64>(),
+                    grant.discord_id.parse::<u64>(),
+                    grant.role_id.parse::<u64>(),
+                ) else {
+                    let _ = database.remove_role_grant(grant.id).await;
+                    continue;
+                };
+
+                match revoke(
+                    &http,
+                    serenity::GuildId::new(guild_id),
+                    serenity::UserId::new(user_id),
+                    serenity::RoleId::new(role_id),
+                    "Purchased role expired",
+                ).await {
+                    Ok(()) => info!("Removed expired role {} from {}", grant.role_id, grant.discord_id),
+                    Err(RoleError::MissingPermissions) => {
+                        // Keep the grant so it's retried once permissions are fixed
+                        warn!("Missing permissions to remove expired role {} in guild {}", grant.role_id, grant.guild_id);
+                        continue;
+                    }
+                    Err(e) => warn!("Failed to remove expired role {}: {}", grant.role_id, e),
+                }
+
+                if let Err(e) = database.remove_role_grant(grant.id).await {
+                    error!("Failed to clear role grant {}: {}", grant.id, e);
+                }
+            }
+        }
+    });
+}
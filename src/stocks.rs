@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use chrono::Utc;
+use rand::Rng;
+use tokio::time::{interval, Duration};
+use tracing::error;
+
+use crate::config;
+use crate::database::{Database, DatabaseError, Stock};
+use crate::health::TaskMonitor;
+
+// Every guild's market opens with these (symbol, name, starting price)
+const LISTINGS: [(&str, &str, i64); 5] = [
+    ("SLUM", "Slum Industries", 100),
+    ("BUB", "Bub's Bubble Tea", 40),
+    ("GRUB", "Grub Burger Co.", 65),
+    ("MOON", "Moonshot Rockets", 250),
+    ("RUG", "Rug Pull Ventures", 15),
+];
+
+const TICK_SECONDS: u64 = 900;
+// Price history is kept this long for charts
+pub const HISTORY_SECONDS: i64 = 30 * 86400;
+// Each tick closes this share of the gap back to the base price, in basis points
+const REVERSION_BPS: i64 = 200;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The guild's stocks, opening the market with the default listings the first time
+pub async fn market(database: &Database, guild_id: &str) -> Result<Vec<Stock>, DatabaseError> {
+    let stocks = database.get_stocks(guild_id).await?;
+    if !stocks.is_empty() {
+        return Ok(stocks);
+    }
+
+    database.list_stocks(guild_id, &LISTINGS, Utc::now().timestamp()).await?;
+    database.get_stocks(guild_id).await
+}
+
+/// One random-walk step: a uniform move of up to `volatility_percent` either way, plus a
+/// pull back towards the base price so stocks don't wander off to zero or the moon for good
+pub fn next_price(stock: &Stock, volatility_percent: i64) -> i64 {
+    let volatility_bps = volatility_percent.clamp(0, 100) * 100;
+    let move_bps = rand::thread_rng().gen_range(-volatility_bps..=volatility_bps);
+    let reversion = (stock.base_price - stock.price) * REVERSION_BPS / 10_000;
+    (stock.price + stock.price * move_bps / 10_000 + reversion).max(1)
+}
+
+/// Percent change from `from` to `to`
+pub fn change_percent(from: i64, to: i64) -> f64 {
+    if from <= 0 {
+        return 0.0;
+    }
+    (to - from) as f64 * 100.0 / from as f64
+}
+
+/// A one-line text chart of `prices`, squeezed into at most `width` bars
+pub fn sparkline(prices: &[i64], width: usize) -> String {
+    if prices.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let step = prices.len().div_ceil(width);
+    let sampled: Vec<i64> = prices.chunks(step).map(|chunk| chunk[chunk.len() - 1]).collect();
+    let low = sampled.iter().copied().min().unwrap_or_default();
+    let high = sampled.iter().copied().max().unwrap_or_default();
+    let levels = SPARKLINE_BARS.len() as i64 - 1;
+
+    sampled
+        .iter()
+        .map(|price| {
+            let level = if high == low { levels / 2 } else { (price - low) * levels / (high - low) };
+            SPARKLINE_BARS[level as usize]
+        })
+        .collect()
+}
+
+/// Move every listed stock's price on a schedule
+pub fn spawn_ticker(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("stocks", Duration::from_secs(TICK_SECONDS));
+
+            let stocks = match database.get_all_stocks().await {
+                Ok(stocks) => stocks,
+                Err(e) => {
+                    error!("Failed to load stocks: {}", e);
+                    continue;
+                }
+            };
+
+            let mut volatility: HashMap<String, i64> = HashMap::new();
+            let mut prices = Vec::new();
+            for stock in &stocks {
+                if !volatility.contains_key(&stock.guild_id) {
+                    match config::get_i64(&database, &stock.guild_id, "stocks.volatility_percent").await {
+                        Ok(percent) => volatility.insert(stock.guild_id.clone(), percent),
+                        Err(e) => {
+                            error!("Failed to load stock volatility for guild {}: {}", stock.guild_id, e);
+                            continue;
+                        }
+                    };
+                }
+                prices.push((stock.id, next_price(stock, volatility[&stock.guild_id])));
+            }
+
+            let now = Utc::now().timestamp();
+            if let Err(e) = database.record_stock_prices(&prices, now, now - HISTORY_SECONDS).await {
+                error!("Failed to record stock prices: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::config;
+use crate::database::{Database, DatabaseError, TaxRun, Transaction};
+use crate::health::TaskMonitor;
+use crate::ledger::TREASURY_ACCOUNT;
+use crate::payroll::members_where;
+
+const TAX_TICK_SECONDS: u64 = 3600;
+// Largest contributors named in the tax report
+const REPORT_TOP_PAYERS: usize = 5;
+
+/// Tax owed on a balance: `percent` of whatever is above the threshold, rounded down
+pub fn tax_for(balance: i64, threshold: i64, percent: i64) -> i64 {
+    (balance - threshold).max(0).saturating_mul(percent.clamp(0, 100)) / 100
+}
+
+async fn post_report(http: &serenity::Http, database: &Database, run: &TaxRun, taxes: &[Transaction], next_run_at: i64) {
+    let channel_id = match config::get(database, &run.guild_id, "tax.channel_id").await {
+        Ok(channel_id) => channel_id,
+        Err(e) => {
+            error!("Failed to load tax channel for guild {}: {}", run.guild_id, e);
+            return;
+        }
+    };
+    let Ok(channel_id) = channel_id.parse::<u64>() else {
+        return;
+    };
+
+    let mut message = format!(
+        "🧾 **Wealth tax collected**\n\
+        {} member(s) with more than {} Slumcoins paid {}% of the excess, \
+        sending **{} Slumcoins** to the treasury.\n",
+        run.taxpayers, run.threshold, run.percent, run.collected
+    );
+    // Taxes are built richest first, so the first entries are the largest
+    for tax in taxes.iter().take(REPORT_TOP_PAYERS) {
+        message.push_str(&format!("• <@{}> paid {}\n", tax.from_user, tax.amount));
+    }
+    message.push_str(&format!("Next collection <t:{}:R>", next_run_at));
+
+    let report = serenity::CreateMessage::new()
+        .content(message)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    if let Err(e) = serenity::ChannelId::new(channel_id).send_message(http, report).await {
+        error!("Failed to post tax report to guild {}: {}", run.guild_id, e);
+    }
+}
+
+async fn collect(http: &serenity::Http, database: &Database, guild_id: &str, percent: i64) -> Result<(), DatabaseError> {
+    let interval_seconds = config::get_i64(database, guild_id, "tax.interval_hours").await?.max(1) * 3600;
+    let now = Utc::now().timestamp();
+    let last_run_at = database.get_last_tax_run(guild_id).await?;
+    if last_run_at.is_some_and(|last_run_at| last_run_at + interval_seconds > now) {
+        return Ok(());
+    }
+
+    let Ok(guild) = guild_id.parse::<u64>() else {
+        return Ok(());
+    };
+    let threshold = config::get_i64(database, guild_id, "tax.threshold").await?.max(0);
+    let candidates = database.get_balances_above(threshold).await?;
+
+    // Balances are shared between servers, so only this guild's members pay its tax
+    let members: HashSet<String> = if candidates.is_empty() {
+        HashSet::new()
+    } else {
+        match members_where(http, serenity::GuildId::new(guild), |_| true).await {
+            Ok(members) => members.iter().map(|member| member.to_string()).collect(),
+            Err(e) => {
+                warn!("Failed to list members for wealth tax in guild {}: {}", guild_id, e);
+                return Ok(());
+            }
+        }
+    };
+
+    let taxes: Vec<Transaction> = candidates
+        .iter()
+        .filter(|(discord_id, _)| members.contains(discord_id))
+        .filter_map(|(discord_id, balance)| {
+            let tax = tax_for(*balance, threshold, percent);
+            (tax > 0).then(|| Transaction::system(discord_id, TREASURY_ACCOUNT, tax, "tax", Some("Wealth tax".to_string())))
+        })
+        .collect();
+
+    let run = TaxRun {
+        guild_id: guild_id.to_string(),
+        percent,
+        threshold,
+        taxpayers: taxes.len() as i64,
+        collected: taxes.iter().map(|tax| tax.amount).sum(),
+        ran_at: now,
+    };
+
+    if database.record_tax_run(&run, last_run_at, &taxes).await? {
+        info!("Collected {} in wealth tax from {} members of guild {}", run.collected, run.taxpayers, guild_id);
+        post_report(http, database, &run, &taxes, now + interval_seconds).await;
+    }
+
+    Ok(())
+}
+
+/// Collect the wealth tax in every guild that has it enabled once its interval has passed
+pub fn spawn_collector(http: Arc<serenity::Http>, database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(TAX_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("tax", Duration::from_secs(TAX_TICK_SECONDS));
+
+            let guilds = match database.get_guild_settings_for_key("tax.percent").await {
+                Ok(guilds) => guilds,
+                Err(e) => {
+                    error!("Failed to load wealth tax settings: {}", e);
+                    continue;
+                }
+            };
+
+            for (guild_id, percent) in guilds {
+                let percent = percent.parse::<i64>().unwrap_or(0);
+                if percent <= 0 {
+                    continue;
+                }
+
+                if let Err(e) = collect(&http, &database, &guild_id, percent).await {
+                    error!("Failed to collect wealth tax in guild {}: {}", guild_id, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,49 @@
+// Ledger account holding every team's bank
+pub const TEAM_BANK_ACCOUNT: &str = "TEAM_BANK";
+
+pub const MAX_NAME_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TeamRole {
+    Invited,
+    Member,
+    Officer,
+    Leader,
+}
+
+impl TeamRole {
+    pub fn parse(key: &str) -> Option<TeamRole> {
+        match key {
+            "invited" => Some(TeamRole::Invited),
+            "member" => Some(TeamRole::Member),
+            "officer" => Some(TeamRole::Officer),
+            "leader" => Some(TeamRole::Leader),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            TeamRole::Invited => "invited",
+            TeamRole::Member => "member",
+            TeamRole::Officer => "officer",
+            TeamRole::Leader => "leader",
+        }
+    }
+
+    /// Officers and the leader invite people and spend from the bank
+    pub fn can_manage(self) -> bool {
+        self >= TeamRole::Officer
+    }
+
+    /// Officers can remove members and invites; only the leader can remove officers
+    pub fn can_remove(self, target: TeamRole) -> bool {
+        self.can_manage() && target < self
+    }
+}
+
+impl std::fmt::Display for TeamRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
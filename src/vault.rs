@@ -0,0 +1,61 @@
+use chrono::Utc;
+use tokio::time::{interval, Duration};
+use tracing::{error, info};
+
+use crate::config;
+use crate::database::{Database, Transaction, SYSTEM_ACCOUNT};
+use crate::health::TaskMonitor;
+
+// Ledger account holding every user's vault savings
+pub const VAULT_ACCOUNT: &str = "VAULT";
+
+// Vaults earn interest once per period
+pub const INTEREST_PERIOD_SECONDS: i64 = 86400;
+const INTEREST_TICK_SECONDS: u64 = 3600;
+
+/// Interest earned on a vault balance for one period at a rate in basis points, rounded down
+pub fn interest_for(balance: i64, rate_bps: i64) -> i64 {
+    balance.saturating_mul(rate_bps.max(0)) / 10_000
+}
+
+/// Pay interest on every vault that has gone a full period without any
+pub fn spawn_interest_payer(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(INTEREST_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("vault", Duration::from_secs(INTEREST_TICK_SECONDS));
+
+            let accrued_before = Utc::now().timestamp() - INTEREST_PERIOD_SECONDS;
+            let due = match database.get_vaults_due_interest(accrued_before).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Failed to load vaults due interest: {}", e);
+                    continue;
+                }
+            };
+
+            for (guild_id, discord_id, balance) in due {
+                let rate_bps = match config::get_i64(&database, &guild_id, "vault.daily_interest_bps").await {
+                    Ok(rate_bps) => rate_bps,
+                    Err(e) => {
+                        error!("Failed to load vault interest rate for guild {}: {}", guild_id, e);
+                        continue;
+                    }
+                };
+
+                let interest = interest_for(balance, rate_bps);
+                let credit = (interest > 0).then(|| {
+                    Transaction::system(SYSTEM_ACCOUNT, VAULT_ACCOUNT, interest, "interest", Some(format!("Vault interest for {}", discord_id)))
+                });
+
+                match database.pay_vault_interest(&guild_id, &discord_id, accrued_before, credit.as_ref()).await {
+                    Ok(true) if interest > 0 => info!("Paid {} interest on {}'s vault in guild {}", interest, discord_id, guild_id),
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to pay vault interest to {}: {}", discord_id, e),
+                }
+            }
+        }
+    });
+}
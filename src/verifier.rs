@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use poise::serenity_prelude as serenity;
+use tokio::time::{interval, Duration};
+use tracing::{error, info, warn};
+
+use crate::checkpoint;
+use crate::crypto::CryptoManager;
+use crate::database::{Database, DatabaseError};
+use crate::health::TaskMonitor;
+
+// Runs well below the full /audit cost: a small random sample every few minutes
+const VERIFY_TICK_SECONDS: u64 = 300;
+const SIGNATURE_SAMPLE_SIZE: u32 = 50;
+const ALERT_DISPLAY_LIMIT: usize = 10;
+const CHECKPOINT_TICK_SECONDS: u64 = 3600;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Anomaly {
+    InvalidSignature { transaction_id: String, from_user: String },
+    DuplicateNonce { discord_id: String, nonce: i64 },
+    NonceGap { discord_id: String, expected: i64, found: i64 },
+}
+
+impl std::fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Anomaly::InvalidSignature { transaction_id, from_user } => {
+                write!(f, "Invalid signature on `{}` (sender <@{}>)", transaction_id, from_user)
+            }
+            Anomaly::DuplicateNonce { discord_id, nonce } => {
+                write!(f, "Nonce {} used more than once by <@{}>", nonce, discord_id)
+            }
+            Anomaly::NonceGap { discord_id, expected, found } => {
+                write!(f, "Nonce gap for <@{}>: expected {}, found {}", discord_id, expected, found)
+            }
+        }
+    }
+}
+
+/// Verify a random sample of user-signed transactions against the senders' public keys
+pub async fn spot_check_signatures(
+    database: &Database,
+    crypto: &CryptoManager,
+    sample_size: u32,
+) -> Result<Vec<Anomaly>, DatabaseError> {
+    let mut anomalies = Vec::new();
+    let mut public_keys: HashMap<String, Option<String>> = HashMap::new();
+
+    for tx in database.sample_signed_transactions(sample_size).await? {
+        if !public_keys.contains_key(&tx.from_user) {
+            let key = database.get_public_key(&tx.from_user).await?;
+            public_keys.insert(tx.from_user.clone(), key);
+        }
+
+        let valid = match &public_keys[&tx.from_user] {
+            Some(public_key) => crypto.verify_signature(public_key, &tx.signature, &tx.signing_payload()),
+            None => false,
+        };
+
+        if !valid {
+            anomalies.push(Anomaly::InvalidSignature {
+                transaction_id: tx.id,
+                from_user: tx.from_user,
+            });
+        }
+    }
+
+    Ok(anomalies)
+}
+
+/// Find duplicated or skipped nonces in each sender's signed transactions.
+/// Nonces start at 1; transactions signed before nonces were assigned carry 0 and are ignored.
+pub fn check_nonces(nonces: &[(String, i64)]) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    let mut last: Option<(&str, i64)> = None;
+
+    for (discord_id, nonce) in nonces {
+        let expected = match last {
+            Some((previous_id, previous)) if previous_id == discord_id => previous + 1,
+            _ => 1,
+        };
+
+        if *nonce < expected {
+            anomalies.push(Anomaly::DuplicateNonce {
+                discord_id: discord_id.clone(),
+                nonce: *nonce,
+            });
+        } else if *nonce > expected {
+            anomalies.push(Anomaly::NonceGap {
+                discord_id: discord_id.clone(),
+                expected,
+                found: *nonce,
+            });
+        }
+
+        last = Some((discord_id, *nonce));
+    }
+
+    anomalies
+}
+
+async fn alert(http: &serenity::Http, database: &Database, anomalies: &[Anomaly]) -> Result<(), DatabaseError> {
+    let mut message = String::from("🚨 **Ledger verifier found anomalies**\n");
+    for anomaly in anomalies.iter().take(ALERT_DISPLAY_LIMIT) {
+        message.push_str(&format!("• {}\n", anomaly));
+    }
+    if anomalies.len() > ALERT_DISPLAY_LIMIT {
+        message.push_str(&format!("...and {} more\n", anomalies.len() - ALERT_DISPLAY_LIMIT));
+    }
+    message.push_str("Run `/audit` for a full report.");
+
+    for (guild_id, channel_id) in database.get_guild_settings_for_key("audit.channel_id").await? {
+        let Ok(channel_id) = channel_id.parse::<u64>() else {
+            continue;
+        };
+
+        if let Err(e) = serenity::ChannelId::new(channel_id).say(http, &message).await {
+            error!("Failed to post verifier alert to guild {}: {}", guild_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Continuously spot-check signatures and nonces, alerting audit channels about new anomalies
+pub fn spawn_verifier(http: Arc<serenity::Http>, database: Database, crypto: Arc<CryptoManager>, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(VERIFY_TICK_SECONDS));
+        // Each anomaly is only reported once per run of the bot
+        let mut reported: HashSet<Anomaly> = HashSet::new();
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("verifier", Duration::from_secs(VERIFY_TICK_SECONDS));
+
+            let mut anomalies = match spot_check_signatures(&database, &crypto, SIGNATURE_SAMPLE_SIZE).await {
+                Ok(anomalies) => anomalies,
+                Err(e) => {
+                    error!("Signature spot-check failed: {}", e);
+                    continue;
+                }
+            };
+
+            match database.get_signed_nonces().await {
+                Ok(nonces) => anomalies.extend(check_nonces(&nonces)),
+                Err(e) => {
+                    error!("Nonce check failed: {}", e);
+                    continue;
+                }
+            }
+
+            anomalies.retain(|anomaly| !reported.contains(anomaly));
+            if anomalies.is_empty() {
+                continue;
+            }
+
+            for anomaly in &anomalies {
+                warn!("Ledger verifier: {}", anomaly);
+            }
+
+            match alert(&http, &database, &anomalies).await {
+                Ok(()) => {
+                    info!("Reported {} ledger anomalies", anomalies.len());
+                    reported.extend(anomalies);
+                }
+                Err(e) => error!("Failed to send verifier alerts: {}", e),
+            }
+        }
+    });
+}
+
+/// Sign a checkpoint of the ledger every hour so later edits or rollbacks can be detected with /checkpoint verify
+pub fn spawn_checkpointer(database: Database, crypto: Arc<CryptoManager>, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(CHECKPOINT_TICK_SECONDS));
+
+        loop {
+            ticker.tick().await;
+            monitor.beat("checkpoint", Duration::from_secs(CHECKPOINT_TICK_SECONDS));
+
+            match checkpoint::create(&database, &crypto).await {
+                Ok(Some(checkpoint)) => info!(
+                    "Signed ledger checkpoint #{} at {} transactions",
+                    checkpoint.id, checkpoint.transaction_count
+                ),
+                Ok(None) => {}
+                Err(e) => error!("Failed to create ledger checkpoint: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,220 @@
+use chrono::Utc;
+use serde_json::json;
+use tokio::time::{interval, Duration};
+use tracing::{error, warn};
+
+use crate::database::{Database, DatabaseError, WebhookDelivery, WebhookEvent, SYSTEM_ACCOUNT};
+use crate::health::TaskMonitor;
+
+const DISPATCH_TICK_SECONDS: u64 = 15;
+const SCAN_BATCH: u32 = 500;
+const DELIVERY_BATCH: u32 = 50;
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+// Retries back off from 30s, doubling each time; after the last one the delivery is marked failed
+const RETRY_BASE_SECONDS: i64 = 30;
+const MAX_ATTEMPTS: i64 = 8;
+
+// How far the dispatcher has read the ledger and the auction history, kept in system_config
+const LEDGER_CURSOR_KEY: &str = "webhooks.ledger_cursor";
+const AUCTION_CURSOR_KEY: &str = "webhooks.auction_cursor";
+
+pub const LARGE_TRANSACTION: &str = "large_transaction";
+pub const AUCTION_END: &str = "auction_end";
+pub const MINT: &str = "mint";
+
+/// Discord webhooks get a plain message; any other URL gets the event as JSON
+fn is_discord_webhook(url: &str) -> bool {
+    url.contains("discord.com/api/webhooks/") || url.contains("discordapp.com/api/webhooks/")
+}
+
+// Cursor stored for `key`, starting at `head` the first time so existing history isn't replayed
+async fn cursor(database: &Database, key: &str, head: i64) -> Result<Option<i64>, DatabaseError> {
+    match database.get_system_config(key).await? {
+        Some(value) => Ok(Some(value.parse().unwrap_or(head))),
+        None => {
+            database.queue_webhook_events(&[], key, head).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Queue new ledger entries that are large or mint coins
+async fn scan_ledger(database: &Database) -> Result<(), DatabaseError> {
+    let head = database.get_latest_chain_seq().await?;
+    let Some(after) = cursor(database, LEDGER_CURSOR_KEY, head).await? else {
+        return Ok(());
+    };
+
+    let entries = database.get_transactions_after_seq(after, SCAN_BATCH).await?;
+    let Some((last_seq, _)) = entries.last() else {
+        return Ok(());
+    };
+    let last_seq = *last_seq;
+
+    let mut events = Vec::new();
+    for (_, transaction) in &entries {
+        let summary = format!(
+            "{} Slumcoins from {} to {} ({})",
+            transaction.amount, transaction.from_user, transaction.to_user, transaction.transaction_type
+        );
+        let payload = |event: &str| json!({
+            "event": event,
+            "summary": summary,
+            "transaction": {
+                "id": transaction.id,
+                "from_user": transaction.from_user,
+                "to_user": transaction.to_user,
+                "amount": transaction.amount,
+                "type": transaction.transaction_type,
+                "message": transaction.message,
+                "timestamp": transaction.timestamp_unix,
+            },
+        }).to_string();
+
+        events.push(WebhookEvent {
+            event: LARGE_TRANSACTION.to_string(),
+            amount: transaction.amount,
+            guild_id: None,
+            payload: payload(LARGE_TRANSACTION),
+        });
+        if transaction.from_user == SYSTEM_ACCOUNT {
+            events.push(WebhookEvent {
+                event: MINT.to_string(),
+                amount: transaction.amount,
+                guild_id: None,
+                payload: payload(MINT),
+            });
+        }
+    }
+
+    database.queue_webhook_events(&events, LEDGER_CURSOR_KEY, last_seq).await
+}
+
+/// Queue auctions that have ended since the last scan
+async fn scan_auctions(database: &Database) -> Result<(), DatabaseError> {
+    let head = database.get_latest_auction_id().await?;
+    let Some(after) = cursor(database, AUCTION_CURSOR_KEY, head).await? else {
+        return Ok(());
+    };
+
+    let auctions = database.get_auctions_after(after, SCAN_BATCH).await?;
+    let Some((last_id, _, _)) = auctions.last() else {
+        return Ok(());
+    };
+    let last_id = *last_id;
+
+    let events: Vec<WebhookEvent> = auctions
+        .iter()
+        .map(|(id, guild_id, record)| {
+            let summary = match (&record.winner_id, record.amount) {
+                (Some(winner), Some(amount)) if record.outcome == "sold" => {
+                    format!("Auction for {} sold to {} for {} Slumcoins", record.item, winner, amount)
+                }
+                _ => format!("Auction for {} ended: {}", record.item, record.outcome.replace('_', " ")),
+            };
+            WebhookEvent {
+                event: AUCTION_END.to_string(),
+                amount: record.amount.unwrap_or(0),
+                guild_id: Some(guild_id.clone()),
+                payload: json!({
+                    "event": AUCTION_END,
+                    "summary": summary,
+                    "auction": {
+                        "id": id,
+                        "guild_id": guild_id,
+                        "item": record.item,
+                        "outcome": record.outcome,
+                        "winner_id": record.winner_id,
+                        "amount": record.amount,
+                        "bid_count": record.bid_count,
+                        "ended_at": record.ended_at,
+                    },
+                }).to_string(),
+            }
+        })
+        .collect();
+
+    database.queue_webhook_events(&events, AUCTION_CURSOR_KEY, last_id).await
+}
+
+async fn send(client: &reqwest::Client, delivery: &WebhookDelivery) -> Result<(), String> {
+    let body = if is_discord_webhook(&delivery.url) {
+        let summary = serde_json::from_str::<serde_json::Value>(&delivery.payload)
+            .ok()
+            .and_then(|payload| payload["summary"].as_str().map(str::to_string))
+            .unwrap_or_else(|| delivery.event.clone());
+        json!({
+            "content": format!("**{}** · {}", delivery.event.replace('_', " "), summary),
+            "allowed_mentions": { "parse": [] },
+        })
+        .to_string()
+    } else {
+        delivery.payload.clone()
+    };
+
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+/// Attempt every delivery that is due, rescheduling failures with backoff
+async fn deliver_due(client: &reqwest::Client, database: &Database) -> Result<(), DatabaseError> {
+    let now = Utc::now().timestamp();
+    for delivery in database.get_due_webhook_deliveries(now, DELIVERY_BATCH).await? {
+        match send(client, &delivery).await {
+            Ok(()) => database.complete_webhook_delivery(delivery.id).await?,
+            Err(e) => {
+                let attempts = delivery.attempts + 1;
+                let next_attempt_at = (attempts < MAX_ATTEMPTS).then(|| now + (RETRY_BASE_SECONDS << (attempts - 1)));
+                if next_attempt_at.is_none() {
+                    warn!("Giving up on webhook #{} delivery {} after {} attempts: {}", delivery.webhook_id, delivery.id, attempts, e);
+                }
+                database.fail_webhook_delivery(delivery.id, &e, next_attempt_at).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically queue notable ledger and auction events and deliver them to configured webhooks
+pub fn spawn_dispatcher(database: Database, monitor: TaskMonitor) {
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to build webhook client, webhooks are disabled: {}", e);
+                return;
+            }
+        };
+
+        let mut ticker = interval(Duration::from_secs(DISPATCH_TICK_SECONDS));
+        loop {
+            ticker.tick().await;
+            monitor.beat("webhooks", Duration::from_secs(DISPATCH_TICK_SECONDS));
+
+            if let Err(e) = scan_ledger(&database).await {
+                error!("Error scanning ledger for webhooks: {}", e);
+            }
+            if let Err(e) = scan_auctions(&database).await {
+                error!("Error scanning auctions for webhooks: {}", e);
+            }
+            if let Err(e) = deliver_due(&client, &database).await {
+                error!("Error delivering webhooks: {}", e);
+            }
+        }
+    });
+}